@@ -2,14 +2,15 @@
 #[macro_use]
 extern crate log;
 use clap::{crate_description, crate_name, value_t, App, Arg};
+use rand::seq::SliceRandom;
 use rayon::prelude::*;
 use solana_measure::measure::Measure;
 use solana_runtime::{
     accounts::{create_test_accounts, update_accounts_bench, Accounts},
-    accounts_index::Ancestors,
+    accounts_index::{AccountSecondaryIndexes, AccountsIndexConfig, Ancestors},
 };
 use solana_sdk::{genesis_config::ClusterType, pubkey::Pubkey};
-use std::{collections::HashSet, env, fs, path::PathBuf};
+use std::{env, fs, path::PathBuf};
 
 fn main() {
     solana_logger::setup();
@@ -44,6 +45,20 @@ fn main() {
                 .takes_value(false)
                 .help("Run clean"),
         )
+        .arg(
+            Arg::with_name("disk_index")
+                .long("disk_index")
+                .takes_value(false)
+                .help("Keep the accounts index on disk"),
+        )
+        .arg(
+            Arg::with_name("index_max_in_memory_entries")
+                .long("index_max_in_memory_entries")
+                .takes_value(true)
+                .value_name("COUNT")
+                .requires("disk_index")
+                .help("Number of accounts index entries kept in memory with --disk_index"),
+        )
         .get_matches();
 
     let num_slots = value_t!(matches, "num_slots", usize).unwrap_or(4);
@@ -51,6 +66,8 @@ fn main() {
     let iterations = value_t!(matches, "iterations", usize).unwrap_or(20);
     let clean = matches.is_present("clean");
     println!("clean: {:?}", clean);
+    let disk_index = matches.is_present("disk_index");
+    println!("disk_index: {:?}", disk_index);
 
     let path = PathBuf::from(env::var("FARF_DIR").unwrap_or_else(|_| "farf".to_owned()))
         .join("accounts-bench");
@@ -58,8 +75,20 @@ fn main() {
     if fs::remove_dir_all(path.clone()).is_err() {
         println!("Warning: Couldn't remove {:?}", path);
     }
-    let accounts =
-        Accounts::new_with_config(vec![path], &ClusterType::Testnet, HashSet::new(), false);
+    let accounts_index_config = disk_index.then(|| {
+        let mut config = AccountsIndexConfig::new(vec![path.join("index")]);
+        if let Ok(max_in_memory_entries) = value_t!(matches, "index_max_in_memory_entries", usize) {
+            config.max_in_memory_entries = max_in_memory_entries;
+        }
+        config
+    });
+    let accounts = Accounts::new_with_config(
+        vec![path],
+        &ClusterType::Testnet,
+        AccountSecondaryIndexes::default(),
+        false,
+        accounts_index_config,
+    );
     println!("Creating {} accounts", num_accounts);
     let mut create_time = Measure::start("create accounts");
     let pubkeys: Vec<_> = (0..num_slots)
@@ -88,6 +117,30 @@ fn main() {
         ancestors.insert(i as u64, i - 1);
         accounts.add_root(i as u64);
     }
+    let (in_memory, on_disk) = accounts.accounts_db.accounts_index.num_entries();
+    println!(
+        "accounts index entries: {} in memory, {} on disk",
+        in_memory, on_disk
+    );
+
+    let mut sample = pubkeys.clone();
+    sample.shuffle(&mut rand::thread_rng());
+    let mut time = Measure::start("load");
+    for pubkey in &sample {
+        accounts.load_slow(&ancestors, pubkey);
+    }
+    time.stop();
+    println!(
+        "loaded {} accounts in random order {}, {}us per account",
+        sample.len(),
+        time,
+        time.as_us() / sample.len().max(1) as u64
+    );
+    let mut time = Measure::start("scan");
+    let num_scanned = accounts.load_all(&ancestors).len();
+    time.stop();
+    println!("scanned {} accounts {}", num_scanned, time);
+
     let mut elapsed = vec![0; iterations];
     let mut elapsed_store = vec![0; iterations];
     for x in 0..iterations {
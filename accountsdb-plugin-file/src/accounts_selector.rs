@@ -0,0 +1,85 @@
+use log::*;
+use std::collections::HashSet;
+
+/// Selects the accounts a plugin is interested in, by account address or by owner program.
+/// An accounts list of `["*"]` selects every account.
+#[derive(Debug)]
+pub struct AccountsSelector {
+    pub accounts: HashSet<Vec<u8>>,
+    pub owners: HashSet<Vec<u8>>,
+    pub select_all_accounts: bool,
+}
+
+impl Default for AccountsSelector {
+    fn default() -> Self {
+        Self {
+            accounts: HashSet::new(),
+            owners: HashSet::new(),
+            select_all_accounts: true,
+        }
+    }
+}
+
+impl AccountsSelector {
+    pub fn new(accounts: &[String], owners: &[String]) -> Result<Self, bs58::decode::Error> {
+        info!(
+            "Creating AccountsSelector from accounts: {:?}, owners: {:?}",
+            accounts, owners
+        );
+
+        let select_all_accounts = accounts.iter().any(|key| key == "*");
+        if select_all_accounts {
+            return Ok(AccountsSelector {
+                accounts: HashSet::new(),
+                owners: HashSet::new(),
+                select_all_accounts,
+            });
+        }
+        let accounts = accounts
+            .iter()
+            .map(|key| bs58::decode(key).into_vec())
+            .collect::<Result<_, _>>()?;
+        let owners = owners
+            .iter()
+            .map(|key| bs58::decode(key).into_vec())
+            .collect::<Result<_, _>>()?;
+        Ok(AccountsSelector {
+            accounts,
+            owners,
+            select_all_accounts,
+        })
+    }
+
+    pub fn is_account_selected(&self, account: &[u8], owner: &[u8]) -> bool {
+        self.select_all_accounts || self.accounts.contains(account) || self.owners.contains(owner)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    #[test]
+    fn test_create_accounts_selector() {
+        let account = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        let selector = AccountsSelector::new(&[account.to_string()], &[]).unwrap();
+        assert!(selector.is_account_selected(account.as_ref(), other.as_ref()));
+        assert!(!selector.is_account_selected(other.as_ref(), owner.as_ref()));
+
+        let selector = AccountsSelector::new(&[], &[owner.to_string()]).unwrap();
+        assert!(selector.is_account_selected(other.as_ref(), owner.as_ref()));
+        assert!(!selector.is_account_selected(account.as_ref(), other.as_ref()));
+
+        let selector = AccountsSelector::new(&["*".to_string()], &[]).unwrap();
+        assert!(selector.is_account_selected(other.as_ref(), other.as_ref()));
+
+        assert!(AccountsSelector::new(&["not base58!".to_string()], &[]).is_err());
+
+        let selector = AccountsSelector::default();
+        assert!(selector.is_account_selected(other.as_ref(), other.as_ref()));
+    }
+}
@@ -0,0 +1,326 @@
+//! The `accountsdb_plugin_file` module implements a reference AccountsDb plugin that appends
+//! every selected account update, slot status change and transaction to a local file as
+//! newline-delimited JSON, so the plugin interface can be exercised without external services.
+//!
+//! An example config file:
+//!
+//! ```json
+//! {
+//!     "libpath": "/path/to/libsolana_accountsdb_plugin_file.so",
+//!     "output_path": "/path/to/updates.jsonl",
+//!     "accounts_selector": {
+//!         "accounts": ["*"],
+//!         "owners": []
+//!     },
+//!     "notify_transactions": true
+//! }
+//! ```
+
+use crate::accounts_selector::AccountsSelector;
+use log::*;
+use serde_derive::{Deserialize, Serialize};
+use solana_accountsdb_plugin_interface::accountsdb_plugin_interface::{
+    AccountsDbPlugin, AccountsDbPluginError, ReplicaAccountInfo, ReplicaTransactionInfo, Result,
+    SlotStatus,
+};
+use solana_transaction_status::UiTransactionStatusMeta;
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    sync::Mutex,
+};
+
+#[derive(Debug, Default, Deserialize)]
+struct AccountsSelectorConfig {
+    #[serde(default)]
+    accounts: Vec<String>,
+    #[serde(default)]
+    owners: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountsDbPluginFileConfig {
+    output_path: String,
+    accounts_selector: Option<AccountsSelectorConfig>,
+    #[serde(default)]
+    notify_transactions: bool,
+    #[serde(default)]
+    include_vote_transactions: bool,
+}
+
+/// One line of the output file
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum FileRecord {
+    Account {
+        slot: u64,
+        pubkey: String,
+        lamports: u64,
+        owner: String,
+        executable: bool,
+        rent_epoch: u64,
+        data: String,
+    },
+    Slot {
+        slot: u64,
+        parent: Option<u64>,
+        status: String,
+    },
+    Transaction {
+        slot: u64,
+        signature: String,
+        is_vote: bool,
+        meta: UiTransactionStatusMeta,
+    },
+}
+
+#[derive(Default)]
+pub struct AccountsDbPluginFile {
+    writer: Option<Mutex<BufWriter<File>>>,
+    accounts_selector: AccountsSelector,
+    notify_transactions: bool,
+    include_vote_transactions: bool,
+}
+
+impl std::fmt::Debug for AccountsDbPluginFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("AccountsDbPluginFile")
+            .field("accounts_selector", &self.accounts_selector)
+            .field("notify_transactions", &self.notify_transactions)
+            .finish()
+    }
+}
+
+impl AccountsDbPluginFile {
+    fn write_record(&self, record: &FileRecord, flush: bool) -> std::io::Result<()> {
+        if let Some(writer) = &self.writer {
+            let mut writer = writer.lock().unwrap();
+            serde_json::to_writer(&mut *writer, record)?;
+            writer.write_all(b"\n")?;
+            if flush {
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AccountsDbPlugin for AccountsDbPluginFile {
+    fn name(&self) -> &'static str {
+        "AccountsDbPluginFile"
+    }
+
+    fn on_load(&mut self, config_file: &str) -> Result<()> {
+        info!("Loading plugin {} from {}", self.name(), config_file);
+        let contents = std::fs::read_to_string(config_file)?;
+        let config: AccountsDbPluginFileConfig =
+            serde_json::from_str(&contents).map_err(|err| {
+                AccountsDbPluginError::ConfigFileReadError {
+                    msg: format!("The config file is not in the expected format: {:?}", err),
+                }
+            })?;
+
+        if let Some(AccountsSelectorConfig { accounts, owners }) = config.accounts_selector {
+            self.accounts_selector = AccountsSelector::new(&accounts, &owners).map_err(|err| {
+                AccountsDbPluginError::ConfigFileReadError {
+                    msg: format!("Invalid accounts selector: {:?}", err),
+                }
+            })?;
+        }
+        self.notify_transactions = config.notify_transactions;
+        self.include_vote_transactions = config.include_vote_transactions;
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&config.output_path)?;
+        self.writer = Some(Mutex::new(BufWriter::new(file)));
+        Ok(())
+    }
+
+    fn on_unload(&mut self) {
+        info!("Unloading plugin: {}", self.name());
+        if let Some(writer) = self.writer.take() {
+            if let Err(err) = writer.into_inner().unwrap().flush() {
+                error!("Failed to flush the output file: {:?}", err);
+            }
+        }
+    }
+
+    fn update_account(&self, account: &ReplicaAccountInfo, slot: u64) -> Result<()> {
+        if !self
+            .accounts_selector
+            .is_account_selected(account.pubkey, account.owner)
+        {
+            return Ok(());
+        }
+        let record = FileRecord::Account {
+            slot,
+            pubkey: bs58::encode(account.pubkey).into_string(),
+            lamports: account.lamports,
+            owner: bs58::encode(account.owner).into_string(),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data: base64::encode(account.data),
+        };
+        self.write_record(&record, false).map_err(|err| {
+            AccountsDbPluginError::AccountsUpdateError {
+                msg: err.to_string(),
+            }
+        })
+    }
+
+    fn update_slot_status(&self, slot: u64, parent: Option<u64>, status: SlotStatus) -> Result<()> {
+        let record = FileRecord::Slot {
+            slot,
+            parent,
+            status: status.as_str().to_string(),
+        };
+        // Flush at slot boundaries so readers observe whole slots
+        self.write_record(&record, true).map_err(|err| {
+            AccountsDbPluginError::SlotStatusUpdateError {
+                msg: err.to_string(),
+            }
+        })
+    }
+
+    fn notify_transaction(&self, transaction: &ReplicaTransactionInfo, slot: u64) -> Result<()> {
+        if !self.notify_transactions || (transaction.is_vote && !self.include_vote_transactions) {
+            return Ok(());
+        }
+        let record = FileRecord::Transaction {
+            slot,
+            signature: transaction.signature.to_string(),
+            is_vote: transaction.is_vote,
+            meta: transaction.transaction_status_meta.clone().into(),
+        };
+        self.write_record(&record, false).map_err(|err| {
+            AccountsDbPluginError::TransactionUpdateError {
+                msg: err.to_string(),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        hash::Hash,
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        system_transaction,
+    };
+    use solana_transaction_status::TransactionStatusMeta;
+    use std::io::{BufRead, BufReader};
+    use tempfile::TempDir;
+
+    fn load_plugin(dir: &TempDir, config: serde_json::Value) -> AccountsDbPluginFile {
+        let config_path = dir.path().join("config.json");
+        std::fs::write(&config_path, config.to_string()).unwrap();
+        let mut plugin = AccountsDbPluginFile::default();
+        plugin.on_load(config_path.to_str().unwrap()).unwrap();
+        plugin
+    }
+
+    fn read_records(dir: &TempDir) -> Vec<FileRecord> {
+        let file = File::open(dir.path().join("out.jsonl")).unwrap();
+        BufReader::new(file)
+            .lines()
+            .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_write_updates() {
+        let dir = tempfile::tempdir().unwrap();
+        let selected = Pubkey::new_unique();
+        let ignored = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut plugin = load_plugin(
+            &dir,
+            serde_json::json!({
+                "libpath": "unused",
+                "output_path": dir.path().join("out.jsonl"),
+                "accounts_selector": { "accounts": [selected.to_string()] },
+                "notify_transactions": true,
+            }),
+        );
+
+        for pubkey in &[selected, ignored] {
+            let account = ReplicaAccountInfo {
+                pubkey: pubkey.as_ref(),
+                lamports: 42,
+                owner: owner.as_ref(),
+                executable: false,
+                rent_epoch: 1,
+                data: &[1, 2, 3],
+            };
+            plugin.update_account(&account, 7).unwrap();
+        }
+
+        let keypair = Keypair::new();
+        let transaction =
+            system_transaction::transfer(&keypair, &keypair.pubkey(), 1, Hash::default());
+        let transaction_status_meta = TransactionStatusMeta {
+            status: Ok(()),
+            fee: 5000,
+            pre_balances: vec![10, 0],
+            post_balances: vec![4999, 1],
+            inner_instructions: None,
+            log_messages: None,
+            pre_token_balances: None,
+            post_token_balances: None,
+        };
+        plugin
+            .notify_transaction(
+                &ReplicaTransactionInfo {
+                    signature: &transaction.signatures[0],
+                    is_vote: false,
+                    transaction: &transaction,
+                    transaction_status_meta: &transaction_status_meta,
+                },
+                7,
+            )
+            .unwrap();
+        plugin
+            .update_slot_status(7, Some(6), SlotStatus::Rooted)
+            .unwrap();
+        plugin.on_unload();
+
+        assert_eq!(
+            read_records(&dir),
+            vec![
+                FileRecord::Account {
+                    slot: 7,
+                    pubkey: selected.to_string(),
+                    lamports: 42,
+                    owner: owner.to_string(),
+                    executable: false,
+                    rent_epoch: 1,
+                    data: base64::encode(&[1u8, 2, 3]),
+                },
+                FileRecord::Transaction {
+                    slot: 7,
+                    signature: transaction.signatures[0].to_string(),
+                    is_vote: false,
+                    meta: transaction_status_meta.into(),
+                },
+                FileRecord::Slot {
+                    slot: 7,
+                    parent: Some(6),
+                    status: "rooted".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.json");
+        std::fs::write(&config_path, r#"{"libpath": "unused"}"#).unwrap();
+        let mut plugin = AccountsDbPluginFile::default();
+        assert!(plugin.on_load(config_path.to_str().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,6 @@
+pub mod accounts_selector;
+pub mod accountsdb_plugin_file;
+
+solana_accountsdb_plugin_interface::declare_plugin!(
+    crate::accountsdb_plugin_file::AccountsDbPluginFile
+);
@@ -0,0 +1,129 @@
+//! The `accountsdb_plugin_interface` module defines the interface a plugin must implement to
+//! receive account, slot status and transaction updates from the validator. A plugin is a shared
+//! library exporting a `_create_plugin` function that returns a `Box<dyn AccountsDbPlugin>` as a
+//! raw pointer, see `declare_plugin!`.
+
+use solana_sdk::{signature::Signature, transaction::Transaction};
+use solana_transaction_status::TransactionStatusMeta;
+use std::{any::Any, error, io};
+use thiserror::Error;
+
+/// The state of an account at the moment it was written
+#[derive(Clone, PartialEq, Debug)]
+pub struct ReplicaAccountInfo<'a> {
+    pub pubkey: &'a [u8],
+    pub lamports: u64,
+    pub owner: &'a [u8],
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub data: &'a [u8],
+}
+
+/// A committed transaction together with its execution status
+#[derive(Clone, Debug)]
+pub struct ReplicaTransactionInfo<'a> {
+    pub signature: &'a Signature,
+    pub is_vote: bool,
+    pub transaction: &'a Transaction,
+    pub transaction_status_meta: &'a TransactionStatusMeta,
+}
+
+#[derive(Error, Debug)]
+pub enum AccountsDbPluginError {
+    #[error("Error opening config file. Error detail: ({0}).")]
+    ConfigFileOpenError(#[from] io::Error),
+
+    #[error("Error reading config file. Error message: ({msg})")]
+    ConfigFileReadError { msg: String },
+
+    #[error("Error updating account. Error message: ({msg})")]
+    AccountsUpdateError { msg: String },
+
+    #[error("Error updating slot status. Error message: ({msg})")]
+    SlotStatusUpdateError { msg: String },
+
+    #[error("Error notifying transaction. Error message: ({msg})")]
+    TransactionUpdateError { msg: String },
+
+    #[error("Plugin-defined custom error. Error message: ({0})")]
+    Custom(Box<dyn error::Error + Send + Sync>),
+}
+
+/// The commitment level a slot has reached
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlotStatus {
+    /// The bank for the slot has been frozen by replay
+    Processed,
+
+    /// The slot has been optimistically confirmed by a supermajority of the cluster
+    Confirmed,
+
+    /// The slot has been rooted
+    Rooted,
+}
+
+impl SlotStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SlotStatus::Processed => "processed",
+            SlotStatus::Confirmed => "confirmed",
+            SlotStatus::Rooted => "rooted",
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, AccountsDbPluginError>;
+
+/// The interface a plugin must implement. Notifications may arrive concurrently from the replay,
+/// banking and transaction status threads, so implementations must do their own synchronization.
+pub trait AccountsDbPlugin: Any + Send + Sync + std::fmt::Debug {
+    fn name(&self) -> &'static str;
+
+    /// The callback called when a plugin is loaded by the system, used for doing whatever
+    /// initialization is required by the plugin. The `config_file` contains the name of the
+    /// config file. The config must be in JSON format and include a field "libpath" indicating
+    /// the full path name of the shared library implementing this interface.
+    fn on_load(&mut self, _config_file: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// The callback called right before a plugin is unloaded by the system
+    /// Used for doing cleanup before unload.
+    fn on_unload(&mut self) {}
+
+    /// Called when an account is updated at a slot.
+    fn update_account(&self, account: &ReplicaAccountInfo, slot: u64) -> Result<()>;
+
+    /// Called when a slot status is updated
+    fn update_slot_status(&self, slot: u64, parent: Option<u64>, status: SlotStatus) -> Result<()>;
+
+    /// Called when a transaction is committed at a slot.
+    fn notify_transaction(&self, transaction: &ReplicaTransactionInfo, slot: u64) -> Result<()>;
+}
+
+/// The symbol name the plugin manager looks up in a plugin's shared library
+pub const CREATE_PLUGIN_SYMBOL: &[u8] = b"_create_plugin";
+
+/// The signature of the function exported as `CREATE_PLUGIN_SYMBOL`
+#[allow(improper_ctypes_definitions)]
+pub type PluginConstructor = unsafe extern "C" fn() -> *mut dyn AccountsDbPlugin;
+
+/// Exports the `_create_plugin` constructor for a plugin type implementing `Default`
+#[macro_export]
+macro_rules! declare_plugin {
+    ($plugin_type:ty) => {
+        #[no_mangle]
+        #[allow(improper_ctypes_definitions)]
+        /// # Safety
+        ///
+        /// This function returns a raw pointer to the plugin, ownership of which is
+        /// transferred to the caller.
+        pub unsafe extern "C" fn _create_plugin(
+        ) -> *mut dyn $crate::accountsdb_plugin_interface::AccountsDbPlugin {
+            let plugin = <$plugin_type>::default();
+            let plugin: Box<dyn $crate::accountsdb_plugin_interface::AccountsDbPlugin> =
+                Box::new(plugin);
+            Box::into_raw(plugin)
+        }
+    };
+}
@@ -19,7 +19,10 @@ use solana_ledger::{
 use solana_measure::measure::Measure;
 use solana_perf::packet::to_packets_chunked;
 use solana_runtime::{
-    accounts_background_service::AbsRequestSender, bank::Bank, bank_forks::BankForks,
+    accounts_background_service::AbsRequestSender,
+    bank::Bank,
+    bank_forks::BankForks,
+    cost_model::{CostModel, MAX_BLOCK_UNITS, MAX_WRITABLE_ACCOUNT_UNITS},
 };
 use solana_sdk::{
     hash::Hash,
@@ -30,6 +33,7 @@ use solana_sdk::{
     transaction::Transaction,
 };
 use std::{
+    str::FromStr,
     sync::{atomic::Ordering, mpsc::Receiver, Arc, Mutex},
     thread::sleep,
     time::{Duration, Instant},
@@ -66,23 +70,61 @@ fn check_txs(
     no_bank
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WriteLockContention {
+    /// No transactions lock the same accounts
+    None,
+    /// Transactions don't lock the same account, unless they're in the same batch
+    SameBatchOnly,
+    /// All transactions write lock the same account
+    Full,
+}
+
+impl WriteLockContention {
+    fn possible_values<'a>() -> &'a [&'static str] {
+        &["none", "same-batch-only", "full"]
+    }
+}
+
+impl FromStr for WriteLockContention {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "same-batch-only" => Ok(Self::SameBatchOnly),
+            "full" => Ok(Self::Full),
+            _ => Err(format!("Invalid write lock contention: {}", s)),
+        }
+    }
+}
+
 fn make_accounts_txs(
     total_num_transactions: usize,
+    packets_per_batch: usize,
     hash: Hash,
     same_payer: bool,
+    contention: WriteLockContention,
 ) -> Vec<Transaction> {
     let to_pubkey = solana_sdk::pubkey::new_rand();
+    let batch_to_pubkeys: Vec<_> = (0..(total_num_transactions + packets_per_batch - 1)
+        / packets_per_batch)
+        .map(|_| solana_sdk::pubkey::new_rand())
+        .collect();
     let payer_key = Keypair::new();
     let dummy = system_transaction::transfer(&payer_key, &to_pubkey, 1, hash);
     (0..total_num_transactions)
         .into_par_iter()
-        .map(|_| {
+        .map(|i| {
             let mut new = dummy.clone();
             let sig: Vec<u8> = (0..64).map(|_| thread_rng().gen()).collect();
             if !same_payer {
                 new.message.account_keys[0] = solana_sdk::pubkey::new_rand();
             }
-            new.message.account_keys[1] = solana_sdk::pubkey::new_rand();
+            new.message.account_keys[1] = match contention {
+                WriteLockContention::None => solana_sdk::pubkey::new_rand(),
+                WriteLockContention::SameBatchOnly => batch_to_pubkeys[i / packets_per_batch],
+                WriteLockContention::Full => to_pubkey,
+            };
             new.signatures = vec![Signature::new(&sig[0..64])];
             new
         })
@@ -150,6 +192,30 @@ fn main() {
                 .takes_value(true)
                 .help("Number of iterations"),
         )
+        .arg(
+            Arg::with_name("write_lock_contention")
+                .long("write-lock-contention")
+                .takes_value(true)
+                .possible_values(WriteLockContention::possible_values())
+                .help("Accounts that test transactions write lock"),
+        )
+        .arg(
+            Arg::with_name("block_cost_limit")
+                .long("block-cost-limit")
+                .takes_value(true)
+                .value_name("UNITS")
+                .help("Maximum cost of the transactions in a block [default: cost model limit]"),
+        )
+        .arg(
+            Arg::with_name("account_cost_limit")
+                .long("account-cost-limit")
+                .takes_value(true)
+                .value_name("UNITS")
+                .help(
+                    "Maximum cost of the transactions that write lock the same account in a block \
+                     [default: cost model limit]",
+                ),
+        )
         .get_matches();
 
     let num_threads =
@@ -158,6 +224,12 @@ fn main() {
     let num_chunks = value_t!(matches, "num_chunks", usize).unwrap_or(16);
     let packets_per_chunk = value_t!(matches, "packets_per_chunk", usize).unwrap_or(192);
     let iterations = value_t!(matches, "iterations", usize).unwrap_or(1000);
+    let write_lock_contention = value_t!(matches, "write_lock_contention", WriteLockContention)
+        .unwrap_or(WriteLockContention::None);
+    let cost_model = CostModel::new(
+        value_t!(matches, "account_cost_limit", u64).unwrap_or(MAX_WRITABLE_ACCOUNT_UNITS),
+        value_t!(matches, "block_cost_limit", u64).unwrap_or(MAX_BLOCK_UNITS),
+    );
 
     let total_num_transactions = num_chunks * num_threads * packets_per_chunk;
     let mint_total = 1_000_000_000_000;
@@ -177,8 +249,13 @@ fn main() {
     info!("threads: {} txs: {}", num_threads, total_num_transactions);
 
     let same_payer = matches.is_present("same_payer");
-    let mut transactions =
-        make_accounts_txs(total_num_transactions, genesis_config.hash(), same_payer);
+    let mut transactions = make_accounts_txs(
+        total_num_transactions,
+        packets_per_chunk,
+        genesis_config.hash(),
+        same_payer,
+        write_lock_contention,
+    );
 
     // fund all the accounts
     transactions.iter().for_each(|tx| {
@@ -203,12 +280,14 @@ fn main() {
             assert!(res.is_ok(), "sanity test transactions error: {:?}", res);
         });
         bank.clear_signatures();
-        //sanity check, make sure all the transactions can execute in parallel
-        let res = bank.process_transactions(&transactions);
-        for r in res {
-            assert!(r.is_ok(), "sanity parallel execution error: {:?}", r);
+        if write_lock_contention == WriteLockContention::None {
+            //sanity check, make sure all the transactions can execute in parallel
+            let res = bank.process_transactions(&transactions);
+            for r in res {
+                assert!(r.is_ok(), "sanity parallel execution error: {:?}", r);
+            }
+            bank.clear_signatures();
         }
-        bank.clear_signatures();
     }
 
     let mut verified: Vec<_> = to_packets_chunked(&transactions, packets_per_chunk);
@@ -221,13 +300,14 @@ fn main() {
             create_test_recorder(&bank, &blockstore, None);
         let cluster_info = ClusterInfo::new_with_invalid_keypair(Node::new_localhost().info);
         let cluster_info = Arc::new(cluster_info);
-        let banking_stage = BankingStage::new(
+        let banking_stage = BankingStage::new_with_cost_model(
             &cluster_info,
             &poh_recorder,
             verified_receiver,
             vote_receiver,
             None,
             replay_vote_sender,
+            cost_model,
         );
         poh_recorder.lock().unwrap().set_bank(&bank);
 
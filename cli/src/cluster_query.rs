@@ -24,8 +24,9 @@ use solana_client::{
     pubsub_client::PubsubClient,
     rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
     rpc_config::{
-        RpcAccountInfoConfig, RpcLargestAccountsConfig, RpcLargestAccountsFilter,
-        RpcProgramAccountsConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter,
+        RpcAccountInfoConfig, RpcBlockProductionConfig, RpcBlockProductionConfigRange,
+        RpcLargestAccountsConfig, RpcLargestAccountsFilter, RpcProgramAccountsConfig,
+        RpcTransactionLogsConfig, RpcTransactionLogsFilter,
     },
     rpc_filter,
     rpc_response::SlotInfo,
@@ -54,7 +55,7 @@ use solana_sdk::{
 };
 use solana_transaction_status::UiTransactionEncoding;
 use std::{
-    collections::{BTreeMap, HashMap, VecDeque},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     fmt,
     net::SocketAddr,
     sync::{
@@ -1072,94 +1073,91 @@ pub fn process_show_block_production(
 
     let progress_bar = new_spinner_progress_bar();
     progress_bar.set_message(&format!(
-        "Fetching confirmed blocks between slots {} and {}...",
+        "Fetching block production between slots {} and {}...",
         start_slot, end_slot
     ));
-    let confirmed_blocks = rpc_client.get_confirmed_blocks(start_slot, Some(end_slot))?;
-
-    let start_slot_index = (start_slot - first_slot_in_epoch) as usize;
-    let end_slot_index = (end_slot - first_slot_in_epoch) as usize;
-    let total_slots = end_slot_index - start_slot_index + 1;
-    let total_blocks_produced = confirmed_blocks.len();
-    assert!(total_blocks_produced <= total_slots);
-    let total_slots_skipped = total_slots - total_blocks_produced;
-    let mut leader_slot_count = HashMap::new();
-    let mut leader_skipped_slots = HashMap::new();
-
-    progress_bar.set_message(&format!("Fetching leader schedule for epoch {}...", epoch));
-    let leader_schedule = rpc_client
-        .get_leader_schedule_with_commitment(Some(start_slot), CommitmentConfig::finalized())?;
-    if leader_schedule.is_none() {
-        return Err(format!("Unable to fetch leader schedule for slot {}", start_slot).into());
-    }
-    let leader_schedule = leader_schedule.unwrap();
-
-    let mut leader_per_slot_index = Vec::new();
-    leader_per_slot_index.resize(total_slots, "?".to_string());
-    for (pubkey, leader_slots) in leader_schedule.iter() {
-        let pubkey = format_labeled_address(pubkey, &config.address_labels);
-        for slot_index in leader_slots.iter() {
-            if *slot_index >= start_slot_index && *slot_index <= end_slot_index {
-                leader_per_slot_index[*slot_index - start_slot_index] = pubkey.clone();
-            }
-        }
-    }
+    let block_production = rpc_client
+        .get_block_production_with_config(RpcBlockProductionConfig {
+            identity: None,
+            range: Some(RpcBlockProductionConfigRange {
+                first_slot: start_slot,
+                last_slot: Some(end_slot),
+            }),
+        })?
+        .value;
+    // The node may have trimmed the range to the slots it has ledger data for
+    let start_slot = block_production.range.first_slot;
+    let end_slot = block_production.range.last_slot;
 
-    progress_bar.set_message(&format!(
-        "Processing {} slots containing {} blocks and {} empty slots...",
-        total_slots, total_blocks_produced, total_slots_skipped
-    ));
+    let mut leaders: Vec<CliBlockProductionEntry> = block_production
+        .by_identity
+        .iter()
+        .map(
+            |(identity, (leader_slots, blocks_produced))| CliBlockProductionEntry {
+                identity_pubkey: format_labeled_address(identity, &config.address_labels),
+                leader_slots: *leader_slots as u64,
+                blocks_produced: *blocks_produced as u64,
+                skipped_slots: (leader_slots - blocks_produced) as u64,
+            },
+        )
+        .collect();
+    leaders.sort_by(|a, b| a.identity_pubkey.partial_cmp(&b.identity_pubkey).unwrap());
+    // Slots whose leader the node doesn't know are left out of the counts, rather than being
+    // taken for skipped slots
+    let total_slots: usize = leaders
+        .iter()
+        .map(|leader| leader.leader_slots as usize)
+        .sum();
+    let total_blocks_produced = leaders
+        .iter()
+        .map(|leader| leader.blocks_produced as usize)
+        .sum();
+    let total_slots_skipped = total_slots.saturating_sub(total_blocks_produced);
 
-    let mut confirmed_blocks_index = 0;
     let mut individual_slot_status = vec![];
-    for (slot_index, leader) in leader_per_slot_index.iter().enumerate() {
-        let slot = start_slot + slot_index as u64;
-        let slot_count = leader_slot_count.entry(leader).or_insert(0);
-        *slot_count += 1;
-        let skipped_slots = leader_skipped_slots.entry(leader).or_insert(0);
-
-        loop {
-            if confirmed_blocks_index < confirmed_blocks.len() {
-                let slot_of_next_confirmed_block = confirmed_blocks[confirmed_blocks_index];
-                if slot_of_next_confirmed_block < slot {
-                    confirmed_blocks_index += 1;
-                    continue;
-                }
-                if slot_of_next_confirmed_block == slot {
-                    individual_slot_status.push(CliSlotStatus {
-                        slot,
-                        leader: (*leader).to_string(),
-                        skipped: false,
-                    });
-                    break;
+    if config.verbose {
+        progress_bar.set_message(&format!(
+            "Fetching confirmed blocks between slots {} and {}...",
+            start_slot, end_slot
+        ));
+        let confirmed_blocks: HashSet<Slot> = rpc_client
+            .get_confirmed_blocks(start_slot, Some(end_slot))?
+            .into_iter()
+            .collect();
+
+        progress_bar.set_message(&format!("Fetching leader schedule for epoch {}...", epoch));
+        let leader_schedule = rpc_client
+            .get_leader_schedule_with_commitment(Some(start_slot), CommitmentConfig::finalized())?
+            .ok_or_else(|| format!("Unable to fetch leader schedule for slot {}", start_slot))?;
+
+        let start_slot_index = (start_slot - first_slot_in_epoch) as usize;
+        let num_slots = (end_slot - start_slot + 1) as usize;
+        let mut leader_per_slot_index = vec!["?".to_string(); num_slots];
+        for (pubkey, leader_slots) in leader_schedule.iter() {
+            let pubkey = format_labeled_address(pubkey, &config.address_labels);
+            for slot_index in leader_slots.iter() {
+                if *slot_index >= start_slot_index && *slot_index < start_slot_index + num_slots {
+                    leader_per_slot_index[*slot_index - start_slot_index] = pubkey.clone();
                 }
             }
-            *skipped_slots += 1;
-            individual_slot_status.push(CliSlotStatus {
-                slot,
-                leader: (*leader).to_string(),
-                skipped: true,
-            });
-            break;
         }
+
+        individual_slot_status = leader_per_slot_index
+            .into_iter()
+            .enumerate()
+            .map(|(slot_index, leader)| {
+                let slot = start_slot + slot_index as u64;
+                CliSlotStatus {
+                    slot,
+                    leader,
+                    skipped: !confirmed_blocks.contains(&slot),
+                }
+            })
+            .collect();
     }
 
     progress_bar.finish_and_clear();
 
-    let mut leaders: Vec<CliBlockProductionEntry> = leader_slot_count
-        .iter()
-        .map(|(leader, leader_slots)| {
-            let skipped_slots = leader_skipped_slots.get(leader).unwrap();
-            let blocks_produced = leader_slots - skipped_slots;
-            CliBlockProductionEntry {
-                identity_pubkey: (**leader).to_string(),
-                leader_slots: *leader_slots,
-                blocks_produced,
-                skipped_slots: *skipped_slots,
-            }
-        })
-        .collect();
-    leaders.sort_by(|a, b| a.identity_pubkey.partial_cmp(&b.identity_pubkey).unwrap());
     let block_production = CliBlockProduction {
         epoch,
         start_slot,
@@ -1803,6 +1801,7 @@ pub fn process_transaction_history(
             before,
             until,
             limit: Some(limit),
+            ..GetConfirmedSignaturesForAddress2Config::default()
         },
     )?;
 
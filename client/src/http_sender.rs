@@ -5,12 +5,14 @@ use crate::{
     rpc_response::RpcSimulateTransactionResult,
     rpc_sender::RpcSender,
 };
+use async_trait::async_trait;
 use log::*;
 use reqwest::{self, header::CONTENT_TYPE, StatusCode};
-use std::{thread::sleep, time::Duration};
+use std::time::Duration;
+use tokio::time::sleep;
 
 pub struct HttpSender {
-    client: reqwest::blocking::Client,
+    client: reqwest::Client,
     url: String,
 }
 
@@ -20,7 +22,7 @@ impl HttpSender {
     }
 
     pub fn new_with_timeout(url: String, timeout: Duration) -> Self {
-        let client = reqwest::blocking::Client::builder()
+        let client = reqwest::Client::builder()
             .timeout(timeout)
             .build()
             .expect("build rpc client");
@@ -36,8 +38,13 @@ struct RpcErrorObject {
     data: serde_json::Value,
 }
 
+#[async_trait]
 impl RpcSender for HttpSender {
-    fn send(&self, request: RpcRequest, params: serde_json::Value) -> Result<serde_json::Value> {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
         // Concurrent requests are not supported so reuse the same request id for all requests
         let request_id = 1;
 
@@ -51,6 +58,7 @@ impl RpcSender for HttpSender {
                 .header(CONTENT_TYPE, "application/json")
                 .body(request_json.to_string())
                 .send()
+                .await
             {
                 Ok(response) => {
                     if !response.status().is_success() {
@@ -64,13 +72,13 @@ impl RpcSender for HttpSender {
                             );
 
                             // Sleep for 500ms to give the server a break
-                            sleep(Duration::from_millis(500));
+                            sleep(Duration::from_millis(500)).await;
                             continue;
                         }
                         return Err(response.error_for_status().unwrap_err().into());
                     }
 
-                    let json: serde_json::Value = serde_json::from_str(&response.text()?)?;
+                    let json: serde_json::Value = serde_json::from_str(&response.text().await?)?;
                     if json["error"].is_object() {
                         return match serde_json::from_value::<RpcErrorObject>(json["error"].clone())
                         {
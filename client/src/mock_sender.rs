@@ -4,13 +4,14 @@ use crate::{
     rpc_response::{Response, RpcResponseContext, RpcVersionInfo},
     rpc_sender::RpcSender,
 };
+use async_trait::async_trait;
 use serde_json::{json, Number, Value};
 use solana_sdk::{
     epoch_info::EpochInfo,
     fee_calculator::{FeeCalculator, FeeRateGovernor},
     instruction::InstructionError,
     signature::Signature,
-    transaction::{self, Transaction, TransactionError},
+    transaction::{self, TransactionError, VersionedTransaction},
 };
 use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
 use solana_version::Version;
@@ -39,8 +40,13 @@ impl MockSender {
     }
 }
 
+#[async_trait]
 impl RpcSender for MockSender {
-    fn send(&self, request: RpcRequest, params: serde_json::Value) -> Result<serde_json::Value> {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
         if let Some(value) = self.mocks.write().unwrap().remove(&request) {
             return Ok(value);
         }
@@ -128,7 +134,7 @@ impl RpcSender for MockSender {
                 } else {
                     let tx_str = params.as_array().unwrap()[0].as_str().unwrap().to_string();
                     let data = base64::decode(tx_str).unwrap();
-                    let tx: Transaction = bincode::deserialize(&data).unwrap();
+                    let tx: VersionedTransaction = bincode::deserialize(&data).unwrap();
                     tx.signatures[0].to_string()
                 };
                 Value::String(signature)
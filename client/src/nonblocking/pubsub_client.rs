@@ -0,0 +1,374 @@
+//! An async client for the websocket pubsub API.
+//!
+//! Unlike [`crate::pubsub_client::PubsubClient`], which opens a websocket and a thread per
+//! subscription, this client multiplexes any number of subscriptions over a single connection
+//! that is driven by a tokio task.
+
+use crate::{
+    rpc_config::{
+        RpcAccountInfoConfig, RpcBlockSubscribeConfig, RpcBlockSubscribeFilter,
+        RpcProgramAccountsConfig, RpcSignatureSubscribeConfig, RpcTransactionLogsConfig,
+        RpcTransactionLogsFilter,
+    },
+    rpc_response::{
+        Response as RpcResponse, RpcBlockUpdate, RpcKeyedAccount, RpcLogsResponse,
+        RpcSignatureResult, RpcVote, SlotInfo, SlotUpdate,
+    },
+};
+use futures_util::{
+    future::{ready, BoxFuture, FutureExt},
+    sink::SinkExt,
+    stream::{BoxStream, StreamExt},
+};
+use log::*;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+use solana_account_decoder::UiAccount;
+use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature};
+use std::collections::BTreeMap;
+use thiserror::Error;
+use tokio::{
+    net::TcpStream,
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+    time::{sleep, Duration},
+};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{
+        protocol::frame::{coding::CloseCode, CloseFrame},
+        Message,
+    },
+    MaybeTlsStream, WebSocketStream,
+};
+use url::Url;
+
+pub type PubsubClientResult<T = ()> = Result<T, PubsubClientError>;
+
+#[derive(Debug, Error)]
+pub enum PubsubClientError {
+    #[error("url parse error")]
+    UrlParseError(#[from] url::ParseError),
+
+    #[error("unable to connect to server")]
+    ConnectionError(tokio_tungstenite::tungstenite::Error),
+
+    #[error("websocket error")]
+    WsError(#[from] tokio_tungstenite::tungstenite::Error),
+
+    #[error("connection closed ({0})")]
+    ConnectionClosed(String),
+
+    #[error("json parse error")]
+    JsonParseError(#[from] serde_json::error::Error),
+
+    #[error("subscribe failed: {reason}")]
+    SubscribeFailed { reason: String, message: String },
+}
+
+/// Closes a single subscription, resolving once the server has acknowledged the unsubscribe
+pub type UnsubscribeFn = Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send>;
+
+type SubscribeResponseMsg =
+    Result<(mpsc::UnboundedReceiver<Value>, UnsubscribeFn), PubsubClientError>;
+type SubscribeRequestMsg = (String, Value, oneshot::Sender<SubscribeResponseMsg>);
+type UnsubscribeRequestMsg = (String, u64, oneshot::Sender<()>);
+type SubscribeResult<'a, T> = PubsubClientResult<(BoxStream<'a, T>, UnsubscribeFn)>;
+
+// How long the connection may be idle before a ping is sent to keep it alive
+const PING_INTERVAL: Duration = Duration::from_secs(10);
+
+pub struct PubsubClient {
+    subscribe_sender: mpsc::UnboundedSender<SubscribeRequestMsg>,
+    shutdown_sender: oneshot::Sender<()>,
+    ws: JoinHandle<PubsubClientResult>,
+}
+
+impl PubsubClient {
+    pub async fn new(url: &str) -> PubsubClientResult<Self> {
+        let url = Url::parse(url)?;
+        let (ws, _response) = connect_async(url)
+            .await
+            .map_err(PubsubClientError::ConnectionError)?;
+
+        let (subscribe_sender, subscribe_receiver) = mpsc::unbounded_channel();
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel();
+
+        Ok(Self {
+            subscribe_sender,
+            shutdown_sender,
+            ws: tokio::spawn(Self::run_ws(ws, subscribe_receiver, shutdown_receiver)),
+        })
+    }
+
+    /// Closes the websocket, ending every subscription made through this client
+    pub async fn shutdown(self) -> PubsubClientResult {
+        let _ = self.shutdown_sender.send(());
+        self.ws
+            .await
+            .map_err(|err| PubsubClientError::ConnectionClosed(err.to_string()))?
+    }
+
+    async fn subscribe<'a, T>(&self, operation: &str, params: Value) -> SubscribeResult<'a, T>
+    where
+        T: DeserializeOwned + Send + 'a,
+    {
+        let (response_sender, response_receiver) = oneshot::channel();
+        self.subscribe_sender
+            .send((operation.to_string(), params, response_sender))
+            .map_err(|err| PubsubClientError::ConnectionClosed(err.to_string()))?;
+
+        let (notifications, unsubscribe) = response_receiver
+            .await
+            .map_err(|err| PubsubClientError::ConnectionClosed(err.to_string()))??;
+        let operation = operation.to_string();
+        Ok((
+            UnboundedReceiverStream::new(notifications)
+                .filter_map(move |value| {
+                    ready(match serde_json::from_value::<T>(value) {
+                        Ok(notification) => Some(notification),
+                        Err(err) => {
+                            warn!("Failed to deserialize {} notification: {}", operation, err);
+                            None
+                        }
+                    })
+                })
+                .boxed(),
+            unsubscribe,
+        ))
+    }
+
+    pub async fn account_subscribe(
+        &self,
+        pubkey: &Pubkey,
+        config: Option<RpcAccountInfoConfig>,
+    ) -> SubscribeResult<'_, RpcResponse<UiAccount>> {
+        let params = json!([pubkey.to_string(), config]);
+        self.subscribe("account", params).await
+    }
+
+    pub async fn block_subscribe(
+        &self,
+        filter: RpcBlockSubscribeFilter,
+        config: Option<RpcBlockSubscribeConfig>,
+    ) -> SubscribeResult<'_, RpcResponse<RpcBlockUpdate>> {
+        self.subscribe("block", json!([filter, config])).await
+    }
+
+    pub async fn logs_subscribe(
+        &self,
+        filter: RpcTransactionLogsFilter,
+        config: RpcTransactionLogsConfig,
+    ) -> SubscribeResult<'_, RpcResponse<RpcLogsResponse>> {
+        self.subscribe("logs", json!([filter, config])).await
+    }
+
+    pub async fn program_subscribe(
+        &self,
+        pubkey: &Pubkey,
+        config: Option<RpcProgramAccountsConfig>,
+    ) -> SubscribeResult<'_, RpcResponse<RpcKeyedAccount>> {
+        let params = json!([pubkey.to_string(), config]);
+        self.subscribe("program", params).await
+    }
+
+    pub async fn vote_subscribe(&self) -> SubscribeResult<'_, RpcVote> {
+        self.subscribe("vote", json!([])).await
+    }
+
+    pub async fn root_subscribe(&self) -> SubscribeResult<'_, Slot> {
+        self.subscribe("root", json!([])).await
+    }
+
+    pub async fn signature_subscribe(
+        &self,
+        signature: &Signature,
+        config: Option<RpcSignatureSubscribeConfig>,
+    ) -> SubscribeResult<'_, RpcResponse<RpcSignatureResult>> {
+        let params = json!([signature.to_string(), config]);
+        self.subscribe("signature", params).await
+    }
+
+    pub async fn slot_subscribe(&self) -> SubscribeResult<'_, SlotInfo> {
+        self.subscribe("slot", json!([])).await
+    }
+
+    pub async fn slot_updates_subscribe(&self) -> SubscribeResult<'_, SlotUpdate> {
+        self.subscribe("slotsUpdates", json!([])).await
+    }
+
+    async fn run_ws(
+        mut ws: WebSocketStream<MaybeTlsStream<TcpStream>>,
+        mut subscribe_receiver: mpsc::UnboundedReceiver<SubscribeRequestMsg>,
+        mut shutdown_receiver: oneshot::Receiver<()>,
+    ) -> PubsubClientResult {
+        let mut request_id: u64 = 0;
+
+        let mut requests_subscribe = BTreeMap::new();
+        let mut requests_unsubscribe = BTreeMap::<u64, oneshot::Sender<()>>::new();
+        let mut subscriptions = BTreeMap::new();
+        let (unsubscribe_sender, mut unsubscribe_receiver) =
+            mpsc::unbounded_channel::<UnsubscribeRequestMsg>();
+
+        loop {
+            tokio::select! {
+                _ = (&mut shutdown_receiver) => {
+                    let frame = CloseFrame { code: CloseCode::Normal, reason: "".into() };
+                    ws.send(Message::Close(Some(frame))).await?;
+                    ws.flush().await?;
+                    break;
+                },
+                () = sleep(PING_INTERVAL) => {
+                    ws.send(Message::Ping(Vec::new())).await?;
+                },
+                Some((operation, params, response_sender)) = subscribe_receiver.recv() => {
+                    request_id += 1;
+                    let method = format!("{}Subscribe", operation);
+                    let text = json!({
+                        "jsonrpc":"2.0","id":request_id,"method":method,"params":params
+                    })
+                    .to_string();
+                    ws.send(Message::Text(text)).await?;
+                    requests_subscribe.insert(request_id, (operation, response_sender));
+                },
+                Some((operation, subscription_id, response_sender)) = unsubscribe_receiver.recv() => {
+                    subscriptions.remove(&subscription_id);
+                    request_id += 1;
+                    let method = format!("{}Unsubscribe", operation);
+                    let text = json!({
+                        "jsonrpc":"2.0","id":request_id,"method":method,"params":[subscription_id]
+                    })
+                    .to_string();
+                    ws.send(Message::Text(text)).await?;
+                    requests_unsubscribe.insert(request_id, response_sender);
+                },
+                next_message = ws.next() => {
+                    let message = match next_message {
+                        Some(message) => message?,
+                        None => break,
+                    };
+                    let text = match message {
+                        Message::Text(text) => text,
+                        Message::Binary(_data) => continue,
+                        Message::Ping(data) => {
+                            ws.send(Message::Pong(data)).await?;
+                            continue;
+                        }
+                        Message::Pong(_data) => continue,
+                        Message::Close(_frame) => break,
+                    };
+                    let mut json: Map<String, Value> = serde_json::from_str(&text)?;
+
+                    // Subscribe/unsubscribe response, eg:
+                    // `{"jsonrpc":"2.0","result":5308752,"id":1}`
+                    if let Some(id) = json.get("id") {
+                        let id = id.as_u64().ok_or_else(|| PubsubClientError::SubscribeFailed {
+                            reason: "invalid `id` field".to_string(),
+                            message: text.clone(),
+                        })?;
+
+                        if let Some(response_sender) = requests_unsubscribe.remove(&id) {
+                            // The caller may have stopped waiting for the acknowledgement
+                            let _ = response_sender.send(());
+                        } else if let Some((operation, response_sender)) =
+                            requests_subscribe.remove(&id)
+                        {
+                            if let Some(error) = json.get("error") {
+                                let _ = response_sender.send(Err(PubsubClientError::SubscribeFailed {
+                                    reason: error.to_string(),
+                                    message: text.clone(),
+                                }));
+                                continue;
+                            }
+
+                            let subscription_id = json
+                                .get("result")
+                                .and_then(Value::as_u64)
+                                .ok_or_else(|| PubsubClientError::SubscribeFailed {
+                                    reason: "invalid `result` field".to_string(),
+                                    message: text.clone(),
+                                })?;
+
+                            let (notifications_sender, notifications_receiver) =
+                                mpsc::unbounded_channel();
+                            let unsubscribe: UnsubscribeFn = {
+                                let operation = operation.clone();
+                                let unsubscribe_sender = unsubscribe_sender.clone();
+                                Box::new(move || {
+                                    async move {
+                                        let (response_sender, response_receiver) =
+                                            oneshot::channel();
+                                        // Nothing to do if the websocket is already closed
+                                        if unsubscribe_sender
+                                            .send((operation, subscription_id, response_sender))
+                                            .is_ok()
+                                        {
+                                            let _ = response_receiver.await;
+                                        }
+                                    }
+                                    .boxed()
+                                })
+                            };
+
+                            if response_sender
+                                .send(Ok((notifications_receiver, unsubscribe)))
+                                .is_err()
+                            {
+                                // The caller stopped waiting for the subscription, which
+                                // nobody can unsubscribe from now but this client
+                                let (response_sender, _response_receiver) = oneshot::channel();
+                                let _ = unsubscribe_sender.send((
+                                    operation,
+                                    subscription_id,
+                                    response_sender,
+                                ));
+                                continue;
+                            }
+                            subscriptions.insert(subscription_id, notifications_sender);
+                        } else {
+                            warn!("Ignoring response to unknown request id: {}", id);
+                        }
+                        continue;
+                    }
+
+                    // Notification, eg:
+                    // `{"jsonrpc":"2.0","method":"logsNotification","params":{"result":{...},"subscription":3114862}}`
+                    if let Some(Value::Object(params)) = json.get_mut("params") {
+                        if let Some(subscription_id) =
+                            params.get("subscription").and_then(Value::as_u64)
+                        {
+                            let mut unsubscribe_required = false;
+
+                            if let Some(notifications_sender) = subscriptions.get(&subscription_id) {
+                                if let Some(result) = params.remove("result") {
+                                    // The stream was dropped without unsubscribing
+                                    if notifications_sender.send(result).is_err() {
+                                        unsubscribe_required = true;
+                                    }
+                                }
+                            } else {
+                                unsubscribe_required = true;
+                            }
+
+                            if unsubscribe_required {
+                                if let Some(Value::String(method)) = json.remove("method") {
+                                    if let Some(operation) = method.strip_suffix("Notification") {
+                                        let (response_sender, _response_receiver) =
+                                            oneshot::channel();
+                                        let _ = unsubscribe_sender.send((
+                                            operation.to_string(),
+                                            subscription_id,
+                                            response_sender,
+                                        ));
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
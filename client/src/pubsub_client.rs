@@ -1,6 +1,13 @@
 use crate::{
-    rpc_config::{RpcSignatureSubscribeConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter},
-    rpc_response::{Response as RpcResponse, RpcLogsResponse, RpcSignatureResult, SlotInfo},
+    rpc_config::{
+        RpcAccountInfoConfig, RpcBlockSubscribeConfig, RpcBlockSubscribeFilter,
+        RpcProgramAccountsConfig, RpcSignatureSubscribeConfig, RpcTransactionLogsConfig,
+        RpcTransactionLogsFilter,
+    },
+    rpc_response::{
+        Response as RpcResponse, RpcBlockUpdate, RpcKeyedAccount, RpcLogsResponse,
+        RpcSignatureResult, RpcVote, SlotInfo, SlotUpdate,
+    },
 };
 use log::*;
 use serde::de::DeserializeOwned;
@@ -9,7 +16,8 @@ use serde_json::{
     value::Value::{Number, Object},
     Map, Value,
 };
-use solana_sdk::signature::Signature;
+use solana_account_decoder::UiAccount;
+use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature};
 use std::{
     marker::PhantomData,
     sync::{
@@ -146,24 +154,42 @@ where
     }
 }
 
+pub type AccountSubscription = (
+    PubsubClientSubscription<RpcResponse<UiAccount>>,
+    Receiver<RpcResponse<UiAccount>>,
+);
+pub type ProgramSubscription = (
+    PubsubClientSubscription<RpcResponse<RpcKeyedAccount>>,
+    Receiver<RpcResponse<RpcKeyedAccount>>,
+);
+pub type BlockSubscription = (
+    PubsubClientSubscription<RpcResponse<RpcBlockUpdate>>,
+    Receiver<RpcResponse<RpcBlockUpdate>>,
+);
 pub type LogsSubscription = (
     PubsubClientSubscription<RpcResponse<RpcLogsResponse>>,
     Receiver<RpcResponse<RpcLogsResponse>>,
 );
 pub type SlotsSubscription = (PubsubClientSubscription<SlotInfo>, Receiver<SlotInfo>);
+pub type SlotsUpdatesSubscription = (PubsubClientSubscription<SlotUpdate>, Receiver<SlotUpdate>);
 pub type SignatureSubscription = (
     PubsubClientSubscription<RpcResponse<RpcSignatureResult>>,
     Receiver<RpcResponse<RpcSignatureResult>>,
 );
+pub type VoteSubscription = (PubsubClientSubscription<RpcVote>, Receiver<RpcVote>);
+pub type RootSubscription = (PubsubClientSubscription<Slot>, Receiver<Slot>);
 
 pub struct PubsubClient {}
 
 impl PubsubClient {
-    pub fn logs_subscribe(
+    fn subscribe<T>(
         url: &str,
-        filter: RpcTransactionLogsFilter,
-        config: RpcTransactionLogsConfig,
-    ) -> Result<LogsSubscription, PubsubClientError> {
+        operation: &'static str,
+        params: Value,
+    ) -> Result<(PubsubClientSubscription<T>, Receiver<T>), PubsubClientError>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
         let url = Url::parse(url)?;
         let (socket, _response) = connect(url)?;
         let (sender, receiver) = channel();
@@ -172,15 +198,14 @@ impl PubsubClient {
         let socket_clone = socket.clone();
         let exit = Arc::new(AtomicBool::new(false));
         let exit_clone = exit.clone();
-
-        let subscription_id =
-            PubsubClientSubscription::<RpcResponse<RpcLogsResponse>>::send_subscribe(
-                &socket_clone,
-                json!({
-                    "jsonrpc":"2.0","id":1,"method":"logsSubscribe","params":[filter, config]
-                })
-                .to_string(),
-            )?;
+        let body = json!({
+            "jsonrpc":"2.0",
+            "id":1,
+            "method":format!("{}Subscribe", operation),
+            "params":params
+        })
+        .to_string();
+        let subscription_id = PubsubClientSubscription::<T>::send_subscribe(&socket_clone, body)?;
 
         let t_cleanup = std::thread::spawn(move || {
             loop {
@@ -208,7 +233,7 @@ impl PubsubClient {
 
         let result = PubsubClientSubscription {
             message_type: PhantomData,
-            operation: "logs",
+            operation,
             socket,
             subscription_id,
             t_cleanup: Some(t_cleanup),
@@ -218,56 +243,46 @@ impl PubsubClient {
         Ok((result, receiver))
     }
 
-    pub fn slot_subscribe(url: &str) -> Result<SlotsSubscription, PubsubClientError> {
-        let url = Url::parse(url)?;
-        let (socket, _response) = connect(url)?;
-        let (sender, receiver) = channel::<SlotInfo>();
+    pub fn account_subscribe(
+        url: &str,
+        pubkey: &Pubkey,
+        config: Option<RpcAccountInfoConfig>,
+    ) -> Result<AccountSubscription, PubsubClientError> {
+        Self::subscribe(url, "account", json!([pubkey.to_string(), config]))
+    }
 
-        let socket = Arc::new(RwLock::new(socket));
-        let socket_clone = socket.clone();
-        let exit = Arc::new(AtomicBool::new(false));
-        let exit_clone = exit.clone();
-        let subscription_id = PubsubClientSubscription::<SlotInfo>::send_subscribe(
-            &socket_clone,
-            json!({
-                "jsonrpc":"2.0","id":1,"method":"slotSubscribe","params":[]
-            })
-            .to_string(),
-        )?;
+    pub fn program_subscribe(
+        url: &str,
+        pubkey: &Pubkey,
+        config: Option<RpcProgramAccountsConfig>,
+    ) -> Result<ProgramSubscription, PubsubClientError> {
+        Self::subscribe(url, "program", json!([pubkey.to_string(), config]))
+    }
 
-        let t_cleanup = std::thread::spawn(move || {
-            loop {
-                if exit_clone.load(Ordering::Relaxed) {
-                    break;
-                }
-                match PubsubClientSubscription::read_message(&socket_clone) {
-                    Ok(message) => match sender.send(message) {
-                        Ok(_) => (),
-                        Err(err) => {
-                            info!("receive error: {:?}", err);
-                            break;
-                        }
-                    },
-                    Err(err) => {
-                        info!("receive error: {:?}", err);
-                        break;
-                    }
-                }
-            }
+    pub fn block_subscribe(
+        url: &str,
+        filter: RpcBlockSubscribeFilter,
+        config: Option<RpcBlockSubscribeConfig>,
+    ) -> Result<BlockSubscription, PubsubClientError> {
+        Self::subscribe(url, "block", json!([filter, config]))
+    }
 
-            info!("websocket - exited receive loop");
-        });
+    pub fn logs_subscribe(
+        url: &str,
+        filter: RpcTransactionLogsFilter,
+        config: RpcTransactionLogsConfig,
+    ) -> Result<LogsSubscription, PubsubClientError> {
+        Self::subscribe(url, "logs", json!([filter, config]))
+    }
 
-        let result = PubsubClientSubscription {
-            message_type: PhantomData,
-            operation: "slot",
-            socket,
-            subscription_id,
-            t_cleanup: Some(t_cleanup),
-            exit,
-        };
+    pub fn slot_subscribe(url: &str) -> Result<SlotsSubscription, PubsubClientError> {
+        Self::subscribe(url, "slot", json!([]))
+    }
 
-        Ok((result, receiver))
+    pub fn slot_updates_subscribe(
+        url: &str,
+    ) -> Result<SlotsUpdatesSubscription, PubsubClientError> {
+        Self::subscribe(url, "slotsUpdates", json!([]))
     }
 
     pub fn signature_subscribe(
@@ -275,66 +290,15 @@ impl PubsubClient {
         signature: &Signature,
         config: Option<RpcSignatureSubscribeConfig>,
     ) -> Result<SignatureSubscription, PubsubClientError> {
-        let url = Url::parse(url)?;
-        let (socket, _response) = connect(url)?;
-        let (sender, receiver) = channel();
-
-        let socket = Arc::new(RwLock::new(socket));
-        let socket_clone = socket.clone();
-        let exit = Arc::new(AtomicBool::new(false));
-        let exit_clone = exit.clone();
-        let body = json!({
-            "jsonrpc":"2.0",
-            "id":1,
-            "method":"signatureSubscribe",
-            "params":[
-                signature.to_string(),
-                config
-            ]
-        })
-        .to_string();
-        let subscription_id =
-            PubsubClientSubscription::<RpcResponse<RpcSignatureResult>>::send_subscribe(
-                &socket_clone,
-                body,
-            )?;
-
-        let t_cleanup = std::thread::spawn(move || {
-            loop {
-                if exit_clone.load(Ordering::Relaxed) {
-                    break;
-                }
-
-                let message: Result<RpcResponse<RpcSignatureResult>, PubsubClientError> =
-                    PubsubClientSubscription::read_message(&socket_clone);
-
-                if let Ok(msg) = message {
-                    match sender.send(msg.clone()) {
-                        Ok(_) => (),
-                        Err(err) => {
-                            info!("receive error: {:?}", err);
-                            break;
-                        }
-                    }
-                } else {
-                    info!("receive error: {:?}", message);
-                    break;
-                }
-            }
-
-            info!("websocket - exited receive loop");
-        });
+        Self::subscribe(url, "signature", json!([signature.to_string(), config]))
+    }
 
-        let result = PubsubClientSubscription {
-            message_type: PhantomData,
-            operation: "signature",
-            socket,
-            subscription_id,
-            t_cleanup: Some(t_cleanup),
-            exit,
-        };
+    pub fn vote_subscribe(url: &str) -> Result<VoteSubscription, PubsubClientError> {
+        Self::subscribe(url, "vote", json!([]))
+    }
 
-        Ok((result, receiver))
+    pub fn root_subscribe(url: &str) -> Result<RootSubscription, PubsubClientError> {
+        Self::subscribe(url, "root", json!([]))
     }
 }
 
@@ -0,0 +1,300 @@
+//! The `quic_client` module sends wire transactions to the QUIC port of a TPU, one
+//! transaction per unidirectional stream.  Connections are authenticated with a self-signed
+//! certificate of the client keypair and are kept open for later transactions.  Batches may
+//! be sent without waiting on them, in which case an endpoint that failed is skipped for a
+//! while, so that callers fall back to UDP rather than stall on it.
+
+use futures_util::future::join_all;
+use log::*;
+use quinn::{
+    ClientConfig, ConnectError, Connection, ConnectionError, Endpoint, EndpointError,
+    NewConnection, WriteError,
+};
+use rustls::{
+    Certificate, ProtocolVersion, RootCertStore, ServerCertVerified, ServerCertVerifier, TLSError,
+};
+use solana_sdk::signature::Keypair;
+use solana_streamer::{
+    quic::ALPN_TPU_PROTOCOL_ID,
+    tls_certificates::{new_self_signed_tls_certificate, QUIC_SERVER_NAME},
+};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tokio::{runtime::Runtime, time::timeout};
+
+/// How long sending to a TPU may take, including connecting to it
+const QUIC_SEND_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long an endpoint which failed a send is skipped by `try_send_wire_transaction_batch`
+const FAILED_ENDPOINT_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Maximum number of batches being sent by `try_send_wire_transaction_batch` at once
+const MAX_PENDING_BATCHES: usize = 256;
+
+#[derive(Error, Debug)]
+pub enum QuicClientError {
+    #[error("certificate error: {0}")]
+    Certificate(#[from] rcgen::RcgenError),
+
+    #[error("TLS error: {0}")]
+    Tls(#[from] TLSError),
+
+    #[error("endpoint error: {0}")]
+    Endpoint(#[from] EndpointError),
+
+    #[error("connect error: {0}")]
+    Connect(#[from] ConnectError),
+
+    #[error("connection error: {0}")]
+    Connection(#[from] ConnectionError),
+
+    #[error("write error: {0}")]
+    Write(#[from] WriteError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("timed out sending to {0}")]
+    Timeout(SocketAddr),
+
+    #[error("{0} failed recently")]
+    FailedEndpoint(SocketAddr),
+
+    #[error("too many batches pending")]
+    TooManyPendingBatches,
+}
+
+/// Transactions are public, so there is no need to check who serves the TPU port
+struct SkipServerVerification;
+
+impl ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _roots: &RootCertStore,
+        _presented_certs: &[Certificate],
+        _dns_name: webpki::DNSNameRef,
+        _ocsp_response: &[u8],
+    ) -> Result<ServerCertVerified, TLSError> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+pub struct QuicClient {
+    runtime: Runtime,
+    connections: Arc<ConnectionCache>,
+    pending_batches: Arc<AtomicUsize>,
+}
+
+impl QuicClient {
+    /// Create a client authenticated by `keypair`
+    pub fn new(keypair: &Keypair) -> Result<Self, QuicClientError> {
+        let (certificate, private_key) = new_self_signed_tls_certificate(keypair)?;
+        let mut crypto = rustls::ClientConfig::new();
+        crypto.versions = vec![ProtocolVersion::TLSv1_3];
+        crypto
+            .dangerous()
+            .set_certificate_verifier(Arc::new(SkipServerVerification));
+        crypto.set_single_client_cert(vec![certificate], private_key)?;
+        crypto.set_protocols(&[ALPN_TPU_PROTOCOL_ID.to_vec()]);
+        let mut client_config = ClientConfig::default();
+        client_config.crypto = Arc::new(crypto);
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .thread_name("sol-quic-client")
+            .enable_all()
+            .build()?;
+        let (endpoint, _) = {
+            let _guard = runtime.enter();
+            let mut builder = Endpoint::builder();
+            builder.default_client_config(client_config);
+            builder.bind(&SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0))?
+        };
+        Ok(Self {
+            runtime,
+            connections: Arc::new(ConnectionCache {
+                endpoint,
+                connections: Mutex::new(HashMap::new()),
+                failed_endpoints: Mutex::new(HashMap::new()),
+            }),
+            pending_batches: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    pub fn send_wire_transaction(
+        &self,
+        tpu_quic_addr: &SocketAddr,
+        wire_transaction: &[u8],
+    ) -> Result<(), QuicClientError> {
+        self.send_wire_transaction_batch(tpu_quic_addr, &[wire_transaction])
+    }
+
+    /// Send the transactions on concurrent streams of a single connection
+    pub fn send_wire_transaction_batch<T: AsRef<[u8]>>(
+        &self,
+        tpu_quic_addr: &SocketAddr,
+        wire_transactions: &[T],
+    ) -> Result<(), QuicClientError> {
+        self.runtime.block_on(
+            self.connections
+                .send_batch(tpu_quic_addr, wire_transactions),
+        )
+    }
+
+    /// Start sending the transactions in the background, like `send_wire_transaction_batch`.
+    /// Returns an error without sending if `tpu_quic_addr` failed a send within
+    /// `FAILED_ENDPOINT_BACKOFF`, or if `MAX_PENDING_BATCHES` batches are still being sent.
+    pub fn try_send_wire_transaction_batch(
+        &self,
+        tpu_quic_addr: &SocketAddr,
+        wire_transactions: Vec<Vec<u8>>,
+    ) -> Result<(), QuicClientError> {
+        if self.connections.has_failed(tpu_quic_addr) {
+            return Err(QuicClientError::FailedEndpoint(*tpu_quic_addr));
+        }
+        if self.pending_batches.fetch_add(1, Ordering::Relaxed) >= MAX_PENDING_BATCHES {
+            self.pending_batches.fetch_sub(1, Ordering::Relaxed);
+            return Err(QuicClientError::TooManyPendingBatches);
+        }
+        let connections = self.connections.clone();
+        let pending_batches = self.pending_batches.clone();
+        let tpu_quic_addr = *tpu_quic_addr;
+        self.runtime.spawn(async move {
+            if let Err(err) = connections
+                .send_batch(&tpu_quic_addr, &wire_transactions)
+                .await
+            {
+                warn!(
+                    "Failed to send transactions to {}: {:?}",
+                    tpu_quic_addr, err
+                );
+            }
+            pending_batches.fetch_sub(1, Ordering::Relaxed);
+        });
+        Ok(())
+    }
+}
+
+/// The open connections of a client, and the endpoints which recently failed a send
+struct ConnectionCache {
+    endpoint: Endpoint,
+    connections: Mutex<HashMap<SocketAddr, Connection>>,
+    failed_endpoints: Mutex<HashMap<SocketAddr, Instant>>,
+}
+
+impl ConnectionCache {
+    async fn send_batch<T: AsRef<[u8]>>(
+        &self,
+        addr: &SocketAddr,
+        wire_transactions: &[T],
+    ) -> Result<(), QuicClientError> {
+        let send = async {
+            let connection = self.get_connection(addr).await?;
+            if Self::send_streams(&connection, wire_transactions)
+                .await
+                .is_ok()
+            {
+                return Ok(());
+            }
+            // The cached connection may have been closed by the server, retry on a new one
+            let connection = self.new_connection(addr).await?;
+            Self::send_streams(&connection, wire_transactions).await
+        };
+        let result = timeout(QUIC_SEND_TIMEOUT, send)
+            .await
+            .unwrap_or(Err(QuicClientError::Timeout(*addr)));
+        let mut failed_endpoints = self.failed_endpoints.lock().unwrap();
+        if result.is_ok() {
+            failed_endpoints.remove(addr);
+        } else {
+            failed_endpoints.insert(*addr, Instant::now());
+        }
+        result
+    }
+
+    fn has_failed(&self, addr: &SocketAddr) -> bool {
+        let mut failed_endpoints = self.failed_endpoints.lock().unwrap();
+        match failed_endpoints.get(addr) {
+            Some(failed) if failed.elapsed() < FAILED_ENDPOINT_BACKOFF => true,
+            Some(_) => {
+                failed_endpoints.remove(addr);
+                false
+            }
+            None => false,
+        }
+    }
+
+    async fn get_connection(&self, addr: &SocketAddr) -> Result<Connection, QuicClientError> {
+        let connection = self.connections.lock().unwrap().get(addr).cloned();
+        match connection {
+            Some(connection) => Ok(connection),
+            None => self.new_connection(addr).await,
+        }
+    }
+
+    async fn new_connection(&self, addr: &SocketAddr) -> Result<Connection, QuicClientError> {
+        let NewConnection { connection, .. } =
+            self.endpoint.connect(addr, QUIC_SERVER_NAME)?.await?;
+        self.connections
+            .lock()
+            .unwrap()
+            .insert(*addr, connection.clone());
+        Ok(connection)
+    }
+
+    async fn send_streams<T: AsRef<[u8]>>(
+        connection: &Connection,
+        data: &[T],
+    ) -> Result<(), QuicClientError> {
+        let results = join_all(data.iter().map(|data| async move {
+            let mut stream = connection.open_uni().await?;
+            stream.write_all(data.as_ref()).await?;
+            stream.finish().await?;
+            Ok::<_, QuicClientError>(())
+        }))
+        .await;
+        results.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::UdpSocket, thread::sleep};
+
+    #[test]
+    fn test_try_send_skips_failed_endpoint() {
+        let client = QuicClient::new(&Keypair::new()).unwrap();
+        // Nothing answers QUIC on this socket
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+        client
+            .try_send_wire_transaction_batch(&addr, vec![vec![0u8; 10]])
+            .unwrap();
+
+        let start = Instant::now();
+        while client.pending_batches.load(Ordering::Relaxed) > 0 {
+            assert!(start.elapsed() < QUIC_SEND_TIMEOUT * 5);
+            sleep(Duration::from_millis(10));
+        }
+        assert!(matches!(
+            client.try_send_wire_transaction_batch(&addr, vec![vec![0u8; 10]]),
+            Err(QuicClientError::FailedEndpoint(failed)) if failed == addr
+        ));
+
+        client
+            .connections
+            .failed_endpoints
+            .lock()
+            .unwrap()
+            .insert(addr, Instant::now() - FAILED_ENDPOINT_BACKOFF);
+        assert!(!client.connections.has_failed(&addr));
+    }
+}
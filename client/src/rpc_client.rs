@@ -1,100 +1,130 @@
+//! A client for the JSON RPC API.
+//!
+//! [`RpcClient`] blocks the calling thread on each request. It is a thin wrapper around
+//! [`crate::nonblocking::rpc_client::RpcClient`], driven by a private tokio runtime.
+
 use crate::{
-    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
-    http_sender::HttpSender,
-    mock_sender::{MockSender, Mocks},
-    rpc_config::RpcAccountInfoConfig,
+    client_error::Result as ClientResult,
+    mock_sender::Mocks,
+    nonblocking,
     rpc_config::{
-        RpcGetConfirmedSignaturesForAddress2Config, RpcLargestAccountsConfig,
-        RpcProgramAccountsConfig, RpcSendTransactionConfig, RpcSimulateTransactionConfig,
-        RpcTokenAccountsFilter,
+        RpcBlockProductionConfig, RpcLargestAccountsConfig, RpcProgramAccountsConfig,
+        RpcSendTransactionConfig, RpcSimulateTransactionConfig,
     },
-    rpc_request::{RpcError, RpcRequest, RpcResponseErrorData, TokenAccountsFilter},
+    rpc_request::{RpcRequest, TokenAccountsFilter},
     rpc_response::*,
     rpc_sender::RpcSender,
 };
-use bincode::serialize;
-use indicatif::{ProgressBar, ProgressStyle};
-use log::*;
-use serde_json::{json, Value};
-use solana_account_decoder::{
-    parse_token::{TokenAccountType, UiTokenAccount, UiTokenAmount},
-    UiAccount, UiAccountData, UiAccountEncoding,
-};
+use serde_json::Value;
+use solana_account_decoder::parse_token::{UiTokenAccount, UiTokenAmount};
 use solana_sdk::{
     account::Account,
-    clock::{Slot, UnixTimestamp, DEFAULT_MS_PER_SLOT, MAX_HASH_AGE_IN_SECONDS},
-    commitment_config::{CommitmentConfig, CommitmentLevel},
+    clock::{Slot, UnixTimestamp},
+    commitment_config::CommitmentConfig,
     epoch_info::EpochInfo,
     epoch_schedule::EpochSchedule,
     fee_calculator::{FeeCalculator, FeeRateGovernor},
     hash::Hash,
     pubkey::Pubkey,
     signature::Signature,
-    transaction::{self, uses_durable_nonce, Transaction},
+    transaction::{self, Transaction, VersionedTransaction},
 };
 use solana_transaction_status::{
-    EncodedConfirmedBlock, EncodedConfirmedTransaction, TransactionStatus, UiTransactionEncoding,
-};
-use solana_vote_program::vote_state::MAX_LOCKOUT_HISTORY;
-use std::{
-    cmp::min,
-    net::SocketAddr,
-    sync::RwLock,
-    thread::sleep,
-    time::{Duration, Instant},
+    EncodedConfirmedBlock, EncodedConfirmedTransaction, TransactionStatus, TransactionStatusFilter,
+    UiTransactionEncoding,
 };
+use std::{future::Future, net::SocketAddr, sync::Arc, time::Duration};
+
+/// A transaction that can be sent to or simulated by the cluster
+pub trait SerializableTransaction: serde::Serialize {
+    fn get_signature(&self) -> &Signature;
+}
+
+impl SerializableTransaction for Transaction {
+    fn get_signature(&self) -> &Signature {
+        &self.signatures[0]
+    }
+}
+
+impl SerializableTransaction for VersionedTransaction {
+    fn get_signature(&self) -> &Signature {
+        &self.signatures[0]
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RpcClientConfig {
+    pub commitment_config: CommitmentConfig,
+}
+
+impl RpcClientConfig {
+    pub fn with_commitment(commitment_config: CommitmentConfig) -> Self {
+        Self { commitment_config }
+    }
+}
 
 pub struct RpcClient {
-    sender: Box<dyn RpcSender + Send + Sync + 'static>,
-    commitment_config: CommitmentConfig,
-    node_version: RwLock<Option<semver::Version>>,
+    rpc_client: Arc<nonblocking::rpc_client::RpcClient>,
+    runtime: Option<tokio::runtime::Runtime>,
 }
 
-fn serialize_encode_transaction(
-    transaction: &Transaction,
-    encoding: UiTransactionEncoding,
-) -> ClientResult<String> {
-    let serialized = serialize(transaction)
-        .map_err(|e| ClientErrorKind::Custom(format!("transaction serialization failed: {}", e)))?;
-    let encoded = match encoding {
-        UiTransactionEncoding::Base58 => bs58::encode(serialized).into_string(),
-        UiTransactionEncoding::Base64 => base64::encode(serialized),
-        _ => {
-            return Err(ClientErrorKind::Custom(format!(
-                "unsupported transaction encoding: {}. Supported encodings: base58, base64",
-                encoding
-            ))
-            .into())
-        }
-    };
-    Ok(encoded)
+impl Drop for RpcClient {
+    fn drop(&mut self) {
+        // Dropping a runtime blocks, which panics if the client is dropped from async code
+        self.runtime.take().expect("runtime").shutdown_background();
+    }
 }
 
 impl RpcClient {
-    fn new_sender<T: RpcSender + Send + Sync + 'static>(
+    pub fn new_sender<T: RpcSender + Send + Sync + 'static>(
         sender: T,
-        commitment_config: CommitmentConfig,
+        config: RpcClientConfig,
     ) -> Self {
+        Self::new_with_nonblocking(nonblocking::rpc_client::RpcClient::new_sender(
+            sender, config,
+        ))
+    }
+
+    fn new_with_nonblocking(rpc_client: nonblocking::rpc_client::RpcClient) -> Self {
         Self {
-            sender: Box::new(sender),
-            node_version: RwLock::new(None),
-            commitment_config,
+            rpc_client: Arc::new(rpc_client),
+            runtime: Some(
+                tokio::runtime::Builder::new_current_thread()
+                    .thread_name("rpc-client")
+                    .enable_io()
+                    .enable_time()
+                    .build()
+                    .unwrap(),
+            ),
         }
     }
 
+    /// The nonblocking client that this client wraps
+    pub fn get_inner_client(&self) -> &Arc<nonblocking::rpc_client::RpcClient> {
+        &self.rpc_client
+    }
+
+    fn invoke<T, F: Future<Output = T>>(&self, f: F) -> T {
+        // `block_on()` panics if called from within an async context, whereas `block_in_place()`
+        // only panics if called from a current_thread runtime
+        tokio::task::block_in_place(move || self.runtime.as_ref().expect("runtime").block_on(f))
+    }
+
     pub fn new(url: String) -> Self {
-        Self::new_with_commitment(url, CommitmentConfig::default())
+        Self::new_with_nonblocking(nonblocking::rpc_client::RpcClient::new(url))
     }
 
     pub fn new_with_commitment(url: String, commitment_config: CommitmentConfig) -> Self {
-        Self::new_sender(HttpSender::new(url), commitment_config)
+        Self::new_with_nonblocking(nonblocking::rpc_client::RpcClient::new_with_commitment(
+            url,
+            commitment_config,
+        ))
     }
 
     pub fn new_with_timeout(url: String, timeout: Duration) -> Self {
-        Self::new_sender(
-            HttpSender::new_with_timeout(url, timeout),
-            CommitmentConfig::default(),
-        )
+        Self::new_with_nonblocking(nonblocking::rpc_client::RpcClient::new_with_timeout(
+            url, timeout,
+        ))
     }
 
     pub fn new_with_timeout_and_commitment(
@@ -102,85 +132,50 @@ impl RpcClient {
         timeout: Duration,
         commitment_config: CommitmentConfig,
     ) -> Self {
-        Self::new_sender(
-            HttpSender::new_with_timeout(url, timeout),
-            commitment_config,
+        Self::new_with_nonblocking(
+            nonblocking::rpc_client::RpcClient::new_with_timeout_and_commitment(
+                url,
+                timeout,
+                commitment_config,
+            ),
         )
     }
 
     pub fn new_mock(url: String) -> Self {
-        Self::new_sender(MockSender::new(url), CommitmentConfig::default())
+        Self::new_with_nonblocking(nonblocking::rpc_client::RpcClient::new_mock(url))
     }
 
     pub fn new_mock_with_mocks(url: String, mocks: Mocks) -> Self {
-        Self::new_sender(
-            MockSender::new_with_mocks(url, mocks),
-            CommitmentConfig::default(),
-        )
+        Self::new_with_nonblocking(nonblocking::rpc_client::RpcClient::new_mock_with_mocks(
+            url, mocks,
+        ))
     }
 
     pub fn new_socket(addr: SocketAddr) -> Self {
-        Self::new(get_rpc_request_str(addr, false))
+        Self::new_with_nonblocking(nonblocking::rpc_client::RpcClient::new_socket(addr))
     }
 
     pub fn new_socket_with_commitment(
         addr: SocketAddr,
         commitment_config: CommitmentConfig,
     ) -> Self {
-        Self::new_with_commitment(get_rpc_request_str(addr, false), commitment_config)
+        Self::new_with_nonblocking(
+            nonblocking::rpc_client::RpcClient::new_socket_with_commitment(addr, commitment_config),
+        )
     }
 
     pub fn new_socket_with_timeout(addr: SocketAddr, timeout: Duration) -> Self {
-        let url = get_rpc_request_str(addr, false);
-        Self::new_with_timeout(url, timeout)
-    }
-
-    fn get_node_version(&self) -> Result<semver::Version, RpcError> {
-        let r_node_version = self.node_version.read().unwrap();
-        if let Some(version) = &*r_node_version {
-            Ok(version.clone())
-        } else {
-            drop(r_node_version);
-            let mut w_node_version = self.node_version.write().unwrap();
-            let node_version = self.get_version().map_err(|e| {
-                RpcError::RpcRequestError(format!("cluster version query failed: {}", e))
-            })?;
-            let node_version = semver::Version::parse(&node_version.solana_core).map_err(|e| {
-                RpcError::RpcRequestError(format!("failed to parse cluster version: {}", e))
-            })?;
-            *w_node_version = Some(node_version.clone());
-            Ok(node_version)
-        }
+        Self::new_with_nonblocking(nonblocking::rpc_client::RpcClient::new_socket_with_timeout(
+            addr, timeout,
+        ))
     }
 
     pub fn commitment(&self) -> CommitmentConfig {
-        self.commitment_config
-    }
-
-    fn use_deprecated_commitment(&self) -> Result<bool, RpcError> {
-        Ok(self.get_node_version()? < semver::Version::new(1, 5, 5))
-    }
-
-    fn maybe_map_commitment(
-        &self,
-        requested_commitment: CommitmentConfig,
-    ) -> Result<CommitmentConfig, RpcError> {
-        if matches!(
-            requested_commitment.commitment,
-            CommitmentLevel::Finalized | CommitmentLevel::Confirmed | CommitmentLevel::Processed
-        ) && self.use_deprecated_commitment()?
-        {
-            return Ok(CommitmentConfig::use_deprecated_commitment(
-                requested_commitment,
-            ));
-        }
-        Ok(requested_commitment)
+        self.rpc_client.commitment()
     }
 
     pub fn confirm_transaction(&self, signature: &Signature) -> ClientResult<bool> {
-        Ok(self
-            .confirm_transaction_with_commitment(signature, self.commitment_config)?
-            .value)
+        self.invoke(self.rpc_client.confirm_transaction(signature))
     }
 
     pub fn confirm_transaction_with_commitment(
@@ -188,172 +183,73 @@ impl RpcClient {
         signature: &Signature,
         commitment_config: CommitmentConfig,
     ) -> RpcResult<bool> {
-        let Response { context, value } = self.get_signature_statuses(&[*signature])?;
-
-        Ok(Response {
-            context,
-            value: value[0]
-                .as_ref()
-                .filter(|result| result.satisfies_commitment(commitment_config))
-                .map(|result| result.status.is_ok())
-                .unwrap_or_default(),
-        })
-    }
-
-    pub fn send_transaction(&self, transaction: &Transaction) -> ClientResult<Signature> {
-        self.send_transaction_with_config(
-            transaction,
-            RpcSendTransactionConfig {
-                preflight_commitment: Some(
-                    self.maybe_map_commitment(self.commitment_config)?
-                        .commitment,
-                ),
-                ..RpcSendTransactionConfig::default()
-            },
+        self.invoke(
+            self.rpc_client
+                .confirm_transaction_with_commitment(signature, commitment_config),
         )
     }
 
-    fn default_cluster_transaction_encoding(&self) -> Result<UiTransactionEncoding, RpcError> {
-        if self.get_node_version()? < semver::Version::new(1, 3, 16) {
-            Ok(UiTransactionEncoding::Base58)
-        } else {
-            Ok(UiTransactionEncoding::Base64)
-        }
+    pub fn send_transaction(
+        &self,
+        transaction: &impl SerializableTransaction,
+    ) -> ClientResult<Signature> {
+        self.invoke(self.rpc_client.send_transaction(transaction))
     }
 
     pub fn send_transaction_with_config(
         &self,
-        transaction: &Transaction,
+        transaction: &impl SerializableTransaction,
         config: RpcSendTransactionConfig,
     ) -> ClientResult<Signature> {
-        let encoding = if let Some(encoding) = config.encoding {
-            encoding
-        } else {
-            self.default_cluster_transaction_encoding()?
-        };
-        let preflight_commitment = CommitmentConfig {
-            commitment: config.preflight_commitment.unwrap_or_default(),
-        };
-        let preflight_commitment = self.maybe_map_commitment(preflight_commitment)?;
-        let config = RpcSendTransactionConfig {
-            encoding: Some(encoding),
-            preflight_commitment: Some(preflight_commitment.commitment),
-            ..config
-        };
-        let serialized_encoded = serialize_encode_transaction(transaction, encoding)?;
-        let signature_base58_str: String = match self.send(
-            RpcRequest::SendTransaction,
-            json!([serialized_encoded, config]),
-        ) {
-            Ok(signature_base58_str) => signature_base58_str,
-            Err(err) => {
-                if let ClientErrorKind::RpcError(RpcError::RpcResponseError {
-                    code,
-                    message,
-                    data,
-                }) = &err.kind
-                {
-                    debug!("{} {}", code, message);
-                    if let RpcResponseErrorData::SendTransactionPreflightFailure(
-                        RpcSimulateTransactionResult {
-                            logs: Some(logs), ..
-                        },
-                    ) = data
-                    {
-                        for (i, log) in logs.iter().enumerate() {
-                            debug!("{:>3}: {}", i + 1, log);
-                        }
-                        debug!("");
-                    }
-                }
-                return Err(err);
-            }
-        };
-
-        let signature = signature_base58_str
-            .parse::<Signature>()
-            .map_err(|err| Into::<ClientError>::into(RpcError::ParseError(err.to_string())))?;
-        // A mismatching RPC response signature indicates an issue with the RPC node, and
-        // should not be passed along to confirmation methods. The transaction may or may
-        // not have been submitted to the cluster, so callers should verify the success of
-        // the correct transaction signature independently.
-        if signature != transaction.signatures[0] {
-            Err(RpcError::RpcRequestError(format!(
-                "RPC node returned mismatched signature {:?}, expected {:?}",
-                signature, transaction.signatures[0]
-            ))
-            .into())
-        } else {
-            Ok(transaction.signatures[0])
-        }
+        self.invoke(
+            self.rpc_client
+                .send_transaction_with_config(transaction, config),
+        )
     }
 
     pub fn simulate_transaction(
         &self,
-        transaction: &Transaction,
+        transaction: &impl SerializableTransaction,
     ) -> RpcResult<RpcSimulateTransactionResult> {
-        self.simulate_transaction_with_config(
-            transaction,
-            RpcSimulateTransactionConfig {
-                commitment: Some(self.commitment_config),
-                ..RpcSimulateTransactionConfig::default()
-            },
-        )
+        self.invoke(self.rpc_client.simulate_transaction(transaction))
     }
 
     pub fn simulate_transaction_with_config(
         &self,
-        transaction: &Transaction,
+        transaction: &impl SerializableTransaction,
         config: RpcSimulateTransactionConfig,
     ) -> RpcResult<RpcSimulateTransactionResult> {
-        let encoding = if let Some(encoding) = config.encoding {
-            encoding
-        } else {
-            self.default_cluster_transaction_encoding()?
-        };
-        let commitment = config.commitment.unwrap_or_default();
-        let commitment = self.maybe_map_commitment(commitment)?;
-        let config = RpcSimulateTransactionConfig {
-            encoding: Some(encoding),
-            commitment: Some(commitment),
-            ..config
-        };
-        let serialized_encoded = serialize_encode_transaction(transaction, encoding)?;
-        self.send(
-            RpcRequest::SimulateTransaction,
-            json!([serialized_encoded, config]),
+        self.invoke(
+            self.rpc_client
+                .simulate_transaction_with_config(transaction, config),
         )
     }
 
     pub fn get_snapshot_slot(&self) -> ClientResult<Slot> {
-        self.send(RpcRequest::GetSnapshotSlot, Value::Null)
+        self.invoke(self.rpc_client.get_snapshot_slot())
     }
 
     pub fn get_signature_status(
         &self,
         signature: &Signature,
     ) -> ClientResult<Option<transaction::Result<()>>> {
-        self.get_signature_status_with_commitment(signature, self.commitment_config)
+        self.invoke(self.rpc_client.get_signature_status(signature))
     }
 
     pub fn get_signature_statuses(
         &self,
         signatures: &[Signature],
     ) -> RpcResult<Vec<Option<TransactionStatus>>> {
-        let signatures: Vec<_> = signatures.iter().map(|s| s.to_string()).collect();
-        self.send(RpcRequest::GetSignatureStatuses, json!([signatures]))
+        self.invoke(self.rpc_client.get_signature_statuses(signatures))
     }
 
     pub fn get_signature_statuses_with_history(
         &self,
         signatures: &[Signature],
     ) -> RpcResult<Vec<Option<TransactionStatus>>> {
-        let signatures: Vec<_> = signatures.iter().map(|s| s.to_string()).collect();
-        self.send(
-            RpcRequest::GetSignatureStatuses,
-            json!([signatures, {
-                "searchTransactionHistory": true
-            }]),
+        self.invoke(
+            self.rpc_client
+                .get_signature_statuses_with_history(signatures),
         )
     }
 
@@ -362,14 +258,10 @@ impl RpcClient {
         signature: &Signature,
         commitment_config: CommitmentConfig,
     ) -> ClientResult<Option<transaction::Result<()>>> {
-        let result: Response<Vec<Option<TransactionStatus>>> = self.send(
-            RpcRequest::GetSignatureStatuses,
-            json!([[signature.to_string()]]),
-        )?;
-        Ok(result.value[0]
-            .clone()
-            .filter(|result| result.satisfies_commitment(commitment_config))
-            .map(|status_meta| status_meta.status))
+        self.invoke(
+            self.rpc_client
+                .get_signature_status_with_commitment(signature, commitment_config),
+        )
     }
 
     pub fn get_signature_status_with_commitment_and_history(
@@ -378,57 +270,49 @@ impl RpcClient {
         commitment_config: CommitmentConfig,
         search_transaction_history: bool,
     ) -> ClientResult<Option<transaction::Result<()>>> {
-        let result: Response<Vec<Option<TransactionStatus>>> = self.send(
-            RpcRequest::GetSignatureStatuses,
-            json!([[signature.to_string()], {
-                "searchTransactionHistory": search_transaction_history
-            }]),
-        )?;
-        Ok(result.value[0]
-            .clone()
-            .filter(|result| result.satisfies_commitment(commitment_config))
-            .map(|status_meta| status_meta.status))
+        self.invoke(
+            self.rpc_client
+                .get_signature_status_with_commitment_and_history(
+                    signature,
+                    commitment_config,
+                    search_transaction_history,
+                ),
+        )
     }
 
     pub fn get_slot(&self) -> ClientResult<Slot> {
-        self.get_slot_with_commitment(self.commitment_config)
+        self.invoke(self.rpc_client.get_slot())
     }
 
     pub fn get_slot_with_commitment(
         &self,
         commitment_config: CommitmentConfig,
     ) -> ClientResult<Slot> {
-        self.send(
-            RpcRequest::GetSlot,
-            json!([self.maybe_map_commitment(commitment_config)?]),
-        )
+        self.invoke(self.rpc_client.get_slot_with_commitment(commitment_config))
     }
 
     pub fn supply(&self) -> RpcResult<RpcSupply> {
-        self.supply_with_commitment(self.commitment_config)
+        self.invoke(self.rpc_client.supply())
     }
 
     pub fn supply_with_commitment(
         &self,
         commitment_config: CommitmentConfig,
     ) -> RpcResult<RpcSupply> {
-        self.send(
-            RpcRequest::GetSupply,
-            json!([self.maybe_map_commitment(commitment_config)?]),
-        )
+        self.invoke(self.rpc_client.supply_with_commitment(commitment_config))
     }
 
     pub fn total_supply(&self) -> ClientResult<u64> {
-        self.total_supply_with_commitment(self.commitment_config)
+        self.invoke(self.rpc_client.total_supply())
     }
 
     pub fn total_supply_with_commitment(
         &self,
         commitment_config: CommitmentConfig,
     ) -> ClientResult<u64> {
-        self.send(
-            RpcRequest::GetTotalSupply,
-            json!([self.maybe_map_commitment(commitment_config)?]),
+        self.invoke(
+            self.rpc_client
+                .total_supply_with_commitment(commitment_config),
         )
     }
 
@@ -436,26 +320,20 @@ impl RpcClient {
         &self,
         config: RpcLargestAccountsConfig,
     ) -> RpcResult<Vec<RpcAccountBalance>> {
-        let commitment = config.commitment.unwrap_or_default();
-        let commitment = self.maybe_map_commitment(commitment)?;
-        let config = RpcLargestAccountsConfig {
-            commitment: Some(commitment),
-            ..config
-        };
-        self.send(RpcRequest::GetLargestAccounts, json!([config]))
+        self.invoke(self.rpc_client.get_largest_accounts_with_config(config))
     }
 
     pub fn get_vote_accounts(&self) -> ClientResult<RpcVoteAccountStatus> {
-        self.get_vote_accounts_with_commitment(self.commitment_config)
+        self.invoke(self.rpc_client.get_vote_accounts())
     }
 
     pub fn get_vote_accounts_with_commitment(
         &self,
         commitment_config: CommitmentConfig,
     ) -> ClientResult<RpcVoteAccountStatus> {
-        self.send(
-            RpcRequest::GetVoteAccounts,
-            json!([self.maybe_map_commitment(commitment_config)?]),
+        self.invoke(
+            self.rpc_client
+                .get_vote_accounts_with_commitment(commitment_config),
         )
     }
 
@@ -464,39 +342,18 @@ impl RpcClient {
         commitment: CommitmentConfig,
         max_stake_percent: f32,
     ) -> ClientResult<()> {
-        let mut current_percent;
-        loop {
-            let vote_accounts = self.get_vote_accounts_with_commitment(commitment)?;
-
-            let mut max = 0;
-            let total_active_stake = vote_accounts
-                .current
-                .iter()
-                .chain(vote_accounts.delinquent.iter())
-                .map(|vote_account| {
-                    max = std::cmp::max(max, vote_account.activated_stake);
-                    vote_account.activated_stake
-                })
-                .sum::<u64>();
-            current_percent = 100f32 * max as f32 / total_active_stake as f32;
-            if current_percent < max_stake_percent {
-                break;
-            }
-            info!(
-                "Waiting for stake to drop below {} current: {:.1}",
-                max_stake_percent, current_percent
-            );
-            sleep(Duration::from_secs(10));
-        }
-        Ok(())
+        self.invoke(
+            self.rpc_client
+                .wait_for_max_stake(commitment, max_stake_percent),
+        )
     }
 
     pub fn get_cluster_nodes(&self) -> ClientResult<Vec<RpcContactInfo>> {
-        self.send(RpcRequest::GetClusterNodes, Value::Null)
+        self.invoke(self.rpc_client.get_cluster_nodes())
     }
 
     pub fn get_confirmed_block(&self, slot: Slot) -> ClientResult<EncodedConfirmedBlock> {
-        self.get_confirmed_block_with_encoding(slot, UiTransactionEncoding::Json)
+        self.invoke(self.rpc_client.get_confirmed_block(slot))
     }
 
     pub fn get_confirmed_block_with_encoding(
@@ -504,7 +361,10 @@ impl RpcClient {
         slot: Slot,
         encoding: UiTransactionEncoding,
     ) -> ClientResult<EncodedConfirmedBlock> {
-        self.send(RpcRequest::GetConfirmedBlock, json!([slot, encoding]))
+        self.invoke(
+            self.rpc_client
+                .get_confirmed_block_with_encoding(slot, encoding),
+        )
     }
 
     pub fn get_confirmed_blocks(
@@ -512,10 +372,7 @@ impl RpcClient {
         start_slot: Slot,
         end_slot: Option<Slot>,
     ) -> ClientResult<Vec<Slot>> {
-        self.send(
-            RpcRequest::GetConfirmedBlocks,
-            json!([start_slot, end_slot]),
-        )
+        self.invoke(self.rpc_client.get_confirmed_blocks(start_slot, end_slot))
     }
 
     pub fn get_confirmed_blocks_with_limit(
@@ -523,9 +380,9 @@ impl RpcClient {
         start_slot: Slot,
         limit: usize,
     ) -> ClientResult<Vec<Slot>> {
-        self.send(
-            RpcRequest::GetConfirmedBlocksWithLimit,
-            json!([start_slot, limit]),
+        self.invoke(
+            self.rpc_client
+                .get_confirmed_blocks_with_limit(start_slot, limit),
         )
     }
 
@@ -535,29 +392,19 @@ impl RpcClient {
         start_slot: Slot,
         end_slot: Slot,
     ) -> ClientResult<Vec<Signature>> {
-        let signatures_base58_str: Vec<String> = self.send(
-            RpcRequest::GetConfirmedSignaturesForAddress,
-            json!([address.to_string(), start_slot, end_slot]),
-        )?;
-
-        let mut signatures = vec![];
-        for signature_base58_str in signatures_base58_str {
-            signatures.push(
-                signature_base58_str.parse::<Signature>().map_err(|err| {
-                    Into::<ClientError>::into(RpcError::ParseError(err.to_string()))
-                })?,
-            );
-        }
-        Ok(signatures)
+        self.invoke(
+            self.rpc_client
+                .get_confirmed_signatures_for_address(address, start_slot, end_slot),
+        )
     }
 
     pub fn get_confirmed_signatures_for_address2(
         &self,
         address: &Pubkey,
     ) -> ClientResult<Vec<RpcConfirmedTransactionStatusWithSignature>> {
-        self.get_confirmed_signatures_for_address2_with_config(
-            address,
-            GetConfirmedSignaturesForAddress2Config::default(),
+        self.invoke(
+            self.rpc_client
+                .get_confirmed_signatures_for_address2(address),
         )
     }
 
@@ -566,18 +413,10 @@ impl RpcClient {
         address: &Pubkey,
         config: GetConfirmedSignaturesForAddress2Config,
     ) -> ClientResult<Vec<RpcConfirmedTransactionStatusWithSignature>> {
-        let config = RpcGetConfirmedSignaturesForAddress2Config {
-            before: config.before.map(|signature| signature.to_string()),
-            until: config.until.map(|signature| signature.to_string()),
-            limit: config.limit,
-        };
-
-        let result: Vec<RpcConfirmedTransactionStatusWithSignature> = self.send(
-            RpcRequest::GetConfirmedSignaturesForAddress2,
-            json!([address.to_string(), config]),
-        )?;
-
-        Ok(result)
+        self.invoke(
+            self.rpc_client
+                .get_confirmed_signatures_for_address2_with_config(address, config),
+        )
     }
 
     pub fn get_confirmed_transaction(
@@ -585,40 +424,27 @@ impl RpcClient {
         signature: &Signature,
         encoding: UiTransactionEncoding,
     ) -> ClientResult<EncodedConfirmedTransaction> {
-        self.send(
-            RpcRequest::GetConfirmedTransaction,
-            json!([signature.to_string(), encoding]),
+        self.invoke(
+            self.rpc_client
+                .get_confirmed_transaction(signature, encoding),
         )
     }
 
     pub fn get_block_time(&self, slot: Slot) -> ClientResult<UnixTimestamp> {
-        let request = RpcRequest::GetBlockTime;
-        let response = self.sender.send(request, json!([slot]));
-
-        response
-            .map(|result_json| {
-                if result_json.is_null() {
-                    return Err(RpcError::ForUser(format!("Block Not Found: slot={}", slot)).into());
-                }
-                let result = serde_json::from_value(result_json)
-                    .map_err(|err| ClientError::new_with_request(err.into(), request))?;
-                trace!("Response block timestamp {:?} {:?}", slot, result);
-                Ok(result)
-            })
-            .map_err(|err| err.into_with_request(request))?
+        self.invoke(self.rpc_client.get_block_time(slot))
     }
 
     pub fn get_epoch_info(&self) -> ClientResult<EpochInfo> {
-        self.get_epoch_info_with_commitment(self.commitment_config)
+        self.invoke(self.rpc_client.get_epoch_info())
     }
 
     pub fn get_epoch_info_with_commitment(
         &self,
         commitment_config: CommitmentConfig,
     ) -> ClientResult<EpochInfo> {
-        self.send(
-            RpcRequest::GetEpochInfo,
-            json!([self.maybe_map_commitment(commitment_config)?]),
+        self.invoke(
+            self.rpc_client
+                .get_epoch_info_with_commitment(commitment_config),
         )
     }
 
@@ -626,7 +452,7 @@ impl RpcClient {
         &self,
         slot: Option<Slot>,
     ) -> ClientResult<Option<RpcLeaderSchedule>> {
-        self.get_leader_schedule_with_commitment(slot, self.commitment_config)
+        self.invoke(self.rpc_client.get_leader_schedule(slot))
     }
 
     pub fn get_leader_schedule_with_commitment(
@@ -634,98 +460,59 @@ impl RpcClient {
         slot: Option<Slot>,
         commitment_config: CommitmentConfig,
     ) -> ClientResult<Option<RpcLeaderSchedule>> {
-        self.send(
-            RpcRequest::GetLeaderSchedule,
-            json!([slot, self.maybe_map_commitment(commitment_config)?]),
+        self.invoke(
+            self.rpc_client
+                .get_leader_schedule_with_commitment(slot, commitment_config),
         )
     }
 
+    /// Get block production for the current epoch
+    pub fn get_block_production(&self) -> RpcResult<RpcBlockProduction> {
+        self.invoke(self.rpc_client.get_block_production())
+    }
+
+    pub fn get_block_production_with_config(
+        &self,
+        config: RpcBlockProductionConfig,
+    ) -> RpcResult<RpcBlockProduction> {
+        self.invoke(self.rpc_client.get_block_production_with_config(config))
+    }
+
     pub fn get_epoch_schedule(&self) -> ClientResult<EpochSchedule> {
-        self.send(RpcRequest::GetEpochSchedule, Value::Null)
+        self.invoke(self.rpc_client.get_epoch_schedule())
     }
 
     pub fn get_identity(&self) -> ClientResult<Pubkey> {
-        let rpc_identity: RpcIdentity = self.send(RpcRequest::GetIdentity, Value::Null)?;
-
-        rpc_identity.identity.parse::<Pubkey>().map_err(|_| {
-            ClientError::new_with_request(
-                RpcError::ParseError("Pubkey".to_string()).into(),
-                RpcRequest::GetIdentity,
-            )
-        })
+        self.invoke(self.rpc_client.get_identity())
     }
 
     pub fn get_inflation_governor(&self) -> ClientResult<RpcInflationGovernor> {
-        self.send(RpcRequest::GetInflationGovernor, Value::Null)
+        self.invoke(self.rpc_client.get_inflation_governor())
     }
 
     pub fn get_inflation_rate(&self) -> ClientResult<RpcInflationRate> {
-        self.send(RpcRequest::GetInflationRate, Value::Null)
+        self.invoke(self.rpc_client.get_inflation_rate())
     }
 
     pub fn get_version(&self) -> ClientResult<RpcVersionInfo> {
-        self.send(RpcRequest::GetVersion, Value::Null)
+        self.invoke(self.rpc_client.get_version())
     }
 
     pub fn minimum_ledger_slot(&self) -> ClientResult<Slot> {
-        self.send(RpcRequest::MinimumLedgerSlot, Value::Null)
+        self.invoke(self.rpc_client.minimum_ledger_slot())
     }
 
     pub fn send_and_confirm_transaction(
         &self,
         transaction: &Transaction,
     ) -> ClientResult<Signature> {
-        let signature = self.send_transaction(transaction)?;
-        let recent_blockhash = if uses_durable_nonce(transaction).is_some() {
-            self.get_recent_blockhash_with_commitment(CommitmentConfig::processed())?
-                .value
-                .0
-        } else {
-            transaction.message.recent_blockhash
-        };
-        let status = loop {
-            let status = self.get_signature_status(&signature)?;
-            if status.is_none() {
-                if self
-                    .get_fee_calculator_for_blockhash_with_commitment(
-                        &recent_blockhash,
-                        CommitmentConfig::processed(),
-                    )?
-                    .value
-                    .is_none()
-                {
-                    break status;
-                }
-            } else {
-                break status;
-            }
-            if cfg!(not(test)) {
-                // Retry twice a second
-                sleep(Duration::from_millis(500));
-            }
-        };
-        if let Some(result) = status {
-            match result {
-                Ok(_) => Ok(signature),
-                Err(err) => Err(err.into()),
-            }
-        } else {
-            Err(RpcError::ForUser(
-                "unable to confirm transaction. \
-                                  This can happen in situations such as transaction expiration \
-                                  and insufficient fee-payer funds"
-                    .to_string(),
-            )
-            .into())
-        }
+        self.invoke(self.rpc_client.send_and_confirm_transaction(transaction))
     }
 
     /// Note that `get_account` returns `Err(..)` if the account does not exist whereas
     /// `get_account_with_commitment` returns `Ok(None)` if the account does not exist.
     pub fn get_account(&self, pubkey: &Pubkey) -> ClientResult<Account> {
-        self.get_account_with_commitment(pubkey, self.commitment_config)?
-            .value
-            .ok_or_else(|| RpcError::ForUser(format!("AccountNotFound: pubkey={}", pubkey)).into())
+        self.invoke(self.rpc_client.get_account(pubkey))
     }
 
     pub fn get_account_with_commitment(
@@ -733,46 +520,14 @@ impl RpcClient {
         pubkey: &Pubkey,
         commitment_config: CommitmentConfig,
     ) -> RpcResult<Option<Account>> {
-        let config = RpcAccountInfoConfig {
-            encoding: Some(UiAccountEncoding::Base64),
-            commitment: Some(self.maybe_map_commitment(commitment_config)?),
-            data_slice: None,
-        };
-        let response = self.sender.send(
-            RpcRequest::GetAccountInfo,
-            json!([pubkey.to_string(), config]),
-        );
-
-        response
-            .map(|result_json| {
-                if result_json.is_null() {
-                    return Err(
-                        RpcError::ForUser(format!("AccountNotFound: pubkey={}", pubkey)).into(),
-                    );
-                }
-                let Response {
-                    context,
-                    value: rpc_account,
-                } = serde_json::from_value::<Response<Option<UiAccount>>>(result_json)?;
-                trace!("Response account {:?} {:?}", pubkey, rpc_account);
-                let account = rpc_account.and_then(|rpc_account| rpc_account.decode());
-                Ok(Response {
-                    context,
-                    value: account,
-                })
-            })
-            .map_err(|err| {
-                Into::<ClientError>::into(RpcError::ForUser(format!(
-                    "AccountNotFound: pubkey={}: {}",
-                    pubkey, err
-                )))
-            })?
+        self.invoke(
+            self.rpc_client
+                .get_account_with_commitment(pubkey, commitment_config),
+        )
     }
 
     pub fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> ClientResult<Vec<Option<Account>>> {
-        Ok(self
-            .get_multiple_accounts_with_commitment(pubkeys, self.commitment_config)?
-            .value)
+        self.invoke(self.rpc_client.get_multiple_accounts(pubkeys))
     }
 
     pub fn get_multiple_accounts_with_commitment(
@@ -780,53 +535,26 @@ impl RpcClient {
         pubkeys: &[Pubkey],
         commitment_config: CommitmentConfig,
     ) -> RpcResult<Vec<Option<Account>>> {
-        let config = RpcAccountInfoConfig {
-            encoding: Some(UiAccountEncoding::Base64),
-            commitment: Some(self.maybe_map_commitment(commitment_config)?),
-            data_slice: None,
-        };
-        let pubkeys: Vec<_> = pubkeys.iter().map(|pubkey| pubkey.to_string()).collect();
-        let response = self.send(RpcRequest::GetMultipleAccounts, json!([pubkeys, config]))?;
-        let Response {
-            context,
-            value: accounts,
-        } = serde_json::from_value::<Response<Vec<Option<UiAccount>>>>(response)?;
-        let accounts: Vec<Option<Account>> = accounts
-            .into_iter()
-            .map(|rpc_account| rpc_account.map(|a| a.decode()).flatten())
-            .collect();
-        Ok(Response {
-            context,
-            value: accounts,
-        })
+        self.invoke(
+            self.rpc_client
+                .get_multiple_accounts_with_commitment(pubkeys, commitment_config),
+        )
     }
 
     pub fn get_account_data(&self, pubkey: &Pubkey) -> ClientResult<Vec<u8>> {
-        Ok(self.get_account(pubkey)?.data)
+        self.invoke(self.rpc_client.get_account_data(pubkey))
     }
 
     pub fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> ClientResult<u64> {
-        let request = RpcRequest::GetMinimumBalanceForRentExemption;
-        let minimum_balance_json = self
-            .sender
-            .send(request, json!([data_len]))
-            .map_err(|err| err.into_with_request(request))?;
-
-        let minimum_balance: u64 = serde_json::from_value(minimum_balance_json)
-            .map_err(|err| ClientError::new_with_request(err.into(), request))?;
-        trace!(
-            "Response minimum balance {:?} {:?}",
-            data_len,
-            minimum_balance
-        );
-        Ok(minimum_balance)
+        self.invoke(
+            self.rpc_client
+                .get_minimum_balance_for_rent_exemption(data_len),
+        )
     }
 
     /// Request the balance of the account `pubkey`.
     pub fn get_balance(&self, pubkey: &Pubkey) -> ClientResult<u64> {
-        Ok(self
-            .get_balance_with_commitment(pubkey, self.commitment_config)?
-            .value)
+        self.invoke(self.rpc_client.get_balance(pubkey))
     }
 
     pub fn get_balance_with_commitment(
@@ -834,27 +562,14 @@ impl RpcClient {
         pubkey: &Pubkey,
         commitment_config: CommitmentConfig,
     ) -> RpcResult<u64> {
-        self.send(
-            RpcRequest::GetBalance,
-            json!([
-                pubkey.to_string(),
-                self.maybe_map_commitment(commitment_config)?
-            ]),
+        self.invoke(
+            self.rpc_client
+                .get_balance_with_commitment(pubkey, commitment_config),
         )
     }
 
     pub fn get_program_accounts(&self, pubkey: &Pubkey) -> ClientResult<Vec<(Pubkey, Account)>> {
-        self.get_program_accounts_with_config(
-            pubkey,
-            RpcProgramAccountsConfig {
-                filters: None,
-                account_config: RpcAccountInfoConfig {
-                    encoding: Some(UiAccountEncoding::Base64),
-                    commitment: Some(self.commitment_config),
-                    ..RpcAccountInfoConfig::default()
-                },
-            },
-        )
+        self.invoke(self.rpc_client.get_program_accounts(pubkey))
     }
 
     pub fn get_program_accounts_with_config(
@@ -862,101 +577,46 @@ impl RpcClient {
         pubkey: &Pubkey,
         config: RpcProgramAccountsConfig,
     ) -> ClientResult<Vec<(Pubkey, Account)>> {
-        let commitment = config.account_config.commitment.unwrap_or_default();
-        let commitment = self.maybe_map_commitment(commitment)?;
-        let account_config = RpcAccountInfoConfig {
-            commitment: Some(commitment),
-            ..config.account_config
-        };
-        let config = RpcProgramAccountsConfig {
-            account_config,
-            ..config
-        };
-        let accounts: Vec<RpcKeyedAccount> = self.send(
-            RpcRequest::GetProgramAccounts,
-            json!([pubkey.to_string(), config]),
-        )?;
-        parse_keyed_accounts(accounts, RpcRequest::GetProgramAccounts)
+        self.invoke(
+            self.rpc_client
+                .get_program_accounts_with_config(pubkey, config),
+        )
     }
 
     /// Request the transaction count.
     pub fn get_transaction_count(&self) -> ClientResult<u64> {
-        self.get_transaction_count_with_commitment(self.commitment_config)
+        self.invoke(self.rpc_client.get_transaction_count())
     }
 
     pub fn get_transaction_count_with_commitment(
         &self,
         commitment_config: CommitmentConfig,
     ) -> ClientResult<u64> {
-        self.send(
-            RpcRequest::GetTransactionCount,
-            json!([self.maybe_map_commitment(commitment_config)?]),
+        self.invoke(
+            self.rpc_client
+                .get_transaction_count_with_commitment(commitment_config),
         )
     }
 
     pub fn get_recent_blockhash(&self) -> ClientResult<(Hash, FeeCalculator)> {
-        let (blockhash, fee_calculator, _last_valid_slot) = self
-            .get_recent_blockhash_with_commitment(self.commitment_config)?
-            .value;
-        Ok((blockhash, fee_calculator))
+        self.invoke(self.rpc_client.get_recent_blockhash())
     }
 
     pub fn get_recent_blockhash_with_commitment(
         &self,
         commitment_config: CommitmentConfig,
     ) -> RpcResult<(Hash, FeeCalculator, Slot)> {
-        let (context, blockhash, fee_calculator, last_valid_slot) = if let Ok(Response {
-            context,
-            value:
-                RpcFees {
-                    blockhash,
-                    fee_calculator,
-                    last_valid_slot,
-                },
-        }) = self
-            .send::<Response<RpcFees>>(
-                RpcRequest::GetFees,
-                json!([self.maybe_map_commitment(commitment_config)?]),
-            ) {
-            (context, blockhash, fee_calculator, last_valid_slot)
-        } else if let Ok(Response {
-            context,
-            value:
-                RpcBlockhashFeeCalculator {
-                    blockhash,
-                    fee_calculator,
-                },
-        }) = self.send::<Response<RpcBlockhashFeeCalculator>>(
-            RpcRequest::GetRecentBlockhash,
-            json!([self.maybe_map_commitment(commitment_config)?]),
-        ) {
-            (context, blockhash, fee_calculator, 0)
-        } else {
-            return Err(ClientError::new_with_request(
-                RpcError::ParseError("RpcBlockhashFeeCalculator or RpcFees".to_string()).into(),
-                RpcRequest::GetRecentBlockhash,
-            ));
-        };
-
-        let blockhash = blockhash.parse().map_err(|_| {
-            ClientError::new_with_request(
-                RpcError::ParseError("Hash".to_string()).into(),
-                RpcRequest::GetRecentBlockhash,
-            )
-        })?;
-        Ok(Response {
-            context,
-            value: (blockhash, fee_calculator, last_valid_slot),
-        })
+        self.invoke(
+            self.rpc_client
+                .get_recent_blockhash_with_commitment(commitment_config),
+        )
     }
 
     pub fn get_fee_calculator_for_blockhash(
         &self,
         blockhash: &Hash,
     ) -> ClientResult<Option<FeeCalculator>> {
-        Ok(self
-            .get_fee_calculator_for_blockhash_with_commitment(blockhash, self.commitment_config)?
-            .value)
+        self.invoke(self.rpc_client.get_fee_calculator_for_blockhash(blockhash))
     }
 
     pub fn get_fee_calculator_for_blockhash_with_commitment(
@@ -964,81 +624,34 @@ impl RpcClient {
         blockhash: &Hash,
         commitment_config: CommitmentConfig,
     ) -> RpcResult<Option<FeeCalculator>> {
-        let Response { context, value } = self.send::<Response<Option<RpcFeeCalculator>>>(
-            RpcRequest::GetFeeCalculatorForBlockhash,
-            json!([
-                blockhash.to_string(),
-                self.maybe_map_commitment(commitment_config)?
-            ]),
-        )?;
-
-        Ok(Response {
-            context,
-            value: value.map(|rf| rf.fee_calculator),
-        })
+        self.invoke(
+            self.rpc_client
+                .get_fee_calculator_for_blockhash_with_commitment(blockhash, commitment_config),
+        )
     }
 
     pub fn get_fee_rate_governor(&self) -> RpcResult<FeeRateGovernor> {
-        let Response {
-            context,
-            value: RpcFeeRateGovernor { fee_rate_governor },
-        } =
-            self.send::<Response<RpcFeeRateGovernor>>(RpcRequest::GetFeeRateGovernor, Value::Null)?;
-
-        Ok(Response {
-            context,
-            value: fee_rate_governor,
-        })
+        self.invoke(self.rpc_client.get_fee_rate_governor())
     }
 
     pub fn get_new_blockhash(&self, blockhash: &Hash) -> ClientResult<(Hash, FeeCalculator)> {
-        let mut num_retries = 0;
-        let start = Instant::now();
-        while start.elapsed().as_secs() < 5 {
-            if let Ok((new_blockhash, fee_calculator)) = self.get_recent_blockhash() {
-                if new_blockhash != *blockhash {
-                    return Ok((new_blockhash, fee_calculator));
-                }
-            }
-            debug!("Got same blockhash ({:?}), will retry...", blockhash);
-
-            // Retry ~twice during a slot
-            sleep(Duration::from_millis(DEFAULT_MS_PER_SLOT / 2));
-            num_retries += 1;
-        }
-        Err(RpcError::ForUser(format!(
-            "Unable to get new blockhash after {}ms (retried {} times), stuck at {}",
-            start.elapsed().as_millis(),
-            num_retries,
-            blockhash
-        ))
-        .into())
+        self.invoke(self.rpc_client.get_new_blockhash(blockhash))
     }
 
     pub fn get_first_available_block(&self) -> ClientResult<Slot> {
-        self.send(RpcRequest::GetFirstAvailableBlock, Value::Null)
+        self.invoke(self.rpc_client.get_first_available_block())
     }
 
     pub fn get_genesis_hash(&self) -> ClientResult<Hash> {
-        let hash_str: String = self.send(RpcRequest::GetGenesisHash, Value::Null)?;
-        let hash = hash_str.parse().map_err(|_| {
-            ClientError::new_with_request(
-                RpcError::ParseError("Hash".to_string()).into(),
-                RpcRequest::GetGenesisHash,
-            )
-        })?;
-        Ok(hash)
+        self.invoke(self.rpc_client.get_genesis_hash())
     }
 
     pub fn get_health(&self) -> ClientResult<()> {
-        self.send::<String>(RpcRequest::GetHealth, Value::Null)
-            .map(|_| ())
+        self.invoke(self.rpc_client.get_health())
     }
 
     pub fn get_token_account(&self, pubkey: &Pubkey) -> ClientResult<Option<UiTokenAccount>> {
-        Ok(self
-            .get_token_account_with_commitment(pubkey, self.commitment_config)?
-            .value)
+        self.invoke(self.rpc_client.get_token_account(pubkey))
     }
 
     pub fn get_token_account_with_commitment(
@@ -1046,60 +659,14 @@ impl RpcClient {
         pubkey: &Pubkey,
         commitment_config: CommitmentConfig,
     ) -> RpcResult<Option<UiTokenAccount>> {
-        let config = RpcAccountInfoConfig {
-            encoding: Some(UiAccountEncoding::JsonParsed),
-            commitment: Some(self.maybe_map_commitment(commitment_config)?),
-            data_slice: None,
-        };
-        let response = self.sender.send(
-            RpcRequest::GetAccountInfo,
-            json!([pubkey.to_string(), config]),
-        );
-
-        response
-            .map(|result_json| {
-                if result_json.is_null() {
-                    return Err(
-                        RpcError::ForUser(format!("AccountNotFound: pubkey={}", pubkey)).into(),
-                    );
-                }
-                let Response {
-                    context,
-                    value: rpc_account,
-                } = serde_json::from_value::<Response<Option<UiAccount>>>(result_json)?;
-                trace!("Response account {:?} {:?}", pubkey, rpc_account);
-                let response = {
-                    if let Some(rpc_account) = rpc_account {
-                        if let UiAccountData::Json(account_data) = rpc_account.data {
-                            let token_account_type: TokenAccountType =
-                                serde_json::from_value(account_data.parsed)?;
-                            if let TokenAccountType::Account(token_account) = token_account_type {
-                                return Ok(Response {
-                                    context,
-                                    value: Some(token_account),
-                                });
-                            }
-                        }
-                    }
-                    Err(Into::<ClientError>::into(RpcError::ForUser(format!(
-                        "Account could not be parsed as token account: pubkey={}",
-                        pubkey
-                    ))))
-                };
-                response?
-            })
-            .map_err(|err| {
-                Into::<ClientError>::into(RpcError::ForUser(format!(
-                    "AccountNotFound: pubkey={}: {}",
-                    pubkey, err
-                )))
-            })?
+        self.invoke(
+            self.rpc_client
+                .get_token_account_with_commitment(pubkey, commitment_config),
+        )
     }
 
     pub fn get_token_account_balance(&self, pubkey: &Pubkey) -> ClientResult<UiTokenAmount> {
-        Ok(self
-            .get_token_account_balance_with_commitment(pubkey, self.commitment_config)?
-            .value)
+        self.invoke(self.rpc_client.get_token_account_balance(pubkey))
     }
 
     pub fn get_token_account_balance_with_commitment(
@@ -1107,12 +674,9 @@ impl RpcClient {
         pubkey: &Pubkey,
         commitment_config: CommitmentConfig,
     ) -> RpcResult<UiTokenAmount> {
-        self.send(
-            RpcRequest::GetTokenAccountBalance,
-            json!([
-                pubkey.to_string(),
-                self.maybe_map_commitment(commitment_config)?
-            ]),
+        self.invoke(
+            self.rpc_client
+                .get_token_account_balance_with_commitment(pubkey, commitment_config),
         )
     }
 
@@ -1121,13 +685,10 @@ impl RpcClient {
         delegate: &Pubkey,
         token_account_filter: TokenAccountsFilter,
     ) -> ClientResult<Vec<RpcKeyedAccount>> {
-        Ok(self
-            .get_token_accounts_by_delegate_with_commitment(
-                delegate,
-                token_account_filter,
-                self.commitment_config,
-            )?
-            .value)
+        self.invoke(
+            self.rpc_client
+                .get_token_accounts_by_delegate(delegate, token_account_filter),
+        )
     }
 
     pub fn get_token_accounts_by_delegate_with_commitment(
@@ -1136,22 +697,13 @@ impl RpcClient {
         token_account_filter: TokenAccountsFilter,
         commitment_config: CommitmentConfig,
     ) -> RpcResult<Vec<RpcKeyedAccount>> {
-        let token_account_filter = match token_account_filter {
-            TokenAccountsFilter::Mint(mint) => RpcTokenAccountsFilter::Mint(mint.to_string()),
-            TokenAccountsFilter::ProgramId(program_id) => {
-                RpcTokenAccountsFilter::ProgramId(program_id.to_string())
-            }
-        };
-
-        let config = RpcAccountInfoConfig {
-            encoding: Some(UiAccountEncoding::JsonParsed),
-            commitment: Some(self.maybe_map_commitment(commitment_config)?),
-            data_slice: None,
-        };
-
-        self.send(
-            RpcRequest::GetTokenAccountsByOwner,
-            json!([delegate.to_string(), token_account_filter, config]),
+        self.invoke(
+            self.rpc_client
+                .get_token_accounts_by_delegate_with_commitment(
+                    delegate,
+                    token_account_filter,
+                    commitment_config,
+                ),
         )
     }
 
@@ -1160,13 +712,10 @@ impl RpcClient {
         owner: &Pubkey,
         token_account_filter: TokenAccountsFilter,
     ) -> ClientResult<Vec<RpcKeyedAccount>> {
-        Ok(self
-            .get_token_accounts_by_owner_with_commitment(
-                owner,
-                token_account_filter,
-                self.commitment_config,
-            )?
-            .value)
+        self.invoke(
+            self.rpc_client
+                .get_token_accounts_by_owner(owner, token_account_filter),
+        )
     }
 
     pub fn get_token_accounts_by_owner_with_commitment(
@@ -1175,29 +724,15 @@ impl RpcClient {
         token_account_filter: TokenAccountsFilter,
         commitment_config: CommitmentConfig,
     ) -> RpcResult<Vec<RpcKeyedAccount>> {
-        let token_account_filter = match token_account_filter {
-            TokenAccountsFilter::Mint(mint) => RpcTokenAccountsFilter::Mint(mint.to_string()),
-            TokenAccountsFilter::ProgramId(program_id) => {
-                RpcTokenAccountsFilter::ProgramId(program_id.to_string())
-            }
-        };
-
-        let config = RpcAccountInfoConfig {
-            encoding: Some(UiAccountEncoding::JsonParsed),
-            commitment: Some(self.maybe_map_commitment(commitment_config)?),
-            data_slice: None,
-        };
-
-        self.send(
-            RpcRequest::GetTokenAccountsByOwner,
-            json!([owner.to_string(), token_account_filter, config]),
-        )
+        self.invoke(self.rpc_client.get_token_accounts_by_owner_with_commitment(
+            owner,
+            token_account_filter,
+            commitment_config,
+        ))
     }
 
     pub fn get_token_supply(&self, mint: &Pubkey) -> ClientResult<UiTokenAmount> {
-        Ok(self
-            .get_token_supply_with_commitment(mint, self.commitment_config)?
-            .value)
+        self.invoke(self.rpc_client.get_token_supply(mint))
     }
 
     pub fn get_token_supply_with_commitment(
@@ -1205,48 +740,20 @@ impl RpcClient {
         mint: &Pubkey,
         commitment_config: CommitmentConfig,
     ) -> RpcResult<UiTokenAmount> {
-        self.send(
-            RpcRequest::GetTokenSupply,
-            json!([
-                mint.to_string(),
-                self.maybe_map_commitment(commitment_config)?
-            ]),
+        self.invoke(
+            self.rpc_client
+                .get_token_supply_with_commitment(mint, commitment_config),
         )
     }
 
-    fn poll_balance_with_timeout_and_commitment(
-        &self,
-        pubkey: &Pubkey,
-        polling_frequency: &Duration,
-        timeout: &Duration,
-        commitment_config: CommitmentConfig,
-    ) -> ClientResult<u64> {
-        let now = Instant::now();
-        loop {
-            match self.get_balance_with_commitment(&pubkey, commitment_config) {
-                Ok(bal) => {
-                    return Ok(bal.value);
-                }
-                Err(e) => {
-                    sleep(*polling_frequency);
-                    if now.elapsed() > *timeout {
-                        return Err(e);
-                    }
-                }
-            };
-        }
-    }
-
     pub fn poll_get_balance_with_commitment(
         &self,
         pubkey: &Pubkey,
         commitment_config: CommitmentConfig,
     ) -> ClientResult<u64> {
-        self.poll_balance_with_timeout_and_commitment(
-            pubkey,
-            &Duration::from_millis(100),
-            &Duration::from_secs(1),
-            commitment_config,
+        self.invoke(
+            self.rpc_client
+                .poll_get_balance_with_commitment(pubkey, commitment_config),
         )
     }
 
@@ -1256,31 +763,16 @@ impl RpcClient {
         expected_balance: Option<u64>,
         commitment_config: CommitmentConfig,
     ) -> Option<u64> {
-        const LAST: usize = 30;
-        for run in 0..LAST {
-            let balance_result = self.poll_get_balance_with_commitment(pubkey, commitment_config);
-            if expected_balance.is_none() {
-                return balance_result.ok();
-            }
-            trace!(
-                "wait_for_balance_with_commitment [{}] {:?} {:?}",
-                run,
-                balance_result,
-                expected_balance
-            );
-            if let (Some(expected_balance), Ok(balance_result)) = (expected_balance, balance_result)
-            {
-                if expected_balance == balance_result {
-                    return Some(balance_result);
-                }
-            }
-        }
-        None
+        self.invoke(self.rpc_client.wait_for_balance_with_commitment(
+            pubkey,
+            expected_balance,
+            commitment_config,
+        ))
     }
 
     /// Poll the server to confirm a transaction.
     pub fn poll_for_signature(&self, signature: &Signature) -> ClientResult<()> {
-        self.poll_for_signature_with_commitment(signature, self.commitment_config)
+        self.invoke(self.rpc_client.poll_for_signature(signature))
     }
 
     /// Poll the server to confirm a transaction.
@@ -1289,23 +781,10 @@ impl RpcClient {
         signature: &Signature,
         commitment_config: CommitmentConfig,
     ) -> ClientResult<()> {
-        let now = Instant::now();
-        loop {
-            if let Ok(Some(_)) =
-                self.get_signature_status_with_commitment(&signature, commitment_config)
-            {
-                break;
-            }
-            if now.elapsed().as_secs() > 15 {
-                return Err(RpcError::ForUser(format!(
-                    "signature not found after {} seconds",
-                    now.elapsed().as_secs()
-                ))
-                .into());
-            }
-            sleep(Duration::from_millis(250));
-        }
-        Ok(())
+        self.invoke(
+            self.rpc_client
+                .poll_for_signature_with_commitment(signature, commitment_config),
+        )
     }
 
     /// Poll the server to confirm a transaction.
@@ -1314,83 +793,29 @@ impl RpcClient {
         signature: &Signature,
         min_confirmed_blocks: usize,
     ) -> ClientResult<usize> {
-        let mut now = Instant::now();
-        let mut confirmed_blocks = 0;
-        loop {
-            let response = self.get_num_blocks_since_signature_confirmation(signature);
-            match response {
-                Ok(count) => {
-                    if confirmed_blocks != count {
-                        info!(
-                            "signature {} confirmed {} out of {} after {} ms",
-                            signature,
-                            count,
-                            min_confirmed_blocks,
-                            now.elapsed().as_millis()
-                        );
-                        now = Instant::now();
-                        confirmed_blocks = count;
-                    }
-                    if count >= min_confirmed_blocks {
-                        break;
-                    }
-                }
-                Err(err) => {
-                    debug!("check_confirmations request failed: {:?}", err);
-                }
-            };
-            if now.elapsed().as_secs() > 20 {
-                info!(
-                    "signature {} confirmed {} out of {} failed after {} ms",
-                    signature,
-                    confirmed_blocks,
-                    min_confirmed_blocks,
-                    now.elapsed().as_millis()
-                );
-                if confirmed_blocks > 0 {
-                    return Ok(confirmed_blocks);
-                } else {
-                    return Err(RpcError::ForUser(format!(
-                        "signature not found after {} seconds",
-                        now.elapsed().as_secs()
-                    ))
-                    .into());
-                }
-            }
-            sleep(Duration::from_millis(250));
-        }
-        Ok(confirmed_blocks)
+        self.invoke(
+            self.rpc_client
+                .poll_for_signature_confirmation(signature, min_confirmed_blocks),
+        )
     }
 
     pub fn get_num_blocks_since_signature_confirmation(
         &self,
         signature: &Signature,
     ) -> ClientResult<usize> {
-        let result: Response<Vec<Option<TransactionStatus>>> = self.send(
-            RpcRequest::GetSignatureStatuses,
-            json!([[signature.to_string()]]),
-        )?;
-
-        let confirmations = result.value[0]
-            .clone()
-            .ok_or_else(|| {
-                ClientError::new_with_request(
-                    ClientErrorKind::Custom("signature not found".to_string()),
-                    RpcRequest::GetSignatureStatuses,
-                )
-            })?
-            .confirmations
-            .unwrap_or(MAX_LOCKOUT_HISTORY + 1);
-        Ok(confirmations)
+        self.invoke(
+            self.rpc_client
+                .get_num_blocks_since_signature_confirmation(signature),
+        )
     }
 
     pub fn send_and_confirm_transaction_with_spinner(
         &self,
         transaction: &Transaction,
     ) -> ClientResult<Signature> {
-        self.send_and_confirm_transaction_with_spinner_and_commitment(
-            transaction,
-            self.commitment_config,
+        self.invoke(
+            self.rpc_client
+                .send_and_confirm_transaction_with_spinner(transaction),
         )
     }
 
@@ -1399,13 +824,9 @@ impl RpcClient {
         transaction: &Transaction,
         commitment: CommitmentConfig,
     ) -> ClientResult<Signature> {
-        self.send_and_confirm_transaction_with_spinner_and_config(
-            transaction,
-            commitment,
-            RpcSendTransactionConfig {
-                preflight_commitment: Some(commitment.commitment),
-                ..RpcSendTransactionConfig::default()
-            },
+        self.invoke(
+            self.rpc_client
+                .send_and_confirm_transaction_with_spinner_and_commitment(transaction, commitment),
         )
     }
 
@@ -1415,107 +836,21 @@ impl RpcClient {
         commitment: CommitmentConfig,
         config: RpcSendTransactionConfig,
     ) -> ClientResult<Signature> {
-        let desired_confirmations = if commitment.is_finalized() {
-            MAX_LOCKOUT_HISTORY + 1
-        } else {
-            1
-        };
-        let mut confirmations = 0;
-
-        let progress_bar = new_spinner_progress_bar();
-
-        progress_bar.set_message(&format!(
-            "[{}/{}] Finalizing transaction {}",
-            confirmations, desired_confirmations, transaction.signatures[0],
-        ));
-        let recent_blockhash = if uses_durable_nonce(transaction).is_some() {
-            self.get_recent_blockhash_with_commitment(CommitmentConfig::processed())?
-                .value
-                .0
-        } else {
-            transaction.message.recent_blockhash
-        };
-        let signature = self.send_transaction_with_config(transaction, config)?;
-        let (signature, status) = loop {
-            // Get recent commitment in order to count confirmations for successful transactions
-            let status = self
-                .get_signature_status_with_commitment(&signature, CommitmentConfig::processed())?;
-            if status.is_none() {
-                if self
-                    .get_fee_calculator_for_blockhash_with_commitment(
-                        &recent_blockhash,
-                        CommitmentConfig::processed(),
-                    )?
-                    .value
-                    .is_none()
-                {
-                    break (signature, status);
-                }
-            } else {
-                break (signature, status);
-            }
-
-            if cfg!(not(test)) {
-                sleep(Duration::from_millis(500));
-            }
-        };
-        if let Some(result) = status {
-            if let Err(err) = result {
-                return Err(err.into());
-            }
-        } else {
-            return Err(RpcError::ForUser(
-                "unable to confirm transaction. \
-                                      This can happen in situations such as transaction expiration \
-                                      and insufficient fee-payer funds"
-                    .to_string(),
-            )
-            .into());
-        }
-        let now = Instant::now();
-        loop {
-            // Return when specified commitment is reached
-            // Failed transactions have already been eliminated, `is_some` check is sufficient
-            if self
-                .get_signature_status_with_commitment(&signature, commitment)?
-                .is_some()
-            {
-                progress_bar.set_message("Transaction confirmed");
-                progress_bar.finish_and_clear();
-                return Ok(signature);
-            }
-
-            progress_bar.set_message(&format!(
-                "[{}/{}] Finalizing transaction {}",
-                min(confirmations + 1, desired_confirmations),
-                desired_confirmations,
-                signature,
-            ));
-            sleep(Duration::from_millis(500));
-            confirmations = self
-                .get_num_blocks_since_signature_confirmation(&signature)
-                .unwrap_or(confirmations);
-            if now.elapsed().as_secs() >= MAX_HASH_AGE_IN_SECONDS as u64 {
-                return Err(
-                    RpcError::ForUser("transaction not finalized. \
-                                      This can happen when a transaction lands in an abandoned fork. \
-                                      Please retry.".to_string()).into(),
-                );
-            }
-        }
+        self.invoke(
+            self.rpc_client
+                .send_and_confirm_transaction_with_spinner_and_config(
+                    transaction,
+                    commitment,
+                    config,
+                ),
+        )
     }
 
     pub fn send<T>(&self, request: RpcRequest, params: Value) -> ClientResult<T>
     where
         T: serde::de::DeserializeOwned,
     {
-        assert!(params.is_array() || params.is_null());
-        let response = self
-            .sender
-            .send(request, params)
-            .map_err(|err| err.into_with_request(request))?;
-        serde_json::from_value(response)
-            .map_err(|err| ClientError::new_with_request(err.into(), request))
+        self.invoke(self.rpc_client.send(request, params))
     }
 }
 
@@ -1524,47 +859,9 @@ pub struct GetConfirmedSignaturesForAddress2Config {
     pub before: Option<Signature>,
     pub until: Option<Signature>,
     pub limit: Option<usize>,
-}
-
-fn new_spinner_progress_bar() -> ProgressBar {
-    let progress_bar = ProgressBar::new(42);
-    progress_bar
-        .set_style(ProgressStyle::default_spinner().template("{spinner:.green} {wide_msg}"));
-    progress_bar.enable_steady_tick(100);
-    progress_bar
-}
-
-fn get_rpc_request_str(rpc_addr: SocketAddr, tls: bool) -> String {
-    if tls {
-        format!("https://{}", rpc_addr)
-    } else {
-        format!("http://{}", rpc_addr)
-    }
-}
-
-fn parse_keyed_accounts(
-    accounts: Vec<RpcKeyedAccount>,
-    request: RpcRequest,
-) -> ClientResult<Vec<(Pubkey, Account)>> {
-    let mut pubkey_accounts: Vec<(Pubkey, Account)> = Vec::new();
-    for RpcKeyedAccount { pubkey, account } in accounts.into_iter() {
-        let pubkey = pubkey.parse().map_err(|_| {
-            ClientError::new_with_request(
-                RpcError::ParseError("Pubkey".to_string()).into(),
-                request,
-            )
-        })?;
-        pubkey_accounts.push((
-            pubkey,
-            account.decode().ok_or_else(|| {
-                ClientError::new_with_request(
-                    RpcError::ParseError("Account from rpc".to_string()).into(),
-                    request,
-                )
-            })?,
-        ));
-    }
-    Ok(pubkey_accounts)
+    pub min_slot: Option<Slot>,
+    pub max_slot: Option<Slot>,
+    pub status: Option<TransactionStatusFilter>,
 }
 
 #[cfg(test)]
@@ -1574,9 +871,12 @@ mod tests {
     use assert_matches::assert_matches;
     use jsonrpc_core::{futures::prelude::*, Error, IoHandler, Params};
     use jsonrpc_http_server::{AccessControlAllowOrigin, DomainsValidation, ServerBuilder};
-    use serde_json::Number;
+    use serde_json::{json, Number};
     use solana_sdk::{
-        instruction::InstructionError, signature::Keypair, system_transaction,
+        instruction::InstructionError,
+        message::{v0, VersionedMessage},
+        signature::{Keypair, Signer},
+        system_instruction, system_transaction,
         transaction::TransactionError,
     };
     use std::{io, sync::mpsc::channel, thread};
@@ -1656,6 +956,20 @@ mod tests {
         let rpc_client = RpcClient::new_mock("malicious".to_string());
         let signature = rpc_client.send_transaction(&tx);
         assert!(signature.is_err());
+
+        // Versioned transactions are sent the same way
+        let rpc_client = RpcClient::new_mock("succeeds".to_string());
+        let message = v0::Message::try_compile(
+            &key.pubkey(),
+            &[system_instruction::transfer(&key.pubkey(), &to, 50)],
+            &[],
+            blockhash,
+        )
+        .unwrap();
+        let versioned_tx =
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &[&key]).unwrap();
+        let signature = rpc_client.send_transaction(&versioned_tx);
+        assert_eq!(signature.unwrap(), versioned_tx.signatures[0]);
     }
     #[test]
     fn test_get_recent_blockhash() {
@@ -1,5 +1,12 @@
 use crate::{client_error::Result, rpc_request::RpcRequest};
+use async_trait::async_trait;
 
+/// A transport for JSON RPC requests, used by both the blocking and nonblocking `RpcClient`
+#[async_trait]
 pub trait RpcSender {
-    fn send(&self, request: RpcRequest, params: serde_json::Value) -> Result<serde_json::Value>;
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value>;
 }
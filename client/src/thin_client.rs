@@ -3,7 +3,10 @@
 //! messages to the network directly. The binary encoding of its messages are
 //! unstable and may change in future releases.
 
-use crate::{rpc_client::RpcClient, rpc_config::RpcProgramAccountsConfig, rpc_response::Response};
+use crate::{
+    quic_client::QuicClient, rpc_client::RpcClient, rpc_config::RpcProgramAccountsConfig,
+    rpc_response::Response,
+};
 use bincode::{serialize_into, serialized_size};
 use log::*;
 use solana_sdk::{
@@ -120,6 +123,7 @@ pub struct ThinClient {
     tpu_addrs: Vec<SocketAddr>,
     rpc_clients: Vec<RpcClient>,
     optimizer: ClientOptimizer,
+    quic_client: Option<QuicClient>,
 }
 
 impl ThinClient {
@@ -153,6 +157,21 @@ impl ThinClient {
             tpu_addrs: vec![tpu_addr],
             rpc_clients: vec![rpc_client],
             optimizer: ClientOptimizer::new(0),
+            quic_client: None,
+        }
+    }
+
+    /// Create a new ThinClient that will interface with the Rpc at `rpc_addr` using TCP
+    /// and the Tpu QUIC port at `tpu_quic_addr` using `quic_client`.
+    pub fn new_with_quic_client(
+        rpc_addr: SocketAddr,
+        tpu_quic_addr: SocketAddr,
+        transactions_socket: UdpSocket,
+        quic_client: QuicClient,
+    ) -> Self {
+        Self {
+            quic_client: Some(quic_client),
+            ..Self::new(rpc_addr, tpu_quic_addr, transactions_socket)
         }
     }
 
@@ -171,6 +190,7 @@ impl ThinClient {
             transactions_socket,
             rpc_clients,
             optimizer,
+            quic_client: None,
         }
     }
 
@@ -182,6 +202,18 @@ impl ThinClient {
         &self.rpc_clients[self.optimizer.best()]
     }
 
+    fn send_wire_transaction(&self, wire_transaction: &[u8]) -> io::Result<()> {
+        match &self.quic_client {
+            Some(quic_client) => quic_client
+                .send_wire_transaction(self.tpu_addr(), wire_transaction)
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, err)),
+            None => self
+                .transactions_socket
+                .send_to(wire_transaction, self.tpu_addr())
+                .map(|_| ()),
+        }
+    }
+
     /// Retry a sending a signed Transaction to the server for processing.
     pub fn retry_transfer_until_confirmed(
         &self,
@@ -223,8 +255,7 @@ impl ThinClient {
             while now.elapsed().as_secs() < wait_time as u64 {
                 if num_confirmed == 0 {
                     // Send the transaction if there has been no confirmation (e.g. the first time)
-                    self.transactions_socket
-                        .send_to(&buf[..], &self.tpu_addr())?;
+                    self.send_wire_transaction(&buf)?;
                 }
 
                 if let Ok(confirmed_blocks) = self.poll_for_signature_confirmation(
@@ -567,8 +598,7 @@ impl AsyncClient for ThinClient {
         serialize_into(&mut wr, &transaction)
             .expect("serialize Transaction in pub fn transfer_signed");
         assert!(buf.len() < PACKET_DATA_SIZE);
-        self.transactions_socket
-            .send_to(&buf[..], &self.tpu_addr())?;
+        self.send_wire_transaction(&buf)?;
         Ok(transaction.signatures[0])
     }
     fn async_send_message<T: Signers>(
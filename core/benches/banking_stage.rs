@@ -12,12 +12,14 @@ use solana_core::cluster_info::ClusterInfo;
 use solana_core::cluster_info::Node;
 use solana_core::poh_recorder::WorkingBankEntry;
 use solana_ledger::blockstore_processor::process_entries;
-use solana_ledger::entry::{next_hash, Entry};
+use solana_ledger::entry::Entry;
 use solana_ledger::genesis_utils::{create_genesis_config, GenesisConfigInfo};
 use solana_ledger::{blockstore::Blockstore, get_tmp_ledger_path};
 use solana_perf::packet::to_packets_chunked;
 use solana_perf::test_tx::test_tx;
 use solana_runtime::bank::Bank;
+use solana_runtime::cost_model::CostModel;
+use solana_runtime::cost_tracker::CostTracker;
 use solana_sdk::genesis_config::GenesisConfig;
 use solana_sdk::hash::Hash;
 use solana_sdk::message::Message;
@@ -32,7 +34,7 @@ use solana_sdk::transaction::Transaction;
 use std::collections::VecDeque;
 use std::sync::atomic::Ordering;
 use std::sync::mpsc::Receiver;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 use test::Bencher;
 
@@ -76,6 +78,10 @@ fn bench_consume_buffered(bencher: &mut Bencher) {
             packets.push_back((batch, vec![0usize; batch_len], false));
         }
         let (s, _r) = unbounded();
+        let cost_tracker = Arc::new(RwLock::new(CostTracker::new(CostModel::new(
+            u64::MAX,
+            u64::MAX,
+        ))));
         // This tests the performance of buffering packets.
         // If the packet buffers are copied, performance will be poor.
         bencher.iter(move || {
@@ -87,6 +93,8 @@ fn bench_consume_buffered(bencher: &mut Bencher) {
                 &s,
                 None::<Box<dyn Fn()>>,
                 None,
+                &cost_tracker,
+                None,
             );
         });
 
@@ -197,13 +205,15 @@ fn bench_banking(bencher: &mut Bencher, tx_type: TransactionType) {
         let cluster_info = ClusterInfo::new_with_invalid_keypair(Node::new_localhost().info);
         let cluster_info = Arc::new(cluster_info);
         let (s, _r) = unbounded();
-        let _banking_stage = BankingStage::new(
+        // Measure raw throughput, without holding back transactions over the cost limits
+        let _banking_stage = BankingStage::new_with_cost_model(
             &cluster_info,
             &poh_recorder,
             verified_receiver,
             vote_receiver,
             None,
             s,
+            CostModel::new(u64::MAX, u64::MAX),
         );
         poh_recorder.lock().unwrap().set_bank(&bank);
 
@@ -290,11 +300,7 @@ fn simulate_process_entries(
     }
 
     // Transfer lamports to each other
-    let entry = Entry {
-        num_hashes: 1,
-        hash: next_hash(&bank.last_blockhash(), 1, &tx_vector),
-        transactions: tx_vector,
-    };
+    let entry = Entry::new(&bank.last_blockhash(), 1, tx_vector);
     process_entries(&bank, &[entry], randomize_txs, None, None).unwrap();
 }
 
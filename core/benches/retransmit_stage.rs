@@ -8,6 +8,7 @@ use solana_core::cluster_info::{ClusterInfo, Node};
 use solana_core::contact_info::ContactInfo;
 use solana_core::max_slots::MaxSlots;
 use solana_core::retransmit_stage::retransmitter;
+use solana_core::turbine_tree::LegacyTurbineTree;
 use solana_ledger::entry::Entry;
 use solana_ledger::genesis_utils::{create_genesis_config, GenesisConfigInfo};
 use solana_ledger::leader_schedule_cache::LeaderScheduleCache;
@@ -95,6 +96,7 @@ fn bench_retransmitter(bencher: &mut Bencher) {
         packet_receiver,
         &Arc::new(MaxSlots::default()),
         None,
+        Arc::new(LegacyTurbineTree::default()),
     );
 
     let mut index = 0;
@@ -38,7 +38,6 @@ impl AccountsHashVerifier {
         trusted_validators: Option<HashSet<Pubkey>>,
         halt_on_trusted_validators_accounts_hash_mismatch: bool,
         fault_injection_rate_slots: u64,
-        snapshot_interval_slots: u64,
     ) -> Self {
         let exit = exit.clone();
         let cluster_info = cluster_info.clone();
@@ -70,7 +69,6 @@ impl AccountsHashVerifier {
                                 &mut hashes,
                                 &exit,
                                 fault_injection_rate_slots,
-                                snapshot_interval_slots,
                                 thread_pool_storage.as_ref(),
                             );
                         }
@@ -95,7 +93,6 @@ impl AccountsHashVerifier {
         hashes: &mut Vec<(Slot, Hash)>,
         exit: &Arc<AtomicBool>,
         fault_injection_rate_slots: u64,
-        snapshot_interval_slots: u64,
         thread_pool: Option<&ThreadPool>,
     ) {
         let accounts_package = solana_runtime::snapshot_utils::process_accounts_package_pre(
@@ -111,7 +108,6 @@ impl AccountsHashVerifier {
             hashes,
             exit,
             fault_injection_rate_slots,
-            snapshot_interval_slots,
         );
     }
 
@@ -124,7 +120,6 @@ impl AccountsHashVerifier {
         hashes: &mut Vec<(Slot, Hash)>,
         exit: &Arc<AtomicBool>,
         fault_injection_rate_slots: u64,
-        snapshot_interval_slots: u64,
     ) {
         let hash = accounts_package.hash;
         if fault_injection_rate_slots != 0
@@ -155,13 +150,24 @@ impl AccountsHashVerifier {
             }
         }
 
-        if accounts_package.block_height % snapshot_interval_slots == 0 {
-            if let Some(pending_snapshot_package) = pending_snapshot_package.as_ref() {
-                *pending_snapshot_package.lock().unwrap() = Some(accounts_package);
+        cluster_info.push_accounts_hashes(hashes.clone());
+
+        if let (Some(snapshot_type), Some(pending_snapshot_package)) = (
+            accounts_package.snapshot_type,
+            pending_snapshot_package.as_ref(),
+        ) {
+            let mut pending_snapshot_package = pending_snapshot_package.lock().unwrap();
+            // Never drop a full snapshot that has yet to be archived in favor of an incremental
+            // snapshot, later incremental snapshots are built on top of it
+            let is_pending_full_snapshot = pending_snapshot_package
+                .as_ref()
+                .and_then(|pending| pending.snapshot_type)
+                .map(|pending_snapshot_type| pending_snapshot_type.is_full_snapshot())
+                .unwrap_or(false);
+            if snapshot_type.is_full_snapshot() || !is_pending_full_snapshot {
+                *pending_snapshot_package = Some(accounts_package);
             }
         }
-
-        cluster_info.push_accounts_hashes(hashes.clone());
     }
 
     fn should_halt(
@@ -221,6 +227,7 @@ mod tests {
     use crate::cluster_info::make_accounts_hashes_message;
     use crate::contact_info::ContactInfo;
     use solana_runtime::bank_forks::ArchiveFormat;
+    use solana_runtime::snapshot_package::SnapshotType;
     use solana_runtime::snapshot_utils::SnapshotVersion;
     use solana_sdk::{
         hash::hash,
@@ -286,6 +293,7 @@ mod tests {
                 storages: vec![],
                 archive_format: ArchiveFormat::TarBzip2,
                 snapshot_version: SnapshotVersion::default(),
+                snapshot_type: None,
             };
 
             AccountsHashVerifier::process_accounts_package(
@@ -297,7 +305,6 @@ mod tests {
                 &mut hashes,
                 &exit,
                 0,
-                100,
             );
             // sleep for 1ms to create a newer timestmap for gossip entry
             // otherwise the timestamp won't be newer.
@@ -319,4 +326,72 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_pending_full_snapshot_is_kept() {
+        use std::{path::PathBuf, sync::Mutex};
+        use tempfile::TempDir;
+        let keypair = Keypair::new();
+        let contact_info = ContactInfo::new_localhost(&keypair.pubkey(), 0);
+        let cluster_info = ClusterInfo::new_with_invalid_keypair(contact_info);
+        let exit = Arc::new(AtomicBool::new(false));
+        let pending_snapshot_package = Some(Arc::new(Mutex::new(None)));
+        let mut hashes = vec![];
+
+        let mut process = |slot, snapshot_type| {
+            let accounts_package = AccountsPackage {
+                hash: hash(&[slot as u8]),
+                block_height: slot,
+                slot,
+                slot_deltas: vec![],
+                snapshot_links: TempDir::new().unwrap(),
+                tar_output_file: PathBuf::from("."),
+                storages: vec![],
+                archive_format: ArchiveFormat::TarBzip2,
+                snapshot_version: SnapshotVersion::default(),
+                snapshot_type,
+            };
+            AccountsHashVerifier::process_accounts_package(
+                accounts_package,
+                &cluster_info,
+                &None,
+                false,
+                &pending_snapshot_package,
+                &mut hashes,
+                &exit,
+                0,
+            );
+        };
+        let pending_slot = || {
+            pending_snapshot_package
+                .as_ref()
+                .unwrap()
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|package| package.slot)
+        };
+
+        // Packages that are only used to verify the hash are never archived
+        process(100, None);
+        assert_eq!(pending_slot(), None);
+
+        process(200, Some(SnapshotType::FullSnapshot));
+        assert_eq!(pending_slot(), Some(200));
+
+        // An incremental snapshot must not replace a full snapshot that is still pending
+        process(300, Some(SnapshotType::IncrementalSnapshot(200)));
+        assert_eq!(pending_slot(), Some(200));
+
+        pending_snapshot_package
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .take();
+        process(400, Some(SnapshotType::IncrementalSnapshot(200)));
+        assert_eq!(pending_slot(), Some(400));
+        process(500, Some(SnapshotType::FullSnapshot));
+        assert_eq!(pending_slot(), Some(500));
+    }
 }
@@ -0,0 +1,204 @@
+//! The `accountsdb_plugin_manager` module loads AccountsDb plugins from shared libraries and
+//! dispatches account, slot status and transaction notifications to them
+
+use crate::transaction_notifier_interface::TransactionNotifier;
+use libloading::{Library, Symbol};
+use log::*;
+use solana_accountsdb_plugin_interface::accountsdb_plugin_interface::{
+    AccountsDbPlugin, PluginConstructor, ReplicaAccountInfo, ReplicaTransactionInfo, SlotStatus,
+    CREATE_PLUGIN_SYMBOL,
+};
+use solana_measure::measure::Measure;
+use solana_metrics::inc_new_counter_debug;
+use solana_runtime::{
+    accounts_update_notifier_interface::AccountsUpdateNotifierInterface,
+    bank::is_simple_vote_transaction,
+};
+use solana_sdk::{
+    account::{AccountSharedData, ReadableAccount},
+    clock::Slot,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::Transaction,
+};
+use solana_transaction_status::TransactionStatusMeta;
+use std::{
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+#[derive(Default)]
+pub struct AccountsDbPluginManager {
+    plugins: Vec<Box<dyn AccountsDbPlugin>>,
+    libs: Vec<Library>,
+}
+
+impl fmt::Debug for AccountsDbPluginManager {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.plugins.iter().map(|plugin| plugin.name()))
+            .finish()
+    }
+}
+
+impl AccountsDbPluginManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the plugin at `libpath` and initialize it with `config_file`
+    ///
+    /// # Safety
+    ///
+    /// The library must export a `_create_plugin` function with the `PluginConstructor`
+    /// signature, typically by way of `declare_plugin!`, and must be built with the same rustc
+    /// and `solana-accountsdb-plugin-interface` version as the validator.
+    pub unsafe fn load_plugin(
+        &mut self,
+        libpath: &str,
+        config_file: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let lib = Library::new(libpath)?;
+        let constructor: Symbol<PluginConstructor> = lib.get(CREATE_PLUGIN_SYMBOL)?;
+        let mut plugin = Box::from_raw(constructor());
+        plugin.on_load(config_file)?;
+        info!(
+            "Loaded AccountsDb plugin {} from {}",
+            plugin.name(),
+            libpath
+        );
+        self.plugins.push(plugin);
+        self.libs.push(lib);
+        Ok(())
+    }
+
+    /// Unload all plugins and their libraries. The plugins must be dropped before the libraries
+    /// that contain their code.
+    pub fn unload(&mut self) {
+        for mut plugin in self.plugins.drain(..) {
+            info!("Unloading AccountsDb plugin {}", plugin.name());
+            plugin.on_unload();
+        }
+        self.libs.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    fn update_account(&self, account: &ReplicaAccountInfo, slot: Slot) {
+        for plugin in &self.plugins {
+            if let Err(err) = plugin.update_account(account, slot) {
+                error!(
+                    "Failed to update account {} at slot {} in plugin {}: {}",
+                    bs58::encode(account.pubkey).into_string(),
+                    slot,
+                    plugin.name(),
+                    err
+                );
+            }
+        }
+    }
+
+    pub fn update_slot_status(&self, slot: Slot, parent: Option<Slot>, status: SlotStatus) {
+        for plugin in &self.plugins {
+            if let Err(err) = plugin.update_slot_status(slot, parent, status) {
+                error!(
+                    "Failed to update slot {} to {} in plugin {}: {}",
+                    slot,
+                    status.as_str(),
+                    plugin.name(),
+                    err
+                );
+            }
+        }
+    }
+
+    fn notify_transaction(&self, transaction: &ReplicaTransactionInfo, slot: Slot) {
+        for plugin in &self.plugins {
+            if let Err(err) = plugin.notify_transaction(transaction, slot) {
+                error!(
+                    "Failed to notify transaction {} at slot {} in plugin {}: {}",
+                    transaction.signature,
+                    slot,
+                    plugin.name(),
+                    err
+                );
+            }
+        }
+    }
+}
+
+/// Forwards account updates from `AccountsDb` to the loaded plugins
+#[derive(Debug)]
+pub struct AccountsUpdateNotifierImpl {
+    plugin_manager: Arc<RwLock<AccountsDbPluginManager>>,
+}
+
+impl AccountsUpdateNotifierImpl {
+    pub fn new(plugin_manager: Arc<RwLock<AccountsDbPluginManager>>) -> Self {
+        Self { plugin_manager }
+    }
+}
+
+impl AccountsUpdateNotifierInterface for AccountsUpdateNotifierImpl {
+    fn notify_account_update(&self, slot: Slot, pubkey: &Pubkey, account: &AccountSharedData) {
+        let mut measure = Measure::start("accountsdb-plugin-notify-account");
+        let account = ReplicaAccountInfo {
+            pubkey: pubkey.as_ref(),
+            lamports: account.lamports(),
+            owner: account.owner().as_ref(),
+            executable: account.executable(),
+            rent_epoch: account.rent_epoch(),
+            data: account.data(),
+        };
+        self.plugin_manager
+            .read()
+            .unwrap()
+            .update_account(&account, slot);
+        measure.stop();
+        inc_new_counter_debug!(
+            "accountsdb-plugin-notify-account-us",
+            measure.as_us() as usize
+        );
+    }
+}
+
+/// Forwards transactions from the `TransactionStatusService` to the loaded plugins
+#[derive(Debug)]
+pub struct TransactionNotifierImpl {
+    plugin_manager: Arc<RwLock<AccountsDbPluginManager>>,
+}
+
+impl TransactionNotifierImpl {
+    pub fn new(plugin_manager: Arc<RwLock<AccountsDbPluginManager>>) -> Self {
+        Self { plugin_manager }
+    }
+}
+
+impl TransactionNotifier for TransactionNotifierImpl {
+    fn notify_transaction(
+        &self,
+        slot: Slot,
+        signature: &Signature,
+        transaction_status_meta: &TransactionStatusMeta,
+        transaction: &Transaction,
+    ) {
+        let mut measure = Measure::start("accountsdb-plugin-notify-transaction");
+        let transaction_info = ReplicaTransactionInfo {
+            signature,
+            is_vote: is_simple_vote_transaction(transaction),
+            transaction,
+            transaction_status_meta,
+        };
+        self.plugin_manager
+            .read()
+            .unwrap()
+            .notify_transaction(&transaction_info, slot);
+        measure.stop();
+        inc_new_counter_debug!(
+            "accountsdb-plugin-notify-transaction-us",
+            measure.as_us() as usize
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! The `accountsdb_plugin_service` module loads the AccountsDb plugins named by the validator's
+//! plugin config files and forwards bank slot status changes to them
+
+use crate::{
+    accountsdb_plugin_manager::{
+        AccountsDbPluginManager, AccountsUpdateNotifierImpl, TransactionNotifierImpl,
+    },
+    optimistically_confirmed_bank_tracker::{BankNotification, BankNotificationReceiver},
+    transaction_notifier_interface::TransactionNotifierLock,
+};
+use crossbeam_channel::RecvTimeoutError;
+use solana_accountsdb_plugin_interface::accountsdb_plugin_interface::SlotStatus;
+use solana_runtime::accounts_update_notifier_interface::AccountsUpdateNotifier;
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread::{self, Builder, JoinHandle},
+    time::Duration,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AccountsDbPluginServiceError {
+    #[error("Cannot open the plugin config file")]
+    CannotOpenConfigFile(String),
+
+    #[error("Cannot read the plugin config file")]
+    CannotReadConfigFile(String),
+
+    #[error("The config file is not in a valid Json format")]
+    InvalidConfigFileFormat(String),
+
+    #[error("Plugin library path is not specified in the config file")]
+    LibPathNotSet,
+
+    #[error("Invalid plugin path")]
+    InvalidPluginPath,
+
+    #[error("Cannot load plugin shared library")]
+    PluginLoadError(String),
+}
+
+pub struct AccountsDbPluginService {
+    slot_status_observer: JoinHandle<()>,
+    plugin_manager: Arc<RwLock<AccountsDbPluginManager>>,
+    accounts_update_notifier: AccountsUpdateNotifier,
+    transaction_notifier: TransactionNotifierLock,
+}
+
+impl AccountsDbPluginService {
+    /// Load each plugin named by `accountsdb_plugin_config_files` and start observing
+    /// `bank_notification_receiver` for slot status changes. Each config file is a JSON document
+    /// with at least a "libpath" field naming the plugin's shared library; the whole file is
+    /// handed to the plugin's `on_load`.
+    pub fn new(
+        bank_notification_receiver: BankNotificationReceiver,
+        accountsdb_plugin_config_files: &[PathBuf],
+        exit: &Arc<AtomicBool>,
+    ) -> Result<Self, AccountsDbPluginServiceError> {
+        info!(
+            "Starting AccountsDbPluginService from config files: {:?}",
+            accountsdb_plugin_config_files
+        );
+        let mut plugin_manager = AccountsDbPluginManager::new();
+        for accountsdb_plugin_config_file in accountsdb_plugin_config_files {
+            Self::load_plugin(&mut plugin_manager, accountsdb_plugin_config_file)?;
+        }
+        let plugin_manager = Arc::new(RwLock::new(plugin_manager));
+
+        let accounts_update_notifier: AccountsUpdateNotifier = Arc::new(RwLock::new(
+            AccountsUpdateNotifierImpl::new(plugin_manager.clone()),
+        ));
+        let transaction_notifier: TransactionNotifierLock = Arc::new(RwLock::new(
+            TransactionNotifierImpl::new(plugin_manager.clone()),
+        ));
+
+        let exit = exit.clone();
+        let slot_status_observer = {
+            let plugin_manager = plugin_manager.clone();
+            Builder::new()
+                .name("solana-accountsdb-plugin-slot-status".to_string())
+                .spawn(move || loop {
+                    if exit.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    match bank_notification_receiver.recv_timeout(Duration::from_secs(1)) {
+                        Ok(notification) => Self::notify_slot_status(&plugin_manager, notification),
+                        Err(RecvTimeoutError::Timeout) => (),
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                })
+                .unwrap()
+        };
+
+        info!("Started AccountsDbPluginService");
+        Ok(Self {
+            slot_status_observer,
+            plugin_manager,
+            accounts_update_notifier,
+            transaction_notifier,
+        })
+    }
+
+    fn load_plugin(
+        plugin_manager: &mut AccountsDbPluginManager,
+        accountsdb_plugin_config_file: &Path,
+    ) -> Result<(), AccountsDbPluginServiceError> {
+        let libpath = Self::read_libpath(accountsdb_plugin_config_file)?;
+        let config_file = accountsdb_plugin_config_file
+            .as_os_str()
+            .to_str()
+            .ok_or(AccountsDbPluginServiceError::InvalidPluginPath)?;
+
+        unsafe { plugin_manager.load_plugin(&libpath, config_file) }
+            .map_err(|err| AccountsDbPluginServiceError::PluginLoadError(err.to_string()))
+    }
+
+    fn read_libpath(
+        accountsdb_plugin_config_file: &Path,
+    ) -> Result<String, AccountsDbPluginServiceError> {
+        let mut file = File::open(accountsdb_plugin_config_file).map_err(|err| {
+            AccountsDbPluginServiceError::CannotOpenConfigFile(format!(
+                "Failed to open the plugin config file {:?}, error: {:?}",
+                accountsdb_plugin_config_file, err
+            ))
+        })?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).map_err(|err| {
+            AccountsDbPluginServiceError::CannotReadConfigFile(format!(
+                "Failed to read the plugin config file {:?}, error: {:?}",
+                accountsdb_plugin_config_file, err
+            ))
+        })?;
+
+        let config: serde_json::Value = serde_json::from_str(&contents).map_err(|err| {
+            AccountsDbPluginServiceError::InvalidConfigFileFormat(format!(
+                "The config file {:?} is not in a valid Json format, error: {:?}",
+                accountsdb_plugin_config_file, err
+            ))
+        })?;
+
+        config["libpath"]
+            .as_str()
+            .map(|libpath| libpath.to_string())
+            .ok_or(AccountsDbPluginServiceError::LibPathNotSet)
+    }
+
+    fn notify_slot_status(
+        plugin_manager: &RwLock<AccountsDbPluginManager>,
+        notification: BankNotification,
+    ) {
+        let plugin_manager = plugin_manager.read().unwrap();
+        match notification {
+            BankNotification::Frozen(bank) => plugin_manager.update_slot_status(
+                bank.slot(),
+                Some(bank.parent_slot()),
+                SlotStatus::Processed,
+            ),
+            BankNotification::OptimisticallyConfirmed(slot) => {
+                plugin_manager.update_slot_status(slot, None, SlotStatus::Confirmed)
+            }
+            BankNotification::Root(bank) => plugin_manager.update_slot_status(
+                bank.slot(),
+                Some(bank.parent_slot()),
+                SlotStatus::Rooted,
+            ),
+        }
+    }
+
+    pub fn get_accounts_update_notifier(&self) -> AccountsUpdateNotifier {
+        self.accounts_update_notifier.clone()
+    }
+
+    pub fn get_transaction_notifier(&self) -> TransactionNotifierLock {
+        self.transaction_notifier.clone()
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.slot_status_observer.join()?;
+        self.plugin_manager.write().unwrap().unload();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_config(contents: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_read_libpath() {
+        let file = write_config(r#"{"libpath": "/tmp/libplugin.so", "output_path": "/tmp/out"}"#);
+        assert_eq!(
+            AccountsDbPluginService::read_libpath(file.path()).unwrap(),
+            "/tmp/libplugin.so"
+        );
+
+        let file = write_config(r#"{"output_path": "/tmp/out"}"#);
+        assert!(matches!(
+            AccountsDbPluginService::read_libpath(file.path()),
+            Err(AccountsDbPluginServiceError::LibPathNotSet)
+        ));
+
+        let file = write_config("libpath = /tmp/libplugin.so");
+        assert!(matches!(
+            AccountsDbPluginService::read_libpath(file.path()),
+            Err(AccountsDbPluginServiceError::InvalidConfigFileFormat(_))
+        ));
+
+        assert!(matches!(
+            AccountsDbPluginService::read_libpath(Path::new("/nonexistent/plugin.json")),
+            Err(AccountsDbPluginServiceError::CannotOpenConfigFile(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_missing_plugin() {
+        let file = write_config(r#"{"libpath": "/nonexistent/libplugin.so"}"#);
+        let (_sender, receiver) = crossbeam_channel::unbounded();
+        let exit = Arc::new(AtomicBool::new(false));
+        assert!(matches!(
+            AccountsDbPluginService::new(receiver, &[file.path().to_path_buf()], &exit),
+            Err(AccountsDbPluginServiceError::PluginLoadError(_))
+        ));
+    }
+}
@@ -10,6 +10,7 @@ use crate::{
 use crossbeam_channel::{Receiver as CrossbeamReceiver, RecvTimeoutError};
 use itertools::Itertools;
 use lru::LruCache;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use retain_mut::RetainMut;
 use solana_ledger::{
     blockstore::Blockstore,
@@ -24,6 +25,7 @@ use solana_perf::{
     packet::{limited_deserialize, Packet, Packets, PACKETS_PER_BATCH},
     perf_libs,
 };
+use solana_rayon_threadlimit::get_thread_count;
 use solana_runtime::{
     accounts_db::ErrorCounters,
     bank::{
@@ -31,7 +33,10 @@ use solana_runtime::{
         TransactionExecutionResult,
     },
     bank_utils,
+    cost_model::{CostModel, TransactionCost},
+    cost_tracker::CostTracker,
     transaction_batch::TransactionBatch,
+    transaction_scheduler::TransactionSchedule,
     vote_sender_types::ReplayVoteSender,
 };
 use solana_sdk::{
@@ -39,10 +44,11 @@ use solana_sdk::{
         Slot, DEFAULT_TICKS_PER_SLOT, MAX_PROCESSING_AGE, MAX_TRANSACTION_FORWARDING_DELAY,
         MAX_TRANSACTION_FORWARDING_DELAY_GPU,
     },
+    compute_budget::ComputeBudgetRequest,
     poh_config::PohConfig,
     pubkey::Pubkey,
     timing::{duration_as_ms, timestamp},
-    transaction::{self, Transaction, TransactionError},
+    transaction::{self, Transaction, TransactionError, VersionedTransaction},
 };
 use solana_transaction_status::token_balances::{
     collect_token_balances, TransactionTokenBalancesSet,
@@ -55,7 +61,7 @@ use std::{
     ops::DerefMut,
     sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
     sync::mpsc::Receiver,
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, RwLock},
     thread::{self, Builder, JoinHandle},
     time::Duration,
     time::Instant,
@@ -91,6 +97,7 @@ pub struct BankingStageStats {
     current_buffered_packets_count: AtomicUsize,
     rebuffered_packets_count: AtomicUsize,
     consumed_buffered_packets_count: AtomicUsize,
+    cost_tracker_rebuffered_count: AtomicUsize,
 }
 
 impl BankingStageStats {
@@ -149,6 +156,12 @@ impl BankingStageStats {
                     self.rebuffered_packets_count.swap(0, Ordering::Relaxed) as i64,
                     i64
                 ),
+                (
+                    "cost_tracker_rebuffered_count",
+                    self.cost_tracker_rebuffered_count
+                        .swap(0, Ordering::Relaxed) as i64,
+                    i64
+                ),
             );
         }
     }
@@ -178,6 +191,57 @@ impl BankingStage {
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: ReplayVoteSender,
     ) -> Self {
+        Self::new_with_cost_model(
+            cluster_info,
+            poh_recorder,
+            verified_receiver,
+            verified_vote_receiver,
+            transaction_status_sender,
+            gossip_vote_sender,
+            CostModel::default(),
+        )
+    }
+
+    /// Create the stage with the block and account cost limits of `cost_model`
+    pub fn new_with_cost_model(
+        cluster_info: &Arc<ClusterInfo>,
+        poh_recorder: &Arc<Mutex<PohRecorder>>,
+        verified_receiver: CrossbeamReceiver<Vec<Packets>>,
+        verified_vote_receiver: CrossbeamReceiver<Vec<Packets>>,
+        transaction_status_sender: Option<TransactionStatusSender>,
+        gossip_vote_sender: ReplayVoteSender,
+        cost_model: CostModel,
+    ) -> Self {
+        Self::new_num_threads(
+            cluster_info,
+            poh_recorder,
+            verified_receiver,
+            verified_vote_receiver,
+            Self::num_threads(),
+            transaction_status_sender,
+            gossip_vote_sender,
+            cost_model,
+            None,
+        )
+    }
+
+    /// Create the stage with the limits of `cost_model` and a thread pool. The received
+    /// transactions are scheduled by their account locks, and the transactions of each wave of
+    /// the schedule are loaded and executed on the thread pool in parallel.
+    pub fn new_with_parallel_execution(
+        cluster_info: &Arc<ClusterInfo>,
+        poh_recorder: &Arc<Mutex<PohRecorder>>,
+        verified_receiver: CrossbeamReceiver<Vec<Packets>>,
+        verified_vote_receiver: CrossbeamReceiver<Vec<Packets>>,
+        transaction_status_sender: Option<TransactionStatusSender>,
+        gossip_vote_sender: ReplayVoteSender,
+        cost_model: CostModel,
+    ) -> Self {
+        let thread_pool = ThreadPoolBuilder::new()
+            .num_threads(get_thread_count())
+            .thread_name(|i| format!("solana-banking-stage-exec-{}", i))
+            .build()
+            .unwrap();
         Self::new_num_threads(
             cluster_info,
             poh_recorder,
@@ -186,9 +250,12 @@ impl BankingStage {
             Self::num_threads(),
             transaction_status_sender,
             gossip_vote_sender,
+            cost_model,
+            Some(Arc::new(thread_pool)),
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn new_num_threads(
         cluster_info: &Arc<ClusterInfo>,
         poh_recorder: &Arc<Mutex<PohRecorder>>,
@@ -197,6 +264,8 @@ impl BankingStage {
         num_threads: u32,
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: ReplayVoteSender,
+        cost_model: CostModel,
+        execution_thread_pool: Option<Arc<ThreadPool>>,
     ) -> Self {
         let batch_limit = TOTAL_BUFFERED_PACKETS / ((num_threads - 1) as usize * PACKETS_PER_BATCH);
         // Single thread to generate entries from many banks.
@@ -207,6 +276,8 @@ impl BankingStage {
             LruCache::new(DEFAULT_LRU_SIZE),
             PacketHasher::default(),
         )));
+        // All threads fill the same block, so they share the cost tracker
+        let cost_tracker = Arc::new(RwLock::new(CostTracker::new(cost_model)));
         // Many banks that process transactions in parallel.
         let bank_thread_hdls: Vec<JoinHandle<()>> = (0..num_threads)
             .map(|i| {
@@ -223,6 +294,8 @@ impl BankingStage {
                 let transaction_status_sender = transaction_status_sender.clone();
                 let gossip_vote_sender = gossip_vote_sender.clone();
                 let duplicates = duplicates.clone();
+                let cost_tracker = cost_tracker.clone();
+                let execution_thread_pool = execution_thread_pool.clone();
                 Builder::new()
                     .name("solana-banking-stage-tx".to_string())
                     .spawn(move || {
@@ -239,6 +312,8 @@ impl BankingStage {
                             transaction_status_sender,
                             gossip_vote_sender,
                             &duplicates,
+                            &cost_tracker,
+                            execution_thread_pool.as_deref(),
                         );
                     })
                     .unwrap()
@@ -294,9 +369,12 @@ impl BankingStage {
         gossip_vote_sender: &ReplayVoteSender,
         test_fn: Option<impl Fn()>,
         banking_stage_stats: Option<&BankingStageStats>,
+        cost_tracker: &Arc<RwLock<CostTracker>>,
+        execution_thread_pool: Option<&ThreadPool>,
     ) {
         let mut rebuffered_packets_len = 0;
         let mut new_tx_count = 0;
+        let mut cost_tracker_rebuffered_count = 0;
         let buffered_len = buffered_packets.len();
         let mut proc_start = Measure::start("consume_buffered_process");
         let mut reached_end_of_slot = None;
@@ -326,6 +404,9 @@ impl BankingStage {
                             original_unprocessed_indexes.to_owned(),
                             transaction_status_sender.clone(),
                             gossip_vote_sender,
+                            cost_tracker,
+                            &mut cost_tracker_rebuffered_count,
+                            execution_thread_pool,
                         );
                     if processed < verified_txs_len {
                         reached_end_of_slot =
@@ -374,6 +455,9 @@ impl BankingStage {
             stats
                 .consumed_buffered_packets_count
                 .fetch_add(new_tx_count, Ordering::Relaxed);
+            stats
+                .cost_tracker_rebuffered_count
+                .fetch_add(cost_tracker_rebuffered_count, Ordering::Relaxed);
         }
     }
 
@@ -421,6 +505,8 @@ impl BankingStage {
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: &ReplayVoteSender,
         banking_stage_stats: &BankingStageStats,
+        cost_tracker: &Arc<RwLock<CostTracker>>,
+        execution_thread_pool: Option<&ThreadPool>,
     ) -> BufferedPacketsDecision {
         let (leader_at_slot_offset, poh_has_bank, would_be_leader, would_be_leader_shortly) = {
             let poh = poh_recorder.lock().unwrap();
@@ -452,6 +538,8 @@ impl BankingStage {
                     gossip_vote_sender,
                     None::<Box<dyn Fn()>>,
                     Some(banking_stage_stats),
+                    cost_tracker,
+                    execution_thread_pool,
                 );
             }
             BufferedPacketsDecision::Forward => {
@@ -528,12 +616,15 @@ impl BankingStage {
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: ReplayVoteSender,
         duplicates: &Arc<Mutex<(LruCache<u64, ()>, PacketHasher)>>,
+        cost_tracker: &Arc<RwLock<CostTracker>>,
+        execution_thread_pool: Option<&ThreadPool>,
     ) {
         let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
         let mut buffered_packets = VecDeque::with_capacity(batch_limit);
         let banking_stage_stats = BankingStageStats::new(id);
         loop {
             while !buffered_packets.is_empty() {
+                let num_buffered_packets = Self::num_buffered_packets(&buffered_packets);
                 let decision = Self::process_buffered_packets(
                     &my_pubkey,
                     &socket,
@@ -544,6 +635,8 @@ impl BankingStage {
                     transaction_status_sender.clone(),
                     &gossip_vote_sender,
                     &banking_stage_stats,
+                    cost_tracker,
+                    execution_thread_pool,
                 );
                 if decision == BufferedPacketsDecision::Hold
                     || decision == BufferedPacketsDecision::ForwardAndHold
@@ -552,6 +645,14 @@ impl BankingStage {
                     // check the receiver for more transactions/for exiting
                     break;
                 }
+                if decision == BufferedPacketsDecision::Consume
+                    && Self::num_buffered_packets(&buffered_packets) == num_buffered_packets
+                {
+                    // Nothing left in the buffer fits in the current bank, e.g. all of it
+                    // write-locks accounts that reached their cost limit. Check the receiver
+                    // for transactions that may still fit instead of retrying the same ones.
+                    break;
+                }
             }
 
             let recv_timeout = if !buffered_packets.is_empty() {
@@ -577,6 +678,8 @@ impl BankingStage {
                 &mut buffered_packets,
                 &banking_stage_stats,
                 duplicates,
+                cost_tracker,
+                execution_thread_pool,
             ) {
                 Ok(()) | Err(RecvTimeoutError::Timeout) => (),
                 Err(RecvTimeoutError::Disconnected) => break,
@@ -586,6 +689,13 @@ impl BankingStage {
         }
     }
 
+    fn num_buffered_packets(buffered_packets: &UnprocessedPackets) -> usize {
+        buffered_packets
+            .iter()
+            .map(|(_packets, indexes, _forwarded)| indexes.len())
+            .sum()
+    }
+
     pub fn num_threads() -> u32 {
         const MIN_THREADS_VOTES: u32 = 1;
         const MIN_THREADS_BANKING: u32 = 1;
@@ -598,7 +708,7 @@ impl BankingStage {
     }
 
     /// Convert the transactions from a blob of binary data to a vector of transactions
-    fn deserialize_transactions(p: &Packets) -> Vec<Option<Transaction>> {
+    fn deserialize_transactions(p: &Packets) -> Vec<Option<VersionedTransaction>> {
         p.packets
             .iter()
             .map(|x| limited_deserialize(&x.data[0..x.meta.size]).ok())
@@ -608,7 +718,7 @@ impl BankingStage {
     #[allow(clippy::match_wild_err_arm)]
     fn record_transactions(
         bank_slot: Slot,
-        txs: &[Transaction],
+        txs: &[VersionedTransaction],
         results: &[TransactionExecutionResult],
         poh: &Arc<Mutex<PohRecorder>>,
     ) -> (Result<usize, PohRecorderError>, Vec<usize>) {
@@ -670,9 +780,11 @@ impl BankingStage {
         bank: &Arc<Bank>,
         poh: &Arc<Mutex<PohRecorder>>,
         batch: &TransactionBatch,
+        versioned_txs: &[VersionedTransaction],
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: &ReplayVoteSender,
-    ) -> (Result<usize, PohRecorderError>, Vec<usize>) {
+        execution_thread_pool: Option<&ThreadPool>,
+    ) -> (Result<usize, PohRecorderError>, Vec<usize>, Vec<usize>) {
         let mut load_execute_time = Measure::start("load_execute_time");
         // Use a shorter maximum age when adding transactions into the pipeline.  This will reduce
         // the likelihood of any single thread getting starved and processing old ids.
@@ -703,20 +815,32 @@ impl BankingStage {
             mut retryable_txs,
             tx_count,
             signature_count,
-        ) = bank.load_and_execute_transactions(
-            batch,
-            MAX_PROCESSING_AGE,
-            transaction_status_sender.is_some(),
-            transaction_status_sender.is_some(),
-            &mut execute_timings,
-        );
+        ) = if let Some(thread_pool) = execution_thread_pool {
+            // The batch holds the locks of all its transactions, so none of them conflict
+            bank.par_load_and_execute_transactions(
+                batch,
+                thread_pool,
+                MAX_PROCESSING_AGE,
+                transaction_status_sender.is_some(),
+                transaction_status_sender.is_some(),
+                &mut execute_timings,
+            )
+        } else {
+            bank.load_and_execute_transactions(
+                batch,
+                MAX_PROCESSING_AGE,
+                transaction_status_sender.is_some(),
+                transaction_status_sender.is_some(),
+                &mut execute_timings,
+            )
+        };
         load_execute_time.stop();
 
         let freeze_lock = bank.freeze_lock();
 
         let mut record_time = Measure::start("record_time");
         let (num_to_commit, retryable_record_txs) =
-            Self::record_transactions(bank.slot(), txs, &results, poh);
+            Self::record_transactions(bank.slot(), versioned_txs, &results, poh);
         inc_new_counter_info!(
             "banking_stage-record_transactions_num_to_commit",
             *num_to_commit.as_ref().unwrap_or(&0)
@@ -727,7 +851,8 @@ impl BankingStage {
         );
         retryable_txs.extend(retryable_record_txs);
         if num_to_commit.is_err() {
-            return (num_to_commit, retryable_txs);
+            // Nothing was recorded, so none of the transactions are committed
+            return (num_to_commit, retryable_txs, (0..txs.len()).collect());
         }
         record_time.stop();
 
@@ -781,31 +906,48 @@ impl BankingStage {
             execute_timings
         );
 
-        (Ok(num_to_commit), retryable_txs)
+        let uncommitted_txs = results
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (r, _h))| if Bank::can_commit(r) { None } else { Some(i) })
+            .collect();
+        (Ok(num_to_commit), retryable_txs, uncommitted_txs)
     }
 
+    /// Executes and records `txs`, the executable forms of `versioned_txs` as resolved by
+    /// `Bank::resolve_versioned_transaction()`.  The versioned transactions are the ones that
+    /// were signed, so they are what gets recorded into entries.
+    ///
+    /// Returns the number of transactions committed, the indexes of the transactions to retry
+    /// and the indexes of all the transactions which were not committed.
     pub fn process_and_record_transactions(
         bank: &Arc<Bank>,
         txs: &[Transaction],
+        versioned_txs: &[VersionedTransaction],
         poh: &Arc<Mutex<PohRecorder>>,
         chunk_offset: usize,
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: &ReplayVoteSender,
-    ) -> (Result<usize, PohRecorderError>, Vec<usize>) {
+        execution_thread_pool: Option<&ThreadPool>,
+    ) -> (Result<usize, PohRecorderError>, Vec<usize>, Vec<usize>) {
         let mut lock_time = Measure::start("lock_time");
         // Once accounts are locked, other threads cannot encode transactions that will modify the
         // same account state
         let batch = bank.prepare_batch(txs, None);
         lock_time.stop();
 
-        let (result, mut retryable_txs) = Self::process_and_record_transactions_locked(
-            bank,
-            poh,
-            &batch,
-            transaction_status_sender,
-            gossip_vote_sender,
-        );
+        let (result, mut retryable_txs, mut uncommitted_txs) =
+            Self::process_and_record_transactions_locked(
+                bank,
+                poh,
+                &batch,
+                versioned_txs,
+                transaction_status_sender,
+                gossip_vote_sender,
+                execution_thread_pool,
+            );
         retryable_txs.iter_mut().for_each(|x| *x += chunk_offset);
+        uncommitted_txs.iter_mut().for_each(|x| *x += chunk_offset);
 
         let mut unlock_time = Measure::start("unlock_time");
         // Once the accounts are new transactions can enter the pipeline to process them
@@ -820,41 +962,57 @@ impl BankingStage {
             txs.len(),
         );
 
-        (result, retryable_txs)
+        (result, retryable_txs, uncommitted_txs)
     }
 
     /// Sends transactions to the bank.
     ///
+    /// Scheduled transactions are in the order of the waves of their `TransactionSchedule`, and
+    /// `wave_ends` holds the index at which each wave ends. A chunk of them never spans waves, so
+    /// that its transactions don't conflict.
+    ///
     /// Returns the number of transactions successfully processed by the bank, which may be less
-    /// than the total number if max PoH height was reached and the bank halted
+    /// than the total number if max PoH height was reached and the bank halted, along with the
+    /// indexes of the transactions to retry and of the ones which were not committed
     fn process_transactions(
         bank: &Arc<Bank>,
         transactions: &[Transaction],
+        versioned_transactions: &[VersionedTransaction],
+        wave_ends: &[usize],
         poh: &Arc<Mutex<PohRecorder>>,
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: &ReplayVoteSender,
-    ) -> (usize, Vec<usize>) {
+        execution_thread_pool: Option<&ThreadPool>,
+    ) -> (usize, Vec<usize>, Vec<usize>) {
         let mut chunk_start = 0;
         let mut unprocessed_txs = vec![];
+        let mut uncommitted_txs = vec![];
         while chunk_start != transactions.len() {
-            let chunk_end = std::cmp::min(
+            let mut chunk_end = std::cmp::min(
                 transactions.len(),
                 chunk_start + MAX_NUM_TRANSACTIONS_PER_BATCH,
             );
+            if let Some(wave_end) = wave_ends.iter().find(|wave_end| **wave_end > chunk_start) {
+                chunk_end = chunk_end.min(*wave_end);
+            }
 
-            let (result, retryable_txs_in_chunk) = Self::process_and_record_transactions(
-                bank,
-                &transactions[chunk_start..chunk_end],
-                poh,
-                chunk_start,
-                transaction_status_sender.clone(),
-                gossip_vote_sender,
-            );
+            let (result, retryable_txs_in_chunk, uncommitted_txs_in_chunk) =
+                Self::process_and_record_transactions(
+                    bank,
+                    &transactions[chunk_start..chunk_end],
+                    &versioned_transactions[chunk_start..chunk_end],
+                    poh,
+                    chunk_start,
+                    transaction_status_sender.clone(),
+                    gossip_vote_sender,
+                    execution_thread_pool,
+                );
             trace!("process_transactions result: {:?}", result);
 
             // Add the retryable txs (transactions that errored in a way that warrants a retry)
             // to the list of unprocessed txs.
             unprocessed_txs.extend_from_slice(&retryable_txs_in_chunk);
+            uncommitted_txs.extend_from_slice(&uncommitted_txs_in_chunk);
             if let Err(PohRecorderError::MaxHeightReached) = result {
                 info!(
                     "process transactions: max height reached slot: {} height: {}",
@@ -865,21 +1023,22 @@ impl BankingStage {
                 // transactions[chunk_start..chunk_end], so we just need to push the remaining
                 // transactions into the unprocessed queue.
                 unprocessed_txs.extend(chunk_end..transactions.len());
+                uncommitted_txs.extend(chunk_end..transactions.len());
                 break;
             }
             // Don't exit early on any other type of error, continue processing...
             chunk_start = chunk_end;
         }
 
-        (chunk_start, unprocessed_txs)
+        (chunk_start, unprocessed_txs, uncommitted_txs)
     }
 
     // This function returns a vector of transactions that are not None. It also returns a vector
     // with position of the transaction in the input list
-    fn filter_transaction_indexes(
-        transactions: Vec<Option<Transaction>>,
+    fn filter_transaction_indexes<T>(
+        transactions: Vec<Option<T>>,
         indexes: &[usize],
-    ) -> (Vec<Transaction>, Vec<usize>) {
+    ) -> (Vec<T>, Vec<usize>) {
         transactions
             .into_iter()
             .zip(indexes)
@@ -916,12 +1075,13 @@ impl BankingStage {
             .collect()
     }
 
-    // This function deserializes packets into transactions and returns non-None transactions
+    // This function deserializes packets into transactions and returns non-None transactions,
+    // resolved against `bank`, along with the versioned transactions they were resolved from
     fn transactions_from_packets(
+        bank: &Bank,
         msgs: &Packets,
         transaction_indexes: &[usize],
-        secp256k1_program_enabled: bool,
-    ) -> (Vec<Transaction>, Vec<usize>) {
+    ) -> (Vec<Transaction>, Vec<VersionedTransaction>, Vec<usize>) {
         let packets = Packets::new(
             transaction_indexes
                 .iter()
@@ -929,27 +1089,50 @@ impl BankingStage {
                 .collect_vec(),
         );
 
-        let transactions = Self::deserialize_transactions(&packets);
-        let maybe_secp_verified_transactions: Vec<_> = if secp256k1_program_enabled {
-            transactions
-                .into_iter()
-                .map(|tx| {
-                    if let Some(tx) = tx {
-                        if tx.verify_precompiles().is_ok() {
-                            Some(tx)
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                })
-                .collect()
-        } else {
-            transactions
-        };
+        let secp256k1_program_enabled = bank.secp256k1_program_enabled();
+        let transactions: Vec<_> = Self::deserialize_transactions(&packets)
+            .into_iter()
+            .map(|tx| {
+                let tx = tx?;
+                if secp256k1_program_enabled && tx.verify_precompiles().is_err() {
+                    return None;
+                }
+                // Transactions whose address table lookups don't resolve can never execute
+                let resolved_tx = bank.resolve_versioned_transaction(tx.clone()).ok()?;
+                Some((resolved_tx, tx))
+            })
+            .collect();
+
+        let (transactions, transaction_to_packet_indexes) =
+            Self::filter_transaction_indexes(transactions, &transaction_indexes);
+        let mut transactions: Vec<_> = transactions
+            .into_iter()
+            .zip(transaction_to_packet_indexes)
+            .collect();
+        if bank.compute_budget_program_enabled() {
+            // Higher priced transactions go first so that they take their account locks before
+            // the transactions they conflict with.  The sort is stable, so transactions paying
+            // the same price keep their packet order
+            transactions.sort_by_key(|((transaction, _), _)| {
+                cmp::Reverse(Self::compute_unit_price(transaction))
+            });
+        }
+        let (transactions, transaction_to_packet_indexes): (Vec<_>, _) =
+            transactions.into_iter().unzip();
+        let (transactions, versioned_transactions) = transactions.into_iter().unzip();
+        (
+            transactions,
+            versioned_transactions,
+            transaction_to_packet_indexes,
+        )
+    }
 
-        Self::filter_transaction_indexes(maybe_secp_verified_transactions, &transaction_indexes)
+    /// The price per compute unit a transaction pays for prioritization, in micro-lamports
+    fn compute_unit_price(transaction: &Transaction) -> u64 {
+        ComputeBudgetRequest::from_message(&transaction.message)
+            .ok()
+            .and_then(|request| request.compute_unit_price)
+            .unwrap_or(0)
     }
 
     /// This function filters pending packets that are still valid
@@ -990,6 +1173,94 @@ impl BankingStage {
         Self::filter_valid_transaction_indexes(&result, transaction_to_packet_indexes)
     }
 
+    /// Reserves the cost of each transaction in the current bank, holding back the ones that
+    /// would exceed the block or account cost limits.
+    ///
+    /// Returns the transactions that fit along with their packet indexes and costs, and the
+    /// packet indexes of the transactions held back.
+    fn filter_transactions_by_cost(
+        bank: &Arc<Bank>,
+        transactions: Vec<Transaction>,
+        versioned_transactions: Vec<VersionedTransaction>,
+        transaction_to_packet_indexes: Vec<usize>,
+        cost_tracker: &Arc<RwLock<CostTracker>>,
+    ) -> (
+        Vec<Transaction>,
+        Vec<VersionedTransaction>,
+        Vec<usize>,
+        Vec<TransactionCost>,
+        Vec<usize>,
+    ) {
+        let mut cost_tracker = cost_tracker.write().unwrap();
+        cost_tracker.reset_if_new_bank(bank.slot());
+
+        let mut filtered_transactions = Vec::with_capacity(transactions.len());
+        let mut filtered_versioned_transactions = Vec::with_capacity(transactions.len());
+        let mut filtered_packet_indexes = Vec::with_capacity(transactions.len());
+        let mut transaction_costs = Vec::with_capacity(transactions.len());
+        let mut retryable_packet_indexes = vec![];
+        for ((tx, versioned_tx), packet_index) in transactions
+            .into_iter()
+            .zip(versioned_transactions)
+            .zip(transaction_to_packet_indexes)
+        {
+            let cost = cost_tracker.cost_model().calculate_cost(&tx);
+            match cost_tracker.try_add(&cost) {
+                Ok(_block_cost) => {
+                    filtered_transactions.push(tx);
+                    filtered_versioned_transactions.push(versioned_tx);
+                    filtered_packet_indexes.push(packet_index);
+                    transaction_costs.push(cost);
+                }
+                Err(err) => {
+                    let cost_model = cost_tracker.cost_model();
+                    if cost.sum()
+                        > cmp::min(
+                            cost_model.account_cost_limit(),
+                            cost_model.block_cost_limit(),
+                        )
+                    {
+                        // Wouldn't fit even in an empty bank, so don't bother retrying it
+                        inc_new_counter_info!("banking_stage-cost_tracker_dropped_txs", 1);
+                        continue;
+                    }
+                    debug!(
+                        "bank: {} holding back transaction {}: {}",
+                        bank.slot(),
+                        tx.signatures[0],
+                        err
+                    );
+                    retryable_packet_indexes.push(packet_index);
+                }
+            }
+        }
+        (
+            filtered_transactions,
+            filtered_versioned_transactions,
+            filtered_packet_indexes,
+            transaction_costs,
+            retryable_packet_indexes,
+        )
+    }
+
+    /// Gives back the reserved cost of the transactions which were not committed, whether they
+    /// will be retried or dropped
+    fn release_transaction_costs(
+        bank: &Arc<Bank>,
+        transaction_costs: &[TransactionCost],
+        uncommitted_tx_indexes: &[usize],
+        cost_tracker: &Arc<RwLock<CostTracker>>,
+    ) {
+        let mut cost_tracker = cost_tracker.write().unwrap();
+        // Costs reserved in an earlier bank have already been forgotten
+        if cost_tracker.current_bank_slot() == bank.slot() {
+            for index in uncommitted_tx_indexes {
+                cost_tracker.remove_transaction_cost(&transaction_costs[*index]);
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn process_received_packets(
         bank: &Arc<Bank>,
         poh: &Arc<Mutex<PohRecorder>>,
@@ -997,11 +1268,55 @@ impl BankingStage {
         packet_indexes: Vec<usize>,
         transaction_status_sender: Option<TransactionStatusSender>,
         gossip_vote_sender: &ReplayVoteSender,
+        cost_tracker: &Arc<RwLock<CostTracker>>,
+        cost_tracker_rebuffered_count: &mut usize,
+        execution_thread_pool: Option<&ThreadPool>,
     ) -> (usize, usize, Vec<usize>) {
-        let (transactions, transaction_to_packet_indexes) = Self::transactions_from_packets(
-            msgs,
-            &packet_indexes,
-            bank.secp256k1_program_enabled(),
+        let (transactions, versioned_transactions, transaction_to_packet_indexes) =
+            Self::transactions_from_packets(bank, msgs, &packet_indexes);
+        let (
+            transactions,
+            versioned_transactions,
+            transaction_to_packet_indexes,
+            transaction_costs,
+            retryable_indexes,
+        ) = Self::filter_transactions_by_cost(
+            bank,
+            transactions,
+            versioned_transactions,
+            transaction_to_packet_indexes,
+            cost_tracker,
+        );
+        let (
+            transactions,
+            versioned_transactions,
+            transaction_to_packet_indexes,
+            transaction_costs,
+            wave_ends,
+        ) = if execution_thread_pool.is_some() {
+            // Transactions which conflict are executed in later waves than the ones they
+            // conflict with, instead of being retried
+            let schedule = TransactionSchedule::new(&transactions);
+            (
+                schedule.reorder(transactions),
+                schedule.reorder(versioned_transactions),
+                schedule.reorder(transaction_to_packet_indexes),
+                schedule.reorder(transaction_costs),
+                schedule.wave_ends(),
+            )
+        } else {
+            (
+                transactions,
+                versioned_transactions,
+                transaction_to_packet_indexes,
+                transaction_costs,
+                vec![],
+            )
+        };
+        *cost_tracker_rebuffered_count += retryable_indexes.len();
+        inc_new_counter_info!(
+            "banking_stage-cost_tracker_rebuffered_txs",
+            retryable_indexes.len()
         );
         debug!(
             "bank: {} filtered transactions {}",
@@ -1011,17 +1326,27 @@ impl BankingStage {
 
         let tx_len = transactions.len();
 
-        let (processed, unprocessed_tx_indexes) = Self::process_transactions(
+        let (processed, unprocessed_tx_indexes, uncommitted_tx_indexes) =
+            Self::process_transactions(
+                bank,
+                &transactions,
+                &versioned_transactions,
+                &wave_ends,
+                poh,
+                transaction_status_sender,
+                gossip_vote_sender,
+                execution_thread_pool,
+            );
+        Self::release_transaction_costs(
             bank,
-            &transactions,
-            poh,
-            transaction_status_sender,
-            gossip_vote_sender,
+            &transaction_costs,
+            &uncommitted_tx_indexes,
+            cost_tracker,
         );
 
         let unprocessed_tx_count = unprocessed_tx_indexes.len();
 
-        let filtered_unprocessed_packet_indexes = Self::filter_pending_packets_from_pending_txs(
+        let mut filtered_unprocessed_packet_indexes = Self::filter_pending_packets_from_pending_txs(
             bank,
             &transactions,
             &transaction_to_packet_indexes,
@@ -1032,6 +1357,10 @@ impl BankingStage {
             unprocessed_tx_count.saturating_sub(filtered_unprocessed_packet_indexes.len())
         );
 
+        // Transactions held back by the cost tracker are retried in a later bank
+        filtered_unprocessed_packet_indexes.extend(retryable_indexes);
+        filtered_unprocessed_packet_indexes.sort_unstable();
+
         (processed, tx_len, filtered_unprocessed_packet_indexes)
     }
 
@@ -1051,16 +1380,13 @@ impl BankingStage {
             }
         }
 
-        let (transactions, transaction_to_packet_indexes) = Self::transactions_from_packets(
-            msgs,
-            &transaction_indexes,
-            bank.secp256k1_program_enabled(),
-        );
+        let (transactions, _versioned_transactions, transaction_to_packet_indexes) =
+            Self::transactions_from_packets(bank, msgs, &transaction_indexes);
 
         let tx_count = transaction_to_packet_indexes.len();
 
         let unprocessed_tx_indexes = (0..transactions.len()).collect_vec();
-        let filtered_unprocessed_packet_indexes = Self::filter_pending_packets_from_pending_txs(
+        let mut filtered_unprocessed_packet_indexes = Self::filter_pending_packets_from_pending_txs(
             bank,
             &transactions,
             &transaction_to_packet_indexes,
@@ -1072,6 +1398,7 @@ impl BankingStage {
             tx_count.saturating_sub(filtered_unprocessed_packet_indexes.len())
         );
 
+        filtered_unprocessed_packet_indexes.sort_unstable();
         filtered_unprocessed_packet_indexes
     }
 
@@ -1105,6 +1432,8 @@ impl BankingStage {
         buffered_packets: &mut UnprocessedPackets,
         banking_stage_stats: &BankingStageStats,
         duplicates: &Arc<Mutex<(LruCache<u64, ()>, PacketHasher)>>,
+        cost_tracker: &Arc<RwLock<CostTracker>>,
+        execution_thread_pool: Option<&ThreadPool>,
     ) -> Result<(), RecvTimeoutError> {
         let mut recv_time = Measure::start("process_packets_recv");
         let mms = verified_receiver.recv_timeout(recv_timeout)?;
@@ -1126,6 +1455,7 @@ impl BankingStage {
         let mut mms_iter = mms.into_iter();
         let mut dropped_batches_count = 0;
         let mut newly_buffered_packets_count = 0;
+        let mut cost_tracker_rebuffered_count = 0;
         while let Some(msgs) = mms_iter.next() {
             let packet_indexes = Self::generate_packet_indexes(&msgs.packets);
             let bank = poh.lock().unwrap().bank();
@@ -1150,6 +1480,9 @@ impl BankingStage {
                 packet_indexes,
                 transaction_status_sender.clone(),
                 gossip_vote_sender,
+                cost_tracker,
+                &mut cost_tracker_rebuffered_count,
+                execution_thread_pool,
             );
 
             new_tx_count += processed;
@@ -1216,6 +1549,9 @@ impl BankingStage {
         banking_stage_stats
             .newly_buffered_packets_count
             .fetch_add(newly_buffered_packets_count, Ordering::Relaxed);
+        banking_stage_stats
+            .cost_tracker_rebuffered_count
+            .fetch_add(cost_tracker_rebuffered_count, Ordering::Relaxed);
         banking_stage_stats
             .current_buffered_packets_count
             .swap(buffered_packets.len(), Ordering::Relaxed);
@@ -1323,9 +1659,11 @@ mod tests {
     };
     use solana_perf::packet::to_packets_chunked;
     use solana_sdk::{
+        compute_budget,
         instruction::InstructionError,
+        message::{v0, VersionedMessage},
         signature::{Keypair, Signer},
-        system_instruction::SystemError,
+        system_instruction::{self, SystemError},
         system_transaction,
         transaction::TransactionError,
     };
@@ -1529,7 +1867,7 @@ mod tests {
                 if !entries.is_empty() {
                     blockhash = entries.last().unwrap().hash;
                     for entry in entries {
-                        bank.process_transactions(&entry.transactions)
+                        bank.process_entry_transactions(entry.transactions)
                             .iter()
                             .for_each(|x| assert_eq!(*x, Ok(())));
                     }
@@ -1618,6 +1956,8 @@ mod tests {
                     2,
                     None,
                     gossip_vote_sender,
+                    CostModel::default(),
+                    None,
                 );
 
                 // wait for banking_stage to eat the packets
@@ -1639,8 +1979,8 @@ mod tests {
                 .collect();
 
             let bank = Bank::new(&genesis_config);
-            for entry in &entries {
-                bank.process_transactions(&entry.transactions)
+            for entry in entries {
+                bank.process_entry_transactions(entry.transactions)
                     .iter()
                     .for_each(|x| assert_eq!(*x, Ok(())));
             }
@@ -1688,9 +2028,10 @@ mod tests {
             let keypair2 = Keypair::new();
             let pubkey2 = solana_sdk::pubkey::new_rand();
 
-            let transactions = vec![
-                system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash()),
-                system_transaction::transfer(&keypair2, &pubkey2, 1, genesis_config.hash()),
+            let transactions: Vec<VersionedTransaction> = vec![
+                system_transaction::transfer(&mint_keypair, &pubkey, 1, genesis_config.hash())
+                    .into(),
+                system_transaction::transfer(&keypair2, &pubkey2, 1, genesis_config.hash()).into(),
             ];
 
             let mut results = vec![(Ok(()), None), (Ok(()), None)];
@@ -2038,10 +2379,12 @@ mod tests {
             BankingStage::process_and_record_transactions(
                 &bank,
                 &transactions,
+                &to_versioned_transactions(&transactions),
                 &poh_recorder,
                 0,
                 None,
                 &gossip_vote_sender,
+                None,
             )
             .0
             .unwrap();
@@ -2075,10 +2418,12 @@ mod tests {
                 BankingStage::process_and_record_transactions(
                     &bank,
                     &transactions,
+                    &to_versioned_transactions(&transactions),
                     &poh_recorder,
                     0,
                     None,
                     &gossip_vote_sender,
+                    None,
                 )
                 .0,
                 Err(PohRecorderError::MaxHeightReached)
@@ -2089,6 +2434,176 @@ mod tests {
         Blockstore::destroy(&ledger_path).unwrap();
     }
 
+    #[test]
+    fn test_bank_process_and_record_transactions_parallel_execution() {
+        solana_logger::setup();
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(10_000);
+        let bank = Arc::new(Bank::new(&genesis_config));
+        let pubkey = solana_sdk::pubkey::new_rand();
+
+        // Fund enough payers that their transfers are split into several chunks
+        let payers: Vec<_> = (0..16).map(|_| Keypair::new()).collect();
+        for payer in &payers {
+            bank.transfer(100, &mint_keypair, &payer.pubkey()).unwrap();
+        }
+        let transactions: Vec<_> = payers
+            .iter()
+            .map(|payer| system_transaction::transfer(payer, &pubkey, 1, genesis_config.hash()))
+            .collect();
+
+        let working_bank = WorkingBank {
+            bank: bank.clone(),
+            min_tick_height: bank.tick_height(),
+            max_tick_height: bank.tick_height() + 1,
+        };
+        let ledger_path = get_tmp_ledger_path!();
+        {
+            let blockstore = Blockstore::open(&ledger_path)
+                .expect("Expected to be able to open database ledger");
+            let (poh_recorder, entry_receiver) = PohRecorder::new(
+                bank.tick_height(),
+                bank.last_blockhash(),
+                bank.slot(),
+                Some((4, 4)),
+                bank.ticks_per_slot(),
+                &pubkey,
+                &Arc::new(blockstore),
+                &Arc::new(LeaderScheduleCache::new_from_bank(&bank)),
+                &Arc::new(PohConfig::default()),
+            );
+            let poh_recorder = Arc::new(Mutex::new(poh_recorder));
+
+            poh_recorder.lock().unwrap().set_working_bank(working_bank);
+            let (gossip_vote_sender, _gossip_vote_receiver) = unbounded();
+            let thread_pool = ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+
+            let (result, retryable_txs, _uncommitted_txs) =
+                BankingStage::process_and_record_transactions(
+                    &bank,
+                    &transactions,
+                    &to_versioned_transactions(&transactions),
+                    &poh_recorder,
+                    0,
+                    None,
+                    &gossip_vote_sender,
+                    Some(&thread_pool),
+                );
+            assert_eq!(result.unwrap(), transactions.len());
+            assert!(retryable_txs.is_empty());
+            poh_recorder.lock().unwrap().tick();
+
+            // The transactions are recorded in their original order
+            let entry = entry_receiver
+                .iter()
+                .map(|(_bank, (entry, _tick_height))| entry)
+                .find(|entry| !entry.is_tick())
+                .unwrap();
+            assert_eq!(entry.transactions, to_versioned_transactions(&transactions));
+            assert_eq!(bank.get_balance(&pubkey), transactions.len() as u64);
+            for payer in &payers {
+                assert_eq!(bank.get_balance(&payer.pubkey()), 99);
+            }
+        }
+        Blockstore::destroy(&ledger_path).unwrap();
+    }
+
+    #[test]
+    fn test_process_transactions_scheduled() {
+        solana_logger::setup();
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(10_000);
+        let bank = Arc::new(Bank::new(&genesis_config));
+        let payer = Keypair::new();
+        bank.transfer(100, &mint_keypair, &payer.pubkey()).unwrap();
+
+        // The transfers of each payer conflict with each other, but not with the other payer's
+        let transactions: Vec<_> = (1..=4)
+            .flat_map(|lamports| {
+                vec![
+                    system_transaction::transfer(
+                        &mint_keypair,
+                        &solana_sdk::pubkey::new_rand(),
+                        lamports,
+                        genesis_config.hash(),
+                    ),
+                    system_transaction::transfer(
+                        &payer,
+                        &solana_sdk::pubkey::new_rand(),
+                        lamports,
+                        genesis_config.hash(),
+                    ),
+                ]
+            })
+            .collect();
+        let schedule = TransactionSchedule::new(&transactions);
+        assert_eq!(schedule.wave_ends(), vec![2, 4, 6, 8]);
+        let transactions = schedule.reorder(transactions);
+
+        let working_bank = WorkingBank {
+            bank: bank.clone(),
+            min_tick_height: bank.tick_height(),
+            max_tick_height: bank.tick_height() + 1,
+        };
+        let ledger_path = get_tmp_ledger_path!();
+        {
+            let blockstore = Blockstore::open(&ledger_path)
+                .expect("Expected to be able to open database ledger");
+            let (poh_recorder, _entry_receiver) = PohRecorder::new(
+                bank.tick_height(),
+                bank.last_blockhash(),
+                bank.slot(),
+                Some((4, 4)),
+                bank.ticks_per_slot(),
+                &solana_sdk::pubkey::new_rand(),
+                &Arc::new(blockstore),
+                &Arc::new(LeaderScheduleCache::new_from_bank(&bank)),
+                &Arc::new(PohConfig::default()),
+            );
+            let poh_recorder = Arc::new(Mutex::new(poh_recorder));
+            poh_recorder.lock().unwrap().set_working_bank(working_bank);
+            let (gossip_vote_sender, _gossip_vote_receiver) = unbounded();
+            let thread_pool = ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+
+            // Without the waves, the transactions which conflict with earlier ones are retried
+            let (processed_transactions_count, retryable_txs, _uncommitted_txs) =
+                BankingStage::process_transactions(
+                    &bank,
+                    &transactions[..4],
+                    &to_versioned_transactions(&transactions[..4]),
+                    &[],
+                    &poh_recorder,
+                    None,
+                    &gossip_vote_sender,
+                    Some(&thread_pool),
+                );
+            assert_eq!(processed_transactions_count, 4);
+            assert_eq!(retryable_txs, vec![2, 3]);
+
+            let (processed_transactions_count, retryable_txs, _uncommitted_txs) =
+                BankingStage::process_transactions(
+                    &bank,
+                    &transactions[2..],
+                    &to_versioned_transactions(&transactions[2..]),
+                    &[2, 4, 6],
+                    &poh_recorder,
+                    None,
+                    &gossip_vote_sender,
+                    Some(&thread_pool),
+                );
+            assert_eq!(processed_transactions_count, 6);
+            assert!(retryable_txs.is_empty());
+            assert_eq!(bank.get_balance(&payer.pubkey()), 90);
+        }
+        Blockstore::destroy(&ledger_path).unwrap();
+    }
+
     #[test]
     fn test_bank_process_and_record_transactions_account_in_use() {
         solana_logger::setup();
@@ -2132,13 +2647,15 @@ mod tests {
 
             let (gossip_vote_sender, _gossip_vote_receiver) = unbounded();
 
-            let (result, unprocessed) = BankingStage::process_and_record_transactions(
+            let (result, unprocessed, _uncommitted) = BankingStage::process_and_record_transactions(
                 &bank,
                 &transactions,
+                &to_versioned_transactions(&transactions),
                 &poh_recorder,
                 0,
                 None,
                 &gossip_vote_sender,
+                None,
             );
 
             assert!(result.is_ok());
@@ -2228,13 +2745,16 @@ mod tests {
 
             let (gossip_vote_sender, _gossip_vote_receiver) = unbounded();
 
-            let (processed_transactions_count, mut retryable_txs) =
+            let (processed_transactions_count, mut retryable_txs, _uncommitted_txs) =
                 BankingStage::process_transactions(
                     &bank,
                     &transactions,
+                    &to_versioned_transactions(&transactions),
+                    &[],
                     &poh_recorder,
                     None,
                     &gossip_vote_sender,
+                    None,
                 );
 
             assert_eq!(processed_transactions_count, 0,);
@@ -2308,6 +2828,8 @@ mod tests {
             let (transaction_status_sender, transaction_status_receiver) = unbounded();
             let transaction_status_service = TransactionStatusService::new(
                 transaction_status_receiver,
+                true,
+                None,
                 blockstore.clone(),
                 &Arc::new(AtomicBool::new(false)),
             );
@@ -2317,6 +2839,7 @@ mod tests {
             let _ = BankingStage::process_and_record_transactions(
                 &bank,
                 &transactions,
+                &to_versioned_transactions(&transactions),
                 &poh_recorder,
                 0,
                 Some(TransactionStatusSender {
@@ -2324,6 +2847,7 @@ mod tests {
                     enable_cpi_and_log_storage: false,
                 }),
                 &gossip_vote_sender,
+                None,
             );
 
             transaction_status_service.join().unwrap();
@@ -2354,6 +2878,14 @@ mod tests {
         Blockstore::destroy(&ledger_path).unwrap();
     }
 
+    fn to_versioned_transactions(transactions: &[Transaction]) -> Vec<VersionedTransaction> {
+        transactions
+            .iter()
+            .cloned()
+            .map(VersionedTransaction::from)
+            .collect()
+    }
+
     fn setup_conflicting_transactions(
         ledger_path: &Path,
     ) -> (
@@ -2417,6 +2949,7 @@ mod tests {
             .collect();
 
             let (gossip_vote_sender, _gossip_vote_receiver) = unbounded();
+            let cost_tracker = Arc::new(RwLock::new(CostTracker::default()));
 
             // When the working bank in poh_recorder is None, no packets should be processed
             assert!(!poh_recorder.lock().unwrap().has_bank());
@@ -2428,6 +2961,8 @@ mod tests {
                 &gossip_vote_sender,
                 None::<Box<dyn Fn()>>,
                 None,
+                &cost_tracker,
+                None,
             );
             assert_eq!(buffered_packets[0].1.len(), num_conflicting_transactions);
             // When the poh recorder has a bank, should process all non conflicting buffered packets.
@@ -2442,6 +2977,8 @@ mod tests {
                     &gossip_vote_sender,
                     None::<Box<dyn Fn()>>,
                     None,
+                    &cost_tracker,
+                    None,
                 );
                 if num_expected_unprocessed == 0 {
                     assert!(buffered_packets.is_empty())
@@ -2453,6 +2990,224 @@ mod tests {
         Blockstore::destroy(&ledger_path).unwrap();
     }
 
+    #[test]
+    fn test_consume_buffered_versioned_packets() {
+        let ledger_path = get_tmp_ledger_path!();
+        {
+            let GenesisConfigInfo {
+                genesis_config,
+                mint_keypair,
+                ..
+            } = create_genesis_config(10_000);
+            let bank = Arc::new(Bank::new(&genesis_config));
+            let blockstore = Blockstore::open(&ledger_path)
+                .expect("Expected to be able to open database ledger");
+            let (poh_recorder, entry_receiver) = PohRecorder::new(
+                bank.tick_height(),
+                bank.last_blockhash(),
+                bank.slot(),
+                Some((4, 4)),
+                bank.ticks_per_slot(),
+                &solana_sdk::pubkey::new_rand(),
+                &Arc::new(blockstore),
+                &Arc::new(LeaderScheduleCache::new_from_bank(&bank)),
+                &Arc::new(PohConfig::default()),
+            );
+            let poh_recorder = Arc::new(Mutex::new(poh_recorder));
+
+            let pubkey = solana_sdk::pubkey::new_rand();
+            let message = v0::Message::try_compile(
+                &mint_keypair.pubkey(),
+                &[system_instruction::transfer(
+                    &mint_keypair.pubkey(),
+                    &pubkey,
+                    1,
+                )],
+                &[],
+                genesis_config.hash(),
+            )
+            .unwrap();
+            let transaction =
+                VersionedTransaction::try_new(VersionedMessage::V0(message), &[&mint_keypair])
+                    .unwrap();
+            let mut packets_vec = to_packets_chunked(&[transaction.clone()], 1);
+            let mut buffered_packets: UnprocessedPackets =
+                vec![(packets_vec.pop().unwrap(), vec![0], false)]
+                    .into_iter()
+                    .collect();
+
+            let (gossip_vote_sender, _gossip_vote_receiver) = unbounded();
+            let cost_tracker = Arc::new(RwLock::new(CostTracker::default()));
+            poh_recorder.lock().unwrap().set_bank(&bank);
+            BankingStage::consume_buffered_packets(
+                &Pubkey::default(),
+                &poh_recorder,
+                &mut buffered_packets,
+                None,
+                &gossip_vote_sender,
+                None::<Box<dyn Fn()>>,
+                None,
+                &cost_tracker,
+                None,
+            );
+            assert!(buffered_packets.is_empty());
+            assert_eq!(bank.get_balance(&pubkey), 1);
+
+            // The signed versioned transaction is recorded, not its resolved form
+            let entry = entry_receiver
+                .try_iter()
+                .map(|(_bank, (entry, _tick_height))| entry)
+                .find(|entry| !entry.is_tick())
+                .unwrap();
+            assert_eq!(entry.transactions, vec![transaction]);
+        }
+        Blockstore::destroy(&ledger_path).unwrap();
+    }
+
+    #[test]
+    fn test_transactions_from_packets_compute_unit_price_order() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(10_000);
+        let bank = Bank::new(&genesis_config);
+        assert!(bank.compute_budget_program_enabled());
+
+        let transfer = |compute_unit_price: Option<u64>| {
+            let mut instructions = vec![];
+            if let Some(micro_lamports) = compute_unit_price {
+                instructions.push(compute_budget::set_compute_unit_price(micro_lamports));
+            }
+            instructions.push(system_instruction::transfer(
+                &mint_keypair.pubkey(),
+                &solana_sdk::pubkey::new_rand(),
+                1,
+            ));
+            Transaction::new_signed_with_payer(
+                &instructions,
+                Some(&mint_keypair.pubkey()),
+                &[&mint_keypair],
+                genesis_config.hash(),
+            )
+        };
+        let transactions = vec![
+            transfer(None),
+            transfer(Some(1)),
+            transfer(Some(10)),
+            transfer(Some(1)),
+        ];
+        let packets = to_packets_chunked(&transactions, transactions.len())
+            .pop()
+            .unwrap();
+
+        // Highest price first, ties keep their packet order
+        let (ordered_transactions, _versioned_transactions, transaction_to_packet_indexes) =
+            BankingStage::transactions_from_packets(&bank, &packets, &[0, 1, 2, 3]);
+        assert_eq!(transaction_to_packet_indexes, vec![2, 1, 3, 0]);
+        assert_eq!(
+            ordered_transactions,
+            transaction_to_packet_indexes
+                .iter()
+                .map(|index| transactions[*index].clone())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_consume_buffered_packets_with_cost_limit() {
+        let ledger_path = get_tmp_ledger_path!();
+        {
+            let (transactions, bank, poh_recorder, _entry_receiver) =
+                setup_conflicting_transactions(&ledger_path);
+            let num_transactions = transactions.len();
+            let mut packets_vec = to_packets_chunked(&transactions, num_transactions);
+            let mut buffered_packets: UnprocessedPackets = vec![(
+                packets_vec.pop().unwrap(),
+                (0..num_transactions).into_iter().collect(),
+                false,
+            )]
+            .into_iter()
+            .collect();
+
+            // All transactions write-lock the mint, which can only afford one of them per bank
+            let transaction_cost = CostModel::default().calculate_cost(&transactions[0]).sum();
+            let cost_tracker = Arc::new(RwLock::new(CostTracker::new(CostModel::new(
+                transaction_cost,
+                u64::MAX,
+            ))));
+            let (gossip_vote_sender, _gossip_vote_receiver) = unbounded();
+            poh_recorder.lock().unwrap().set_bank(&bank);
+
+            let consume = |buffered_packets: &mut UnprocessedPackets| {
+                BankingStage::consume_buffered_packets(
+                    &Pubkey::default(),
+                    &poh_recorder,
+                    buffered_packets,
+                    None,
+                    &gossip_vote_sender,
+                    None::<Box<dyn Fn()>>,
+                    None,
+                    &cost_tracker,
+                    None,
+                );
+            };
+            consume(&mut buffered_packets);
+            assert_eq!(buffered_packets[0].1.len(), num_transactions - 1);
+            assert_eq!(cost_tracker.read().unwrap().block_cost(), transaction_cost);
+
+            // The mint is at its limit, so the rest are held back for the next bank
+            consume(&mut buffered_packets);
+            assert_eq!(buffered_packets[0].1.len(), num_transactions - 1);
+            assert_eq!(cost_tracker.read().unwrap().block_cost(), transaction_cost);
+
+            // Costs tracked for an earlier bank don't count against the current one
+            cost_tracker
+                .write()
+                .unwrap()
+                .reset_if_new_bank(bank.slot() + 1);
+            consume(&mut buffered_packets);
+            assert_eq!(buffered_packets[0].1.len(), num_transactions - 2);
+        }
+        Blockstore::destroy(&ledger_path).unwrap();
+    }
+
+    #[test]
+    fn test_consume_buffered_packets_releases_uncommitted_cost() {
+        let ledger_path = get_tmp_ledger_path!();
+        {
+            let (transactions, bank, poh_recorder, _entry_receiver) =
+                setup_conflicting_transactions(&ledger_path);
+            // The transaction is already in the status cache, so it won't be committed again
+            bank.process_transaction(&transactions[0]).unwrap();
+            let mut packets_vec = to_packets_chunked(&transactions[..1], 1);
+            let mut buffered_packets: UnprocessedPackets =
+                vec![(packets_vec.pop().unwrap(), vec![0], false)]
+                    .into_iter()
+                    .collect();
+
+            let cost_tracker = Arc::new(RwLock::new(CostTracker::default()));
+            let (gossip_vote_sender, _gossip_vote_receiver) = unbounded();
+            poh_recorder.lock().unwrap().set_bank(&bank);
+            BankingStage::consume_buffered_packets(
+                &Pubkey::default(),
+                &poh_recorder,
+                &mut buffered_packets,
+                None,
+                &gossip_vote_sender,
+                None::<Box<dyn Fn()>>,
+                None,
+                &cost_tracker,
+                None,
+            );
+
+            // The AlreadyProcessed transaction is dropped, and so is its cost
+            assert!(buffered_packets.is_empty());
+            assert_eq!(cost_tracker.read().unwrap().block_cost(), 0);
+        }
+        Blockstore::destroy(&ledger_path).unwrap();
+    }
+
     #[test]
     fn test_consume_buffered_packets_interrupted() {
         let ledger_path = get_tmp_ledger_path!();
@@ -2486,6 +3241,7 @@ mod tests {
             poh_recorder.lock().unwrap().set_bank(&bank);
             let poh_recorder_ = poh_recorder.clone();
             let (gossip_vote_sender, _gossip_vote_receiver) = unbounded();
+            let cost_tracker = Arc::new(RwLock::new(CostTracker::default()));
             // Start up thread to process the banks
             let t_consume = Builder::new()
                 .name("consume-buffered-packets".to_string())
@@ -2498,6 +3254,8 @@ mod tests {
                         &gossip_vote_sender,
                         test_fn,
                         None,
+                        &cost_tracker,
+                        None,
                     );
 
                     // Check everything is correct. All indexes after `interrupted_iteration`
@@ -9,7 +9,7 @@ use solana_ledger::{
     entry::Entry,
     shred::{ProcessShredsStats, Shred, Shredder, RECOMMENDED_FEC_RATE, SHRED_TICK_REFERENCE_MASK},
 };
-use solana_sdk::{pubkey::Pubkey, signature::Keypair, timing::duration_as_us};
+use solana_sdk::{feature_set, pubkey::Pubkey, signature::Keypair, timing::duration_as_us};
 use std::collections::HashMap;
 use std::sync::RwLock;
 use std::time::Duration;
@@ -130,6 +130,29 @@ impl StandardBroadcastRun {
 
         data_shreds
     }
+    fn entries_to_merkle_shreds(
+        &mut self,
+        shredder: &Shredder,
+        next_shred_index: u32,
+        entries: &[Entry],
+        is_slot_end: bool,
+        process_stats: &mut ProcessShredsStats,
+    ) -> (Vec<Shred>, Vec<Shred>) {
+        let (data_shreds, coding_shreds, new_next_shred_index) = shredder.entries_to_merkle_shreds(
+            entries,
+            is_slot_end,
+            next_shred_index,
+            process_stats,
+        );
+
+        self.unfinished_slot = Some(UnfinishedSlotInfo {
+            next_shred_index: new_next_shred_index,
+            slot: shredder.slot,
+            parent: shredder.parent_slot,
+        });
+
+        (data_shreds, coding_shreds)
+    }
 
     #[cfg(test)]
     fn test_process_receive_results(
@@ -193,13 +216,30 @@ impl StandardBroadcastRun {
             (bank.tick_height() % bank.ticks_per_slot()) as u8,
         );
         let is_last_in_slot = last_tick_height == bank.max_tick_height();
-        let data_shreds = self.entries_to_data_shreds(
-            &shredder,
-            next_shred_index,
-            &receive_results.entries,
-            is_last_in_slot,
-            &mut process_stats,
-        );
+        // Coding shreds of Merkle FEC sets are part of the signed tree, so they are generated
+        // along with the data shreds
+        let (data_shreds, merkle_coding_shreds) = if bank
+            .feature_set
+            .is_active(&feature_set::merkle_shreds::id())
+        {
+            let (data_shreds, coding_shreds) = self.entries_to_merkle_shreds(
+                &shredder,
+                next_shred_index,
+                &receive_results.entries,
+                is_last_in_slot,
+                &mut process_stats,
+            );
+            (data_shreds, Some(coding_shreds))
+        } else {
+            let data_shreds = self.entries_to_data_shreds(
+                &shredder,
+                next_shred_index,
+                &receive_results.entries,
+                is_last_in_slot,
+                &mut process_stats,
+            );
+            (data_shreds, None)
+        };
         // Insert the first shred so blockstore stores that the leader started this block
         // This must be done before the blocks are sent out over the wire.
         if !data_shreds.is_empty() && data_shreds[0].index() == 0 {
@@ -258,8 +298,10 @@ impl StandardBroadcastRun {
         blockstore_sender.send((data_shreds.clone(), batch_info.clone()))?;
 
         // Create and send coding shreds
-        let coding_shreds = shredder
-            .data_shreds_to_coding_shreds(&data_shreds[0..last_data_shred], &mut process_stats);
+        let coding_shreds = merkle_coding_shreds.unwrap_or_else(|| {
+            shredder
+                .data_shreds_to_coding_shreds(&data_shreds[0..last_data_shred], &mut process_stats)
+        });
         let coding_shreds = Arc::new(coding_shreds);
         socket_sender.send(((stakes, coding_shreds.clone()), batch_info.clone()))?;
         blockstore_sender.send((coding_shreds, batch_info))?;
@@ -455,6 +497,7 @@ mod test {
         shred::max_ticks_per_n_shreds,
     };
     use solana_runtime::bank::Bank;
+    use solana_runtime::genesis_utils::activate_all_features;
     use solana_sdk::{
         genesis_config::GenesisConfig,
         signature::{Keypair, Signer},
@@ -484,6 +527,10 @@ mod test {
         let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
         let mut genesis_config = create_genesis_config(10_000).genesis_config;
         genesis_config.ticks_per_slot = max_ticks_per_n_shreds(num_shreds_per_slot, None) + 1;
+        // The shred counts below assume legacy shreds
+        genesis_config
+            .accounts
+            .remove(&feature_set::merkle_shreds::id());
         let bank0 = Arc::new(Bank::new(&genesis_config));
         (
             blockstore,
@@ -664,4 +711,37 @@ mod test {
             .unwrap();
         assert!(standard_broadcast_run.unfinished_slot.is_none())
     }
+
+    #[test]
+    fn test_slot_finish_merkle_shreds() {
+        let (blockstore, mut genesis_config, cluster_info, _bank0, leader_keypair, socket) =
+            setup(2);
+        activate_all_features(&mut genesis_config);
+        let bank0 = Arc::new(Bank::new(&genesis_config));
+        assert!(bank0
+            .feature_set
+            .is_active(&feature_set::merkle_shreds::id()));
+
+        let ticks = create_ticks(genesis_config.ticks_per_slot, 0, genesis_config.hash());
+        let receive_results = ReceiveResults {
+            entries: ticks.clone(),
+            time_elapsed: Duration::new(3, 0),
+            bank: bank0,
+            last_tick_height: ticks.len() as u64,
+        };
+
+        let mut standard_broadcast_run = StandardBroadcastRun::new(leader_keypair.clone(), 0);
+        standard_broadcast_run
+            .test_process_receive_results(&cluster_info, &socket, &blockstore, receive_results)
+            .unwrap();
+        assert!(standard_broadcast_run.unfinished_slot.is_none());
+        assert!(blockstore.is_full(0));
+        assert_eq!(blockstore.get_slot_entries(0, 0).unwrap(), ticks);
+        let shreds = blockstore.get_data_shreds_for_slot(0, 0).unwrap();
+        assert!(!shreds.is_empty());
+        for shred in shreds {
+            assert!(shred.is_merkle());
+            assert!(shred.verify(&leader_keypair.pubkey()));
+        }
+    }
 }
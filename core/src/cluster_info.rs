@@ -18,8 +18,8 @@ use crate::{
     crds_gossip_error::CrdsGossipError,
     crds_gossip_pull::{CrdsFilter, ProcessPullStats, CRDS_GOSSIP_PULL_CRDS_TIMEOUT_MS},
     crds_value::{
-        self, CrdsData, CrdsValue, CrdsValueLabel, EpochSlotsIndex, LowestSlot, NodeInstance,
-        SnapshotHash, Version, Vote, MAX_WALLCLOCK,
+        self, CrdsData, CrdsDataKinds, CrdsValue, CrdsValueLabel, EpochSlotsIndex, LowestSlot,
+        NodeInstance, SnapshotHash, Version, Vote, MAX_WALLCLOCK,
     },
     data_budget::DataBudget,
     epoch_slots::EpochSlots,
@@ -43,7 +43,7 @@ use solana_measure::measure::Measure;
 use solana_measure::thread_mem_usage;
 use solana_metrics::{inc_new_counter_debug, inc_new_counter_error};
 use solana_net_utils::{
-    bind_common, bind_common_in_range, bind_in_range, find_available_port_in_range,
+    bind_common, bind_common_in_range, bind_in_range, bind_to, find_available_port_in_range,
     multi_bind_in_range, PortRange,
 };
 use solana_perf::packet::{
@@ -61,6 +61,7 @@ use solana_sdk::{
     timing::timestamp,
     transaction::Transaction,
 };
+use solana_streamer::quic::QUIC_PORT_OFFSET;
 use solana_streamer::sendmmsg::multicast;
 use solana_streamer::streamer::{PacketReceiver, PacketSender};
 use solana_vote_program::vote_state::MAX_LOCKOUT_HISTORY;
@@ -299,14 +300,18 @@ struct GossipStats {
     epoch_slots_lookup: Counter,
     new_pull_requests: Counter,
     new_pull_requests_count: Counter,
+    new_pull_requests_with_kinds_count: Counter,
     mark_pull_request: Counter,
+    skip_pull_response_kinds: Counter,
     skip_pull_response_shred_version: Counter,
     skip_pull_shred_version: Counter,
     skip_push_message_shred_version: Counter,
     push_message_count: Counter,
     push_message_value_count: Counter,
     push_response_count: Counter,
+    push_active_set_evictions: Counter,
     pull_requests_count: Counter,
+    pull_requests_with_kinds_count: Counter,
 }
 
 pub struct ClusterInfo {
@@ -427,7 +432,7 @@ pub fn make_accounts_hashes_message(
 type Ping = ping_pong::Ping<[u8; GOSSIP_PING_TOKEN_SIZE]>;
 
 // TODO These messages should go through the gpu pipeline for spam filtering
-#[frozen_abi(digest = "CH5BWuhAyvUiUQYgu2Lcwu7eoiW6bQitvtLS1yFsdmrE")]
+#[frozen_abi(digest = "5YLcXXuaZib7Rk3yySdXKSgfCPyBCNsn7TBmgPue9yGH")]
 #[derive(Serialize, Deserialize, Debug, AbiEnumVisitor, AbiExample)]
 #[allow(clippy::large_enum_variant)]
 enum Protocol {
@@ -440,12 +445,15 @@ enum Protocol {
     PruneMessage(Pubkey, PruneData),
     PingMessage(Ping),
     PongMessage(Pong),
+    /// Pull request for only the given kinds of values
+    PullRequestWithKinds(CrdsFilter, CrdsValue, CrdsDataKinds),
 }
 
 impl Protocol {
     fn par_verify(self) -> Option<Self> {
         match self {
-            Protocol::PullRequest(_, ref caller) => {
+            Protocol::PullRequest(_, ref caller)
+            | Protocol::PullRequestWithKinds(_, ref caller, _) => {
                 if caller.verify() {
                     Some(self)
                 } else {
@@ -529,6 +537,11 @@ impl Sanitize for Protocol {
             }
             Protocol::PingMessage(ping) => ping.sanitize(),
             Protocol::PongMessage(pong) => pong.sanitize(),
+            Protocol::PullRequestWithKinds(filter, val, kinds) => {
+                filter.sanitize()?;
+                kinds.sanitize()?;
+                val.sanitize()
+            }
         }
     }
 }
@@ -662,10 +675,12 @@ impl ClusterInfo {
                 local_message_pending_push_queue.push((entry, now));
             }
         }
-        self.gossip
-            .write()
-            .unwrap()
-            .refresh_push_active_set(stakes, gossip_validators);
+        let mut gossip = self.gossip.write().unwrap();
+        let num_evicted = gossip.push.num_evicted;
+        gossip.refresh_push_active_set(stakes, gossip_validators);
+        self.stats
+            .push_active_set_evictions
+            .add_relaxed((gossip.push.num_evicted - num_evicted) as u64);
     }
 
     // TODO kill insert_info, only used by tests
@@ -682,6 +697,12 @@ impl ClusterInfo {
         *self.entrypoints.write().unwrap() = entrypoints;
     }
 
+    /// Only pull values of the given kinds, e.g. for nodes which only need
+    /// the contact infos and versions of the cluster
+    pub fn set_pull_request_kinds(&self, kinds: CrdsDataKinds) {
+        self.gossip.write().unwrap().pull.pull_request_kinds = kinds;
+    }
+
     pub fn save_contact_info(&self) {
         let nodes = {
             let gossip = self.gossip.read().unwrap();
@@ -1662,6 +1683,7 @@ impl ClusterInfo {
         thread_pool: &ThreadPool,
         gossip_validators: Option<&HashSet<Pubkey>>,
         stakes: &HashMap<Pubkey, u64>,
+        pull_request_with_kinds: bool,
     ) -> Vec<(SocketAddr, Protocol)> {
         let now = timestamp();
         let mut pulls: Vec<_> = {
@@ -1703,7 +1725,17 @@ impl ClusterInfo {
         pulls
             .into_iter()
             .map(|(_, filter, gossip, self_info)| {
-                (gossip, Protocol::PullRequest(filter, self_info))
+                // Nodes which don't know about PullRequestWithKinds drop it, so until the
+                // feature is active the filter goes out as a plain PullRequest and values of
+                // other kinds are dropped from the responses instead
+                let protocol = if filter.kinds.is_all() || !pull_request_with_kinds {
+                    Protocol::PullRequest(filter, self_info)
+                } else {
+                    self.stats.new_pull_requests_with_kinds_count.add_relaxed(1);
+                    let kinds = filter.kinds;
+                    Protocol::PullRequestWithKinds(filter, self_info, kinds)
+                };
+                (gossip, protocol)
             })
             .collect()
     }
@@ -1764,10 +1796,16 @@ impl ClusterInfo {
         stakes: &HashMap<Pubkey, u64>,
         generate_pull_requests: bool,
         require_stake_for_gossip: bool,
+        pull_request_with_kinds: bool,
     ) -> Vec<(SocketAddr, Protocol)> {
         self.trim_crds_table(CRDS_UNIQUE_PUBKEY_CAPACITY, &stakes);
         let mut pulls: Vec<_> = if generate_pull_requests {
-            self.new_pull_requests(&thread_pool, gossip_validators, stakes)
+            self.new_pull_requests(
+                &thread_pool,
+                gossip_validators,
+                stakes,
+                pull_request_with_kinds,
+            )
         } else {
             vec![]
         };
@@ -1792,6 +1830,7 @@ impl ClusterInfo {
         sender: &PacketSender,
         generate_pull_requests: bool,
         require_stake_for_gossip: bool,
+        pull_request_with_kinds: bool,
     ) -> Result<()> {
         let reqs = self.generate_new_gossip_requests(
             thread_pool,
@@ -1799,6 +1838,7 @@ impl ClusterInfo {
             stakes,
             generate_pull_requests,
             require_stake_for_gossip,
+            pull_request_with_kinds,
         );
         if !reqs.is_empty() {
             let packets = to_packets_with_destination(recycler.clone(), &reqs);
@@ -1979,6 +2019,11 @@ impl ClusterInfo {
                     };
                     let require_stake_for_gossip =
                         self.require_stake_for_gossip(feature_set.as_deref(), &stakes);
+                    let pull_request_with_kinds =
+                        feature_set.as_deref().map_or(false, |feature_set| {
+                            feature_set
+                                .is_active(&feature_set::gossip_pull_request_with_kinds::id())
+                        });
                     let _ = self.run_gossip(
                         &thread_pool,
                         gossip_validators.as_ref(),
@@ -1987,6 +2032,7 @@ impl ClusterInfo {
                         &sender,
                         generate_pull_requests,
                         require_stake_for_gossip,
+                        pull_request_with_kinds,
                     );
                     if exit.load(Ordering::Relaxed) {
                         return;
@@ -2388,6 +2434,12 @@ impl ClusterInfo {
             shred_version,
             self.my_shred_version(),
         );
+        let shred_version_len = crds_values.len();
+        // Responders which got a plain PullRequest don't know which kinds were asked for
+        let kinds = self.gossip.read().unwrap().pull.pull_request_kinds;
+        if !kinds.is_all() {
+            crds_values.retain(|value| kinds.contains(value.data.kind()));
+        }
         let filtered_len = crds_values.len();
 
         let mut pull_stats = ProcessPullStats::default();
@@ -2412,7 +2464,10 @@ impl ClusterInfo {
 
         self.stats
             .skip_pull_response_shred_version
-            .add_relaxed((len - filtered_len) as u64);
+            .add_relaxed((len - shred_version_len) as u64);
+        self.stats
+            .skip_pull_response_kinds
+            .add_relaxed((shred_version_len - filtered_len) as u64);
         self.stats.process_pull_response_count.add_relaxed(1);
         self.stats
             .process_pull_response_len
@@ -2749,6 +2804,11 @@ impl ClusterInfo {
                 Protocol::PullRequest(filter, caller) => {
                     pull_requests.push((from_addr, filter, caller))
                 }
+                Protocol::PullRequestWithKinds(mut filter, caller, kinds) => {
+                    self.stats.pull_requests_with_kinds_count.add_relaxed(1);
+                    filter.kinds = kinds;
+                    pull_requests.push((from_addr, filter, caller))
+                }
                 Protocol::PullResponse(from, data) => {
                     check_duplicate_instance(&data)?;
                     pull_responses.push((from, data));
@@ -3047,6 +3107,11 @@ impl ClusterInfo {
                     self.stats.skip_push_message_shred_version.clear(),
                     i64
                 ),
+                (
+                    "skip_pull_response_kinds",
+                    self.stats.skip_pull_response_kinds.clear(),
+                    i64
+                ),
                 (
                     "skip_pull_response_shred_version",
                     self.stats.skip_pull_response_shred_version.clear(),
@@ -3072,6 +3137,16 @@ impl ClusterInfo {
                     self.stats.new_pull_requests_count.clear(),
                     i64
                 ),
+                (
+                    "new_pull_requests_with_kinds_count",
+                    self.stats.new_pull_requests_with_kinds_count.clear(),
+                    i64
+                ),
+                (
+                    "push_active_set_evictions",
+                    self.stats.push_active_set_evictions.clear(),
+                    i64
+                ),
                 (
                     "prune_message_count",
                     self.stats.prune_message_count.clear(),
@@ -3090,6 +3165,11 @@ impl ClusterInfo {
                     self.stats.pull_requests_count.clear(),
                     i64
                 ),
+                (
+                    "pull_requests_with_kinds_count",
+                    self.stats.pull_requests_with_kinds_count.clear(),
+                    i64
+                ),
                 (
                     "packets_received_count",
                     self.stats.packets_received_count.clear(),
@@ -3311,6 +3391,7 @@ pub struct Sockets {
     pub tvu_forwards: Vec<UdpSocket>,
     pub tpu: Vec<UdpSocket>,
     pub tpu_forwards: Vec<UdpSocket>,
+    pub tpu_quic: UdpSocket,
     pub broadcast: Vec<UdpSocket>,
     pub repair: UdpSocket,
     pub retransmit_sockets: Vec<UdpSocket>,
@@ -3330,7 +3411,9 @@ impl Node {
     }
     pub fn new_localhost_with_pubkey(pubkey: &Pubkey) -> Self {
         let bind_ip_addr = IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0));
-        let tpu = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let (_, mut tpu, tpu_quic) =
+            Self::bind_tpu(IpAddr::V4(Ipv4Addr::LOCALHOST), (1024, 65535), 1);
+        let tpu = tpu.pop().unwrap();
         let (gossip_port, (gossip, ip_echo)) =
             bind_common_in_range(bind_ip_addr, (1024, 65535)).unwrap();
         let gossip_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), gossip_port);
@@ -3347,7 +3430,6 @@ impl Node {
         let broadcast = vec![UdpSocket::bind("0.0.0.0:0").unwrap()];
         let retransmit_socket = UdpSocket::bind("0.0.0.0:0").unwrap();
         let serve_repair = UdpSocket::bind("127.0.0.1:0").unwrap();
-        let unused = UdpSocket::bind("0.0.0.0:0").unwrap();
         let info = ContactInfo {
             id: *pubkey,
             gossip: gossip_addr,
@@ -3356,7 +3438,7 @@ impl Node {
             repair: repair.local_addr().unwrap(),
             tpu: tpu.local_addr().unwrap(),
             tpu_forwards: tpu_forwards.local_addr().unwrap(),
-            unused: unused.local_addr().unwrap(),
+            tpu_quic: tpu_quic.local_addr().unwrap(),
             rpc: rpc_addr,
             rpc_pubsub: rpc_pubsub_addr,
             serve_repair: serve_repair.local_addr().unwrap(),
@@ -3372,6 +3454,7 @@ impl Node {
                 tvu_forwards: vec![tvu_forwards],
                 tpu: vec![tpu],
                 tpu_forwards: vec![tpu_forwards],
+                tpu_quic,
                 broadcast,
                 repair,
                 retransmit_sockets: vec![retransmit_socket],
@@ -3398,6 +3481,24 @@ impl Node {
     fn bind(bind_ip_addr: IpAddr, port_range: PortRange) -> (u16, UdpSocket) {
         bind_in_range(bind_ip_addr, port_range).expect("Failed to bind")
     }
+    /// Bind `num` TPU sockets to a port in `port_range`, and the QUIC socket to the port at
+    /// `QUIC_PORT_OFFSET` from it
+    fn bind_tpu(
+        bind_ip_addr: IpAddr,
+        port_range: PortRange,
+        num: usize,
+    ) -> (u16, Vec<UdpSocket>, UdpSocket) {
+        const NUM_TRIES: usize = 100;
+        let tpu_port_range = (port_range.0, port_range.1.saturating_sub(QUIC_PORT_OFFSET));
+        for _ in 0..NUM_TRIES {
+            let (tpu_port, tpu_sockets) =
+                multi_bind_in_range(bind_ip_addr, tpu_port_range, num).expect("tpu multi_bind");
+            if let Ok(tpu_quic) = bind_to(bind_ip_addr, tpu_port + QUIC_PORT_OFFSET, false) {
+                return (tpu_port, tpu_sockets, tpu_quic);
+            }
+        }
+        panic!("Failed to bind tpu_quic in {:?}", port_range);
+    }
 
     pub fn new_with_external_ip(
         pubkey: &Pubkey,
@@ -3408,15 +3509,15 @@ impl Node {
         let (gossip_port, (gossip, ip_echo)) =
             Self::get_gossip_port(gossip_addr, port_range, bind_ip_addr);
 
+        // Bind the TPU first, while the port at the QUIC offset from it is most likely free
+        let (tpu_port, tpu_sockets, tpu_quic) = Self::bind_tpu(bind_ip_addr, port_range, 32);
+
         let (tvu_port, tvu_sockets) =
             multi_bind_in_range(bind_ip_addr, port_range, 8).expect("tvu multi_bind");
 
         let (tvu_forwards_port, tvu_forwards_sockets) =
             multi_bind_in_range(bind_ip_addr, port_range, 8).expect("tvu_forwards multi_bind");
 
-        let (tpu_port, tpu_sockets) =
-            multi_bind_in_range(bind_ip_addr, port_range, 32).expect("tpu multi_bind");
-
         let (tpu_forwards_port, tpu_forwards_sockets) =
             multi_bind_in_range(bind_ip_addr, port_range, 8).expect("tpu_forwards multi_bind");
 
@@ -3437,7 +3538,7 @@ impl Node {
             repair: SocketAddr::new(gossip_addr.ip(), repair_port),
             tpu: SocketAddr::new(gossip_addr.ip(), tpu_port),
             tpu_forwards: SocketAddr::new(gossip_addr.ip(), tpu_forwards_port),
-            unused: socketaddr_any!(),
+            tpu_quic: SocketAddr::new(gossip_addr.ip(), tpu_port + QUIC_PORT_OFFSET),
             rpc: socketaddr_any!(),
             rpc_pubsub: socketaddr_any!(),
             serve_repair: SocketAddr::new(gossip_addr.ip(), serve_repair_port),
@@ -3454,6 +3555,7 @@ impl Node {
                 tvu_forwards: tvu_forwards_sockets,
                 tpu: tpu_sockets,
                 tpu_forwards: tpu_forwards_sockets,
+                tpu_quic,
                 broadcast,
                 repair,
                 retransmit_sockets,
@@ -3476,7 +3578,7 @@ pub fn stake_weight_peers(
 mod tests {
     use super::*;
     use crate::{
-        crds_value::{CrdsValue, CrdsValueLabel, Vote as CrdsVote},
+        crds_value::{CrdsDataKind, CrdsValue, CrdsValueLabel, Vote as CrdsVote},
         duplicate_shred::{self, tests::new_rand_shred, MAX_DUPLICATE_SHREDS},
     };
     use itertools::izip;
@@ -3849,6 +3951,7 @@ mod tests {
             &HashMap::new(),
             true,  // generate_pull_requests
             false, // require_stake_for_gossip
+            true,  // pull_request_with_kinds
         );
         //assert none of the addrs are invalid.
         reqs.iter().all(|(addr, _)| {
@@ -3907,6 +4010,12 @@ mod tests {
 
         check_sockets(&node.sockets.tvu, ip, range);
         check_sockets(&node.sockets.tpu, ip, range);
+        check_socket(&node.sockets.tpu_quic, ip, range);
+        assert_eq!(
+            node.sockets.tpu_quic.local_addr().unwrap().port(),
+            node.sockets.tpu[0].local_addr().unwrap().port() + QUIC_PORT_OFFSET
+        );
+        assert_eq!(node.info.valid_tpu_quic_addr(), Some(node.info.tpu_quic));
     }
 
     #[test]
@@ -4147,7 +4256,7 @@ mod tests {
         let entrypoint_pubkey = solana_sdk::pubkey::new_rand();
         let entrypoint = ContactInfo::new_localhost(&entrypoint_pubkey, timestamp());
         cluster_info.set_entrypoint(entrypoint.clone());
-        let pulls = cluster_info.new_pull_requests(&thread_pool, None, &HashMap::new());
+        let pulls = cluster_info.new_pull_requests(&thread_pool, None, &HashMap::new(), true);
         assert_eq!(1, pulls.len() as u64);
         match pulls.get(0) {
             Some((addr, msg)) => {
@@ -4174,7 +4283,7 @@ mod tests {
             vec![entrypoint_crdsvalue],
             &timeouts,
         );
-        let pulls = cluster_info.new_pull_requests(&thread_pool, None, &HashMap::new());
+        let pulls = cluster_info.new_pull_requests(&thread_pool, None, &HashMap::new(), true);
         assert_eq!(1, pulls.len() as u64);
         assert_eq!(*cluster_info.entrypoints.read().unwrap(), vec![entrypoint]);
     }
@@ -4282,10 +4391,82 @@ mod tests {
 
     fn check_pull_request_size(filter: CrdsFilter) {
         let value = CrdsValue::new_unsigned(CrdsData::ContactInfo(ContactInfo::default()));
-        let protocol = Protocol::PullRequest(filter, value);
+        let protocol = Protocol::PullRequest(filter.clone(), value.clone());
+        assert!(serialized_size(&protocol).unwrap() <= PACKET_DATA_SIZE as u64);
+        let protocol = Protocol::PullRequestWithKinds(filter, value, CrdsDataKinds::all());
         assert!(serialized_size(&protocol).unwrap() <= PACKET_DATA_SIZE as u64);
     }
 
+    #[test]
+    fn test_new_pull_requests_with_kinds() {
+        let thread_pool = ThreadPoolBuilder::new().build().unwrap();
+        let node_keypair = Arc::new(Keypair::new());
+        let cluster_info = ClusterInfo::new(
+            ContactInfo::new_localhost(&node_keypair.pubkey(), timestamp()),
+            node_keypair,
+        );
+        let entrypoint = ContactInfo::new_localhost(&solana_sdk::pubkey::new_rand(), timestamp());
+        cluster_info.set_entrypoint(entrypoint);
+        let kinds: CrdsDataKinds = vec![CrdsDataKind::ContactInfo, CrdsDataKind::Version]
+            .into_iter()
+            .collect();
+        cluster_info.set_pull_request_kinds(kinds);
+        let pulls = cluster_info.new_pull_requests(&thread_pool, None, &HashMap::new(), true);
+        assert_eq!(pulls.len(), 1);
+        match &pulls[0].1 {
+            Protocol::PullRequestWithKinds(filter, value, pull_kinds) => {
+                assert_eq!(*pull_kinds, kinds);
+                assert_eq!(filter.kinds, kinds);
+                assert_eq!(value.pubkey(), cluster_info.id());
+            }
+            _ => panic!("wrong protocol"),
+        }
+        // The kinds survive a round trip through the wire format.
+        let bytes = bincode::serialize(&pulls[0].1).unwrap();
+        let protocol: Protocol = bincode::deserialize(&bytes).unwrap();
+        assert!(protocol.sanitize().is_ok());
+        match protocol {
+            Protocol::PullRequestWithKinds(_, _, pull_kinds) => assert_eq!(pull_kinds, kinds),
+            _ => panic!("wrong protocol"),
+        }
+        assert_eq!(
+            cluster_info
+                .stats
+                .new_pull_requests_with_kinds_count
+                .clear(),
+            1
+        );
+        // Without the feature the kinds are not put on the wire.
+        let pulls = cluster_info.new_pull_requests(&thread_pool, None, &HashMap::new(), false);
+        assert_eq!(pulls.len(), 1);
+        match &pulls[0].1 {
+            Protocol::PullRequest(filter, _) => assert_eq!(filter.kinds, kinds),
+            _ => panic!("wrong protocol"),
+        }
+    }
+
+    #[test]
+    fn test_handle_pull_response_with_kinds() {
+        let node_keypair = Arc::new(Keypair::new());
+        let cluster_info = ClusterInfo::new(
+            ContactInfo::new_localhost(&node_keypair.pubkey(), timestamp()),
+            node_keypair,
+        );
+        let kinds: CrdsDataKinds = vec![CrdsDataKind::ContactInfo].into_iter().collect();
+        cluster_info.set_pull_request_kinds(kinds);
+        let from = solana_sdk::pubkey::new_rand();
+        let mut data = test_crds_values(from);
+        data.push(CrdsValue::new_unsigned(CrdsData::LowestSlot(
+            0,
+            LowestSlot::new(from, 0, 0),
+        )));
+        assert_eq!(
+            ClusterInfo::handle_pull_response(&cluster_info, &from, data, &HashMap::new()),
+            (0, 0, 1)
+        );
+        assert_eq!(cluster_info.stats.skip_pull_response_kinds.clear(), 1);
+    }
+
     #[test]
     fn test_tvu_peers_and_stakes() {
         let d = ContactInfo::new_localhost(&Pubkey::new(&[0; 32]), timestamp());
@@ -4355,21 +4536,21 @@ mod tests {
 
         // Pull request 1:  `other_node` is present but `entrypoint` was just added (so it has a
         // fresh timestamp).  There should only be one pull request to `other_node`
-        let pulls = cluster_info.new_pull_requests(&thread_pool, None, &stakes);
+        let pulls = cluster_info.new_pull_requests(&thread_pool, None, &stakes, true);
         assert_eq!(1, pulls.len() as u64);
         assert_eq!(pulls.get(0).unwrap().0, other_node.gossip);
 
         // Pull request 2: pretend it's been a while since we've pulled from `entrypoint`.  There should
         // now be two pull requests
         cluster_info.entrypoints.write().unwrap()[0].wallclock = 0;
-        let pulls = cluster_info.new_pull_requests(&thread_pool, None, &stakes);
+        let pulls = cluster_info.new_pull_requests(&thread_pool, None, &stakes, true);
         assert_eq!(2, pulls.len() as u64);
         assert_eq!(pulls.get(0).unwrap().0, other_node.gossip);
         assert_eq!(pulls.get(1).unwrap().0, entrypoint.gossip);
 
         // Pull request 3:  `other_node` is present and `entrypoint` was just pulled from.  There should
         // only be one pull request to `other_node`
-        let pulls = cluster_info.new_pull_requests(&thread_pool, None, &stakes);
+        let pulls = cluster_info.new_pull_requests(&thread_pool, None, &stakes, true);
         assert_eq!(1, pulls.len() as u64);
         assert_eq!(pulls.get(0).unwrap().0, other_node.gossip);
     }
@@ -4439,9 +4620,10 @@ mod tests {
     fn max_bloom_size() -> usize {
         let filter_size = serialized_size(&CrdsFilter::default())
             .expect("unable to serialize default filter") as usize;
-        let protocol = Protocol::PullRequest(
+        let protocol = Protocol::PullRequestWithKinds(
             CrdsFilter::default(),
             CrdsValue::new_unsigned(CrdsData::ContactInfo(ContactInfo::default())),
+            CrdsDataKinds::default(),
         );
         let protocol_size =
             serialized_size(&protocol).expect("unable to serialize gossip protocol") as usize;
@@ -6,6 +6,7 @@ use solana_sdk::sanitize::{Sanitize, SanitizeError};
 #[cfg(test)]
 use solana_sdk::signature::{Keypair, Signer};
 use solana_sdk::timing::timestamp;
+use solana_streamer::quic::QUIC_PORT_OFFSET;
 use std::cmp::{Ord, Ordering, PartialEq, PartialOrd};
 use std::net::{IpAddr, SocketAddr};
 
@@ -25,8 +26,10 @@ pub struct ContactInfo {
     pub tpu: SocketAddr,
     /// address to forward unprocessed transactions to
     pub tpu_forwards: SocketAddr,
-    /// address to which to send bank state requests
-    pub unused: SocketAddr,
+    /// address to send transactions to over QUIC; serialized in the place of a formerly unused
+    /// address so that the gossip wire format does not change
+    #[serde(rename = "unused")]
+    pub tpu_quic: SocketAddr,
     /// address to which to send JSON-RPC requests
     pub rpc: SocketAddr,
     /// websocket for JSON-RPC push notifications
@@ -95,7 +98,7 @@ impl Default for ContactInfo {
             repair: socketaddr_any!(),
             tpu: socketaddr_any!(),
             tpu_forwards: socketaddr_any!(),
-            unused: socketaddr_any!(),
+            tpu_quic: socketaddr_any!(),
             rpc: socketaddr_any!(),
             rpc_pubsub: socketaddr_any!(),
             serve_repair: socketaddr_any!(),
@@ -115,7 +118,7 @@ impl ContactInfo {
             repair: socketaddr!("127.0.0.1:1237"),
             tpu: socketaddr!("127.0.0.1:1238"),
             tpu_forwards: socketaddr!("127.0.0.1:1239"),
-            unused: socketaddr!("127.0.0.1:1240"),
+            tpu_quic: socketaddr!("127.0.0.1:1244"),
             rpc: socketaddr!("127.0.0.1:1241"),
             rpc_pubsub: socketaddr!("127.0.0.1:1242"),
             serve_repair: socketaddr!("127.0.0.1:1243"),
@@ -145,7 +148,7 @@ impl ContactInfo {
             repair: addr,
             tpu: addr,
             tpu_forwards: addr,
-            unused: addr,
+            tpu_quic: addr,
             rpc: addr,
             rpc_pubsub: addr,
             serve_repair: addr,
@@ -179,7 +182,7 @@ impl ContactInfo {
             repair,
             tpu,
             tpu_forwards,
-            unused: "0.0.0.0:0".parse().unwrap(),
+            tpu_quic: "0.0.0.0:0".parse().unwrap(),
             rpc,
             rpc_pubsub,
             serve_repair,
@@ -221,6 +224,16 @@ impl ContactInfo {
         (self.rpc, self.tpu)
     }
 
+    /// The QUIC transactions address, if the node accepts transactions over QUIC.  Nodes
+    /// serve QUIC at `QUIC_PORT_OFFSET` from their TPU port, any other address is left over in
+    /// the formerly unused field by a node which does not serve QUIC.
+    pub fn valid_tpu_quic_addr(&self) -> Option<SocketAddr> {
+        let quic_port = self.tpu.port().checked_add(QUIC_PORT_OFFSET)?;
+        Some(self.tpu_quic).filter(|addr| {
+            Self::is_valid_address(addr) && addr.ip() == self.tpu.ip() && addr.port() == quic_port
+        })
+    }
+
     pub fn valid_client_facing_addr(&self) -> Option<(SocketAddr, SocketAddr)> {
         if ContactInfo::is_valid_address(&self.rpc) && ContactInfo::is_valid_address(&self.tpu) {
             Some((self.rpc, self.tpu))
@@ -256,7 +269,7 @@ mod tests {
         assert!(ci.rpc.ip().is_unspecified());
         assert!(ci.rpc_pubsub.ip().is_unspecified());
         assert!(ci.tpu.ip().is_unspecified());
-        assert!(ci.unused.ip().is_unspecified());
+        assert!(ci.tpu_quic.ip().is_unspecified());
         assert!(ci.serve_repair.ip().is_unspecified());
     }
     #[test]
@@ -268,7 +281,7 @@ mod tests {
         assert!(ci.rpc.ip().is_multicast());
         assert!(ci.rpc_pubsub.ip().is_multicast());
         assert!(ci.tpu.ip().is_multicast());
-        assert!(ci.unused.ip().is_multicast());
+        assert!(ci.tpu_quic.ip().is_multicast());
         assert!(ci.serve_repair.ip().is_multicast());
     }
     #[test]
@@ -281,7 +294,7 @@ mod tests {
         assert!(ci.rpc.ip().is_unspecified());
         assert!(ci.rpc_pubsub.ip().is_unspecified());
         assert!(ci.tpu.ip().is_unspecified());
-        assert!(ci.unused.ip().is_unspecified());
+        assert!(ci.tpu_quic.ip().is_unspecified());
         assert!(ci.serve_repair.ip().is_unspecified());
     }
     #[test]
@@ -294,10 +307,29 @@ mod tests {
         assert_eq!(ci.tpu_forwards.port(), 13);
         assert_eq!(ci.rpc.port(), rpc_port::DEFAULT_RPC_PORT);
         assert_eq!(ci.rpc_pubsub.port(), rpc_port::DEFAULT_RPC_PUBSUB_PORT);
-        assert!(ci.unused.ip().is_unspecified());
+        assert!(ci.tpu_quic.ip().is_unspecified());
         assert_eq!(ci.serve_repair.port(), 16);
     }
 
+    #[test]
+    fn test_valid_tpu_quic_addr() {
+        let mut ci = ContactInfo::new_localhost(&Pubkey::default(), 0);
+        assert_eq!(ci.valid_tpu_quic_addr(), Some(ci.tpu_quic));
+
+        // A node which does not serve QUIC leaves the field unspecified, or set to an address
+        // from before it carried the QUIC address
+        ci.tpu_quic = socketaddr_any!();
+        assert_eq!(ci.valid_tpu_quic_addr(), None);
+        ci.tpu_quic = ci.tpu_forwards;
+        assert_eq!(ci.valid_tpu_quic_addr(), None);
+        ci.tpu_quic = socketaddr!("127.0.0.2:1244");
+        assert_eq!(ci.valid_tpu_quic_addr(), None);
+
+        ci.tpu = socketaddr!("127.0.0.1:65535");
+        ci.tpu_quic = socketaddr!("127.0.0.1:5");
+        assert_eq!(ci.valid_tpu_quic_addr(), None);
+    }
+
     #[test]
     fn replayed_data_new_with_socketaddr_with_pubkey() {
         let keypair = Keypair::new();
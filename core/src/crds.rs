@@ -28,6 +28,7 @@ use crate::contact_info::ContactInfo;
 use crate::crds_shards::CrdsShards;
 use crate::crds_value::{CrdsData, CrdsValue, CrdsValueLabel, LowestSlot};
 use bincode::serialize;
+use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
 use indexmap::map::{rayon::ParValues, Entry, IndexMap};
 use indexmap::set::IndexSet;
 use rayon::{prelude::*, ThreadPool};
@@ -56,6 +57,8 @@ pub struct Crds {
     epoch_slots: BTreeSet<(u64 /*insert timestamp*/, usize)>,
     // Indices of all crds values associated with a node.
     records: HashMap<Pubkey, IndexSet<usize>>,
+    // Receives every value inserted into the table, see `subscribe_inserts`.
+    insert_sender: Option<Sender<VersionedCrdsValue>>,
 }
 
 #[derive(PartialEq, Debug)]
@@ -118,6 +121,7 @@ impl Default for Crds {
             votes: IndexSet::default(),
             epoch_slots: BTreeSet::default(),
             records: HashMap::default(),
+            insert_sender: None,
         }
     }
 }
@@ -166,6 +170,7 @@ impl Crds {
                     .entry(new_value.value.pubkey())
                     .or_default()
                     .insert(entry_index);
+                Self::notify_insert(&self.insert_sender, &new_value);
                 entry.insert(new_value);
                 self.num_inserts += 1;
                 Ok(None)
@@ -184,6 +189,7 @@ impl Crds {
                 // As long as the pubkey does not change, self.records
                 // does not need to be updated.
                 debug_assert_eq!(entry.get().value.pubkey(), new_value.value.pubkey());
+                Self::notify_insert(&self.insert_sender, &new_value);
                 Ok(Some(entry.insert(new_value)))
             }
             _ => {
@@ -204,6 +210,22 @@ impl Crds {
         let new_value = self.new_versioned(local_timestamp, value);
         self.insert_versioned(new_value)
     }
+    /// Returns a receiver of every value inserted into the table from now on, including the
+    /// ones which are replaced before they could be looked up. Replaces any earlier receiver.
+    /// Values inserted while `capacity` values are waiting in the receiver are dropped, so that
+    /// a slow subscriber does not hold up gossip.
+    pub fn subscribe_inserts(&mut self, capacity: usize) -> Receiver<VersionedCrdsValue> {
+        let (sender, receiver) = bounded(capacity);
+        self.insert_sender = Some(sender);
+        receiver
+    }
+    fn notify_insert(sender: &Option<Sender<VersionedCrdsValue>>, value: &VersionedCrdsValue) {
+        if let Some(sender) = sender {
+            if let Err(TrySendError::Full(_)) = sender.try_send(value.clone()) {
+                inc_new_counter_info!("crds-insert-subscriber-dropped", 1);
+            }
+        }
+    }
     pub fn lookup(&self, label: &CrdsValueLabel) -> Option<&CrdsValue> {
         self.table.get(label).map(|x| &x.value)
     }
@@ -272,8 +294,7 @@ impl Crds {
         self.table.is_empty()
     }
 
-    #[cfg(test)]
-    pub(crate) fn values(&self) -> impl Iterator<Item = &VersionedCrdsValue> {
+    pub fn values(&self) -> impl Iterator<Item = &VersionedCrdsValue> {
         self.table.values()
     }
 
@@ -503,6 +524,42 @@ mod test {
         assert!(crds.table.contains_key(&val.label()));
         assert_eq!(crds.table[&val.label()].local_timestamp, 0);
     }
+    #[test]
+    fn test_subscribe_inserts() {
+        let mut crds = Crds::default();
+        let val = CrdsValue::new_unsigned(CrdsData::ContactInfo(ContactInfo::default()));
+        assert_eq!(crds.insert(val, 0), Ok(None));
+        let receiver = crds.subscribe_inserts(3);
+
+        let mut ci = ContactInfo::default();
+        ci.wallclock += 1;
+        let val1 = CrdsValue::new_unsigned(CrdsData::ContactInfo(ci.clone()));
+        ci.wallclock += 1;
+        let val2 = CrdsValue::new_unsigned(CrdsData::ContactInfo(ci));
+        assert_matches!(crds.insert(val1.clone(), 1), Ok(Some(_)));
+        assert_matches!(crds.insert(val2.clone(), 2), Ok(Some(_)));
+        // Values which fail to insert are not sent
+        assert_eq!(crds.insert(val1.clone(), 3), Err(CrdsError::InsertFailed));
+        let node = CrdsValue::new_rand(&mut thread_rng(), None);
+        assert_eq!(crds.insert(node.clone(), 4), Ok(None));
+
+        let inserted: Vec<_> = receiver
+            .try_iter()
+            .map(|value| (value.value, value.insert_timestamp))
+            .collect();
+        assert_eq!(inserted, vec![(val1, 1), (val2, 2), (node, 4)]);
+
+        // Values inserted while the receiver is full are dropped
+        let nodes: Vec<_> = repeat_with(|| CrdsValue::new_rand(&mut thread_rng(), None))
+            .take(5)
+            .collect();
+        for node in &nodes {
+            assert_eq!(crds.insert(node.clone(), 5), Ok(None));
+        }
+        let inserted: Vec<_> = receiver.try_iter().map(|value| value.value).collect();
+        assert_eq!(inserted, nodes[..3]);
+    }
+
     #[test]
     fn test_update_old() {
         let mut crds = Crds::default();
@@ -13,7 +13,7 @@ use crate::contact_info::ContactInfo;
 use crate::crds::{Crds, VersionedCrdsValue};
 use crate::crds_gossip::{get_stake, get_weight, CRDS_GOSSIP_DEFAULT_BLOOM_ITEMS};
 use crate::crds_gossip_error::CrdsGossipError;
-use crate::crds_value::{CrdsValue, CrdsValueLabel};
+use crate::crds_value::{CrdsDataKinds, CrdsValue, CrdsValueLabel};
 use itertools::Itertools;
 use rand::distributions::{Distribution, WeightedIndex};
 use rand::Rng;
@@ -41,6 +41,11 @@ pub struct CrdsFilter {
     pub filter: Bloom<Hash>,
     mask: u64,
     mask_bits: u32,
+    /// Kinds of values the requester is interested in.  Not part of the
+    /// serialized filter; it is sent alongside the filter in
+    /// Protocol::PullRequestWithKinds.
+    #[serde(skip)]
+    pub kinds: CrdsDataKinds,
 }
 
 impl Default for CrdsFilter {
@@ -49,6 +54,7 @@ impl Default for CrdsFilter {
             filter: Bloom::default(),
             mask: !0u64,
             mask_bits: 0u32,
+            kinds: CrdsDataKinds::default(),
         }
     }
 }
@@ -72,6 +78,7 @@ impl CrdsFilter {
             filter,
             mask,
             mask_bits,
+            kinds: CrdsDataKinds::default(),
         }
     }
 
@@ -155,6 +162,7 @@ impl From<CrdsFilterSet> for Vec<CrdsFilter> {
                 filter: filter.into(),
                 mask: CrdsFilter::compute_mask(seed as u64, mask_bits),
                 mask_bits,
+                kinds: CrdsDataKinds::default(),
             })
             .collect()
     }
@@ -182,6 +190,9 @@ pub struct CrdsGossipPull {
     pub crds_timeout: u64,
     pub msg_timeout: u64,
     pub num_pulls: usize,
+    /// Kinds of values to ask for in pull requests; nodes which only need a
+    /// partial table (e.g. ContactInfo and Version) may restrict this.
+    pub pull_request_kinds: CrdsDataKinds,
 }
 
 impl Default for CrdsGossipPull {
@@ -193,6 +204,7 @@ impl Default for CrdsGossipPull {
             crds_timeout: CRDS_GOSSIP_PULL_CRDS_TIMEOUT_MS,
             msg_timeout: CRDS_GOSSIP_PULL_MSG_TIMEOUT_MS,
             num_pulls: 0,
+            pull_request_kinds: CrdsDataKinds::default(),
         }
     }
 }
@@ -445,14 +457,28 @@ impl CrdsGossipPull {
         bloom_size: usize,
     ) -> Vec<CrdsFilter> {
         const PAR_MIN_LENGTH: usize = 512;
+        let kinds = self.pull_request_kinds;
+        // Only values of the requested kinds need to be in the filters, so
+        // that partial requests need smaller filters.
+        let num_values = if kinds.is_all() {
+            crds.len()
+        } else {
+            thread_pool.install(|| {
+                crds.par_values()
+                    .with_min_len(PAR_MIN_LENGTH)
+                    .filter(|v| kinds.contains(v.value.data.kind()))
+                    .count()
+            })
+        };
         let num = cmp::max(
             CRDS_GOSSIP_DEFAULT_BLOOM_ITEMS,
-            crds.len() + self.purged_values.len() + self.failed_inserts.len(),
+            num_values + self.purged_values.len() + self.failed_inserts.len(),
         );
         let filters = CrdsFilterSet::new(num, bloom_size);
         thread_pool.install(|| {
             crds.par_values()
                 .with_min_len(PAR_MIN_LENGTH)
+                .filter(|v| kinds.contains(v.value.data.kind()))
                 .map(|v| v.value_hash)
                 .chain(
                     self.purged_values
@@ -468,7 +494,11 @@ impl CrdsGossipPull {
                 )
                 .for_each(|v| filters.add(v));
         });
-        filters.into()
+        let mut filters: Vec<CrdsFilter> = filters.into();
+        for filter in &mut filters {
+            filter.kinds = kinds;
+        }
+        filters
     }
 
     /// filter values that fail the bloom filter up to max_bytes
@@ -486,6 +516,7 @@ impl CrdsGossipPull {
         let past = now.saturating_sub(msg_timeout);
         let mut dropped_requests = 0;
         let mut total_skipped = 0;
+        let mut skipped_kinds = 0;
         let ret: Vec<_> = filters
             .iter()
             .map(|(caller, filter)| {
@@ -506,6 +537,9 @@ impl CrdsGossipPull {
                         if item.value.wallclock() > caller_wallclock {
                             total_skipped += 1;
                             None
+                        } else if !filter.kinds.contains(item.value.data.kind()) {
+                            skipped_kinds += 1;
+                            None
                         } else if filter.filter_contains(&item.value_hash) {
                             None
                         } else {
@@ -524,6 +558,7 @@ impl CrdsGossipPull {
             dropped_requests + filters.len() - ret.len()
         );
         inc_new_counter_info!("gossip_filter_crds_values-dropped_values", total_skipped);
+        inc_new_counter_info!("gossip_filter_crds_values-skipped_kinds", skipped_kinds);
         ret
     }
     pub fn make_timeouts_def(
@@ -612,13 +647,14 @@ mod test {
     use super::*;
     use crate::cluster_info::MAX_BLOOM_SIZE;
     use crate::contact_info::ContactInfo;
-    use crate::crds_value::{CrdsData, Vote};
+    use crate::crds_value::{CrdsData, CrdsDataKind, Vote};
     use itertools::Itertools;
     use rand::thread_rng;
     use rayon::ThreadPoolBuilder;
     use solana_perf::test_tx::test_tx;
     use solana_sdk::hash::{hash, HASH_BYTES};
     use solana_sdk::packet::PACKET_DATA_SIZE;
+    use solana_sdk::timing::timestamp;
 
     #[test]
     fn test_hash_as_u64() {
@@ -904,6 +940,67 @@ mod test {
         assert!(false_positives < 50_000, "fp: {}", false_positives);
     }
 
+    #[test]
+    fn test_build_crds_filter_with_kinds() {
+        let mut rng = thread_rng();
+        let thread_pool = ThreadPoolBuilder::new().build().unwrap();
+        let mut crds_gossip_pull = CrdsGossipPull::default();
+        let mut crds = Crds::default();
+        for _ in 0..20_000 {
+            let _ = crds.insert(CrdsValue::new_rand(&mut rng, None), rng.gen());
+        }
+        let kinds: CrdsDataKinds = vec![CrdsDataKind::ContactInfo, CrdsDataKind::Version]
+            .into_iter()
+            .collect();
+        crds_gossip_pull.pull_request_kinds = kinds;
+        let filters = crds_gossip_pull.build_crds_filters(&thread_pool, &crds, MAX_BLOOM_SIZE);
+        // Fewer values than the whole table need fewer filters.
+        assert!(filters.len() < 32);
+        assert!(filters.iter().all(|filter| filter.kinds == kinds));
+        for value in crds.values() {
+            if kinds.contains(value.value.data.kind()) {
+                assert!(filters
+                    .iter()
+                    .any(|filter| filter.test_mask(&value.value_hash)
+                        && filter.contains(&value.value_hash)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_pull_responses_with_kinds() {
+        let mut rng = thread_rng();
+        let mut crds = Crds::default();
+        for _ in 0..1_000 {
+            let _ = crds.insert(CrdsValue::new_rand(&mut rng, None), 0);
+        }
+        let node = CrdsGossipPull::default();
+        // Callers newer than all the values in the table.
+        let now = timestamp() + 20 * 60 * 1000;
+        let caller = CrdsValue::new_unsigned(CrdsData::ContactInfo(ContactInfo::new_localhost(
+            &solana_sdk::pubkey::new_rand(),
+            now,
+        )));
+        let kinds: CrdsDataKinds = vec![CrdsDataKind::ContactInfo, CrdsDataKind::Version]
+            .into_iter()
+            .collect();
+        let filter = CrdsFilter::new_rand(1, MAX_BLOOM_SIZE);
+        let filters = vec![
+            (caller.clone(), filter.clone()),
+            (caller, CrdsFilter { kinds, ..filter }),
+        ];
+        let rsp = node.generate_pull_responses(&crds, &filters, usize::MAX, now);
+        assert_eq!(rsp.len(), 2);
+        assert_eq!(rsp[0].len(), crds.len());
+        let num_values = crds
+            .values()
+            .filter(|v| kinds.contains(v.value.data.kind()))
+            .count();
+        assert!(num_values > 0);
+        assert_eq!(rsp[1].len(), num_values);
+        assert!(rsp[1].iter().all(|v| kinds.contains(v.data.kind())));
+    }
+
     #[test]
     fn test_new_pull_request() {
         let thread_pool = ThreadPoolBuilder::new().build().unwrap();
@@ -19,7 +19,7 @@ use crate::{
 use bincode::serialized_size;
 use indexmap::map::IndexMap;
 use itertools::Itertools;
-use rand::{seq::SliceRandom, Rng};
+use rand::Rng;
 use solana_runtime::bloom::{AtomicBloom, Bloom};
 use solana_sdk::{hash::Hash, packet::PACKET_DATA_SIZE, pubkey::Pubkey, timing::timestamp};
 use std::{
@@ -47,6 +47,8 @@ pub struct CrdsGossipPush {
     pub max_bytes: usize,
     /// active set of validators for push
     active_set: IndexMap<Pubkey, AtomicBloom<Pubkey>>,
+    /// stake weights of the active set, in the same order as active_set
+    active_set_weights: Vec<f32>,
     /// push message queue
     push_messages: HashMap<CrdsValueLabel, Hash>,
     /// Cache that tracks which validators a message was received from
@@ -63,6 +65,7 @@ pub struct CrdsGossipPush {
     pub num_total: usize,
     pub num_old: usize,
     pub num_pushes: usize,
+    pub num_evicted: usize,
 }
 
 impl Default for CrdsGossipPush {
@@ -71,6 +74,7 @@ impl Default for CrdsGossipPush {
             // Allow upto 64 Crds Values per PUSH
             max_bytes: PACKET_DATA_SIZE * 64,
             active_set: IndexMap::new(),
+            active_set_weights: Vec::new(),
             push_messages: HashMap::new(),
             received_cache: HashMap::new(),
             last_pushed_to: HashMap::new(),
@@ -82,6 +86,7 @@ impl Default for CrdsGossipPush {
             num_total: 0,
             num_old: 0,
             num_pushes: 0,
+            num_evicted: 0,
         }
     }
 }
@@ -215,6 +220,8 @@ impl CrdsGossipPush {
     /// peers.
     /// The list of push messages is created such that all the randomly selected peers have not
     /// pruned the source addresses.
+    /// Peers are picked from the active set by a stake weighted shuffle seeded
+    /// with the origin, so that values of the same origin take the same path.
     pub fn new_push_messages(&mut self, crds: &Crds, now: u64) -> HashMap<Pubkey, Vec<CrdsValue>> {
         trace!("new_push_messages {}", self.push_messages.len());
         let push_fanout = self.push_fanout.min(self.active_set.len());
@@ -235,13 +242,22 @@ impl CrdsGossipPush {
                 Some(&value.value)
             }
         };
+        let weights: Vec<f32> = if self.active_set_weights.len() == self.active_set.len() {
+            self.active_set_weights.clone()
+        } else {
+            vec![1.0; self.active_set.len()]
+        };
+        let mut fanouts: HashMap<Pubkey, Vec<usize>> = HashMap::new();
         let mut push_value = |origin: Pubkey, value: &CrdsValue| {
-            //use a consistent index for the same origin so
+            //use a consistent shuffle for the same origin so
             //the active set learns the MST for that origin
-            let start = origin.as_ref()[0] as usize;
-            for i in start..(start + push_fanout) {
-                let index = i % self.active_set.len();
-                let (peer, filter) = self.active_set.get_index(index).unwrap();
+            let fanout = fanouts.entry(origin).or_insert_with(|| {
+                let mut shuffle = weighted_shuffle(weights.clone(), origin.to_bytes());
+                shuffle.truncate(push_fanout);
+                shuffle
+            });
+            for index in fanout.iter() {
+                let (peer, filter) = self.active_set.get_index(*index).unwrap();
                 if !filter.contains(&origin) || value.should_force_push(peer) {
                     trace!("new_push_messages insert {} {:?}", *peer, value);
                     push_messages.entry(*peer).or_default().push(value.clone());
@@ -298,6 +314,8 @@ impl CrdsGossipPush {
 
     /// refresh the push active set
     /// * ratio - active_set.len()/ratio is the number of actives to rotate
+    /// Both new peers and the peers rotated out are picked by stake, so that
+    /// low staked peers are the most likely to be evicted.
     pub fn refresh_push_active_set(
         &mut self,
         crds: &Crds,
@@ -349,15 +367,31 @@ impl CrdsGossipPush {
                 _ => break,
             }
         }
-        let mut keys: Vec<Pubkey> = self.active_set.keys().cloned().collect();
-        keys.shuffle(&mut rng);
-        let num = keys.len() / ratio;
-        for k in &keys[..num] {
+        let num = self.active_set.len() / ratio;
+        rng.fill(&mut seed[..]);
+        let evict: Vec<Pubkey> = weighted_shuffle(
+            self.active_set
+                .keys()
+                .map(|k| 1.0 / get_stake(k, stakes))
+                .collect_vec(),
+            seed,
+        )
+        .into_iter()
+        .take(num)
+        .map(|index| *self.active_set.get_index(index).unwrap().0)
+        .collect();
+        for k in &evict {
             self.active_set.swap_remove(k);
         }
+        self.num_evicted += evict.len();
         for (k, v) in new_items {
             self.active_set.insert(k, v);
         }
+        self.active_set_weights = self
+            .active_set
+            .keys()
+            .map(|k| get_stake(k, stakes))
+            .collect();
     }
 
     fn push_options<'a>(
@@ -429,6 +463,7 @@ impl CrdsGossipPush {
         }
         Self {
             active_set,
+            active_set_weights: self.active_set_weights.clone(),
             push_messages: self.push_messages.clone(),
             received_cache: self.received_cache.clone(),
             last_pushed_to: self.last_pushed_to.clone(),
@@ -442,6 +477,7 @@ mod test {
     use super::*;
     use crate::contact_info::ContactInfo;
     use crate::crds_value::CrdsData;
+    use std::iter::repeat_with;
 
     #[test]
     fn test_prune() {
@@ -831,6 +867,80 @@ mod test {
         assert_eq!(push.active_set.len(), 3);
         assert_eq!(push.new_push_messages(&crds, now), expected);
     }
+    #[test]
+    fn test_stake_weighted_push_fanout() {
+        let now = timestamp();
+        let mut crds = Crds::default();
+        let mut push = CrdsGossipPush::default();
+        let mut stakes = HashMap::new();
+        let peers: Vec<_> = repeat_with(solana_sdk::pubkey::new_rand).take(20).collect();
+        for (i, peer) in peers.iter().enumerate() {
+            let value = CrdsValue::new_unsigned(CrdsData::ContactInfo(ContactInfo::new_localhost(
+                peer, now,
+            )));
+            assert_eq!(crds.insert(value, now), Ok(None));
+            if i < 4 {
+                stakes.insert(*peer, 1 << 30);
+            }
+        }
+        push.refresh_push_active_set(&crds, &stakes, None, &Pubkey::default(), 0, 1, 1);
+        assert_eq!(push.active_set.len(), peers.len());
+
+        let origins: Vec<_> = repeat_with(solana_sdk::pubkey::new_rand)
+            .take(100)
+            .collect();
+        for origin in &origins {
+            let value = CrdsValue::new_unsigned(CrdsData::ContactInfo(ContactInfo::new_localhost(
+                origin, now,
+            )));
+            assert_eq!(
+                push.process_push_message(&mut crds, &Pubkey::default(), value, now),
+                Ok(None)
+            );
+        }
+        let push_messages = push.new_push_messages(&crds, now);
+        let num_received =
+            |peer: &Pubkey| push_messages.get(peer).map(Vec::len).unwrap_or_default();
+        let min_staked = peers[..4].iter().map(num_received).min().unwrap();
+        let max_unstaked = peers[4..].iter().map(num_received).max().unwrap();
+        assert!(min_staked > max_unstaked, "{} {}", min_staked, max_unstaked);
+        assert_eq!(
+            push_messages.values().map(Vec::len).sum::<usize>(),
+            origins.len() * push.push_fanout
+        );
+    }
+
+    #[test]
+    fn test_stake_weighted_eviction() {
+        let now = timestamp();
+        let mut crds = Crds::default();
+        let mut push = CrdsGossipPush::default();
+        let mut stakes = HashMap::new();
+        let peers: Vec<_> = repeat_with(solana_sdk::pubkey::new_rand).take(20).collect();
+        for (i, peer) in peers.iter().enumerate() {
+            let value = CrdsValue::new_unsigned(CrdsData::ContactInfo(ContactInfo::new_localhost(
+                peer, now,
+            )));
+            assert_eq!(crds.insert(value, now), Ok(None));
+            if i < 10 {
+                stakes.insert(*peer, 1 << 30);
+            }
+        }
+        push.refresh_push_active_set(&crds, &stakes, None, &Pubkey::default(), 0, 1, 1);
+        assert_eq!(push.active_set.len(), peers.len());
+        // Rotate half of the active set out; there are no other peers to
+        // replace them with.
+        push.refresh_push_active_set(&crds, &stakes, None, &Pubkey::default(), 0, 1, 2);
+        assert_eq!(push.active_set.len(), peers.len() / 2);
+        assert_eq!(push.num_evicted, peers.len() / 2);
+        assert_eq!(push.active_set_weights.len(), push.active_set.len());
+        let num_staked = peers[..10]
+            .iter()
+            .filter(|peer| push.active_set.contains_key(*peer))
+            .count();
+        assert!(num_staked > push.active_set.len() - num_staked);
+    }
+
     #[test]
     fn test_process_prune() {
         let mut crds = Crds::default();
@@ -134,7 +134,86 @@ pub(crate) fn new_rand_timestamp<R: Rng>(rng: &mut R) -> u64 {
     timestamp() - DELAY + rng.gen_range(0, 2 * DELAY)
 }
 
+/// Kinds of CrdsData, in the order of the CrdsData variants
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CrdsDataKind {
+    ContactInfo,
+    Vote,
+    LowestSlot,
+    SnapshotHashes,
+    AccountsHashes,
+    EpochSlots,
+    LegacyVersion,
+    Version,
+    NodeInstance,
+    DuplicateShred,
+}
+
+/// Set of CrdsData kinds, e.g. the kinds of values a pull request asks for
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, AbiExample)]
+pub struct CrdsDataKinds(u32);
+
+impl CrdsDataKinds {
+    const ALL: u32 = (1 << (CrdsDataKind::DuplicateShred as u32 + 1)) - 1;
+
+    pub fn all() -> Self {
+        Self(Self::ALL)
+    }
+
+    pub fn is_all(&self) -> bool {
+        self.0 & Self::ALL == Self::ALL
+    }
+
+    pub fn contains(&self, kind: CrdsDataKind) -> bool {
+        self.0 & (1 << kind as u32) != 0
+    }
+
+    pub fn insert(&mut self, kind: CrdsDataKind) {
+        self.0 |= 1 << kind as u32;
+    }
+}
+
+impl Default for CrdsDataKinds {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl std::iter::FromIterator<CrdsDataKind> for CrdsDataKinds {
+    fn from_iter<I: IntoIterator<Item = CrdsDataKind>>(kinds: I) -> Self {
+        let mut out = Self(0);
+        for kind in kinds {
+            out.insert(kind);
+        }
+        out
+    }
+}
+
+impl Sanitize for CrdsDataKinds {
+    fn sanitize(&self) -> Result<(), SanitizeError> {
+        if self.0 & !Self::ALL != 0 {
+            return Err(SanitizeError::ValueOutOfBounds);
+        }
+        Ok(())
+    }
+}
+
 impl CrdsData {
+    pub fn kind(&self) -> CrdsDataKind {
+        match self {
+            CrdsData::ContactInfo(_) => CrdsDataKind::ContactInfo,
+            CrdsData::Vote(_, _) => CrdsDataKind::Vote,
+            CrdsData::LowestSlot(_, _) => CrdsDataKind::LowestSlot,
+            CrdsData::SnapshotHashes(_) => CrdsDataKind::SnapshotHashes,
+            CrdsData::AccountsHashes(_) => CrdsDataKind::AccountsHashes,
+            CrdsData::EpochSlots(_, _) => CrdsDataKind::EpochSlots,
+            CrdsData::LegacyVersion(_) => CrdsDataKind::LegacyVersion,
+            CrdsData::Version(_) => CrdsDataKind::Version,
+            CrdsData::NodeInstance(_) => CrdsDataKind::NodeInstance,
+            CrdsData::DuplicateShred(_, _) => CrdsDataKind::DuplicateShred,
+        }
+    }
+
     /// New random CrdsData for tests and benchmarks.
     fn new_rand<R: Rng>(rng: &mut R, pubkey: Option<Pubkey>) -> CrdsData {
         let kind = rng.gen_range(0, 7);
@@ -716,6 +795,30 @@ mod test {
         assert_eq!(v.label(), CrdsValueLabel::LowestSlot(key));
     }
 
+    #[test]
+    fn test_crds_data_kinds() {
+        let kinds = CrdsDataKinds::all();
+        assert!(kinds.is_all());
+        assert_eq!(kinds, CrdsDataKinds::default());
+        assert!(kinds.sanitize().is_ok());
+        let kinds: CrdsDataKinds = vec![CrdsDataKind::ContactInfo, CrdsDataKind::Version]
+            .into_iter()
+            .collect();
+        assert!(!kinds.is_all());
+        assert!(kinds.sanitize().is_ok());
+        assert!(kinds.contains(CrdsDataKind::ContactInfo));
+        assert!(kinds.contains(CrdsDataKind::Version));
+        assert!(!kinds.contains(CrdsDataKind::Vote));
+        assert!(!kinds.contains(CrdsDataKind::DuplicateShred));
+        let mut rng = rand::thread_rng();
+        for _ in 0..32 {
+            let value = CrdsValue::new_rand(&mut rng, None);
+            assert!(CrdsDataKinds::all().contains(value.data.kind()));
+        }
+        let kinds: CrdsDataKinds = deserialize(&[0xff, 0xff, 0xff, 0xff]).unwrap();
+        assert_eq!(kinds.sanitize(), Err(SanitizeError::ValueOutOfBounds));
+    }
+
     #[test]
     fn test_lowest_slot_sanitize() {
         let ls = LowestSlot::new(Pubkey::default(), 0, 0);
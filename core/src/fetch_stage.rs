@@ -1,4 +1,5 @@
-//! The `fetch_stage` batches input from a UDP socket and sends it to a channel.
+//! The `fetch_stage` batches input from a UDP socket, and optionally from QUIC connections,
+//! and sends it to a channel.
 
 use crate::banking_stage::HOLD_TRANSACTIONS_SLOT_OFFSET;
 use crate::poh_recorder::PohRecorder;
@@ -7,18 +8,29 @@ use solana_measure::thread_mem_usage;
 use solana_metrics::{inc_new_counter_debug, inc_new_counter_info};
 use solana_perf::packet::PacketsRecycler;
 use solana_perf::recycler::Recycler;
-use solana_sdk::clock::DEFAULT_TICKS_PER_SLOT;
-use solana_streamer::streamer::{self, PacketReceiver, PacketSender};
+use solana_sdk::{clock::DEFAULT_TICKS_PER_SLOT, signature::Keypair};
+use solana_streamer::{
+    quic::{self, StakedNodes},
+    streamer::{self, PacketReceiver, PacketSender},
+};
 use std::net::UdpSocket;
 use std::sync::atomic::AtomicBool;
 use std::sync::mpsc::{channel, RecvTimeoutError};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread::{self, Builder, JoinHandle};
 
 pub struct FetchStage {
     thread_hdls: Vec<JoinHandle<()>>,
 }
 
+/// The QUIC transaction socket of the TPU, along with the identity its certificate is issued
+/// for and the stakes that weight the stream quotas of its connections
+pub struct TpuQuicConfig {
+    pub socket: UdpSocket,
+    pub keypair: Arc<Keypair>,
+    pub staked_nodes: Arc<RwLock<StakedNodes>>,
+}
+
 impl FetchStage {
     #[allow(clippy::new_ret_no_self)]
     pub fn new(
@@ -38,6 +50,7 @@ impl FetchStage {
                 &poh_recorder,
                 None,
                 coalesce_ms,
+                None,
             ),
             receiver,
         )
@@ -50,6 +63,7 @@ impl FetchStage {
         poh_recorder: &Arc<Mutex<PohRecorder>>,
         allocated_packet_limit: Option<u32>,
         coalesce_ms: u64,
+        tpu_quic: Option<TpuQuicConfig>,
     ) -> Self {
         let tx_sockets = sockets.into_iter().map(Arc::new).collect();
         let tpu_forwards_sockets = tpu_forwards_sockets.into_iter().map(Arc::new).collect();
@@ -61,6 +75,7 @@ impl FetchStage {
             &poh_recorder,
             allocated_packet_limit,
             coalesce_ms,
+            tpu_quic,
         )
     }
 
@@ -107,10 +122,24 @@ impl FetchStage {
         poh_recorder: &Arc<Mutex<PohRecorder>>,
         limit: Option<u32>,
         coalesce_ms: u64,
+        tpu_quic: Option<TpuQuicConfig>,
     ) -> Self {
         let recycler: PacketsRecycler =
             Recycler::warmed(1000, 1024, limit, "fetch_stage_recycler_shrink");
 
+        let tpu_quic_thread = tpu_quic.map(|tpu_quic| {
+            quic::spawn_server(
+                tpu_quic.socket,
+                &tpu_quic.keypair,
+                sender.clone(),
+                recycler.clone(),
+                exit.clone(),
+                tpu_quic.staked_nodes,
+                coalesce_ms,
+            )
+            .expect("TPU QUIC server")
+        });
+
         let tpu_threads = sockets.into_iter().map(|socket| {
             streamer::receiver(
                 socket,
@@ -157,6 +186,7 @@ impl FetchStage {
 
         let mut thread_hdls: Vec<_> = tpu_threads.chain(tpu_forwards_threads).collect();
         thread_hdls.push(fwd_thread_hdl);
+        thread_hdls.extend(tpu_quic_thread);
         Self { thread_hdls }
     }
 
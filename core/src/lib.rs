@@ -8,6 +8,8 @@
 //!
 
 pub mod accounts_hash_verifier;
+pub mod accountsdb_plugin_manager;
+pub mod accountsdb_plugin_service;
 pub mod banking_stage;
 pub mod bigtable_upload_service;
 pub mod broadcast_stage;
@@ -71,10 +73,13 @@ pub mod sigverify;
 pub mod sigverify_shreds;
 pub mod sigverify_stage;
 pub mod snapshot_packager_service;
+pub mod staked_nodes_updater_service;
 pub mod test_validator;
 pub mod tpu;
+pub mod transaction_notifier_interface;
 pub mod transaction_status_service;
 pub mod tree_diff;
+pub mod turbine_tree;
 pub mod tvu;
 pub mod validator;
 pub mod verified_vote_packets;
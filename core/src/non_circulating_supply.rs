@@ -21,12 +21,9 @@ pub fn calculate_non_circulating_supply(bank: &Arc<Bank>) -> NonCirculatingSuppl
     let withdraw_authority_list = withdraw_authority();
 
     let clock = bank.clock();
-    let stake_accounts = if bank
-        .rc
-        .accounts
-        .accounts_db
-        .account_indexes
-        .contains(&AccountIndex::ProgramId)
+    let account_indexes = &bank.rc.accounts.accounts_db.account_indexes;
+    let stake_accounts = if account_indexes.contains(&AccountIndex::ProgramId)
+        && account_indexes.include_key(&solana_stake_program::id())
     {
         bank.get_filtered_indexed_accounts(
             &IndexKey::ProgramId(solana_stake_program::id()),
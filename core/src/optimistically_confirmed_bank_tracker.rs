@@ -29,6 +29,7 @@ impl OptimisticallyConfirmedBank {
     }
 }
 
+#[derive(Clone)]
 pub enum BankNotification {
     OptimisticallyConfirmed(Slot),
     Frozen(Arc<Bank>),
@@ -61,6 +62,7 @@ impl OptimisticallyConfirmedBankTracker {
         bank_forks: Arc<RwLock<BankForks>>,
         optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
         subscriptions: Arc<RpcSubscriptions>,
+        bank_notification_subscriber: Option<BankNotificationSender>,
     ) -> Self {
         let exit_ = exit.clone();
         let mut pending_optimistically_confirmed_banks = HashSet::new();
@@ -77,6 +79,7 @@ impl OptimisticallyConfirmedBankTracker {
                     &optimistically_confirmed_bank,
                     &subscriptions,
                     &mut pending_optimistically_confirmed_banks,
+                    &bank_notification_subscriber,
                 ) {
                     break;
                 }
@@ -91,8 +94,14 @@ impl OptimisticallyConfirmedBankTracker {
         optimistically_confirmed_bank: &Arc<RwLock<OptimisticallyConfirmedBank>>,
         subscriptions: &Arc<RpcSubscriptions>,
         mut pending_optimistically_confirmed_banks: &mut HashSet<Slot>,
+        bank_notification_subscriber: &Option<BankNotificationSender>,
     ) -> Result<(), RecvTimeoutError> {
         let notification = receiver.recv_timeout(Duration::from_secs(1))?;
+        if let Some(bank_notification_subscriber) = bank_notification_subscriber {
+            bank_notification_subscriber
+                .send(notification.clone())
+                .unwrap_or_else(|err| warn!("bank_notification_subscriber failed: {:?}", err));
+        }
         Self::process_notification(
             notification,
             bank_forks,
@@ -6,7 +6,7 @@ use crate::{
     cluster_slots::ClusterSlots,
     repair_weight::RepairWeight,
     result::Result,
-    serve_repair::{RepairType, ServeRepair, DEFAULT_NONCE},
+    serve_repair::{AncestorHashesResponse, RepairType, ServeRepair, DEFAULT_NONCE},
 };
 use crossbeam_channel::{Receiver as CrossbeamReceiver, Sender as CrossbeamSender};
 use solana_ledger::{
@@ -17,9 +17,12 @@ use solana_measure::measure::Measure;
 use solana_runtime::{
     bank::Bank, bank_forks::BankForks, commitment::VOTE_THRESHOLD_SIZE, contains::Contains,
 };
-use solana_sdk::{clock::Slot, epoch_schedule::EpochSchedule, pubkey::Pubkey, timing::timestamp};
+use solana_sdk::{
+    clock::Slot, epoch_schedule::EpochSchedule, hash::Hash, packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey, timing::timestamp,
+};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
     iter::Iterator,
     net::SocketAddr,
     net::UdpSocket,
@@ -70,6 +73,7 @@ pub struct RepairStats {
     pub shred: RepairStatsGroup,
     pub highest_shred: RepairStatsGroup,
     pub orphan: RepairStatsGroup,
+    pub ancestor_hashes: RepairStatsGroup,
     pub get_best_orphans_us: u64,
     pub get_best_shreds_us: u64,
 }
@@ -104,6 +108,16 @@ pub const MAX_REPAIR_PER_DUPLICATE: usize = 20;
 pub const MAX_DUPLICATE_WAIT_MS: usize = 10_000;
 pub const REPAIR_MS: u64 = 100;
 pub const MAX_ORPHANS: usize = 5;
+/// Number of stake-weighted peers an `AncestorHashes` request for a slot is sent to
+pub const ANCESTOR_HASHES_SAMPLE_SIZE: usize = 8;
+/// Time to wait for all sampled peers to respond before deciding with the responses received
+pub const ANCESTOR_HASHES_TIMEOUT_MS: u64 = 2_000;
+/// Time before ancestor hashes are requested again for a slot that is still dead or duplicate
+pub const ANCESTOR_HASHES_RETRY_MS: u64 = 10_000;
+/// Fraction of the stake of the sampled peers which must return the same ancestor hashes before
+/// they are compared with the local chain.  A sample rarely holds much of the epoch's stake on
+/// a large cluster, so the threshold can't be a fraction of the epoch's total stake
+pub const ANCESTOR_HASHES_THRESHOLD: f64 = 0.34;
 
 pub struct RepairInfo {
     pub bank_forks: Arc<RwLock<BankForks>>,
@@ -132,6 +146,16 @@ pub struct DuplicateSlotRepairStatus {
     repair_pubkey_and_addr: Option<(Pubkey, SocketAddr)>,
 }
 
+/// An `AncestorHashes` request sent for a dead or duplicate slot
+struct AncestorHashesRequestStatus {
+    start: u64,
+    nonce: Nonce,
+    /// The sampled peers, keyed by their serve repair address, and their responses
+    peers: HashMap<SocketAddr, (Pubkey, Option<Vec<(Slot, Hash)>>)>,
+    /// Set once the responses were compared with the local chain
+    decided: bool,
+}
+
 pub struct RepairService {
     t_repair: JoinHandle<()>,
 }
@@ -146,6 +170,12 @@ impl RepairService {
         cluster_slots: Arc<ClusterSlots>,
         verified_vote_receiver: VerifiedVoteReceiver,
     ) -> Self {
+        // Responses to ancestor hashes requests are not shreds, so they are received on their
+        // own socket rather than on the repair socket
+        let ancestor_hashes_socket =
+            UdpSocket::bind(SocketAddr::new(repair_socket.local_addr().unwrap().ip(), 0))
+                .expect("bind ancestor hashes socket");
+        ancestor_hashes_socket.set_nonblocking(true).unwrap();
         let t_repair = Builder::new()
             .name("solana-repair-service".to_string())
             .spawn(move || {
@@ -153,6 +183,7 @@ impl RepairService {
                     &blockstore,
                     &exit,
                     &repair_socket,
+                    &ancestor_hashes_socket,
                     cluster_info,
                     repair_info,
                     &cluster_slots,
@@ -168,18 +199,21 @@ impl RepairService {
         blockstore: &Blockstore,
         exit: &AtomicBool,
         repair_socket: &UdpSocket,
+        ancestor_hashes_socket: &UdpSocket,
         cluster_info: Arc<ClusterInfo>,
         repair_info: RepairInfo,
         cluster_slots: &ClusterSlots,
         verified_vote_receiver: VerifiedVoteReceiver,
     ) {
         let mut repair_weight = RepairWeight::new(repair_info.bank_forks.read().unwrap().root());
-        let serve_repair = ServeRepair::new(cluster_info.clone());
+        let serve_repair = ServeRepair::new(cluster_info.clone(), repair_info.bank_forks.clone());
         let id = cluster_info.id();
         let mut repair_stats = RepairStats::default();
         let mut repair_timing = RepairTiming::default();
         let mut last_stats = Instant::now();
-        let duplicate_slot_repair_statuses: HashMap<Slot, DuplicateSlotRepairStatus> =
+        let mut duplicate_slot_repair_statuses: HashMap<Slot, DuplicateSlotRepairStatus> =
+            HashMap::new();
+        let mut ancestor_hashes_request_statuses: HashMap<Slot, AncestorHashesRequestStatus> =
             HashMap::new();
 
         loop {
@@ -197,6 +231,7 @@ impl RepairService {
                 // Purge outdated slots from the weighting heuristic
                 set_root_elapsed = Measure::start("set_root_elapsed");
                 repair_weight.set_root(new_root);
+                duplicate_slot_repair_statuses.retain(|slot, _| *slot > new_root);
                 set_root_elapsed.stop();
 
                 // Add new votes to the weighting heuristic
@@ -222,20 +257,35 @@ impl RepairService {
                     root_bank.epoch_schedule(),
                 );
                 add_votes_elapsed.stop();
-                /*let new_duplicate_slots = Self::find_new_duplicate_slots(
-                    &duplicate_slot_repair_statuses,
-                    blockstore,
-                    cluster_slots,
-                    &root_bank,
+
+                // Compare the ancestors of dead and duplicate slots with the ones sampled
+                // peers have, dumping any slot of which we have the wrong version
+                Self::receive_ancestor_hashes_responses(
+                    ancestor_hashes_socket,
+                    &mut ancestor_hashes_request_statuses,
                 );
-                Self::process_new_duplicate_slots(
-                    &new_duplicate_slots,
+                Self::process_ancestor_hashes_responses(
+                    &mut ancestor_hashes_request_statuses,
                     &mut duplicate_slot_repair_statuses,
                     cluster_slots,
                     &root_bank,
                     blockstore,
                     &serve_repair,
-                    &repair_info.duplicate_slots_reset_sender,
+                    &repair_info,
+                );
+                let ancestor_hashes_slots = Self::find_ancestor_hashes_slots(
+                    &ancestor_hashes_request_statuses,
+                    &duplicate_slot_repair_statuses,
+                    blockstore,
+                    new_root,
+                );
+                Self::send_ancestor_hashes_requests(
+                    &ancestor_hashes_slots,
+                    &mut ancestor_hashes_request_statuses,
+                    &root_bank,
+                    &serve_repair,
+                    &mut repair_stats,
+                    ancestor_hashes_socket,
                     &repair_info.repair_validators,
                 );
                 Self::generate_and_send_duplicate_repairs(
@@ -246,7 +296,7 @@ impl RepairService {
                     &mut repair_stats,
                     &repair_socket,
                     &repair_info.repair_validators,
-                );*/
+                );
 
                 repair_weight.get_best_weighted_repairs(
                     blockstore,
@@ -286,7 +336,8 @@ impl RepairService {
             if last_stats.elapsed().as_secs() > 2 {
                 let repair_total = repair_stats.shred.count
                     + repair_stats.highest_shred.count
-                    + repair_stats.orphan.count;
+                    + repair_stats.orphan.count
+                    + repair_stats.ancestor_hashes.count;
                 let slot_to_count: Vec<_> = repair_stats
                     .shred
                     .slot_pubkeys
@@ -312,6 +363,11 @@ impl RepairService {
                         ("shred-count", repair_stats.shred.count, i64),
                         ("highest-shred-count", repair_stats.highest_shred.count, i64),
                         ("orphan-count", repair_stats.orphan.count, i64),
+                        (
+                            "ancestor-hashes-count",
+                            repair_stats.ancestor_hashes.count,
+                            i64
+                        ),
                         ("repair-highest-slot", repair_stats.highest_shred.max, i64),
                         ("repair-orphan", repair_stats.orphan.max, i64),
                     );
@@ -433,7 +489,6 @@ impl RepairService {
         }
     }
 
-    #[allow(dead_code)]
     fn generate_duplicate_repairs_for_slot(
         blockstore: &Blockstore,
         slot: Slot,
@@ -458,7 +513,6 @@ impl RepairService {
         }
     }
 
-    #[allow(dead_code)]
     fn generate_and_send_duplicate_repairs(
         duplicate_slot_repair_statuses: &mut HashMap<Slot, DuplicateSlotRepairStatus>,
         cluster_slots: &ClusterSlots,
@@ -506,7 +560,6 @@ impl RepairService {
         })
     }
 
-    #[allow(dead_code)]
     fn serialize_and_send_request(
         repair_type: &RepairType,
         repair_socket: &UdpSocket,
@@ -522,7 +575,6 @@ impl RepairService {
         Ok(())
     }
 
-    #[allow(dead_code)]
     fn update_duplicate_slot_repair_addr(
         slot: Slot,
         status: &mut DuplicateSlotRepairStatus,
@@ -544,7 +596,6 @@ impl RepairService {
         }
     }
 
-    #[allow(dead_code)]
     fn process_new_duplicate_slots(
         new_duplicate_slots: &[Slot],
         duplicate_slot_repair_statuses: &mut HashMap<Slot, DuplicateSlotRepairStatus>,
@@ -648,6 +699,236 @@ impl RepairService {
             .collect()
     }
 
+    /// Dead and duplicate slots above the root that no ancestor hashes request is outstanding
+    /// for, and that are not already being repaired from a single peer
+    fn find_ancestor_hashes_slots(
+        ancestor_hashes_request_statuses: &HashMap<Slot, AncestorHashesRequestStatus>,
+        duplicate_slot_repair_statuses: &HashMap<Slot, DuplicateSlotRepairStatus>,
+        blockstore: &Blockstore,
+        root: Slot,
+    ) -> Vec<Slot> {
+        let dead_slots_iter = blockstore
+            .dead_slots_iterator(root + 1)
+            .expect("Couldn't get dead slots iterator from blockstore");
+        let duplicate_slots_iter = blockstore
+            .duplicate_slots_iterator(root + 1)
+            .expect("Couldn't get duplicate slots iterator from blockstore");
+        dead_slots_iter
+            .chain(duplicate_slots_iter)
+            .filter(|slot| {
+                !ancestor_hashes_request_statuses.contains_key(slot)
+                    && !duplicate_slot_repair_statuses.contains_key(slot)
+            })
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Stake of each node in the epoch of `slot`
+    fn node_stakes(root_bank: &Bank, slot: Slot) -> HashMap<Pubkey, u64> {
+        let epoch = root_bank.get_epoch_and_slot_index(slot).0;
+        root_bank
+            .epoch_stakes(epoch)
+            .map(|epoch_stakes| {
+                epoch_stakes
+                    .node_id_to_vote_accounts()
+                    .iter()
+                    .map(|(node_id, vote_accounts)| (*node_id, vote_accounts.total_stake))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn send_ancestor_hashes_requests(
+        slots: &[Slot],
+        ancestor_hashes_request_statuses: &mut HashMap<Slot, AncestorHashesRequestStatus>,
+        root_bank: &Bank,
+        serve_repair: &ServeRepair,
+        repair_stats: &mut RepairStats,
+        ancestor_hashes_socket: &UdpSocket,
+        repair_validators: &Option<HashSet<Pubkey>>,
+    ) {
+        for slot in slots {
+            let stakes = Self::node_stakes(root_bank, *slot);
+            let peers = serve_repair.ancestor_hashes_request_peers(
+                *slot,
+                &stakes,
+                repair_validators,
+                ANCESTOR_HASHES_SAMPLE_SIZE,
+            );
+            if peers.is_empty() {
+                continue;
+            }
+            let nonce = rand::random();
+            for (repair_pubkey, repair_addr) in &peers {
+                if let Err(e) = Self::serialize_and_send_request(
+                    &RepairType::AncestorHashes(*slot),
+                    ancestor_hashes_socket,
+                    repair_pubkey,
+                    repair_addr,
+                    serve_repair,
+                    repair_stats,
+                    nonce,
+                ) {
+                    info!(
+                        "ancestor hashes req send_to {} ({}) error {:?}",
+                        repair_pubkey, repair_addr, e
+                    );
+                }
+            }
+            ancestor_hashes_request_statuses.insert(
+                *slot,
+                AncestorHashesRequestStatus {
+                    start: timestamp(),
+                    nonce,
+                    peers: peers
+                        .into_iter()
+                        .map(|(pubkey, addr)| (addr, (pubkey, None)))
+                        .collect(),
+                    decided: false,
+                },
+            );
+        }
+    }
+
+    fn receive_ancestor_hashes_responses(
+        ancestor_hashes_socket: &UdpSocket,
+        ancestor_hashes_request_statuses: &mut HashMap<Slot, AncestorHashesRequestStatus>,
+    ) {
+        let mut buf = [0u8; PACKET_DATA_SIZE];
+        while let Ok((size, from_addr)) = ancestor_hashes_socket.recv_from(&mut buf) {
+            let (response, nonce) = match AncestorHashesResponse::from_packet_data(&buf[..size]) {
+                Some(response) => response,
+                None => continue,
+            };
+            // Responses start with the requested slot
+            let slot = response.hashes[0].0;
+            let status = match ancestor_hashes_request_statuses.get_mut(&slot) {
+                Some(status) if status.nonce == nonce && !status.decided => status,
+                _ => continue,
+            };
+            // Only the first response of each sampled peer is counted
+            if let Some((_, hashes)) = status.peers.get_mut(&from_addr) {
+                if hashes.is_none() {
+                    *hashes = Some(response.hashes);
+                }
+            }
+        }
+    }
+
+    /// The ancestor hashes returned by peers holding more than `ANCESTOR_HASHES_THRESHOLD` of
+    /// the stake of all sampled peers, whether they responded or not, along with the peers which
+    /// returned them.  Unstaked peers have nothing at stake, so their responses are ignored
+    fn majority_ancestor_hashes(
+        status: &AncestorHashesRequestStatus,
+        stakes: &HashMap<Pubkey, u64>,
+    ) -> Option<(Vec<(Slot, Hash)>, Vec<(Pubkey, SocketAddr)>)> {
+        let mut sampled_stake = 0;
+        let mut responses: HashMap<&Vec<(Slot, Hash)>, (u64, Vec<(Pubkey, SocketAddr)>)> =
+            HashMap::new();
+        for (addr, (pubkey, hashes)) in &status.peers {
+            let stake = stakes.get(pubkey).copied().unwrap_or_default();
+            sampled_stake += stake;
+            if let (Some(hashes), true) = (hashes, stake > 0) {
+                let (response_stake, peers) = responses.entry(hashes).or_default();
+                *response_stake += stake;
+                peers.push((*pubkey, *addr));
+            }
+        }
+        let threshold = sampled_stake as f64 * ANCESTOR_HASHES_THRESHOLD;
+        let mut agreed = responses
+            .into_iter()
+            .filter(|(_, (stake, _))| *stake as f64 > threshold);
+        // If more than one version passes the threshold the cluster itself is split
+        match (agreed.next(), agreed.next()) {
+            (Some((hashes, (_, peers))), None) => Some((hashes.clone(), peers)),
+            _ => None,
+        }
+    }
+
+    /// The earliest slot after the root in `ancestor_hashes` of which the local version
+    /// differs, either because its bank hash doesn't match or because it is dead
+    fn find_mismatched_ancestor(
+        ancestor_hashes: &[(Slot, Hash)],
+        bank_forks: &BankForks,
+        blockstore: &Blockstore,
+    ) -> Option<Slot> {
+        let root = bank_forks.root();
+        ancestor_hashes
+            .iter()
+            .rev()
+            .filter(|(slot, _)| *slot > root)
+            .find(|(slot, hash)| match bank_forks.get(*slot) {
+                Some(bank) if bank.is_frozen() => bank.hash() != *hash,
+                _ => blockstore.is_dead(*slot),
+            })
+            .map(|(slot, _)| *slot)
+    }
+
+    /// Compare the responses of requests which are complete or timed out with the local
+    /// chain, and dump the earliest slot of which the cluster has a different version
+    fn process_ancestor_hashes_responses(
+        ancestor_hashes_request_statuses: &mut HashMap<Slot, AncestorHashesRequestStatus>,
+        duplicate_slot_repair_statuses: &mut HashMap<Slot, DuplicateSlotRepairStatus>,
+        cluster_slots: &ClusterSlots,
+        root_bank: &Bank,
+        blockstore: &Blockstore,
+        serve_repair: &ServeRepair,
+        repair_info: &RepairInfo,
+    ) {
+        let now = timestamp();
+        ancestor_hashes_request_statuses.retain(|slot, status| {
+            *slot > root_bank.slot()
+                && !(status.decided && now.saturating_sub(status.start) >= ANCESTOR_HASHES_RETRY_MS)
+        });
+        for (slot, status) in ancestor_hashes_request_statuses.iter_mut() {
+            let all_responded = status.peers.values().all(|(_, hashes)| hashes.is_some());
+            if status.decided
+                || (!all_responded && now.saturating_sub(status.start) < ANCESTOR_HASHES_TIMEOUT_MS)
+            {
+                continue;
+            }
+            status.decided = true;
+            let stakes = Self::node_stakes(root_bank, *slot);
+            let (ancestor_hashes, agreeing_peers) =
+                match Self::majority_ancestor_hashes(status, &stakes) {
+                    Some(majority) => majority,
+                    None => continue,
+                };
+            let mismatched_slot = Self::find_mismatched_ancestor(
+                &ancestor_hashes,
+                &repair_info.bank_forks.read().unwrap(),
+                blockstore,
+            );
+            let mismatched_slot = match mismatched_slot {
+                Some(mismatched_slot)
+                    if !duplicate_slot_repair_statuses.contains_key(&mismatched_slot) =>
+                {
+                    mismatched_slot
+                }
+                _ => continue,
+            };
+            warn!(
+                "Ancestor hashes of slot {} from sampled peers disagree with ours at slot {}",
+                slot, mismatched_slot
+            );
+            Self::process_new_duplicate_slots(
+                &[mismatched_slot],
+                duplicate_slot_repair_statuses,
+                cluster_slots,
+                root_bank,
+                blockstore,
+                serve_repair,
+                &repair_info.duplicate_slots_reset_sender,
+                &repair_info.repair_validators,
+            );
+            // Repair from a peer known to have the cluster's version of the slot
+            if let Some(status) = duplicate_slot_repair_statuses.get_mut(&mismatched_slot) {
+                status.repair_pubkey_and_addr = agreeing_peers.first().copied();
+            }
+        }
+    }
+
     pub fn join(self) -> thread::Result<()> {
         self.t_repair.join()
     }
@@ -1041,7 +1322,10 @@ mod test {
         let cluster_info = Arc::new(ClusterInfo::new_with_invalid_keypair(
             Node::new_localhost().info,
         ));
-        let serve_repair = ServeRepair::new(cluster_info.clone());
+        let serve_repair = ServeRepair::new(
+            cluster_info.clone(),
+            Arc::new(RwLock::new(BankForks::new(Bank::default()))),
+        );
         let valid_repair_peer = Node::new_localhost().info;
 
         // Signal that this peer has completed the dead slot, and is thus
@@ -1171,6 +1455,232 @@ mod test {
         assert!(reset_receiver.try_recv().is_ok());
     }
 
+    #[test]
+    pub fn test_find_ancestor_hashes_slots() {
+        let blockstore_path = get_tmp_ledger_path!();
+        let blockstore = Blockstore::open(&blockstore_path).unwrap();
+        let mut ancestor_hashes_request_statuses = HashMap::new();
+        let mut duplicate_slot_repair_statuses = HashMap::new();
+        let (shreds, _) = make_slot_entries(9, 0, 1);
+        blockstore
+            .store_duplicate_slot(9, shreds[0].payload.clone(), shreds[0].payload.clone())
+            .unwrap();
+        for slot in &[2, 5, 9, 11] {
+            blockstore.set_dead_slot(*slot).unwrap();
+        }
+        assert_eq!(
+            RepairService::find_ancestor_hashes_slots(
+                &ancestor_hashes_request_statuses,
+                &duplicate_slot_repair_statuses,
+                &blockstore,
+                2,
+            ),
+            vec![5, 9, 11]
+        );
+
+        // Slots with outstanding requests or being repaired are skipped
+        ancestor_hashes_request_statuses.insert(
+            9,
+            AncestorHashesRequestStatus {
+                start: timestamp(),
+                nonce: 0,
+                peers: HashMap::new(),
+                decided: false,
+            },
+        );
+        duplicate_slot_repair_statuses.insert(11, DuplicateSlotRepairStatus::default());
+        assert_eq!(
+            RepairService::find_ancestor_hashes_slots(
+                &ancestor_hashes_request_statuses,
+                &duplicate_slot_repair_statuses,
+                &blockstore,
+                2,
+            ),
+            vec![5]
+        );
+    }
+
+    #[test]
+    pub fn test_process_ancestor_hashes_responses() {
+        let blockstore_path = get_tmp_ledger_path!();
+        let blockstore = Blockstore::open(&blockstore_path).unwrap();
+        let cluster_slots = ClusterSlots::default();
+        let serve_repair = ServeRepair::new_with_invalid_keypair(Node::new_localhost().info);
+        let mut duplicate_slot_repair_statuses = HashMap::new();
+        let mut ancestor_hashes_request_statuses = HashMap::new();
+        let (shreds, _) = make_many_slot_entries(0, 3, 1);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+
+        let keypairs = ValidatorVoteKeypairs::new_rand();
+        let GenesisConfigInfo { genesis_config, .. } =
+            genesis_utils::create_genesis_config_with_vote_accounts(
+                1_000_000_000,
+                &[&keypairs],
+                vec![10000],
+            );
+        let bank0 = Bank::new(&genesis_config);
+        bank0.freeze();
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank0)));
+        let (bank1, bank2) = {
+            let mut bank_forks = bank_forks.write().unwrap();
+            let bank1 = Bank::new_from_parent(&bank_forks[0], &Pubkey::default(), 1);
+            bank1.freeze();
+            let bank1 = bank_forks.insert(bank1);
+            let bank2 = Bank::new_from_parent(&bank1, &Pubkey::default(), 2);
+            bank2.freeze();
+            (bank1, bank_forks.insert(bank2))
+        };
+        let root_bank = bank_forks.read().unwrap().root_bank();
+        let (reset_sender, reset_receiver) = unbounded();
+        let repair_info = RepairInfo {
+            bank_forks,
+            epoch_schedule: EpochSchedule::default(),
+            duplicate_slots_reset_sender: reset_sender,
+            repair_validators: None,
+        };
+
+        // The staked peer has a different version of slot 2, while the unstaked ones agree
+        // with the local chain
+        let staked_peer = (
+            keypairs.node_keypair.pubkey(),
+            socketaddr!("127.0.0.1:1234"),
+        );
+        let staked_response = vec![
+            (2, Hash::new_unique()),
+            (1, bank1.hash()),
+            (0, root_bank.hash()),
+        ];
+        let local_response = vec![(2, bank2.hash()), (1, bank1.hash()), (0, root_bank.hash())];
+        let mut peers = HashMap::new();
+        peers.insert(staked_peer.1, (staked_peer.0, Some(staked_response)));
+        peers.insert(
+            socketaddr!("127.0.0.1:1235"),
+            (Pubkey::new_unique(), Some(local_response.clone())),
+        );
+        // Requests are only decided once all peers responded or after a timeout
+        let unresponsive_addr = socketaddr!("127.0.0.1:1236");
+        peers.insert(unresponsive_addr, (Pubkey::new_unique(), None));
+        ancestor_hashes_request_statuses.insert(
+            2,
+            AncestorHashesRequestStatus {
+                start: timestamp(),
+                nonce: 0,
+                peers,
+                decided: false,
+            },
+        );
+        let process_responses =
+            |ancestor_hashes_request_statuses: &mut HashMap<_, _>,
+             duplicate_slot_repair_statuses: &mut HashMap<_, _>| {
+                RepairService::process_ancestor_hashes_responses(
+                    ancestor_hashes_request_statuses,
+                    duplicate_slot_repair_statuses,
+                    &cluster_slots,
+                    &root_bank,
+                    &blockstore,
+                    &serve_repair,
+                    &repair_info,
+                )
+            };
+        process_responses(
+            &mut ancestor_hashes_request_statuses,
+            &mut duplicate_slot_repair_statuses,
+        );
+        assert!(!ancestor_hashes_request_statuses[&2].decided);
+
+        ancestor_hashes_request_statuses
+            .get_mut(&2)
+            .unwrap()
+            .peers
+            .remove(&unresponsive_addr);
+        process_responses(
+            &mut ancestor_hashes_request_statuses,
+            &mut duplicate_slot_repair_statuses,
+        );
+        assert!(ancestor_hashes_request_statuses[&2].decided);
+
+        // Slot 2 is dumped and repaired from the staked peer
+        assert_eq!(reset_receiver.try_recv(), Ok(2));
+        assert_eq!(
+            duplicate_slot_repair_statuses[&2].repair_pubkey_and_addr,
+            Some(staked_peer)
+        );
+    }
+
+    #[test]
+    pub fn test_majority_ancestor_hashes() {
+        let bank_forks = BankForks::new(Bank::default());
+        let blockstore_path = get_tmp_ledger_path!();
+        let blockstore = Blockstore::open(&blockstore_path).unwrap();
+        let mut status = AncestorHashesRequestStatus {
+            start: timestamp(),
+            nonce: 0,
+            peers: HashMap::new(),
+            decided: false,
+        };
+        // The sampled peers hold less than a tenth of the epoch's stake
+        let mut stakes: HashMap<_, _> = vec![(Pubkey::new_unique(), 1_000)].into_iter().collect();
+        let hashes_a = vec![(4, Hash::new_unique()), (2, Hash::new_unique())];
+        let hashes_b = vec![(4, Hash::new_unique()), (3, Hash::new_unique())];
+        fn insert_response(
+            status: &mut AncestorHashesRequestStatus,
+            stakes: &mut HashMap<Pubkey, u64>,
+            port: u16,
+            stake: u64,
+            hashes: Option<&Vec<(Slot, Hash)>>,
+        ) {
+            let pubkey = Pubkey::new_unique();
+            stakes.insert(pubkey, stake);
+            status.peers.insert(
+                SocketAddr::from(([127, 0, 0, 1], port)),
+                (pubkey, hashes.cloned()),
+            );
+        }
+
+        // A single unstaked peer which disagrees is all of the responding stake, but is ignored
+        insert_response(&mut status, &mut stakes, 1000, 0, Some(&hashes_a));
+        assert!(RepairService::majority_ancestor_hashes(&status, &stakes).is_none());
+
+        // Sampled peers which don't respond count against the threshold, b: 10 of 50
+        insert_response(&mut status, &mut stakes, 1001, 40, None);
+        insert_response(&mut status, &mut stakes, 1002, 10, Some(&hashes_b));
+        assert!(RepairService::majority_ancestor_hashes(&status, &stakes).is_none());
+
+        // b: 10 + 15 = 25 of 65
+        insert_response(&mut status, &mut stakes, 1003, 15, Some(&hashes_b));
+        let (hashes, peers) = RepairService::majority_ancestor_hashes(&status, &stakes).unwrap();
+        assert_eq!(hashes, hashes_b);
+        assert_eq!(peers.len(), 2);
+
+        // a: 40 of 65 as well, two versions passing the threshold is a split cluster
+        status
+            .peers
+            .get_mut(&SocketAddr::from(([127, 0, 0, 1], 1001)))
+            .unwrap()
+            .1 = Some(hashes_a);
+        assert!(RepairService::majority_ancestor_hashes(&status, &stakes).is_none());
+
+        // Slots which are not replayed yet are unknown, dead ones are mismatched
+        assert_eq!(
+            RepairService::find_mismatched_ancestor(&hashes_b, &bank_forks, &blockstore),
+            None
+        );
+        blockstore.set_dead_slot(3).unwrap();
+        assert_eq!(
+            RepairService::find_mismatched_ancestor(&hashes_b, &bank_forks, &blockstore),
+            Some(3)
+        );
+        // Rooted slots are never reported as mismatched
+        assert_eq!(
+            RepairService::find_mismatched_ancestor(
+                &[(0, Hash::new_unique())],
+                &bank_forks,
+                &blockstore
+            ),
+            None
+        );
+    }
+
     #[test]
     pub fn test_find_new_duplicate_slots() {
         let blockstore_path = get_tmp_ledger_path!();
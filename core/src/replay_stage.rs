@@ -105,6 +105,8 @@ pub struct ReplayStageConfig {
     pub rewards_recorder_sender: Option<RewardsRecorderSender>,
     pub cache_block_time_sender: Option<CacheBlockTimeSender>,
     pub bank_notification_sender: Option<BankNotificationSender>,
+    /// Replay non-conflicting transactions of a block in parallel
+    pub schedule_transactions: bool,
 }
 
 #[derive(Default)]
@@ -265,6 +267,7 @@ impl ReplayStage {
             rewards_recorder_sender,
             cache_block_time_sender,
             bank_notification_sender,
+            schedule_transactions,
         } = config;
 
         trace!("replay stage");
@@ -333,6 +336,7 @@ impl ReplayStage {
                         &bank_notification_sender,
                         &rewards_recorder_sender,
                         &subscriptions,
+                        schedule_transactions,
                     );
                     replay_active_banks_time.stop();
                     Self::report_memory(&allocated, "replay_active_banks", start);
@@ -1000,6 +1004,7 @@ impl ReplayStage {
         replay_vote_sender: &ReplayVoteSender,
         verify_recyclers: &VerifyRecyclers,
         subscriptions: &Arc<RpcSubscriptions>,
+        schedule_transactions: bool,
     ) -> result::Result<usize, BlockstoreProcessorError> {
         let tx_count_before = bank_progress.replay_progress.num_txs;
         let confirm_result = blockstore_processor::confirm_slot(
@@ -1013,6 +1018,7 @@ impl ReplayStage {
             None,
             verify_recyclers,
             false,
+            schedule_transactions,
         );
         let tx_count_after = bank_progress.replay_progress.num_txs;
         let tx_count = tx_count_after - tx_count_before;
@@ -1331,6 +1337,7 @@ impl ReplayStage {
         bank_notification_sender: &Option<BankNotificationSender>,
         rewards_recorder_sender: &Option<RewardsRecorderSender>,
         subscriptions: &Arc<RpcSubscriptions>,
+        schedule_transactions: bool,
     ) -> bool {
         let mut did_complete_bank = false;
         let mut tx_count = 0;
@@ -1379,6 +1386,7 @@ impl ReplayStage {
                     replay_vote_sender,
                     verify_recyclers,
                     subscriptions,
+                    schedule_transactions,
                 );
                 match replay_result {
                     Ok(replay_tx_count) => tx_count += replay_tx_count,
@@ -2586,6 +2594,7 @@ pub(crate) mod tests {
                     block_commitment_cache,
                     OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
                 )),
+                false,
             );
 
             // Check that the erroring bank was marked as dead in the progress map
@@ -2746,6 +2755,8 @@ pub(crate) mod tests {
         let (replay_vote_sender, _replay_vote_receiver) = unbounded();
         let transaction_status_service = TransactionStatusService::new(
             transaction_status_receiver,
+            true,
+            None,
             blockstore,
             &Arc::new(AtomicBool::new(false)),
         );
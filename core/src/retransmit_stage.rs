@@ -2,7 +2,7 @@
 #![allow(clippy::rc_buffer)]
 
 use crate::{
-    cluster_info::{compute_retransmit_peers, ClusterInfo, DATA_PLANE_FANOUT},
+    cluster_info::{compute_retransmit_peers, ClusterInfo},
     cluster_info_vote_listener::VerifiedVoteReceiver,
     cluster_slots::ClusterSlots,
     cluster_slots_service::ClusterSlotsService,
@@ -14,6 +14,7 @@ use crate::{
     result::{Error, Result},
     rpc_completed_slots_service::RpcCompletedSlotsService,
     rpc_subscriptions::RpcSubscriptions,
+    turbine_tree::{self, LegacyTurbineTree, TurbineNode, TurbineTree},
     window_service::{should_retransmit_and_persist, WindowService},
 };
 use crossbeam_channel::Receiver;
@@ -209,8 +210,9 @@ fn update_retransmit_stats(
 
 #[derive(Default)]
 struct EpochStakesCache {
+    // Retransmit peers including self, in the same order as `nodes`.
     peers: Vec<ContactInfo>,
-    stakes_and_index: Vec<(u64, usize)>,
+    nodes: Vec<TurbineNode>,
 }
 
 use crate::packet_hasher::PacketHasher;
@@ -304,6 +306,7 @@ fn retransmit(
     max_slots: &MaxSlots,
     first_shreds_received: &Mutex<BTreeSet<Slot>>,
     rpc_subscriptions: &Option<Arc<RpcSubscriptions>>,
+    turbine_tree: &dyn TurbineTree,
 ) -> Result<()> {
     let timer = Duration::new(1, 0);
     let r_lock = r.lock().unwrap();
@@ -338,10 +341,15 @@ fn retransmit(
         let epoch_staked_nodes = r_bank.epoch_staked_nodes(bank_epoch);
         let (peers, stakes_and_index) =
             cluster_info.sorted_retransmit_peers_and_stakes(epoch_staked_nodes.as_ref());
+        let nodes = turbine_tree::sorted_nodes(&peers, &stakes_and_index);
+        let peers = stakes_and_index
+            .into_iter()
+            .map(|(_, index)| peers[index].clone())
+            .collect();
         {
             let mut epoch_stakes_cache = epoch_stakes_cache.write().unwrap();
             epoch_stakes_cache.peers = peers;
-            epoch_stakes_cache.stakes_and_index = stakes_and_index;
+            epoch_stakes_cache.nodes = nodes;
         }
         {
             let mut sr = shreds_received.lock().unwrap();
@@ -389,26 +397,31 @@ fn retransmit(
                 }
             }
 
+            let leader =
+                leader_schedule_cache.slot_leader_at(packet.meta.slot, Some(r_bank.as_ref()));
             let mut compute_turbine_peers = Measure::start("turbine_start");
-            let (my_index, mut shuffled_stakes_and_index) = ClusterInfo::shuffle_peers_and_index(
-                &my_id,
-                &r_epoch_stakes_cache.peers,
-                &r_epoch_stakes_cache.stakes_and_index,
+            let mut indexes = turbine_tree.shuffle(
+                leader.as_ref(),
+                shred_slot,
                 packet.meta.seed,
+                &r_epoch_stakes_cache.nodes,
             );
-            peers_len = cmp::max(peers_len, shuffled_stakes_and_index.len());
+            // Nodes left out of the tree do not retransmit the shred.
+            let my_index = match indexes
+                .iter()
+                .position(|index| r_epoch_stakes_cache.nodes[*index].id == my_id)
+            {
+                Some(my_index) => my_index,
+                None => continue,
+            };
+            peers_len = cmp::max(peers_len, indexes.len());
             // Until the patch is activated, do the old buggy thing.
             if !enable_turbine_retransmit_peers_patch(shred_slot, root_bank.deref()) {
-                shuffled_stakes_and_index.remove(my_index);
+                indexes.remove(my_index);
             }
-            // split off the indexes, we don't need the stakes anymore
-            let indexes: Vec<_> = shuffled_stakes_and_index
-                .into_iter()
-                .map(|(_, index)| index)
-                .collect();
 
             let (neighbors, children) =
-                compute_retransmit_peers(DATA_PLANE_FANOUT, my_index, &indexes);
+                compute_retransmit_peers(turbine_tree.fanout(), my_index, &indexes);
             let neighbors: Vec<_> = neighbors
                 .into_iter()
                 .filter_map(|index| {
@@ -432,8 +445,6 @@ fn retransmit(
                 .entry(packet.meta.addr().to_string())
                 .or_insert(0) += 1;
 
-            let leader =
-                leader_schedule_cache.slot_leader_at(packet.meta.slot, Some(r_bank.as_ref()));
             let mut retransmit_time = Measure::start("retransmit_to");
             if !packet.meta.forward {
                 ClusterInfo::retransmit_to(&neighbors, packet, leader, sock, true)?;
@@ -480,6 +491,7 @@ fn retransmit(
 /// * `leader_schedule_cache` - The leader schedule to verify shreds
 /// * `cluster_info` - This structure needs to be updated and populated by the bank and via gossip.
 /// * `r` - Receive channel for shreds to be retransmitted to all the layer 1 nodes.
+/// * `turbine_tree` - Computes the peers each shred is retransmitted to.
 pub fn retransmitter(
     sockets: Arc<Vec<UdpSocket>>,
     bank_forks: Arc<RwLock<BankForks>>,
@@ -488,6 +500,7 @@ pub fn retransmitter(
     r: Arc<Mutex<PacketReceiver>>,
     max_slots: &Arc<MaxSlots>,
     rpc_subscriptions: Option<Arc<RpcSubscriptions>>,
+    turbine_tree: Arc<dyn TurbineTree>,
 ) -> Vec<JoinHandle<()>> {
     let stats = Arc::new(RetransmitStats::default());
     let shreds_received = Arc::new(Mutex::new((
@@ -509,6 +522,7 @@ pub fn retransmitter(
             let max_slots = max_slots.clone();
             let first_shreds_received = first_shreds_received.clone();
             let rpc_subscriptions = rpc_subscriptions.clone();
+            let turbine_tree = turbine_tree.clone();
 
             Builder::new()
                 .name("solana-retransmitter".to_string())
@@ -529,6 +543,7 @@ pub fn retransmitter(
                             &max_slots,
                             &first_shreds_received,
                             &rpc_subscriptions,
+                            turbine_tree.as_ref(),
                         ) {
                             match e {
                                 Error::RecvTimeoutError(RecvTimeoutError::Disconnected) => break,
@@ -587,6 +602,7 @@ impl RetransmitStage {
             retransmit_receiver,
             max_slots,
             rpc_subscriptions.clone(),
+            Arc::new(LegacyTurbineTree::default()),
         );
 
         let [rpc_completed_slots_receiver, cluster_completed_slots_receiver] =
@@ -715,6 +731,7 @@ mod tests {
             Arc::new(Mutex::new(retransmit_receiver)),
             &Arc::new(MaxSlots::default()),
             None,
+            Arc::new(LegacyTurbineTree::default()),
         );
         let _thread_hdls = vec![t_retransmit];
 
@@ -36,12 +36,15 @@ use solana_client::{
     rpc_response::*,
 };
 use solana_faucet::faucet::request_airdrop_transaction;
-use solana_ledger::{blockstore::Blockstore, blockstore_db::BlockstoreError, get_tmp_ledger_path};
+use solana_ledger::{
+    blockstore::Blockstore, blockstore_db::BlockstoreError, get_tmp_ledger_path,
+    leader_schedule_cache::LeaderScheduleCache,
+};
 use solana_metrics::inc_new_counter_info;
 use solana_perf::packet::PACKET_DATA_SIZE;
 use solana_runtime::{
     accounts::AccountAddressFilter,
-    accounts_index::{AccountIndex, IndexKey},
+    accounts_index::{AccountIndex, AccountSecondaryIndexes, IndexKey},
     bank::Bank,
     bank_forks::{BankForks, SnapshotConfig},
     commitment::{BlockCommitmentArray, BlockCommitmentCache, CommitmentSlots},
@@ -62,12 +65,12 @@ use solana_sdk::{
     stake_history::StakeHistory,
     system_instruction,
     sysvar::stake_history,
-    transaction::{self, Transaction},
+    transaction::{self, Transaction, VersionedTransaction},
 };
 use solana_stake_program::stake_state::StakeState;
 use solana_transaction_status::{
-    EncodedConfirmedBlock, EncodedConfirmedTransaction, TransactionConfirmationStatus,
-    TransactionStatus, UiTransactionEncoding,
+    EncodedConfirmedBlock, EncodedConfirmedTransaction, SignaturesForAddressFilter,
+    TransactionConfirmationStatus, TransactionStatus, UiTransactionEncoding,
 };
 use solana_vote_program::vote_state::{VoteState, MAX_LOCKOUT_HISTORY};
 use spl_token_v2_0::{
@@ -116,7 +119,7 @@ pub struct JsonRpcConfig {
     pub enable_bigtable_ledger_storage: bool,
     pub enable_bigtable_ledger_upload: bool,
     pub max_multiple_accounts: Option<usize>,
-    pub account_indexes: HashSet<AccountIndex>,
+    pub account_indexes: AccountSecondaryIndexes,
     pub rpc_threads: usize,
     pub rpc_bigtable_timeout: Option<Duration>,
     pub minimal_api: bool,
@@ -139,6 +142,7 @@ pub struct JsonRpcRequestProcessor {
     optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
     largest_accounts_cache: Arc<RwLock<LargestAccountsCache>>,
     max_slots: Arc<MaxSlots>,
+    leader_schedule_cache: Arc<LeaderScheduleCache>,
 }
 impl Metadata for JsonRpcRequestProcessor {}
 
@@ -223,6 +227,7 @@ impl JsonRpcRequestProcessor {
         optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
         largest_accounts_cache: Arc<RwLock<LargestAccountsCache>>,
         max_slots: Arc<MaxSlots>,
+        leader_schedule_cache: Arc<LeaderScheduleCache>,
     ) -> (Self, Receiver<TransactionInfo>) {
         let (sender, receiver) = channel();
         (
@@ -242,6 +247,7 @@ impl JsonRpcRequestProcessor {
                 optimistically_confirmed_bank,
                 largest_accounts_cache,
                 max_slots,
+                leader_schedule_cache,
             },
             receiver,
         )
@@ -283,6 +289,7 @@ impl JsonRpcRequestProcessor {
             })),
             largest_accounts_cache: Arc::new(RwLock::new(LargestAccountsCache::new(30))),
             max_slots: Arc::new(MaxSlots::default()),
+            leader_schedule_cache: Arc::new(LeaderScheduleCache::new_from_bank(bank)),
         }
     }
 
@@ -342,11 +349,11 @@ impl JsonRpcRequestProcessor {
         check_slice_and_encoding(&encoding, data_slice_config.is_some())?;
         let keyed_accounts = {
             if let Some(owner) = get_spl_token_owner_filter(program_id, &filters) {
-                self.get_filtered_spl_token_accounts_by_owner(&bank, &owner, filters)
+                self.get_filtered_spl_token_accounts_by_owner(&bank, &owner, filters)?
             } else if let Some(mint) = get_spl_token_mint_filter(program_id, &filters) {
-                self.get_filtered_spl_token_accounts_by_mint(&bank, &mint, filters)
+                self.get_filtered_spl_token_accounts_by_mint(&bank, &mint, filters)?
             } else {
-                self.get_filtered_program_accounts(&bank, program_id, filters)
+                self.get_filtered_program_accounts(&bank, program_id, filters)?
             }
         };
         let result =
@@ -735,6 +742,9 @@ impl JsonRpcRequestProcessor {
         {
             let result = self.blockstore.get_confirmed_block(slot, true);
             self.check_blockstore_root(&result, slot)?;
+            if let Err(BlockstoreError::UnsupportedTransactionVersion) = result {
+                return Err(RpcCustomError::UnsupportedTransactionVersion { slot }.into());
+            }
             if result.is_err() {
                 if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
                     let bigtable_result = self
@@ -809,6 +819,97 @@ impl JsonRpcRequestProcessor {
             .collect())
     }
 
+    pub fn get_block_production(
+        &self,
+        config: Option<RpcBlockProductionConfig>,
+    ) -> Result<RpcResponse<RpcBlockProduction>> {
+        let config = config.unwrap_or_default();
+        // Only finalized slots are reported, so that a slot counted as skipped stays skipped
+        let bank = self.bank(Some(CommitmentConfig::finalized()));
+        let identity = config.identity.map(verify_pubkey).transpose()?;
+
+        let (first_slot, last_slot) = match config.range {
+            None => (
+                bank.epoch_schedule().get_first_slot_in_epoch(bank.epoch()),
+                bank.slot(),
+            ),
+            Some(range) => {
+                let last_slot = range.last_slot.unwrap_or_else(|| bank.slot());
+                if last_slot > bank.slot() {
+                    return Err(Error::invalid_params(format!(
+                        "lastSlot, {}, cannot be greater than the highest finalized slot, {}",
+                        last_slot,
+                        bank.slot()
+                    )));
+                }
+                (range.first_slot, last_slot)
+            }
+        };
+        if first_slot > last_slot {
+            return Err(Error::invalid_params(format!(
+                "lastSlot, {}, cannot be less than firstSlot, {}",
+                last_slot, first_slot
+            )));
+        }
+
+        // Slots that were cleaned up from the ledger would all look skipped, so don't report
+        // them unless BigTable can fill them in
+        let first_slot = if self.bigtable_ledger_storage.is_some() {
+            first_slot
+        } else {
+            first_slot.max(self.blockstore.lowest_slot())
+        };
+        if first_slot > last_slot {
+            return Err(Error::invalid_params(format!(
+                "Ledger data not available for slots {} to {}",
+                first_slot, last_slot
+            )));
+        }
+
+        let confirmed_blocks: HashSet<Slot> = self
+            .get_confirmed_blocks(first_slot, Some(last_slot))?
+            .into_iter()
+            .collect();
+        let mut by_identity = HashMap::new();
+        // Slots whose leader isn't known, because their epoch's leader schedule is no longer
+        // cached, are left out of the counts and of the reported range
+        let mut leader_slots_range: Option<(Slot, Slot)> = None;
+        for slot in first_slot..=last_slot {
+            let leader = match self.leader_schedule_cache.slot_leader_at(slot, Some(&bank)) {
+                Some(leader) => leader,
+                None => continue,
+            };
+            leader_slots_range =
+                Some(leader_slots_range.map_or((slot, slot), |(first, _)| (first, slot)));
+            if identity.map_or(false, |identity| identity != leader) {
+                continue;
+            }
+            let (leader_slots, blocks_produced) =
+                by_identity.entry(leader.to_string()).or_insert((0, 0));
+            *leader_slots += 1;
+            if confirmed_blocks.contains(&slot) {
+                *blocks_produced += 1;
+            }
+        }
+        let (first_slot, last_slot) = leader_slots_range.ok_or_else(|| {
+            Error::invalid_params(format!(
+                "Leader schedule not available for slots {} to {}",
+                first_slot, last_slot
+            ))
+        })?;
+
+        Ok(new_response(
+            &bank,
+            RpcBlockProduction {
+                by_identity,
+                range: RpcBlockProductionRange {
+                    first_slot,
+                    last_slot,
+                },
+            },
+        ))
+    }
+
     pub fn get_confirmed_blocks_with_limit(
         &self,
         start_slot: Slot,
@@ -1057,6 +1158,7 @@ impl JsonRpcRequestProcessor {
         mut before: Option<Signature>,
         until: Option<Signature>,
         mut limit: usize,
+        filter: SignaturesForAddressFilter,
     ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
         if self.config.enable_rpc_transaction_history {
             let highest_confirmed_root = self
@@ -1073,6 +1175,7 @@ impl JsonRpcRequestProcessor {
                     before,
                     until,
                     limit,
+                    filter,
                 )
                 .map_err(|err| Error::invalid_params(format!("{}", err)))?;
 
@@ -1089,6 +1192,7 @@ impl JsonRpcRequestProcessor {
                             before.as_ref(),
                             until.as_ref(),
                             limit,
+                            filter,
                         ),
                     );
                     match bigtable_results {
@@ -1253,7 +1357,7 @@ impl JsonRpcRequestProcessor {
             ));
         }
         let mut token_balances: Vec<RpcTokenAccountBalance> = self
-            .get_filtered_spl_token_accounts_by_mint(&bank, &mint, vec![])
+            .get_filtered_spl_token_accounts_by_mint(&bank, &mint, vec![])?
             .into_iter()
             .map(|(address, account)| {
                 let amount = TokenAccount::unpack(&account.data())
@@ -1301,7 +1405,8 @@ impl JsonRpcRequestProcessor {
             }));
         }
 
-        let keyed_accounts = self.get_filtered_spl_token_accounts_by_owner(&bank, owner, filters);
+        let keyed_accounts =
+            self.get_filtered_spl_token_accounts_by_owner(&bank, owner, filters)?;
         let accounts = if encoding == UiAccountEncoding::JsonParsed {
             get_parsed_token_accounts(bank.clone(), keyed_accounts.into_iter()).collect()
         } else {
@@ -1353,13 +1458,13 @@ impl JsonRpcRequestProcessor {
         ];
         // Optional filter on Mint address, uses mint account index for scan
         let keyed_accounts = if let Some(mint) = mint {
-            self.get_filtered_spl_token_accounts_by_mint(&bank, &mint, filters)
+            self.get_filtered_spl_token_accounts_by_mint(&bank, &mint, filters)?
         } else {
             // Filter on Token Account state
             filters.push(RpcFilterType::DataSize(
                 TokenAccount::get_packed_len() as u64
             ));
-            self.get_filtered_program_accounts(&bank, &token_program_id, filters)
+            self.get_filtered_program_accounts(&bank, &token_program_id, filters)?
         };
         let accounts = if encoding == UiAccountEncoding::JsonParsed {
             get_parsed_token_accounts(bank.clone(), keyed_accounts.into_iter()).collect()
@@ -1387,28 +1492,42 @@ impl JsonRpcRequestProcessor {
         bank: &Arc<Bank>,
         program_id: &Pubkey,
         filters: Vec<RpcFilterType>,
-    ) -> Vec<(Pubkey, AccountSharedData)> {
+    ) -> Result<Vec<(Pubkey, AccountSharedData)>> {
         let filter_closure = |account: &AccountSharedData| {
             filters.iter().all(|filter_type| match filter_type {
                 RpcFilterType::DataSize(size) => account.data().len() as u64 == *size,
                 RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data()),
             })
         };
-        if self
+        if let Some(index_key) =
+            get_custom_index_filter(&self.config.account_indexes, program_id, &filters)
+        {
+            if let IndexKey::Custom(_, key) = index_key {
+                self.check_key_included_in_secondary_indexes(&key)?;
+            }
+            // As with the program-id index below, accounts may remain in a custom index after
+            // being wiped, so all filters are applied again.
+            Ok(bank.get_filtered_indexed_accounts(&index_key, |account| {
+                account.owner == *program_id && filter_closure(account)
+            }))
+        } else if self
             .config
             .account_indexes
             .contains(&AccountIndex::ProgramId)
         {
-            bank.get_filtered_indexed_accounts(&IndexKey::ProgramId(*program_id), |account| {
-                // The program-id account index checks for Account owner on inclusion. However, due
-                // to the current AccountsDb implementation, an account may remain in storage as a
-                // zero-lamport AccountSharedData::Default() after being wiped and reinitialized in later
-                // updates. We include the redundant filters here to avoid returning these
-                // accounts.
-                account.owner == *program_id && filter_closure(account)
-            })
+            self.check_key_included_in_secondary_indexes(program_id)?;
+            Ok(
+                bank.get_filtered_indexed_accounts(&IndexKey::ProgramId(*program_id), |account| {
+                    // The program-id account index checks for Account owner on inclusion.
+                    // However, due to the current AccountsDb implementation, an account may remain
+                    // in storage as a zero-lamport AccountSharedData::Default() after being wiped
+                    // and reinitialized in later updates. We include the redundant filters here
+                    // to avoid returning these accounts.
+                    account.owner == *program_id && filter_closure(account)
+                }),
+            )
         } else {
-            bank.get_filtered_program_accounts(program_id, filter_closure)
+            Ok(bank.get_filtered_program_accounts(program_id, filter_closure))
         }
     }
 
@@ -1418,7 +1537,7 @@ impl JsonRpcRequestProcessor {
         bank: &Arc<Bank>,
         owner_key: &Pubkey,
         mut filters: Vec<RpcFilterType>,
-    ) -> Vec<(Pubkey, AccountSharedData)> {
+    ) -> Result<Vec<(Pubkey, AccountSharedData)>> {
         // The by-owner accounts index checks for Token Account state and Owner address on
         // inclusion. However, due to the current AccountsDb implementation, an account may remain
         // in storage as a zero-lamport AccountSharedData::Default() after being wiped and reinitialized in
@@ -1440,13 +1559,17 @@ impl JsonRpcRequestProcessor {
             .account_indexes
             .contains(&AccountIndex::SplTokenOwner)
         {
-            bank.get_filtered_indexed_accounts(&IndexKey::SplTokenOwner(*owner_key), |account| {
-                account.owner == spl_token_id_v2_0()
-                    && filters.iter().all(|filter_type| match filter_type {
-                        RpcFilterType::DataSize(size) => account.data().len() as u64 == *size,
-                        RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data()),
-                    })
-            })
+            self.check_key_included_in_secondary_indexes(owner_key)?;
+            Ok(bank.get_filtered_indexed_accounts(
+                &IndexKey::SplTokenOwner(*owner_key),
+                |account| {
+                    account.owner == spl_token_id_v2_0()
+                        && filters.iter().all(|filter_type| match filter_type {
+                            RpcFilterType::DataSize(size) => account.data().len() as u64 == *size,
+                            RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data()),
+                        })
+                },
+            ))
         } else {
             self.get_filtered_program_accounts(bank, &spl_token_id_v2_0(), filters)
         }
@@ -1458,7 +1581,7 @@ impl JsonRpcRequestProcessor {
         bank: &Arc<Bank>,
         mint_key: &Pubkey,
         mut filters: Vec<RpcFilterType>,
-    ) -> Vec<(Pubkey, AccountSharedData)> {
+    ) -> Result<Vec<(Pubkey, AccountSharedData)>> {
         // The by-mint accounts index checks for Token Account state and Mint address on inclusion.
         // However, due to the current AccountsDb implementation, an account may remain in storage
         // as be zero-lamport AccountSharedData::Default() after being wiped and reinitialized in later
@@ -1479,20 +1602,36 @@ impl JsonRpcRequestProcessor {
             .account_indexes
             .contains(&AccountIndex::SplTokenMint)
         {
-            bank.get_filtered_indexed_accounts(&IndexKey::SplTokenMint(*mint_key), |account| {
-                account.owner == spl_token_id_v2_0()
-                    && filters.iter().all(|filter_type| match filter_type {
-                        RpcFilterType::DataSize(size) => account.data().len() as u64 == *size,
-                        RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data()),
-                    })
-            })
+            self.check_key_included_in_secondary_indexes(mint_key)?;
+            Ok(
+                bank.get_filtered_indexed_accounts(&IndexKey::SplTokenMint(*mint_key), |account| {
+                    account.owner == spl_token_id_v2_0()
+                        && filters.iter().all(|filter_type| match filter_type {
+                            RpcFilterType::DataSize(size) => account.data().len() as u64 == *size,
+                            RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data()),
+                        })
+                }),
+            )
         } else {
             self.get_filtered_program_accounts(bank, &spl_token_id_v2_0(), filters)
         }
     }
+
+    /// Fails with a clear error, rather than returning nothing, when `key` has been left out
+    /// of the secondary indexes by the validator's include or exclude key list
+    fn check_key_included_in_secondary_indexes(&self, key: &Pubkey) -> Result<()> {
+        if self.config.account_indexes.include_key(key) {
+            Ok(())
+        } else {
+            Err(RpcCustomError::KeyExcludedFromSecondaryIndex {
+                index_key: key.to_string(),
+            }
+            .into())
+        }
+    }
 }
 
-fn verify_transaction(transaction: &Transaction) -> Result<()> {
+fn verify_versioned_transaction(transaction: &VersionedTransaction) -> Result<()> {
     if transaction.verify().is_err() {
         return Err(RpcCustomError::TransactionSignatureVerificationFailure.into());
     }
@@ -1616,6 +1755,45 @@ fn get_spl_token_owner_filter(program_id: &Pubkey, filters: &[RpcFilterType]) ->
     }
 }
 
+/// Returns the key of a custom account index covering one of the `Memcmp` filters on the
+/// accounts of `program_id`, if any
+fn get_custom_index_filter(
+    account_indexes: &AccountSecondaryIndexes,
+    program_id: &Pubkey,
+    filters: &[RpcFilterType],
+) -> Option<IndexKey> {
+    let custom_indexes: Vec<_> = account_indexes
+        .indexes
+        .iter()
+        .filter_map(|account_index| match account_index {
+            AccountIndex::Custom(custom_index) if custom_index.program_id == *program_id => {
+                Some(custom_index)
+            }
+            _ => None,
+        })
+        .collect();
+    if custom_indexes.is_empty() {
+        return None;
+    }
+    filters.iter().find_map(|filter| match filter {
+        RpcFilterType::Memcmp(Memcmp {
+            offset,
+            bytes: MemcmpEncodedBytes::Binary(bytes),
+            ..
+        }) => {
+            let bytes = bs58::decode(bytes).into_vec().ok()?;
+            custom_indexes
+                .iter()
+                .filter(|custom_index| custom_index.offset == *offset)
+                .find_map(|custom_index| {
+                    let key = custom_index.key(&bytes)?;
+                    Some(IndexKey::Custom(**custom_index, key))
+                })
+        }
+        _ => None,
+    })
+}
+
 fn get_spl_token_mint_filter(program_id: &Pubkey, filters: &[RpcFilterType]) -> Option<Pubkey> {
     if program_id != &spl_token_id_v2_0() {
         return None;
@@ -2207,6 +2385,13 @@ pub mod rpc_full {
             limit: usize,
         ) -> Result<Vec<Slot>>;
 
+        #[rpc(meta, name = "getBlockProduction")]
+        fn get_block_production(
+            &self,
+            meta: Self::Metadata,
+            config: Option<RpcBlockProductionConfig>,
+        ) -> Result<RpcResponse<RpcBlockProduction>>;
+
         #[rpc(meta, name = "getConfirmedTransaction")]
         fn get_confirmed_transaction(
             &self,
@@ -2676,12 +2861,16 @@ pub mod rpc_full {
             debug!("send_transaction rpc request received");
             let config = config.unwrap_or_default();
             let encoding = config.encoding.unwrap_or(UiTransactionEncoding::Base58);
-            let (wire_transaction, transaction) = deserialize_transaction(data, encoding)?;
+            let (wire_transaction, versioned_transaction) =
+                deserialize_transaction(data, encoding)?;
 
             let preflight_commitment = config
                 .preflight_commitment
                 .map(|commitment| CommitmentConfig { commitment });
             let preflight_bank = &*meta.bank(preflight_commitment);
+            let transaction = preflight_bank
+                .resolve_versioned_transaction(versioned_transaction.clone())
+                .map_err(|err| Error::invalid_params(format!("invalid transaction: {}", err)))?;
 
             let mut last_valid_slot = preflight_bank
                 .get_blockhash_last_valid_slot(&transaction.message.recent_blockhash)
@@ -2704,7 +2893,7 @@ pub mod rpc_full {
             }
 
             if !config.skip_preflight {
-                if let Err(e) = verify_transaction(&transaction) {
+                if let Err(e) = verify_versioned_transaction(&versioned_transaction) {
                     return Err(e);
                 }
 
@@ -2757,13 +2946,16 @@ pub mod rpc_full {
             let (_, transaction) = deserialize_transaction(data, encoding)?;
 
             if config.sig_verify {
-                if let Err(e) = verify_transaction(&transaction) {
+                if let Err(e) = verify_versioned_transaction(&transaction) {
                     return Err(e);
                 }
             }
 
             let bank = &*meta.bank(config.commitment);
-            let (result, logs) = bank.simulate_transaction(transaction);
+            let (result, logs) = match bank.resolve_versioned_transaction(transaction) {
+                Ok(transaction) => bank.simulate_transaction(transaction),
+                Err(err) => (Err(err), vec![]),
+            };
 
             Ok(new_response(
                 &bank,
@@ -2824,6 +3016,15 @@ pub mod rpc_full {
             meta.get_confirmed_blocks_with_limit(start_slot, limit)
         }
 
+        fn get_block_production(
+            &self,
+            meta: Self::Metadata,
+            config: Option<RpcBlockProductionConfig>,
+        ) -> Result<RpcResponse<RpcBlockProduction>> {
+            debug!("get_block_production rpc request received: {:?}", config);
+            meta.get_block_production(config)
+        }
+
         fn get_block_time(
             &self,
             meta: Self::Metadata,
@@ -2907,7 +3108,13 @@ pub mod rpc_full {
                 )));
             }
 
-            meta.get_confirmed_signatures_for_address2(address, before, until, limit)
+            let filter = SignaturesForAddressFilter {
+                min_slot: config.min_slot,
+                max_slot: config.max_slot,
+                status: config.status,
+            };
+
+            meta.get_confirmed_signatures_for_address2(address, before, until, limit, filter)
         }
 
         fn get_first_available_block(&self, meta: Self::Metadata) -> Result<Slot> {
@@ -3007,7 +3214,7 @@ const WORST_CASE_BASE64_TX: usize = 1644; // Golden, bump if PACKET_DATA_SIZE ch
 fn deserialize_transaction(
     encoded_transaction: String,
     encoding: UiTransactionEncoding,
-) -> Result<(Vec<u8>, Transaction)> {
+) -> Result<(Vec<u8>, VersionedTransaction)> {
     let wire_transaction = match encoding {
         UiTransactionEncoding::Base58 => {
             inc_new_counter_info!("rpc-base58_encoded_tx", 1);
@@ -3061,7 +3268,7 @@ fn deserialize_transaction(
             info!("transaction deserialize error: {:?}", err);
             Error::invalid_params(&err.to_string())
         })
-        .and_then(|transaction: Transaction| {
+        .and_then(|transaction: VersionedTransaction| {
             if let Err(err) = transaction.sanitize() {
                 Err(Error::invalid_params(format!(
                     "invalid transaction: {}",
@@ -3096,14 +3303,19 @@ pub mod tests {
     use bincode::deserialize;
     use jsonrpc_core::{futures, ErrorCode, MetaIoHandler, Output, Response, Value};
     use jsonrpc_core_client::transports::local;
-    use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes};
+    use solana_client::{
+        rpc_custom_error::JSON_RPC_SERVER_ERROR_KEY_EXCLUDED_FROM_SECONDARY_INDEX,
+        rpc_filter::{Memcmp, MemcmpEncodedBytes},
+    };
     use solana_ledger::{
         blockstore_meta::PerfSample,
         blockstore_processor::fill_blockstore_slot_with_ticks,
         genesis_utils::{create_genesis_config, GenesisConfigInfo},
     };
     use solana_runtime::{
-        accounts_background_service::AbsRequestSender, commitment::BlockCommitment,
+        accounts_background_service::AbsRequestSender,
+        accounts_index::{AccountSecondaryIndexesIncludeExclude, CustomIndex},
+        commitment::BlockCommitment,
     };
     use solana_sdk::{
         account::Account,
@@ -3111,7 +3323,7 @@ pub mod tests {
         fee_calculator::DEFAULT_BURN_PERCENT,
         hash::{hash, Hash},
         instruction::InstructionError,
-        message::Message,
+        message::{v0, Message, VersionedMessage},
         nonce, rpc_port,
         signature::{Keypair, Signer},
         system_program, system_transaction,
@@ -3295,6 +3507,9 @@ pub mod tests {
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
             Arc::new(RwLock::new(LargestAccountsCache::new(30))),
             max_slots,
+            Arc::new(LeaderScheduleCache::new_from_bank(
+                &bank_forks.read().unwrap().root_bank(),
+            )),
         );
         SendTransactionService::new(tpu_address, &bank_forks, None, receiver, 1000, 1);
 
@@ -4670,6 +4885,49 @@ pub mod tests {
         assert_eq!(error["code"], ErrorCode::InvalidParams.code());
     }
 
+    #[test]
+    fn test_rpc_send_versioned_tx() {
+        let genesis = create_genesis_config(100);
+        let bank = Arc::new(Bank::new(&genesis.genesis_config));
+        // Freeze the bank to prevent a panic in `run_transaction_simulation()`
+        bank.freeze();
+        let meta = JsonRpcRequestProcessor::new_from_bank(&bank);
+
+        let mut io = MetaIoHandler::default();
+        io.extend_with(rpc_full::FullImpl.to_delegate());
+
+        let mint_pubkey = genesis.mint_keypair.pubkey();
+        let message = v0::Message::try_compile(
+            &mint_pubkey,
+            &[system_instruction::transfer(
+                &mint_pubkey,
+                &solana_sdk::pubkey::new_rand(),
+                1,
+            )],
+            &[],
+            bank.last_blockhash(),
+        )
+        .unwrap();
+        let tx =
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &[&genesis.mint_keypair])
+                .unwrap();
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"sendTransaction","params":["{}"]}}"#,
+            bs58::encode(serialize(&tx).unwrap()).into_string(),
+        );
+        let res = io.handle_request_sync(&req, meta);
+        let expected = json!({
+            "jsonrpc": "2.0",
+            "result": tx.signatures[0].to_string(),
+            "id": 1,
+        });
+        let expected: Response =
+            serde_json::from_value(expected).expect("expected response deserialization");
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(expected, result);
+    }
+
     #[test]
     fn test_rpc_send_transaction_preflight() {
         let exit = Arc::new(AtomicBool::new(false));
@@ -4704,6 +4962,9 @@ pub mod tests {
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
             Arc::new(RwLock::new(LargestAccountsCache::new(30))),
             Arc::new(MaxSlots::default()),
+            Arc::new(LeaderScheduleCache::new_from_bank(
+                &bank_forks.read().unwrap().root_bank(),
+            )),
         );
         SendTransactionService::new(tpu_address, &bank_forks, None, receiver, 1000, 1);
 
@@ -4979,6 +5240,9 @@ pub mod tests {
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
             Arc::new(RwLock::new(LargestAccountsCache::new(30))),
             Arc::new(MaxSlots::default()),
+            Arc::new(LeaderScheduleCache::new_from_bank(
+                &bank_forks.read().unwrap().root_bank(),
+            )),
         );
         SendTransactionService::new(tpu_address, &bank_forks, None, receiver, 1000, 1);
         assert_eq!(
@@ -5296,6 +5560,65 @@ pub mod tests {
         assert_eq!(confirmed_blocks, Vec::<Slot>::new());
     }
 
+    #[test]
+    fn test_get_block_production() {
+        let bob_pubkey = solana_sdk::pubkey::new_rand();
+        let roots = vec![0, 1, 3, 4, 8];
+        let RpcHandler {
+            io,
+            meta,
+            block_commitment_cache,
+            leader_pubkey,
+            ..
+        } = start_rpc_handler_with_tx_and_blockstore(&bob_pubkey, roots);
+        block_commitment_cache
+            .write()
+            .unwrap()
+            .set_highest_confirmed_root(8);
+
+        let req = r#"{"jsonrpc":"2.0","id":1,"method":"getBlockProduction","params":[{"range":{"firstSlot":0,"lastSlot":8}}]}"#;
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        let block_production: RpcResponse<RpcBlockProduction> =
+            serde_json::from_value(result["result"].clone()).unwrap();
+        // Slot 0 is below the lowest slot kept in the blockstore, so it isn't reported
+        assert_eq!(
+            block_production.value.range,
+            RpcBlockProductionRange {
+                first_slot: 1,
+                last_slot: 8,
+            }
+        );
+        let mut expected_by_identity = HashMap::new();
+        expected_by_identity.insert(leader_pubkey.to_string(), (8, 4));
+        assert_eq!(block_production.value.by_identity, expected_by_identity);
+
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getBlockProduction","params":[{{"identity":"{}","range":{{"firstSlot":2,"lastSlot":4}}}}]}}"#,
+            bob_pubkey
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        let block_production: RpcResponse<RpcBlockProduction> =
+            serde_json::from_value(result["result"].clone()).unwrap();
+        assert!(block_production.value.by_identity.is_empty());
+
+        // Slots past the highest finalized slot can't be requested
+        let req = r#"{"jsonrpc":"2.0","id":1,"method":"getBlockProduction","params":[{"range":{"firstSlot":0,"lastSlot":9}}]}"#;
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert!(result["error"].is_object());
+
+        let req = r#"{"jsonrpc":"2.0","id":1,"method":"getBlockProduction","params":[{"range":{"firstSlot":5,"lastSlot":4}}]}"#;
+        let res = io.handle_request_sync(&req, meta);
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert!(result["error"].is_object());
+    }
+
     #[test]
     fn test_get_block_time() {
         let bob_pubkey = solana_sdk::pubkey::new_rand();
@@ -6168,6 +6491,92 @@ pub mod tests {
         .is_none());
     }
 
+    #[test]
+    fn test_rpc_get_program_accounts_excluded_key() {
+        let genesis = create_genesis_config(100);
+        let bank = Arc::new(Bank::new(&genesis.genesis_config));
+        let mut request_processor = JsonRpcRequestProcessor::new_from_bank(&bank);
+        let excluded_program_id = Pubkey::new_unique();
+        request_processor.config.account_indexes = AccountSecondaryIndexes {
+            keys: Some(AccountSecondaryIndexesIncludeExclude {
+                exclude: true,
+                keys: vec![excluded_program_id].into_iter().collect(),
+            }),
+            indexes: vec![AccountIndex::ProgramId].into_iter().collect(),
+        };
+
+        assert!(request_processor
+            .get_program_accounts(&Pubkey::new_unique(), None, vec![])
+            .is_ok());
+        let error = request_processor
+            .get_program_accounts(&excluded_program_id, None, vec![])
+            .unwrap_err();
+        assert_eq!(
+            error.code,
+            ErrorCode::ServerError(JSON_RPC_SERVER_ERROR_KEY_EXCLUDED_FROM_SECONDARY_INDEX)
+        );
+        assert!(error.message.contains(&excluded_program_id.to_string()));
+
+        // Without the program-id index the accounts are scanned, so no key is excluded
+        request_processor.config.account_indexes.indexes.clear();
+        assert!(request_processor
+            .get_program_accounts(&excluded_program_id, None, vec![])
+            .is_ok());
+    }
+
+    #[test]
+    fn test_get_custom_index_filter() {
+        let program_id = Pubkey::new_unique();
+        let custom_index = CustomIndex::new(program_id, 8, 32);
+        let account_indexes = AccountSecondaryIndexes {
+            keys: None,
+            indexes: vec![AccountIndex::ProgramId, AccountIndex::Custom(custom_index)]
+                .into_iter()
+                .collect(),
+        };
+        let key = Pubkey::new_unique();
+        let memcmp = |offset, bytes: &[u8]| {
+            RpcFilterType::Memcmp(Memcmp {
+                offset,
+                bytes: MemcmpEncodedBytes::Binary(bs58::encode(bytes).into_string()),
+                encoding: None,
+            })
+        };
+        match get_custom_index_filter(
+            &account_indexes,
+            &program_id,
+            &[RpcFilterType::DataSize(100), memcmp(8, key.as_ref())],
+        ) {
+            Some(IndexKey::Custom(index, index_key)) => {
+                assert_eq!(index, custom_index);
+                assert_eq!(index_key, key);
+            }
+            _ => panic!("expected a custom index key"),
+        }
+
+        // Wrong offset
+        assert!(
+            get_custom_index_filter(&account_indexes, &program_id, &[memcmp(0, key.as_ref())],)
+                .is_none()
+        );
+
+        // Prefix of the indexed field
+        assert!(get_custom_index_filter(
+            &account_indexes,
+            &program_id,
+            &[memcmp(8, &key.as_ref()[..16])],
+        )
+        .is_none());
+
+        // Wrong program id
+        assert!(get_custom_index_filter(
+            &account_indexes,
+            &Pubkey::new_unique(),
+            &[memcmp(8, key.as_ref())],
+        )
+        .is_none());
+    }
+
     #[test]
     fn test_rpc_single_gossip() {
         let exit = Arc::new(AtomicBool::new(false));
@@ -6217,6 +6626,9 @@ pub mod tests {
             optimistically_confirmed_bank.clone(),
             Arc::new(RwLock::new(LargestAccountsCache::new(30))),
             Arc::new(MaxSlots::default()),
+            Arc::new(LeaderScheduleCache::new_from_bank(
+                &bank_forks.read().unwrap().root_bank(),
+            )),
         );
 
         let mut io = MetaIoHandler::default();
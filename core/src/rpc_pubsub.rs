@@ -1,18 +1,19 @@
 //! The `pubsub` module implements a threaded subscription service on client RPC request
 
-use crate::rpc_subscriptions::{RpcSubscriptions, RpcVote};
+use crate::rpc_subscriptions::RpcSubscriptions;
 use jsonrpc_core::{Error, ErrorCode, Result};
 use jsonrpc_derive::rpc;
 use jsonrpc_pubsub::{typed::Subscriber, Session, SubscriptionId};
 use solana_account_decoder::UiAccount;
 use solana_client::{
     rpc_config::{
-        RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSignatureSubscribeConfig,
-        RpcTransactionLogsConfig, RpcTransactionLogsFilter,
+        RpcAccountInfoConfig, RpcBlockSubscribeConfig, RpcBlockSubscribeFilter,
+        RpcProgramAccountsConfig, RpcSignatureSubscribeConfig, RpcTransactionLogsConfig,
+        RpcTransactionLogsFilter,
     },
     rpc_response::{
-        Response as RpcResponse, RpcKeyedAccount, RpcLogsResponse, RpcSignatureResult, SlotInfo,
-        SlotUpdate,
+        Response as RpcResponse, RpcBlockUpdate, RpcKeyedAccount, RpcLogsResponse,
+        RpcSignatureResult, RpcVote, SlotInfo, SlotUpdate,
     },
 };
 #[cfg(test)]
@@ -187,6 +188,24 @@ pub trait RpcSolPubSub {
         name = "rootUnsubscribe"
     )]
     fn root_unsubscribe(&self, meta: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool>;
+
+    // Get notification when a block reaches the requested commitment
+    #[pubsub(subscription = "blockNotification", subscribe, name = "blockSubscribe")]
+    fn block_subscribe(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<RpcResponse<RpcBlockUpdate>>,
+        filter: RpcBlockSubscribeFilter,
+        config: Option<RpcBlockSubscribeConfig>,
+    );
+
+    // Unsubscribe from block notification subscription.
+    #[pubsub(
+        subscription = "blockNotification",
+        unsubscribe,
+        name = "blockUnsubscribe"
+    )]
+    fn block_unsubscribe(&self, meta: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool>;
 }
 
 pub struct RpcSolPubSubImpl {
@@ -536,6 +555,51 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
             })
         }
     }
+
+    fn block_subscribe(
+        &self,
+        _meta: Self::Metadata,
+        subscriber: Subscriber<RpcResponse<RpcBlockUpdate>>,
+        filter: RpcBlockSubscribeFilter,
+        config: Option<RpcBlockSubscribeConfig>,
+    ) {
+        info!("block_subscribe");
+        if let Err(err) = self.check_subscription_count() {
+            subscriber.reject(err).unwrap_or_default();
+            return;
+        }
+
+        let mentioned_address = match filter {
+            RpcBlockSubscribeFilter::All => None,
+            RpcBlockSubscribeFilter::MentionsAccountOrProgram(address) => {
+                match param::<Pubkey>(&address, "mentionsAccountOrProgram") {
+                    Ok(address) => Some(address),
+                    Err(e) => {
+                        subscriber.reject(e).unwrap_or_default();
+                        return;
+                    }
+                }
+            }
+        };
+
+        let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
+        let sub_id = SubscriptionId::Number(id as u64);
+        self.subscriptions
+            .add_block_subscription(mentioned_address, config, sub_id, subscriber)
+    }
+
+    fn block_unsubscribe(&self, _meta: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+        info!("block_unsubscribe: id={:?}", id);
+        if self.subscriptions.remove_block_subscription(&id) {
+            Ok(true)
+        } else {
+            Err(Error {
+                code: ErrorCode::InvalidParams,
+                message: "Invalid Request: Subscription id does not exist".into(),
+                data: None,
+            })
+        }
+    }
 }
 
 #[cfg(test)]
@@ -544,6 +608,7 @@ mod tests {
     use crate::{
         cluster_info_vote_listener::{ClusterInfoVoteListener, VoteTracker},
         optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
+        rpc_pubsub_service::PubSubConfig,
         rpc_subscriptions::tests::robust_poll_or_panic,
     };
     use crossbeam_channel::unbounded;
@@ -1239,12 +1304,16 @@ mod tests {
         // Setup Subscriptions
         let optimistically_confirmed_bank =
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
-        let subscriptions = RpcSubscriptions::new_with_vote_subscription(
+        let subscriptions = RpcSubscriptions::new_with_config(
             &exit,
             bank_forks,
             block_commitment_cache,
             optimistically_confirmed_bank,
-            true,
+            None,
+            &PubSubConfig {
+                enable_vote_subscription: true,
+                ..PubSubConfig::default()
+            },
         );
         rpc.subscriptions = Arc::new(subscriptions);
         rpc.vote_subscribe(session, subscriber);
@@ -17,8 +17,8 @@ use jsonrpc_http_server::{
     RequestMiddlewareAction, ServerBuilder,
 };
 use regex::Regex;
-use solana_client::rpc_cache::LargestAccountsCache;
-use solana_ledger::blockstore::Blockstore;
+use solana_client::{quic_client::QuicClient, rpc_cache::LargestAccountsCache};
+use solana_ledger::{blockstore::Blockstore, leader_schedule_cache::LeaderScheduleCache};
 use solana_metrics::inc_new_counter_info;
 use solana_runtime::{
     bank_forks::{BankForks, SnapshotConfig},
@@ -66,7 +66,7 @@ impl RpcRequestMiddleware {
         Self {
             ledger_path,
             snapshot_archive_path_regex: Regex::new(
-                r"^/snapshot-\d+-[[:alnum:]]+\.(tar|tar\.bz2|tar\.zst|tar\.gz)$",
+                r"^/(snapshot-\d+|incremental-snapshot-\d+-\d+)-[[:alnum:]]+\.(tar|tar\.bz2|tar\.zst|tar\.gz)$",
             )
             .unwrap(),
             snapshot_config,
@@ -210,6 +210,32 @@ impl RequestMiddleware for RpcRequestMiddleware {
                 }
                 .into();
             }
+            if request.uri().path() == "/incremental-snapshot.tar.bz2" {
+                // Convenience redirect to the latest incremental snapshot on top of the latest
+                // full snapshot
+                return if let Some((incremental_snapshot_archive, _)) =
+                    snapshot_utils::get_highest_snapshot_archive_path(
+                        &snapshot_config.snapshot_package_output_path,
+                    )
+                    .and_then(|(_, (full_snapshot_slot, _, _))| {
+                        snapshot_utils::get_highest_incremental_snapshot_archive_path(
+                            &snapshot_config.snapshot_package_output_path,
+                            full_snapshot_slot,
+                        )
+                    }) {
+                    RpcRequestMiddleware::redirect(&format!(
+                        "/{}",
+                        incremental_snapshot_archive
+                            .file_name()
+                            .unwrap_or_else(|| std::ffi::OsStr::new(""))
+                            .to_str()
+                            .unwrap_or(&"")
+                    ))
+                } else {
+                    RpcRequestMiddleware::not_found()
+                }
+                .into();
+            }
         }
 
         if let Some(result) = process_rest(&self.bank_forks, request.uri().path()) {
@@ -274,7 +300,9 @@ impl JsonRpcService {
         optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
         send_transaction_retry_ms: u64,
         send_transaction_leader_forward_count: u64,
+        send_transaction_use_quic: bool,
         max_slots: Arc<MaxSlots>,
+        leader_schedule_cache: Arc<LeaderScheduleCache>,
     ) -> Self {
         info!("rpc bound to {:?}", rpc_addr);
         info!("rpc configuration: {:?}", config);
@@ -354,17 +382,24 @@ impl JsonRpcService {
             optimistically_confirmed_bank,
             largest_accounts_cache,
             max_slots,
+            leader_schedule_cache,
         );
 
         let leader_info =
             poh_recorder.map(|recorder| LeaderInfo::new(cluster_info.clone(), recorder));
-        let _send_transaction_service = Arc::new(SendTransactionService::new(
+        let quic_client = if send_transaction_use_quic {
+            Some(QuicClient::new(&cluster_info.keypair).expect("QuicClient"))
+        } else {
+            None
+        };
+        let _send_transaction_service = Arc::new(SendTransactionService::new_with_quic_client(
             tpu_address,
             &bank_forks,
             leader_info,
             receiver,
             send_transaction_retry_ms,
             send_transaction_leader_forward_count,
+            quic_client,
         ));
 
         #[cfg(test)]
@@ -474,7 +509,7 @@ mod tests {
         get_tmp_ledger_path,
     };
     use solana_runtime::{bank::Bank, bank_forks::ArchiveFormat, snapshot_utils::SnapshotVersion};
-    use solana_sdk::{genesis_config::ClusterType, signature::Signer};
+    use solana_sdk::{clock::Slot, genesis_config::ClusterType, signature::Signer};
     use std::io::Write;
     use std::net::{IpAddr, Ipv4Addr};
 
@@ -500,6 +535,9 @@ mod tests {
         let block_commitment_cache = Arc::new(RwLock::new(BlockCommitmentCache::default()));
         let optimistically_confirmed_bank =
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
+        let leader_schedule_cache = Arc::new(LeaderScheduleCache::new_from_bank(
+            &bank_forks.read().unwrap().root_bank(),
+        ));
         let mut rpc_service = JsonRpcService::new(
             rpc_addr,
             JsonRpcConfig::default(),
@@ -517,7 +555,9 @@ mod tests {
             optimistically_confirmed_bank,
             1000,
             1,
+            false,
             Arc::new(MaxSlots::default()),
+            leader_schedule_cache,
         );
         let thread = rpc_service.thread_hdl.thread();
         assert_eq!(thread.name().unwrap(), "solana-jsonrpc");
@@ -566,6 +606,7 @@ mod tests {
             PathBuf::from("/"),
             Some(SnapshotConfig {
                 snapshot_interval_slots: 0,
+                incremental_snapshot_interval_slots: Slot::MAX,
                 snapshot_package_output_path: PathBuf::from("/"),
                 snapshot_path: PathBuf::from("/"),
                 archive_format: ArchiveFormat::TarBzip2,
@@ -600,6 +641,20 @@ mod tests {
 
         assert!(!rrm_with_snapshot_config.is_file_get_path("../../../test/snapshot-123-xxx.tar"));
 
+        assert!(!rrm.is_file_get_path("/incremental-snapshot.tar.bz2")); // This is a redirect
+        assert!(!rrm.is_file_get_path(
+            "/incremental-snapshot-100-200-AvFf9oS8A8U78HdjT9YG2sTTThLHJZmhaMn2g8vkWYnr.tar.bz2"
+        ));
+        assert!(rrm_with_snapshot_config.is_file_get_path(
+            "/incremental-snapshot-100-200-AvFf9oS8A8U78HdjT9YG2sTTThLHJZmhaMn2g8vkWYnr.tar.bz2"
+        ));
+        assert!(rrm_with_snapshot_config.is_file_get_path(
+            "/incremental-snapshot-100-200-AvFf9oS8A8U78HdjT9YG2sTTThLHJZmhaMn2g8vkWYnr.tar.zst"
+        ));
+        assert!(!rrm_with_snapshot_config.is_file_get_path(
+            "/incremental-snapshot-100-AvFf9oS8A8U78HdjT9YG2sTTThLHJZmhaMn2g8vkWYnr.tar.bz2"
+        ));
+
         assert!(!rrm.is_file_get_path("/"));
         assert!(!rrm.is_file_get_path(".."));
         assert!(!rrm.is_file_get_path("🎣"));
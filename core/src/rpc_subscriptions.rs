@@ -3,6 +3,7 @@
 use crate::{
     optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
     rpc::{get_parsed_token_account, get_parsed_token_accounts},
+    rpc_pubsub_service::PubSubConfig,
 };
 use core::hash::Hash;
 use jsonrpc_pubsub::{
@@ -12,13 +13,18 @@ use jsonrpc_pubsub::{
 use serde::Serialize;
 use solana_account_decoder::{parse_token::spl_token_id_v2_0, UiAccount, UiAccountEncoding};
 use solana_client::{
-    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSignatureSubscribeConfig},
+    rpc_config::{
+        RpcAccountInfoConfig, RpcBlockSubscribeConfig, RpcProgramAccountsConfig,
+        RpcSignatureSubscribeConfig,
+    },
     rpc_filter::RpcFilterType,
     rpc_response::{
-        ProcessedSignatureResult, ReceivedSignatureResult, Response, RpcKeyedAccount,
-        RpcLogsResponse, RpcResponseContext, RpcSignatureResult, SlotInfo, SlotUpdate,
+        ProcessedSignatureResult, ReceivedSignatureResult, Response, RpcBlockUpdate,
+        RpcBlockUpdateError, RpcKeyedAccount, RpcLogsResponse, RpcResponseContext,
+        RpcSignatureResult, RpcVote, SlotInfo, SlotUpdate,
     },
 };
+use solana_ledger::blockstore::Blockstore;
 use solana_measure::measure::Measure;
 use solana_runtime::{
     bank::{
@@ -29,13 +35,14 @@ use solana_runtime::{
 };
 use solana_sdk::{
     account::{AccountSharedData, ReadableAccount},
-    clock::{Slot, UnixTimestamp},
+    clock::Slot,
     commitment_config::CommitmentConfig,
     pubkey::Pubkey,
     signature::Signature,
     timing::timestamp,
     transaction,
 };
+use solana_transaction_status::{ConfirmedBlock, TransactionDetails, UiTransactionEncoding};
 use solana_vote_program::vote_state::Vote;
 use std::{
     collections::{HashMap, HashSet},
@@ -51,6 +58,10 @@ use std::{
 
 const RECEIVE_DELAY_MILLIS: u64 = 100;
 
+/// How many slots block notifications wait for the transaction statuses of a block, which the
+/// TransactionStatusService writes asynchronously, before sending the block as incomplete
+const MAX_BLOCK_STATUS_WAIT_SLOTS: Slot = 32;
+
 trait BankGetTransactionLogsAdapter {
     fn get_transaction_logs_adapter(
         &self,
@@ -73,14 +84,6 @@ impl BankGetTransactionLogsAdapter for Bank {
     }
 }
 
-// A more human-friendly version of Vote, with the bank state signature base58 encoded.
-#[derive(Serialize, Deserialize, Debug)]
-pub struct RpcVote {
-    pub slots: Vec<Slot>,
-    pub hash: String,
-    pub timestamp: Option<UnixTimestamp>,
-}
-
 enum NotificationEntry {
     Slot(SlotInfo),
     SlotUpdate(SlotUpdate),
@@ -122,6 +125,12 @@ struct ProgramConfig {
     filters: Vec<RpcFilterType>,
     encoding: Option<UiAccountEncoding>,
 }
+#[derive(Clone)]
+struct BlockConfig {
+    encoding: UiTransactionEncoding,
+    transaction_details: TransactionDetails,
+    show_rewards: bool,
+}
 type RpcAccountSubscriptions = RwLock<
     HashMap<
         Pubkey,
@@ -146,6 +155,12 @@ type RpcSignatureSubscriptions = RwLock<
         HashMap<SubscriptionId, SubscriptionData<Response<RpcSignatureResult>, bool>>,
     >,
 >;
+type RpcBlockSubscriptions = RwLock<
+    HashMap<
+        Option<Pubkey>,
+        HashMap<SubscriptionId, SubscriptionData<Response<RpcBlockUpdate>, BlockConfig>>,
+    >,
+>;
 type RpcSlotSubscriptions = RwLock<HashMap<SubscriptionId, Sink<SlotInfo>>>;
 type RpcSlotUpdateSubscriptions = RwLock<HashMap<SubscriptionId, Sink<Arc<SlotUpdate>>>>;
 type RpcVoteSubscriptions = RwLock<HashMap<SubscriptionId, Sink<RpcVote>>>;
@@ -372,6 +387,35 @@ fn filter_logs_results(
     }
 }
 
+fn filter_block_transactions(
+    block: &ConfirmedBlock,
+    mentioned_address: &Option<Pubkey>,
+) -> Option<ConfirmedBlock> {
+    let transactions = match mentioned_address {
+        Some(address) => {
+            let transactions: Vec<_> = block
+                .transactions
+                .iter()
+                .filter(|tx| tx.transaction.message.account_keys.contains(address))
+                .cloned()
+                .collect();
+            if transactions.is_empty() {
+                return None;
+            }
+            transactions
+        }
+        None => block.transactions.clone(),
+    };
+    Some(ConfirmedBlock {
+        previous_blockhash: block.previous_blockhash.clone(),
+        blockhash: block.blockhash.clone(),
+        parent_slot: block.parent_slot,
+        transactions,
+        rewards: block.rewards.clone(),
+        block_time: block.block_time,
+    })
+}
+
 fn total_nested_subscriptions<K, L, V>(
     subscription_map: &RwLock<HashMap<K, HashMap<L, V>>>,
 ) -> usize {
@@ -388,10 +432,12 @@ struct Subscriptions {
     program_subscriptions: Arc<RpcProgramSubscriptions>,
     logs_subscriptions: Arc<RpcLogsSubscriptions>,
     signature_subscriptions: Arc<RpcSignatureSubscriptions>,
+    block_subscriptions: Arc<RpcBlockSubscriptions>,
     gossip_account_subscriptions: Arc<RpcAccountSubscriptions>,
     gossip_logs_subscriptions: Arc<RpcLogsSubscriptions>,
     gossip_program_subscriptions: Arc<RpcProgramSubscriptions>,
     gossip_signature_subscriptions: Arc<RpcSignatureSubscriptions>,
+    gossip_block_subscriptions: Arc<RpcBlockSubscriptions>,
     slot_subscriptions: Arc<RpcSlotSubscriptions>,
     slots_updates_subscriptions: Arc<RpcSlotUpdateSubscriptions>,
     vote_subscriptions: Arc<RpcVoteSubscriptions>,
@@ -405,10 +451,12 @@ impl Subscriptions {
         total += total_nested_subscriptions(&self.program_subscriptions);
         total += total_nested_subscriptions(&self.logs_subscriptions);
         total += total_nested_subscriptions(&self.signature_subscriptions);
+        total += total_nested_subscriptions(&self.block_subscriptions);
         total += total_nested_subscriptions(&self.gossip_account_subscriptions);
         total += total_nested_subscriptions(&self.gossip_logs_subscriptions);
         total += total_nested_subscriptions(&self.gossip_program_subscriptions);
         total += total_nested_subscriptions(&self.gossip_signature_subscriptions);
+        total += total_nested_subscriptions(&self.gossip_block_subscriptions);
         total += self.slot_subscriptions.read().unwrap().len();
         total += self.vote_subscriptions.read().unwrap().len();
         total += self.root_subscriptions.read().unwrap().len();
@@ -425,6 +473,7 @@ pub struct RpcSubscriptions {
     optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
     exit: Arc<AtomicBool>,
     enable_vote_subscription: bool,
+    enable_block_subscription: bool,
 }
 
 impl Drop for RpcSubscriptions {
@@ -442,22 +491,28 @@ impl RpcSubscriptions {
         block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
         optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
     ) -> Self {
-        Self::new_with_vote_subscription(
+        Self::new_with_config(
             exit,
             bank_forks,
             block_commitment_cache,
             optimistically_confirmed_bank,
-            false,
+            None,
+            &PubSubConfig::default(),
         )
     }
 
-    pub fn new_with_vote_subscription(
+    /// Block subscriptions are only accepted if `config.enable_block_subscription` is set and a
+    /// `blockstore` to read the blocks from is provided
+    pub fn new_with_config(
         exit: &Arc<AtomicBool>,
         bank_forks: Arc<RwLock<BankForks>>,
         block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
         optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
-        enable_vote_subscription: bool,
+        blockstore: Option<Arc<Blockstore>>,
+        config: &PubSubConfig,
     ) -> Self {
+        let blockstore = blockstore.filter(|_| config.enable_block_subscription);
+        let enable_block_subscription = blockstore.is_some();
         let (notification_sender, notification_receiver): (
             Sender<NotificationEntry>,
             Receiver<NotificationEntry>,
@@ -467,10 +522,12 @@ impl RpcSubscriptions {
         let logs_subscriptions = Arc::new(RpcLogsSubscriptions::default());
         let program_subscriptions = Arc::new(RpcProgramSubscriptions::default());
         let signature_subscriptions = Arc::new(RpcSignatureSubscriptions::default());
+        let block_subscriptions = Arc::new(RpcBlockSubscriptions::default());
         let gossip_account_subscriptions = Arc::new(RpcAccountSubscriptions::default());
         let gossip_logs_subscriptions = Arc::new(RpcLogsSubscriptions::default());
         let gossip_program_subscriptions = Arc::new(RpcProgramSubscriptions::default());
         let gossip_signature_subscriptions = Arc::new(RpcSignatureSubscriptions::default());
+        let gossip_block_subscriptions = Arc::new(RpcBlockSubscriptions::default());
         let slot_subscriptions = Arc::new(RpcSlotSubscriptions::default());
         let slots_updates_subscriptions = Arc::new(RpcSlotUpdateSubscriptions::default());
         let vote_subscriptions = Arc::new(RpcVoteSubscriptions::default());
@@ -485,10 +542,12 @@ impl RpcSubscriptions {
             logs_subscriptions,
             program_subscriptions,
             signature_subscriptions,
+            block_subscriptions,
             gossip_account_subscriptions,
             gossip_logs_subscriptions,
             gossip_program_subscriptions,
             gossip_signature_subscriptions,
+            gossip_block_subscriptions,
             slot_subscriptions,
             slots_updates_subscriptions,
             vote_subscriptions,
@@ -506,6 +565,7 @@ impl RpcSubscriptions {
                     notification_receiver,
                     _subscriptions,
                     _bank_forks,
+                    blockstore,
                 );
             })
             .unwrap();
@@ -518,7 +578,8 @@ impl RpcSubscriptions {
             block_commitment_cache,
             optimistically_confirmed_bank,
             exit: exit.clone(),
-            enable_vote_subscription,
+            enable_vote_subscription: config.enable_vote_subscription,
+            enable_block_subscription,
         }
     }
 
@@ -526,12 +587,16 @@ impl RpcSubscriptions {
     pub fn default_with_bank_forks(bank_forks: Arc<RwLock<BankForks>>) -> Self {
         let optimistically_confirmed_bank =
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
-        Self::new_with_vote_subscription(
+        Self::new_with_config(
             &Arc::new(AtomicBool::new(false)),
             bank_forks,
             Arc::new(RwLock::new(BlockCommitmentCache::default())),
             optimistically_confirmed_bank,
-            true,
+            None,
+            &PubSubConfig {
+                enable_vote_subscription: true,
+                ..PubSubConfig::default()
+            },
         )
     }
 
@@ -618,6 +683,111 @@ impl RpcSubscriptions {
         notified_ids
     }
 
+    /// Notify block subscribers of every block on the fork of the commitment slot that was
+    /// completed since their last notification. Returns the number of notifications sent.
+    ///
+    /// A block whose transaction statuses aren't all written yet is held back, along with the
+    /// blocks after it, for up to `MAX_BLOCK_STATUS_WAIT_SLOTS` slots, and is then sent with an
+    /// `IncompleteBlock` error.
+    fn check_blocks(
+        block_subscriptions: &Arc<RpcBlockSubscriptions>,
+        blockstore: &Blockstore,
+        bank_forks: &Arc<RwLock<BankForks>>,
+        commitment_slots: &CommitmentSlots,
+        notifier: &RpcNotifier,
+    ) -> usize {
+        let subscriptions = block_subscriptions.read().unwrap();
+        // Every block is read once, along with whether it is complete, and then filtered for
+        // each subscriber
+        let mut blocks: HashMap<Slot, Option<(ConfirmedBlock, bool)>> = HashMap::new();
+        let mut num_notified = 0;
+        for (mentioned_address, hashmap) in subscriptions.iter() {
+            for SubscriptionData {
+                sink,
+                commitment,
+                last_notified_slot,
+                config,
+            } in hashmap.values()
+            {
+                let slot = if commitment.is_finalized() {
+                    commitment_slots.highest_confirmed_root
+                } else {
+                    commitment_slots.highest_confirmed_slot
+                };
+                let mut w_last_notified_slot = last_notified_slot.write().unwrap();
+                if slot <= *w_last_notified_slot {
+                    continue;
+                }
+                let bank = match bank_forks.read().unwrap().get(slot).cloned() {
+                    Some(bank) => bank,
+                    None => continue,
+                };
+                let config = config.as_ref().unwrap();
+
+                // Skip slots that are not on the fork of `bank`, including those that were
+                // skipped by the leader
+                let slots = (*w_last_notified_slot + 1..=slot)
+                    .filter(|s| bank.ancestors.contains_key(s) || blockstore.is_root(*s));
+                let mut notified_slot = slot;
+                for block_slot in slots {
+                    let block = blocks.entry(block_slot).or_insert_with(|| {
+                        match blockstore.get_complete_block(block_slot, false) {
+                            Ok(block) => {
+                                let is_complete =
+                                    block.transactions.iter().all(|tx| tx.meta.is_some());
+                                Some((block, is_complete))
+                            }
+                            Err(err) => {
+                                warn!("failed to load block {}: {:?}", block_slot, err);
+                                None
+                            }
+                        }
+                    });
+                    let block_update = match block {
+                        Some((block, is_complete)) => {
+                            if !*is_complete && slot - block_slot < MAX_BLOCK_STATUS_WAIT_SLOTS {
+                                notified_slot = block_slot - 1;
+                                break;
+                            }
+                            filter_block_transactions(block, mentioned_address).map(|block| {
+                                RpcBlockUpdate {
+                                    slot: block_slot,
+                                    block: Some(block.configure(
+                                        config.encoding,
+                                        config.transaction_details,
+                                        config.show_rewards,
+                                    )),
+                                    err: if *is_complete {
+                                        None
+                                    } else {
+                                        Some(RpcBlockUpdateError::IncompleteBlock)
+                                    },
+                                }
+                            })
+                        }
+                        None => Some(RpcBlockUpdate {
+                            slot: block_slot,
+                            block: None,
+                            err: Some(RpcBlockUpdateError::BlockStoreError),
+                        }),
+                    };
+                    if let Some(block_update) = block_update {
+                        notifier.notify(
+                            Response {
+                                context: RpcResponseContext { slot: block_slot },
+                                value: block_update,
+                            },
+                            sink,
+                        );
+                        num_notified += 1;
+                    }
+                }
+                *w_last_notified_slot = notified_slot;
+            }
+        }
+        num_notified
+    }
+
     pub fn total(&self) -> usize {
         self.subscriptions.total()
     }
@@ -900,6 +1070,83 @@ impl RpcSubscriptions {
         }
     }
 
+    pub fn add_block_subscription(
+        &self,
+        mentioned_address: Option<Pubkey>,
+        config: Option<RpcBlockSubscribeConfig>,
+        sub_id: SubscriptionId,
+        subscriber: Subscriber<Response<RpcBlockUpdate>>,
+    ) {
+        if !self.enable_block_subscription {
+            let _ = subscriber.reject(jsonrpc_core::Error::new(
+                jsonrpc_core::ErrorCode::MethodNotFound,
+            ));
+            return;
+        }
+
+        let config = config.unwrap_or_default();
+        let commitment = config
+            .commitment
+            .unwrap_or_else(CommitmentConfig::confirmed);
+        if commitment.is_processed() {
+            // Transaction statuses of processed blocks may not be in the blockstore yet
+            let _ = subscriber.reject(jsonrpc_core::Error::invalid_params(
+                "Invalid Request: Only confirmed and finalized commitments are supported",
+            ));
+            return;
+        }
+
+        let (mut subscriptions, last_notified_slot) = if commitment.is_finalized() {
+            (
+                self.subscriptions.block_subscriptions.write().unwrap(),
+                self.block_commitment_cache
+                    .read()
+                    .unwrap()
+                    .highest_confirmed_root(),
+            )
+        } else {
+            (
+                self.subscriptions
+                    .gossip_block_subscriptions
+                    .write()
+                    .unwrap(),
+                self.optimistically_confirmed_bank
+                    .read()
+                    .unwrap()
+                    .bank
+                    .slot(),
+            )
+        };
+
+        add_subscription(
+            &mut subscriptions,
+            mentioned_address,
+            commitment,
+            sub_id,
+            subscriber,
+            last_notified_slot,
+            Some(BlockConfig {
+                encoding: config.encoding.unwrap_or(UiTransactionEncoding::Json),
+                transaction_details: config.transaction_details.unwrap_or_default(),
+                show_rewards: config.show_rewards.unwrap_or(true),
+            }),
+        );
+    }
+
+    pub fn remove_block_subscription(&self, id: &SubscriptionId) -> bool {
+        let mut subscriptions = self.subscriptions.block_subscriptions.write().unwrap();
+        if remove_subscription(&mut subscriptions, id) {
+            true
+        } else {
+            let mut subscriptions = self
+                .subscriptions
+                .gossip_block_subscriptions
+                .write()
+                .unwrap();
+            remove_subscription(&mut subscriptions, id)
+        }
+    }
+
     /// Notify subscribers of changes to any accounts or new signatures since
     /// the bank's last checkpoint.
     pub fn notify_subscribers(&self, commitment_slots: CommitmentSlots) {
@@ -1029,6 +1276,7 @@ impl RpcSubscriptions {
         notification_receiver: Receiver<NotificationEntry>,
         subscriptions: Subscriptions,
         bank_forks: Arc<RwLock<BankForks>>,
+        blockstore: Option<Arc<Blockstore>>,
     ) {
         loop {
             if exit.load(Ordering::Relaxed) {
@@ -1108,7 +1356,20 @@ impl RpcSubscriptions {
                             &commitment_slots,
                             &notifier,
                             "bank",
-                        )
+                        );
+                        if let Some(blockstore) = &blockstore {
+                            let num_blocks_notified = Self::check_blocks(
+                                &subscriptions.block_subscriptions,
+                                blockstore,
+                                &bank_forks,
+                                &commitment_slots,
+                                &notifier,
+                            );
+                            inc_new_counter_info!(
+                                "rpc-subscription-notify-block",
+                                num_blocks_notified
+                            );
+                        }
                     }
                     NotificationEntry::Gossip(slot) => {
                         Self::process_gossip_notification(
@@ -1116,6 +1377,7 @@ impl RpcSubscriptions {
                             &notifier,
                             &subscriptions,
                             &bank_forks,
+                            blockstore.as_deref(),
                         );
                     }
                     NotificationEntry::SignaturesReceived(slot_signatures) => {
@@ -1147,6 +1409,7 @@ impl RpcSubscriptions {
         notifier: &RpcNotifier,
         subscriptions: &Subscriptions,
         bank_forks: &Arc<RwLock<BankForks>>,
+        blockstore: Option<&Blockstore>,
     ) {
         let commitment_slots = CommitmentSlots {
             highest_confirmed_slot: slot,
@@ -1162,6 +1425,16 @@ impl RpcSubscriptions {
             &notifier,
             "gossip",
         );
+        if let Some(blockstore) = blockstore {
+            let num_blocks_notified = Self::check_blocks(
+                &subscriptions.gossip_block_subscriptions,
+                blockstore,
+                bank_forks,
+                &commitment_slots,
+                notifier,
+            );
+            inc_new_counter_info!("rpc-subscription-notify-gossip-block", num_blocks_notified);
+        }
     }
 
     fn notify_accounts_logs_programs_signatures(
@@ -1338,16 +1611,21 @@ pub(crate) mod tests {
     use jsonrpc_core::futures::StreamExt;
     use jsonrpc_pubsub::typed::Subscriber;
     use serial_test::serial;
+    use solana_ledger::{
+        blockstore::entries_to_test_shreds, entry::next_entry, get_tmp_ledger_path,
+    };
     use solana_runtime::{
         commitment::BlockCommitment,
         genesis_utils::{create_genesis_config, GenesisConfigInfo},
     };
     use solana_sdk::{
+        hash::Hash,
         message::Message,
         signature::{Keypair, Signer},
         system_instruction, system_program, system_transaction,
         transaction::Transaction,
     };
+    use solana_transaction_status::{TransactionStatusMeta, TransactionWithStatusMeta};
     use std::{fmt::Debug, sync::mpsc::channel};
     use tokio::{
         runtime::Runtime,
@@ -1828,6 +2106,196 @@ pub(crate) mod tests {
         );
     }
 
+    #[test]
+    #[serial]
+    fn test_check_block_subscribe() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(100);
+        let bank = Bank::new(&genesis_config);
+        let blockhash = bank.last_blockhash();
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+        let bank0 = bank_forks.read().unwrap().get(0).unwrap().clone();
+        let bank1 = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        bank_forks.write().unwrap().insert(bank1);
+        let alice = Keypair::new();
+
+        let ledger_path = get_tmp_ledger_path!();
+        let blockstore = Arc::new(Blockstore::open(&ledger_path).unwrap());
+        let tx = system_transaction::transfer(&mint_keypair, &alice.pubkey(), 1, blockhash);
+        let entry = next_entry(&blockhash, 1, vec![tx.clone()]);
+        let entry_hash = entry.hash;
+        blockstore
+            .insert_shreds(
+                entries_to_test_shreds(vec![entry], 1, 0, true, 0),
+                None,
+                false,
+            )
+            .unwrap();
+
+        let exit = Arc::new(AtomicBool::new(false));
+        let subscriptions = RpcSubscriptions::new_with_config(
+            &exit,
+            bank_forks.clone(),
+            Arc::new(RwLock::new(BlockCommitmentCache::new_for_tests())),
+            OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks),
+            Some(blockstore.clone()),
+            &PubSubConfig {
+                enable_block_subscription: true,
+                ..PubSubConfig::default()
+            },
+        );
+        let check_blocks = |slot| {
+            RpcSubscriptions::check_blocks(
+                &subscriptions.subscriptions.gossip_block_subscriptions,
+                &blockstore,
+                &bank_forks,
+                &CommitmentSlots {
+                    highest_confirmed_slot: slot,
+                    ..CommitmentSlots::default()
+                },
+                &RpcNotifier,
+            )
+        };
+
+        let (subscriber, _id_receiver, transport_receiver) =
+            Subscriber::new_test("blockNotification");
+        let sub_id = SubscriptionId::Number(0);
+        subscriptions.add_block_subscription(
+            Some(alice.pubkey()),
+            Some(RpcBlockSubscribeConfig {
+                commitment: Some(CommitmentConfig::confirmed()),
+                encoding: None,
+                transaction_details: Some(TransactionDetails::Signatures),
+                show_rewards: Some(false),
+            }),
+            sub_id.clone(),
+            subscriber,
+        );
+        assert!(subscriptions
+            .subscriptions
+            .gossip_block_subscriptions
+            .read()
+            .unwrap()
+            .contains_key(&Some(alice.pubkey())));
+
+        // The block is held back until the status of its transaction is written
+        assert_eq!(check_blocks(1), 0);
+        blockstore
+            .write_transaction_status(
+                1,
+                tx.signatures[0],
+                vec![&mint_keypair.pubkey(), &alice.pubkey()],
+                vec![&system_program::id()],
+                TransactionStatusMeta::default(),
+            )
+            .unwrap();
+        subscriptions.notify_gossip_subscribers(1);
+        let (response, transport_receiver) = robust_poll_or_panic(transport_receiver);
+        let expected = json!({
+           "jsonrpc": "2.0",
+           "method": "blockNotification",
+           "params": {
+               "result": {
+                   "context": { "slot": 1 },
+                   "value": {
+                       "slot": 1,
+                       "block": {
+                           "previousBlockhash": Hash::default().to_string(),
+                           "blockhash": entry_hash.to_string(),
+                           "parentSlot": 0,
+                           "signatures": [tx.signatures[0].to_string()],
+                           "blockTime": null,
+                       },
+                       "err": null,
+                   },
+               },
+               "subscription": 0,
+           }
+        });
+        assert_eq!(serde_json::to_string(&expected).unwrap(), response);
+
+        // A block whose transaction status is never written is sent as incomplete
+        let tx2 = system_transaction::transfer(&mint_keypair, &alice.pubkey(), 2, blockhash);
+        let entry2 = next_entry(&entry_hash, 1, vec![tx2.clone()]);
+        blockstore
+            .insert_shreds(
+                entries_to_test_shreds(vec![entry2], 2, 1, true, 0),
+                None,
+                false,
+            )
+            .unwrap();
+        let bank1 = bank_forks.read().unwrap().get(1).unwrap().clone();
+        let bank2 = Bank::new_from_parent(&bank1, &Pubkey::default(), 2);
+        let bank2 = bank_forks.write().unwrap().insert(bank2);
+        assert_eq!(check_blocks(2), 0);
+        let last_slot = 2 + MAX_BLOCK_STATUS_WAIT_SLOTS;
+        let last_bank = Bank::new_from_parent(&bank2, &Pubkey::default(), last_slot);
+        bank_forks.write().unwrap().insert(last_bank);
+        subscriptions.notify_gossip_subscribers(last_slot);
+        let (response, _) = robust_poll_or_panic(transport_receiver);
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        let value = &response["params"]["result"]["value"];
+        assert_eq!(value["slot"], 2);
+        assert_eq!(value["err"], "incompleteBlock");
+        assert_eq!(
+            value["block"]["signatures"],
+            json!([tx2.signatures[0].to_string()])
+        );
+
+        assert!(subscriptions.remove_block_subscription(&sub_id));
+        assert!(!subscriptions.remove_block_subscription(&sub_id));
+        drop(subscriptions);
+        Blockstore::destroy(&ledger_path).unwrap();
+    }
+
+    #[test]
+    fn test_filter_block_transactions() {
+        let mint_keypair = Keypair::new();
+        let alice = Pubkey::new_unique();
+        let tx = system_transaction::transfer(&mint_keypair, &alice, 1, Hash::default());
+        let block = || ConfirmedBlock {
+            previous_blockhash: Hash::default().to_string(),
+            blockhash: Hash::default().to_string(),
+            parent_slot: 0,
+            transactions: vec![TransactionWithStatusMeta {
+                transaction: tx.clone(),
+                meta: None,
+            }],
+            rewards: vec![],
+            block_time: None,
+        };
+
+        assert_eq!(filter_block_transactions(&block(), &None), Some(block()));
+        assert_eq!(
+            filter_block_transactions(&block(), &Some(alice)),
+            Some(block())
+        );
+        assert_eq!(
+            filter_block_transactions(&block(), &Some(system_program::id())),
+            Some(block())
+        );
+        assert_eq!(
+            filter_block_transactions(&block(), &Some(Pubkey::new_unique())),
+            None
+        );
+    }
+
+    #[test]
+    fn test_block_subscription_disabled() {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(100);
+        let bank = Bank::new(&genesis_config);
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+        let subscriptions = RpcSubscriptions::default_with_bank_forks(bank_forks);
+
+        let (subscriber, _id_receiver, _transport_receiver) =
+            Subscriber::new_test("blockNotification");
+        subscriptions.add_block_subscription(None, None, SubscriptionId::Number(0), subscriber);
+        assert_eq!(subscriptions.total(), 0);
+    }
+
     #[test]
     #[serial]
     fn test_check_slot_subscribe() {
@@ -2,6 +2,7 @@
 use crate::cluster_info::ClusterInfo;
 use crate::poh_recorder::PohRecorder;
 use log::*;
+use solana_client::quic_client::QuicClient;
 use solana_metrics::{datapoint_warn, inc_new_counter_info};
 use solana_runtime::{bank::Bank, bank_forks::BankForks};
 use solana_sdk::{
@@ -57,6 +58,9 @@ pub struct LeaderInfo {
     cluster_info: Arc<ClusterInfo>,
     poh_recorder: Arc<Mutex<PohRecorder>>,
     recent_peers: HashMap<Pubkey, SocketAddr>,
+    /// TPU QUIC addresses of the recent peers that accept transactions over QUIC, keyed by
+    /// their UDP TPU address
+    recent_quic_peers: HashMap<SocketAddr, SocketAddr>,
 }
 
 impl LeaderInfo {
@@ -65,16 +69,22 @@ impl LeaderInfo {
             cluster_info,
             poh_recorder,
             recent_peers: HashMap::new(),
+            recent_quic_peers: HashMap::new(),
         }
     }
 
     pub fn refresh_recent_peers(&mut self) {
-        self.recent_peers = self
-            .cluster_info
-            .tpu_peers()
-            .into_iter()
-            .map(|ci| (ci.id, ci.tpu))
+        let tpu_peers = self.cluster_info.tpu_peers();
+        self.recent_quic_peers = tpu_peers
+            .iter()
+            .filter_map(|ci| Some((ci.tpu, ci.valid_tpu_quic_addr()?)))
             .collect();
+        self.recent_peers = tpu_peers.into_iter().map(|ci| (ci.id, ci.tpu)).collect();
+    }
+
+    /// Return the QUIC address of the peer at `tpu`, if it accepts transactions over QUIC
+    pub fn get_tpu_quic(&self, tpu: &SocketAddr) -> Option<&SocketAddr> {
+        self.recent_quic_peers.get(tpu)
     }
 
     pub fn get_leader_tpus(&self, max_count: u64) -> Vec<&SocketAddr> {
@@ -112,6 +122,27 @@ impl SendTransactionService {
         receiver: Receiver<TransactionInfo>,
         retry_rate_ms: u64,
         leader_forward_count: u64,
+    ) -> Self {
+        Self::new_with_quic_client(
+            tpu_address,
+            bank_forks,
+            leader_info,
+            receiver,
+            retry_rate_ms,
+            leader_forward_count,
+            None,
+        )
+    }
+
+    /// Like `new`, but sends transactions over QUIC to the leaders that accept them
+    pub fn new_with_quic_client(
+        tpu_address: SocketAddr,
+        bank_forks: &Arc<RwLock<BankForks>>,
+        leader_info: Option<LeaderInfo>,
+        receiver: Receiver<TransactionInfo>,
+        retry_rate_ms: u64,
+        leader_forward_count: u64,
+        quic_client: Option<QuicClient>,
     ) -> Self {
         let thread = Self::retry_thread(
             tpu_address,
@@ -120,6 +151,7 @@ impl SendTransactionService {
             leader_info,
             retry_rate_ms,
             leader_forward_count,
+            quic_client,
         );
         Self { thread }
     }
@@ -131,6 +163,7 @@ impl SendTransactionService {
         mut leader_info: Option<LeaderInfo>,
         retry_rate_ms: u64,
         leader_forward_count: u64,
+        quic_client: Option<QuicClient>,
     ) -> JoinHandle<()> {
         let mut last_status_check = Instant::now();
         let mut last_leader_refresh = Instant::now();
@@ -148,23 +181,18 @@ impl SendTransactionService {
                     Err(RecvTimeoutError::Disconnected) => break,
                     Err(RecvTimeoutError::Timeout) => {}
                     Ok(transaction_info) => {
-                        let addresses = leader_info
-                            .as_ref()
-                            .map(|leader_info| leader_info.get_leader_tpus(leader_forward_count));
-                        let addresses = addresses
-                            .map(|address_list| {
-                                if address_list.is_empty() {
-                                    vec![&tpu_address]
-                                } else {
-                                    address_list
-                                }
-                            })
-                            .unwrap_or_else(|| vec![&tpu_address]);
+                        let addresses = Self::get_tpu_addresses(
+                            &tpu_address,
+                            &leader_info,
+                            leader_forward_count,
+                        );
                         for address in addresses {
-                            Self::send_transaction(
+                            Self::send_transactions(
                                 &send_socket,
+                                quic_client.as_ref(),
+                                &leader_info,
                                 address,
-                                &transaction_info.wire_transaction,
+                                std::slice::from_ref(&transaction_info.wire_transaction),
                             );
                         }
                         if transactions.len() < MAX_TRANSACTION_QUEUE_SIZE {
@@ -193,6 +221,7 @@ impl SendTransactionService {
                             &working_bank,
                             &root_bank,
                             &send_socket,
+                            quic_client.as_ref(),
                             &tpu_address,
                             &mut transactions,
                             &leader_info,
@@ -215,12 +244,14 @@ impl SendTransactionService {
         working_bank: &Arc<Bank>,
         root_bank: &Arc<Bank>,
         send_socket: &UdpSocket,
+        quic_client: Option<&QuicClient>,
         tpu_address: &SocketAddr,
         transactions: &mut HashMap<Signature, TransactionInfo>,
         leader_info: &Option<LeaderInfo>,
         leader_forward_count: u64,
     ) -> ProcessTransactionsResult {
         let mut result = ProcessTransactionsResult::default();
+        let mut retry_transactions = vec![];
 
         transactions.retain(|signature, transaction_info| {
             if transaction_info.durable_nonce_info.is_some() {
@@ -257,25 +288,7 @@ impl SendTransactionService {
                     info!("Retrying transaction: {}", signature);
                     result.retried += 1;
                     inc_new_counter_info!("send_transaction_service-retry", 1);
-                    let addresses = leader_info
-                        .as_ref()
-                        .map(|leader_info| leader_info.get_leader_tpus(leader_forward_count));
-                    let addresses = addresses
-                        .map(|address_list| {
-                            if address_list.is_empty() {
-                                vec![tpu_address]
-                            } else {
-                                address_list
-                            }
-                        })
-                        .unwrap_or_else(|| vec![&tpu_address]);
-                    for address in addresses {
-                        Self::send_transaction(
-                            &send_socket,
-                            address,
-                            &transaction_info.wire_transaction,
-                        );
-                    }
+                    retry_transactions.push(transaction_info.wire_transaction.clone());
                     true
                 }
                 Some((_slot, status)) => {
@@ -292,16 +305,64 @@ impl SendTransactionService {
             }
         });
 
+        if !retry_transactions.is_empty() {
+            let addresses = Self::get_tpu_addresses(tpu_address, leader_info, leader_forward_count);
+            for address in addresses {
+                Self::send_transactions(
+                    send_socket,
+                    quic_client,
+                    leader_info,
+                    address,
+                    &retry_transactions,
+                );
+            }
+        }
+
         result
     }
 
-    fn send_transaction(
+    fn get_tpu_addresses<'a>(
+        tpu_address: &'a SocketAddr,
+        leader_info: &'a Option<LeaderInfo>,
+        leader_forward_count: u64,
+    ) -> Vec<&'a SocketAddr> {
+        leader_info
+            .as_ref()
+            .map(|leader_info| leader_info.get_leader_tpus(leader_forward_count))
+            .filter(|addresses| !addresses.is_empty())
+            .unwrap_or_else(|| vec![tpu_address])
+    }
+
+    /// Send the transactions over QUIC if the leader at `tpu_address` accepts them, without
+    /// waiting for the send to finish, and over UDP otherwise
+    fn send_transactions(
         send_socket: &UdpSocket,
+        quic_client: Option<&QuicClient>,
+        leader_info: &Option<LeaderInfo>,
         tpu_address: &SocketAddr,
-        wire_transaction: &[u8],
+        wire_transactions: &[Vec<u8>],
     ) {
-        if let Err(err) = send_socket.send_to(wire_transaction, tpu_address) {
-            warn!("Failed to send transaction to {}: {:?}", tpu_address, err);
+        let tpu_quic = leader_info
+            .as_ref()
+            .and_then(|leader_info| leader_info.get_tpu_quic(tpu_address));
+        if let (Some(quic_client), Some(tpu_quic)) = (quic_client, tpu_quic) {
+            match quic_client.try_send_wire_transaction_batch(tpu_quic, wire_transactions.to_vec())
+            {
+                Ok(()) => return,
+                // Fall back to UDP
+                Err(err) => {
+                    inc_new_counter_info!(
+                        "send_transaction_service-quic-fallback",
+                        wire_transactions.len()
+                    );
+                    debug!("Not sending transactions to {}: {:?}", tpu_quic, err);
+                }
+            }
+        }
+        for wire_transaction in wire_transactions {
+            if let Err(err) = send_socket.send_to(wire_transaction, tpu_address) {
+                warn!("Failed to send transaction to {}: {:?}", tpu_address, err);
+            }
         }
     }
 
@@ -393,6 +454,7 @@ mod test {
             &working_bank,
             &root_bank,
             &send_socket,
+            None,
             &tpu_address,
             &mut transactions,
             &None,
@@ -416,6 +478,7 @@ mod test {
             &working_bank,
             &root_bank,
             &send_socket,
+            None,
             &tpu_address,
             &mut transactions,
             &None,
@@ -439,6 +502,7 @@ mod test {
             &working_bank,
             &root_bank,
             &send_socket,
+            None,
             &tpu_address,
             &mut transactions,
             &None,
@@ -462,6 +526,7 @@ mod test {
             &working_bank,
             &root_bank,
             &send_socket,
+            None,
             &tpu_address,
             &mut transactions,
             &None,
@@ -486,6 +551,7 @@ mod test {
             &working_bank,
             &root_bank,
             &send_socket,
+            None,
             &tpu_address,
             &mut transactions,
             &None,
@@ -565,6 +631,7 @@ mod test {
             &working_bank,
             &root_bank,
             &send_socket,
+            None,
             &tpu_address,
             &mut transactions,
             &None,
@@ -592,6 +659,7 @@ mod test {
             &working_bank,
             &root_bank,
             &send_socket,
+            None,
             &tpu_address,
             &mut transactions,
             &None,
@@ -621,6 +689,7 @@ mod test {
             &working_bank,
             &root_bank,
             &send_socket,
+            None,
             &tpu_address,
             &mut transactions,
             &None,
@@ -648,6 +717,7 @@ mod test {
             &working_bank,
             &root_bank,
             &send_socket,
+            None,
             &tpu_address,
             &mut transactions,
             &None,
@@ -676,6 +746,7 @@ mod test {
             &working_bank,
             &root_bank,
             &send_socket,
+            None,
             &tpu_address,
             &mut transactions,
             &None,
@@ -704,6 +775,7 @@ mod test {
             &working_bank,
             &root_bank,
             &send_socket,
+            None,
             &tpu_address,
             &mut transactions,
             &None,
@@ -733,6 +805,7 @@ mod test {
             &working_bank,
             &root_bank,
             &send_socket,
+            None,
             &tpu_address,
             &mut transactions,
             &None,
@@ -761,6 +834,7 @@ mod test {
             &working_bank,
             &root_bank,
             &send_socket,
+            None,
             &tpu_address,
             &mut transactions,
             &None,
@@ -843,6 +917,7 @@ mod test {
                 cluster_info,
                 poh_recorder: Arc::new(Mutex::new(poh_recorder)),
                 recent_peers: recent_peers.clone(),
+                recent_quic_peers: HashMap::new(),
             };
 
             let slot = bank.slot();
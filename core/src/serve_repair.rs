@@ -5,24 +5,33 @@ use crate::{
     repair_response,
     repair_service::RepairStats,
     result::{Error, Result},
-    weighted_shuffle::weighted_best,
+    weighted_shuffle::{weighted_best, weighted_shuffle},
 };
 use bincode::serialize;
 use rand::distributions::{Distribution, WeightedIndex};
-use solana_ledger::{blockstore::Blockstore, shred::Nonce};
+use solana_ledger::{
+    blockstore::Blockstore,
+    shred::{Nonce, SIZE_OF_NONCE},
+};
 use solana_measure::measure::Measure;
 use solana_measure::thread_mem_usage;
 use solana_metrics::{datapoint_debug, inc_new_counter_debug};
-use solana_perf::packet::{limited_deserialize, Packets, PacketsRecycler};
+use solana_perf::packet::{limited_deserialize, Packet, Packets, PacketsRecycler};
+use solana_runtime::{bank::Bank, bank_forks::BankForks};
 use solana_sdk::{
     clock::Slot,
+    feature_set,
+    hash::{Hash, HASH_BYTES},
+    packet::PACKET_DATA_SIZE,
     pubkey::Pubkey,
-    signature::{Keypair, Signer},
-    timing::duration_as_ms,
+    signature::{Keypair, Signature, Signer, SIGNATURE_BYTES},
+    timing::{duration_as_ms, timestamp},
 };
 use solana_streamer::streamer::{PacketReceiver, PacketSender};
 use std::{
     collections::{hash_map::Entry, HashMap, HashSet},
+    io::Cursor,
+    mem::size_of,
     net::SocketAddr,
     sync::atomic::{AtomicBool, Ordering},
     sync::{Arc, RwLock},
@@ -33,12 +42,23 @@ use std::{
 /// the number of slots to respond with when responding to `Orphan` requests
 pub const MAX_ORPHAN_REPAIR_RESPONSES: usize = 10;
 pub const DEFAULT_NONCE: u32 = 42;
+/// the number of `(slot, hash)` pairs that fit in a response to an `AncestorHashes` request
+pub const MAX_ANCESTOR_RESPONSES: usize =
+    (PACKET_DATA_SIZE - SIZE_OF_NONCE - size_of::<u64>()) / (size_of::<Slot>() + HASH_BYTES);
+/// Signed requests with a timestamp further than this from the local clock are dropped
+const SIGNED_REPAIR_TIME_WINDOW: Duration = Duration::from_secs(60 * 10);
+/// Number of signed requests served for a single sender in every `REPAIR_RATE_LIMIT_INTERVAL`
+const MAX_REPAIR_REQUESTS_PER_SENDER: usize = 2048;
+const REPAIR_RATE_LIMIT_INTERVAL: Duration = Duration::from_secs(1);
+/// Signed requests start with the enum variant index, followed by the header signature
+const SIGNED_REPAIR_SIGNATURE_OFFSET: usize = 4;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub enum RepairType {
     Orphan(Slot),
     HighestShred(Slot, u64),
     Shred(Slot, u64),
+    AncestorHashes(Slot),
 }
 
 impl RepairType {
@@ -47,7 +67,27 @@ impl RepairType {
             RepairType::Orphan(slot) => *slot,
             RepairType::HighestShred(slot, _) => *slot,
             RepairType::Shred(slot, _) => *slot,
+            RepairType::AncestorHashes(slot) => *slot,
+        }
+    }
+}
+
+/// Response to an `AncestorHashes` request: the bank hashes of the requested slot and its
+/// ancestors, starting from the requested slot
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AncestorHashesResponse {
+    pub hashes: Vec<(Slot, Hash)>,
+}
+
+impl AncestorHashesResponse {
+    /// Deserialize a response packet, returning the response along with its nonce
+    pub fn from_packet_data(data: &[u8]) -> Option<(Self, Nonce)> {
+        let nonce = repair_response::nonce(data)?;
+        let response: Self = limited_deserialize(&data[..data.len() - SIZE_OF_NONCE]).ok()?;
+        if response.hashes.is_empty() || response.hashes.len() > MAX_ANCESTOR_RESPONSES {
+            return None;
         }
+        Some((response, nonce))
     }
 }
 
@@ -60,17 +100,129 @@ pub struct ServeRepairStats {
     pub window_index: usize,
     pub highest_window_index: usize,
     pub orphan: usize,
+    pub ancestor_hashes: usize,
+    pub err_unsigned: usize,
+    pub err_id_mismatch: usize,
+    pub err_time_skew: usize,
+    pub err_sig_verify: usize,
+    pub err_rate_limited: usize,
+}
+
+/// Header of signed repair requests, identifying both ends of the request
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RepairRequestHeader {
+    /// Signature of the serialized request, excluding the signature itself
+    signature: Signature,
+    sender: Pubkey,
+    recipient: Pubkey,
+    /// Wallclock in milliseconds when the request was created
+    timestamp: u64,
+    nonce: Nonce,
+}
+
+impl RepairRequestHeader {
+    pub fn new(sender: Pubkey, recipient: Pubkey, timestamp: u64, nonce: Nonce) -> Self {
+        Self {
+            signature: Signature::default(),
+            sender,
+            recipient,
+            timestamp,
+            nonce,
+        }
+    }
 }
 
 /// Window protocol messages
 #[derive(Serialize, Deserialize, Debug)]
 pub enum RepairProtocol {
-    WindowIndex(ContactInfo, u64, u64),
-    HighestWindowIndex(ContactInfo, u64, u64),
-    Orphan(ContactInfo, u64),
-    WindowIndexWithNonce(ContactInfo, u64, u64, Nonce),
-    HighestWindowIndexWithNonce(ContactInfo, u64, u64, Nonce),
-    OrphanWithNonce(ContactInfo, u64, Nonce),
+    LegacyWindowIndex(ContactInfo, u64, u64),
+    LegacyHighestWindowIndex(ContactInfo, u64, u64),
+    LegacyOrphan(ContactInfo, u64),
+    LegacyWindowIndexWithNonce(ContactInfo, u64, u64, Nonce),
+    LegacyHighestWindowIndexWithNonce(ContactInfo, u64, u64, Nonce),
+    LegacyOrphanWithNonce(ContactInfo, u64, Nonce),
+    WindowIndex {
+        header: RepairRequestHeader,
+        slot: Slot,
+        shred_index: u64,
+    },
+    HighestWindowIndex {
+        header: RepairRequestHeader,
+        slot: Slot,
+        shred_index: u64,
+    },
+    Orphan {
+        header: RepairRequestHeader,
+        slot: Slot,
+    },
+    AncestorHashes {
+        header: RepairRequestHeader,
+        slot: Slot,
+    },
+}
+
+impl RepairProtocol {
+    /// The sender of the request; only authenticated for signed requests
+    fn sender(&self) -> &Pubkey {
+        match self {
+            Self::LegacyWindowIndex(from, _, _) => &from.id,
+            Self::LegacyHighestWindowIndex(from, _, _) => &from.id,
+            Self::LegacyOrphan(from, _) => &from.id,
+            Self::LegacyWindowIndexWithNonce(from, _, _, _) => &from.id,
+            Self::LegacyHighestWindowIndexWithNonce(from, _, _, _) => &from.id,
+            Self::LegacyOrphanWithNonce(from, _, _) => &from.id,
+            Self::WindowIndex { header, .. } => &header.sender,
+            Self::HighestWindowIndex { header, .. } => &header.sender,
+            Self::Orphan { header, .. } => &header.sender,
+            Self::AncestorHashes { header, .. } => &header.sender,
+        }
+    }
+
+    fn header(&self) -> Option<&RepairRequestHeader> {
+        match self {
+            Self::LegacyWindowIndex(_, _, _)
+            | Self::LegacyHighestWindowIndex(_, _, _)
+            | Self::LegacyOrphan(_, _)
+            | Self::LegacyWindowIndexWithNonce(_, _, _, _)
+            | Self::LegacyHighestWindowIndexWithNonce(_, _, _, _)
+            | Self::LegacyOrphanWithNonce(_, _, _) => None,
+            Self::WindowIndex { header, .. }
+            | Self::HighestWindowIndex { header, .. }
+            | Self::Orphan { header, .. }
+            | Self::AncestorHashes { header, .. } => Some(header),
+        }
+    }
+}
+
+/// Number of signed requests served for each sender since the last reset
+struct SenderRequestCounts {
+    counts: HashMap<Pubkey, usize>,
+    last_reset: Instant,
+}
+
+impl Default for SenderRequestCounts {
+    fn default() -> Self {
+        Self {
+            counts: HashMap::new(),
+            last_reset: Instant::now(),
+        }
+    }
+}
+
+impl SenderRequestCounts {
+    /// Count a request from `sender`, returning false if the sender is over its limit
+    fn check_and_increment(&mut self, sender: &Pubkey) -> bool {
+        if self.last_reset.elapsed() >= REPAIR_RATE_LIMIT_INTERVAL {
+            self.counts.clear();
+            self.last_reset = Instant::now();
+        }
+        let count = self.counts.entry(*sender).or_default();
+        if *count >= MAX_REPAIR_REQUESTS_PER_SENDER {
+            return false;
+        }
+        *count += 1;
+        true
+    }
 }
 
 #[derive(Clone)]
@@ -79,6 +231,7 @@ pub struct ServeRepair {
     keypair: Arc<Keypair>,
     my_info: ContactInfo,
     cluster_info: Arc<ClusterInfo>,
+    bank_forks: Arc<RwLock<BankForks>>,
 }
 
 type RepairCache = HashMap<Slot, (Vec<ContactInfo>, WeightedIndex<u64>)>;
@@ -86,17 +239,19 @@ type RepairCache = HashMap<Slot, (Vec<ContactInfo>, WeightedIndex<u64>)>;
 impl ServeRepair {
     /// Without a valid keypair gossip will not function. Only useful for tests.
     pub fn new_with_invalid_keypair(contact_info: ContactInfo) -> Self {
-        Self::new(Arc::new(ClusterInfo::new_with_invalid_keypair(
-            contact_info,
-        )))
+        Self::new(
+            Arc::new(ClusterInfo::new_with_invalid_keypair(contact_info)),
+            Arc::new(RwLock::new(BankForks::new(Bank::default()))),
+        )
     }
 
-    pub fn new(cluster_info: Arc<ClusterInfo>) -> Self {
+    pub fn new(cluster_info: Arc<ClusterInfo>, bank_forks: Arc<RwLock<BankForks>>) -> Self {
         let (keypair, my_info) = { (cluster_info.keypair.clone(), cluster_info.my_contact_info()) };
         Self {
             keypair,
             my_info,
             cluster_info,
+            bank_forks,
         }
     }
 
@@ -108,15 +263,68 @@ impl ServeRepair {
         &self.keypair
     }
 
-    fn get_repair_sender(request: &RepairProtocol) -> &ContactInfo {
-        match request {
-            RepairProtocol::WindowIndex(ref from, _, _) => from,
-            RepairProtocol::HighestWindowIndex(ref from, _, _) => from,
-            RepairProtocol::Orphan(ref from, _) => from,
-            RepairProtocol::WindowIndexWithNonce(ref from, _, _, _) => from,
-            RepairProtocol::HighestWindowIndexWithNonce(ref from, _, _, _) => from,
-            RepairProtocol::OrphanWithNonce(ref from, _, _) => from,
+    /// Whether requests sent by this node should be signed
+    fn sign_repair_requests(&self) -> bool {
+        let root_bank = self.bank_forks.read().unwrap().root_bank();
+        root_bank
+            .feature_set
+            .is_active(&feature_set::sign_repair_requests::id())
+    }
+
+    /// Unsigned requests are still served for the rest of the epoch signing is activated in,
+    /// so that nodes which haven't rooted the activation yet can be repaired
+    fn drop_unsigned_repair_requests(root_bank: &Bank) -> bool {
+        root_bank
+            .feature_set
+            .activated_slot(&feature_set::sign_repair_requests::id())
+            .map(|activation_slot| {
+                root_bank.epoch() > root_bank.epoch_schedule().get_epoch(activation_slot)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Check that a signed request is addressed to this node, is recent, and was signed by
+    /// its sender.  Unsigned requests are not checked here.
+    fn verify_signed_packet(
+        my_id: &Pubkey,
+        packet: &Packet,
+        request: &RepairProtocol,
+        stats: &mut ServeRepairStats,
+    ) -> bool {
+        let header = match request.header() {
+            Some(header) => header,
+            None => return true,
+        };
+        if header.recipient != *my_id {
+            stats.err_id_mismatch += 1;
+            return false;
+        }
+        let now = timestamp();
+        let time_diff_ms = if now > header.timestamp {
+            now - header.timestamp
+        } else {
+            header.timestamp - now
+        };
+        if u128::from(time_diff_ms) > SIGNED_REPAIR_TIME_WINDOW.as_millis() {
+            stats.err_time_skew += 1;
+            return false;
+        }
+        let data = &packet.data[..packet.meta.size];
+        let signature_end = SIGNED_REPAIR_SIGNATURE_OFFSET + SIGNATURE_BYTES;
+        if data.len() < signature_end
+            || !header.signature.verify(
+                header.sender.as_ref(),
+                &[
+                    &data[..SIGNED_REPAIR_SIGNATURE_OFFSET],
+                    &data[signature_end..],
+                ]
+                .concat(),
+            )
+        {
+            stats.err_sig_verify += 1;
+            return false;
         }
+        true
     }
 
     fn handle_repair(
@@ -129,17 +337,21 @@ impl ServeRepair {
     ) -> Option<Packets> {
         let now = Instant::now();
 
-        //TODO verify from is signed
         let my_id = me.read().unwrap().keypair.pubkey();
-        let from = Self::get_repair_sender(&request);
-        if from.id == my_id {
+        let from = request.sender();
+        if *from == my_id {
             stats.self_repair += 1;
             return None;
         }
 
         let (res, label) = {
             match &request {
-                RepairProtocol::WindowIndexWithNonce(_, slot, shred_index, nonce) => {
+                RepairProtocol::LegacyWindowIndexWithNonce(_, slot, shred_index, nonce)
+                | RepairProtocol::WindowIndex {
+                    header: RepairRequestHeader { nonce, .. },
+                    slot,
+                    shred_index,
+                } => {
                     stats.window_index += 1;
                     (
                         Self::run_window_request(
@@ -152,10 +364,20 @@ impl ServeRepair {
                             *shred_index,
                             *nonce,
                         ),
-                        "WindowIndexWithNonce",
+                        "WindowIndex",
                     )
                 }
-                RepairProtocol::HighestWindowIndexWithNonce(_, slot, highest_index, nonce) => {
+                RepairProtocol::LegacyHighestWindowIndexWithNonce(
+                    _,
+                    slot,
+                    highest_index,
+                    nonce,
+                )
+                | RepairProtocol::HighestWindowIndex {
+                    header: RepairRequestHeader { nonce, .. },
+                    slot,
+                    shred_index: highest_index,
+                } => {
                     stats.highest_window_index += 1;
                     (
                         Self::run_highest_window_request(
@@ -166,10 +388,14 @@ impl ServeRepair {
                             *highest_index,
                             *nonce,
                         ),
-                        "HighestWindowIndexWithNonce",
+                        "HighestWindowIndex",
                     )
                 }
-                RepairProtocol::OrphanWithNonce(_, slot, nonce) => {
+                RepairProtocol::LegacyOrphanWithNonce(_, slot, nonce)
+                | RepairProtocol::Orphan {
+                    header: RepairRequestHeader { nonce, .. },
+                    slot,
+                } => {
                     stats.orphan += 1;
                     (
                         Self::run_orphan(
@@ -180,9 +406,24 @@ impl ServeRepair {
                             MAX_ORPHAN_REPAIR_RESPONSES,
                             *nonce,
                         ),
-                        "OrphanWithNonce",
+                        "Orphan",
                     )
                 }
+                RepairProtocol::AncestorHashes {
+                    header: RepairRequestHeader { nonce, .. },
+                    slot,
+                } => {
+                    stats.ancestor_hashes += 1;
+                    let bank_forks = me.read().unwrap().bank_forks.clone();
+                    let packets = Self::run_ancestor_hashes(
+                        recycler,
+                        &from_addr,
+                        &bank_forks.read().unwrap(),
+                        *slot,
+                        *nonce,
+                    );
+                    (packets, "AncestorHashes")
+                }
                 _ => (None, "Unsupported repair type"),
             }
         };
@@ -207,6 +448,7 @@ impl ServeRepair {
         requests_receiver: &PacketReceiver,
         response_sender: &PacketSender,
         stats: &mut ServeRepairStats,
+        request_counts: &mut SenderRequestCounts,
         max_packets: &mut usize,
     ) -> Result<()> {
         //TODO cache connections
@@ -228,9 +470,20 @@ impl ServeRepair {
         stats.dropped_packets += dropped_packets;
         stats.total_packets += total_packets;
 
+        let root_bank = obj.read().unwrap().bank_forks.read().unwrap().root_bank();
+        let drop_unsigned_requests = Self::drop_unsigned_repair_requests(&root_bank);
         let mut time = Measure::start("repair::handle_packets");
         for reqs in reqs_v {
-            Self::handle_packets(obj, &recycler, blockstore, reqs, response_sender, stats);
+            Self::handle_packets(
+                obj,
+                &recycler,
+                blockstore,
+                reqs,
+                response_sender,
+                stats,
+                request_counts,
+                drop_unsigned_requests,
+            );
         }
         time.stop();
         if total_packets >= *max_packets {
@@ -267,6 +520,15 @@ impl ServeRepair {
             stats.highest_window_index
         );
         inc_new_counter_debug!("serve_repair-request-orphan", stats.orphan);
+        inc_new_counter_debug!(
+            "serve_repair-request-ancestor-hashes",
+            stats.ancestor_hashes
+        );
+        inc_new_counter_debug!("serve_repair-err-unsigned", stats.err_unsigned);
+        inc_new_counter_debug!("serve_repair-err-id-mismatch", stats.err_id_mismatch);
+        inc_new_counter_debug!("serve_repair-err-time-skew", stats.err_time_skew);
+        inc_new_counter_debug!("serve_repair-err-sig-verify", stats.err_sig_verify);
+        inc_new_counter_debug!("serve_repair-err-rate-limited", stats.err_rate_limited);
 
         *stats = ServeRepairStats::default();
     }
@@ -285,6 +547,7 @@ impl ServeRepair {
             .spawn(move || {
                 let mut last_print = Instant::now();
                 let mut stats = ServeRepairStats::default();
+                let mut request_counts = SenderRequestCounts::default();
                 let mut max_packets = 1024;
                 loop {
                     let result = Self::run_listen(
@@ -294,6 +557,7 @@ impl ServeRepair {
                         &requests_receiver,
                         &response_sender,
                         &mut stats,
+                        &mut request_counts,
                         &mut max_packets,
                     );
                     match result {
@@ -313,6 +577,7 @@ impl ServeRepair {
             .unwrap()
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn handle_packets(
         me: &Arc<RwLock<Self>>,
         recycler: &PacketsRecycler,
@@ -320,7 +585,10 @@ impl ServeRepair {
         packets: Packets,
         response_sender: &PacketSender,
         stats: &mut ServeRepairStats,
+        request_counts: &mut SenderRequestCounts,
+        drop_unsigned_requests: bool,
     ) {
+        let my_id = me.read().unwrap().keypair.pubkey();
         // iter over the packets
         let allocated = thread_mem_usage::Allocatedp::default();
         packets.packets.iter().for_each(|packet| {
@@ -328,7 +596,18 @@ impl ServeRepair {
             let from_addr = packet.meta.addr();
             limited_deserialize(&packet.data[..packet.meta.size])
                 .into_iter()
-                .for_each(|request| {
+                .for_each(|request: RepairProtocol| {
+                    if request.header().is_none() {
+                        if drop_unsigned_requests {
+                            stats.err_unsigned += 1;
+                            return;
+                        }
+                    } else if !Self::verify_signed_packet(&my_id, packet, &request, stats) {
+                        return;
+                    } else if !request_counts.check_and_increment(request.sender()) {
+                        stats.err_rate_limited += 1;
+                        return;
+                    }
                     stats.processed += 1;
                     let rsp =
                         Self::handle_repair(me, recycler, &from_addr, blockstore, request, stats);
@@ -343,38 +622,21 @@ impl ServeRepair {
         });
     }
 
-    fn window_index_request_bytes(
-        &self,
-        slot: Slot,
-        shred_index: u64,
-        nonce: Nonce,
-    ) -> Result<Vec<u8>> {
-        let req =
-            RepairProtocol::WindowIndexWithNonce(self.my_info.clone(), slot, shred_index, nonce);
-        let out = serialize(&req)?;
-        Ok(out)
-    }
-
-    fn window_highest_index_request_bytes(
-        &self,
-        slot: Slot,
-        shred_index: u64,
-        nonce: Nonce,
-    ) -> Result<Vec<u8>> {
-        let req = RepairProtocol::HighestWindowIndexWithNonce(
-            self.my_info.clone(),
-            slot,
-            shred_index,
-            nonce,
-        );
-        let out = serialize(&req)?;
-        Ok(out)
-    }
-
-    fn orphan_bytes(&self, slot: Slot, nonce: Nonce) -> Result<Vec<u8>> {
-        let req = RepairProtocol::OrphanWithNonce(self.my_info.clone(), slot, nonce);
-        let out = serialize(&req)?;
-        Ok(out)
+    /// Serialize a request, signing it if it has a header
+    fn repair_proto_to_bytes(request: &RepairProtocol, keypair: &Keypair) -> Result<Vec<u8>> {
+        let mut payload = serialize(&request)?;
+        if request.header().is_some() {
+            let signature_end = SIGNED_REPAIR_SIGNATURE_OFFSET + SIGNATURE_BYTES;
+            let signable_data = [
+                &payload[..SIGNED_REPAIR_SIGNATURE_OFFSET],
+                &payload[signature_end..],
+            ]
+            .concat();
+            let signature = keypair.sign_message(&signable_data);
+            payload[SIGNED_REPAIR_SIGNATURE_OFFSET..signature_end]
+                .copy_from_slice(signature.as_ref());
+        }
+        Ok(payload)
     }
 
     pub fn repair_request(
@@ -435,24 +697,105 @@ impl ServeRepair {
         repair_stats: &mut RepairStats,
         nonce: Nonce,
     ) -> Result<Vec<u8>> {
-        match repair_request {
+        let header = if self.sign_repair_requests() {
+            Some(RepairRequestHeader::new(
+                self.keypair.pubkey(),
+                *repair_peer_id,
+                timestamp(),
+                nonce,
+            ))
+        } else {
+            None
+        };
+        let request = match repair_request {
             RepairType::Shred(slot, shred_index) => {
                 repair_stats
                     .shred
                     .update(repair_peer_id, *slot, *shred_index);
-                Ok(self.window_index_request_bytes(*slot, *shred_index, nonce)?)
+                match header {
+                    Some(header) => RepairProtocol::WindowIndex {
+                        header,
+                        slot: *slot,
+                        shred_index: *shred_index,
+                    },
+                    None => RepairProtocol::LegacyWindowIndexWithNonce(
+                        self.my_info.clone(),
+                        *slot,
+                        *shred_index,
+                        nonce,
+                    ),
+                }
             }
             RepairType::HighestShred(slot, shred_index) => {
                 repair_stats
                     .highest_shred
                     .update(repair_peer_id, *slot, *shred_index);
-                Ok(self.window_highest_index_request_bytes(*slot, *shred_index, nonce)?)
+                match header {
+                    Some(header) => RepairProtocol::HighestWindowIndex {
+                        header,
+                        slot: *slot,
+                        shred_index: *shred_index,
+                    },
+                    None => RepairProtocol::LegacyHighestWindowIndexWithNonce(
+                        self.my_info.clone(),
+                        *slot,
+                        *shred_index,
+                        nonce,
+                    ),
+                }
             }
             RepairType::Orphan(slot) => {
                 repair_stats.orphan.update(repair_peer_id, *slot, 0);
-                Ok(self.orphan_bytes(*slot, nonce)?)
+                match header {
+                    Some(header) => RepairProtocol::Orphan {
+                        header,
+                        slot: *slot,
+                    },
+                    None => {
+                        RepairProtocol::LegacyOrphanWithNonce(self.my_info.clone(), *slot, nonce)
+                    }
+                }
             }
-        }
+            RepairType::AncestorHashes(slot) => {
+                repair_stats
+                    .ancestor_hashes
+                    .update(repair_peer_id, *slot, 0);
+                // There is no unsigned version of this request
+                let header = header.unwrap_or_else(|| {
+                    RepairRequestHeader::new(
+                        self.keypair.pubkey(),
+                        *repair_peer_id,
+                        timestamp(),
+                        nonce,
+                    )
+                });
+                RepairProtocol::AncestorHashes {
+                    header,
+                    slot: *slot,
+                }
+            }
+        };
+        Self::repair_proto_to_bytes(&request, &self.keypair)
+    }
+
+    /// Sample up to `num_peers` repair peers for `slot`, weighted by the given stakes
+    pub fn ancestor_hashes_request_peers(
+        &self,
+        slot: Slot,
+        stakes: &HashMap<Pubkey, u64>,
+        repair_validators: &Option<HashSet<Pubkey>>,
+        num_peers: usize,
+    ) -> Vec<(Pubkey, SocketAddr)> {
+        let repair_peers = self.repair_peers(repair_validators, slot);
+        let weights: Vec<_> = repair_peers
+            .iter()
+            .map(|peer| stakes.get(&peer.id).copied().unwrap_or_default().max(1))
+            .collect();
+        weighted_shuffle(weights, solana_sdk::pubkey::new_rand().to_bytes())
+            .into_iter()
+            .take(num_peers)
+            .map(|i| (repair_peers[i].id, repair_peers[i].serve_repair))
+            .collect()
     }
 
     fn repair_peers(
@@ -478,7 +821,7 @@ impl ServeRepair {
 
     fn run_window_request(
         recycler: &PacketsRecycler,
-        from: &ContactInfo,
+        from: &Pubkey,
         from_addr: &SocketAddr,
         blockstore: Option<&Arc<Blockstore>>,
         me: &ContactInfo,
@@ -506,7 +849,7 @@ impl ServeRepair {
         trace!(
             "{}: failed WindowIndex {} {} {}",
             me.id,
-            from.id,
+            from,
             slot,
             shred_index,
         );
@@ -578,6 +921,31 @@ impl ServeRepair {
         }
         Some(res)
     }
+
+    /// Respond with the hashes of the frozen bank for `slot` and its ancestors, if this node
+    /// has replayed the slot
+    fn run_ancestor_hashes(
+        recycler: &PacketsRecycler,
+        from_addr: &SocketAddr,
+        bank_forks: &BankForks,
+        slot: Slot,
+        nonce: Nonce,
+    ) -> Option<Packets> {
+        let bank = bank_forks.get(slot).filter(|bank| bank.is_frozen())?;
+        let hashes = std::iter::once(bank.clone())
+            .chain(bank.parents())
+            .take(MAX_ANCESTOR_RESPONSES)
+            .map(|bank| (bank.slot(), bank.hash()))
+            .collect();
+        let response = AncestorHashesResponse { hashes };
+        let mut packet = Packet::default();
+        let mut wr = Cursor::new(&mut packet.data[..]);
+        bincode::serialize_into(&mut wr, &response).ok()?;
+        bincode::serialize_into(&mut wr, &nonce).ok()?;
+        packet.meta.size = wr.position() as usize;
+        packet.meta.set_addr(from_addr);
+        Packets::new_with_recycler_data(recycler, vec![packet])
+    }
 }
 
 #[cfg(test)]
@@ -588,11 +956,184 @@ mod tests {
     use solana_ledger::{
         blockstore::make_many_slot_entries,
         blockstore_processor::fill_blockstore_slot_with_ticks,
+        genesis_utils::{create_genesis_config, GenesisConfigInfo},
         shred::{
             max_ticks_per_n_shreds, CodingShredHeader, DataShredHeader, Shred, ShredCommonHeader,
         },
     };
-    use solana_sdk::{hash::Hash, pubkey::Pubkey, timing::timestamp};
+    use solana_sdk::{hash::Hash, pubkey::Pubkey};
+
+    fn new_test_bank_forks() -> Arc<RwLock<BankForks>> {
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        Arc::new(RwLock::new(BankForks::new(Bank::new(&genesis_config))))
+    }
+
+    fn new_packet(data: &[u8]) -> Packet {
+        let mut packet = Packet::default();
+        packet.data[..data.len()].copy_from_slice(data);
+        packet.meta.size = data.len();
+        packet
+    }
+
+    #[test]
+    fn test_map_repair_request_signed() {
+        let me = ContactInfo::new_localhost(&solana_sdk::pubkey::new_rand(), timestamp());
+        let cluster_info = Arc::new(ClusterInfo::new_with_invalid_keypair(me));
+        let serve_repair = ServeRepair::new(cluster_info, new_test_bank_forks());
+        let repair_peer_id = solana_sdk::pubkey::new_rand();
+        let nonce = 9;
+
+        let request_bytes = serve_repair
+            .map_repair_request(
+                &RepairType::Shred(5, 3),
+                &repair_peer_id,
+                &mut RepairStats::default(),
+                nonce,
+            )
+            .unwrap();
+        let request: RepairProtocol = limited_deserialize(&request_bytes).unwrap();
+        match &request {
+            RepairProtocol::WindowIndex {
+                header,
+                slot,
+                shred_index,
+            } => {
+                assert_eq!(header.sender, serve_repair.keypair().pubkey());
+                assert_eq!(header.recipient, repair_peer_id);
+                assert_eq!(header.nonce, nonce);
+                assert_eq!((*slot, *shred_index), (5, 3));
+            }
+            _ => panic!("unexpected request: {:?}", request),
+        }
+
+        let mut stats = ServeRepairStats::default();
+        let packet = new_packet(&request_bytes);
+        assert!(ServeRepair::verify_signed_packet(
+            &repair_peer_id,
+            &packet,
+            &request,
+            &mut stats
+        ));
+
+        // Requests addressed to another node are rejected
+        assert!(!ServeRepair::verify_signed_packet(
+            &solana_sdk::pubkey::new_rand(),
+            &packet,
+            &request,
+            &mut stats
+        ));
+        assert_eq!(stats.err_id_mismatch, 1);
+
+        // Any change to the signed data invalidates the signature
+        let mut tampered_bytes = request_bytes;
+        *tampered_bytes.last_mut().unwrap() ^= 1;
+        let tampered_request: RepairProtocol = limited_deserialize(&tampered_bytes).unwrap();
+        assert!(!ServeRepair::verify_signed_packet(
+            &repair_peer_id,
+            &new_packet(&tampered_bytes),
+            &tampered_request,
+            &mut stats
+        ));
+        assert_eq!(stats.err_sig_verify, 1);
+    }
+
+    #[test]
+    fn test_map_repair_request_unsigned() {
+        // Signing is not active on the default bank
+        let me = ContactInfo::new_localhost(&solana_sdk::pubkey::new_rand(), timestamp());
+        let serve_repair = ServeRepair::new_with_invalid_keypair(me.clone());
+        let request_bytes = serve_repair
+            .map_repair_request(
+                &RepairType::Orphan(5),
+                &solana_sdk::pubkey::new_rand(),
+                &mut RepairStats::default(),
+                DEFAULT_NONCE,
+            )
+            .unwrap();
+        let request: RepairProtocol = limited_deserialize(&request_bytes).unwrap();
+        assert_matches!(
+            request,
+            RepairProtocol::LegacyOrphanWithNonce(_, 5, DEFAULT_NONCE)
+        );
+        assert!(request.header().is_none());
+        assert_eq!(*request.sender(), me.id);
+
+        // Ancestor hashes requests are always signed
+        let request_bytes = serve_repair
+            .map_repair_request(
+                &RepairType::AncestorHashes(5),
+                &solana_sdk::pubkey::new_rand(),
+                &mut RepairStats::default(),
+                DEFAULT_NONCE,
+            )
+            .unwrap();
+        let request: RepairProtocol = limited_deserialize(&request_bytes).unwrap();
+        assert_matches!(request, RepairProtocol::AncestorHashes { slot: 5, .. });
+        assert_eq!(*request.sender(), serve_repair.keypair().pubkey());
+    }
+
+    #[test]
+    fn test_verify_signed_packet_time_skew() {
+        let keypair = Keypair::new();
+        let my_id = solana_sdk::pubkey::new_rand();
+        let mut stats = ServeRepairStats::default();
+        for (request_timestamp, expected) in &[
+            (timestamp(), true),
+            (timestamp() + 1000, true),
+            (0, false),
+            (
+                timestamp() + 2 * SIGNED_REPAIR_TIME_WINDOW.as_millis() as u64,
+                false,
+            ),
+        ] {
+            let request = RepairProtocol::HighestWindowIndex {
+                header: RepairRequestHeader::new(keypair.pubkey(), my_id, *request_timestamp, 0),
+                slot: 1,
+                shred_index: 0,
+            };
+            let request_bytes = ServeRepair::repair_proto_to_bytes(&request, &keypair).unwrap();
+            let request: RepairProtocol = limited_deserialize(&request_bytes).unwrap();
+            assert_eq!(
+                ServeRepair::verify_signed_packet(
+                    &my_id,
+                    &new_packet(&request_bytes),
+                    &request,
+                    &mut stats
+                ),
+                *expected
+            );
+        }
+        assert_eq!(stats.err_time_skew, 2);
+        assert_eq!(stats.err_sig_verify, 0);
+    }
+
+    #[test]
+    fn test_sender_request_counts() {
+        let mut request_counts = SenderRequestCounts::default();
+        let sender = solana_sdk::pubkey::new_rand();
+        for _ in 0..MAX_REPAIR_REQUESTS_PER_SENDER {
+            assert!(request_counts.check_and_increment(&sender));
+        }
+        assert!(!request_counts.check_and_increment(&sender));
+        assert!(request_counts.check_and_increment(&solana_sdk::pubkey::new_rand()));
+
+        request_counts.last_reset -= REPAIR_RATE_LIMIT_INTERVAL;
+        assert!(request_counts.check_and_increment(&sender));
+    }
+
+    #[test]
+    fn test_drop_unsigned_repair_requests() {
+        assert!(!ServeRepair::drop_unsigned_repair_requests(&Bank::default()));
+
+        // Signing is activated at genesis, unsigned requests are served until the next epoch
+        let bank = new_test_bank_forks().read().unwrap().root_bank();
+        assert!(!ServeRepair::drop_unsigned_repair_requests(&bank));
+        let next_epoch_slot = bank
+            .epoch_schedule()
+            .get_first_slot_in_epoch(bank.epoch() + 1);
+        let bank = Bank::new_from_parent(&bank, &Pubkey::default(), next_epoch_slot);
+        assert!(ServeRepair::drop_unsigned_repair_requests(&bank));
+    }
 
     #[test]
     fn test_run_highest_window_request() {
@@ -682,7 +1223,7 @@ mod tests {
                 repair: socketaddr!("127.0.0.1:1237"),
                 tpu: socketaddr!("127.0.0.1:1238"),
                 tpu_forwards: socketaddr!("127.0.0.1:1239"),
-                unused: socketaddr!("127.0.0.1:1240"),
+                tpu_quic: socketaddr!("127.0.0.1:1240"),
                 rpc: socketaddr!("127.0.0.1:1241"),
                 rpc_pubsub: socketaddr!("127.0.0.1:1242"),
                 serve_repair: socketaddr!("127.0.0.1:1243"),
@@ -691,7 +1232,7 @@ mod tests {
             };
             let rv = ServeRepair::run_window_request(
                 &recycler,
-                &me,
+                &me.id,
                 &socketaddr_any!(),
                 Some(&blockstore),
                 &me,
@@ -722,7 +1263,7 @@ mod tests {
             let index = 1;
             let rv = ServeRepair::run_window_request(
                 &recycler,
-                &me,
+                &me.id,
                 &socketaddr_any!(),
                 Some(&blockstore),
                 &me,
@@ -751,7 +1292,7 @@ mod tests {
         let cluster_slots = ClusterSlots::default();
         let me = ContactInfo::new_localhost(&solana_sdk::pubkey::new_rand(), timestamp());
         let cluster_info = Arc::new(ClusterInfo::new_with_invalid_keypair(me));
-        let serve_repair = ServeRepair::new(cluster_info.clone());
+        let serve_repair = ServeRepair::new(cluster_info.clone(), new_test_bank_forks());
         let rv = serve_repair.repair_request(
             &cluster_slots,
             RepairType::Shred(0, 0),
@@ -770,7 +1311,7 @@ mod tests {
             repair: socketaddr!([127, 0, 0, 1], 1237),
             tpu: socketaddr!([127, 0, 0, 1], 1238),
             tpu_forwards: socketaddr!([127, 0, 0, 1], 1239),
-            unused: socketaddr!([127, 0, 0, 1], 1240),
+            tpu_quic: socketaddr!([127, 0, 0, 1], 1240),
             rpc: socketaddr!([127, 0, 0, 1], 1241),
             rpc_pubsub: socketaddr!([127, 0, 0, 1], 1242),
             serve_repair: serve_repair_addr,
@@ -799,7 +1340,7 @@ mod tests {
             repair: socketaddr!([127, 0, 0, 1], 1237),
             tpu: socketaddr!([127, 0, 0, 1], 1238),
             tpu_forwards: socketaddr!([127, 0, 0, 1], 1239),
-            unused: socketaddr!([127, 0, 0, 1], 1240),
+            tpu_quic: socketaddr!([127, 0, 0, 1], 1240),
             rpc: socketaddr!([127, 0, 0, 1], 1241),
             rpc_pubsub: socketaddr!([127, 0, 0, 1], 1242),
             serve_repair: serve_repair_addr2,
@@ -965,6 +1506,61 @@ mod tests {
         Blockstore::destroy(&ledger_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_run_ancestor_hashes() {
+        let recycler = PacketsRecycler::new_without_limit("");
+        let bank_forks = new_test_bank_forks();
+        let mut bank_forks = bank_forks.write().unwrap();
+        let bank0 = bank_forks.root_bank();
+        bank0.freeze();
+        let bank1 = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        bank1.freeze();
+        let bank1 = bank_forks.insert(bank1);
+        let bank3 = bank_forks.insert(Bank::new_from_parent(&bank1, &Pubkey::default(), 3));
+        let nonce = 9;
+
+        // Slots which are not replayed or not frozen yet are not served
+        assert!(ServeRepair::run_ancestor_hashes(
+            &recycler,
+            &socketaddr_any!(),
+            &bank_forks,
+            2,
+            nonce
+        )
+        .is_none());
+        assert!(ServeRepair::run_ancestor_hashes(
+            &recycler,
+            &socketaddr_any!(),
+            &bank_forks,
+            3,
+            nonce
+        )
+        .is_none());
+
+        bank3.freeze();
+        let packets =
+            ServeRepair::run_ancestor_hashes(&recycler, &socketaddr_any!(), &bank_forks, 3, nonce)
+                .unwrap();
+        assert_eq!(packets.packets.len(), 1);
+        let packet = &packets.packets[0];
+        let (response, response_nonce) =
+            AncestorHashesResponse::from_packet_data(&packet.data[..packet.meta.size]).unwrap();
+        assert_eq!(response_nonce, nonce);
+        assert_eq!(
+            response.hashes,
+            vec![(3, bank3.hash()), (1, bank1.hash()), (0, bank0.hash())]
+        );
+    }
+
+    #[test]
+    fn test_ancestor_hashes_response_max_size() {
+        let response = AncestorHashesResponse {
+            hashes: vec![(Slot::MAX, Hash::new_unique()); MAX_ANCESTOR_RESPONSES],
+        };
+        let size = bincode::serialized_size(&response).unwrap() as usize + SIZE_OF_NONCE;
+        assert!(size <= PACKET_DATA_SIZE);
+    }
+
     #[test]
     fn test_repair_with_repair_validators() {
         let cluster_slots = ClusterSlots::default();
@@ -978,7 +1574,7 @@ mod tests {
             ContactInfo::new_localhost(&solana_sdk::pubkey::new_rand(), timestamp());
         cluster_info.insert_info(contact_info2.clone());
         cluster_info.insert_info(contact_info3.clone());
-        let serve_repair = ServeRepair::new(cluster_info);
+        let serve_repair = ServeRepair::new(cluster_info, new_test_bank_forks());
 
         // If:
         // 1) repair validator set doesn't exist in gossip
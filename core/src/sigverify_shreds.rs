@@ -63,11 +63,15 @@ impl SigVerifier for ShredSigVerifier {
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use solana_ledger::entry::create_ticks;
     use solana_ledger::genesis_utils::create_genesis_config_with_leader;
-    use solana_ledger::shred::{Shred, Shredder};
+    use solana_ledger::shred::{ProcessShredsStats, Shred, Shredder};
     use solana_perf::packet::Packet;
     use solana_runtime::bank::Bank;
-    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::{
+        hash::Hash,
+        signature::{Keypair, Signer},
+    };
 
     #[test]
     fn test_sigverify_shreds_read_slots() {
@@ -159,4 +163,49 @@ pub mod tests {
         assert_eq!(rv[0].packets[0].meta.discard, false);
         assert_eq!(rv[0].packets[1].meta.discard, true);
     }
+
+    #[test]
+    fn test_sigverify_merkle_shreds_verify_batch() {
+        let leader_keypair = Arc::new(Keypair::new());
+        let leader_pubkey = leader_keypair.pubkey();
+        let bank =
+            Bank::new(&create_genesis_config_with_leader(100, &leader_pubkey, 10).genesis_config);
+        let cache = Arc::new(LeaderScheduleCache::new_from_bank(&bank));
+        let bf = Arc::new(RwLock::new(BankForks::new(bank)));
+        let verifier = ShredSigVerifier::new(bf, cache);
+
+        let entries = create_ticks(100, 0, Hash::default());
+        let shreds_from = |keypair: &Arc<Keypair>| {
+            let shredder = Shredder::new(1, 0, 1.0, keypair.clone(), 0, 0).unwrap();
+            let (data_shreds, coding_shreds, _) = shredder.entries_to_merkle_shreds(
+                &entries,
+                true,
+                0,
+                &mut ProcessShredsStats::default(),
+            );
+            data_shreds
+                .into_iter()
+                .chain(coding_shreds)
+                .collect::<Vec<_>>()
+        };
+        let shreds = shreds_from(&leader_keypair);
+        let wrong_shreds = shreds_from(&Arc::new(Keypair::new()));
+
+        let mut batch = vec![Packets::default()];
+        batch[0]
+            .packets
+            .resize(shreds.len() + wrong_shreds.len(), Packet::default());
+        for (shred, packet) in shreds
+            .iter()
+            .chain(&wrong_shreds)
+            .zip(batch[0].packets.iter_mut())
+        {
+            shred.copy_to_packet(packet);
+        }
+
+        let rv = verifier.verify_batch(batch);
+        let (leader_packets, wrong_packets) = rv[0].packets[..].split_at(shreds.len());
+        assert!(leader_packets.iter().all(|packet| !packet.meta.discard));
+        assert!(wrong_packets.iter().all(|packet| packet.meta.discard));
+    }
 }
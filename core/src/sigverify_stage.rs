@@ -4,23 +4,54 @@
 //! signature in that packet is valid. It assumes each packet contains one
 //! transaction. All processing is done on the CPU by default and on a GPU
 //! if perf-libs are available
+//!
+//! When it is given the stakes of the senders and falls behind, the stage drops the
+//! packets over separate budgets for staked and unstaked senders, so that spam from
+//! unstaked addresses can't squeeze out the traffic of staked nodes.
+//!
+//! Senders are told apart by the source IP address of their packets, which UDP doesn't
+//! authenticate. A spammer spoofing the addresses of staked nodes is counted against, and
+//! can use up, their budget, so this only protects staked nodes from spam sent from other
+//! addresses.
 
-use crate::sigverify;
+use crate::{cluster_info::ClusterInfo, sigverify};
 use crossbeam_channel::{SendError, Sender as CrossbeamSender};
 use solana_measure::measure::Measure;
-use solana_metrics::datapoint_debug;
+use solana_metrics::{datapoint_debug, datapoint_info};
 use solana_perf::packet::Packets;
 use solana_perf::perf_libs;
+use solana_runtime::bank_forks::BankForks;
 use solana_sdk::timing;
 use solana_streamer::streamer::{self, PacketReceiver, StreamerError};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::net::IpAddr;
 use std::sync::mpsc::{Receiver, RecvTimeoutError};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread::{self, Builder, JoinHandle};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 const RECV_BATCH_MAX_CPU: usize = 1_000;
 const RECV_BATCH_MAX_GPU: usize = 5_000;
 
+const PACKET_BUDGETS_CPU: PacketBudgets = PacketBudgets {
+    staked: 750,
+    unstaked: 250,
+};
+const PACKET_BUDGETS_GPU: PacketBudgets = PacketBudgets {
+    staked: 3_750,
+    unstaked: 1_250,
+};
+
+/// With the stakes of the senders, a verifier takes up to this many times its budget of
+/// packets off the queue at once.  Only if the queue holds more than that is the stage
+/// behind, and are the packets which don't fit in the budget dropped.
+const MAX_RECV_BUDGETS: usize = 10;
+
+const STAKED_SENDERS_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+const STATS_REPORT_INTERVAL: Duration = Duration::from_secs(2);
+
 #[derive(Error, Debug)]
 pub enum SigVerifyServiceError {
     #[error("send packets batch error")]
@@ -36,6 +67,107 @@ pub struct SigVerifyStage {
     thread_hdls: Vec<JoinHandle<()>>,
 }
 
+/// Number of packets from staked and from unstaked senders a verifier verifies at once.
+/// Budget left unused by one class is lent to the other.
+#[derive(Clone, Copy, Debug)]
+struct PacketBudgets {
+    staked: usize,
+    unstaked: usize,
+}
+
+impl PacketBudgets {
+    fn new() -> Self {
+        if perf_libs::api().is_some() {
+            PACKET_BUDGETS_GPU
+        } else {
+            PACKET_BUDGETS_CPU
+        }
+    }
+
+    fn total(&self) -> usize {
+        self.staked + self.unstaked
+    }
+}
+
+/// Maps the IP addresses of the nodes in gossip to their stake in the current epoch.  Packets
+/// are attributed to a node by their source address, which can be spoofed.
+pub struct StakedSenders {
+    cluster_info: Arc<ClusterInfo>,
+    bank_forks: Arc<RwLock<BankForks>>,
+    stakes: RwLock<(Instant, Arc<HashMap<IpAddr, u64>>)>,
+}
+
+impl StakedSenders {
+    pub fn new(cluster_info: Arc<ClusterInfo>, bank_forks: Arc<RwLock<BankForks>>) -> Self {
+        let stakes = Arc::new(Self::compute_stakes(&cluster_info, &bank_forks));
+        Self {
+            cluster_info,
+            bank_forks,
+            stakes: RwLock::new((Instant::now(), stakes)),
+        }
+    }
+
+    fn compute_stakes(
+        cluster_info: &ClusterInfo,
+        bank_forks: &RwLock<BankForks>,
+    ) -> HashMap<IpAddr, u64> {
+        let root_bank = bank_forks.read().unwrap().root_bank();
+        let node_stakes = match root_bank.epoch_stakes(root_bank.epoch()) {
+            Some(epoch_stakes) => epoch_stakes.node_id_to_vote_accounts().clone(),
+            None => return HashMap::new(),
+        };
+        let mut stakes = HashMap::new();
+        for node in cluster_info.all_tvu_peers() {
+            if let Some(node_vote_accounts) = node_stakes.get(&node.id) {
+                // Nodes sharing an address share their stakes
+                *stakes.entry(node.gossip.ip()).or_default() += node_vote_accounts.total_stake;
+            }
+        }
+        stakes
+    }
+
+    fn get_stakes(&self) -> Arc<HashMap<IpAddr, u64>> {
+        {
+            let stakes = self.stakes.read().unwrap();
+            if stakes.0.elapsed() < STAKED_SENDERS_REFRESH_INTERVAL {
+                return stakes.1.clone();
+            }
+        }
+        let stakes = Arc::new(Self::compute_stakes(&self.cluster_info, &self.bank_forks));
+        *self.stakes.write().unwrap() = (Instant::now(), stakes.clone());
+        stakes
+    }
+}
+
+struct SigVerifierStats {
+    staked_packets_dropped: usize,
+    unstaked_packets_dropped: usize,
+    last_report: Instant,
+}
+
+impl SigVerifierStats {
+    fn new() -> Self {
+        Self {
+            staked_packets_dropped: 0,
+            unstaked_packets_dropped: 0,
+            last_report: Instant::now(),
+        }
+    }
+
+    fn maybe_report(&mut self, id: usize) {
+        if self.last_report.elapsed() < STATS_REPORT_INTERVAL {
+            return;
+        }
+        datapoint_info!(
+            "sigverify_stage-dropped_packets",
+            ("id", id, i64),
+            ("staked", self.staked_packets_dropped, i64),
+            ("unstaked", self.unstaked_packets_dropped, i64),
+        );
+        *self = Self::new();
+    }
+}
+
 pub trait SigVerifier {
     fn verify_batch(&self, batch: Vec<Packets>) -> Vec<Packets>;
 }
@@ -56,25 +188,99 @@ impl SigVerifyStage {
         packet_receiver: Receiver<Packets>,
         verified_sender: CrossbeamSender<Vec<Packets>>,
         verifier: T,
+        staked_senders: Option<StakedSenders>,
     ) -> Self {
-        let thread_hdls = Self::verifier_services(packet_receiver, verified_sender, verifier);
+        let thread_hdls = Self::verifier_services(
+            packet_receiver,
+            verified_sender,
+            verifier,
+            staked_senders.map(Arc::new),
+        );
         Self { thread_hdls }
     }
 
+    /// Drop the packets over the budgets, keeping the packets of the senders with the most
+    /// stake first.  Returns the number of staked and unstaked packets dropped.
+    fn apply_packet_budgets(
+        batch: &mut Vec<Packets>,
+        budgets: &PacketBudgets,
+        stakes: &HashMap<IpAddr, u64>,
+    ) -> (usize, usize) {
+        let mut staked = vec![];
+        let mut unstaked = vec![];
+        for (i, packets) in batch.iter().enumerate() {
+            for (j, packet) in packets.packets.iter().enumerate() {
+                match stakes.get(&packet.meta.addr().ip()) {
+                    Some(stake) if *stake > 0 => staked.push((i, j, *stake)),
+                    _ => unstaked.push((i, j)),
+                }
+            }
+        }
+        let num_staked = staked
+            .len()
+            .min(budgets.staked + budgets.unstaked.saturating_sub(unstaked.len()));
+        let num_unstaked = unstaked
+            .len()
+            .min(budgets.unstaked + budgets.staked.saturating_sub(num_staked));
+        // The sort is stable, so packets of the same stake are kept in the order received
+        staked.sort_by_key(|(_, _, stake)| Reverse(*stake));
+
+        let mut keep: Vec<_> = batch
+            .iter()
+            .map(|packets| vec![false; packets.packets.len()])
+            .collect();
+        for (i, j, _) in &staked[..num_staked] {
+            keep[*i][*j] = true;
+        }
+        for (i, j) in &unstaked[..num_unstaked] {
+            keep[*i][*j] = true;
+        }
+        for (packets, keep) in batch.iter_mut().zip(keep) {
+            let mut num_kept = 0;
+            for (index, keep) in keep.into_iter().enumerate() {
+                if keep {
+                    packets.packets[..].swap(num_kept, index);
+                    num_kept += 1;
+                }
+            }
+            packets.packets.truncate(num_kept);
+        }
+        batch.retain(|packets| !packets.packets.is_empty());
+        (staked.len() - num_staked, unstaked.len() - num_unstaked)
+    }
+
     fn verifier<T: SigVerifier>(
         recvr: &Arc<Mutex<PacketReceiver>>,
         sendr: &CrossbeamSender<Vec<Packets>>,
         id: usize,
         verifier: &T,
+        staked_senders: Option<&StakedSenders>,
+        stats: &mut SigVerifierStats,
     ) -> Result<()> {
-        let (batch, len, recv_time) = streamer::recv_batch(
+        let budgets = PacketBudgets::new();
+        // With the stakes of the senders, take more than the budget off the queue so that the
+        // backlog is seen, and dropped rather than left to pile up
+        let max_recv = budgets.total() * MAX_RECV_BUDGETS;
+        let (mut batch, mut len, recv_time) = streamer::recv_batch(
             &recvr.lock().expect("'recvr' lock in fn verifier"),
-            if perf_libs::api().is_some() {
-                RECV_BATCH_MAX_GPU
-            } else {
-                RECV_BATCH_MAX_CPU
+            match staked_senders {
+                Some(_) => max_recv,
+                None if perf_libs::api().is_some() => RECV_BATCH_MAX_GPU,
+                None => RECV_BATCH_MAX_CPU,
             },
         )?;
+        if let Some(staked_senders) = staked_senders {
+            // `recv_batch` stops once it is over `max_recv`, so a smaller batch is everything
+            // that was queued, which is verified in full
+            if len > max_recv {
+                let (staked_dropped, unstaked_dropped) =
+                    Self::apply_packet_budgets(&mut batch, &budgets, &staked_senders.get_stakes());
+                len -= staked_dropped + unstaked_dropped;
+                stats.staked_packets_dropped += staked_dropped;
+                stats.unstaked_packets_dropped += unstaked_dropped;
+            }
+            stats.maybe_report(id);
+        }
 
         let mut verify_batch_time = Measure::start("sigverify_batch_time");
         let batch_len = batch.len();
@@ -119,12 +325,21 @@ impl SigVerifyStage {
         verified_sender: CrossbeamSender<Vec<Packets>>,
         id: usize,
         verifier: &T,
+        staked_senders: Option<Arc<StakedSenders>>,
     ) -> JoinHandle<()> {
         let verifier = verifier.clone();
+        let mut stats = SigVerifierStats::new();
         Builder::new()
             .name(format!("solana-verifier-{}", id))
             .spawn(move || loop {
-                if let Err(e) = Self::verifier(&packet_receiver, &verified_sender, id, &verifier) {
+                if let Err(e) = Self::verifier(
+                    &packet_receiver,
+                    &verified_sender,
+                    id,
+                    &verifier,
+                    staked_senders.as_deref(),
+                    &mut stats,
+                ) {
                     match e {
                         SigVerifyServiceError::StreamerError(StreamerError::RecvTimeoutError(
                             RecvTimeoutError::Disconnected,
@@ -146,11 +361,18 @@ impl SigVerifyStage {
         packet_receiver: PacketReceiver,
         verified_sender: CrossbeamSender<Vec<Packets>>,
         verifier: T,
+        staked_senders: Option<Arc<StakedSenders>>,
     ) -> Vec<JoinHandle<()>> {
         let receiver = Arc::new(Mutex::new(packet_receiver));
         (0..4)
             .map(|id| {
-                Self::verifier_service(receiver.clone(), verified_sender.clone(), id, &verifier)
+                Self::verifier_service(
+                    receiver.clone(),
+                    verified_sender.clone(),
+                    id,
+                    &verifier,
+                    staked_senders.clone(),
+                )
             })
             .collect()
     }
@@ -162,3 +384,142 @@ impl SigVerifyStage {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contact_info::ContactInfo;
+    use crossbeam_channel::unbounded;
+    use solana_perf::packet::Packet;
+    use solana_runtime::{
+        bank::Bank,
+        genesis_utils::{create_genesis_config, GenesisConfigInfo},
+    };
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::sync::mpsc::channel;
+
+    fn new_batch(addrs: &[(IpAddr, usize)]) -> Vec<Packets> {
+        let packets: Vec<_> = addrs
+            .iter()
+            .flat_map(|(addr, count)| std::iter::repeat(*addr).take(*count))
+            .enumerate()
+            .map(|(i, addr)| {
+                let mut packet = Packet::default();
+                packet.data[0] = i as u8;
+                packet.meta.set_addr(&SocketAddr::new(addr, 8000));
+                packet
+            })
+            .collect();
+        packets
+            .chunks(4)
+            .map(|chunk| Packets::new(chunk.to_vec()))
+            .collect()
+    }
+
+    fn kept_packets(batch: &[Packets]) -> Vec<u8> {
+        let mut kept: Vec<_> = batch
+            .iter()
+            .flat_map(|packets| packets.packets.iter().map(|packet| packet.data[0]))
+            .collect();
+        kept.sort_unstable();
+        kept
+    }
+
+    #[test]
+    fn test_apply_packet_budgets() {
+        let whale = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let minnow = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let unstaked = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 3));
+        let stakes: HashMap<_, _> = vec![(whale, 10), (minnow, 1)].into_iter().collect();
+        let budgets = PacketBudgets {
+            staked: 4,
+            unstaked: 2,
+        };
+
+        // Packets 0..5 are from the minnow, 5..10 from the whale and 10..15 unstaked
+        let mut batch = new_batch(&[(minnow, 5), (whale, 5), (unstaked, 5)]);
+        assert_eq!(
+            SigVerifyStage::apply_packet_budgets(&mut batch, &budgets, &stakes),
+            (6, 3)
+        );
+        assert_eq!(kept_packets(&batch), vec![5, 6, 7, 8, 10, 11]);
+
+        // Budget unused by staked senders is lent to unstaked ones
+        let mut batch = new_batch(&[(minnow, 1), (unstaked, 9)]);
+        assert_eq!(
+            SigVerifyStage::apply_packet_budgets(&mut batch, &budgets, &stakes),
+            (0, 4)
+        );
+        assert_eq!(kept_packets(&batch), vec![0, 1, 2, 3, 4, 5]);
+
+        // And the other way around
+        let mut batch = new_batch(&[(unstaked, 1), (minnow, 3), (whale, 6)]);
+        assert_eq!(
+            SigVerifyStage::apply_packet_budgets(&mut batch, &budgets, &stakes),
+            (4, 0)
+        );
+        assert_eq!(kept_packets(&batch), vec![0, 4, 5, 6, 7, 8]);
+        assert!(batch.iter().all(|packets| !packets.packets.is_empty()));
+    }
+
+    #[test]
+    fn test_verifier_drops_packets_only_when_behind() {
+        let staked = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let unstaked = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(Bank::new(&genesis_config))));
+        let cluster_info = Arc::new(ClusterInfo::new_with_invalid_keypair(
+            ContactInfo::new_localhost(&solana_sdk::pubkey::new_rand(), 0),
+        ));
+        let staked_senders = StakedSenders {
+            cluster_info,
+            bank_forks,
+            stakes: RwLock::new((
+                Instant::now(),
+                Arc::new(vec![(staked, 1)].into_iter().collect()),
+            )),
+        };
+        let (packet_sender, packet_receiver) = channel();
+        let packet_receiver = Arc::new(Mutex::new(packet_receiver));
+        let (verified_sender, verified_receiver) = unbounded();
+        let mut stats = SigVerifierStats::new();
+        let verify = |stats: &mut SigVerifierStats| {
+            SigVerifyStage::verifier(
+                &packet_receiver,
+                &verified_sender,
+                0,
+                &DisabledSigVerifier::default(),
+                Some(&staked_senders),
+                stats,
+            )
+            .unwrap();
+            verified_receiver
+                .try_iter()
+                .flatten()
+                .map(|packets| packets.packets.len())
+                .sum::<usize>()
+        };
+
+        // A queue over the budget, but not behind, is verified in full
+        let budgets = PacketBudgets::new();
+        let num_packets = budgets.total() * MAX_RECV_BUDGETS;
+        for packets in new_batch(&[(staked, 8), (unstaked, num_packets - 8)]) {
+            packet_sender.send(packets).unwrap();
+        }
+        assert_eq!(verify(&mut stats), num_packets);
+        assert_eq!(stats.staked_packets_dropped, 0);
+        assert_eq!(stats.unstaked_packets_dropped, 0);
+
+        // Once the queue holds more than it takes at once, the packets over the budget are
+        // dropped
+        for packets in new_batch(&[(staked, 8), (unstaked, num_packets - 4)]) {
+            packet_sender.send(packets).unwrap();
+        }
+        assert_eq!(verify(&mut stats), budgets.total());
+        assert_eq!(stats.staked_packets_dropped, 0);
+        assert_eq!(
+            stats.unstaked_packets_dropped,
+            num_packets + 4 - budgets.total()
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use crate::cluster_info::{ClusterInfo, MAX_SNAPSHOT_HASHES};
-use solana_runtime::{snapshot_package::AccountsPackage, snapshot_utils};
+use solana_runtime::{accounts_db::AccountsDb, snapshot_package::AccountsPackage, snapshot_utils};
 use solana_sdk::{clock::Slot, hash::Hash};
 use std::{
     sync::{
@@ -22,6 +22,7 @@ impl SnapshotPackagerService {
         starting_snapshot_hash: Option<(Slot, Hash)>,
         exit: &Arc<AtomicBool>,
         cluster_info: &Arc<ClusterInfo>,
+        accounts_db: Arc<AccountsDb>,
     ) -> Self {
         let exit = exit.clone();
         let cluster_info = cluster_info.clone();
@@ -45,7 +46,18 @@ impl SnapshotPackagerService {
                             snapshot_utils::archive_snapshot_package(&snapshot_package)
                         {
                             warn!("Failed to create snapshot archive: {}", err);
-                        } else {
+                        } else if snapshot_package
+                            .snapshot_type
+                            .map(|snapshot_type| snapshot_type.is_full_snapshot())
+                            .unwrap_or(true)
+                        {
+                            // Clean keeps the zero-lamport accounts updated after the last full
+                            // snapshot, which incremental snapshots depend on. Only move it
+                            // once the full snapshot archive exists.
+                            accounts_db.set_last_full_snapshot_slot(snapshot_package.slot);
+
+                            // Only full snapshots are advertised, incremental snapshots are
+                            // fetched on top of them
                             hashes.push((snapshot_package.slot, snapshot_package.hash));
                             while hashes.len() > MAX_SNAPSHOT_HASHES {
                                 hashes.remove(0);
@@ -77,7 +89,7 @@ mod tests {
         accounts_db::AccountStorageEntry,
         bank::BankSlotDelta,
         bank_forks::ArchiveFormat,
-        snapshot_package::AccountsPackage,
+        snapshot_package::{AccountsPackage, SnapshotType},
         snapshot_utils::{self, SnapshotVersion, SNAPSHOT_STATUS_CACHE_FILE_NAME},
     };
     use solana_sdk::hash::Hash;
@@ -170,6 +182,7 @@ mod tests {
             Hash::default(),
             ArchiveFormat::TarBzip2,
             SnapshotVersion::default(),
+            Some(SnapshotType::FullSnapshot),
         );
 
         // Make tarball from packageable snapshot
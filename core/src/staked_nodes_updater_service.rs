@@ -0,0 +1,51 @@
+//! The `staked_nodes_updater_service` refreshes the stakes that weight the stream quotas of
+//! the TPU QUIC connections from the root bank.
+
+use solana_runtime::bank_forks::BankForks;
+use solana_streamer::quic::StakedNodes;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread::{self, sleep, Builder, JoinHandle},
+    time::{Duration, Instant},
+};
+
+const STAKED_NODES_UPDATE_INTERVAL: Duration = Duration::from_secs(5);
+
+pub struct StakedNodesUpdaterService {
+    thread_hdl: JoinHandle<()>,
+}
+
+impl StakedNodesUpdaterService {
+    pub fn new(
+        exit: &Arc<AtomicBool>,
+        bank_forks: Arc<RwLock<BankForks>>,
+        staked_nodes: Arc<RwLock<StakedNodes>>,
+    ) -> Self {
+        let exit = exit.clone();
+        let thread_hdl = Builder::new()
+            .name("sol-staked-nodes".to_string())
+            .spawn(move || {
+                let mut last_update: Option<Instant> = None;
+                while !exit.load(Ordering::Relaxed) {
+                    if last_update
+                        .map(|last_update| last_update.elapsed() >= STAKED_NODES_UPDATE_INTERVAL)
+                        .unwrap_or(true)
+                    {
+                        let stakes = bank_forks.read().unwrap().root_bank().staked_nodes();
+                        *staked_nodes.write().unwrap() = StakedNodes::new(stakes);
+                        last_update = Some(Instant::now());
+                    }
+                    sleep(Duration::from_millis(100));
+                }
+            })
+            .unwrap();
+        Self { thread_hdl }
+    }
+
+    pub fn join(self) -> thread::Result<()> {
+        self.thread_hdl.join()
+    }
+}
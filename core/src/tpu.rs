@@ -6,19 +6,22 @@ use crate::{
     broadcast_stage::{BroadcastStage, BroadcastStageType, RetransmitSlotsReceiver},
     cluster_info::ClusterInfo,
     cluster_info_vote_listener::{ClusterInfoVoteListener, VerifiedVoteSender, VoteTracker},
-    fetch_stage::FetchStage,
+    fetch_stage::{FetchStage, TpuQuicConfig},
     optimistically_confirmed_bank_tracker::BankNotificationSender,
     poh_recorder::{PohRecorder, WorkingBankEntry},
     rpc_subscriptions::RpcSubscriptions,
     sigverify::TransactionSigVerifier,
-    sigverify_stage::SigVerifyStage,
+    sigverify_stage::{SigVerifyStage, StakedSenders},
+    staked_nodes_updater_service::StakedNodesUpdaterService,
 };
 use crossbeam_channel::unbounded;
 use solana_ledger::{blockstore::Blockstore, blockstore_processor::TransactionStatusSender};
 use solana_runtime::{
     bank_forks::BankForks,
+    cost_model::CostModel,
     vote_sender_types::{ReplayVoteReceiver, ReplayVoteSender},
 };
+use solana_streamer::quic::StakedNodes;
 use std::{
     net::UdpSocket,
     sync::{
@@ -37,6 +40,7 @@ pub struct Tpu {
     banking_stage: BankingStage,
     cluster_info_vote_listener: ClusterInfoVoteListener,
     broadcast_stage: BroadcastStage,
+    staked_nodes_updater_service: Option<StakedNodesUpdaterService>,
 }
 
 impl Tpu {
@@ -48,6 +52,7 @@ impl Tpu {
         retransmit_slots_receiver: RetransmitSlotsReceiver,
         transactions_sockets: Vec<UdpSocket>,
         tpu_forwards_sockets: Vec<UdpSocket>,
+        tpu_quic_socket: Option<UdpSocket>,
         broadcast_sockets: Vec<UdpSocket>,
         subscriptions: &Arc<RpcSubscriptions>,
         transaction_status_sender: Option<TransactionStatusSender>,
@@ -62,8 +67,18 @@ impl Tpu {
         replay_vote_sender: ReplayVoteSender,
         bank_notification_sender: Option<BankNotificationSender>,
         tpu_coalesce_ms: u64,
+        enable_transaction_scheduler: bool,
     ) -> Self {
         let (packet_sender, packet_receiver) = channel();
+        let staked_nodes = Arc::new(RwLock::new(StakedNodes::default()));
+        let staked_nodes_updater_service = tpu_quic_socket.as_ref().map(|_| {
+            StakedNodesUpdaterService::new(exit, bank_forks.clone(), staked_nodes.clone())
+        });
+        let tpu_quic = tpu_quic_socket.map(|socket| TpuQuicConfig {
+            socket,
+            keypair: cluster_info.keypair.clone(),
+            staked_nodes,
+        });
         let fetch_stage = FetchStage::new_with_sender(
             transactions_sockets,
             tpu_forwards_sockets,
@@ -74,12 +89,19 @@ impl Tpu {
             // 20GB
             Some(20_000),
             tpu_coalesce_ms,
+            tpu_quic,
         );
         let (verified_sender, verified_receiver) = unbounded();
 
         let sigverify_stage = {
             let verifier = TransactionSigVerifier::default();
-            SigVerifyStage::new(packet_receiver, verified_sender, verifier)
+            let staked_senders = StakedSenders::new(cluster_info.clone(), bank_forks.clone());
+            SigVerifyStage::new(
+                packet_receiver,
+                verified_sender,
+                verifier,
+                Some(staked_senders),
+            )
         };
 
         let (verified_vote_packets_sender, verified_vote_packets_receiver) = unbounded();
@@ -97,14 +119,28 @@ impl Tpu {
             bank_notification_sender,
         );
 
-        let banking_stage = BankingStage::new(
-            &cluster_info,
-            poh_recorder,
-            verified_receiver,
-            verified_vote_packets_receiver,
-            transaction_status_sender,
-            replay_vote_sender,
-        );
+        let cost_model = CostModel::default();
+        let banking_stage = if enable_transaction_scheduler {
+            BankingStage::new_with_parallel_execution(
+                &cluster_info,
+                poh_recorder,
+                verified_receiver,
+                verified_vote_packets_receiver,
+                transaction_status_sender,
+                replay_vote_sender,
+                cost_model,
+            )
+        } else {
+            BankingStage::new_with_cost_model(
+                &cluster_info,
+                poh_recorder,
+                verified_receiver,
+                verified_vote_packets_receiver,
+                transaction_status_sender,
+                replay_vote_sender,
+                cost_model,
+            )
+        };
 
         let broadcast_stage = broadcast_type.new_broadcast_stage(
             broadcast_sockets,
@@ -122,6 +158,7 @@ impl Tpu {
             banking_stage,
             cluster_info_vote_listener,
             broadcast_stage,
+            staked_nodes_updater_service,
         }
     }
 
@@ -132,6 +169,9 @@ impl Tpu {
             self.cluster_info_vote_listener.join(),
             self.banking_stage.join(),
         ];
+        if let Some(staked_nodes_updater_service) = self.staked_nodes_updater_service {
+            staked_nodes_updater_service.join()?;
+        }
         let broadcast_result = self.broadcast_stage.join();
         for result in results {
             result?;
@@ -0,0 +1,17 @@
+use solana_sdk::{clock::Slot, signature::Signature, transaction::Transaction};
+use solana_transaction_status::TransactionStatusMeta;
+use std::sync::{Arc, RwLock};
+
+/// Receives every committed transaction and its status as it is processed by the
+/// `TransactionStatusService`
+pub trait TransactionNotifier {
+    fn notify_transaction(
+        &self,
+        slot: Slot,
+        signature: &Signature,
+        transaction_status_meta: &TransactionStatusMeta,
+        transaction: &Transaction,
+    );
+}
+
+pub type TransactionNotifierLock = Arc<RwLock<dyn TransactionNotifier + Sync + Send>>;
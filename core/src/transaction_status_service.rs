@@ -1,3 +1,4 @@
+use crate::transaction_notifier_interface::TransactionNotifierLock;
 use crossbeam_channel::{Receiver, RecvTimeoutError};
 use itertools::izip;
 use solana_ledger::{blockstore::Blockstore, blockstore_processor::TransactionStatusBatch};
@@ -5,6 +6,7 @@ use solana_runtime::{
     bank::{Bank, InnerInstructionsList, NonceRollbackInfo, TransactionLogMessages},
     transaction_utils::OrderedIterator,
 };
+use solana_sdk::fee_calculator::FeeConfig;
 use solana_transaction_status::{InnerInstructions, TransactionStatusMeta};
 use std::{
     sync::{
@@ -23,6 +25,8 @@ impl TransactionStatusService {
     #[allow(clippy::new_ret_no_self)]
     pub fn new(
         write_transaction_status_receiver: Receiver<TransactionStatusBatch>,
+        enable_rpc_transaction_history: bool,
+        transaction_notifier: Option<TransactionNotifierLock>,
         blockstore: Arc<Blockstore>,
         exit: &Arc<AtomicBool>,
     ) -> Self {
@@ -35,6 +39,8 @@ impl TransactionStatusService {
                 }
                 if let Err(RecvTimeoutError::Disconnected) = Self::write_transaction_status_batch(
                     &write_transaction_status_receiver,
+                    enable_rpc_transaction_history,
+                    &transaction_notifier,
                     &blockstore,
                 ) {
                     break;
@@ -46,6 +52,8 @@ impl TransactionStatusService {
 
     fn write_transaction_status_batch(
         write_transaction_status_receiver: &Receiver<TransactionStatusBatch>,
+        enable_rpc_transaction_history: bool,
+        transaction_notifier: &Option<TransactionNotifierLock>,
         blockstore: &Arc<Blockstore>,
     ) -> Result<(), RecvTimeoutError> {
         let TransactionStatusBatch {
@@ -98,7 +106,13 @@ impl TransactionStatusService {
                         bank.get_fee_calculator(&transaction.message().recent_blockhash)
                     })
                     .expect("FeeCalculator must exist");
-                let fee = fee_calculator.calculate_fee(transaction.message());
+                let fee = fee_calculator.calculate_fee_with_config(
+                    transaction.message(),
+                    &FeeConfig {
+                        secp256k1_program_enabled: bank.secp256k1_program_enabled(),
+                        compute_budget_program_enabled: bank.compute_budget_program_enabled(),
+                    },
+                );
                 let (writable_keys, readonly_keys) =
                     transaction.message.get_account_keys_by_lock_type();
 
@@ -118,24 +132,36 @@ impl TransactionStatusService {
                 let pre_token_balances = Some(pre_token_balances);
                 let post_token_balances = Some(post_token_balances);
 
-                blockstore
-                    .write_transaction_status(
+                let transaction_status_meta = TransactionStatusMeta {
+                    status,
+                    fee,
+                    pre_balances,
+                    post_balances,
+                    inner_instructions,
+                    log_messages,
+                    pre_token_balances,
+                    post_token_balances,
+                };
+
+                if let Some(transaction_notifier) = transaction_notifier.as_ref() {
+                    transaction_notifier.read().unwrap().notify_transaction(
                         slot,
-                        transaction.signatures[0],
-                        writable_keys,
-                        readonly_keys,
-                        TransactionStatusMeta {
-                            status,
-                            fee,
-                            pre_balances,
-                            post_balances,
-                            inner_instructions,
-                            log_messages,
-                            pre_token_balances,
-                            post_token_balances,
-                        },
-                    )
-                    .expect("Expect database write to succeed");
+                        &transaction.signatures[0],
+                        &transaction_status_meta,
+                        transaction,
+                    );
+                }
+                if enable_rpc_transaction_history {
+                    blockstore
+                        .write_transaction_status(
+                            slot,
+                            transaction.signatures[0],
+                            writable_keys,
+                            readonly_keys,
+                            transaction_status_meta,
+                        )
+                        .expect("Expect database write to succeed");
+                }
             }
         }
         Ok(())
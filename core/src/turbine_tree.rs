@@ -0,0 +1,388 @@
+//! The `turbine_tree` module decides how shreds propagate through the cluster: the order in
+//! which nodes are placed in the tree of a shred, and which nodes each of them retransmits the
+//! shred to. See `cluster_info` for network layer definitions.
+//!
+//! Every node in the cluster has to compute the same tree for a given shred, so the tree in
+//! use by `retransmit_stage` is a cluster wide choice and not a local configuration.
+//! Validators always run `LegacyTurbineTree`: `RetransmitStage` builds it itself, and
+//! `broadcast_stage` picks the root of each shred the same way. `StakeWeightedSlotTurbineTree`
+//! only runs in `solana-turbine-simulator`, until switching trees is gated behind a feature
+//! activated across the cluster.
+
+use crate::{
+    cluster_info::{compute_retransmit_peers, DATA_PLANE_FANOUT},
+    contact_info::ContactInfo,
+    weighted_shuffle::weighted_shuffle,
+};
+use solana_sdk::{clock::Slot, hash::hashv, pubkey::Pubkey};
+use std::cmp::Reverse;
+
+/// A node in the turbine tree along with its stake
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TurbineNode {
+    pub id: Pubkey,
+    /// Stake weight of the node, at least 1
+    pub stake: u64,
+}
+
+/// Sorts nodes by stake and then by pubkey in descending order, which is the order the
+/// shuffles of the tree are computed over. Zero stakes are treated as 1.
+pub fn sort_nodes(nodes: &mut [TurbineNode]) {
+    for node in nodes.iter_mut() {
+        node.stake = node.stake.max(1);
+    }
+    nodes.sort_unstable_by_key(|node| Reverse((node.stake, node.id)));
+}
+
+/// Returns the nodes of `peers` in the order of `stakes_and_index`, as returned by
+/// `ClusterInfo::sorted_retransmit_peers_and_stakes`.
+pub fn sorted_nodes(peers: &[ContactInfo], stakes_and_index: &[(u64, usize)]) -> Vec<TurbineNode> {
+    stakes_and_index
+        .iter()
+        .map(|(stake, index)| TurbineNode {
+            id: peers[*index].id,
+            stake: *stake,
+        })
+        .collect()
+}
+
+/// Returns the number of layers of a tree of `num_nodes` nodes.
+pub fn num_layers(fanout: usize, num_nodes: usize) -> usize {
+    let mut num_layers = 0;
+    let mut layer_size = 1;
+    let mut capacity = 0;
+    while capacity < num_nodes {
+        layer_size *= fanout;
+        capacity += layer_size;
+        num_layers += 1;
+    }
+    num_layers
+}
+
+/// Returns the smallest fanout which fits `num_nodes` nodes in at most `num_layers` layers.
+pub fn fanout_for_num_layers(num_layers: usize, num_nodes: usize) -> usize {
+    assert!(num_layers > 0, "a turbine tree has at least one layer");
+    (2..)
+        .find(|fanout| self::num_layers(*fanout, num_nodes) <= num_layers)
+        .unwrap()
+}
+
+pub trait TurbineTree: Send + Sync {
+    /// Number of nodes in each neighborhood, which is also the number of children of each
+    /// node in the next layer.
+    fn fanout(&self) -> usize;
+
+    /// Returns the nodes in the tree of the shred, ordered by their position in the tree, as
+    /// indexes into `nodes`. `nodes` must be sorted by `sort_nodes`. Nodes which are missing
+    /// from the output do not take part in the propagation of the shred.
+    fn shuffle(
+        &self,
+        leader: Option<&Pubkey>,
+        slot: Slot,
+        shred_seed: [u8; 32],
+        nodes: &[TurbineNode],
+    ) -> Vec<usize>;
+
+    /// Returns the node the leader sends the shred to, which is the first node in the tree
+    /// other than the leader itself, as an index into `nodes`.
+    fn root(
+        &self,
+        leader: Option<&Pubkey>,
+        slot: Slot,
+        shred_seed: [u8; 32],
+        nodes: &[TurbineNode],
+    ) -> Option<usize> {
+        self.shuffle(leader, slot, shred_seed, nodes)
+            .into_iter()
+            .find(|index| Some(&nodes[*index].id) != leader)
+    }
+
+    /// Returns the neighbors `my_id` retransmits the shred to, including `my_id` itself, and
+    /// its children in the next layer, as indexes into `nodes`.
+    fn retransmit_peers(
+        &self,
+        my_id: &Pubkey,
+        leader: Option<&Pubkey>,
+        slot: Slot,
+        shred_seed: [u8; 32],
+        nodes: &[TurbineNode],
+    ) -> (Vec<usize> /*neighbors*/, Vec<usize> /*children*/) {
+        let shuffle = self.shuffle(leader, slot, shred_seed, nodes);
+        match shuffle.iter().position(|index| nodes[*index].id == *my_id) {
+            None => (Vec::default(), Vec::default()),
+            Some(my_index) => compute_retransmit_peers(self.fanout(), my_index, &shuffle),
+        }
+    }
+}
+
+/// Shuffles the nodes by their stake, independently for each shred, seeded by the shred's
+/// signature. This is the tree the cluster has been running with.
+#[derive(Clone, Copy, Debug)]
+pub struct LegacyTurbineTree {
+    fanout: usize,
+}
+
+impl LegacyTurbineTree {
+    pub fn new(fanout: usize) -> Self {
+        assert!(fanout > 0, "turbine fanout must be positive");
+        Self { fanout }
+    }
+}
+
+impl Default for LegacyTurbineTree {
+    fn default() -> Self {
+        Self::new(DATA_PLANE_FANOUT)
+    }
+}
+
+impl TurbineTree for LegacyTurbineTree {
+    fn fanout(&self) -> usize {
+        self.fanout
+    }
+
+    fn shuffle(
+        &self,
+        _leader: Option<&Pubkey>,
+        _slot: Slot,
+        shred_seed: [u8; 32],
+        nodes: &[TurbineNode],
+    ) -> Vec<usize> {
+        let stakes = nodes.iter().map(|node| node.stake).collect();
+        weighted_shuffle(stakes, shred_seed)
+    }
+}
+
+/// Shuffles the nodes by their stake once per slot, seeded by the slot and its leader, so
+/// that all shreds of a slot go through the same tree. The leader is left out of the tree,
+/// since it does not retransmit its own shreds. Not used by validators yet, see the module
+/// documentation.
+#[derive(Clone, Copy, Debug)]
+pub struct StakeWeightedSlotTurbineTree {
+    fanout: usize,
+}
+
+impl StakeWeightedSlotTurbineTree {
+    pub fn new(fanout: usize) -> Self {
+        assert!(fanout > 0, "turbine fanout must be positive");
+        Self { fanout }
+    }
+
+    fn seed(leader: Option<&Pubkey>, slot: Slot) -> [u8; 32] {
+        let leader: &[u8] = leader.map(|leader| leader.as_ref()).unwrap_or_default();
+        hashv(&[&slot.to_le_bytes(), leader]).to_bytes()
+    }
+}
+
+impl Default for StakeWeightedSlotTurbineTree {
+    fn default() -> Self {
+        Self::new(DATA_PLANE_FANOUT)
+    }
+}
+
+impl TurbineTree for StakeWeightedSlotTurbineTree {
+    fn fanout(&self) -> usize {
+        self.fanout
+    }
+
+    fn shuffle(
+        &self,
+        leader: Option<&Pubkey>,
+        slot: Slot,
+        _shred_seed: [u8; 32],
+        nodes: &[TurbineNode],
+    ) -> Vec<usize> {
+        let (stakes, indexes): (Vec<_>, Vec<_>) = nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| Some(&node.id) != leader)
+            .map(|(index, node)| (node.stake, index))
+            .unzip();
+        weighted_shuffle(stakes, Self::seed(leader, slot))
+            .into_iter()
+            .map(|k| indexes[k])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cluster_info::ClusterInfo;
+    use rand::Rng;
+    use solana_sdk::pubkey;
+    use std::collections::{HashMap, HashSet};
+
+    fn new_rand_nodes<R: Rng>(rng: &mut R, num_nodes: usize) -> Vec<TurbineNode> {
+        let mut nodes: Vec<_> = std::iter::repeat_with(|| TurbineNode {
+            id: pubkey::new_rand(),
+            stake: rng.gen_range(0, 1_000),
+        })
+        .take(num_nodes)
+        .collect();
+        sort_nodes(&mut nodes);
+        nodes
+    }
+
+    // Returns the number of times each node receives the shred, if it propagates from the
+    // root through the tree. Nodes in a neighborhood may receive the shred both from their
+    // parent and from their neighbors, but retransmit it only once. Forwarded shreds are
+    // only retransmitted to children.
+    fn propagate<T: TurbineTree>(
+        tree: &T,
+        leader: &Pubkey,
+        slot: Slot,
+        shred_seed: [u8; 32],
+        nodes: &[TurbineNode],
+    ) -> HashMap<Pubkey, usize> {
+        let mut received = HashMap::new();
+        // (node, forwarded) pairs, where forwarded nodes only retransmit to their children.
+        let root = tree.root(Some(leader), slot, shred_seed, nodes).unwrap();
+        let mut queue = vec![(nodes[root].id, false)];
+        while let Some((id, forwarded)) = queue.pop() {
+            *received.entry(id).or_default() += 1;
+            if received[&id] > 1 {
+                continue;
+            }
+            let (neighbors, children) =
+                tree.retransmit_peers(&id, Some(leader), slot, shred_seed, nodes);
+            if !forwarded {
+                queue.extend(
+                    neighbors
+                        .into_iter()
+                        .map(|index| nodes[index].id)
+                        .filter(|node| *node != id)
+                        .map(|node| (node, true)),
+                );
+            }
+            queue.extend(
+                children
+                    .into_iter()
+                    .map(|index| (nodes[index].id, forwarded)),
+            );
+        }
+        received
+    }
+
+    #[test]
+    fn test_num_layers() {
+        assert_eq!(num_layers(200, 0), 0);
+        assert_eq!(num_layers(200, 1), 1);
+        assert_eq!(num_layers(200, 200), 1);
+        assert_eq!(num_layers(200, 201), 2);
+        assert_eq!(num_layers(200, 200 + 200 * 200), 2);
+        assert_eq!(num_layers(200, 200 + 200 * 200 + 1), 3);
+        assert_eq!(fanout_for_num_layers(1, 1000), 1000);
+        assert_eq!(fanout_for_num_layers(2, 1000), 31);
+        assert_eq!(fanout_for_num_layers(3, 1000), 10);
+        assert_eq!(fanout_for_num_layers(2, 10), 3);
+    }
+
+    #[test]
+    fn test_sort_nodes() {
+        let mut rng = rand::thread_rng();
+        let nodes = new_rand_nodes(&mut rng, 64);
+        assert!(nodes.iter().all(|node| node.stake > 0));
+        assert!(nodes
+            .windows(2)
+            .all(|w| (w[0].stake, w[0].id) > (w[1].stake, w[1].id)));
+    }
+
+    // The legacy tree should match the shuffle retransmit stage was computing before.
+    #[test]
+    fn test_legacy_turbine_tree() {
+        let mut rng = rand::thread_rng();
+        let peers: Vec<_> = std::iter::repeat_with(|| {
+            ContactInfo::new_localhost(&pubkey::new_rand(), /*now:*/ 0)
+        })
+        .take(1000)
+        .collect();
+        let stakes: HashMap<_, _> = peers
+            .iter()
+            .filter_map(|peer| {
+                if rng.gen_ratio(2, 3) {
+                    Some((peer.id, rng.gen_range(0, 1_000)))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        let mut peers_copy = peers.clone();
+        let stakes_and_index =
+            crate::cluster_info::stake_weight_peers(&mut peers_copy, Some(&stakes));
+        let nodes = sorted_nodes(&peers_copy, &stakes_and_index);
+        let tree = LegacyTurbineTree::new(20);
+        for _ in 0..4 {
+            let seed: [u8; 32] = rng.gen();
+            let my_id = peers[rng.gen_range(0, peers.len())].id;
+            let (my_index, shuffle) =
+                ClusterInfo::shuffle_peers_and_index(&my_id, &peers_copy, &stakes_and_index, seed);
+            let shuffle: Vec<_> = shuffle.into_iter().map(|(_, index)| index).collect();
+            let (neighbors, children) = compute_retransmit_peers(20, my_index, &shuffle);
+            let to_ids = |indexes: Vec<usize>| -> Vec<Pubkey> {
+                indexes.into_iter().map(|k| peers_copy[k].id).collect()
+            };
+            let (tree_neighbors, tree_children) =
+                tree.retransmit_peers(&my_id, None, 0, seed, &nodes);
+            let to_node_ids = |indexes: Vec<usize>| -> Vec<Pubkey> {
+                indexes.into_iter().map(|k| nodes[k].id).collect()
+            };
+            assert_eq!(to_ids(neighbors), to_node_ids(tree_neighbors));
+            assert_eq!(to_ids(children), to_node_ids(tree_children));
+        }
+    }
+
+    #[test]
+    fn test_stake_weighted_slot_turbine_tree() {
+        let mut rng = rand::thread_rng();
+        let nodes = new_rand_nodes(&mut rng, 500);
+        let leader = nodes[rng.gen_range(0, nodes.len())].id;
+        let tree = StakeWeightedSlotTurbineTree::new(8);
+        let shuffle = tree.shuffle(Some(&leader), 42, rng.gen(), &nodes);
+        // The leader is not in the tree, and all other nodes are.
+        assert_eq!(shuffle.len(), nodes.len() - 1);
+        assert!(shuffle.iter().all(|index| nodes[*index].id != leader));
+        assert_eq!(
+            shuffle.iter().collect::<HashSet<_>>().len(),
+            nodes.len() - 1
+        );
+        // Same tree for all shreds of the slot.
+        assert_eq!(tree.shuffle(Some(&leader), 42, rng.gen(), &nodes), shuffle);
+        // but not for other slots or leaders.
+        assert_ne!(tree.shuffle(Some(&leader), 43, rng.gen(), &nodes), shuffle);
+        assert_ne!(tree.shuffle(None, 42, rng.gen(), &nodes), shuffle);
+        assert_eq!(
+            tree.retransmit_peers(&leader, Some(&leader), 42, rng.gen(), &nodes),
+            (vec![], vec![])
+        );
+    }
+
+    #[test]
+    fn test_turbine_tree_propagation() {
+        let mut rng = rand::thread_rng();
+        let nodes = new_rand_nodes(&mut rng, 1000);
+        let leader = nodes[rng.gen_range(0, nodes.len())].id;
+        let trees: Vec<Box<dyn Fn([u8; 32]) -> HashMap<Pubkey, usize>>> = vec![
+            Box::new(|seed| propagate(&LegacyTurbineTree::new(7), &leader, 7, seed, &nodes)),
+            Box::new(|seed| {
+                propagate(
+                    &StakeWeightedSlotTurbineTree::new(7),
+                    &leader,
+                    7,
+                    seed,
+                    &nodes,
+                )
+            }),
+        ];
+        for (k, propagate) in trees.into_iter().enumerate() {
+            let received = propagate(rng.gen());
+            for node in &nodes {
+                if k == 1 && node.id == leader {
+                    assert!(!received.contains_key(&node.id));
+                } else {
+                    // Every other node receives the shred.
+                    assert!(received[&node.id] > 0);
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 //! The `validator` module hosts all the validator microservices.
 
 use crate::{
+    accountsdb_plugin_service::AccountsDbPluginService,
     broadcast_stage::BroadcastStageType,
     cache_block_time_service::{CacheBlockTimeSender, CacheBlockTimeService},
     cluster_info::{
@@ -29,6 +30,7 @@ use crate::{
     sigverify,
     snapshot_packager_service::{PendingSnapshotPackage, SnapshotPackagerService},
     tpu::{Tpu, DEFAULT_TPU_COALESCE_MS},
+    transaction_notifier_interface::TransactionNotifierLock,
     transaction_status_service::TransactionStatusService,
     tvu::{Sockets, Tvu, TvuConfig},
 };
@@ -37,7 +39,7 @@ use rand::{thread_rng, Rng};
 use solana_ledger::{
     bank_forks_utils,
     blockstore::{Blockstore, BlockstoreSignals, CompletedSlotsReceiver, PurgeType},
-    blockstore_db::BlockstoreRecoveryMode,
+    blockstore_db::{BlockstoreOptions, BlockstoreRecoveryMode, ColumnOptions},
     blockstore_processor::{self, TransactionStatusSender},
     leader_schedule::FixedSchedule,
     leader_schedule_cache::LeaderScheduleCache,
@@ -46,9 +48,11 @@ use solana_ledger::{
 use solana_measure::measure::Measure;
 use solana_metrics::datapoint_info;
 use solana_runtime::{
-    accounts_index::AccountIndex,
+    accounts_index::{AccountSecondaryIndexes, AccountsIndexConfig},
+    accounts_update_notifier_interface::AccountsUpdateNotifier,
     bank::Bank,
     bank_forks::{BankForks, SnapshotConfig},
+    cold_storage::DEFAULT_COLD_STORAGE_CACHE_SIZE,
     commitment::BlockCommitmentCache,
     hardened_unpack::{open_genesis_config, MAX_GENESIS_ARCHIVE_UNPACKED_SIZE},
 };
@@ -65,7 +69,7 @@ use solana_sdk::{
 use solana_vote_program::vote_state::VoteState;
 use std::time::Instant;
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fmt,
     net::SocketAddr,
     ops::Deref,
@@ -111,6 +115,7 @@ pub struct ValidatorConfig {
     pub accounts_hash_interval_slots: u64,
     pub max_genesis_archive_unpacked_size: u64,
     pub wal_recovery_mode: Option<BlockstoreRecoveryMode>,
+    pub ledger_column_options: HashMap<String, ColumnOptions>,
     pub poh_verify: bool, // Perform PoH verification during blockstore processing at boo
     pub cuda: bool,
     pub require_tower: bool,
@@ -120,16 +125,23 @@ pub struct ValidatorConfig {
     pub bpf_jit: bool,
     pub send_transaction_retry_ms: u64,
     pub send_transaction_leader_forward_count: u64,
+    pub send_transaction_use_quic: bool,
     pub no_poh_speed_test: bool,
     pub poh_pinned_cpu_core: usize,
     pub poh_hashes_per_batch: u64,
-    pub account_indexes: HashSet<AccountIndex>,
+    pub account_indexes: AccountSecondaryIndexes,
     pub accounts_db_caching_enabled: bool,
+    pub accounts_index_config: Option<AccountsIndexConfig>,
+    pub accounts_cold_storage_slot_age: Option<Slot>,
+    pub accounts_cold_storage_cache_size: usize,
+    pub enable_transaction_scheduler: bool,
     pub warp_slot: Option<Slot>,
     pub accounts_db_test_hash_calculation: bool,
     pub accounts_db_use_index_hash_calculation: bool,
     pub tpu_coalesce_ms: u64,
+    pub tpu_use_quic: bool,
     pub validator_exit: Arc<RwLock<ValidatorExit>>,
+    pub accountsdb_plugin_config_files: Option<Vec<PathBuf>>,
 }
 
 impl Default for ValidatorConfig {
@@ -165,6 +177,7 @@ impl Default for ValidatorConfig {
             accounts_hash_interval_slots: std::u64::MAX,
             max_genesis_archive_unpacked_size: MAX_GENESIS_ARCHIVE_UNPACKED_SIZE,
             wal_recovery_mode: None,
+            ledger_column_options: HashMap::new(),
             poh_verify: true,
             cuda: false,
             require_tower: false,
@@ -174,16 +187,23 @@ impl Default for ValidatorConfig {
             bpf_jit: false,
             send_transaction_retry_ms: 2000,
             send_transaction_leader_forward_count: 2,
+            send_transaction_use_quic: false,
             no_poh_speed_test: true,
             poh_pinned_cpu_core: poh_service::DEFAULT_PINNED_CPU_CORE,
             poh_hashes_per_batch: poh_service::DEFAULT_HASHES_PER_BATCH,
-            account_indexes: HashSet::new(),
+            account_indexes: AccountSecondaryIndexes::default(),
             accounts_db_caching_enabled: false,
+            accounts_index_config: None,
+            accounts_cold_storage_slot_age: None,
+            accounts_cold_storage_cache_size: DEFAULT_COLD_STORAGE_CACHE_SIZE,
+            enable_transaction_scheduler: false,
             warp_slot: None,
             accounts_db_test_hash_calculation: false,
             accounts_db_use_index_hash_calculation: true,
             tpu_coalesce_ms: DEFAULT_TPU_COALESCE_MS,
+            tpu_use_quic: false,
             validator_exit: Arc::new(RwLock::new(ValidatorExit::default())),
+            accountsdb_plugin_config_files: None,
         }
     }
 }
@@ -271,6 +291,7 @@ pub struct Validator {
     tpu: Tpu,
     tvu: Tvu,
     ip_echo_server: solana_net_utils::IpEchoServer,
+    accountsdb_plugin_service: Option<AccountsDbPluginService>,
 }
 
 // in the distant future, get rid of ::new()/exit() and use Result properly...
@@ -370,6 +391,26 @@ impl Validator {
                 .register_exit(Box::new(move || exit.store(true, Ordering::Relaxed)));
         }
 
+        let (accountsdb_plugin_service, accountsdb_plugin_bank_notification_sender) =
+            if let Some(accountsdb_plugin_config_files) = &config.accountsdb_plugin_config_files {
+                let (bank_notification_sender, bank_notification_receiver) = unbounded();
+                let accountsdb_plugin_service = AccountsDbPluginService::new(
+                    bank_notification_receiver,
+                    accountsdb_plugin_config_files,
+                    &exit,
+                )
+                .unwrap_or_else(|err| {
+                    error!("Failed to load the AccountsDb plugins: {:?}", err);
+                    abort();
+                });
+                (
+                    Some(accountsdb_plugin_service),
+                    Some(bank_notification_sender),
+                )
+            } else {
+                (None, None)
+            };
+
         let (replay_vote_sender, replay_vote_receiver) = unbounded();
         let (
             genesis_config,
@@ -397,6 +438,12 @@ impl Validator {
             &exit,
             config.enforce_ulimit_nofile,
             &start_progress,
+            accountsdb_plugin_service
+                .as_ref()
+                .map(|service| service.get_accounts_update_notifier()),
+            accountsdb_plugin_service
+                .as_ref()
+                .map(|service| service.get_transaction_notifier()),
         );
 
         *start_progress.write().unwrap() = ValidatorStartProgress::StartingServices;
@@ -406,6 +453,10 @@ impl Validator {
         if let Some(ref shrink_paths) = config.account_shrink_paths {
             bank.set_shrink_paths(shrink_paths.clone());
         }
+        if let Some(slot_age) = config.accounts_cold_storage_slot_age {
+            bank.set_cold_storage_slot_age(slot_age);
+        }
+        bank.set_cold_storage_cache_size(config.accounts_cold_storage_cache_size);
         let bank_forks = Arc::new(RwLock::new(bank_forks));
 
         let sample_performance_service =
@@ -445,6 +496,10 @@ impl Validator {
             }
         }
 
+        if !config.tpu_use_quic {
+            // Don't advertise a QUIC port that nobody listens on
+            node.info.tpu_quic = socketaddr_any!();
+        }
         let mut cluster_info = ClusterInfo::new(node.info.clone(), identity_keypair.clone());
         cluster_info.set_contact_debug_interval(config.contact_debug_interval);
         cluster_info.set_entrypoints(cluster_entrypoints);
@@ -457,12 +512,13 @@ impl Validator {
         let optimistically_confirmed_bank =
             OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
 
-        let subscriptions = Arc::new(RpcSubscriptions::new_with_vote_subscription(
+        let subscriptions = Arc::new(RpcSubscriptions::new_with_config(
             &exit,
             bank_forks.clone(),
             block_commitment_cache.clone(),
             optimistically_confirmed_bank.clone(),
-            config.pubsub_config.enable_vote_subscription,
+            Some(blockstore.clone()),
+            &config.pubsub_config,
         ));
 
         let max_slots = Arc::new(MaxSlots::default());
@@ -540,7 +596,9 @@ impl Validator {
                     optimistically_confirmed_bank.clone(),
                     config.send_transaction_retry_ms,
                     config.send_transaction_leader_forward_count,
+                    config.send_transaction_use_quic,
                     max_slots.clone(),
+                    leader_schedule_cache.clone(),
                 )),
                 if config.rpc_config.minimal_api {
                     None
@@ -558,11 +616,13 @@ impl Validator {
                     bank_forks.clone(),
                     optimistically_confirmed_bank,
                     subscriptions.clone(),
+                    accountsdb_plugin_bank_notification_sender,
                 )),
                 Some(bank_notification_sender),
             )
         } else {
-            (None, None, None, None)
+            // Without an RPC service, the AccountsDb plugins receive bank notifications directly
+            (None, None, None, accountsdb_plugin_bank_notification_sender)
         };
 
         if config.dev_halt_at_slot.is_some() {
@@ -589,7 +649,10 @@ impl Validator {
             should_check_duplicate_instance,
             &exit,
         );
-        let serve_repair = Arc::new(RwLock::new(ServeRepair::new(cluster_info.clone())));
+        let serve_repair = Arc::new(RwLock::new(ServeRepair::new(
+            cluster_info.clone(),
+            bank_forks.clone(),
+        )));
         let serve_repair_service = ServeRepairService::new(
             &serve_repair,
             Some(blockstore.clone()),
@@ -602,7 +665,12 @@ impl Validator {
                 if is_snapshot_config_invalid(
                     snapshot_config.snapshot_interval_slots,
                     config.accounts_hash_interval_slots,
-                ) {
+                ) || (snapshot_config.incremental_snapshots_enabled()
+                    && is_snapshot_config_invalid(
+                        snapshot_config.incremental_snapshot_interval_slots,
+                        config.accounts_hash_interval_slots,
+                    ))
+                {
                     error!("Snapshot config is invalid");
                 }
 
@@ -614,6 +682,14 @@ impl Validator {
                     snapshot_hash,
                     &exit,
                     &cluster_info,
+                    bank_forks
+                        .read()
+                        .unwrap()
+                        .root_bank()
+                        .rc
+                        .accounts
+                        .accounts_db
+                        .clone(),
                 );
                 (
                     Some(snapshot_packager_service),
@@ -720,6 +796,7 @@ impl Validator {
                 use_index_hash_calculation: config.accounts_db_use_index_hash_calculation,
                 rocksdb_compaction_interval: config.rocksdb_compaction_interval,
                 rocksdb_max_compaction_jitter: config.rocksdb_compaction_interval,
+                schedule_transactions: config.enable_transaction_scheduler,
             },
             &max_slots,
         );
@@ -731,6 +808,11 @@ impl Validator {
             retransmit_slots_receiver,
             node.sockets.tpu,
             node.sockets.tpu_forwards,
+            if config.tpu_use_quic {
+                Some(node.sockets.tpu_quic)
+            } else {
+                None
+            },
             node.sockets.broadcast,
             &subscriptions,
             transaction_status_sender,
@@ -745,6 +827,7 @@ impl Validator {
             replay_vote_sender,
             bank_notification_sender,
             config.tpu_coalesce_ms,
+            config.enable_transaction_scheduler,
         );
 
         datapoint_info!("validator-new", ("id", id.to_string(), String));
@@ -767,6 +850,7 @@ impl Validator {
             poh_recorder,
             ip_echo_server,
             validator_exit: config.validator_exit.clone(),
+            accountsdb_plugin_service,
         }
     }
 
@@ -863,6 +947,12 @@ impl Validator {
             .join()
             .expect("completed_data_sets_service");
         self.ip_echo_server.shutdown_background();
+
+        if let Some(accountsdb_plugin_service) = self.accountsdb_plugin_service {
+            accountsdb_plugin_service
+                .join()
+                .expect("accountsdb_plugin_service");
+        }
     }
 }
 
@@ -995,7 +1085,7 @@ fn post_process_restored_tower(
         })
 }
 
-#[allow(clippy::type_complexity)]
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 fn new_banks_from_ledger(
     validator_identity: &Pubkey,
     vote_account: &Pubkey,
@@ -1005,6 +1095,8 @@ fn new_banks_from_ledger(
     exit: &Arc<AtomicBool>,
     enforce_ulimit_nofile: bool,
     start_progress: &Arc<RwLock<ValidatorStartProgress>>,
+    accounts_update_notifier: Option<AccountsUpdateNotifier>,
+    transaction_notifier: Option<TransactionNotifierLock>,
 ) -> (
     GenesisConfig,
     BankForks,
@@ -1043,10 +1135,14 @@ fn new_banks_from_ledger(
         ledger_signal_receiver,
         completed_slots_receivers,
         ..
-    } = Blockstore::open_with_signal(
+    } = Blockstore::open_with_options_and_signal(
         ledger_path,
-        config.wal_recovery_mode.clone(),
-        enforce_ulimit_nofile,
+        BlockstoreOptions {
+            recovery_mode: config.wal_recovery_mode.clone(),
+            enforce_ulimit_nofile,
+            column_options: config.ledger_column_options.clone(),
+            ..BlockstoreOptions::default()
+        },
     )
     .expect("Failed to open ledger database");
     blockstore.set_no_compaction(config.no_rocksdb_compaction);
@@ -1068,16 +1164,23 @@ fn new_banks_from_ledger(
         debug_keys: config.debug_keys.clone(),
         account_indexes: config.account_indexes.clone(),
         accounts_db_caching_enabled: config.accounts_db_caching_enabled,
+        accounts_index_config: config.accounts_index_config.clone(),
+        accounts_update_notifier,
+        schedule_transactions: config.enable_transaction_scheduler,
         ..blockstore_processor::ProcessOptions::default()
     };
 
     let blockstore = Arc::new(blockstore);
+    let enable_rpc_transaction_history =
+        config.rpc_addrs.is_some() && config.rpc_config.enable_rpc_transaction_history;
     let transaction_history_services =
-        if config.rpc_addrs.is_some() && config.rpc_config.enable_rpc_transaction_history {
+        if enable_rpc_transaction_history || transaction_notifier.is_some() {
             initialize_rpc_transaction_history_services(
                 blockstore.clone(),
                 exit,
+                enable_rpc_transaction_history,
                 config.rpc_config.enable_cpi_and_log_storage,
+                transaction_notifier,
             )
         } else {
             TransactionHistoryServices::default()
@@ -1248,7 +1351,9 @@ fn backup_and_clear_blockstore(ledger_path: &Path, start_slot: Slot, shred_versi
 fn initialize_rpc_transaction_history_services(
     blockstore: Arc<Blockstore>,
     exit: &Arc<AtomicBool>,
+    enable_rpc_transaction_history: bool,
     enable_cpi_and_log_storage: bool,
+    transaction_notifier: Option<TransactionNotifierLock>,
 ) -> TransactionHistoryServices {
     let (transaction_status_sender, transaction_status_receiver) = unbounded();
     let transaction_status_sender = Some(TransactionStatusSender {
@@ -1257,10 +1362,21 @@ fn initialize_rpc_transaction_history_services(
     });
     let transaction_status_service = Some(TransactionStatusService::new(
         transaction_status_receiver,
+        enable_rpc_transaction_history,
+        transaction_notifier,
         blockstore.clone(),
         exit,
     ));
 
+    if !enable_rpc_transaction_history {
+        // Only the AccountsDb plugins want transactions; rewards and block times are not needed
+        return TransactionHistoryServices {
+            transaction_status_sender,
+            transaction_status_service,
+            ..TransactionHistoryServices::default()
+        };
+    }
+
     let (rewards_recorder_sender, rewards_receiver) = unbounded();
     let rewards_recorder_sender = Some(rewards_recorder_sender);
     let rewards_recorder_service = Some(RewardsRecorderService::new(
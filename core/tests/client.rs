@@ -1,4 +1,7 @@
-use solana_client::{pubsub_client::PubsubClient, rpc_client::RpcClient, rpc_response::SlotInfo};
+use futures_util::StreamExt;
+use solana_client::{
+    nonblocking, pubsub_client::PubsubClient, rpc_client::RpcClient, rpc_response::SlotInfo,
+};
 use solana_core::{
     optimistically_confirmed_bank_tracker::OptimisticallyConfirmedBank,
     rpc_pubsub_service::{PubSubConfig, PubSubService},
@@ -91,20 +94,7 @@ fn test_slot_subscription() {
         rpc_port::DEFAULT_RPC_PUBSUB_PORT,
     );
     let exit = Arc::new(AtomicBool::new(false));
-    let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
-    let bank = Bank::new(&genesis_config);
-    let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
-    let optimistically_confirmed_bank =
-        OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
-    let subscriptions = Arc::new(RpcSubscriptions::new(
-        &exit,
-        bank_forks,
-        Arc::new(RwLock::new(BlockCommitmentCache::default())),
-        optimistically_confirmed_bank,
-    ));
-    let pubsub_service =
-        PubSubService::new(PubSubConfig::default(), &subscriptions, pubsub_addr, &exit);
-    std::thread::sleep(Duration::from_millis(400));
+    let (subscriptions, pubsub_service) = start_pubsub_service(pubsub_addr, &exit);
 
     let (mut client, receiver) =
         PubsubClient::slot_subscribe(&format!("ws://0.0.0.0:{}/", pubsub_addr.port())).unwrap();
@@ -141,3 +131,103 @@ fn test_slot_subscription() {
 
     assert_eq!(errors, [].to_vec());
 }
+
+fn start_pubsub_service(
+    pubsub_addr: SocketAddr,
+    exit: &Arc<AtomicBool>,
+) -> (Arc<RpcSubscriptions>, PubSubService) {
+    let GenesisConfigInfo { genesis_config, .. } = create_genesis_config(10_000);
+    let bank = Bank::new(&genesis_config);
+    let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+    let optimistically_confirmed_bank =
+        OptimisticallyConfirmedBank::locked_from_bank_forks_root(&bank_forks);
+    let subscriptions = Arc::new(RpcSubscriptions::new(
+        exit,
+        bank_forks,
+        Arc::new(RwLock::new(BlockCommitmentCache::default())),
+        optimistically_confirmed_bank,
+    ));
+    let pubsub_service =
+        PubSubService::new(PubSubConfig::default(), &subscriptions, pubsub_addr, exit);
+    std::thread::sleep(Duration::from_millis(400));
+    (subscriptions, pubsub_service)
+}
+
+#[test]
+fn test_root_subscription() {
+    let pubsub_addr = SocketAddr::new(
+        IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
+        rpc_port::DEFAULT_RPC_PUBSUB_PORT + 1,
+    );
+    let exit = Arc::new(AtomicBool::new(false));
+    let (subscriptions, pubsub_service) = start_pubsub_service(pubsub_addr, &exit);
+
+    let (mut client, receiver) =
+        PubsubClient::root_subscribe(&format!("ws://0.0.0.0:{}/", pubsub_addr.port())).unwrap();
+
+    subscriptions.notify_roots(vec![2, 1]);
+    let mut roots = vec![];
+    while let Ok(root) = receiver.recv_timeout(Duration::from_millis(400)) {
+        roots.push(root);
+    }
+
+    exit.store(true, Ordering::Relaxed);
+    client.shutdown().unwrap();
+    pubsub_service.close().unwrap();
+
+    assert_eq!(roots, vec![1, 2]);
+}
+
+#[test]
+fn test_nonblocking_subscriptions() {
+    let pubsub_addr = SocketAddr::new(
+        IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
+        rpc_port::DEFAULT_RPC_PUBSUB_PORT + 2,
+    );
+    let exit = Arc::new(AtomicBool::new(false));
+    let (subscriptions, pubsub_service) = start_pubsub_service(pubsub_addr, &exit);
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let client = nonblocking::pubsub_client::PubsubClient::new(&format!(
+            "ws://0.0.0.0:{}/",
+            pubsub_addr.port()
+        ))
+        .await
+        .unwrap();
+
+        // Both subscriptions share the client's single websocket
+        let (mut slot_notifications, slot_unsubscribe) = client.slot_subscribe().await.unwrap();
+        let (mut root_notifications, root_unsubscribe) = client.root_subscribe().await.unwrap();
+
+        subscriptions.notify_slot(1, 0, 0);
+        subscriptions.notify_roots(vec![1]);
+
+        let timeout = Duration::from_secs(2);
+        assert_eq!(
+            tokio::time::timeout(timeout, slot_notifications.next())
+                .await
+                .unwrap(),
+            Some(SlotInfo {
+                slot: 1,
+                parent: 0,
+                root: 0,
+            })
+        );
+        assert_eq!(
+            tokio::time::timeout(timeout, root_notifications.next())
+                .await
+                .unwrap(),
+            Some(1)
+        );
+
+        drop(slot_notifications);
+        drop(root_notifications);
+        slot_unsubscribe().await;
+        root_unsubscribe().await;
+        client.shutdown().await.unwrap();
+    });
+
+    exit.store(true, Ordering::Relaxed);
+    pubsub_service.close().unwrap();
+}
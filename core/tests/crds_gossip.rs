@@ -11,7 +11,9 @@ use solana_core::crds_gossip_error::CrdsGossipError;
 use solana_core::crds_gossip_pull::{ProcessPullStats, CRDS_GOSSIP_PULL_CRDS_TIMEOUT_MS};
 use solana_core::crds_gossip_push::CRDS_GOSSIP_PUSH_MSG_TIMEOUT_MS;
 use solana_core::crds_value::CrdsValueLabel;
-use solana_core::crds_value::{CrdsData, CrdsValue};
+use solana_core::crds_value::{
+    CrdsData, CrdsDataKind, CrdsDataKinds, CrdsValue, SnapshotHash, Version,
+};
 use solana_rayon_threadlimit::get_thread_count;
 use solana_sdk::hash::hash;
 use solana_sdk::pubkey::Pubkey;
@@ -48,6 +50,8 @@ struct Network {
     nodes: HashMap<Pubkey, Node>,
     stake_pruned: u64,
     connections_pruned: HashSet<(Pubkey, Pubkey)>,
+    // Number of push messages each node has received.
+    push_messages_received: HashMap<Pubkey, usize>,
 }
 
 impl Network {
@@ -56,6 +60,7 @@ impl Network {
             nodes,
             connections_pruned: HashSet::new(),
             stake_pruned: 0,
+            push_messages_received: HashMap::new(),
         }
     }
 }
@@ -227,10 +232,9 @@ fn network_simulator(thread_pool: &ThreadPool, network: &mut Network, max_conver
     trace!("network_simulator_push_{}: converged: {}", num, converged);
     // make sure there is someone in the active set
     let network_values: Vec<Node> = network.values().cloned().collect();
+    let stakes = stakes(network);
     network_values.par_iter().for_each(|node| {
-        node.lock()
-            .unwrap()
-            .refresh_push_active_set(&HashMap::new(), None);
+        node.lock().unwrap().refresh_push_active_set(&stakes, None);
     });
     let mut total_bytes = bytes_tx;
     let mut ts = timestamp();
@@ -312,9 +316,11 @@ fn network_run_push(
                 let mut delivered: usize = 0;
                 let mut num_msgs: usize = 0;
                 let mut pruned: HashSet<(Pubkey, Pubkey)> = HashSet::new();
+                let mut received = Vec::new();
                 for (to, msgs) in push_messages {
                     bytes += serialized_size(&msgs).unwrap() as usize;
                     num_msgs += 1;
+                    received.push(to);
                     let updated = network
                         .get(&to)
                         .map(|node| {
@@ -356,14 +362,17 @@ fn network_run_push(
                             .unwrap();
                     }
                 }
-                (bytes, delivered, num_msgs, pruned)
+                (bytes, delivered, num_msgs, pruned, received)
             })
             .collect();
 
-        for (b, d, m, p) in transfered {
+        for (b, d, m, p, r) in transfered {
             bytes += b;
             delivered += d;
             num_msgs += m;
+            for to in r {
+                *network.push_messages_received.entry(to).or_default() += 1;
+            }
 
             for (from, to) in p {
                 let from_stake = stakes.get(&from).unwrap();
@@ -375,9 +384,7 @@ fn network_run_push(
         }
         if now % CRDS_GOSSIP_PUSH_MSG_TIMEOUT_MS == 0 && now > 0 {
             network_values.par_iter().for_each(|node| {
-                node.lock()
-                    .unwrap()
-                    .refresh_push_active_set(&HashMap::new(), None);
+                node.lock().unwrap().refresh_push_active_set(&stakes, None);
             });
         }
         total = network_values
@@ -596,6 +603,167 @@ fn test_connected_staked_network() {
     )
 }
 #[test]
+#[serial]
+fn test_connected_staked_network_push_by_stake() {
+    solana_logger::setup();
+    let thread_pool = build_gossip_thread_pool();
+    let stakes = [[1_000_000; 5].to_vec(), [1; 45].to_vec()].concat();
+    let mut network = connected_staked_network_create(&stakes);
+    network_simulator(&thread_pool, &mut network, 1.0);
+
+    let (mut staked, mut unstaked) = (vec![], vec![]);
+    for (pubkey, node) in network.iter() {
+        let received = *network.push_messages_received.get(pubkey).unwrap_or(&0);
+        if node.stake > 1 {
+            staked.push(received);
+        } else {
+            unstaked.push(received);
+        }
+    }
+    let avg_staked = staked.iter().sum::<usize>() / staked.len();
+    let avg_unstaked = unstaked.iter().sum::<usize>() / unstaked.len();
+    trace!(
+        "connected staked network, avg push messages received by staked nodes: {}, unstaked nodes: {}",
+        avg_staked,
+        avg_unstaked
+    );
+    assert!(
+        avg_staked > avg_unstaked,
+        "staked nodes should receive more push messages"
+    );
+}
+#[test]
+fn test_pull_request_kinds() {
+    let thread_pool = build_gossip_thread_pool();
+    let kinds: CrdsDataKinds = vec![CrdsDataKind::ContactInfo, CrdsDataKind::Version]
+        .into_iter()
+        .collect();
+    // A full node which has values of several kinds.
+    let mut full = CrdsGossip::default();
+    let full_id = solana_sdk::pubkey::new_rand();
+    full.set_self(&full_id);
+    let full_info = ContactInfo::new_localhost(&full_id, timestamp());
+    full.crds
+        .insert(
+            CrdsValue::new_unsigned(CrdsData::ContactInfo(full_info.clone())),
+            timestamp(),
+        )
+        .unwrap();
+    for _ in 0..200 {
+        let pubkey = solana_sdk::pubkey::new_rand();
+        let snapshot_hashes = vec![(0, hash(pubkey.as_ref()))];
+        let mut insert = |data| {
+            full.crds
+                .insert(CrdsValue::new_unsigned(data), timestamp())
+                .unwrap();
+        };
+        insert(CrdsData::ContactInfo(ContactInfo::new_localhost(
+            &pubkey,
+            timestamp(),
+        )));
+        insert(CrdsData::Version(Version::new(pubkey)));
+        insert(CrdsData::SnapshotHashes(SnapshotHash::new(
+            pubkey,
+            snapshot_hashes.clone(),
+        )));
+        insert(CrdsData::AccountsHashes(SnapshotHash::new(
+            pubkey,
+            snapshot_hashes,
+        )));
+    }
+    // A light node which only syncs contact infos and versions.
+    let mut light = CrdsGossip::default();
+    let light_id = solana_sdk::pubkey::new_rand();
+    light.set_self(&light_id);
+    light.pull.pull_request_kinds = kinds;
+    let now = timestamp();
+    light
+        .crds
+        .insert(
+            CrdsValue::new_unsigned(CrdsData::ContactInfo(ContactInfo::new_localhost(
+                &light_id, now,
+            ))),
+            now,
+        )
+        .unwrap();
+    light
+        .crds
+        .insert(
+            CrdsValue::new_unsigned(CrdsData::ContactInfo(full_info)),
+            now,
+        )
+        .unwrap();
+
+    let timeouts = light.make_timeouts_test();
+    let mut full_bytes = 0;
+    let mut partial_bytes = 0;
+    for _ in 0..10 {
+        let (to, filters, caller) = light
+            .new_pull_request(
+                &thread_pool,
+                now,
+                None,
+                &HashMap::new(),
+                cluster_info::MAX_BLOOM_SIZE,
+            )
+            .unwrap();
+        assert_eq!(to, full_id);
+        assert!(filters.iter().all(|filter| filter.kinds == kinds));
+        let filters: Vec<_> = filters.into_iter().map(|f| (caller.clone(), f)).collect();
+        let rsp: Vec<_> = full
+            .generate_pull_responses(&filters, /*output_size_limit=*/ usize::MAX, now)
+            .into_iter()
+            .flatten()
+            .collect();
+        assert!(rsp.iter().all(|value| kinds.contains(value.data.kind())));
+        partial_bytes += serialized_size(&rsp).unwrap();
+        // The same request for all kinds of values.
+        let all_filters: Vec<_> = filters
+            .iter()
+            .map(|(caller, filter)| {
+                let mut filter = filter.clone();
+                filter.kinds = CrdsDataKinds::all();
+                (caller.clone(), filter)
+            })
+            .collect();
+        full_bytes += full
+            .generate_pull_responses(&all_filters, /*output_size_limit=*/ usize::MAX, now)
+            .iter()
+            .map(|rsp| serialized_size(rsp).unwrap())
+            .sum::<u64>();
+        let mut stats = ProcessPullStats::default();
+        let (vers, vers_expired_timeout, failed_inserts) =
+            light.filter_pull_responses(&timeouts, rsp, now, &mut stats);
+        light.process_pull_responses(
+            &full_id,
+            vers,
+            vers_expired_timeout,
+            failed_inserts,
+            now,
+            &mut stats,
+        );
+    }
+    trace!(
+        "pull request kinds, partial bytes: {}, full bytes: {}",
+        partial_bytes,
+        full_bytes
+    );
+    assert!(partial_bytes < full_bytes);
+    // The light node has synced all the values of the requested kinds, and
+    // nothing else.
+    assert!(light
+        .crds
+        .par_values()
+        .all(|value| kinds.contains(value.value.data.kind())));
+    let num_values = full
+        .crds
+        .par_values()
+        .filter(|value| kinds.contains(value.value.data.kind()))
+        .count();
+    // Plus the light node's own contact info.
+    assert_eq!(light.crds.len(), num_values + 1);
+}
+#[test]
 #[ignore]
 fn test_star_network_large_pull() {
     solana_logger::setup();
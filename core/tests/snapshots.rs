@@ -29,6 +29,11 @@ macro_rules! DEFINE_SNAPSHOT_VERSION_PARAMETERIZED_TEST_FUNCTIONS {
             fn test_slots_to_snapshot() {
                 run_test_slots_to_snapshot(SNAPSHOT_VERSION, CLUSTER_TYPE)
             }
+
+            #[test]
+            fn test_incremental_snapshot() {
+                run_test_incremental_snapshot(SNAPSHOT_VERSION, CLUSTER_TYPE)
+            }
         }
     };
 }
@@ -47,9 +52,11 @@ mod tests {
     use solana_runtime::{
         accounts_background_service::{AbsRequestSender, SnapshotRequestHandler},
         accounts_db,
+        accounts_index::AccountSecondaryIndexes,
         bank::{Bank, BankSlotDelta},
         bank_forks::{ArchiveFormat, BankForks, SnapshotConfig},
         genesis_utils::{create_genesis_config, GenesisConfigInfo},
+        snapshot_package::SnapshotType,
         snapshot_utils,
         snapshot_utils::SnapshotVersion,
         status_cache::MAX_CACHE_ENTRIES,
@@ -60,7 +67,8 @@ mod tests {
         hash::hashv,
         pubkey::Pubkey,
         signature::{Keypair, Signer},
-        system_transaction,
+        system_instruction, system_transaction,
+        transaction::Transaction,
     };
     use std::{
         collections::HashSet,
@@ -106,8 +114,9 @@ mod tests {
                 &[],
                 None,
                 None,
-                HashSet::new(),
+                AccountSecondaryIndexes::default(),
                 false,
+                None,
             );
             bank0.freeze();
             let mut bank_forks = BankForks::new(bank0);
@@ -115,6 +124,7 @@ mod tests {
 
             let snapshot_config = SnapshotConfig {
                 snapshot_interval_slots,
+                incremental_snapshot_interval_slots: Slot::MAX,
                 snapshot_package_output_path: PathBuf::from(snapshot_output_path.path()),
                 snapshot_path: PathBuf::from(snapshot_dir.path()),
                 archive_format: ArchiveFormat::TarBzip2,
@@ -163,8 +173,9 @@ mod tests {
             old_genesis_config,
             None,
             None,
-            HashSet::new(),
+            AccountSecondaryIndexes::default(),
             false,
+            None,
         )
         .unwrap();
 
@@ -241,6 +252,7 @@ mod tests {
             ArchiveFormat::TarBzip2,
             snapshot_version,
             None,
+            Some(SnapshotType::FullSnapshot),
         )
         .unwrap();
         let snapshot_package = snapshot_utils::process_accounts_package_pre(
@@ -365,6 +377,7 @@ mod tests {
                 snapshot_config.snapshot_version,
                 &snapshot_config.archive_format,
                 None,
+                Some(SnapshotType::FullSnapshot),
             )
             .unwrap();
 
@@ -441,6 +454,7 @@ mod tests {
             None,
             &exit,
             &cluster_info,
+            bank_forks.root_bank().rc.accounts.accounts_db.clone(),
         );
 
         let thread_pool = accounts_db::make_min_priority_thread_pool();
@@ -548,6 +562,114 @@ mod tests {
         }
     }
 
+    fn run_test_incremental_snapshot(snapshot_version: SnapshotVersion, cluster_type: ClusterType) {
+        solana_logger::setup();
+        const FULL_SNAPSHOT_SLOT: Slot = 2;
+        const INCREMENTAL_SNAPSHOT_SLOT: Slot = 4;
+
+        let mut snapshot_test_config = SnapshotTestConfig::new(snapshot_version, cluster_type, 1);
+        let bank_forks = &mut snapshot_test_config.bank_forks;
+        let mint_keypair = &snapshot_test_config.genesis_config_info.mint_keypair;
+        let snapshot_config = &snapshot_test_config.snapshot_config;
+        let keypair = Keypair::new();
+
+        for slot in 0..INCREMENTAL_SNAPSHOT_SLOT {
+            let mut bank = Bank::new_from_parent(&bank_forks[slot], &Pubkey::default(), slot + 1);
+            match bank.slot() {
+                1 => {
+                    let tx = system_transaction::transfer(
+                        &mint_keypair,
+                        &keypair.pubkey(),
+                        10,
+                        bank.last_blockhash(),
+                    );
+                    assert_eq!(bank.process_transaction(&tx), Ok(()));
+                }
+                3 => {
+                    // Drain the account after the full snapshot, so only the incremental
+                    // snapshot knows it is gone
+                    let tx = Transaction::new_signed_with_payer(
+                        &[system_instruction::transfer(
+                            &keypair.pubkey(),
+                            &mint_keypair.pubkey(),
+                            10,
+                        )],
+                        Some(&mint_keypair.pubkey()),
+                        &[mint_keypair, &keypair],
+                        bank.last_blockhash(),
+                    );
+                    assert_eq!(bank.process_transaction(&tx), Ok(()));
+                }
+                _ => (),
+            }
+            goto_end_of_slot(&mut bank);
+            let bank = bank_forks.insert(bank);
+            bank_forks.set_root(bank.slot(), &AbsRequestSender::default(), None);
+
+            if bank.slot() == FULL_SNAPSHOT_SLOT {
+                snapshot_utils::bank_to_snapshot_archive(
+                    &snapshot_config.snapshot_path,
+                    &bank,
+                    Some(snapshot_version),
+                    &snapshot_config.snapshot_package_output_path,
+                    ArchiveFormat::TarBzip2,
+                    None,
+                )
+                .unwrap();
+            }
+        }
+
+        let bank = bank_forks.get(INCREMENTAL_SNAPSHOT_SLOT).unwrap().clone();
+        snapshot_utils::bank_to_incremental_snapshot_archive(
+            &snapshot_config.snapshot_path,
+            &bank,
+            FULL_SNAPSHOT_SLOT,
+            Some(snapshot_version),
+            &snapshot_config.snapshot_package_output_path,
+            ArchiveFormat::TarBzip2,
+            None,
+        )
+        .unwrap();
+
+        let (full_snapshot_archive, (full_snapshot_slot, _, full_snapshot_archive_format)) =
+            snapshot_utils::get_highest_snapshot_archive_path(
+                &snapshot_config.snapshot_package_output_path,
+            )
+            .unwrap();
+        assert_eq!(full_snapshot_slot, FULL_SNAPSHOT_SLOT);
+        let (
+            incremental_snapshot_archive,
+            (_, incremental_snapshot_slot, _, incremental_snapshot_archive_format),
+        ) = snapshot_utils::get_highest_incremental_snapshot_archive_path(
+            &snapshot_config.snapshot_package_output_path,
+            full_snapshot_slot,
+        )
+        .unwrap();
+        assert_eq!(incremental_snapshot_slot, INCREMENTAL_SNAPSHOT_SLOT);
+
+        let accounts_dir = TempDir::new().unwrap();
+        let deserialized_bank = snapshot_utils::bank_from_snapshot_archives(
+            &[accounts_dir.path().to_path_buf()],
+            &[],
+            &snapshot_config.snapshot_path,
+            (&full_snapshot_archive, full_snapshot_archive_format),
+            Some((
+                &incremental_snapshot_archive,
+                incremental_snapshot_archive_format,
+            )),
+            &snapshot_test_config.genesis_config_info.genesis_config,
+            None,
+            None,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(*bank, deserialized_bank);
+        assert_eq!(deserialized_bank.get_balance(&keypair.pubkey()), 0);
+    }
+
     fn run_test_bank_forks_status_cache_snapshot_n(
         snapshot_version: SnapshotVersion,
         cluster_type: ClusterType,
@@ -63,17 +63,17 @@ fn run_dos(
         match data_type.as_str() {
             "repair_highest" => {
                 let slot = 100;
-                let req = RepairProtocol::WindowIndexWithNonce(contact, slot, 0, 0);
+                let req = RepairProtocol::LegacyWindowIndexWithNonce(contact, slot, 0, 0);
                 data = bincode::serialize(&req).unwrap();
             }
             "repair_shred" => {
                 let slot = 100;
-                let req = RepairProtocol::HighestWindowIndexWithNonce(contact, slot, 0, 0);
+                let req = RepairProtocol::LegacyHighestWindowIndexWithNonce(contact, slot, 0, 0);
                 data = bincode::serialize(&req).unwrap();
             }
             "repair_orphan" => {
                 let slot = 100;
-                let req = RepairProtocol::OrphanWithNonce(contact, slot, 0);
+                let req = RepairProtocol::LegacyOrphanWithNonce(contact, slot, 0);
                 data = bincode::serialize(&req).unwrap();
             }
             "random" => {
@@ -210,3 +210,55 @@ pub fn download_snapshot(
     }
     Err("Snapshot couldn't be downloaded".to_string())
 }
+
+/// Download the highest incremental snapshot that the RPC node built on top of the full snapshot
+/// at `full_snapshot_slot`
+pub fn download_incremental_snapshot(
+    rpc_addr: &SocketAddr,
+    ledger_path: &Path,
+    full_snapshot_slot: Slot,
+    use_progress_bar: bool,
+) -> Result<(), String> {
+    // Ask the RPC node for the name of its highest incremental snapshot without following the
+    // redirect, so that its base slot can be checked before downloading anything
+    let client = reqwest::blocking::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|err| err.to_string())?;
+    let response = client
+        .get(&format!("http://{}/incremental-snapshot.tar.bz2", rpc_addr))
+        .send()
+        .map_err(|err| err.to_string())?;
+    let location = response
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .ok_or_else(|| "Incremental snapshot not available".to_string())?;
+    let archive_filename = location.trim_start_matches('/');
+
+    let (base_slot, slot, hash, archive_format) =
+        snapshot_utils::incremental_snapshot_hash_of(archive_filename)
+            .ok_or_else(|| format!("Invalid incremental snapshot name: {}", archive_filename))?;
+    if base_slot != full_snapshot_slot {
+        return Err(format!(
+            "Incremental snapshot for slot {} is based on slot {}, not the full snapshot slot {}",
+            slot, base_slot, full_snapshot_slot
+        ));
+    }
+
+    let desired_snapshot_package = snapshot_utils::get_incremental_snapshot_archive_path(
+        ledger_path.to_path_buf(),
+        full_snapshot_slot,
+        &(slot, hash),
+        archive_format,
+    );
+    if desired_snapshot_package.is_file() {
+        return Ok(());
+    }
+
+    download_file(
+        &format!("http://{}/{}", rpc_addr, archive_filename),
+        &desired_snapshot_package,
+        use_progress_bar,
+    )
+}
@@ -0,0 +1,172 @@
+//! The `crds_dump` module records the values of a node's gossip table as newline-delimited
+//! JSON, and answers queries over a recorded file.
+
+use serde::{Deserialize, Serialize};
+use solana_core::{
+    crds::VersionedCrdsValue,
+    crds_value::{CrdsData, CrdsDataKind, CrdsValue},
+};
+use solana_sdk::{clock::Slot, hash::Hash};
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+/// A gossip value along with where and when it originated
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CrdsRecord {
+    /// Local time the value was inserted into the gossip table at
+    pub timestamp: u64,
+    /// Pubkey of the node which created the value
+    pub origin: String,
+    pub wallclock: u64,
+    pub kind: CrdsDataKind,
+    pub label: String,
+    pub value: CrdsValue,
+}
+
+impl CrdsRecord {
+    pub fn new(value: &CrdsValue, timestamp: u64) -> Self {
+        let label = value.label();
+        Self {
+            timestamp,
+            origin: label.pubkey().to_string(),
+            wallclock: value.wallclock(),
+            kind: value.data.kind(),
+            label: label.to_string(),
+            value: value.clone(),
+        }
+    }
+}
+
+impl From<&VersionedCrdsValue> for CrdsRecord {
+    fn from(value: &VersionedCrdsValue) -> Self {
+        Self::new(&value.value, value.insert_timestamp)
+    }
+}
+
+pub fn write_records<W: Write>(writer: &mut W, records: &[CrdsRecord]) -> io::Result<()> {
+    for record in records {
+        serde_json::to_writer(&mut *writer, record)?;
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+pub fn read_records<R: BufRead>(reader: R) -> impl Iterator<Item = io::Result<CrdsRecord>> {
+    reader
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| -> io::Result<CrdsRecord> { Ok(serde_json::from_str(&line?)?) })
+}
+
+/// Returns the nodes whose latest recorded snapshot hashes (or accounts hashes) advertise
+/// `hash`, along with the slot of the hash, sorted by origin
+pub fn find_snapshot_hash<I>(records: I, hash: &Hash, accounts_hashes: bool) -> Vec<(String, Slot)>
+where
+    I: IntoIterator<Item = CrdsRecord>,
+{
+    let mut latest: HashMap<String, (u64, Vec<(Slot, Hash)>)> = HashMap::new();
+    for record in records {
+        let hashes = match record.value.data {
+            CrdsData::SnapshotHashes(snapshot_hash) if !accounts_hashes => snapshot_hash.hashes,
+            CrdsData::AccountsHashes(snapshot_hash) if accounts_hashes => snapshot_hash.hashes,
+            _ => continue,
+        };
+        match latest.get(&record.origin) {
+            Some((wallclock, _)) if *wallclock > record.wallclock => (),
+            _ => {
+                latest.insert(record.origin, (record.wallclock, hashes));
+            }
+        }
+    }
+    let mut nodes: Vec<_> = latest
+        .into_iter()
+        .filter_map(|(origin, (_, hashes))| {
+            let (slot, _) = hashes.into_iter().find(|(_, h)| h == hash)?;
+            Some((origin, slot))
+        })
+        .collect();
+    nodes.sort();
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_core::crds_value::SnapshotHash;
+    use solana_sdk::{
+        pubkey::{self, Pubkey},
+        timing::timestamp,
+    };
+
+    fn new_snapshot_hashes(from: Pubkey, hashes: Vec<(Slot, Hash)>, wallclock: u64) -> CrdsValue {
+        CrdsValue::new_unsigned(CrdsData::SnapshotHashes(SnapshotHash {
+            from,
+            hashes,
+            wallclock,
+        }))
+    }
+
+    #[test]
+    fn test_write_read_records() {
+        let mut rng = rand::thread_rng();
+        let now = timestamp();
+        let records: Vec<_> = std::iter::repeat_with(|| CrdsValue::new_rand(&mut rng, None))
+            .take(32)
+            .map(|value| CrdsRecord::new(&value, now))
+            .collect();
+        let mut buffer = vec![];
+        write_records(&mut buffer, &records).unwrap();
+        write_records(&mut buffer, &records[..1]).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer.clone()).unwrap().lines().count(),
+            33
+        );
+        let read: Vec<_> = read_records(&buffer[..])
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(read[..32], records[..]);
+        assert_eq!(read[32], records[0]);
+
+        assert!(read_records(&b"not json\n"[..]).next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_find_snapshot_hash() {
+        let hash = Hash::new_unique();
+        let other_hash = Hash::new_unique();
+        let node0 = pubkey::new_rand();
+        let node1 = pubkey::new_rand();
+        let node2 = pubkey::new_rand();
+        let records: Vec<_> = vec![
+            new_snapshot_hashes(node0, vec![(10, other_hash), (20, hash)], 1),
+            // node1 advertised the hash, but no longer does.
+            new_snapshot_hashes(node1, vec![(20, hash)], 1),
+            new_snapshot_hashes(node1, vec![(30, other_hash)], 2),
+            // Out of order records of node2.
+            new_snapshot_hashes(node2, vec![(20, hash)], 4),
+            new_snapshot_hashes(node2, vec![(10, other_hash)], 3),
+            CrdsValue::new_unsigned(CrdsData::AccountsHashes(SnapshotHash {
+                from: node1,
+                hashes: vec![(40, hash)],
+                wallclock: 1,
+            })),
+        ]
+        .iter()
+        .map(|value| CrdsRecord::new(value, 0))
+        .collect();
+
+        let mut expected = vec![(node0.to_string(), 20), (node2.to_string(), 20)];
+        expected.sort();
+        assert_eq!(
+            find_snapshot_hash(records.iter().cloned(), &hash, false),
+            expected
+        );
+        assert_eq!(
+            find_snapshot_hash(records.iter().cloned(), &hash, true),
+            vec![(node1.to_string(), 40)]
+        );
+        assert!(find_snapshot_hash(records, &Hash::new_unique(), false).is_empty());
+    }
+}
@@ -6,17 +6,48 @@ use clap::{
 };
 use solana_clap_utils::{
     input_parsers::keypair_of,
-    input_validators::{is_keypair_or_ask_keyword, is_port, is_pubkey},
+    input_validators::{is_hash, is_keypair_or_ask_keyword, is_parsable, is_port, is_pubkey},
 };
-use solana_core::{contact_info::ContactInfo, gossip_service::discover};
-use solana_sdk::pubkey::Pubkey;
+use solana_core::{
+    contact_info::ContactInfo,
+    gossip_service::{discover, make_gossip_node},
+};
+use solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Keypair};
 use std::{
     error,
+    fs::{File, OpenOptions},
+    io::{self, stdout, BufReader, BufWriter, Write},
     net::{IpAddr, Ipv4Addr, SocketAddr},
     process::exit,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
+mod crds_dump;
+use crds_dump::{find_snapshot_hash, read_records, write_records, CrdsRecord};
+
+/// How long the record command waits for new values before checking its timeout
+const RECORD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How many inserted values the record command may fall behind before values are dropped
+const RECORD_QUEUE_CAPACITY: usize = 100_000;
+
+const CRDS_DATA_KINDS: &[&str] = &[
+    "ContactInfo",
+    "Vote",
+    "LowestSlot",
+    "SnapshotHashes",
+    "AccountsHashes",
+    "EpochSlots",
+    "LegacyVersion",
+    "Version",
+    "NodeInstance",
+    "DuplicateShred",
+];
+
 fn parse_matches() -> ArgMatches<'static> {
     let shred_version_arg = Arg::with_name("shred_version")
         .long("shred-version")
@@ -24,6 +55,28 @@ fn parse_matches() -> ArgMatches<'static> {
         .takes_value(true)
         .default_value("0")
         .help("Filter gossip nodes by this shred version");
+    let gossip_port_arg = Arg::with_name("gossip_port")
+        .long("gossip-port")
+        .value_name("PORT")
+        .takes_value(true)
+        .validator(is_port)
+        .help("Gossip port number for the node");
+    let gossip_host_arg = Arg::with_name("gossip_host")
+        .long("gossip-host")
+        .value_name("HOST")
+        .takes_value(true)
+        .validator(solana_net_utils::is_host)
+        .help(
+            "Gossip DNS name or IP address for the node to advertise in gossip \
+               [default: ask --entrypoint, or 127.0.0.1 when --entrypoint is not provided]",
+        );
+    let identity_arg = Arg::with_name("identity")
+        .short("i")
+        .long("identity")
+        .value_name("PATH")
+        .takes_value(true)
+        .validator(is_keypair_or_ask_keyword)
+        .help("Identity keypair [default: ephemeral keypair]");
 
     App::new(crate_name!())
         .about(crate_description!())
@@ -79,32 +132,9 @@ fn parse_matches() -> ArgMatches<'static> {
                         .validator(solana_net_utils::is_host_port)
                         .help("Rendezvous with the cluster at this entrypoint"),
                 )
-                .arg(
-                    clap::Arg::with_name("gossip_port")
-                        .long("gossip-port")
-                        .value_name("PORT")
-                        .takes_value(true)
-                        .validator(is_port)
-                        .help("Gossip port number for the node"),
-                )
-                .arg(
-                    clap::Arg::with_name("gossip_host")
-                        .long("gossip-host")
-                        .value_name("HOST")
-                        .takes_value(true)
-                        .validator(solana_net_utils::is_host)
-                        .help("Gossip DNS name or IP address for the node to advertise in gossip \
-                               [default: ask --entrypoint, or 127.0.0.1 when --entrypoint is not provided]"),
-                )
-                .arg(
-                    Arg::with_name("identity")
-                        .short("i")
-                        .long("identity")
-                        .value_name("PATH")
-                        .takes_value(true)
-                        .validator(is_keypair_or_ask_keyword)
-                        .help("Identity keypair [default: ephemeral keypair]"),
-                )
+                .arg(&gossip_port_arg)
+                .arg(&gossip_host_arg)
+                .arg(&identity_arg)
                 .arg(
                     Arg::with_name("num_nodes")
                         .short("N")
@@ -140,6 +170,93 @@ fn parse_matches() -> ArgMatches<'static> {
                         .help("Maximum time to wait in seconds [default: wait forever]"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("record")
+                .about(
+                    "Join gossip and record every value inserted into the gossip table, \
+                        including the ones replaced right after, as newline-delimited JSON",
+                )
+                .setting(AppSettings::DisableVersion)
+                .arg(
+                    Arg::with_name("entrypoint")
+                        .short("n")
+                        .long("entrypoint")
+                        .value_name("HOST:PORT")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(solana_net_utils::is_host_port)
+                        .help("Rendezvous with the cluster at this entrypoint"),
+                )
+                .arg(&gossip_port_arg)
+                .arg(&gossip_host_arg)
+                .arg(&identity_arg)
+                .arg(&shred_version_arg)
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("FILE")
+                        .takes_value(true)
+                        .help("Append the values to this file [default: stdout]"),
+                )
+                .arg(
+                    Arg::with_name("timeout")
+                        .long("timeout")
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .validator(is_parsable::<u64>)
+                        .help("Stop recording after this many seconds [default: record forever]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("query")
+                .about("Query the values recorded by the record command")
+                .setting(AppSettings::DisableVersion)
+                .arg(
+                    Arg::with_name("input")
+                        .index(1)
+                        .value_name("FILE")
+                        .takes_value(true)
+                        .required(true)
+                        .help("File written by the record command"),
+                )
+                .arg(
+                    Arg::with_name("snapshot_hash")
+                        .long("snapshot-hash")
+                        .value_name("HASH")
+                        .takes_value(true)
+                        .validator(is_hash)
+                        .conflicts_with("accounts_hash")
+                        .help("List the nodes which advertise this snapshot hash"),
+                )
+                .arg(
+                    Arg::with_name("accounts_hash")
+                        .long("accounts-hash")
+                        .value_name("HASH")
+                        .takes_value(true)
+                        .validator(is_hash)
+                        .help("List the nodes which advertise this accounts hash"),
+                )
+                .arg(
+                    Arg::with_name("kind")
+                        .long("kind")
+                        .value_name("KIND")
+                        .takes_value(true)
+                        .multiple(true)
+                        .possible_values(CRDS_DATA_KINDS)
+                        .conflicts_with_all(&["snapshot_hash", "accounts_hash"])
+                        .help("Only print values of these kinds"),
+                )
+                .arg(
+                    Arg::with_name("origin")
+                        .long("origin")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .conflicts_with_all(&["snapshot_hash", "accounts_hash"])
+                        .help("Only print values created by this node"),
+                ),
+        )
         .get_matches()
 }
 
@@ -226,18 +343,7 @@ fn process_spy(matches: &ArgMatches) -> std::io::Result<()> {
 
     let entrypoint_addr = parse_entrypoint(matches);
 
-    let gossip_host = parse_gossip_host(matches, entrypoint_addr);
-
-    let gossip_addr = SocketAddr::new(
-        gossip_host,
-        value_t!(matches, "gossip_port", u16).unwrap_or_else(|_| {
-            solana_net_utils::find_available_port_in_range(
-                IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
-                (0, 1),
-            )
-            .expect("unable to find an available gossip port")
-        }),
-    );
+    let gossip_addr = parse_gossip_addr(matches, entrypoint_addr);
 
     let (_all_peers, validators) = discover(
         identity_keypair,
@@ -255,6 +361,120 @@ fn process_spy(matches: &ArgMatches) -> std::io::Result<()> {
     Ok(())
 }
 
+fn parse_gossip_addr(matches: &ArgMatches, entrypoint_addr: Option<SocketAddr>) -> SocketAddr {
+    let gossip_host = parse_gossip_host(matches, entrypoint_addr);
+    SocketAddr::new(
+        gossip_host,
+        value_t!(matches, "gossip_port", u16).unwrap_or_else(|_| {
+            solana_net_utils::find_available_port_in_range(
+                IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
+                (0, 1),
+            )
+            .expect("unable to find an available gossip port")
+        }),
+    )
+}
+
+fn process_record(matches: &ArgMatches) -> io::Result<()> {
+    let timeout = value_t!(matches, "timeout", u64)
+        .ok()
+        .map(Duration::from_secs);
+    let shred_version = value_t_or_exit!(matches, "shred_version", u16);
+    let identity_keypair = keypair_of(&matches, "identity").unwrap_or_else(Keypair::new);
+    let entrypoint_addr = parse_entrypoint(matches);
+    let gossip_addr = parse_gossip_addr(matches, entrypoint_addr);
+    let mut output: Box<dyn Write> = match matches.value_of("output") {
+        Some(path) => Box::new(BufWriter::new(
+            OpenOptions::new().create(true).append(true).open(path)?,
+        )),
+        None => Box::new(stdout()),
+    };
+
+    let exit = Arc::new(AtomicBool::new(false));
+    let (gossip_service, ip_echo, cluster_info) = make_gossip_node(
+        Arc::new(identity_keypair),
+        entrypoint_addr.as_ref(),
+        &exit,
+        Some(&gossip_addr),
+        shred_version,
+        true, // should_check_duplicate_instance
+    );
+    let _ip_echo_server = ip_echo.map(solana_net_utils::ip_echo_server);
+    eprintln!(
+        "Recording gossip as {} at {}",
+        cluster_info.id(),
+        gossip_addr
+    );
+
+    // Subscribe and read the values already in the table under the same lock, so that every
+    // value is recorded exactly once
+    let (mut records, receiver) = {
+        let mut gossip = cluster_info.gossip.write().unwrap();
+        let receiver = gossip.crds.subscribe_inserts(RECORD_QUEUE_CAPACITY);
+        let mut records: Vec<_> = gossip.crds.values().map(CrdsRecord::from).collect();
+        records.sort_by_key(|record| record.wallclock);
+        (records, receiver)
+    };
+    let mut num_records = 0;
+    let start = Instant::now();
+    let result = loop {
+        if let Err(err) = write_records(&mut output, &records) {
+            break Err(err);
+        }
+        num_records += records.len();
+        records.clear();
+        if timeout.map_or(false, |timeout| start.elapsed() >= timeout) {
+            break Ok(());
+        }
+        match receiver.recv_timeout(RECORD_INTERVAL) {
+            Ok(value) => {
+                records.push(CrdsRecord::from(&value));
+                records.extend(receiver.try_iter().map(|value| CrdsRecord::from(&value)));
+            }
+            Err(err) if err.is_disconnected() => break Ok(()),
+            Err(_) => (),
+        }
+    };
+    exit.store(true, Ordering::Relaxed);
+    gossip_service.join().unwrap();
+    eprintln!("Recorded {} values", num_records);
+    result
+}
+
+fn process_query(matches: &ArgMatches) -> io::Result<()> {
+    let input = BufReader::new(File::open(matches.value_of("input").unwrap())?);
+    let records = read_records(input).collect::<io::Result<Vec<_>>>()?;
+
+    let snapshot_hash = value_t!(matches, "snapshot_hash", Hash).ok();
+    let accounts_hash = value_t!(matches, "accounts_hash", Hash).ok();
+    if let Some((hash, accounts_hashes)) = snapshot_hash
+        .map(|hash| (hash, false))
+        .or_else(|| accounts_hash.map(|hash| (hash, true)))
+    {
+        let nodes = find_snapshot_hash(records, &hash, accounts_hashes);
+        if nodes.is_empty() {
+            eprintln!("No node advertises {}", hash);
+            exit(1);
+        }
+        for (origin, slot) in nodes {
+            println!("{} {}", origin, slot);
+        }
+        return Ok(());
+    }
+
+    let kinds: Option<Vec<&str>> = matches.values_of("kind").map(|kinds| kinds.collect());
+    let origin = matches.value_of("origin");
+    let records: Vec<_> = records
+        .into_iter()
+        .filter(|record| {
+            kinds.as_ref().map_or(true, |kinds| {
+                kinds.contains(&format!("{:?}", record.kind).as_str())
+            }) && origin.map_or(true, |origin| record.origin == origin)
+        })
+        .collect();
+    write_records(&mut stdout(), &records)
+}
+
 fn parse_entrypoint(matches: &ArgMatches) -> Option<SocketAddr> {
     matches.value_of("entrypoint").map(|entrypoint| {
         solana_net_utils::parse_host_port(entrypoint).unwrap_or_else(|e| {
@@ -320,6 +540,12 @@ fn main() -> Result<(), Box<dyn error::Error>> {
         ("rpc-url", Some(matches)) => {
             process_rpc_url(matches)?;
         }
+        ("record", Some(matches)) => {
+            process_record(matches)?;
+        }
+        ("query", Some(matches)) => {
+            process_query(matches)?;
+        }
         _ => unreachable!(),
     }
 
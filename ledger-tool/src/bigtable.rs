@@ -10,7 +10,9 @@ use solana_cli_output::{
 };
 use solana_ledger::{blockstore::Blockstore, blockstore_db::AccessType};
 use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature};
-use solana_transaction_status::{ConfirmedBlock, EncodedTransaction, UiTransactionEncoding};
+use solana_transaction_status::{
+    ConfirmedBlock, EncodedTransaction, SignaturesForAddressFilter, UiTransactionEncoding,
+};
 use std::{
     path::Path,
     process::exit,
@@ -142,6 +144,7 @@ pub async fn transaction_history(
                 before.as_ref(),
                 until.as_ref(),
                 limit.min(query_chunk_size),
+                SignaturesForAddressFilter::default(),
             )
             .await?;
 
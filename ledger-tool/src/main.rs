@@ -19,7 +19,9 @@ use solana_ledger::{
     ancestor_iterator::AncestorIterator,
     bank_forks_utils,
     blockstore::{create_new_ledger, Blockstore, PurgeType},
-    blockstore_db::{self, AccessType, BlockstoreRecoveryMode, Column, Database},
+    blockstore_db::{
+        self, AccessType, BlockstoreOptions, BlockstoreRecoveryMode, Column, Database,
+    },
     blockstore_processor::ProcessOptions,
     shred::Shred,
 };
@@ -118,13 +120,17 @@ fn output_entry(
                     })
                     .map(|transaction_status| transaction_status.into());
 
-                solana_cli_output::display::println_transaction(
-                    &transaction,
-                    &transaction_status,
-                    "      ",
-                    None,
-                    None,
-                );
+                match transaction.clone().into_legacy_transaction() {
+                    Some(transaction) => solana_cli_output::display::println_transaction(
+                        &transaction,
+                        &transaction_status,
+                        "      ",
+                        None,
+                        None,
+                    ),
+                    // Versioned messages can't be displayed yet
+                    None => println!("      {:?}", transaction),
+                }
             }
         }
         LedgerOutputMethod::Json => {
@@ -185,9 +191,10 @@ fn output_slot(
             transactions += entry.transactions.len();
             hashes += entry.num_hashes;
             for transaction in &entry.transactions {
-                for instruction in &transaction.message().instructions {
-                    let program_id =
-                        transaction.message().account_keys[instruction.program_id_index as usize];
+                // Programs are always listed by the message itself
+                for instruction in transaction.message.instructions() {
+                    let program_id = transaction.message.static_account_keys()
+                        [instruction.program_id_index as usize];
                     *program_ids.entry(program_id).or_insert(0) += 1;
                 }
             }
@@ -616,6 +623,30 @@ fn analyze_storage(database: &Database) {
     analyze_column::<Rewards>(database, "Rewards", Rewards::key_size());
 }
 
+fn print_column_sizes(database: &Database) {
+    let column_stats = database.column_storage_stats().unwrap_or_else(|err| {
+        eprintln!("Unable to read the column sizes: {:?}", err);
+        exit(1);
+    });
+    println!(
+        "{:<26} {:>16} {:>16} {:>12}",
+        "Column", "On-disk bytes", "Raw bytes", "Compression"
+    );
+    let mut total_disk_size = 0;
+    for (name, stats) in column_stats {
+        let compression_ratio = stats
+            .compression_ratio()
+            .map(|ratio| format!("{:.2}x", ratio))
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<26} {:>16} {:>16} {:>12}",
+            name, stats.disk_size, stats.raw_size, compression_ratio
+        );
+        total_disk_size += stats.disk_size;
+    }
+    println!("{:<26} {:>16}", "Total", total_disk_size);
+}
+
 fn open_blockstore(
     ledger_path: &Path,
     access_type: AccessType,
@@ -631,7 +662,11 @@ fn open_blockstore(
 }
 
 fn open_database(ledger_path: &Path, access_type: AccessType) -> Database {
-    match Database::open(&ledger_path.join("rocksdb"), access_type, None) {
+    let options = BlockstoreOptions {
+        access_type,
+        ..BlockstoreOptions::default()
+    };
+    match Database::open(&ledger_path.join("rocksdb"), &options) {
         Ok(database) => database,
         Err(err) => {
             eprintln!("Unable to read the Ledger rocksdb: {:?}", err);
@@ -670,6 +705,7 @@ fn load_bank_forks(
             snapshot_archive_path.unwrap_or_else(|| blockstore.ledger_path().to_path_buf());
         Some(SnapshotConfig {
             snapshot_interval_slots: 0, // Value doesn't matter
+            incremental_snapshot_interval_slots: Slot::MAX,
             snapshot_package_output_path,
             snapshot_path,
             archive_format: ArchiveFormat::TarBzip2,
@@ -1092,6 +1128,14 @@ fn main() {
                     .takes_value(true)
                     .help("Output directory for the snapshot [default: --ledger directory]"),
             )
+            .arg(
+                Arg::with_name("incremental")
+                    .long("incremental")
+                    .takes_value(false)
+                    .conflicts_with("warp_slot")
+                    .help("Create an incremental snapshot relative to the full snapshot \
+                           that the ledger was loaded from"),
+            )
             .arg(
                 Arg::with_name("warp_slot")
                     .required(false)
@@ -1336,6 +1380,11 @@ fn main() {
                 .about("Output statistics in JSON format about \
                         all column families in the ledger rocksdb")
         )
+        .subcommand(
+            SubCommand::with_name("column-sizes")
+                .about("Print the on-disk size and compression ratio of \
+                        each column family in the ledger rocksdb")
+        )
         .get_matches();
 
     info!("{} {}", crate_name!(), solana_version::version!());
@@ -1768,6 +1817,7 @@ fn main() {
             let output_directory = value_t!(arg_matches, "output_directory", PathBuf)
                 .unwrap_or_else(|_| ledger_path.clone());
             let mut warp_slot = value_t!(arg_matches, "warp_slot", Slot).ok();
+            let is_incremental = arg_matches.is_present("incremental");
             let remove_stake_accounts = arg_matches.is_present("remove_stake_accounts");
             let new_hard_forks = hardforks_of(arg_matches, "hard_forks");
 
@@ -2006,27 +2056,58 @@ fn main() {
                         bank
                     };
 
-                    println!(
-                        "Creating a version {} snapshot of slot {}",
-                        snapshot_version,
-                        bank.slot(),
-                    );
-
-                    let archive_file = snapshot_utils::bank_to_snapshot_archive(
-                        ledger_path,
-                        &bank,
-                        Some(snapshot_version),
-                        output_directory,
-                        ArchiveFormat::TarZstd,
-                        None,
-                    )
+                    let archive_file = if is_incremental {
+                        let full_snapshot_slot = bank
+                            .rc
+                            .accounts
+                            .accounts_db
+                            .last_full_snapshot_slot()
+                            .unwrap_or_else(|| {
+                                eprintln!(
+                                    "Unable to create incremental snapshot: \
+                                     a full snapshot must be loaded first"
+                                );
+                                exit(1);
+                            });
+                        println!(
+                            "Creating a version {} incremental snapshot of slot {} \
+                             based on full snapshot slot {}",
+                            snapshot_version,
+                            bank.slot(),
+                            full_snapshot_slot,
+                        );
+                        snapshot_utils::bank_to_incremental_snapshot_archive(
+                            ledger_path,
+                            &bank,
+                            full_snapshot_slot,
+                            Some(snapshot_version),
+                            output_directory,
+                            ArchiveFormat::TarZstd,
+                            None,
+                        )
+                    } else {
+                        println!(
+                            "Creating a version {} snapshot of slot {}",
+                            snapshot_version,
+                            bank.slot(),
+                        );
+                        snapshot_utils::bank_to_snapshot_archive(
+                            ledger_path,
+                            &bank,
+                            Some(snapshot_version),
+                            output_directory,
+                            ArchiveFormat::TarZstd,
+                            None,
+                        )
+                    }
                     .unwrap_or_else(|err| {
                         eprintln!("Unable to create snapshot: {}", err);
                         exit(1);
                     });
 
                     println!(
-                        "Successfully created snapshot for slot {}, hash {}: {}",
+                        "Successfully created {}snapshot for slot {}, hash {}: {}",
+                        if is_incremental { "incremental " } else { "" },
                         bank.slot(),
                         bank.hash(),
                         archive_file.display(),
@@ -2854,6 +2935,12 @@ fn main() {
             ));
             println!("Ok.");
         }
+        ("column-sizes", _) => {
+            print_column_sizes(&open_database(
+                &ledger_path,
+                AccessType::TryPrimaryThenSecondary,
+            ));
+        }
         ("", _) => {
             eprintln!("{}", matches.usage());
             exit(1);
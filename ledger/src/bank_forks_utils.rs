@@ -52,6 +52,17 @@ pub fn load(
         ) {
             Some((archive_filename, (archive_slot, archive_snapshot_hash, compression))) => {
                 info!("Loading snapshot package: {:?}", archive_filename);
+                let incremental_snapshot_archive =
+                    snapshot_utils::get_highest_incremental_snapshot_archive_path(
+                        &snapshot_config.snapshot_package_output_path,
+                        archive_slot,
+                    );
+                if let Some((incremental_archive_filename, _)) = &incremental_snapshot_archive {
+                    info!(
+                        "Loading incremental snapshot package: {:?}",
+                        incremental_archive_filename
+                    );
+                }
                 // Fail hard here if snapshot fails to load, don't silently continue
 
                 if account_paths.is_empty() {
@@ -59,32 +70,47 @@ pub fn load(
                     process::exit(1);
                 }
 
-                let deserialized_bank = snapshot_utils::bank_from_archive(
+                let deserialized_bank = snapshot_utils::bank_from_snapshot_archives(
                     &account_paths,
                     &process_options.frozen_accounts,
                     &snapshot_config.snapshot_path,
-                    &archive_filename,
-                    compression,
+                    (&archive_filename, compression),
+                    incremental_snapshot_archive.as_ref().map(
+                        |(incremental_archive_filename, (_, _, _, incremental_compression))| {
+                            (
+                                incremental_archive_filename.as_path(),
+                                *incremental_compression,
+                            )
+                        },
+                    ),
                     genesis_config,
                     process_options.debug_keys.clone(),
                     Some(&crate::builtins::get(process_options.bpf_jit)),
                     process_options.account_indexes.clone(),
                     process_options.accounts_db_caching_enabled,
+                    process_options.accounts_index_config.clone(),
                 )
                 .expect("Load from snapshot failed");
                 if let Some(shrink_paths) = shrink_paths {
                     deserialized_bank.set_shrink_paths(shrink_paths);
                 }
+                if let Some(accounts_update_notifier) = &process_options.accounts_update_notifier {
+                    deserialized_bank
+                        .set_accounts_update_notifier(accounts_update_notifier.clone());
+                }
 
                 let deserialized_snapshot_hash = (
                     deserialized_bank.slot(),
                     deserialized_bank.get_accounts_hash(),
                 );
 
-                if deserialized_snapshot_hash != (archive_slot, archive_snapshot_hash) {
+                let expected_snapshot_hash = incremental_snapshot_archive
+                    .map(|(_, (_, slot, hash, _))| (slot, hash))
+                    .unwrap_or((archive_slot, archive_snapshot_hash));
+                if deserialized_snapshot_hash != expected_snapshot_hash {
                     error!(
                         "Snapshot has mismatch:\narchive: {:?}\ndeserialized: {:?}",
-                        archive_snapshot_hash, deserialized_snapshot_hash
+                        expected_snapshot_hash, deserialized_snapshot_hash
                     );
                     process::exit(1);
                 }
@@ -97,7 +123,8 @@ pub fn load(
                         &VerifyRecyclers::default(),
                         transaction_status_sender,
                     ),
-                    Some(deserialized_snapshot_hash),
+                    // Only full snapshots are advertised to the cluster
+                    Some((archive_slot, archive_snapshot_hash)),
                 );
             }
             None => info!("No snapshot package available"),
@@ -4,8 +4,8 @@
 pub use crate::{blockstore_db::BlockstoreError, blockstore_meta::SlotMeta};
 use crate::{
     blockstore_db::{
-        columns as cf, AccessType, BlockstoreRecoveryMode, Column, Database, IteratorDirection,
-        IteratorMode, LedgerColumn, Result, WriteBatch,
+        columns as cf, AccessType, BackendType, BlockstoreOptions, BlockstoreRecoveryMode, Column,
+        Database, IteratorDirection, IteratorMode, LedgerColumn, Result, WriteBatch,
     },
     blockstore_meta::*,
     entry::{create_ticks, Entry},
@@ -20,7 +20,6 @@ use rayon::{
     iter::{IntoParallelRefIterator, ParallelIterator},
     ThreadPool,
 };
-use rocksdb::DBRawIterator;
 use solana_measure::measure::Measure;
 use solana_metrics::{datapoint_debug, datapoint_error};
 use solana_rayon_threadlimit::get_thread_count;
@@ -38,7 +37,7 @@ use solana_sdk::{
 use solana_storage_proto::{StoredExtendedRewards, StoredTransactionStatusMeta};
 use solana_transaction_status::{
     ConfirmedBlock, ConfirmedTransaction, ConfirmedTransactionStatusWithSignature, Rewards,
-    TransactionStatusMeta, TransactionWithStatusMeta,
+    SignaturesForAddressFilter, TransactionStatusMeta, TransactionWithStatusMeta,
 };
 use std::{
     cell::RefCell,
@@ -251,7 +250,18 @@ impl Blockstore {
 
     /// Opens a Ledger in directory, provides "infinite" window of shreds
     pub fn open(ledger_path: &Path) -> Result<Blockstore> {
-        Self::do_open(ledger_path, AccessType::PrimaryOnly, None, true)
+        Self::open_with_options(ledger_path, BlockstoreOptions::default())
+    }
+
+    /// Opens a Ledger in directory, with its columns stored by `backend_type`
+    pub fn open_with_backend(ledger_path: &Path, backend_type: BackendType) -> Result<Blockstore> {
+        Self::open_with_options(
+            ledger_path,
+            BlockstoreOptions {
+                backend_type,
+                ..BlockstoreOptions::default()
+            },
+        )
     }
 
     pub fn open_with_access_type(
@@ -260,29 +270,27 @@ impl Blockstore {
         recovery_mode: Option<BlockstoreRecoveryMode>,
         enforce_ulimit_nofile: bool,
     ) -> Result<Blockstore> {
-        Self::do_open(
+        Self::open_with_options(
             ledger_path,
-            access_type,
-            recovery_mode,
-            enforce_ulimit_nofile,
+            BlockstoreOptions {
+                access_type,
+                recovery_mode,
+                enforce_ulimit_nofile,
+                ..BlockstoreOptions::default()
+            },
         )
     }
 
-    fn do_open(
-        ledger_path: &Path,
-        access_type: AccessType,
-        recovery_mode: Option<BlockstoreRecoveryMode>,
-        enforce_ulimit_nofile: bool,
-    ) -> Result<Blockstore> {
+    pub fn open_with_options(ledger_path: &Path, options: BlockstoreOptions) -> Result<Blockstore> {
         fs::create_dir_all(&ledger_path)?;
         let blockstore_path = ledger_path.join(BLOCKSTORE_DIRECTORY);
 
-        adjust_ulimit_nofile(enforce_ulimit_nofile)?;
+        adjust_ulimit_nofile(options.enforce_ulimit_nofile)?;
 
         // Open the database
         let mut measure = Measure::start("open");
         info!("Opening database at {:?}", blockstore_path);
-        let db = Database::open(&blockstore_path, access_type, recovery_mode)?;
+        let db = Database::open(&blockstore_path, &options)?;
 
         // Create the metadata column family
         let meta_cf = db.column();
@@ -371,12 +379,21 @@ impl Blockstore {
         recovery_mode: Option<BlockstoreRecoveryMode>,
         enforce_ulimit_nofile: bool,
     ) -> Result<BlockstoreSignals> {
-        let mut blockstore = Self::open_with_access_type(
+        Self::open_with_options_and_signal(
             ledger_path,
-            AccessType::PrimaryOnly,
-            recovery_mode,
-            enforce_ulimit_nofile,
-        )?;
+            BlockstoreOptions {
+                recovery_mode,
+                enforce_ulimit_nofile,
+                ..BlockstoreOptions::default()
+            },
+        )
+    }
+
+    pub fn open_with_options_and_signal(
+        ledger_path: &Path,
+        options: BlockstoreOptions,
+    ) -> Result<BlockstoreSignals> {
+        let mut blockstore = Self::open_with_options(ledger_path, options)?;
         let (ledger_signal_sender, ledger_signal_receiver) = sync_channel(1);
         let (completed_slots_sender1, completed_slots_receiver1) =
             sync_channel(MAX_COMPLETED_SLOTS_IN_CHANNEL);
@@ -1570,7 +1587,7 @@ impl Blockstore {
     // indexes in the ledger in the range [start_index, end_index)
     // for the slot with the specified slot
     fn find_missing_indexes<C>(
-        db_iterator: &mut DBRawIterator,
+        mut db_iterator: impl Iterator<Item = (C::Index, Box<[u8]>)>,
         slot: Slot,
         first_timestamp: u64,
         start_index: u64,
@@ -1588,22 +1605,21 @@ impl Blockstore {
         let ticks_since_first_insert =
             DEFAULT_TICKS_PER_SECOND * (timestamp() - first_timestamp) / 1000;
 
-        // Seek to the first shred with index >= start_index
-        db_iterator.seek(&C::key((slot, start_index)));
-
         // The index of the first missing shred in the slot
         let mut prev_index = start_index;
         'outer: loop {
-            if !db_iterator.valid() {
-                for i in prev_index..end_index {
-                    missing_indexes.push(i);
-                    if missing_indexes.len() == max_missing {
-                        break;
+            let ((current_slot, index), value) = match db_iterator.next() {
+                Some(entry) => entry,
+                None => {
+                    for i in prev_index..end_index {
+                        missing_indexes.push(i);
+                        if missing_indexes.len() == max_missing {
+                            break;
+                        }
                     }
+                    break;
                 }
-                break;
-            }
-            let (current_slot, index) = C::index(&db_iterator.key().expect("Expect a valid key"));
+            };
 
             let current_index = {
                 if current_slot > slot {
@@ -1615,9 +1631,7 @@ impl Blockstore {
 
             let upper_index = cmp::min(current_index, end_index);
             // the tick that will be used to figure out the timeout for this hole
-            let reference_tick = u64::from(Shred::reference_tick_from_data(
-                &db_iterator.value().expect("couldn't read value"),
-            ));
+            let reference_tick = u64::from(Shred::reference_tick_from_data(&value));
 
             if ticks_since_first_insert < reference_tick + MAX_TURBINE_DELAY_IN_TICKS {
                 // The higher index holes have not timed out yet
@@ -1639,7 +1653,6 @@ impl Blockstore {
             }
 
             prev_index = current_index + 1;
-            db_iterator.next();
         }
 
         missing_indexes
@@ -1653,12 +1666,13 @@ impl Blockstore {
         end_index: u64,
         max_missing: usize,
     ) -> Vec<u64> {
-        if let Ok(mut db_iterator) = self
-            .db
-            .raw_iterator_cf(self.db.cf_handle::<cf::ShredData>())
-        {
+        // Start from the first shred with index >= start_index
+        if let Ok(db_iterator) = self.data_shred_cf.iter(IteratorMode::From(
+            (slot, start_index),
+            IteratorDirection::Forward,
+        )) {
             Self::find_missing_indexes::<cf::ShredData>(
-                &mut db_iterator,
+                db_iterator,
                 slot,
                 first_timestamp,
                 start_index,
@@ -1705,74 +1719,110 @@ impl Blockstore {
             "blockstore-rpc-api",
             ("method", "get_confirmed_block".to_string(), String)
         );
+        self.get_block(slot, require_previous_blockhash, true)
+    }
+
+    /// Returns the block for `slot` once all of its shreds have been received, whether or not the
+    /// slot has been rooted yet. Transaction statuses are included if they have already been
+    /// written by the `TransactionStatusService`.
+    pub fn get_complete_block(
+        &self,
+        slot: Slot,
+        require_previous_blockhash: bool,
+    ) -> Result<ConfirmedBlock> {
+        datapoint_info!(
+            "blockstore-rpc-api",
+            ("method", "get_complete_block".to_string(), String)
+        );
+        self.get_block(slot, require_previous_blockhash, false)
+    }
+
+    fn get_block(
+        &self,
+        slot: Slot,
+        require_previous_blockhash: bool,
+        require_root: bool,
+    ) -> Result<ConfirmedBlock> {
         let lowest_cleanup_slot = self.lowest_cleanup_slot.read().unwrap();
         // lowest_cleanup_slot is the last slot that was not cleaned up by
         // LedgerCleanupService
         if *lowest_cleanup_slot > 0 && *lowest_cleanup_slot >= slot {
             return Err(BlockstoreError::SlotCleanedUp);
         }
-        if self.is_root(slot) {
-            let slot_meta_cf = self.db.column::<cf::SlotMeta>();
-            let slot_meta = match slot_meta_cf.get(slot)? {
-                Some(slot_meta) => slot_meta,
-                None => {
-                    info!("SlotMeta not found for rooted slot {}", slot);
-                    return Err(BlockstoreError::SlotCleanedUp);
-                }
-            };
+        if require_root && !self.is_root(slot) {
+            return Err(BlockstoreError::SlotNotRooted);
+        }
+        let slot_meta_cf = self.db.column::<cf::SlotMeta>();
+        let slot_meta = match slot_meta_cf.get(slot)? {
+            Some(slot_meta) => slot_meta,
+            None if require_root => {
+                info!("SlotMeta not found for rooted slot {}", slot);
+                return Err(BlockstoreError::SlotCleanedUp);
+            }
+            None => return Err(BlockstoreError::SlotUnavailable),
+        };
+        if !require_root && !slot_meta.is_full() {
+            return Err(BlockstoreError::SlotUnavailable);
+        }
 
-            let slot_entries = self.get_slot_entries(slot, 0)?;
-            if !slot_entries.is_empty() {
-                let slot_transaction_iterator = slot_entries
-                    .iter()
-                    .cloned()
-                    .flat_map(|entry| entry.transactions)
-                    .map(|transaction| {
-                        if let Err(err) = transaction.sanitize() {
-                            warn!(
-                                "Blockstore::get_confirmed_block sanitize failed: {:?}, \
-                                slot: {:?}, \
-                                {:?}",
-                                err, slot, transaction,
-                            );
-                        }
-                        transaction
-                    });
-                let parent_slot_entries = self
-                    .get_slot_entries(slot_meta.parent_slot, 0)
-                    .unwrap_or_default();
-                if parent_slot_entries.is_empty() && require_previous_blockhash {
-                    return Err(BlockstoreError::ParentEntriesUnavailable);
-                }
-                let previous_blockhash = if !parent_slot_entries.is_empty() {
-                    get_last_hash(parent_slot_entries.iter()).unwrap()
-                } else {
-                    Hash::default()
-                };
+        let slot_entries = self.get_slot_entries(slot, 0)?;
+        if !slot_entries.is_empty() {
+            let slot_transactions = slot_entries
+                .iter()
+                .cloned()
+                .flat_map(|entry| entry.transactions)
+                .map(|transaction| {
+                    if let Err(err) = transaction.sanitize() {
+                        warn!(
+                            "Blockstore::get_confirmed_block sanitize failed: {:?}, \
+                            slot: {:?}, \
+                            {:?}",
+                            err, slot, transaction,
+                        );
+                    }
+                    transaction
+                        .into_legacy_transaction()
+                        .ok_or(BlockstoreError::UnsupportedTransactionVersion)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let parent_slot_entries = self
+                .get_slot_entries(slot_meta.parent_slot, 0)
+                .unwrap_or_default();
+            if parent_slot_entries.is_empty() && require_previous_blockhash {
+                return Err(BlockstoreError::ParentEntriesUnavailable);
+            }
+            let previous_blockhash = if !parent_slot_entries.is_empty() {
+                get_last_hash(parent_slot_entries.iter()).unwrap()
+            } else {
+                Hash::default()
+            };
 
-                let blockhash = get_last_hash(slot_entries.iter())
-                    .unwrap_or_else(|| panic!("Rooted slot {:?} must have blockhash", slot));
+            let blockhash = get_last_hash(slot_entries.iter())
+                .unwrap_or_else(|| panic!("Rooted slot {:?} must have blockhash", slot));
 
-                let rewards = self
-                    .rewards_cf
-                    .get_protobuf_or_bincode::<StoredExtendedRewards>(slot)?
-                    .unwrap_or_default()
-                    .into();
-                let block_time = self.blocktime_cf.get(slot)?;
-
-                let block = ConfirmedBlock {
-                    previous_blockhash: previous_blockhash.to_string(),
-                    blockhash: blockhash.to_string(),
-                    parent_slot: slot_meta.parent_slot,
-                    transactions: self
-                        .map_transactions_to_statuses(slot, slot_transaction_iterator),
-                    rewards,
-                    block_time,
-                };
-                return Ok(block);
-            }
+            let rewards = self
+                .rewards_cf
+                .get_protobuf_or_bincode::<StoredExtendedRewards>(slot)?
+                .unwrap_or_default()
+                .into();
+            let block_time = self.blocktime_cf.get(slot)?;
+
+            let block = ConfirmedBlock {
+                previous_blockhash: previous_blockhash.to_string(),
+                blockhash: blockhash.to_string(),
+                parent_slot: slot_meta.parent_slot,
+                transactions: self
+                    .map_transactions_to_statuses(slot, slot_transactions.into_iter()),
+                rewards,
+                block_time,
+            };
+            return Ok(block);
+        }
+        if require_root {
+            Err(BlockstoreError::SlotNotRooted)
+        } else {
+            Err(BlockstoreError::SlotUnavailable)
         }
-        Err(BlockstoreError::SlotNotRooted)
     }
 
     fn map_transactions_to_statuses<'a>(
@@ -2002,7 +2052,7 @@ impl Blockstore {
         signature: Signature,
     ) -> Result<Option<Transaction>> {
         let slot_entries = self.get_slot_entries(slot, 0)?;
-        Ok(slot_entries
+        slot_entries
             .iter()
             .cloned()
             .flat_map(|entry| entry.transactions)
@@ -2017,7 +2067,35 @@ impl Blockstore {
                 }
                 transaction
             })
-            .find(|transaction| transaction.signatures[0] == signature))
+            .find(|transaction| transaction.signatures[0] == signature)
+            .map(|transaction| {
+                transaction
+                    .into_legacy_transaction()
+                    .ok_or(BlockstoreError::UnsupportedTransactionVersion)
+            })
+            .transpose()
+    }
+
+    // Returns the first signature of every transaction in a rooted slot, sorted in descending
+    // order.  Sorting gives a stable ordering within a slot, as the AddressSignatures column is
+    // ordered by signatures within a slot, not by block ordering
+    fn get_sorted_block_signatures(&self, slot: Slot) -> Result<Vec<Signature>> {
+        let lowest_cleanup_slot = self.lowest_cleanup_slot.read().unwrap();
+        if *lowest_cleanup_slot > 0 && *lowest_cleanup_slot >= slot {
+            return Err(BlockstoreError::SlotCleanedUp);
+        }
+        if !self.is_root(slot) {
+            return Err(BlockstoreError::SlotNotRooted);
+        }
+        let mut slot_signatures: Vec<_> = self
+            .get_slot_entries(slot, 0)?
+            .into_iter()
+            .flat_map(|entry| entry.transactions)
+            .filter_map(|transaction| transaction.signatures.into_iter().next())
+            .collect();
+        slot_signatures.sort();
+        slot_signatures.reverse();
+        Ok(slot_signatures)
     }
 
     // Returns all cached signatures for an address, ordered by slot that the transaction was
@@ -2072,6 +2150,21 @@ impl Blockstore {
             .map(|signatures| signatures.iter().map(|(_, signature)| *signature).collect())
     }
 
+    // Check the status of a listed signature against the status filter of an address signatures
+    // query, without looking up the status if there is no filter
+    fn signature_matches_filter(
+        &self,
+        signature: Signature,
+        filter: &SignaturesForAddressFilter,
+    ) -> Result<bool> {
+        if filter.status.is_none() {
+            return Ok(true);
+        }
+        let (transaction_status, _) = self.get_transaction_status_with_counter(signature)?;
+        let err = transaction_status.and_then(|(_slot, status)| status.status.err());
+        Ok(filter.matches_status(&err))
+    }
+
     pub fn get_confirmed_signatures_for_address2(
         &self,
         address: Pubkey,
@@ -2079,6 +2172,7 @@ impl Blockstore {
         before: Option<Signature>,
         until: Option<Signature>,
         limit: usize,
+        filter: SignaturesForAddressFilter,
     ) -> Result<Vec<ConfirmedTransactionStatusWithSignature>> {
         datapoint_info!(
             "blockstore-rpc-api",
@@ -2090,43 +2184,28 @@ impl Blockstore {
         );
 
         // Figure the `slot` to start listing signatures at, based on the ledger location of the
-        // `before` signature if present.  Also generate a HashSet of signatures that should
-        // be excluded from the results.
+        // `before` signature if present, or the `max_slot` bound if that is lower.  Also
+        // generate a HashSet of signatures that should be excluded from the results.
         let mut get_before_slot_timer = Measure::start("get_before_slot_timer");
-        let (slot, mut before_excluded_signatures) = match before {
-            None => (highest_confirmed_root, None),
+        let max_slot = filter.max_slot.map_or(highest_confirmed_root, |max_slot| {
+            max_slot.min(highest_confirmed_root)
+        });
+        let (slot, before_excluded_signatures) = match before {
+            None => (max_slot, None),
             Some(before) => {
                 let transaction_status = self.get_transaction_status(before)?;
                 match transaction_status {
                     None => return Ok(vec![]),
+                    Some((slot, _)) if slot > max_slot => (max_slot, None),
                     Some((slot, _)) => {
-                        let confirmed_block =
-                            self.get_confirmed_block(slot, false).map_err(|err| {
+                        let mut slot_signatures =
+                            self.get_sorted_block_signatures(slot).map_err(|err| {
                                 BlockstoreError::Io(IoError::new(
                                     ErrorKind::Other,
                                     format!("Unable to get confirmed block: {}", err),
                                 ))
                             })?;
 
-                        // Load all signatures for the block
-                        let mut slot_signatures: Vec<_> = confirmed_block
-                            .transactions
-                            .into_iter()
-                            .filter_map(|transaction_with_meta| {
-                                transaction_with_meta
-                                    .transaction
-                                    .signatures
-                                    .into_iter()
-                                    .next()
-                            })
-                            .collect();
-
-                        // Sort signatures as a way to entire a stable ordering within a slot, as
-                        // the AddressSignatures column is ordered by signatures within a slot,
-                        // not by block ordering
-                        slot_signatures.sort();
-                        slot_signatures.reverse();
-
                         if let Some(pos) = slot_signatures.iter().position(|&x| x == before) {
                             slot_signatures.truncate(pos + 1);
                         }
@@ -2151,33 +2230,14 @@ impl Blockstore {
                 match transaction_status {
                     None => (0, HashSet::new()),
                     Some((slot, _)) => {
-                        let confirmed_block =
-                            self.get_confirmed_block(slot, false).map_err(|err| {
+                        let mut slot_signatures =
+                            self.get_sorted_block_signatures(slot).map_err(|err| {
                                 BlockstoreError::Io(IoError::new(
                                     ErrorKind::Other,
                                     format!("Unable to get confirmed block: {}", err),
                                 ))
                             })?;
 
-                        // Load all signatures for the block
-                        let mut slot_signatures: Vec<_> = confirmed_block
-                            .transactions
-                            .into_iter()
-                            .filter_map(|transaction_with_meta| {
-                                transaction_with_meta
-                                    .transaction
-                                    .signatures
-                                    .into_iter()
-                                    .next()
-                            })
-                            .collect();
-
-                        // Sort signatures as a way to entire a stable ordering within a slot, as
-                        // the AddressSignatures column is ordered by signatures within a slot,
-                        // not by block ordering
-                        slot_signatures.sort();
-                        slot_signatures.reverse();
-
                         if let Some(pos) = slot_signatures.iter().position(|&x| x == until) {
                             slot_signatures = slot_signatures.split_off(pos);
                         }
@@ -2189,22 +2249,31 @@ impl Blockstore {
         };
         get_until_slot_timer.stop();
 
+        // The `min_slot` bound stops the listing early if it is above the `until` signature
+        let lowest_slot = filter
+            .min_slot
+            .map_or(lowest_slot, |min_slot| min_slot.max(lowest_slot));
+        if slot < lowest_slot {
+            return Ok(vec![]);
+        }
+
         // Fetch the list of signatures that affect the given address
         let first_available_block = self.get_first_available_block()?;
         let mut address_signatures = vec![];
 
-        // Get signatures in `slot`
+        // Get signatures in `slot`, which may be an abandoned fork if it was clamped to `max_slot`
         let mut get_initial_slot_timer = Measure::start("get_initial_slot_timer");
-        let mut signatures = self.find_address_signatures(address, slot, slot)?;
-        signatures.reverse();
-        if let Some(excluded_signatures) = before_excluded_signatures.take() {
-            address_signatures.extend(
-                signatures
-                    .into_iter()
-                    .filter(|(_, signature)| !excluded_signatures.contains(&signature)),
-            )
-        } else {
-            address_signatures.append(&mut signatures);
+        if slot == highest_confirmed_root || self.is_root(slot) {
+            let mut signatures = self.find_address_signatures(address, slot, slot)?;
+            signatures.reverse();
+            let excluded_signatures = before_excluded_signatures.unwrap_or_default();
+            for (slot, signature) in signatures {
+                if !excluded_signatures.contains(&signature)
+                    && self.signature_matches_filter(signature, &filter)?
+                {
+                    address_signatures.push((slot, signature));
+                }
+            }
         }
         get_initial_slot_timer.stop();
 
@@ -2235,7 +2304,9 @@ impl Blockstore {
                         && key_address == address
                         && slot >= first_available_block
                     {
-                        if self.is_root(slot) {
+                        if self.is_root(slot)
+                            && self.signature_matches_filter(signature, &filter)?
+                        {
                             address_signatures.push((slot, signature));
                         }
                         continue;
@@ -2249,7 +2320,11 @@ impl Blockstore {
                 let mut signatures =
                     self.find_address_signatures(address, next_max_slot, next_max_slot)?;
                 signatures.reverse();
-                address_signatures.append(&mut signatures);
+                for (slot, signature) in signatures {
+                    if self.signature_matches_filter(signature, &filter)? {
+                        address_signatures.push((slot, signature));
+                    }
+                }
             }
         }
         starting_primary_index_iter_timer.stop();
@@ -2273,7 +2348,7 @@ impl Blockstore {
                     && key_address == address
                     && slot >= first_available_block
                 {
-                    if self.is_root(slot) {
+                    if self.is_root(slot) && self.signature_matches_filter(signature, &filter)? {
                         address_signatures.push((slot, signature));
                     }
                     continue;
@@ -2726,6 +2801,13 @@ impl Blockstore {
         Ok(dead_slots_iterator.map(|(slot, _)| slot))
     }
 
+    pub fn duplicate_slots_iterator(&self, slot: Slot) -> Result<impl Iterator<Item = Slot> + '_> {
+        let duplicate_slots_iterator = self
+            .db
+            .iter::<cf::DuplicateSlots>(IteratorMode::From(slot, IteratorDirection::Forward))?;
+        Ok(duplicate_slots_iterator.map(|(slot, _)| slot))
+    }
+
     pub fn last_root(&self) -> Slot {
         *self.last_root.read().unwrap()
     }
@@ -3556,7 +3638,9 @@ pub mod tests {
         transaction::TransactionError,
     };
     use solana_storage_proto::convert::generated;
-    use solana_transaction_status::{InnerInstructions, Reward, Rewards, TransactionTokenBalance};
+    use solana_transaction_status::{
+        InnerInstructions, Reward, Rewards, TransactionStatusFilter, TransactionTokenBalance,
+    };
     use std::time::Duration;
 
     // used for tests only
@@ -3774,6 +3858,34 @@ pub mod tests {
         Blockstore::destroy(&ledger_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_open_with_memory_backend() {
+        let ledger_path = get_tmp_ledger_path!();
+        let blockstore = Blockstore::open_with_backend(&ledger_path, BackendType::Memory).unwrap();
+        assert!(!ledger_path.join(BLOCKSTORE_DIRECTORY).exists());
+
+        let num_entries = 10;
+        for slot in 1..4 {
+            let (shreds, entries) = make_slot_entries(slot, slot - 1, num_entries);
+            blockstore.insert_shreds(shreds, None, false).unwrap();
+            assert_eq!(blockstore.get_slot_entries(slot, 0).unwrap(), entries);
+        }
+        assert_eq!(blockstore.meta(3).unwrap().unwrap().parent_slot, 2);
+        assert!(blockstore.storage_size().unwrap() > 0);
+
+        blockstore.purge_and_compact_slots(0, 2);
+        assert!(blockstore.meta(2).unwrap().is_none());
+        assert!(blockstore.get_slot_entries(2, 0).unwrap().is_empty());
+        assert!(blockstore.meta(3).unwrap().is_some());
+        assert_eq!(
+            blockstore.slot_data_iterator(3, 0).unwrap().count(),
+            blockstore.meta(3).unwrap().unwrap().received as usize
+        );
+
+        drop(blockstore);
+        Blockstore::destroy(&ledger_path).expect("Expected successful database destruction");
+    }
+
     #[test]
     fn test_read_shred_bytes() {
         let slot = 0;
@@ -5713,6 +5825,7 @@ pub mod tests {
             .cloned()
             .filter(|entry| !entry.is_tick())
             .flat_map(|entry| entry.transactions)
+            .map(|transaction| transaction.into_legacy_transaction().unwrap())
             .map(|transaction| {
                 let mut pre_balances: Vec<u64> = vec![];
                 let mut post_balances: Vec<u64> = vec![];
@@ -5820,6 +5933,49 @@ pub mod tests {
         Blockstore::destroy(&ledger_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_get_complete_block() {
+        let slot = 10;
+        let entries = make_slot_entries_with_transactions(100);
+        let blockhash = get_last_hash(entries.iter()).unwrap();
+        let shreds = entries_to_test_shreds(entries.clone(), slot, slot - 1, true, 0);
+        let ledger_path = get_tmp_ledger_path!();
+        let ledger = Blockstore::open(&ledger_path).unwrap();
+
+        // Unknown slot
+        assert_matches!(
+            ledger.get_complete_block(slot, false).unwrap_err(),
+            BlockstoreError::SlotUnavailable
+        );
+
+        // Incomplete slot
+        let (first_shred, remaining_shreds) = shreds.split_at(1);
+        ledger
+            .insert_shreds(first_shred.to_vec(), None, false)
+            .unwrap();
+        assert_matches!(
+            ledger.get_complete_block(slot, false).unwrap_err(),
+            BlockstoreError::SlotUnavailable
+        );
+
+        // A full slot is available before it is rooted
+        ledger
+            .insert_shreds(remaining_shreds.to_vec(), None, false)
+            .unwrap();
+        assert_matches!(
+            ledger.get_confirmed_block(slot, false).unwrap_err(),
+            BlockstoreError::SlotNotRooted
+        );
+        let block = ledger.get_complete_block(slot, false).unwrap();
+        assert_eq!(block.parent_slot, slot - 1);
+        assert_eq!(block.blockhash, blockhash.to_string());
+        assert_eq!(block.transactions.len(), 100);
+        assert!(block.transactions.iter().all(|tx| tx.meta.is_none()));
+
+        drop(ledger);
+        Blockstore::destroy(&ledger_path).expect("Expected successful database destruction");
+    }
+
     #[test]
     fn test_persist_transaction_status() {
         let blockstore_path = get_tmp_ledger_path!();
@@ -6293,6 +6449,7 @@ pub mod tests {
             .cloned()
             .filter(|entry| !entry.is_tick())
             .flat_map(|entry| entry.transactions)
+            .map(|transaction| transaction.into_legacy_transaction().unwrap())
             .map(|transaction| {
                 let mut pre_balances: Vec<u64> = vec![];
                 let mut post_balances: Vec<u64> = vec![];
@@ -6559,7 +6716,7 @@ pub mod tests {
                             .write_transaction_status(
                                 slot,
                                 transaction.signatures[0],
-                                transaction.message.account_keys.iter().collect(),
+                                transaction.message.static_account_keys().iter().collect(),
                                 vec![],
                                 TransactionStatusMeta::default(),
                             )
@@ -6579,6 +6736,7 @@ pub mod tests {
                     None,
                     None,
                     usize::MAX,
+                    SignaturesForAddressFilter::default(),
                 )
                 .unwrap();
             assert_eq!(all0.len(), 12);
@@ -6591,6 +6749,7 @@ pub mod tests {
                     None,
                     None,
                     usize::MAX,
+                    SignaturesForAddressFilter::default(),
                 )
                 .unwrap();
             assert_eq!(all1.len(), 12);
@@ -6610,6 +6769,7 @@ pub mod tests {
                         },
                         None,
                         1,
+                        SignaturesForAddressFilter::default(),
                     )
                     .unwrap();
                 assert_eq!(results.len(), 1);
@@ -6632,6 +6792,7 @@ pub mod tests {
                             Some(all0[i + 1].signature)
                         },
                         10,
+                        SignaturesForAddressFilter::default(),
                     )
                     .unwrap();
                 assert_eq!(results.len(), 1);
@@ -6645,6 +6806,7 @@ pub mod tests {
                     Some(all0[all0.len() - 1].signature),
                     None,
                     1,
+                    SignaturesForAddressFilter::default(),
                 )
                 .unwrap()
                 .is_empty());
@@ -6656,6 +6818,7 @@ pub mod tests {
                     None,
                     Some(all0[0].signature),
                     2,
+                    SignaturesForAddressFilter::default(),
                 )
                 .unwrap()
                 .is_empty());
@@ -6674,6 +6837,7 @@ pub mod tests {
                         },
                         None,
                         3,
+                        SignaturesForAddressFilter::default(),
                     )
                     .unwrap();
                 assert_eq!(results.len(), 3);
@@ -6696,6 +6860,7 @@ pub mod tests {
                         },
                         None,
                         2,
+                        SignaturesForAddressFilter::default(),
                     )
                     .unwrap();
                 assert_eq!(results.len(), 2);
@@ -6713,6 +6878,7 @@ pub mod tests {
                     Some(all1[0].signature),
                     None,
                     usize::MAX,
+                    SignaturesForAddressFilter::default(),
                 )
                 .unwrap();
             // The exact number of results returned is variable, based on the sort order of the
@@ -6726,6 +6892,7 @@ pub mod tests {
                     Some(all1[0].signature),
                     Some(all1[4].signature),
                     usize::MAX,
+                    SignaturesForAddressFilter::default(),
                 )
                 .unwrap();
             assert!(results2.len() < results.len());
@@ -6733,6 +6900,158 @@ pub mod tests {
         Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_get_confirmed_signatures_for_address2_filter() {
+        let blockstore_path = get_tmp_ledger_path!();
+        {
+            let blockstore = Blockstore::open(&blockstore_path).unwrap();
+            let address = solana_sdk::pubkey::new_rand();
+
+            // Two transactions per slot, the first of which failed
+            for slot in 2..=6 {
+                let mut entries = vec![];
+                for _ in 0..2 {
+                    let transaction = Transaction::new_with_compiled_instructions(
+                        &[&Keypair::new()],
+                        &[address],
+                        Hash::default(),
+                        vec![solana_sdk::pubkey::new_rand()],
+                        vec![CompiledInstruction::new(1, &(), vec![0])],
+                    );
+                    entries.push(next_entry_mut(&mut Hash::default(), 0, vec![transaction]));
+                }
+                let shreds = entries_to_test_shreds(entries.clone(), slot, slot - 1, true, 0);
+                blockstore.insert_shreds(shreds, None, false).unwrap();
+
+                for (i, entry) in entries.iter().enumerate() {
+                    let transaction = &entry.transactions[0];
+                    let status = if i == 0 {
+                        Err(TransactionError::AccountNotFound)
+                    } else {
+                        Ok(())
+                    };
+                    blockstore
+                        .write_transaction_status(
+                            slot,
+                            transaction.signatures[0],
+                            transaction.message.static_account_keys().iter().collect(),
+                            vec![],
+                            TransactionStatusMeta {
+                                status,
+                                ..TransactionStatusMeta::default()
+                            },
+                        )
+                        .unwrap();
+                }
+            }
+            blockstore.set_roots(&[1, 2, 3, 4, 5, 6]).unwrap();
+            let highest_confirmed_root = 6;
+
+            let get_signatures = |before, limit, filter| {
+                blockstore
+                    .get_confirmed_signatures_for_address2(
+                        address,
+                        highest_confirmed_root,
+                        before,
+                        None,
+                        limit,
+                        filter,
+                    )
+                    .unwrap()
+            };
+
+            let all = get_signatures(None, usize::MAX, SignaturesForAddressFilter::default());
+            assert_eq!(all.len(), 10);
+
+            // Slot range
+            let results = get_signatures(
+                None,
+                usize::MAX,
+                SignaturesForAddressFilter {
+                    min_slot: Some(3),
+                    max_slot: Some(5),
+                    status: None,
+                },
+            );
+            assert_eq!(results, all[2..8].to_vec());
+
+            // A `before` signature above the slot range starts listing at `max_slot`
+            let results = get_signatures(
+                Some(all[0].signature),
+                usize::MAX,
+                SignaturesForAddressFilter {
+                    max_slot: Some(4),
+                    ..SignaturesForAddressFilter::default()
+                },
+            );
+            assert_eq!(results, all[4..].to_vec());
+
+            // Empty range
+            let results = get_signatures(
+                None,
+                usize::MAX,
+                SignaturesForAddressFilter {
+                    min_slot: Some(5),
+                    max_slot: Some(4),
+                    status: None,
+                },
+            );
+            assert!(results.is_empty());
+
+            // Status filters apply before the limit
+            let results = get_signatures(
+                None,
+                3,
+                SignaturesForAddressFilter {
+                    status: Some(TransactionStatusFilter::Failed),
+                    ..SignaturesForAddressFilter::default()
+                },
+            );
+            assert_eq!(results.len(), 3);
+            assert!(results.iter().all(|result| result.err.is_some()));
+            let results = get_signatures(
+                None,
+                usize::MAX,
+                SignaturesForAddressFilter {
+                    min_slot: Some(5),
+                    max_slot: None,
+                    status: Some(TransactionStatusFilter::Succeeded),
+                },
+            );
+            assert_eq!(results.len(), 2);
+            assert!(results
+                .iter()
+                .all(|result| result.err.is_none() && result.slot >= 5));
+
+            // A `max_slot` on an abandoned fork below the root doesn't list its signatures
+            blockstore
+                .write_transaction_status(
+                    7,
+                    Signature::new(&[7; 64]),
+                    vec![&address],
+                    vec![],
+                    TransactionStatusMeta::default(),
+                )
+                .unwrap();
+            blockstore.set_roots(&[8]).unwrap();
+            let results = blockstore
+                .get_confirmed_signatures_for_address2(
+                    address,
+                    8,
+                    None,
+                    None,
+                    usize::MAX,
+                    SignaturesForAddressFilter {
+                        max_slot: Some(7),
+                        ..SignaturesForAddressFilter::default()
+                    },
+                )
+                .unwrap();
+            assert_eq!(results, all);
+        }
+        Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
+    }
+
     #[test]
     #[allow(clippy::same_item_push)]
     fn test_get_last_hash() {
@@ -7135,6 +7454,20 @@ pub mod tests {
 
             // Slot is now marked as duplicate
             assert!(blockstore.has_duplicate_shreds_in_slot(slot));
+            assert_eq!(
+                blockstore
+                    .duplicate_slots_iterator(slot)
+                    .unwrap()
+                    .collect::<Vec<_>>(),
+                vec![slot]
+            );
+            assert_eq!(
+                blockstore
+                    .duplicate_slots_iterator(slot + 1)
+                    .unwrap()
+                    .count(),
+                0
+            );
 
             // Check ability to fetch the duplicates
             let duplicate_proof = blockstore.get_duplicate_slot(slot).unwrap();
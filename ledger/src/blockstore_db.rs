@@ -5,8 +5,8 @@ use log::*;
 use prost::Message;
 pub use rocksdb::Direction as IteratorDirection;
 use rocksdb::{
-    self, ColumnFamily, ColumnFamilyDescriptor, DBIterator, DBRawIterator, DBRecoveryMode,
-    IteratorMode as RocksIteratorMode, Options, WriteBatch as RWriteBatch, DB,
+    self, BlockBasedOptions, ColumnFamily, ColumnFamilyDescriptor, DBCompressionType,
+    DBRecoveryMode, IteratorMode as RocksIteratorMode, Options, WriteBatch as RWriteBatch, DB,
 };
 use serde::de::DeserializeOwned;
 use serde::Serialize;
@@ -17,7 +17,15 @@ use solana_sdk::{
     signature::Signature,
 };
 use solana_storage_proto::convert::generated;
-use std::{collections::HashMap, fs, marker::PhantomData, path::Path, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    marker::PhantomData,
+    ops::Bound,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
 use thiserror::Error;
 
 const MAX_WRITE_BUFFER_SIZE: u64 = 256 * 1024 * 1024; // 256MB
@@ -75,6 +83,8 @@ pub enum BlockstoreError {
     ProtobufEncodeError(#[from] prost::EncodeError),
     ProtobufDecodeError(#[from] prost::DecodeError),
     ParentEntriesUnavailable,
+    SlotUnavailable,
+    UnsupportedTransactionVersion,
 }
 pub type Result<T> = std::result::Result<T, BlockstoreError>;
 
@@ -152,6 +162,7 @@ pub mod columns {
     pub struct PerfSamples;
 }
 
+#[derive(Debug, Clone)]
 pub enum AccessType {
     PrimaryOnly,
     PrimaryOnlyForMaintenance, // this indicates no compaction
@@ -201,96 +212,224 @@ impl From<BlockstoreRecoveryMode> for DBRecoveryMode {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockstoreCompressionType {
+    None,
+    Lz4,
+    Zstd,
+}
+
+impl Default for BlockstoreCompressionType {
+    fn default() -> Self {
+        BlockstoreCompressionType::None
+    }
+}
+
+impl FromStr for BlockstoreCompressionType {
+    type Err = String;
+
+    fn from_str(string: &str) -> std::result::Result<Self, Self::Err> {
+        match string {
+            "none" => Ok(BlockstoreCompressionType::None),
+            "lz4" => Ok(BlockstoreCompressionType::Lz4),
+            "zstd" => Ok(BlockstoreCompressionType::Zstd),
+            bad_type => Err(format!("Invalid compression type: {}", bad_type)),
+        }
+    }
+}
+
+impl From<BlockstoreCompressionType> for DBCompressionType {
+    fn from(compression_type: BlockstoreCompressionType) -> Self {
+        match compression_type {
+            BlockstoreCompressionType::None => DBCompressionType::None,
+            BlockstoreCompressionType::Lz4 => DBCompressionType::Lz4,
+            BlockstoreCompressionType::Zstd => DBCompressionType::Zstd,
+        }
+    }
+}
+
+/// Storage options of a single column
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColumnOptions {
+    pub compression_type: BlockstoreCompressionType,
+    /// Size of the uncompressed data blocks, RocksDB's default if `None`
+    pub block_size: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockstoreOptions {
+    pub backend_type: BackendType,
+    pub access_type: AccessType,
+    pub recovery_mode: Option<BlockstoreRecoveryMode>,
+    pub enforce_ulimit_nofile: bool,
+    /// Storage options keyed by column name, see `column_names()`.  Only data written after
+    /// the options change is affected.
+    pub column_options: HashMap<String, ColumnOptions>,
+}
+
+impl Default for BlockstoreOptions {
+    fn default() -> Self {
+        Self {
+            backend_type: BackendType::default(),
+            access_type: AccessType::PrimaryOnly,
+            recovery_mode: None,
+            enforce_ulimit_nofile: true,
+            column_options: HashMap::new(),
+        }
+    }
+}
+
+/// Storage usage of a column
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColumnStorageStats {
+    /// Size of the table files of the column
+    pub disk_size: u64,
+    /// Size of the keys and values in the table files, before compression
+    pub raw_size: u64,
+    /// Size of the data blocks of the table files, after compression
+    pub data_blocks_size: u64,
+    /// Number of data blocks in the table files
+    pub num_data_blocks: u64,
+}
+
+impl ColumnStorageStats {
+    pub fn compression_ratio(&self) -> Option<f64> {
+        if self.data_blocks_size == 0 {
+            None
+        } else {
+            Some(self.raw_size as f64 / self.data_blocks_size as f64)
+        }
+    }
+}
+
+/// Key-value store holding the blockstore columns.  Keys and values are raw bytes, the
+/// `Column` implementations define how they are encoded.
+trait Backend: std::fmt::Debug + Send + Sync {
+    fn get_cf(&self, cf: &'static str, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    fn put_cf(&self, cf: &'static str, key: &[u8], value: &[u8]) -> Result<()>;
+
+    /// Iterate the column as it was when the iterator was created, writes made while iterating
+    /// are not seen
+    fn iterator_cf(
+        &self,
+        cf: &'static str,
+        iterator_mode: IteratorMode<Vec<u8>>,
+    ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + '_>;
+
+    /// Atomically apply all the operations of a write batch
+    fn write(&self, operations: Vec<WriteOperation>) -> Result<()>;
+
+    /// Reclaim the space of the deleted keys in `from..to`.  This never changes what is read
+    /// from the column, so a backend which drops deleted keys right away may do nothing.
+    fn compact_range_cf(&self, cf: &'static str, from: &[u8], to: &[u8]);
+
+    fn storage_size(&self) -> Result<u64>;
+
+    fn column_storage_stats(&self, cf: &'static str) -> Result<ColumnStorageStats>;
+
+    fn is_primary_access(&self) -> bool;
+}
+
+/// Which `Backend` stores the columns of a `Database`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendType {
+    RocksDb,
+    /// Keep all the columns in memory, nothing is persisted.  Meant for tests.  Deleted keys
+    /// are dropped right away, so compaction does nothing and isn't exercised by tests using
+    /// this backend.
+    Memory,
+}
+
+impl Default for BackendType {
+    fn default() -> Self {
+        BackendType::RocksDb
+    }
+}
+
 #[derive(Debug)]
-struct Rocks(rocksdb::DB, ActualAccessType);
+enum WriteOperation {
+    Put {
+        cf: &'static str,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+    Delete {
+        cf: &'static str,
+        key: Vec<u8>,
+    },
+    /// Delete the keys in [from, to)
+    DeleteRange {
+        cf: &'static str,
+        from: Vec<u8>,
+        to: Vec<u8>,
+    },
+}
 
-impl Rocks {
-    fn open(
-        path: &Path,
-        access_type: AccessType,
-        recovery_mode: Option<BlockstoreRecoveryMode>,
-    ) -> Result<Rocks> {
-        use columns::{
-            AddressSignatures, Blocktime, DeadSlots, DuplicateSlots, ErasureMeta, Index, Orphans,
-            PerfSamples, Rewards, Root, ShredCode, ShredData, SlotMeta, TransactionStatus,
-            TransactionStatusIndex,
-        };
+/// Names of all the blockstore columns
+pub fn column_names() -> Vec<&'static str> {
+    use columns::{
+        AddressSignatures, Blocktime, DeadSlots, DuplicateSlots, ErasureMeta, Index, Orphans,
+        PerfSamples, Rewards, Root, ShredCode, ShredData, SlotMeta, TransactionStatus,
+        TransactionStatusIndex,
+    };
+
+    vec![
+        ErasureMeta::NAME,
+        DeadSlots::NAME,
+        DuplicateSlots::NAME,
+        Index::NAME,
+        Orphans::NAME,
+        Root::NAME,
+        SlotMeta::NAME,
+        ShredData::NAME,
+        ShredCode::NAME,
+        TransactionStatus::NAME,
+        AddressSignatures::NAME,
+        TransactionStatusIndex::NAME,
+        Rewards::NAME,
+        Blocktime::NAME,
+        PerfSamples::NAME,
+    ]
+}
 
+#[derive(Debug)]
+struct Rocks {
+    db: rocksdb::DB,
+    access_type: ActualAccessType,
+    path: PathBuf,
+}
+
+impl Rocks {
+    fn open(path: &Path, options: &BlockstoreOptions) -> Result<Rocks> {
         fs::create_dir_all(&path)?;
 
         // Use default database options
+        let access_type = &options.access_type;
         if matches!(access_type, AccessType::PrimaryOnlyForMaintenance) {
             warn!("Disabling rocksdb's auto compaction for maintenance bulk ledger update...");
         }
-        let mut db_options = get_db_options(&access_type);
-        if let Some(recovery_mode) = recovery_mode {
+        let mut db_options = get_db_options(access_type);
+        if let Some(recovery_mode) = options.recovery_mode.clone() {
             db_options.set_wal_recovery_mode(recovery_mode.into());
         }
 
         // Column family names
-        let meta_cf_descriptor =
-            ColumnFamilyDescriptor::new(SlotMeta::NAME, get_cf_options(&access_type));
-        let dead_slots_cf_descriptor =
-            ColumnFamilyDescriptor::new(DeadSlots::NAME, get_cf_options(&access_type));
-        let duplicate_slots_cf_descriptor =
-            ColumnFamilyDescriptor::new(DuplicateSlots::NAME, get_cf_options(&access_type));
-        let erasure_meta_cf_descriptor =
-            ColumnFamilyDescriptor::new(ErasureMeta::NAME, get_cf_options(&access_type));
-        let orphans_cf_descriptor =
-            ColumnFamilyDescriptor::new(Orphans::NAME, get_cf_options(&access_type));
-        let root_cf_descriptor =
-            ColumnFamilyDescriptor::new(Root::NAME, get_cf_options(&access_type));
-        let index_cf_descriptor =
-            ColumnFamilyDescriptor::new(Index::NAME, get_cf_options(&access_type));
-        let shred_data_cf_descriptor =
-            ColumnFamilyDescriptor::new(ShredData::NAME, get_cf_options(&access_type));
-        let shred_code_cf_descriptor =
-            ColumnFamilyDescriptor::new(ShredCode::NAME, get_cf_options(&access_type));
-        let transaction_status_cf_descriptor =
-            ColumnFamilyDescriptor::new(TransactionStatus::NAME, get_cf_options(&access_type));
-        let address_signatures_cf_descriptor =
-            ColumnFamilyDescriptor::new(AddressSignatures::NAME, get_cf_options(&access_type));
-        let transaction_status_index_cf_descriptor =
-            ColumnFamilyDescriptor::new(TransactionStatusIndex::NAME, get_cf_options(&access_type));
-        let rewards_cf_descriptor =
-            ColumnFamilyDescriptor::new(Rewards::NAME, get_cf_options(&access_type));
-        let blocktime_cf_descriptor =
-            ColumnFamilyDescriptor::new(Blocktime::NAME, get_cf_options(&access_type));
-        let perf_samples_cf_descriptor =
-            ColumnFamilyDescriptor::new(PerfSamples::NAME, get_cf_options(&access_type));
-
-        let cfs = vec![
-            (SlotMeta::NAME, meta_cf_descriptor),
-            (DeadSlots::NAME, dead_slots_cf_descriptor),
-            (DuplicateSlots::NAME, duplicate_slots_cf_descriptor),
-            (ErasureMeta::NAME, erasure_meta_cf_descriptor),
-            (Orphans::NAME, orphans_cf_descriptor),
-            (Root::NAME, root_cf_descriptor),
-            (Index::NAME, index_cf_descriptor),
-            (ShredData::NAME, shred_data_cf_descriptor),
-            (ShredCode::NAME, shred_code_cf_descriptor),
-            (TransactionStatus::NAME, transaction_status_cf_descriptor),
-            (AddressSignatures::NAME, address_signatures_cf_descriptor),
-            (
-                TransactionStatusIndex::NAME,
-                transaction_status_index_cf_descriptor,
-            ),
-            (Rewards::NAME, rewards_cf_descriptor),
-            (Blocktime::NAME, blocktime_cf_descriptor),
-            (PerfSamples::NAME, perf_samples_cf_descriptor),
-        ];
+        let names = column_names();
+        let cfs = names.iter().map(|name| {
+            let column_options = options.column_options.get(*name);
+            ColumnFamilyDescriptor::new(*name, get_cf_options(access_type, column_options))
+        });
 
         // Open the database
-        let db = match access_type {
-            AccessType::PrimaryOnly | AccessType::PrimaryOnlyForMaintenance => Rocks(
-                DB::open_cf_descriptors(&db_options, path, cfs.into_iter().map(|c| c.1))?,
+        let (db, access_type) = match access_type {
+            AccessType::PrimaryOnly | AccessType::PrimaryOnlyForMaintenance => (
+                DB::open_cf_descriptors(&db_options, path, cfs)?,
                 ActualAccessType::Primary,
             ),
             AccessType::TryPrimaryThenSecondary => {
-                let names: Vec<_> = cfs.iter().map(|c| c.0).collect();
-
-                match DB::open_cf_descriptors(&db_options, path, cfs.into_iter().map(|c| c.1)) {
-                    Ok(db) => Rocks(db, ActualAccessType::Primary),
+                match DB::open_cf_descriptors(&db_options, path, cfs) {
+                    Ok(db) => (db, ActualAccessType::Primary),
                     Err(err) => {
                         let secondary_path = path.join("solana-secondary");
 
@@ -301,7 +440,7 @@ impl Rocks {
                         // This is needed according to https://github.com/facebook/rocksdb/wiki/Secondary-instance
                         db_options.set_max_open_files(-1);
 
-                        Rocks(
+                        (
                             DB::open_cf_as_secondary(&db_options, path, &secondary_path, names)?,
                             ActualAccessType::Secondary,
                         )
@@ -310,33 +449,11 @@ impl Rocks {
             }
         };
 
-        Ok(db)
-    }
-
-    fn columns(&self) -> Vec<&'static str> {
-        use columns::{
-            AddressSignatures, Blocktime, DeadSlots, DuplicateSlots, ErasureMeta, Index, Orphans,
-            PerfSamples, Rewards, Root, ShredCode, ShredData, SlotMeta, TransactionStatus,
-            TransactionStatusIndex,
-        };
-
-        vec![
-            ErasureMeta::NAME,
-            DeadSlots::NAME,
-            DuplicateSlots::NAME,
-            Index::NAME,
-            Orphans::NAME,
-            Root::NAME,
-            SlotMeta::NAME,
-            ShredData::NAME,
-            ShredCode::NAME,
-            TransactionStatus::NAME,
-            AddressSignatures::NAME,
-            TransactionStatusIndex::NAME,
-            Rewards::NAME,
-            Blocktime::NAME,
-            PerfSamples::NAME,
-        ]
+        Ok(Rocks {
+            db,
+            access_type,
+            path: path.to_path_buf(),
+        })
     }
 
     fn destroy(path: &Path) -> Result<()> {
@@ -346,52 +463,251 @@ impl Rocks {
     }
 
     fn cf_handle(&self, cf: &str) -> &ColumnFamily {
-        self.0
+        self.db
             .cf_handle(cf)
             .expect("should never get an unknown column")
     }
+}
 
-    fn get_cf(&self, cf: &ColumnFamily, key: &[u8]) -> Result<Option<Vec<u8>>> {
-        let opt = self.0.get_cf(cf, key)?.map(|db_vec| db_vec.to_vec());
+impl Backend for Rocks {
+    fn get_cf(&self, cf: &'static str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let opt = self
+            .db
+            .get_cf(self.cf_handle(cf), key)?
+            .map(|db_vec| db_vec.to_vec());
         Ok(opt)
     }
 
-    fn put_cf(&self, cf: &ColumnFamily, key: &[u8], value: &[u8]) -> Result<()> {
-        self.0.put_cf(cf, key, value)?;
+    fn put_cf(&self, cf: &'static str, key: &[u8], value: &[u8]) -> Result<()> {
+        self.db.put_cf(self.cf_handle(cf), key, value)?;
         Ok(())
     }
 
-    fn iterator_cf<C>(&self, cf: &ColumnFamily, iterator_mode: IteratorMode<C::Index>) -> DBIterator
-    where
-        C: Column,
-    {
-        let start_key;
+    fn iterator_cf(
+        &self,
+        cf: &'static str,
+        iterator_mode: IteratorMode<Vec<u8>>,
+    ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + '_> {
         let iterator_mode = match iterator_mode {
-            IteratorMode::From(start_from, direction) => {
-                start_key = C::key(start_from);
-                RocksIteratorMode::From(&start_key, direction)
+            IteratorMode::From(ref start_key, direction) => {
+                RocksIteratorMode::From(start_key, direction)
             }
             IteratorMode::Start => RocksIteratorMode::Start,
             IteratorMode::End => RocksIteratorMode::End,
         };
-        self.0.iterator_cf(cf, iterator_mode)
+        Box::new(self.db.iterator_cf(self.cf_handle(cf), iterator_mode))
     }
 
-    fn raw_iterator_cf(&self, cf: &ColumnFamily) -> DBRawIterator {
-        self.0.raw_iterator_cf(cf)
+    fn write(&self, operations: Vec<WriteOperation>) -> Result<()> {
+        let mut batch = RWriteBatch::default();
+        for operation in operations {
+            match operation {
+                WriteOperation::Put { cf, key, value } => {
+                    batch.put_cf(self.cf_handle(cf), key, value)
+                }
+                WriteOperation::Delete { cf, key } => batch.delete_cf(self.cf_handle(cf), key),
+                WriteOperation::DeleteRange { cf, from, to } => {
+                    batch.delete_range_cf(self.cf_handle(cf), from, to)
+                }
+            }
+        }
+        self.db.write(batch)?;
+        Ok(())
     }
 
-    fn batch(&self) -> RWriteBatch {
-        RWriteBatch::default()
+    fn compact_range_cf(&self, cf: &'static str, from: &[u8], to: &[u8]) {
+        self.db
+            .compact_range_cf(self.cf_handle(cf), Some(from), Some(to));
+    }
+
+    fn storage_size(&self) -> Result<u64> {
+        Ok(fs_extra::dir::get_size(&self.path)?)
     }
 
-    fn write(&self, batch: RWriteBatch) -> Result<()> {
-        self.0.write(batch)?;
+    fn column_storage_stats(&self, cf: &'static str) -> Result<ColumnStorageStats> {
+        let cf = self.cf_handle(cf);
+        let disk_size = self
+            .db
+            .property_int_value_cf(cf, "rocksdb.total-sst-files-size")?
+            .unwrap_or_default();
+        let table_properties = self
+            .db
+            .property_value_cf(cf, "rocksdb.aggregated-table-properties")?
+            .unwrap_or_default();
+        let table_property =
+            |name: &str| parse_table_property(&table_properties, name).unwrap_or_default();
+        Ok(ColumnStorageStats {
+            disk_size,
+            raw_size: table_property("raw key size") + table_property("raw value size"),
+            data_blocks_size: table_property("data block size"),
+            num_data_blocks: table_property("# data blocks"),
+        })
+    }
+
+    fn is_primary_access(&self) -> bool {
+        self.access_type == ActualAccessType::Primary
+    }
+}
+
+type MemoryColumn = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// Backend keeping every column in a sorted map, with the same key ordering as RocksDB.
+/// Iterators share the column with the backend, a write to a column being iterated copies it
+/// first.
+#[derive(Debug)]
+struct Memory {
+    columns: RwLock<HashMap<&'static str, Arc<MemoryColumn>>>,
+}
+
+impl Memory {
+    fn new() -> Self {
+        let columns = column_names()
+            .into_iter()
+            .map(|name| (name, Arc::new(MemoryColumn::new())))
+            .collect();
+        Self {
+            columns: RwLock::new(columns),
+        }
+    }
+
+    fn apply(columns: &mut HashMap<&'static str, Arc<MemoryColumn>>, operation: WriteOperation) {
+        match operation {
+            WriteOperation::Put { cf, key, value } => {
+                Self::column_mut(columns, cf).insert(key, value);
+            }
+            WriteOperation::Delete { cf, key } => {
+                Self::column_mut(columns, cf).remove(&key);
+            }
+            WriteOperation::DeleteRange { cf, from, to } => {
+                let column = Self::column_mut(columns, cf);
+                let mut deleted = column.split_off(&from);
+                let mut retained = deleted.split_off(&to);
+                column.append(&mut retained);
+            }
+        }
+    }
+
+    fn column_mut<'a>(
+        columns: &'a mut HashMap<&'static str, Arc<MemoryColumn>>,
+        cf: &str,
+    ) -> &'a mut MemoryColumn {
+        let column = columns
+            .get_mut(cf)
+            .expect("should never get an unknown column");
+        Arc::make_mut(column)
+    }
+}
+
+impl Backend for Memory {
+    fn get_cf(&self, cf: &'static str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let columns = self.columns.read().unwrap();
+        Ok(columns[cf].get(key).cloned())
+    }
+
+    fn put_cf(&self, cf: &'static str, key: &[u8], value: &[u8]) -> Result<()> {
+        let mut columns = self.columns.write().unwrap();
+        Self::column_mut(&mut columns, cf).insert(key.to_vec(), value.to_vec());
+        Ok(())
+    }
+
+    fn iterator_cf(
+        &self,
+        cf: &'static str,
+        iterator_mode: IteratorMode<Vec<u8>>,
+    ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + '_> {
+        let (position, direction) = match iterator_mode {
+            IteratorMode::Start => (Bound::Unbounded, IteratorDirection::Forward),
+            IteratorMode::End => (Bound::Unbounded, IteratorDirection::Reverse),
+            IteratorMode::From(key, direction) => (Bound::Included(key), direction),
+        };
+        let column = self.columns.read().unwrap()[cf].clone();
+        Box::new(MemoryIterator {
+            column,
+            position,
+            direction,
+        })
+    }
+
+    fn write(&self, operations: Vec<WriteOperation>) -> Result<()> {
+        let mut columns = self.columns.write().unwrap();
+        for operation in operations {
+            Self::apply(&mut columns, operation);
+        }
         Ok(())
     }
 
+    fn compact_range_cf(&self, _cf: &'static str, _from: &[u8], _to: &[u8]) {
+        // Intentionally a no-op: deleted keys are dropped right away, there is nothing to
+        // compact
+    }
+
+    fn storage_size(&self) -> Result<u64> {
+        let columns = self.columns.read().unwrap();
+        let size = columns
+            .values()
+            .flat_map(|column| column.iter())
+            .map(|(key, value)| (key.len() + value.len()) as u64)
+            .sum();
+        Ok(size)
+    }
+
+    fn column_storage_stats(&self, cf: &'static str) -> Result<ColumnStorageStats> {
+        let columns = self.columns.read().unwrap();
+        let size = columns[cf]
+            .iter()
+            .map(|(key, value)| (key.len() + value.len()) as u64)
+            .sum();
+        Ok(ColumnStorageStats {
+            disk_size: 0,
+            raw_size: size,
+            data_blocks_size: size,
+            num_data_blocks: 0,
+        })
+    }
+
     fn is_primary_access(&self) -> bool {
-        self.1 == ActualAccessType::Primary
+        true
+    }
+}
+
+/// Value of `name` in RocksDB's "name=value; name=value" formatting of table properties
+fn parse_table_property(table_properties: &str, name: &str) -> Option<u64> {
+    table_properties.split(';').find_map(|property| {
+        let mut property = property.splitn(2, '=');
+        if property.next()?.trim() == name {
+            property.next()?.trim().parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+/// Iterates a snapshot of a `Memory` column, looking up the entry after the last one returned
+/// on each step
+struct MemoryIterator {
+    column: Arc<MemoryColumn>,
+    /// Bound on the keys left to iterate, on the side the iteration starts from
+    position: Bound<Vec<u8>>,
+    direction: IteratorDirection,
+}
+
+impl Iterator for MemoryIterator {
+    type Item = (Box<[u8]>, Box<[u8]>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let position = self.position.clone();
+        let (key, value) = match self.direction {
+            IteratorDirection::Forward => self.column.range((position, Bound::Unbounded)).next()?,
+            IteratorDirection::Reverse => self
+                .column
+                .range((Bound::Unbounded, position))
+                .next_back()?,
+        };
+        self.position = Bound::Excluded(key.clone());
+        Some((
+            key.clone().into_boxed_slice(),
+            value.clone().into_boxed_slice(),
+        ))
     }
 }
 
@@ -709,8 +1025,7 @@ impl TypedColumn for columns::ErasureMeta {
 
 #[derive(Debug, Clone)]
 pub struct Database {
-    backend: Arc<Rocks>,
-    path: Arc<Path>,
+    backend: Arc<dyn Backend>,
 }
 
 #[derive(Debug, Clone)]
@@ -718,27 +1033,42 @@ pub struct LedgerColumn<C>
 where
     C: Column,
 {
-    backend: Arc<Rocks>,
+    backend: Arc<dyn Backend>,
     column: PhantomData<C>,
 }
 
-pub struct WriteBatch<'a> {
-    write_batch: RWriteBatch,
-    map: HashMap<&'static str, &'a ColumnFamily>,
+#[derive(Default)]
+pub struct WriteBatch {
+    operations: Vec<WriteOperation>,
+}
+
+fn backend_iterator_mode<C: Column>(
+    iterator_mode: IteratorMode<C::Index>,
+) -> IteratorMode<Vec<u8>> {
+    match iterator_mode {
+        IteratorMode::Start => IteratorMode::Start,
+        IteratorMode::End => IteratorMode::End,
+        IteratorMode::From(start_from, direction) => {
+            IteratorMode::From(C::key(start_from), direction)
+        }
+    }
 }
 
 impl Database {
-    pub fn open(
-        path: &Path,
-        access_type: AccessType,
-        recovery_mode: Option<BlockstoreRecoveryMode>,
-    ) -> Result<Self> {
-        let backend = Arc::new(Rocks::open(path, access_type, recovery_mode)?);
-
-        Ok(Database {
-            backend,
-            path: Arc::from(path),
-        })
+    pub fn open(path: &Path, options: &BlockstoreOptions) -> Result<Self> {
+        let backend: Arc<dyn Backend> = match options.backend_type {
+            BackendType::RocksDb => Arc::new(Rocks::open(path, options)?),
+            BackendType::Memory => Arc::new(Memory::new()),
+        };
+
+        Ok(Database { backend })
+    }
+
+    /// Open an empty database that only lives in memory
+    pub fn open_in_memory() -> Self {
+        Database {
+            backend: Arc::new(Memory::new()),
+        }
     }
 
     pub fn destroy(path: &Path) -> Result<()> {
@@ -751,7 +1081,7 @@ impl Database {
     where
         C: TypedColumn + ColumnName,
     {
-        if let Some(serialized_value) = self.backend.get_cf(self.cf_handle::<C>(), &C::key(key))? {
+        if let Some(serialized_value) = self.backend.get_cf(C::NAME, &C::key(key))? {
             let value = deserialize(&serialized_value)?;
 
             Ok(Some(value))
@@ -767,19 +1097,12 @@ impl Database {
     where
         C: Column + ColumnName,
     {
-        let cf = self.cf_handle::<C>();
-        let iter = self.backend.iterator_cf::<C>(cf, iterator_mode);
+        let iter = self
+            .backend
+            .iterator_cf(C::NAME, backend_iterator_mode::<C>(iterator_mode));
         Ok(iter.map(|(key, value)| (C::index(&key), value)))
     }
 
-    #[inline]
-    pub fn cf_handle<C: ColumnName>(&self) -> &ColumnFamily
-    where
-        C: Column + ColumnName,
-    {
-        self.backend.cf_handle(C::NAME)
-    }
-
     pub fn column<C>(&self) -> LedgerColumn<C>
     where
         C: Column + ColumnName,
@@ -790,29 +1113,23 @@ impl Database {
         }
     }
 
-    #[inline]
-    pub fn raw_iterator_cf(&self, cf: &ColumnFamily) -> Result<DBRawIterator> {
-        Ok(self.backend.raw_iterator_cf(cf))
-    }
-
     pub fn batch(&self) -> Result<WriteBatch> {
-        let write_batch = self.backend.batch();
-        let map = self
-            .backend
-            .columns()
-            .into_iter()
-            .map(|desc| (desc, self.backend.cf_handle(desc)))
-            .collect();
-
-        Ok(WriteBatch { write_batch, map })
+        Ok(WriteBatch::default())
     }
 
     pub fn write(&self, batch: WriteBatch) -> Result<()> {
-        self.backend.write(batch.write_batch)
+        self.backend.write(batch.operations)
     }
 
     pub fn storage_size(&self) -> Result<u64> {
-        Ok(fs_extra::dir::get_size(&self.path)?)
+        self.backend.storage_size()
+    }
+
+    pub fn column_storage_stats(&self) -> Result<Vec<(&'static str, ColumnStorageStats)>> {
+        column_names()
+            .into_iter()
+            .map(|name| Ok((name, self.backend.column_storage_stats(name)?)))
+            .collect()
     }
 
     // Adds a range to delete to the given write batch
@@ -820,10 +1137,9 @@ impl Database {
     where
         C: Column + ColumnName,
     {
-        let cf = self.cf_handle::<C>();
         let from_index = C::as_index(from);
         let to_index = C::as_index(to);
-        batch.delete_range_cf::<C>(cf, from_index, to_index)
+        batch.delete_range_cf::<C>(from_index, to_index)
     }
 
     pub fn is_primary_access(&self) -> bool {
@@ -836,15 +1152,16 @@ where
     C: Column + ColumnName,
 {
     pub fn get_bytes(&self, key: C::Index) -> Result<Option<Vec<u8>>> {
-        self.backend.get_cf(self.handle(), &C::key(key))
+        self.backend.get_cf(C::NAME, &C::key(key))
     }
 
     pub fn iter(
         &self,
         iterator_mode: IteratorMode<C::Index>,
     ) -> Result<impl Iterator<Item = (C::Index, Box<[u8]>)> + '_> {
-        let cf = self.handle();
-        let iter = self.backend.iterator_cf::<C>(cf, iterator_mode);
+        let iter = self
+            .backend
+            .iterator_cf(C::NAME, backend_iterator_mode::<C>(iterator_mode));
         Ok(iter.map(|(key, value)| (C::index(&key), value)))
     }
 
@@ -886,27 +1203,20 @@ where
     where
         C::Index: PartialOrd + Copy,
     {
-        let cf = self.handle();
-        let from = Some(C::key(C::as_index(from)));
-        let to = Some(C::key(C::as_index(to)));
-        self.backend.0.compact_range_cf(cf, from, to);
+        let from = C::key(C::as_index(from));
+        let to = C::key(C::as_index(to));
+        self.backend.compact_range_cf(C::NAME, &from, &to);
         Ok(true)
     }
 
-    #[inline]
-    pub fn handle(&self) -> &ColumnFamily {
-        self.backend.cf_handle(C::NAME)
-    }
-
     #[cfg(test)]
     pub fn is_empty(&self) -> Result<bool> {
-        let mut iter = self.backend.raw_iterator_cf(self.handle());
-        iter.seek_to_first();
-        Ok(!iter.valid())
+        let mut iter = self.backend.iterator_cf(C::NAME, IteratorMode::Start);
+        Ok(iter.next().is_none())
     }
 
     pub fn put_bytes(&self, key: C::Index, value: &[u8]) -> Result<()> {
-        self.backend.put_cf(self.handle(), &C::key(key), value)
+        self.backend.put_cf(C::NAME, &C::key(key), value)
     }
 }
 
@@ -915,7 +1225,7 @@ where
     C: TypedColumn + ColumnName,
 {
     pub fn get(&self, key: C::Index) -> Result<Option<C::Type>> {
-        if let Some(serialized_value) = self.backend.get_cf(self.handle(), &C::key(key))? {
+        if let Some(serialized_value) = self.backend.get_cf(C::NAME, &C::key(key))? {
             let value = deserialize(&serialized_value)?;
 
             Ok(Some(value))
@@ -928,7 +1238,7 @@ where
         let serialized_value = serialize(value)?;
 
         self.backend
-            .put_cf(self.handle(), &C::key(key), &serialized_value)
+            .put_cf(C::NAME, &C::key(key), &serialized_value)
     }
 }
 
@@ -940,7 +1250,7 @@ where
         &self,
         key: C::Index,
     ) -> Result<Option<C::Type>> {
-        if let Some(serialized_value) = self.backend.get_cf(self.handle(), &C::key(key))? {
+        if let Some(serialized_value) = self.backend.get_cf(C::NAME, &C::key(key))? {
             let value = match C::Type::decode(&serialized_value[..]) {
                 Ok(value) => value,
                 Err(_) => deserialize::<T>(&serialized_value)?.into(),
@@ -952,7 +1262,7 @@ where
     }
 
     pub fn get_protobuf(&self, key: C::Index) -> Result<Option<C::Type>> {
-        if let Some(serialized_value) = self.backend.get_cf(self.handle(), &C::key(key))? {
+        if let Some(serialized_value) = self.backend.get_cf(C::NAME, &C::key(key))? {
             Ok(Some(C::Type::decode(&serialized_value[..])?))
         } else {
             Ok(None)
@@ -962,19 +1272,25 @@ where
     pub fn put_protobuf(&self, key: C::Index, value: &C::Type) -> Result<()> {
         let mut buf = Vec::with_capacity(value.encoded_len());
         value.encode(&mut buf)?;
-        self.backend.put_cf(self.handle(), &C::key(key), &buf)
+        self.backend.put_cf(C::NAME, &C::key(key), &buf)
     }
 }
 
-impl<'a> WriteBatch<'a> {
+impl WriteBatch {
     pub fn put_bytes<C: Column + ColumnName>(&mut self, key: C::Index, bytes: &[u8]) -> Result<()> {
-        self.write_batch
-            .put_cf(self.get_cf::<C>(), &C::key(key), bytes);
+        self.operations.push(WriteOperation::Put {
+            cf: C::NAME,
+            key: C::key(key),
+            value: bytes.to_vec(),
+        });
         Ok(())
     }
 
     pub fn delete<C: Column + ColumnName>(&mut self, key: C::Index) -> Result<()> {
-        self.write_batch.delete_cf(self.get_cf::<C>(), &C::key(key));
+        self.operations.push(WriteOperation::Delete {
+            cf: C::NAME,
+            key: C::key(key),
+        });
         Ok(())
     }
 
@@ -984,29 +1300,29 @@ impl<'a> WriteBatch<'a> {
         value: &C::Type,
     ) -> Result<()> {
         let serialized_value = serialize(&value)?;
-        self.write_batch
-            .put_cf(self.get_cf::<C>(), &C::key(key), &serialized_value);
+        self.operations.push(WriteOperation::Put {
+            cf: C::NAME,
+            key: C::key(key),
+            value: serialized_value,
+        });
         Ok(())
     }
 
-    #[inline]
-    fn get_cf<C: Column + ColumnName>(&self) -> &'a ColumnFamily {
-        self.map[C::NAME]
-    }
-
-    pub fn delete_range_cf<C: Column>(
+    pub fn delete_range_cf<C: Column + ColumnName>(
         &mut self,
-        cf: &ColumnFamily,
         from: C::Index,
         to: C::Index,
     ) -> Result<()> {
-        self.write_batch
-            .delete_range_cf(cf, C::key(from), C::key(to));
+        self.operations.push(WriteOperation::DeleteRange {
+            cf: C::NAME,
+            from: C::key(from),
+            to: C::key(to),
+        });
         Ok(())
     }
 }
 
-fn get_cf_options(access_type: &AccessType) -> Options {
+fn get_cf_options(access_type: &AccessType, column_options: Option<&ColumnOptions>) -> Options {
     let mut options = Options::default();
     // 256 * 8 = 2GB. 6 of these columns should take at most 12GB of RAM
     options.set_max_write_buffer_number(8);
@@ -1023,6 +1339,14 @@ fn get_cf_options(access_type: &AccessType) -> Options {
     if matches!(access_type, AccessType::PrimaryOnlyForMaintenance) {
         options.set_disable_auto_compactions(true);
     }
+    if let Some(column_options) = column_options {
+        options.set_compression_type(column_options.compression_type.into());
+        if let Some(block_size) = column_options.block_size {
+            let mut block_based_options = BlockBasedOptions::default();
+            block_based_options.set_block_size(block_size);
+            options.set_block_based_table_factory(&block_based_options);
+        }
+    }
 
     options
 }
@@ -1051,3 +1375,180 @@ fn get_db_options(access_type: &AccessType) -> Options {
 
     options
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use columns::{ShredCode, ShredData};
+
+    fn shred_data_slots(db: &Database, iterator_mode: IteratorMode<(u64, u64)>) -> Vec<u64> {
+        db.iter::<ShredData>(iterator_mode)
+            .unwrap()
+            .map(|((slot, _), _)| slot)
+            .collect()
+    }
+
+    #[test]
+    fn test_memory_iterator_modes() {
+        let db = Database::open_in_memory();
+        let column = db.column::<ShredData>();
+        for slot in [3, 1, 4, 2].iter() {
+            column.put_bytes((*slot, 0), &[*slot as u8]).unwrap();
+        }
+        assert_eq!(shred_data_slots(&db, IteratorMode::Start), vec![1, 2, 3, 4]);
+        assert_eq!(shred_data_slots(&db, IteratorMode::End), vec![4, 3, 2, 1]);
+        assert_eq!(
+            shred_data_slots(&db, IteratorMode::From((2, 0), IteratorDirection::Forward)),
+            vec![2, 3, 4]
+        );
+        assert_eq!(
+            shred_data_slots(&db, IteratorMode::From((2, 1), IteratorDirection::Forward)),
+            vec![3, 4]
+        );
+        assert_eq!(
+            shred_data_slots(&db, IteratorMode::From((3, 1), IteratorDirection::Reverse)),
+            vec![3, 2, 1]
+        );
+        assert_eq!(column.get_bytes((4, 0)).unwrap(), Some(vec![4]));
+        assert_eq!(column.get_bytes((5, 0)).unwrap(), None);
+        assert!(db.column::<columns::ShredCode>().is_empty().unwrap());
+    }
+
+    #[test]
+    fn test_memory_write_batch() {
+        let db = Database::open_in_memory();
+        let mut batch = db.batch().unwrap();
+        for slot in 0..10 {
+            batch.put_bytes::<ShredData>((slot, 0), &[0; 8]).unwrap();
+            batch.put::<columns::Root>(slot, &true).unwrap();
+        }
+        // Nothing is visible until the batch is written
+        assert!(db.column::<ShredData>().is_empty().unwrap());
+        db.write(batch).unwrap();
+        assert_eq!(db.storage_size().unwrap(), 10 * (16 + 8) + 10 * (8 + 1));
+
+        let mut batch = db.batch().unwrap();
+        db.delete_range_cf::<ShredData>(&mut batch, 2, 5).unwrap();
+        batch.delete::<columns::Root>(9).unwrap();
+        db.write(batch).unwrap();
+        assert_eq!(
+            shred_data_slots(&db, IteratorMode::Start),
+            vec![0, 1, 5, 6, 7, 8, 9]
+        );
+        assert_eq!(db.get::<columns::Root>(8).unwrap(), Some(true));
+        assert_eq!(db.get::<columns::Root>(9).unwrap(), None);
+    }
+
+    #[test]
+    fn test_column_options() {
+        assert_eq!(
+            "zstd".parse::<BlockstoreCompressionType>(),
+            Ok(BlockstoreCompressionType::Zstd)
+        );
+        assert!("snappy".parse::<BlockstoreCompressionType>().is_err());
+
+        let ledger_path = get_tmp_ledger_path!();
+        let mut options = BlockstoreOptions::default();
+        options.column_options.insert(
+            ShredData::NAME.to_string(),
+            ColumnOptions {
+                compression_type: BlockstoreCompressionType::Lz4,
+                block_size: Some(64 * 1024),
+            },
+        );
+        options.column_options.insert(
+            ShredCode::NAME.to_string(),
+            ColumnOptions {
+                compression_type: BlockstoreCompressionType::None,
+                block_size: None,
+            },
+        );
+        {
+            let db = Database::open(&ledger_path, &options).unwrap();
+            let data_column = db.column::<ShredData>();
+            let code_column = db.column::<ShredCode>();
+            // 1 MiB of compressible values in each column
+            for index in 0..256 {
+                data_column.put_bytes((1, index), &[7; 4096]).unwrap();
+                code_column.put_bytes((1, index), &[7; 4096]).unwrap();
+            }
+            assert_eq!(data_column.get_bytes((1, 0)).unwrap(), Some(vec![7; 4096]));
+            // Compacting flushes the memtables into table files
+            data_column.compact_range(0, 2).unwrap();
+            code_column.compact_range(0, 2).unwrap();
+
+            let stats: HashMap<_, _> = db.column_storage_stats().unwrap().into_iter().collect();
+            assert_eq!(stats.len(), column_names().len());
+            let data_stats = &stats[ShredData::NAME];
+            let code_stats = &stats[ShredCode::NAME];
+            assert!(data_stats.raw_size >= 256 * 4096);
+            assert_eq!(data_stats.raw_size, code_stats.raw_size);
+            // Only the compressed column is smaller on disk than its keys and values
+            assert!(data_stats.disk_size < data_stats.raw_size / 10);
+            assert!(data_stats.compression_ratio().unwrap() > 10.0);
+            assert!(code_stats.disk_size > data_stats.raw_size / 2);
+            assert!(code_stats.compression_ratio().unwrap() < 1.5);
+            // 64 KiB blocks instead of RocksDB's default 4 KiB, which fit a single value
+            assert!(data_stats.num_data_blocks > 0 && data_stats.num_data_blocks <= 32);
+            assert!(code_stats.num_data_blocks >= 256);
+        }
+        Database::destroy(&ledger_path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_table_property() {
+        let table_properties = "# data blocks=2; # entries=10; raw key size=160; \
+                                raw average key size=16.000000; raw value size=2000; \
+                                data block size=1100; index block size (user-key? 1)=60";
+        assert_eq!(
+            parse_table_property(table_properties, "raw key size"),
+            Some(160)
+        );
+        assert_eq!(
+            parse_table_property(table_properties, "data block size"),
+            Some(1100)
+        );
+        assert_eq!(
+            parse_table_property(table_properties, "# data blocks"),
+            Some(2)
+        );
+        assert_eq!(parse_table_property(table_properties, "filter size"), None);
+        assert_eq!(parse_table_property("", "raw key size"), None);
+
+        let stats = ColumnStorageStats {
+            disk_size: 1200,
+            raw_size: 2160,
+            data_blocks_size: 1080,
+            num_data_blocks: 2,
+        };
+        assert_eq!(stats.compression_ratio(), Some(2.0));
+        assert_eq!(ColumnStorageStats::default().compression_ratio(), None);
+    }
+
+    #[test]
+    fn test_memory_write_while_iterating() {
+        let db = Database::open_in_memory();
+        let column = db.column::<columns::SlotMeta>();
+        column.put_bytes(0, &[]).unwrap();
+        column.put_bytes(2, &[]).unwrap();
+        column.put_bytes(4, &[]).unwrap();
+
+        // Iterators don't see the writes made after they were created
+        let mut iter = column.iter(IteratorMode::Start).unwrap();
+        assert_eq!(iter.next().unwrap().0, 0);
+        column.put_bytes(1, &[]).unwrap();
+        column.put_bytes(3, &[]).unwrap();
+        let mut batch = db.batch().unwrap();
+        batch.delete::<columns::SlotMeta>(4).unwrap();
+        db.write(batch).unwrap();
+        let slots: Vec<_> = iter.map(|(slot, _)| slot).collect();
+        assert_eq!(slots, vec![2, 4]);
+
+        let slots: Vec<_> = column
+            .iter(IteratorMode::From(3, IteratorDirection::Reverse))
+            .unwrap()
+            .map(|(slot, _)| slot)
+            .collect();
+        assert_eq!(slots, vec![3, 2, 1, 0]);
+    }
+}
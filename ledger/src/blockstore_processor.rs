@@ -16,7 +16,8 @@ use solana_measure::{measure::Measure, thread_mem_usage};
 use solana_metrics::{datapoint_error, inc_new_counter_debug};
 use solana_rayon_threadlimit::get_thread_count;
 use solana_runtime::{
-    accounts_index::AccountIndex,
+    accounts_index::{AccountSecondaryIndexes, AccountsIndexConfig},
+    accounts_update_notifier_interface::AccountsUpdateNotifier,
     bank::{
         Bank, ExecuteTimings, InnerInstructionsList, TransactionBalancesSet,
         TransactionExecutionResult, TransactionLogMessages, TransactionResults,
@@ -25,6 +26,7 @@ use solana_runtime::{
     bank_utils,
     commitment::VOTE_THRESHOLD_SIZE,
     transaction_batch::TransactionBatch,
+    transaction_scheduler::TransactionSchedule,
     transaction_utils::OrderedIterator,
     vote_account::ArcVoteAccount,
     vote_sender_types::ReplayVoteSender,
@@ -107,6 +109,7 @@ fn execute_batch(
     transaction_status_sender: Option<TransactionStatusSender>,
     replay_vote_sender: Option<&ReplayVoteSender>,
     timings: &mut ExecuteTimings,
+    execution_thread_pool: Option<&ThreadPool>,
 ) -> Result<()> {
     let record_token_balances = transaction_status_sender.is_some();
 
@@ -119,14 +122,26 @@ fn execute_batch(
     };
 
     let (tx_results, balances, inner_instructions, transaction_logs) =
-        batch.bank().load_execute_and_commit_transactions(
-            batch,
-            MAX_PROCESSING_AGE,
-            transaction_status_sender.is_some(),
-            transaction_status_sender.is_some(),
-            transaction_status_sender.is_some(),
-            timings,
-        );
+        if let Some(thread_pool) = execution_thread_pool {
+            batch.bank().par_load_execute_and_commit_transactions(
+                batch,
+                thread_pool,
+                MAX_PROCESSING_AGE,
+                transaction_status_sender.is_some(),
+                transaction_status_sender.is_some(),
+                transaction_status_sender.is_some(),
+                timings,
+            )
+        } else {
+            batch.bank().load_execute_and_commit_transactions(
+                batch,
+                MAX_PROCESSING_AGE,
+                transaction_status_sender.is_some(),
+                transaction_status_sender.is_some(),
+                transaction_status_sender.is_some(),
+                timings,
+            )
+        };
 
     bank_utils::find_and_send_votes(batch.transactions(), &tx_results, replay_vote_sender);
 
@@ -185,6 +200,7 @@ fn execute_batches(
                             sender.clone(),
                             replay_vote_sender,
                             &mut timings,
+                            None,
                         );
                         if let Some(entry_callback) = entry_callback {
                             entry_callback(bank);
@@ -223,6 +239,7 @@ pub fn process_entries(
         transaction_status_sender,
         replay_vote_sender,
         &mut timings,
+        false,
     );
 
     debug!("process_entries: {:?}", timings);
@@ -237,11 +254,24 @@ fn process_entries_with_callback(
     transaction_status_sender: Option<TransactionStatusSender>,
     replay_vote_sender: Option<&ReplayVoteSender>,
     timings: &mut ExecuteTimings,
+    schedule_transactions: bool,
 ) -> Result<()> {
+    let entry_transactions = resolve_entry_transactions(bank, entries)?;
+    if schedule_transactions {
+        return process_entries_scheduled(
+            bank,
+            entries,
+            entry_transactions,
+            entry_callback,
+            transaction_status_sender,
+            replay_vote_sender,
+            timings,
+        );
+    }
     // accumulator for entries that can be processed in parallel
     let mut batches = vec![];
     let mut tick_hashes = vec![];
-    for entry in entries {
+    for (entry, transactions) in entries.iter().zip(&entry_transactions) {
         if entry.is_tick() {
             // If it's a tick, save it for later
             tick_hashes.push(entry.hash);
@@ -267,7 +297,7 @@ fn process_entries_with_callback(
         // else loop on processing the entry
         loop {
             let iteration_order = if randomize {
-                let mut iteration_order: Vec<usize> = (0..entry.transactions.len()).collect();
+                let mut iteration_order: Vec<usize> = (0..transactions.len()).collect();
                 iteration_order.shuffle(&mut thread_rng());
                 Some(iteration_order)
             } else {
@@ -275,7 +305,7 @@ fn process_entries_with_callback(
             };
 
             // try to lock the accounts
-            let batch = bank.prepare_batch(&entry.transactions, iteration_order);
+            let batch = bank.prepare_batch(transactions, iteration_order);
 
             let first_lock_err = first_err(batch.lock_results());
 
@@ -331,6 +361,150 @@ fn process_entries_with_callback(
     Ok(())
 }
 
+/// Resolve the transactions of each entry into the transactions `bank` executes.  Entries
+/// record transactions as they were signed, so the address table lookups of versioned messages
+/// are resolved against the bank that replays them.  A transaction that doesn't resolve makes
+/// the entries invalid.
+fn resolve_entry_transactions(bank: &Bank, entries: &[Entry]) -> Result<Vec<Vec<Transaction>>> {
+    entries
+        .iter()
+        .map(|entry| {
+            entry
+                .transactions
+                .iter()
+                .map(|transaction| {
+                    bank.resolve_versioned_transaction(transaction.clone())
+                        .map_err(|err| {
+                            warn!(
+                                "failed to resolve transaction {:?}: {:?}",
+                                transaction.signatures.get(0),
+                                err
+                            );
+                            err
+                        })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Process an ordered list of entries, scheduling their transactions by account locks
+/// 1. Collect the transactions of the entries up to a block boundary, checking that no entry
+///    conflicts with itself
+/// 2. Execute them with `execute_scheduled()`, which runs the ones that don't conflict in
+///    parallel
+/// 3. Register the ticks, goto 1
+fn process_entries_scheduled(
+    bank: &Arc<Bank>,
+    entries: &[Entry],
+    entry_transactions: Vec<Vec<Transaction>>,
+    entry_callback: Option<&ProcessCallback>,
+    transaction_status_sender: Option<TransactionStatusSender>,
+    replay_vote_sender: Option<&ReplayVoteSender>,
+    timings: &mut ExecuteTimings,
+) -> Result<()> {
+    let mut transactions = vec![];
+    let mut tick_hashes = vec![];
+    for (entry, entry_transactions) in entries.iter().zip(entry_transactions) {
+        if entry.is_tick() {
+            tick_hashes.push(entry.hash);
+            if bank.is_block_boundary(bank.tick_height() + tick_hashes.len() as u64) {
+                execute_scheduled(
+                    bank,
+                    std::mem::take(&mut transactions),
+                    entry_callback,
+                    transaction_status_sender.clone(),
+                    replay_vote_sender,
+                    timings,
+                )?;
+                for hash in &tick_hashes {
+                    bank.register_tick(hash);
+                }
+                tick_hashes.clear();
+            }
+            continue;
+        }
+        // The transactions of an entry must not lock the same accounts, as when they are
+        // locked as a batch
+        if TransactionSchedule::new(&entry_transactions).num_waves() > 1 {
+            execute_scheduled(
+                bank,
+                transactions,
+                entry_callback,
+                transaction_status_sender,
+                replay_vote_sender,
+                timings,
+            )?;
+            datapoint_error!(
+                "validator_process_entry_error",
+                (
+                    "error",
+                    format!(
+                        "Lock accounts error, entry conflicts with itself, txs: {:?}",
+                        entry.transactions
+                    ),
+                    String
+                )
+            );
+            return Err(TransactionError::AccountInUse);
+        }
+        transactions.extend(entry_transactions);
+    }
+    execute_scheduled(
+        bank,
+        transactions,
+        entry_callback,
+        transaction_status_sender,
+        replay_vote_sender,
+        timings,
+    )?;
+    for hash in tick_hashes {
+        bank.register_tick(&hash);
+    }
+    Ok(())
+}
+
+/// Executes `transactions` one wave of their `TransactionSchedule` after another. Each wave is
+/// locked as a batch and executed in parallel by `execute_batch()`, which also sends its
+/// transaction statuses.
+fn execute_scheduled(
+    bank: &Arc<Bank>,
+    transactions: Vec<Transaction>,
+    entry_callback: Option<&ProcessCallback>,
+    transaction_status_sender: Option<TransactionStatusSender>,
+    replay_vote_sender: Option<&ReplayVoteSender>,
+    timings: &mut ExecuteTimings,
+) -> Result<()> {
+    if transactions.is_empty() {
+        return Ok(());
+    }
+    inc_new_counter_debug!("bank-par_execute_scheduled-count", transactions.len());
+    let schedule = TransactionSchedule::new(&transactions);
+    // Puts the transactions of each wave next to each other, so that they can be batched
+    let transactions = schedule.reorder(transactions);
+    let result = PAR_THREAD_POOL.with(|thread_pool| {
+        let thread_pool = thread_pool.borrow();
+        let mut wave_start = 0;
+        for wave_end in schedule.wave_ends() {
+            let batch = bank.prepare_batch(&transactions[wave_start..wave_end], None);
+            wave_start = wave_end;
+            execute_batch(
+                &batch,
+                bank,
+                transaction_status_sender.clone(),
+                replay_vote_sender,
+                timings,
+                Some(&thread_pool),
+            )?;
+        }
+        Ok(())
+    });
+    if let Some(entry_callback) = entry_callback {
+        entry_callback(bank);
+    }
+    result
+}
+
 #[derive(Error, Debug)]
 pub enum BlockstoreProcessorError {
     #[error("failed to load entries")]
@@ -369,9 +543,12 @@ pub struct ProcessOptions {
     pub new_hard_forks: Option<Vec<Slot>>,
     pub frozen_accounts: Vec<Pubkey>,
     pub debug_keys: Option<Arc<HashSet<Pubkey>>>,
-    pub account_indexes: HashSet<AccountIndex>,
+    pub account_indexes: AccountSecondaryIndexes,
     pub accounts_db_caching_enabled: bool,
+    pub accounts_index_config: Option<AccountsIndexConfig>,
     pub allow_dead_slots: bool,
+    pub accounts_update_notifier: Option<AccountsUpdateNotifier>,
+    pub schedule_transactions: bool,
 }
 
 pub fn process_blockstore(
@@ -398,7 +575,11 @@ pub fn process_blockstore(
         Some(&crate::builtins::get(opts.bpf_jit)),
         opts.account_indexes.clone(),
         opts.accounts_db_caching_enabled,
+        opts.accounts_index_config.clone(),
     );
+    if let Some(accounts_update_notifier) = &opts.accounts_update_notifier {
+        bank0.set_accounts_update_notifier(accounts_update_notifier.clone());
+    }
     let bank0 = Arc::new(bank0);
     info!("processing ledger for slot 0...");
     let recyclers = VerifyRecyclers::default();
@@ -608,6 +789,7 @@ fn confirm_full_slot(
         opts.entry_callback.as_ref(),
         recyclers,
         opts.allow_dead_slots,
+        opts.schedule_transactions,
     )?;
 
     timing.accumulate(&confirmation_timing.execute_timings);
@@ -675,6 +857,7 @@ pub fn confirm_slot(
     entry_callback: Option<&ProcessCallback>,
     recyclers: &VerifyRecyclers,
     allow_dead_slots: bool,
+    schedule_transactions: bool,
 ) -> result::Result<(), BlockstoreProcessorError> {
     let slot = bank.slot();
 
@@ -747,6 +930,7 @@ pub fn confirm_slot(
         transaction_status_sender,
         replay_vote_sender,
         &mut execute_timings,
+        schedule_transactions,
     )
     .map_err(BlockstoreProcessorError::from);
     replay_elapsed.stop();
@@ -1212,11 +1396,15 @@ pub mod tests {
         account::{AccountSharedData, WritableAccount},
         epoch_schedule::EpochSchedule,
         hash::Hash,
+        message::{
+            v0::{self, MessageAddressTableLookup},
+            VersionedMessage,
+        },
         pubkey::Pubkey,
         signature::{Keypair, Signer},
-        system_instruction::SystemError,
+        system_instruction::{self, SystemError},
         system_transaction,
-        transaction::{Transaction, TransactionError},
+        transaction::{Transaction, TransactionError, VersionedTransaction},
     };
     use solana_vote_program::{
         self,
@@ -2276,20 +2464,75 @@ pub mod tests {
         assert_eq!(bank.get_balance(&keypair2.pubkey()), 4);
 
         // Check all accounts are unlocked
-        let txs1 = &entry_1_to_mint.transactions[..];
-        let txs2 = &entry_2_to_3_mint_to_1.transactions[..];
-        let batch1 = bank.prepare_batch(txs1, None);
+        let txs1: Vec<_> = entry_1_to_mint
+            .transactions
+            .into_iter()
+            .map(|tx| tx.into_legacy_transaction().unwrap())
+            .collect();
+        let txs2: Vec<_> = entry_2_to_3_mint_to_1
+            .transactions
+            .into_iter()
+            .map(|tx| tx.into_legacy_transaction().unwrap())
+            .collect();
+        let batch1 = bank.prepare_batch(&txs1, None);
         for result in batch1.lock_results() {
             assert!(result.is_ok());
         }
         // txs1 and txs2 have accounts that conflict, so we must drop txs1 first
         drop(batch1);
-        let batch2 = bank.prepare_batch(txs2, None);
+        let batch2 = bank.prepare_batch(&txs2, None);
         for result in batch2.lock_results() {
             assert!(result.is_ok());
         }
     }
 
+    #[test]
+    fn test_process_entries_versioned_transactions() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(1000);
+        let bank = Arc::new(Bank::new(&genesis_config));
+        let pubkey = solana_sdk::pubkey::new_rand();
+
+        let message = v0::Message::try_compile(
+            &mint_keypair.pubkey(),
+            &[system_instruction::transfer(
+                &mint_keypair.pubkey(),
+                &pubkey,
+                1,
+            )],
+            &[],
+            bank.last_blockhash(),
+        )
+        .unwrap();
+        let tx =
+            VersionedTransaction::try_new(VersionedMessage::V0(message.clone()), &[&mint_keypair])
+                .unwrap();
+        let entry = Entry::new_versioned(&bank.last_blockhash(), 1, vec![tx.clone()]);
+        assert!(std::slice::from_ref(&entry).verify_transaction_signatures(true));
+        assert_eq!(process_entries(&bank, &[entry], true, None, None), Ok(()));
+        assert_eq!(bank.get_balance(&pubkey), 1);
+        assert_eq!(bank.get_signature_status(&tx.signatures[0]), Some(Ok(())));
+
+        // Entries with lookups that don't resolve are invalid
+        let mut message = message;
+        message.address_table_lookups = vec![MessageAddressTableLookup {
+            account_key: solana_sdk::pubkey::new_rand(),
+            writable_indexes: vec![0],
+            readonly_indexes: vec![],
+        }];
+        let tx =
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &[&mint_keypair]).unwrap();
+        let entry = Entry::new_versioned(&bank.last_blockhash(), 1, vec![tx]);
+        assert_eq!(
+            process_entries(&bank, &[entry], true, None, None),
+            Err(TransactionError::AccountNotFound)
+        );
+        assert_eq!(bank.get_balance(&pubkey), 1);
+    }
+
     #[test]
     fn test_process_entries_2nd_entry_collision_with_self_and_error() {
         solana_logger::setup();
@@ -2389,6 +2632,152 @@ pub mod tests {
         assert_eq!(bank.get_balance(&keypair3.pubkey()), 2);
     }
 
+    #[test]
+    fn test_process_entries_scheduled() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(1_000_000);
+        let bank = Arc::new(Bank::new(&genesis_config));
+        let scheduled_bank = Arc::new(Bank::new(&genesis_config));
+        let keypairs: Vec<_> = (0..8).map(|_| Keypair::new()).collect();
+        for keypair in &keypairs {
+            for bank in &[&bank, &scheduled_bank] {
+                assert_matches!(bank.transfer(1000, &mint_keypair, &keypair.pubkey()), Ok(_));
+            }
+        }
+
+        // Every entry moves lamports around in pairs which conflict with the other entries,
+        // some of the transfers fail, and a tick is registered in between
+        let mut entries = vec![];
+        let mut hash = bank.last_blockhash();
+        for i in 0..16 {
+            let transactions = (0..keypairs.len() / 2)
+                .map(|j| {
+                    let from = &keypairs[(i + 2 * j) % keypairs.len()];
+                    let to = &keypairs[(i + 2 * j + 1) % keypairs.len()];
+                    let lamports = if (i + j) % 5 == 0 {
+                        10_000
+                    } else {
+                        100 + i as u64
+                    };
+                    system_transaction::transfer(
+                        from,
+                        &to.pubkey(),
+                        lamports,
+                        bank.last_blockhash(),
+                    )
+                })
+                .collect();
+            let entry = next_entry(&hash, 1, transactions);
+            hash = entry.hash;
+            entries.push(entry);
+            if i == 7 {
+                let tick = next_entry(&hash, 1, vec![]);
+                hash = tick.hash;
+                entries.push(tick);
+            }
+        }
+
+        // Both send the same transaction statuses, in batches of different transactions
+        let mut transaction_statuses = vec![];
+        for (bank, schedule_transactions) in &[(&bank, false), (&scheduled_bank, true)] {
+            let (sender, receiver) = unbounded();
+            process_entries_with_callback(
+                bank,
+                &entries,
+                false,
+                None,
+                Some(TransactionStatusSender {
+                    sender,
+                    enable_cpi_and_log_storage: false,
+                }),
+                None,
+                &mut ExecuteTimings::default(),
+                *schedule_transactions,
+            )
+            .unwrap();
+            let mut statuses: Vec<_> = receiver
+                .try_iter()
+                .flat_map(|batch| {
+                    let TransactionStatusBatch {
+                        transactions,
+                        statuses,
+                        balances,
+                        ..
+                    } = batch;
+                    transactions
+                        .into_iter()
+                        .zip(statuses)
+                        .zip(
+                            balances
+                                .pre_balances
+                                .into_iter()
+                                .zip(balances.post_balances),
+                        )
+                        .map(|((transaction, (status, _)), balances)| {
+                            (transaction.signatures[0], status, balances)
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+            assert_eq!(statuses.len(), 16 * keypairs.len() / 2);
+            statuses.sort_by_key(|(signature, _, _)| *signature);
+            transaction_statuses.push(statuses);
+        }
+        assert_eq!(transaction_statuses[0], transaction_statuses[1]);
+        for keypair in &keypairs {
+            assert_eq!(
+                bank.get_balance(&keypair.pubkey()),
+                scheduled_bank.get_balance(&keypair.pubkey())
+            );
+        }
+        assert_eq!(bank.transaction_count(), scheduled_bank.transaction_count());
+        assert_eq!(bank.tick_height(), scheduled_bank.tick_height());
+        bank.freeze();
+        scheduled_bank.freeze();
+        assert_eq!(bank.hash(), scheduled_bank.hash());
+
+        // An entry which conflicts with itself is still an error
+        let entry = next_entry(
+            &hash,
+            1,
+            vec![
+                system_transaction::transfer(
+                    &keypairs[0],
+                    &keypairs[1].pubkey(),
+                    1,
+                    bank.last_blockhash(),
+                ),
+                system_transaction::transfer(
+                    &keypairs[0],
+                    &keypairs[2].pubkey(),
+                    1,
+                    bank.last_blockhash(),
+                ),
+            ],
+        );
+        let bank = Arc::new(Bank::new_from_parent(
+            &scheduled_bank,
+            &Pubkey::default(),
+            1,
+        ));
+        assert_eq!(
+            process_entries_with_callback(
+                &bank,
+                &[entry],
+                false,
+                None,
+                None,
+                None,
+                &mut ExecuteTimings::default(),
+                true,
+            ),
+            Err(TransactionError::AccountInUse)
+        );
+    }
+
     #[test]
     fn test_process_entries_2_entries_par() {
         let GenesisConfigInfo {
@@ -2959,6 +3348,7 @@ pub mod tests {
             None,
             None,
             &mut ExecuteTimings::default(),
+            false,
         )
         .unwrap();
         assert_eq!(bank0.get_balance(&keypair.pubkey()), 1)
@@ -2974,8 +3364,9 @@ pub mod tests {
             &[],
             None,
             None,
-            HashSet::new(),
+            AccountSecondaryIndexes::default(),
             false,
+            None,
         );
         *bank.epoch_schedule()
     }
@@ -20,7 +20,7 @@ use solana_rayon_threadlimit::get_thread_count;
 use solana_sdk::hash::Hash;
 use solana_sdk::packet::PACKET_DATA_SIZE;
 use solana_sdk::timing;
-use solana_sdk::transaction::Transaction;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
 use std::cell::RefCell;
 use std::ffi::OsStr;
 use std::sync::mpsc::{Receiver, Sender};
@@ -115,12 +115,23 @@ pub struct Entry {
     /// An unordered list of transactions that were observed before the Entry ID was
     /// generated. They may have been observed before a previous Entry ID but were
     /// pushed back into this list to ensure deterministic interpretation of the ledger.
-    pub transactions: Vec<Transaction>,
+    pub transactions: Vec<VersionedTransaction>,
 }
 
 impl Entry {
     /// Creates the next Entry `num_hashes` after `start_hash`.
-    pub fn new(prev_hash: &Hash, mut num_hashes: u64, transactions: Vec<Transaction>) -> Self {
+    pub fn new(prev_hash: &Hash, num_hashes: u64, transactions: Vec<Transaction>) -> Self {
+        let transactions = transactions.into_iter().map(Into::into).collect();
+        Self::new_versioned(prev_hash, num_hashes, transactions)
+    }
+
+    /// Creates the next Entry `num_hashes` after `start_hash` from transactions which may have
+    /// versioned messages.
+    pub fn new_versioned(
+        prev_hash: &Hash,
+        mut num_hashes: u64,
+        transactions: Vec<VersionedTransaction>,
+    ) -> Self {
         // If you passed in transactions, but passed in num_hashes == 0, then
         // next_hash will generate the next hash and set num_hashes == 1
         if num_hashes == 0 && !transactions.is_empty() {
@@ -175,7 +186,7 @@ impl Entry {
     }
 }
 
-pub fn hash_transactions(transactions: &[Transaction]) -> Hash {
+pub fn hash_transactions(transactions: &[VersionedTransaction]) -> Hash {
     // a hash of a slice of transactions only needs to hash the signatures
     let signatures: Vec<_> = transactions
         .iter()
@@ -193,7 +204,11 @@ pub fn hash_transactions(transactions: &[Transaction]) -> Hash {
 /// a signature, the final hash will be a hash of both the previous ID and
 /// the signature.  If num_hashes is zero and there's no transaction data,
 ///  start_hash is returned.
-pub fn next_hash(start_hash: &Hash, num_hashes: u64, transactions: &[Transaction]) -> Hash {
+pub fn next_hash(
+    start_hash: &Hash,
+    num_hashes: u64,
+    transactions: &[VersionedTransaction],
+) -> Hash {
     if num_hashes == 0 && transactions.is_empty() {
         return *start_hash;
     }
@@ -500,7 +515,7 @@ impl EntrySlice for [Entry] {
     }
 
     fn verify_transaction_signatures(&self, secp256k1_program_enabled: bool) -> bool {
-        let verify = |tx: &Transaction| {
+        let verify = |tx: &VersionedTransaction| {
             tx.verify().is_ok()
                 && {
                     match bincode::serialized_size(tx) {
@@ -688,6 +703,7 @@ pub fn create_random_ticks(num_ticks: u64, max_hashes_per_tick: u64, mut hash: H
 /// Creates the next Tick or Transaction Entry `num_hashes` after `start_hash`.
 pub fn next_entry(prev_hash: &Hash, num_hashes: u64, transactions: Vec<Transaction>) -> Entry {
     assert!(num_hashes > 0 || transactions.is_empty());
+    let transactions: Vec<_> = transactions.into_iter().map(Into::into).collect();
     Entry {
         num_hashes,
         hash: next_hash(prev_hash, num_hashes, &transactions),
@@ -703,9 +719,9 @@ mod tests {
     use solana_budget_program::budget_instruction;
     use solana_sdk::{
         hash::{hash, new_rand as hash_new_rand, Hash},
-        message::Message,
+        message::{v0, Message, VersionedMessage},
         signature::{Keypair, Signer},
-        system_transaction,
+        system_instruction, system_transaction,
         transaction::Transaction,
     };
 
@@ -754,8 +770,8 @@ mod tests {
         assert!(e0.verify(&zero));
 
         // Next, swap two transactions and ensure verification fails.
-        e0.transactions[0] = tx1; // <-- attack
-        e0.transactions[1] = tx0;
+        e0.transactions[0] = tx1.into(); // <-- attack
+        e0.transactions[1] = tx0.into();
         assert!(!e0.verify(&zero));
     }
 
@@ -793,6 +809,34 @@ mod tests {
         assert!(e0.verify(&zero));
     }
 
+    #[test]
+    fn test_versioned_transaction_signing() {
+        let zero = Hash::default();
+        let keypair = Keypair::new();
+        let message = v0::Message::try_compile(
+            &keypair.pubkey(),
+            &[system_instruction::transfer(
+                &keypair.pubkey(),
+                &solana_sdk::pubkey::new_rand(),
+                1,
+            )],
+            &[],
+            zero,
+        )
+        .unwrap();
+        let tx = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&keypair]).unwrap();
+
+        let mut e0 = vec![Entry::new_versioned(&zero, 0, vec![tx])];
+        assert!(e0.verify(&zero));
+        assert!(e0.verify_transaction_signatures(true));
+
+        // The signature covers the versioned message
+        if let VersionedMessage::V0(message) = &mut e0[0].transactions[0].message {
+            message.recent_blockhash = hash(&[1]);
+        }
+        assert!(!e0.verify_transaction_signatures(true));
+    }
+
     #[test]
     fn test_witness_reorder_attack() {
         let zero = Hash::default();
@@ -805,8 +849,8 @@ mod tests {
         assert!(e0.verify(&zero));
 
         // Next, swap two witness transactions and ensure verification fails.
-        e0.transactions[0] = tx1; // <-- attack
-        e0.transactions[1] = tx0;
+        e0.transactions[0] = tx1.into(); // <-- attack
+        e0.transactions[1] = tx0.into();
         assert!(!e0.verify(&zero));
     }
 
@@ -825,7 +869,7 @@ mod tests {
         let tx0 = create_sample_timestamp(&keypair, zero);
         let entry0 = next_entry(&zero, 1, vec![tx0.clone()]);
         assert_eq!(entry0.num_hashes, 1);
-        assert_eq!(entry0.hash, next_hash(&zero, 1, &[tx0]));
+        assert_eq!(entry0.hash, next_hash(&zero, 1, &[tx0.into()]));
     }
 
     #[test]
@@ -955,7 +999,7 @@ mod tests {
     #[test]
     fn test_verify_tick_hash_count() {
         let hashes_per_tick = 10;
-        let tx = Transaction::default();
+        let tx = VersionedTransaction::default();
 
         let no_hash_tx_entry = Entry {
             transactions: vec![tx.clone()],
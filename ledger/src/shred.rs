@@ -7,17 +7,20 @@ use crate::{
 use bincode::config::Options;
 use core::cell::RefCell;
 use rayon::{
-    iter::{IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator},
+    iter::{
+        IndexedParallelIterator, IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator,
+    },
     slice::ParallelSlice,
     ThreadPool,
 };
 use serde::{Deserialize, Serialize};
 use solana_measure::measure::Measure;
+use solana_merkle_tree::MerkleTree;
 use solana_perf::packet::{limited_deserialize, Packet};
 use solana_rayon_threadlimit::get_thread_count;
 use solana_sdk::{
     clock::Slot,
-    hash::Hash,
+    hash::{hashv, Hash},
     packet::PACKET_DATA_SIZE,
     pubkey::Pubkey,
     signature::{Keypair, Signature, Signer},
@@ -67,6 +70,7 @@ pub const SIZE_OF_SHRED_TYPE: usize = 1;
 pub const SIZE_OF_SHRED_SLOT: usize = 8;
 pub const SIZE_OF_SHRED_INDEX: usize = 4;
 pub const SIZE_OF_NONCE: usize = 4;
+pub const SIZE_OF_MERKLE_PROOF_ENTRY: usize = 32;
 pub const SIZE_OF_DATA_SHRED_IGNORED_TAIL: usize =
     SIZE_OF_COMMON_SHRED_HEADER + SIZE_OF_CODING_SHRED_HEADER;
 pub const SIZE_OF_DATA_SHRED_PAYLOAD: usize = PACKET_DATA_SIZE
@@ -90,6 +94,12 @@ thread_local!(static PAR_THREAD_POOL: RefCell<ThreadPool> = RefCell::new(rayon::
 pub const DATA_SHRED: u8 = 0b1010_0101;
 pub const CODING_SHRED: u8 = 0b0101_1010;
 
+/// The constants that define if a Merkle shred is data or coding.  The low bits of the shred
+/// type hold the depth of the Merkle inclusion proof carried by the shred.
+pub const MERKLE_DATA_SHRED: u8 = 0b1000_0000;
+pub const MERKLE_CODING_SHRED: u8 = 0b0100_0000;
+const MERKLE_PROOF_DEPTH_MASK: u8 = 0b0000_1111;
+
 pub const MAX_DATA_SHREDS_PER_FEC_BLOCK: u32 = 32;
 pub const RECOMMENDED_FEC_RATE: f32 = 1.0;
 
@@ -127,6 +137,89 @@ impl Default for ShredType {
     }
 }
 
+impl ShredType {
+    pub fn new_merkle_data(proof_depth: usize) -> Self {
+        assert!(proof_depth <= usize::from(MERKLE_PROOF_DEPTH_MASK));
+        ShredType(MERKLE_DATA_SHRED | proof_depth as u8)
+    }
+
+    pub fn new_merkle_coding(proof_depth: usize) -> Self {
+        assert!(proof_depth <= usize::from(MERKLE_PROOF_DEPTH_MASK));
+        ShredType(MERKLE_CODING_SHRED | proof_depth as u8)
+    }
+
+    pub fn is_data(self) -> bool {
+        self.0 == DATA_SHRED || self.0 & !MERKLE_PROOF_DEPTH_MASK == MERKLE_DATA_SHRED
+    }
+
+    pub fn is_code(self) -> bool {
+        self.0 == CODING_SHRED || self.0 & !MERKLE_PROOF_DEPTH_MASK == MERKLE_CODING_SHRED
+    }
+
+    /// Depth of the Merkle inclusion proof of a Merkle shred, `None` for legacy shreds
+    pub fn merkle_proof_depth(self) -> Option<usize> {
+        match self.0 & !MERKLE_PROOF_DEPTH_MASK {
+            MERKLE_DATA_SHRED | MERKLE_CODING_SHRED => {
+                Some(usize::from(self.0 & MERKLE_PROOF_DEPTH_MASK))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Size of the erasure coded shard of a Merkle shred.  Data shreds are coded from the end of
+/// the signature on, and coding shreds hold their parity after the coding header.
+fn merkle_shard_size(proof_depth: usize) -> usize {
+    SHRED_PAYLOAD_SIZE
+        - SIZE_OF_COMMON_SHRED_HEADER
+        - SIZE_OF_CODING_SHRED_HEADER
+        - proof_depth * SIZE_OF_MERKLE_PROOF_ENTRY
+}
+
+/// Offset of the inclusion proof of a Merkle shred, which fills the end of the payload.  The
+/// leaf of a shred in the Merkle tree of its FEC set is everything between the signature and
+/// the proof, so the signed root covers the whole payload.
+fn merkle_proof_offset(proof_depth: usize) -> usize {
+    SHRED_PAYLOAD_SIZE - proof_depth * SIZE_OF_MERKLE_PROOF_ENTRY
+}
+
+/// Number of bytes of entry data a Merkle data shred with the given proof depth can hold
+pub fn merkle_data_shred_capacity(proof_depth: usize) -> usize {
+    SIZE_OF_SIGNATURE + merkle_shard_size(proof_depth)
+        - SIZE_OF_COMMON_SHRED_HEADER
+        - SIZE_OF_DATA_SHRED_HEADER
+}
+
+/// Recomputes the Merkle root a Merkle shred commits to from its leaf and inclusion proof.
+/// Returns `None` for legacy shreds and malformed payloads.
+pub fn get_merkle_root(payload: &[u8]) -> Option<Hash> {
+    if payload.len() < SIZE_OF_COMMON_SHRED_HEADER + SIZE_OF_CODING_SHRED_HEADER {
+        return None;
+    }
+    let mut start = 0;
+    let common_header: ShredCommonHeader =
+        Shred::deserialize_obj(&mut start, SIZE_OF_COMMON_SHRED_HEADER, payload).ok()?;
+    let proof_depth = common_header.shred_type.merkle_proof_depth()?;
+    let leaf_index = if common_header.shred_type.is_data() {
+        common_header
+            .index
+            .checked_sub(common_header.fec_set_index)? as usize
+    } else {
+        let coding_header: CodingShredHeader =
+            Shred::deserialize_obj(&mut start, SIZE_OF_CODING_SHRED_HEADER, payload).ok()?;
+        usize::from(coding_header.num_data_shreds) + usize::from(coding_header.position)
+    };
+    if payload.len() < SHRED_PAYLOAD_SIZE {
+        return None;
+    }
+    let proof_offset = merkle_proof_offset(proof_depth);
+    Some(MerkleTree::root_from_siblings(
+        &payload[SIZE_OF_SIGNATURE..proof_offset],
+        leaf_index,
+        payload[proof_offset..SHRED_PAYLOAD_SIZE].chunks(SIZE_OF_MERKLE_PROOF_ENTRY),
+    ))
+}
+
 /// A common header that is present in data and code shred headers
 #[derive(Serialize, Clone, Deserialize, Default, PartialEq, Debug)]
 pub struct ShredCommonHeader {
@@ -278,7 +371,7 @@ impl Shred {
         // the `expected_data_size`.
         assert!(payload.len() >= expected_data_size);
         payload.truncate(expected_data_size);
-        let shred = if common_header.shred_type == ShredType(CODING_SHRED) {
+        let shred = if common_header.shred_type.is_code() {
             let coding_header: CodingShredHeader =
                 Self::deserialize_obj(&mut start, SIZE_OF_CODING_SHRED_HEADER, &payload)?;
             Self {
@@ -287,7 +380,7 @@ impl Shred {
                 coding_header,
                 payload,
             }
-        } else if common_header.shred_type == ShredType(DATA_SHRED) {
+        } else if common_header.shred_type.is_data() {
             let size_of_data_shred_header = SIZE_OF_DATA_SHRED_HEADER;
             let data_header: DataShredHeader =
                 Self::deserialize_obj(&mut start, size_of_data_shred_header, &payload)?;
@@ -345,7 +438,7 @@ impl Shred {
             &common_header,
         )
         .expect("Failed to write header into shred buffer");
-        if common_header.shred_type == ShredType(DATA_SHRED) {
+        if common_header.shred_type.is_data() {
             Self::serialize_obj_into(
                 &mut start,
                 SIZE_OF_DATA_SHRED_HEADER,
@@ -353,7 +446,7 @@ impl Shred {
                 &data_header,
             )
             .expect("Failed to write data header into shred buffer");
-        } else if common_header.shred_type == ShredType(CODING_SHRED) {
+        } else if common_header.shred_type.is_code() {
             Self::serialize_obj_into(
                 &mut start,
                 SIZE_OF_CODING_SHRED_HEADER,
@@ -425,6 +518,12 @@ impl Shred {
     }
 
     pub fn seed(&self) -> [u8; 32] {
+        if self.is_merkle() {
+            // All shreds of a Merkle FEC set share the signature, so seed from the type and
+            // index as well to spread the set over different retransmit trees
+            return hashv(&[&self.payload[..OFFSET_OF_SHRED_INDEX + SIZE_OF_SHRED_INDEX]])
+                .to_bytes();
+        }
         let mut seed = [0; 32];
         let seed_len = seed.len();
         let sig = self.common_header.signature.as_ref();
@@ -433,10 +532,28 @@ impl Shred {
     }
 
     pub fn is_data(&self) -> bool {
-        self.common_header.shred_type == ShredType(DATA_SHRED)
+        self.common_header.shred_type.is_data()
     }
     pub fn is_code(&self) -> bool {
-        self.common_header.shred_type == ShredType(CODING_SHRED)
+        self.common_header.shred_type.is_code()
+    }
+    pub fn is_merkle(&self) -> bool {
+        self.common_header.shred_type.merkle_proof_depth().is_some()
+    }
+
+    /// The entry data a data shred can hold, including any zero padding after the data
+    fn data_payload(&self) -> &[u8] {
+        let offset = SIZE_OF_COMMON_SHRED_HEADER + SIZE_OF_DATA_SHRED_HEADER;
+        let end = match self.common_header.shred_type.merkle_proof_depth() {
+            Some(proof_depth) => offset + merkle_data_shred_capacity(proof_depth),
+            None => SHRED_PAYLOAD_SIZE - SIZE_OF_DATA_SHRED_IGNORED_TAIL,
+        };
+        &self.payload[offset..end]
+    }
+
+    /// The Merkle root signed for the FEC set of a Merkle shred
+    pub fn merkle_root(&self) -> Option<Hash> {
+        get_merkle_root(&self.payload)
     }
 
     pub fn last_in_slot(&self) -> bool {
@@ -509,6 +626,12 @@ impl Shred {
     }
 
     pub fn verify(&self, pubkey: &Pubkey) -> bool {
+        if self.is_merkle() {
+            return match self.merkle_root() {
+                Some(root) => self.signature().verify(pubkey.as_ref(), root.as_ref()),
+                None => false,
+            };
+        }
         self.signature()
             .verify(pubkey.as_ref(), &self.payload[SIZE_OF_SIGNATURE..])
     }
@@ -671,6 +794,210 @@ impl Shredder {
         coding_shreds
     }
 
+    /// Shreds the entries into FEC sets of Merkle shreds.  The data and coding shreds of a set
+    /// are the leaves of a Merkle tree, only its root is signed, and every shred carries its
+    /// inclusion proof so it can be verified on its own.
+    pub fn entries_to_merkle_shreds(
+        &self,
+        entries: &[Entry],
+        is_last_in_slot: bool,
+        next_shred_index: u32,
+        process_stats: &mut ProcessShredsStats,
+    ) -> (Vec<Shred>, Vec<Shred>, u32) {
+        let mut serialize_time = Measure::start("shred_serialize");
+        let serialized_shreds =
+            bincode::serialize(entries).expect("Expect to serialize all entries");
+        serialize_time.stop();
+
+        let mut gen_data_time = Measure::start("shred_gen_data_time");
+        // Split the data into FEC sets. Shreds hold less data the deeper the tree of their
+        // set is, so the last set gets the fewest data shreds its data fits into.
+        let mut fec_sets = vec![];
+        let mut offset = 0;
+        let mut fec_set_index = next_shred_index;
+        while offset < serialized_shreds.len() {
+            let remaining = serialized_shreds.len() - offset;
+            let (num_data, num_coding) = self.merkle_fec_set_size(remaining);
+            let proof_depth = MerkleTree::proof_depth(num_data + num_coding);
+            let end = serialized_shreds
+                .len()
+                .min(offset + num_data * merkle_data_shred_capacity(proof_depth));
+            fec_sets.push((fec_set_index, num_data, num_coding, offset..end));
+            offset = end;
+            fec_set_index += num_data as u32;
+        }
+        let last_shred_index = fec_set_index - 1;
+
+        let fec_sets: Vec<(Vec<Shred>, Vec<Shred>)> = PAR_THREAD_POOL.with(|thread_pool| {
+            thread_pool.borrow().install(|| {
+                fec_sets
+                    .into_par_iter()
+                    .map(|(fec_set_index, num_data, num_coding, range)| {
+                        self.make_merkle_fec_set(
+                            &serialized_shreds[range],
+                            fec_set_index,
+                            num_data,
+                            num_coding,
+                            last_shred_index,
+                            is_last_in_slot,
+                        )
+                    })
+                    .collect()
+            })
+        });
+        gen_data_time.stop();
+
+        process_stats.serialize_elapsed += serialize_time.as_us();
+        process_stats.gen_data_elapsed += gen_data_time.as_us();
+
+        let mut data_shreds = vec![];
+        let mut coding_shreds = vec![];
+        for (mut data, mut coding) in fec_sets {
+            data_shreds.append(&mut data);
+            coding_shreds.append(&mut coding);
+        }
+        (data_shreds, coding_shreds, last_shred_index + 1)
+    }
+
+    /// Number of data and coding shreds of the next Merkle FEC set, given the size of the
+    /// data left to shred
+    fn merkle_fec_set_size(&self, remaining: usize) -> (usize, usize) {
+        let max_data = MAX_DATA_SHREDS_PER_FEC_BLOCK as usize;
+        let fec_set_size = |num_data| {
+            let num_coding = if self.fec_rate != 0.0 {
+                Self::calculate_num_coding_shreds(num_data, self.fec_rate, num_data)
+            } else {
+                0
+            };
+            (num_data, num_coding)
+        };
+        (1..max_data)
+            .map(&fec_set_size)
+            .find(|&(num_data, num_coding)| {
+                let proof_depth = MerkleTree::proof_depth(num_data + num_coding);
+                num_data * merkle_data_shred_capacity(proof_depth) >= remaining
+            })
+            .unwrap_or_else(|| fec_set_size(max_data))
+    }
+
+    fn make_merkle_fec_set(
+        &self,
+        data: &[u8],
+        fec_set_index: u32,
+        num_data: usize,
+        num_coding: usize,
+        last_shred_index: u32,
+        is_last_in_slot: bool,
+    ) -> (Vec<Shred>, Vec<Shred>) {
+        let proof_depth = MerkleTree::proof_depth(num_data + num_coding);
+        let shard_size = merkle_shard_size(proof_depth);
+        let mut data_shreds: Vec<Shred> = data
+            .chunks(merkle_data_shred_capacity(proof_depth))
+            .enumerate()
+            .map(|(i, shred_data)| {
+                let shred_index = fec_set_index + i as u32;
+                let is_last_data = shred_index == last_shred_index;
+                let mut shred = Shred::new_from_data(
+                    self.slot,
+                    shred_index,
+                    (self.slot - self.parent_slot) as u16,
+                    Some(shred_data),
+                    is_last_data,
+                    is_last_data && is_last_in_slot,
+                    self.reference_tick,
+                    self.version,
+                    fec_set_index,
+                );
+                shred.common_header.shred_type = ShredType::new_merkle_data(proof_depth);
+                Shred::serialize_obj_into(
+                    &mut 0,
+                    SIZE_OF_COMMON_SHRED_HEADER,
+                    &mut shred.payload,
+                    &shred.common_header,
+                )
+                .expect("Failed to write header into shred buffer");
+                shred
+            })
+            .collect();
+        debug_assert_eq!(data_shreds.len(), num_data);
+
+        let coding_block_offset = SIZE_OF_COMMON_SHRED_HEADER + SIZE_OF_CODING_SHRED_HEADER;
+        let mut coding_shreds: Vec<Shred> = (0..num_coding)
+            .map(|i| {
+                let (mut common_header, coding_header) = Self::new_coding_shred_header(
+                    self.slot,
+                    fec_set_index + i as u32,
+                    fec_set_index,
+                    num_data,
+                    num_coding,
+                    i,
+                    self.version,
+                );
+                common_header.shred_type = ShredType::new_merkle_coding(proof_depth);
+                Shred::new_empty_from_header(
+                    common_header,
+                    DataShredHeader::default(),
+                    coding_header,
+                )
+            })
+            .collect();
+        if num_coding > 0 {
+            let data_ptrs: Vec<_> = data_shreds
+                .iter()
+                .map(|shred| &shred.payload[SIZE_OF_SIGNATURE..SIZE_OF_SIGNATURE + shard_size])
+                .collect();
+            let mut coding_ptrs: Vec<_> = coding_shreds
+                .iter_mut()
+                .map(|shred| {
+                    &mut shred.payload[coding_block_offset..coding_block_offset + shard_size]
+                })
+                .collect();
+            Session::new(num_data, num_coding)
+                .and_then(|session| session.encode(&data_ptrs, coding_ptrs.as_mut_slice()))
+                .expect("Failed in erasure encode");
+        }
+
+        let proof_offset = merkle_proof_offset(proof_depth);
+        let (tree, signature) = {
+            let leaves: Vec<&[u8]> = data_shreds
+                .iter()
+                .chain(&coding_shreds)
+                .map(|shred| &shred.payload[SIZE_OF_SIGNATURE..proof_offset])
+                .collect();
+            let tree = MerkleTree::new(&leaves);
+            let signature = self.keypair.sign_message(tree.get_root().unwrap().as_ref());
+            (tree, signature)
+        };
+        for (i, shred) in data_shreds
+            .iter_mut()
+            .chain(coding_shreds.iter_mut())
+            .enumerate()
+        {
+            Self::set_merkle_proof(shred, &tree, i, proof_offset, signature);
+        }
+        (data_shreds, coding_shreds)
+    }
+
+    /// Writes the signature of the Merkle root and the inclusion proof of the leaf at `index`
+    /// into the shred
+    fn set_merkle_proof(
+        shred: &mut Shred,
+        tree: &MerkleTree,
+        index: usize,
+        proof_offset: usize,
+        signature: Signature,
+    ) {
+        let proof = tree.find_path(index).expect("leaf index out of range");
+        for (sibling, entry) in proof
+            .siblings()
+            .zip(shred.payload[proof_offset..].chunks_mut(SIZE_OF_MERKLE_PROOF_ENTRY))
+        {
+            entry.copy_from_slice(sibling.as_ref());
+        }
+        shred.payload[..SIZE_OF_SIGNATURE].copy_from_slice(signature.as_ref());
+        shred.common_header.signature = signature;
+    }
+
     pub fn sign_shred(signer: &Keypair, shred: &mut Shred) {
         let signature = signer.sign_message(&shred.payload[SIZE_OF_SIGNATURE..]);
         bincode::serialize_into(&mut shred.payload[..SIZE_OF_SIGNATURE], &signature)
@@ -831,6 +1158,16 @@ impl Shredder {
         slot: Slot,
     ) -> std::result::Result<Vec<Shred>, reed_solomon_erasure::Error> {
         Self::verify_consistent_shred_payload_sizes(&"try_recovery()", &shreds)?;
+        if shreds[0].is_merkle() {
+            return Self::try_merkle_recovery(
+                shreds,
+                num_data,
+                num_coding,
+                first_index,
+                first_code_index,
+                slot,
+            );
+        }
         let mut recovered_data = vec![];
         let fec_set_size = num_data + num_coding;
 
@@ -918,6 +1255,144 @@ impl Shredder {
         Ok(recovered_data)
     }
 
+    fn try_merkle_recovery(
+        shreds: Vec<Shred>,
+        num_data: usize,
+        num_coding: usize,
+        first_index: usize,
+        first_code_index: usize,
+        slot: Slot,
+    ) -> std::result::Result<Vec<Shred>, reed_solomon_erasure::Error> {
+        let fec_set_size = num_data + num_coding;
+        if num_coding == 0 || shreds.len() >= fec_set_size {
+            return Ok(vec![]);
+        }
+        let proof_depth = MerkleTree::proof_depth(fec_set_size);
+        let shard_size = merkle_shard_size(proof_depth);
+        let proof_offset = merkle_proof_offset(proof_depth);
+        let coding_block_offset = SIZE_OF_COMMON_SHRED_HEADER + SIZE_OF_CODING_SHRED_HEADER;
+        // Shreds were verified against the signed root on their own, but the shreds of a set
+        // may still have been signed under different roots by a misbehaving leader
+        let root = shreds[0].merkle_root();
+        let signature = shreds[0].signature();
+        let version = shreds[0].version();
+        let mut shards = vec![None; fec_set_size];
+        let mut data_leaves = vec![None; num_data];
+        for shred in &shreds {
+            if shred.common_header.shred_type.merkle_proof_depth() != Some(proof_depth) {
+                return Err(reed_solomon_erasure::Error::IncorrectShardSize);
+            }
+            if shred.merkle_root() != root || shred.signature() != signature {
+                return Ok(vec![]);
+            }
+            let position = Self::get_shred_index(shred, num_data, first_index, first_code_index)
+                .checked_sub(first_index)
+                .filter(|position| *position < fec_set_size)
+                .ok_or(reed_solomon_erasure::Error::InvalidIndex)?;
+            let shard_offset = if shred.is_data() {
+                SIZE_OF_SIGNATURE
+            } else {
+                coding_block_offset
+            };
+            shards[position] =
+                Some(shred.payload[shard_offset..shard_offset + shard_size].to_vec());
+            if position < num_data {
+                data_leaves[position] = Some(&shred.payload[SIZE_OF_SIGNATURE..proof_offset]);
+            }
+        }
+        let present: Vec<bool> = shards.iter().map(Option::is_some).collect();
+        let mut shards: Vec<Vec<u8>> = shards
+            .into_iter()
+            .map(|shard| shard.unwrap_or_else(|| vec![0; shard_size]))
+            .collect();
+
+        let session = Session::new(num_data, num_coding)?;
+        let mut blocks: Vec<(&mut [u8], bool)> = shards
+            .iter_mut()
+            .map(Vec::as_mut_slice)
+            .zip(present.iter().copied())
+            .collect();
+        session.decode_blocks(&mut blocks)?;
+
+        // Re-encode the coding shreds to rebuild the whole tree, which has to match the root
+        // the leader signed for the recovered shreds to be trusted
+        let mut coding_shreds: Vec<Shred> = (0..num_coding)
+            .map(|i| {
+                let (mut common_header, coding_header) = Self::new_coding_shred_header(
+                    slot,
+                    (first_code_index + i) as u32,
+                    first_index as u32,
+                    num_data,
+                    num_coding,
+                    i,
+                    version,
+                );
+                common_header.shred_type = ShredType::new_merkle_coding(proof_depth);
+                Shred::new_empty_from_header(
+                    common_header,
+                    DataShredHeader::default(),
+                    coding_header,
+                )
+            })
+            .collect();
+        {
+            let data_ptrs: Vec<&[u8]> = shards[..num_data].iter().map(Vec::as_slice).collect();
+            let mut coding_ptrs: Vec<&mut [u8]> = coding_shreds
+                .iter_mut()
+                .map(|shred| {
+                    &mut shred.payload[coding_block_offset..coding_block_offset + shard_size]
+                })
+                .collect();
+            session.encode(&data_ptrs, coding_ptrs.as_mut_slice())?;
+        }
+        // Recovered data shreds are zero between the shard and the proof
+        let recovered_leaves: Vec<Vec<u8>> = shards[..num_data]
+            .iter()
+            .map(|shard| {
+                let mut leaf = vec![0; proof_offset - SIZE_OF_SIGNATURE];
+                leaf[..shard_size].copy_from_slice(shard);
+                leaf
+            })
+            .collect();
+        let leaves: Vec<&[u8]> = data_leaves
+            .iter()
+            .zip(&recovered_leaves)
+            .map(|(leaf, recovered_leaf)| leaf.unwrap_or(recovered_leaf.as_slice()))
+            .chain(
+                coding_shreds
+                    .iter()
+                    .map(|shred| &shred.payload[SIZE_OF_SIGNATURE..proof_offset]),
+            )
+            .collect();
+        let tree = MerkleTree::new(&leaves);
+        if tree.get_root() != root.as_ref() {
+            return Ok(vec![]);
+        }
+
+        let mut recovered_data = vec![];
+        for (position, shard) in shards.iter().enumerate().take(num_data) {
+            if present[position] {
+                continue;
+            }
+            let mut payload = vec![0; SHRED_PAYLOAD_SIZE];
+            payload[SIZE_OF_SIGNATURE..SIZE_OF_SIGNATURE + shard_size].copy_from_slice(shard);
+            if let Ok(mut shred) = Shred::new_from_serialized_shred(payload) {
+                let shred_index = shred.index() as usize;
+                // Valid shred must be a data shred of the set, in the same slot as the
+                // original shreds
+                if shred.is_data()
+                    && shred.slot() == slot
+                    && shred_index == first_index + position
+                    && shred.common_header.shred_type.merkle_proof_depth() == Some(proof_depth)
+                {
+                    Self::set_merkle_proof(&mut shred, &tree, position, proof_offset, signature);
+                    recovered_data.push(shred)
+                }
+            }
+        }
+        Ok(recovered_data)
+    }
+
     /// Combines all shreds to recreate the original buffer
     pub fn deshred(shreds: &[Shred]) -> std::result::Result<Vec<u8>, reed_solomon_erasure::Error> {
         let num_data = shreds.len();
@@ -935,7 +1410,7 @@ impl Shredder {
                 return Err(reed_solomon_erasure::Error::TooFewDataShards);
             }
 
-            shreds.iter().map(|shred| &shred.payload).collect()
+            shreds.iter().map(Shred::data_payload).collect()
         };
 
         Ok(Self::reassemble_payload(num_data, data_shred_bufs))
@@ -954,14 +1429,10 @@ impl Shredder {
         }
     }
 
-    fn reassemble_payload(num_data: usize, data_shred_bufs: Vec<&Vec<u8>>) -> Vec<u8> {
-        let valid_data_len = SHRED_PAYLOAD_SIZE - SIZE_OF_DATA_SHRED_IGNORED_TAIL;
+    fn reassemble_payload(num_data: usize, data_shred_bufs: Vec<&[u8]>) -> Vec<u8> {
         data_shred_bufs[..num_data]
             .iter()
-            .flat_map(|data| {
-                let offset = SIZE_OF_COMMON_SHRED_HEADER + SIZE_OF_DATA_SHRED_HEADER;
-                data[offset..valid_data_len].iter()
-            })
+            .flat_map(|data| data.iter())
             .cloned()
             .collect()
     }
@@ -1046,9 +1517,9 @@ pub fn get_shred_slot_index_type(
         }
     }
 
-    let shred_type = p.data[OFFSET_OF_SHRED_TYPE];
-    if shred_type == DATA_SHRED || shred_type == CODING_SHRED {
-        return Some((slot, index, shred_type == DATA_SHRED));
+    let shred_type = ShredType(p.data[OFFSET_OF_SHRED_TYPE]);
+    if shred_type.is_data() || shred_type.is_code() {
+        return Some((slot, index, shred_type.is_data()));
     } else {
         stats.bad_shred_type += 1;
     }
@@ -1856,4 +2327,184 @@ pub mod tests {
         assert_eq!(None, get_shred_slot_index_type(&packet, &mut stats));
         assert_eq!(1, stats.bad_shred_type);
     }
+
+    #[test]
+    fn test_shred_type() {
+        assert!(ShredType(DATA_SHRED).is_data());
+        assert!(!ShredType(DATA_SHRED).is_code());
+        assert_eq!(ShredType(DATA_SHRED).merkle_proof_depth(), None);
+        assert!(ShredType(CODING_SHRED).is_code());
+        assert!(!ShredType(CODING_SHRED).is_data());
+        assert_eq!(ShredType(CODING_SHRED).merkle_proof_depth(), None);
+        for proof_depth in 0..=usize::from(MERKLE_PROOF_DEPTH_MASK) {
+            let shred_type = ShredType::new_merkle_data(proof_depth);
+            assert!(shred_type.is_data() && !shred_type.is_code());
+            assert_eq!(shred_type.merkle_proof_depth(), Some(proof_depth));
+            let shred_type = ShredType::new_merkle_coding(proof_depth);
+            assert!(shred_type.is_code() && !shred_type.is_data());
+            assert_eq!(shred_type.merkle_proof_depth(), Some(proof_depth));
+        }
+        assert!(!ShredType(u8::MAX).is_data());
+        assert!(!ShredType(u8::MAX).is_code());
+    }
+
+    fn make_merkle_shreds(
+        slot: Slot,
+        keypair: Arc<Keypair>,
+        num_ticks: u64,
+    ) -> (Vec<Entry>, Vec<Shred>, Vec<Shred>) {
+        let shredder = Shredder::new(slot, slot - 5, 1.0, keypair, 0, 0).unwrap();
+        let entries = create_ticks(num_ticks, 0, Hash::default());
+        let (data_shreds, coding_shreds, next_shred_index) = shredder.entries_to_merkle_shreds(
+            &entries,
+            true,
+            0,
+            &mut ProcessShredsStats::default(),
+        );
+        assert_eq!(next_shred_index as usize, data_shreds.len());
+        (entries, data_shreds, coding_shreds)
+    }
+
+    #[test]
+    fn test_merkle_shreds() {
+        let slot = 0x1234_5678_9abc_def0;
+        let keypair = Arc::new(Keypair::new());
+        let num_ticks = max_ticks_per_n_shreds(40, None);
+        let (entries, data_shreds, coding_shreds) =
+            make_merkle_shreds(slot, keypair.clone(), num_ticks);
+
+        // A full FEC set followed by the smallest set the rest of the data fits in
+        assert!(data_shreds.len() > MAX_DATA_SHREDS_PER_FEC_BLOCK as usize);
+        assert_eq!(data_shreds.len(), coding_shreds.len());
+        for (i, shred) in data_shreds.iter().enumerate() {
+            assert!(shred.is_merkle());
+            verify_test_data_shred(
+                shred,
+                i as u32,
+                slot,
+                slot - 5,
+                &keypair.pubkey(),
+                true,
+                i == data_shreds.len() - 1,
+                i == data_shreds.len() - 1,
+            );
+            assert_eq!(
+                shred.common_header.fec_set_index,
+                i as u32 / MAX_DATA_SHREDS_PER_FEC_BLOCK * MAX_DATA_SHREDS_PER_FEC_BLOCK
+            );
+            assert!(!shred.verify(&Pubkey::new_unique()));
+            assert_eq!(
+                Shred::new_from_serialized_shred(shred.payload.clone()).unwrap(),
+                *shred
+            );
+        }
+        for shred in &coding_shreds {
+            assert!(shred.is_merkle());
+            verify_test_code_shred(shred, shred.index(), slot, &keypair.pubkey(), true);
+        }
+
+        // Only the root of each FEC set is signed
+        let fec_sets: HashSet<_> = data_shreds
+            .iter()
+            .chain(&coding_shreds)
+            .map(|shred| (shred.signature(), shred.merkle_root().unwrap()))
+            .collect();
+        assert_eq!(fec_sets.len(), 2);
+
+        // Shreds no longer match the signed root once modified
+        let mut shred = data_shreds[3].clone();
+        shred.payload[SIZE_OF_COMMON_SHRED_HEADER + SIZE_OF_DATA_SHRED_HEADER] ^= 1;
+        assert!(!shred.verify(&keypair.pubkey()));
+        let mut shred = coding_shreds[3].clone();
+        let proof_offset = SHRED_PAYLOAD_SIZE
+            - MerkleTree::proof_depth(2 * MAX_DATA_SHREDS_PER_FEC_BLOCK as usize)
+                * SIZE_OF_MERKLE_PROOF_ENTRY;
+        shred.payload[proof_offset] ^= 1;
+        assert!(!shred.verify(&keypair.pubkey()));
+        // Every byte of a data shred after the signature is covered by the signed root,
+        // including the ones between the shard and the proof
+        let proof_depth = data_shreds[3].common_header.shred_type.merkle_proof_depth();
+        let proof_offset = merkle_proof_offset(proof_depth.unwrap());
+        let shard_end = SIZE_OF_SIGNATURE + merkle_shard_size(proof_depth.unwrap());
+        for offset in &[
+            SIZE_OF_SIGNATURE,
+            shard_end,
+            proof_offset - 1,
+            SHRED_PAYLOAD_SIZE - 1,
+        ] {
+            let mut shred = data_shreds[3].clone();
+            shred.payload[*offset] ^= 1;
+            assert!(!shred.verify(&keypair.pubkey()));
+        }
+
+        let data = Shredder::deshred(&data_shreds).unwrap();
+        let serialized_entries = bincode::serialize(&entries).unwrap();
+        assert_eq!(&data[..serialized_entries.len()], &serialized_entries[..]);
+        assert!(data[serialized_entries.len()..]
+            .iter()
+            .all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn test_merkle_shreds_recovery() {
+        let slot = 0x1234_5678_9abc_def0;
+        let keypair = Arc::new(Keypair::new());
+        let num_ticks = max_ticks_per_n_shreds(5, None);
+        let (_, data_shreds, coding_shreds) = make_merkle_shreds(slot, keypair.clone(), num_ticks);
+        let num_data = data_shreds.len();
+        let num_coding = coding_shreds.len();
+        assert!(num_data > 2);
+        assert_eq!(num_data, num_coding);
+
+        // Every other shred of the set is missing
+        let available: Vec<Shred> = data_shreds
+            .iter()
+            .chain(&coding_shreds)
+            .step_by(2)
+            .cloned()
+            .collect();
+        let recovered_data =
+            Shredder::try_recovery(available.clone(), num_data, num_coding, 0, 0, slot).unwrap();
+        let missing_data: Vec<Shred> = data_shreds.iter().skip(1).step_by(2).cloned().collect();
+        assert_eq!(recovered_data, missing_data);
+        for shred in &recovered_data {
+            assert!(shred.verify(&keypair.pubkey()));
+        }
+
+        // Nothing is recovered from shreds signed under different roots
+        let (_, other_data_shreds, _) = make_merkle_shreds(slot, keypair, num_ticks + 1);
+        let mut available = available;
+        available[0] = other_data_shreds[0].clone();
+        assert_eq!(
+            Shredder::try_recovery(available, num_data, num_coding, 0, 0, slot).unwrap(),
+            vec![]
+        );
+
+        // Too few shreds to recover the set
+        let available: Vec<Shred> = coding_shreds[1..].to_vec();
+        assert_matches!(
+            Shredder::try_recovery(available, num_data, num_coding, 0, 0, slot),
+            Err(reed_solomon_erasure::Error::TooFewShardsPresent)
+        );
+    }
+
+    #[test]
+    fn test_merkle_shred_offsets() {
+        let keypair = Arc::new(Keypair::new());
+        let (_, data_shreds, coding_shreds) =
+            make_merkle_shreds(8, keypair, max_ticks_per_n_shreds(1, None));
+        let mut packet = Packet::default();
+        let mut stats = ShredFetchStats::default();
+        data_shreds[0].copy_to_packet(&mut packet);
+        assert_eq!(
+            Some((8, 0, true)),
+            get_shred_slot_index_type(&packet, &mut stats)
+        );
+        coding_shreds[0].copy_to_packet(&mut packet);
+        assert_eq!(
+            Some((8, 0, false)),
+            get_shred_slot_index_type(&packet, &mut stats)
+        );
+        assert_eq!(stats, ShredFetchStats::default());
+    }
 }
@@ -1,5 +1,5 @@
 #![allow(clippy::implicit_hasher)]
-use crate::shred::{ShredType, SIZE_OF_NONCE};
+use crate::shred::{get_merkle_root, ShredType, OFFSET_OF_SHRED_TYPE, SIZE_OF_NONCE};
 use rayon::{
     iter::{
         IndexedParallelIterator, IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator,
@@ -18,12 +18,16 @@ use solana_perf::{
 use solana_rayon_threadlimit::get_thread_count;
 use solana_sdk::{
     clock::Slot,
+    hash::Hash,
     pubkey::Pubkey,
     signature::Signature,
     signature::{Keypair, Signer},
 };
 use std::sync::Arc;
-use std::{collections::HashMap, mem::size_of};
+use std::{
+    collections::{HashMap, HashSet},
+    mem::size_of,
+};
 
 pub const SIGN_SHRED_GPU_MIN: usize = 256;
 
@@ -35,6 +39,26 @@ lazy_static! {
         .unwrap();
 }
 
+fn is_merkle_packet(packet: &Packet) -> bool {
+    packet.meta.size > OFFSET_OF_SHRED_TYPE
+        && ShredType(packet.data[OFFSET_OF_SHRED_TYPE])
+            .merkle_proof_depth()
+            .is_some()
+}
+
+/// The slot, signature and signed Merkle root of a Merkle shred packet
+fn get_merkle_root_from_packet(packet: &Packet) -> Option<(Slot, Signature, Hash)> {
+    let slot_start = size_of::<Signature>() + size_of::<ShredType>();
+    let slot_end = slot_start + size_of::<u64>();
+    if packet.meta.discard || packet.meta.size < slot_end {
+        return None;
+    }
+    let slot: u64 = limited_deserialize(&packet.data[slot_start..slot_end]).ok()?;
+    let signature = Signature::new(&packet.data[..size_of::<Signature>()]);
+    let root = get_merkle_root(&packet.data[..packet.meta.size])?;
+    Some((slot, signature, root))
+}
+
 /// Assuming layout is
 /// signature: Signature
 /// signed_msg: {
@@ -43,6 +67,7 @@ lazy_static! {
 ///   ...
 /// }
 /// Signature is the first thing in the packet, and slot is the first thing in the signed message.
+/// Merkle shreds sign the root of the Merkle tree of their FEC set instead.
 pub fn verify_shred_cpu(packet: &Packet, slot_leaders: &HashMap<u64, [u8; 32]>) -> Option<u8> {
     let sig_start = 0;
     let sig_end = size_of::<Signature>();
@@ -56,6 +81,14 @@ pub fn verify_shred_cpu(packet: &Packet, slot_leaders: &HashMap<u64, [u8; 32]>)
     if packet.meta.size < slot_end {
         return Some(0);
     }
+    if is_merkle_packet(packet) {
+        let (slot, signature, root) = match get_merkle_root_from_packet(packet) {
+            Some(merkle_root) => merkle_root,
+            None => return Some(0),
+        };
+        let pubkey = slot_leaders.get(&slot)?;
+        return Some(u8::from(signature.verify(pubkey, root.as_ref())));
+    }
     let slot: u64 = limited_deserialize(&packet.data[slot_start..slot_end]).ok()?;
     let msg_end = if packet.meta.repair {
         packet.meta.size.saturating_sub(SIZE_OF_NONCE)
@@ -75,17 +108,87 @@ pub fn verify_shred_cpu(packet: &Packet, slot_leaders: &HashMap<u64, [u8; 32]>)
     Some(1)
 }
 
+/// Verifies the signatures of the Merkle shreds in the batches.  All the shreds of an FEC set
+/// share the signed root, so each root is only verified once.
+fn verify_merkle_shreds_cpu(
+    batches: &[Packets],
+    slot_leaders: &HashMap<u64, [u8; 32]>,
+) -> Vec<Vec<Option<u8>>> {
+    use rayon::prelude::*;
+    let merkle_roots: Vec<Vec<Option<(Slot, Signature, Hash)>>> =
+        SIGVERIFY_THREAD_POOL.install(|| {
+            batches
+                .into_par_iter()
+                .map(|p| {
+                    p.packets
+                        .par_iter()
+                        .map(|packet| {
+                            if is_merkle_packet(packet) {
+                                get_merkle_root_from_packet(packet)
+                            } else {
+                                None
+                            }
+                        })
+                        .collect()
+                })
+                .collect()
+        });
+    let unique_roots: Vec<(Slot, Signature, Hash)> = merkle_roots
+        .iter()
+        .flatten()
+        .flatten()
+        .copied()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let verified_roots: HashMap<(Slot, Signature, Hash), u8> =
+        SIGVERIFY_THREAD_POOL.install(|| {
+            unique_roots
+                .into_par_iter()
+                .map(|(slot, signature, root)| {
+                    let verified = slot_leaders
+                        .get(&slot)
+                        .map(|pubkey| signature.verify(pubkey, root.as_ref()))
+                        .unwrap_or(false);
+                    ((slot, signature, root), u8::from(verified))
+                })
+                .collect()
+        });
+    batches
+        .iter()
+        .zip(merkle_roots)
+        .map(|(p, roots)| {
+            p.packets
+                .iter()
+                .zip(roots)
+                .map(|(packet, root)| match root {
+                    Some(root) => Some(verified_roots[&root]),
+                    // Malformed Merkle shreds fail verification
+                    None if is_merkle_packet(packet) => Some(0),
+                    None => None,
+                })
+                .collect()
+        })
+        .collect()
+}
+
 fn verify_shreds_cpu(batches: &[Packets], slot_leaders: &HashMap<u64, [u8; 32]>) -> Vec<Vec<u8>> {
     use rayon::prelude::*;
     let count = batch_size(batches);
     debug!("CPU SHRED ECDSA for {}", count);
+    let merkle_results = verify_merkle_shreds_cpu(batches, slot_leaders);
     let rv = SIGVERIFY_THREAD_POOL.install(|| {
         batches
             .into_par_iter()
-            .map(|p| {
+            .zip(merkle_results)
+            .map(|(p, merkle_results)| {
                 p.packets
                     .par_iter()
-                    .map(|p| verify_shred_cpu(p, slot_leaders).unwrap_or(0))
+                    .zip(merkle_results)
+                    .map(|(p, merkle_result)| {
+                        merkle_result
+                            .unwrap_or_else(|| verify_shred_cpu(p, slot_leaders).unwrap_or(0))
+                    })
                     .collect()
             })
             .collect()
@@ -205,7 +308,8 @@ fn shred_gpu_offsets(
             };
             signature_offsets.push(sig_start as u32);
             msg_start_offsets.push(msg_start as u32);
-            let msg_size = if msg_end < msg_start {
+            // Merkle shreds don't sign their payload, they are verified on the cpu
+            let msg_size = if msg_end < msg_start || is_merkle_packet(packet) {
                 0
             } else {
                 msg_end - msg_start
@@ -290,6 +394,14 @@ pub fn verify_shreds_gpu(
     trace!("out buf {:?}", out);
 
     sigverify::copy_return_values(&v_sig_lens, &out, &mut rvs);
+    let merkle_results = verify_merkle_shreds_cpu(batches, slot_leaders);
+    for (rv, merkle_results) in rvs.iter_mut().zip(merkle_results) {
+        for (rv, merkle_result) in rv.iter_mut().zip(merkle_results) {
+            if let Some(merkle_result) = merkle_result {
+                *rv = merkle_result;
+            }
+        }
+    }
 
     inc_new_counter_debug!("ed25519_shred_verify_gpu", count);
     rvs
@@ -303,6 +415,7 @@ pub fn verify_shreds_gpu(
 ///   ...
 /// }
 /// Signature is the first thing in the packet, and slot is the first thing in the signed message.
+/// Merkle shreds sign the root of the Merkle tree of their FEC set instead.
 fn sign_shred_cpu(keypair: &Keypair, packet: &mut Packet) {
     let sig_start = 0;
     let sig_end = sig_start + size_of::<Signature>();
@@ -312,7 +425,13 @@ fn sign_shred_cpu(keypair: &Keypair, packet: &mut Packet) {
         packet.meta.size >= msg_end,
         "packet is not large enough for a signature"
     );
-    let signature = keypair.sign_message(&packet.data[msg_start..msg_end]);
+    let signature = if is_merkle_packet(packet) {
+        let root = get_merkle_root(&packet.data[..packet.meta.size])
+            .expect("Merkle shred without a valid inclusion proof");
+        keypair.sign_message(root.as_ref())
+    } else {
+        keypair.sign_message(&packet.data[msg_start..msg_end])
+    };
     trace!("signature {:?}", signature);
     packet.data[0..sig_end].copy_from_slice(&signature.as_ref());
 }
@@ -442,6 +561,10 @@ pub fn sign_shreds_gpu(
                     .par_iter_mut()
                     .enumerate()
                     .for_each(|(packet_ix, packet)| {
+                        // The gpu signs the payload, Merkle shreds sign their root instead
+                        if is_merkle_packet(packet) {
+                            return sign_shred_cpu(keypair, packet);
+                        }
                         let sig_ix = packet_ix + num_packets;
                         let sig_start = sig_ix * sig_size;
                         let sig_end = sig_start + sig_size;
@@ -456,7 +579,12 @@ pub fn sign_shreds_gpu(
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use crate::shred::{Shred, Shredder, SIZE_OF_DATA_SHRED_PAYLOAD};
+    use crate::{
+        entry::create_ticks,
+        shred::{
+            max_ticks_per_n_shreds, ProcessShredsStats, Shred, Shredder, SIZE_OF_DATA_SHRED_PAYLOAD,
+        },
+    };
     use solana_sdk::signature::{Keypair, Signer};
 
     fn run_test_sigverify_shred_cpu(slot: Slot) {
@@ -711,4 +839,120 @@ pub mod tests {
     fn test_sigverify_shreds_sign_cpu() {
         run_test_sigverify_shreds_sign_cpu(0xdead_c0de);
     }
+
+    fn run_test_sigverify_merkle_shreds(slot: Slot) {
+        solana_logger::setup();
+        let recycler_cache = RecyclerCache::new("", "");
+        let keypair = Arc::new(Keypair::new());
+        let shredder = Shredder::new(slot, slot - 1, 1.0, keypair.clone(), 0, 0).unwrap();
+        let entries = create_ticks(max_ticks_per_n_shreds(40, None), 0, Hash::default());
+        let (data_shreds, coding_shreds, _) = shredder.entries_to_merkle_shreds(
+            &entries,
+            true,
+            0,
+            &mut ProcessShredsStats::default(),
+        );
+        // Merkle shreds can be verified in the same batch as legacy shreds
+        let mut legacy_shred = Shred::new_from_data(
+            slot,
+            0xc0de,
+            1,
+            Some(&[1, 2, 3, 4]),
+            true,
+            true,
+            0,
+            0,
+            0xc0de,
+        );
+        Shredder::sign_shred(&keypair, &mut legacy_shred);
+        let shreds: Vec<_> = data_shreds
+            .iter()
+            .chain(&coding_shreds)
+            .chain(std::iter::once(&legacy_shred))
+            .collect();
+        let mut batch = [Packets::default()];
+        batch[0].packets.resize(shreds.len(), Packet::default());
+        for (shred, packet) in shreds.iter().zip(batch[0].packets.iter_mut()) {
+            shred.copy_to_packet(packet);
+        }
+
+        let leader_slots = [
+            (std::u64::MAX, Pubkey::default().to_bytes()),
+            (slot, keypair.pubkey().to_bytes()),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        assert_eq!(
+            verify_shreds_cpu(&batch, &leader_slots),
+            vec![vec![1; shreds.len()]]
+        );
+        assert_eq!(
+            verify_shreds_gpu(&batch, &leader_slots, &recycler_cache),
+            vec![vec![1; shreds.len()]]
+        );
+        for packet in &batch[0].packets {
+            assert_eq!(verify_shred_cpu(packet, &leader_slots), Some(1));
+        }
+
+        // A modified shred fails without failing the rest of its FEC set
+        let mut expected = vec![1; shreds.len()];
+        batch[0].packets[3].data[200] ^= 1;
+        batch[0].packets[data_shreds.len() + 3].meta.discard = true;
+        expected[3] = 0;
+        expected[data_shreds.len() + 3] = 0;
+        assert_eq!(
+            verify_shreds_cpu(&batch, &leader_slots),
+            vec![expected.clone()]
+        );
+        assert_eq!(
+            verify_shreds_gpu(&batch, &leader_slots, &recycler_cache),
+            vec![expected]
+        );
+
+        // Signing Merkle shreds signs the roots their payloads commit to
+        let other_keypair = Keypair::new();
+        let pinned_keypair = sign_shreds_gpu_pinned_keypair(&other_keypair, &recycler_cache);
+        let pinned_keypair = Some(Arc::new(pinned_keypair));
+        let mut signed_batches = vec![batch[0].clone(), batch[0].clone()];
+        sign_shreds_cpu(&other_keypair, &mut signed_batches[..1]);
+        sign_shreds_gpu(
+            &other_keypair,
+            &pinned_keypair,
+            &mut signed_batches[1..],
+            &recycler_cache,
+        );
+        let other_leader_slots = [(slot, other_keypair.pubkey().to_bytes())]
+            .iter()
+            .cloned()
+            .collect();
+        let mut expected = vec![1; shreds.len()];
+        expected[data_shreds.len() + 3] = 0;
+        assert_eq!(
+            verify_shreds_cpu(&signed_batches, &other_leader_slots),
+            vec![expected.clone(), expected]
+        );
+
+        let wrong_keypair = Keypair::new();
+        let leader_slots = [
+            (std::u64::MAX, Pubkey::default().to_bytes()),
+            (slot, wrong_keypair.pubkey().to_bytes()),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        assert_eq!(
+            verify_shreds_cpu(&batch, &leader_slots),
+            vec![vec![0; shreds.len()]]
+        );
+        assert_eq!(
+            verify_shreds_gpu(&batch, &leader_slots, &recycler_cache),
+            vec![vec![0; shreds.len()]]
+        );
+    }
+
+    #[test]
+    fn test_sigverify_merkle_shreds() {
+        run_test_sigverify_merkle_shreds(0xdead_c0de);
+    }
 }
@@ -34,6 +34,7 @@ pub fn safe_clone_config(config: &ValidatorConfig) -> ValidatorConfig {
         accounts_hash_interval_slots: config.accounts_hash_interval_slots,
         max_genesis_archive_unpacked_size: config.max_genesis_archive_unpacked_size,
         wal_recovery_mode: config.wal_recovery_mode.clone(),
+        ledger_column_options: config.ledger_column_options.clone(),
         poh_verify: config.poh_verify,
         cuda: config.cuda,
         require_tower: config.require_tower,
@@ -43,16 +44,23 @@ pub fn safe_clone_config(config: &ValidatorConfig) -> ValidatorConfig {
         bpf_jit: config.bpf_jit,
         send_transaction_retry_ms: config.send_transaction_retry_ms,
         send_transaction_leader_forward_count: config.send_transaction_leader_forward_count,
+        send_transaction_use_quic: config.send_transaction_use_quic,
         no_poh_speed_test: config.no_poh_speed_test,
         poh_pinned_cpu_core: config.poh_pinned_cpu_core,
         account_indexes: config.account_indexes.clone(),
         accounts_db_caching_enabled: config.accounts_db_caching_enabled,
+        accounts_index_config: config.accounts_index_config.clone(),
+        accounts_cold_storage_slot_age: config.accounts_cold_storage_slot_age,
+        accounts_cold_storage_cache_size: config.accounts_cold_storage_cache_size,
+        enable_transaction_scheduler: config.enable_transaction_scheduler,
         warp_slot: config.warp_slot,
         accounts_db_test_hash_calculation: config.accounts_db_test_hash_calculation,
         accounts_db_use_index_hash_calculation: config.accounts_db_use_index_hash_calculation,
         tpu_coalesce_ms: config.tpu_coalesce_ms,
+        tpu_use_quic: config.tpu_use_quic,
         validator_exit: Arc::new(RwLock::new(ValidatorExit::default())),
         poh_hashes_per_batch: config.poh_hashes_per_batch,
+        accountsdb_plugin_config_files: config.accountsdb_plugin_config_files.clone(),
     }
 }
 
@@ -2479,6 +2479,7 @@ fn setup_snapshot_validator_config(
     let snapshot_output_path = tempfile::tempdir_in(farf_dir()).unwrap();
     let snapshot_config = SnapshotConfig {
         snapshot_interval_slots,
+        incremental_snapshot_interval_slots: Slot::MAX,
         snapshot_package_output_path: PathBuf::from(snapshot_output_path.path()),
         snapshot_path: PathBuf::from(snapshot_dir.path()),
         archive_format: ArchiveFormat::TarBzip2,
@@ -36,6 +36,11 @@ impl<'a> ProofEntry<'a> {
         assert!((None == left_sibling) ^ (None == right_sibling));
         Self(target, left_sibling, right_sibling)
     }
+
+    /// The node hashed together with the path node to get `target`
+    pub fn sibling(&self) -> &'a Hash {
+        self.1.or(self.2).unwrap()
+    }
 }
 
 #[derive(Debug, Default, PartialEq)]
@@ -60,6 +65,11 @@ impl<'a> Proof<'a> {
         });
         matches!(result, Some(_))
     }
+
+    /// The siblings along the path, from the leaf level up to the root
+    pub fn siblings(&self) -> impl Iterator<Item = &'a Hash> + '_ {
+        self.0.iter().map(ProofEntry::sibling)
+    }
 }
 
 impl MerkleTree {
@@ -135,6 +145,40 @@ impl MerkleTree {
         mt
     }
 
+    /// Number of siblings on the path from any leaf to the root of a tree with `leaf_count`
+    /// leaves
+    pub fn proof_depth(leaf_count: usize) -> usize {
+        let mut depth = 0;
+        let mut level_len = leaf_count;
+        while level_len > 1 {
+            level_len = MerkleTree::next_level_len(level_len);
+            depth += 1;
+        }
+        depth
+    }
+
+    /// Computes the root of the tree holding `leaf` at `index`, given the siblings along its
+    /// path from the leaf level up, as returned by `Proof::siblings`
+    pub fn root_from_siblings<T: AsRef<[u8]>>(
+        leaf: &[u8],
+        index: usize,
+        siblings: impl IntoIterator<Item = T>,
+    ) -> Hash {
+        let (root, _) =
+            siblings
+                .into_iter()
+                .fold((hash_leaf!(leaf), index), |(node, index), sibling| {
+                    let sibling = sibling.as_ref();
+                    let parent = if index % 2 == 0 {
+                        hash_intermediate!(node, sibling)
+                    } else {
+                        hash_intermediate!(sibling, node)
+                    };
+                    (parent, index / 2)
+                });
+        root
+    }
+
     pub fn get_root(&self) -> Option<&Hash> {
         self.nodes.iter().last()
     }
@@ -247,6 +291,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_root_from_siblings() {
+        for leaf_count in 1..TEST.len() {
+            let items = &TEST[..leaf_count];
+            let mt = MerkleTree::new(items);
+            let root = mt.get_root().unwrap();
+            for (i, item) in items.iter().enumerate() {
+                let path = mt.find_path(i).unwrap();
+                assert_eq!(path.siblings().count(), MerkleTree::proof_depth(leaf_count));
+                assert_eq!(
+                    MerkleTree::root_from_siblings(item, i, path.siblings()),
+                    *root
+                );
+                // The path only proves the leaf at its own index
+                assert_ne!(
+                    MerkleTree::root_from_siblings(BAD[0], i, path.siblings()),
+                    *root
+                );
+                if i ^ 1 < leaf_count {
+                    assert_ne!(
+                        MerkleTree::root_from_siblings(item, i ^ 1, path.siblings()),
+                        *root
+                    );
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_proof_entry_instantiation_lsib_set() {
         ProofEntry::new(&Hash::default(), Some(&Hash::default()), None);
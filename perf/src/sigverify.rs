@@ -12,7 +12,7 @@ use bincode::serialized_size;
 use rayon::ThreadPool;
 use solana_metrics::inc_new_counter_debug;
 use solana_rayon_threadlimit::get_thread_count;
-use solana_sdk::message::MessageHeader;
+use solana_sdk::message::{MessageHeader, MESSAGE_VERSION_PREFIX};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::short_vec::decode_len;
 use solana_sdk::signature::Signature;
@@ -66,6 +66,7 @@ pub enum PacketError {
     InvalidSignatureLen,
     MismatchSignatureLen,
     PayerNotWritable,
+    UnsupportedVersion,
 }
 
 impl std::convert::From<std::boxed::Box<bincode::ErrorKind>> for PacketError {
@@ -167,16 +168,34 @@ fn do_get_packet_offsets(
         return Err(PacketError::InvalidSignatureLen);
     }
 
+    // read the message prefix. Versioned messages start with a byte that has its top bit set,
+    // legacy messages start with the MessageHeader
+    let message_prefix = packet.data[msg_start_offset];
+    let message_header_offset = if message_prefix & MESSAGE_VERSION_PREFIX != 0 {
+        let version = message_prefix & !MESSAGE_VERSION_PREFIX;
+        if version != 0 {
+            return Err(PacketError::UnsupportedVersion);
+        }
+        // the header follows the prefix byte
+        let message_header_offset = msg_start_offset + 1;
+        if (message_header_offset + message_header_size + 1) > packet.meta.size {
+            return Err(PacketError::InvalidSignatureLen);
+        }
+        message_header_offset
+    } else {
+        msg_start_offset
+    };
+
     // read MessageHeader.num_required_signatures (serialized with u8)
-    let sig_len_maybe_trusted = packet.data[msg_start_offset] as usize;
+    let sig_len_maybe_trusted = packet.data[message_header_offset] as usize;
 
-    let message_account_keys_len_offset = msg_start_offset + message_header_size;
+    let message_account_keys_len_offset = message_header_offset + message_header_size;
 
     // This reads and compares the MessageHeader num_required_signatures and
     // num_readonly_signed_accounts bytes. If num_required_signatures is not larger than
     // num_readonly_signed_accounts, the first account is not debitable, and cannot be charged
     // required transaction fees.
-    if packet.data[msg_start_offset] <= packet.data[msg_start_offset + 1] {
+    if packet.data[message_header_offset] <= packet.data[message_header_offset + 1] {
         return Err(PacketError::PayerNotWritable);
     }
 
@@ -192,7 +211,7 @@ fn do_get_packet_offsets(
 
     let sig_start = current_offset as usize + sig_size;
     let msg_start = current_offset as usize + msg_start_offset;
-    let pubkey_start = msg_start + message_header_size + pubkey_len_size;
+    let pubkey_start = current_offset as usize + message_account_keys_len_offset + pubkey_len_size;
 
     if sig_len_maybe_trusted != sig_len_untrusted {
         return Err(PacketError::MismatchSignatureLen);
@@ -443,9 +462,10 @@ mod tests {
     use crate::test_tx::{test_multisig_tx, test_tx};
     use bincode::{deserialize, serialize};
     use solana_sdk::hash::Hash;
-    use solana_sdk::message::{Message, MessageHeader};
-    use solana_sdk::signature::Signature;
-    use solana_sdk::transaction::Transaction;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::{v0, Message, MessageHeader, VersionedMessage};
+    use solana_sdk::signature::{Keypair, Signature, Signer};
+    use solana_sdk::transaction::{Transaction, VersionedTransaction};
 
     const SIG_OFFSET: usize = 1;
 
@@ -549,9 +569,53 @@ mod tests {
 
         let unsanitized_packet_offsets = sigverify::do_get_packet_offsets(&packet, 0);
 
+        // a first message byte with the top bit set is a version prefix
         assert_eq!(
             unsanitized_packet_offsets,
-            Err(PacketError::MismatchSignatureLen)
+            Err(PacketError::UnsupportedVersion)
+        );
+    }
+
+    #[test]
+    fn test_versioned_transaction_layout() {
+        let keypair = Keypair::new();
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![AccountMeta::new(Pubkey::new_unique(), false)],
+        );
+        let message =
+            v0::Message::try_compile(&keypair.pubkey(), &[instruction], &[], Hash::default())
+                .unwrap();
+        let tx = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&keypair]).unwrap();
+        let tx_bytes = serialize(&tx).unwrap();
+        let mut packet = Packet::default();
+        packet.meta.size = tx_bytes.len();
+        packet.data[..packet.meta.size].copy_from_slice(&tx_bytes);
+
+        let packet_offsets = sigverify::do_get_packet_offsets(&packet, 0).unwrap();
+        assert_eq!(packet_offsets.sig_len, 1);
+        assert_eq!(
+            memfind(&tx_bytes, &tx.message_data()),
+            Some(packet_offsets.msg_start as usize)
+        );
+        assert_eq!(
+            memfind(&tx_bytes, keypair.pubkey().as_ref()),
+            Some(packet_offsets.pubkey_start as usize)
+        );
+
+        let mut batches = generate_packet_vec(&packet, 1, 1);
+        let recycler = Recycler::new_without_limit("");
+        let recycler_out = Recycler::new_without_limit("");
+        sigverify::ed25519_verify(&mut batches, &recycler, &recycler_out);
+        assert!(!batches[0].packets[0].meta.discard);
+
+        // versions other than 0 are not supported yet
+        let msg_start = packet_offsets.msg_start as usize;
+        packet.data[msg_start] = MESSAGE_VERSION_PREFIX | 1;
+        assert_eq!(
+            sigverify::do_get_packet_offsets(&packet, 0),
+            Err(PacketError::UnsupportedVersion)
         );
     }
 
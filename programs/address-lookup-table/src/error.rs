@@ -0,0 +1,29 @@
+use solana_sdk::transaction::TransactionError;
+use thiserror::Error;
+
+/// Reasons the addresses of a message lookup could not be loaded
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AddressLookupError {
+    #[error("Attempted to lookup addresses from a table that does not exist")]
+    LookupTableAccountNotFound,
+
+    #[error("Attempted to lookup addresses from an account owned by the wrong program")]
+    InvalidAccountOwner,
+
+    #[error("Attempted to lookup addresses from an invalid account")]
+    InvalidAccountData,
+
+    #[error("Address lookup contains an invalid index")]
+    InvalidLookupIndex,
+}
+
+impl From<AddressLookupError> for TransactionError {
+    fn from(err: AddressLookupError) -> Self {
+        match err {
+            AddressLookupError::LookupTableAccountNotFound
+            | AddressLookupError::InvalidAccountOwner
+            | AddressLookupError::InvalidAccountData => Self::AccountNotFound,
+            AddressLookupError::InvalidLookupIndex => Self::InvalidAccountIndex,
+        }
+    }
+}
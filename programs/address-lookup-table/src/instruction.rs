@@ -0,0 +1,137 @@
+use crate::{id, state::LOOKUP_TABLE_SIZE};
+use serde_derive::{Deserialize, Serialize};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_instruction, sysvar,
+};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum ProgramInstruction {
+    /// Initialize an address lookup table account created by the system program
+    ///
+    /// # Account references
+    ///   0. [WRITE, SIGNER] Uninitialized address lookup table account
+    ///   1. [] Authority allowed to modify the table
+    InitializeLookupTable,
+
+    /// Append addresses to a lookup table.  They can be looked up from the next slot on.
+    ///
+    /// # Account references
+    ///   0. [WRITE] Address lookup table account
+    ///   1. [SIGNER] Lookup table authority
+    ///   2. [] Clock sysvar
+    ExtendLookupTable { new_addresses: Vec<Pubkey> },
+
+    /// Permanently remove the authority of a lookup table, so that its addresses can't change
+    ///
+    /// # Account references
+    ///   0. [WRITE] Address lookup table account
+    ///   1. [SIGNER] Lookup table authority
+    FreezeLookupTable,
+
+    /// Deactivate a lookup table, so that it can be closed once the deactivation slot is no
+    /// longer in the slot hashes history
+    ///
+    /// # Account references
+    ///   0. [WRITE] Address lookup table account
+    ///   1. [SIGNER] Lookup table authority
+    ///   2. [] Clock sysvar
+    DeactivateLookupTable,
+
+    /// Close a deactivated lookup table and withdraw its lamports
+    ///
+    /// # Account references
+    ///   0. [WRITE] Address lookup table account
+    ///   1. [SIGNER] Lookup table authority
+    ///   2. [WRITE] Recipient of the lamports
+    ///   3. [] Clock sysvar
+    ///   4. [] Slot hashes sysvar
+    CloseLookupTable,
+}
+
+/// Create and initialize a lookup table account
+pub fn create_lookup_table(
+    payer: &Pubkey,
+    lookup_table: &Pubkey,
+    authority: &Pubkey,
+    lamports: u64,
+) -> Vec<Instruction> {
+    let create_ix = system_instruction::create_account(
+        payer,
+        lookup_table,
+        lamports,
+        LOOKUP_TABLE_SIZE as u64,
+        &id(),
+    );
+    let init_ix = initialize_lookup_table(lookup_table, authority);
+    vec![create_ix, init_ix]
+}
+
+pub fn initialize_lookup_table(lookup_table: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction::new_with_bincode(
+        id(),
+        &ProgramInstruction::InitializeLookupTable,
+        vec![
+            AccountMeta::new(*lookup_table, true),
+            AccountMeta::new_readonly(*authority, false),
+        ],
+    )
+}
+
+pub fn extend_lookup_table(
+    lookup_table: &Pubkey,
+    authority: &Pubkey,
+    new_addresses: Vec<Pubkey>,
+) -> Instruction {
+    Instruction::new_with_bincode(
+        id(),
+        &ProgramInstruction::ExtendLookupTable { new_addresses },
+        vec![
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+pub fn freeze_lookup_table(lookup_table: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction::new_with_bincode(
+        id(),
+        &ProgramInstruction::FreezeLookupTable,
+        vec![
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+pub fn deactivate_lookup_table(lookup_table: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction::new_with_bincode(
+        id(),
+        &ProgramInstruction::DeactivateLookupTable,
+        vec![
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+pub fn close_lookup_table(
+    lookup_table: &Pubkey,
+    authority: &Pubkey,
+    recipient: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bincode(
+        id(),
+        &ProgramInstruction::CloseLookupTable,
+        vec![
+            AccountMeta::new(*lookup_table, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+        ],
+    )
+}
@@ -0,0 +1,12 @@
+#![allow(clippy::integer_arithmetic)]
+//! Address lookup table program
+//!
+//! Stores lists of addresses on chain, so that versioned transactions can reference accounts by
+//! a one byte index into a table instead of listing their full address.
+
+pub mod error;
+pub mod instruction;
+pub mod processor;
+pub mod state;
+
+solana_sdk::declare_id!("AddressLookupTab1e1111111111111111111111111");
@@ -0,0 +1,514 @@
+use crate::{
+    instruction::ProgramInstruction,
+    state::{AddressLookupTable, ProgramState, LOOKUP_TABLE_MAX_ADDRESSES},
+};
+use log::*;
+use solana_sdk::{
+    account::{ReadableAccount, WritableAccount},
+    clock::Clock,
+    ic_msg,
+    instruction::InstructionError,
+    keyed_account::{from_keyed_account, next_keyed_account, KeyedAccount},
+    process_instruction::InvokeContext,
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    sysvar::slot_hashes::SlotHashes,
+};
+use std::convert::TryFrom;
+
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    keyed_accounts: &[KeyedAccount],
+    data: &[u8],
+    invoke_context: &mut dyn InvokeContext,
+) -> Result<(), InstructionError> {
+    trace!("process_instruction: {:?}", data);
+    trace!("keyed_accounts: {:?}", keyed_accounts);
+
+    let keyed_accounts_iter = &mut keyed_accounts.iter();
+    let table_account = next_keyed_account(keyed_accounts_iter)?;
+    if table_account.owner()? != crate::id() {
+        return Err(InstructionError::InvalidAccountOwner);
+    }
+
+    match limited_deserialize(data)? {
+        ProgramInstruction::InitializeLookupTable => initialize_lookup_table(
+            invoke_context,
+            table_account,
+            next_keyed_account(keyed_accounts_iter)?,
+        ),
+        ProgramInstruction::ExtendLookupTable { new_addresses } => extend_lookup_table(
+            invoke_context,
+            table_account,
+            next_keyed_account(keyed_accounts_iter)?,
+            &from_keyed_account::<Clock>(next_keyed_account(keyed_accounts_iter)?)?,
+            new_addresses,
+        ),
+        ProgramInstruction::FreezeLookupTable => freeze_lookup_table(
+            invoke_context,
+            table_account,
+            next_keyed_account(keyed_accounts_iter)?,
+        ),
+        ProgramInstruction::DeactivateLookupTable => deactivate_lookup_table(
+            invoke_context,
+            table_account,
+            next_keyed_account(keyed_accounts_iter)?,
+            &from_keyed_account::<Clock>(next_keyed_account(keyed_accounts_iter)?)?,
+        ),
+        ProgramInstruction::CloseLookupTable => close_lookup_table(
+            invoke_context,
+            table_account,
+            next_keyed_account(keyed_accounts_iter)?,
+            next_keyed_account(keyed_accounts_iter)?,
+            &from_keyed_account::<Clock>(next_keyed_account(keyed_accounts_iter)?)?,
+            &from_keyed_account::<SlotHashes>(next_keyed_account(keyed_accounts_iter)?)?,
+        ),
+    }
+}
+
+fn initialize_lookup_table(
+    invoke_context: &mut dyn InvokeContext,
+    table_account: &KeyedAccount,
+    authority_account: &KeyedAccount,
+) -> Result<(), InstructionError> {
+    if table_account.signer_key().is_none() {
+        ic_msg!(invoke_context, "Lookup table account must be a signer");
+        return Err(InstructionError::MissingRequiredSignature);
+    }
+    if ProgramState::deserialize(&table_account.try_account_ref()?.data())?
+        != ProgramState::Uninitialized
+    {
+        ic_msg!(invoke_context, "Lookup table is already initialized");
+        return Err(InstructionError::AccountAlreadyInitialized);
+    }
+
+    AddressLookupTable::new(*authority_account.unsigned_key())
+        .serialize_into(table_account.try_account_ref_mut()?.data_as_mut_slice())
+}
+
+fn check_authority(
+    invoke_context: &mut dyn InvokeContext,
+    table: &AddressLookupTable,
+    authority_account: &KeyedAccount,
+) -> Result<(), InstructionError> {
+    match table.meta.authority {
+        None => {
+            ic_msg!(invoke_context, "Lookup table is frozen");
+            Err(InstructionError::Immutable)
+        }
+        Some(authority) if authority != *authority_account.unsigned_key() => {
+            ic_msg!(invoke_context, "Incorrect lookup table authority");
+            Err(InstructionError::IncorrectAuthority)
+        }
+        Some(_) if authority_account.signer_key().is_none() => {
+            ic_msg!(invoke_context, "Lookup table authority did not sign");
+            Err(InstructionError::MissingRequiredSignature)
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+fn extend_lookup_table(
+    invoke_context: &mut dyn InvokeContext,
+    table_account: &KeyedAccount,
+    authority_account: &KeyedAccount,
+    clock: &Clock,
+    new_addresses: Vec<Pubkey>,
+) -> Result<(), InstructionError> {
+    let mut table = AddressLookupTable::deserialize(&table_account.try_account_ref()?.data())?;
+    check_authority(invoke_context, &table, authority_account)?;
+    if table.meta.is_deactivated() {
+        ic_msg!(
+            invoke_context,
+            "Deactivated lookup tables cannot be extended"
+        );
+        return Err(InstructionError::InvalidArgument);
+    }
+    if new_addresses.is_empty() {
+        ic_msg!(
+            invoke_context,
+            "Must extend lookup table with at least one address"
+        );
+        return Err(InstructionError::InvalidInstructionData);
+    }
+    let new_len = table.addresses.len().saturating_add(new_addresses.len());
+    if new_len > LOOKUP_TABLE_MAX_ADDRESSES {
+        ic_msg!(
+            invoke_context,
+            "Extended lookup table length {} would exceed max capacity of {}",
+            new_len,
+            LOOKUP_TABLE_MAX_ADDRESSES
+        );
+        return Err(InstructionError::InvalidInstructionData);
+    }
+
+    if clock.slot != table.meta.last_extended_slot {
+        table.meta.last_extended_slot = clock.slot;
+        table.meta.last_extended_slot_start_index = u8::try_from(table.addresses.len())
+            .map_err(|_| InstructionError::InvalidAccountData)?;
+    }
+    table.addresses.extend(new_addresses);
+    table.serialize_into(table_account.try_account_ref_mut()?.data_as_mut_slice())
+}
+
+fn freeze_lookup_table(
+    invoke_context: &mut dyn InvokeContext,
+    table_account: &KeyedAccount,
+    authority_account: &KeyedAccount,
+) -> Result<(), InstructionError> {
+    let mut table = AddressLookupTable::deserialize(&table_account.try_account_ref()?.data())?;
+    check_authority(invoke_context, &table, authority_account)?;
+    if table.meta.is_deactivated() {
+        ic_msg!(invoke_context, "Deactivated lookup tables cannot be frozen");
+        return Err(InstructionError::InvalidArgument);
+    }
+    if table.addresses.is_empty() {
+        ic_msg!(invoke_context, "Empty lookup tables cannot be frozen");
+        return Err(InstructionError::InvalidInstructionData);
+    }
+
+    table.meta.authority = None;
+    table.serialize_into(table_account.try_account_ref_mut()?.data_as_mut_slice())
+}
+
+fn deactivate_lookup_table(
+    invoke_context: &mut dyn InvokeContext,
+    table_account: &KeyedAccount,
+    authority_account: &KeyedAccount,
+    clock: &Clock,
+) -> Result<(), InstructionError> {
+    let mut table = AddressLookupTable::deserialize(&table_account.try_account_ref()?.data())?;
+    check_authority(invoke_context, &table, authority_account)?;
+    if table.meta.is_deactivated() {
+        ic_msg!(invoke_context, "Lookup table is already deactivated");
+        return Err(InstructionError::InvalidArgument);
+    }
+
+    table.meta.deactivation_slot = clock.slot;
+    table.serialize_into(table_account.try_account_ref_mut()?.data_as_mut_slice())
+}
+
+fn close_lookup_table(
+    invoke_context: &mut dyn InvokeContext,
+    table_account: &KeyedAccount,
+    authority_account: &KeyedAccount,
+    recipient_account: &KeyedAccount,
+    clock: &Clock,
+    slot_hashes: &SlotHashes,
+) -> Result<(), InstructionError> {
+    if recipient_account.unsigned_key() == table_account.unsigned_key() {
+        ic_msg!(
+            invoke_context,
+            "Lookup table cannot be the recipient of reclaimed lamports"
+        );
+        return Err(InstructionError::InvalidArgument);
+    }
+    let table = AddressLookupTable::deserialize(&table_account.try_account_ref()?.data())?;
+    check_authority(invoke_context, &table, authority_account)?;
+    if !table.meta.is_deactivated() {
+        ic_msg!(invoke_context, "Lookup table is not deactivated");
+        return Err(InstructionError::InvalidArgument);
+    }
+    if table.is_active(clock.slot, slot_hashes) {
+        ic_msg!(
+            invoke_context,
+            "Lookup table cannot be closed until its deactivation slot is no longer recent"
+        );
+        return Err(InstructionError::InvalidArgument);
+    }
+
+    let lamports = table_account.lamports()?;
+    {
+        let mut recipient = recipient_account.try_account_ref_mut()?;
+        recipient.lamports = recipient
+            .lamports
+            .checked_add(lamports)
+            .ok_or(InstructionError::ArithmeticOverflow)?;
+    }
+    let mut table_account = table_account.try_account_ref_mut()?;
+    table_account.lamports = 0;
+    table_account
+        .data_as_mut_slice()
+        .iter_mut()
+        .for_each(|byte| *byte = 0);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        id,
+        instruction::{
+            close_lookup_table, create_lookup_table, deactivate_lookup_table, extend_lookup_table,
+            freeze_lookup_table,
+        },
+        state::LOOKUP_TABLE_SIZE,
+    };
+    use solana_sdk::{
+        account::{self, Account, AccountSharedData},
+        hash::Hash,
+        instruction::Instruction,
+        process_instruction::MockInvokeContext,
+        sysvar,
+    };
+    use std::cell::RefCell;
+
+    struct TestAccounts {
+        table_key: Pubkey,
+        table: RefCell<AccountSharedData>,
+        authority_key: Pubkey,
+        authority: RefCell<AccountSharedData>,
+        recipient_key: Pubkey,
+        recipient: RefCell<AccountSharedData>,
+        clock: RefCell<AccountSharedData>,
+        slot_hashes: RefCell<AccountSharedData>,
+    }
+
+    impl TestAccounts {
+        fn new() -> Self {
+            Self {
+                table_key: Pubkey::new_unique(),
+                table: RefCell::new(AccountSharedData::from(Account {
+                    lamports: 42,
+                    data: vec![0; LOOKUP_TABLE_SIZE],
+                    owner: id(),
+                    ..Account::default()
+                })),
+                authority_key: Pubkey::new_unique(),
+                authority: RefCell::new(AccountSharedData::default()),
+                recipient_key: Pubkey::new_unique(),
+                recipient: RefCell::new(AccountSharedData::default()),
+                clock: RefCell::new(account::create_account_shared_data(&Clock::default(), 1)),
+                slot_hashes: RefCell::new(account::create_account_shared_data(
+                    &SlotHashes::default(),
+                    1,
+                )),
+            }
+        }
+
+        fn set_slot(&self, slot: u64, slot_hashes: &[u64]) {
+            let clock = Clock {
+                slot,
+                ..Clock::default()
+            };
+            *self.clock.borrow_mut() = account::create_account_shared_data(&clock, 1);
+            let slot_hashes: Vec<_> = slot_hashes
+                .iter()
+                .map(|slot| (*slot, Hash::new_unique()))
+                .collect();
+            *self.slot_hashes.borrow_mut() =
+                account::create_account_shared_data(&SlotHashes::new(&slot_hashes), 1);
+        }
+
+        fn process(&self, instruction: &Instruction) -> Result<(), InstructionError> {
+            let keyed_accounts: Vec<_> = instruction
+                .accounts
+                .iter()
+                .map(|meta| {
+                    let account = if meta.pubkey == self.table_key {
+                        &self.table
+                    } else if meta.pubkey == self.authority_key {
+                        &self.authority
+                    } else if meta.pubkey == self.recipient_key {
+                        &self.recipient
+                    } else if meta.pubkey == sysvar::clock::id() {
+                        &self.clock
+                    } else if meta.pubkey == sysvar::slot_hashes::id() {
+                        &self.slot_hashes
+                    } else {
+                        panic!("unexpected account {}", meta.pubkey)
+                    };
+                    if meta.is_writable {
+                        KeyedAccount::new(&meta.pubkey, meta.is_signer, account)
+                    } else {
+                        KeyedAccount::new_readonly(&meta.pubkey, meta.is_signer, account)
+                    }
+                })
+                .collect();
+            process_instruction(
+                &id(),
+                &keyed_accounts,
+                &instruction.data,
+                &mut MockInvokeContext::default(),
+            )
+        }
+
+        fn table(&self) -> AddressLookupTable {
+            AddressLookupTable::deserialize(&self.table.borrow().data()).unwrap()
+        }
+
+        fn initialize(&self) {
+            let instructions = create_lookup_table(
+                &self.recipient_key,
+                &self.table_key,
+                &self.authority_key,
+                42,
+            );
+            assert_eq!(self.process(&instructions[1]), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_initialize_lookup_table() {
+        let accounts = TestAccounts::new();
+        accounts.initialize();
+        assert_eq!(
+            accounts.table(),
+            AddressLookupTable::new(accounts.authority_key)
+        );
+
+        let instructions = create_lookup_table(
+            &accounts.recipient_key,
+            &accounts.table_key,
+            &accounts.authority_key,
+            42,
+        );
+        assert_eq!(
+            accounts.process(&instructions[1]),
+            Err(InstructionError::AccountAlreadyInitialized)
+        );
+
+        // The table must be owned by the program
+        let accounts = TestAccounts::new();
+        accounts.table.borrow_mut().owner = Pubkey::new_unique();
+        assert_eq!(
+            accounts.process(&instructions[1]),
+            Err(InstructionError::InvalidAccountOwner)
+        );
+    }
+
+    #[test]
+    fn test_extend_lookup_table() {
+        let accounts = TestAccounts::new();
+        accounts.initialize();
+        accounts.set_slot(5, &[]);
+
+        let addresses: Vec<_> = (0..LOOKUP_TABLE_MAX_ADDRESSES)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        let extend = |new_addresses: &[Pubkey]| {
+            extend_lookup_table(
+                &accounts.table_key,
+                &accounts.authority_key,
+                new_addresses.to_vec(),
+            )
+        };
+
+        assert_eq!(
+            accounts.process(&extend(&[])),
+            Err(InstructionError::InvalidInstructionData)
+        );
+        assert_eq!(accounts.process(&extend(&addresses[..10])), Ok(()));
+        assert_eq!(accounts.process(&extend(&addresses[10..20])), Ok(()));
+        let table = accounts.table();
+        assert_eq!(table.addresses, addresses[..20].to_vec());
+        assert_eq!(table.meta.last_extended_slot, 5);
+        assert_eq!(table.meta.last_extended_slot_start_index, 0);
+
+        accounts.set_slot(6, &[5]);
+        assert_eq!(accounts.process(&extend(&addresses[20..])), Ok(()));
+        let table = accounts.table();
+        assert_eq!(table.addresses, addresses);
+        assert_eq!(table.meta.last_extended_slot, 6);
+        assert_eq!(table.meta.last_extended_slot_start_index, 20);
+
+        assert_eq!(
+            accounts.process(&extend(&[Pubkey::new_unique()])),
+            Err(InstructionError::InvalidInstructionData)
+        );
+
+        // Only the authority can extend the table
+        let mut instruction = extend(&[Pubkey::new_unique()]);
+        instruction.accounts[1].is_signer = false;
+        assert_eq!(
+            accounts.process(&instruction),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+        let mut instruction = extend(&[Pubkey::new_unique()]);
+        instruction.accounts[1].pubkey = accounts.recipient_key;
+        assert_eq!(
+            accounts.process(&instruction),
+            Err(InstructionError::IncorrectAuthority)
+        );
+    }
+
+    #[test]
+    fn test_freeze_lookup_table() {
+        let accounts = TestAccounts::new();
+        accounts.initialize();
+        let freeze = freeze_lookup_table(&accounts.table_key, &accounts.authority_key);
+        assert_eq!(
+            accounts.process(&freeze),
+            Err(InstructionError::InvalidInstructionData)
+        );
+
+        let extend = extend_lookup_table(
+            &accounts.table_key,
+            &accounts.authority_key,
+            vec![Pubkey::new_unique()],
+        );
+        assert_eq!(accounts.process(&extend), Ok(()));
+        assert_eq!(accounts.process(&freeze), Ok(()));
+        assert_eq!(accounts.table().meta.authority, None);
+
+        assert_eq!(accounts.process(&extend), Err(InstructionError::Immutable));
+        assert_eq!(accounts.process(&freeze), Err(InstructionError::Immutable));
+        assert_eq!(
+            accounts.process(&deactivate_lookup_table(
+                &accounts.table_key,
+                &accounts.authority_key
+            )),
+            Err(InstructionError::Immutable)
+        );
+    }
+
+    #[test]
+    fn test_deactivate_and_close_lookup_table() {
+        let accounts = TestAccounts::new();
+        accounts.initialize();
+        let deactivate = deactivate_lookup_table(&accounts.table_key, &accounts.authority_key);
+        let close = close_lookup_table(
+            &accounts.table_key,
+            &accounts.authority_key,
+            &accounts.recipient_key,
+        );
+
+        accounts.set_slot(10, &[9]);
+        assert_eq!(
+            accounts.process(&close),
+            Err(InstructionError::InvalidArgument)
+        );
+        assert_eq!(accounts.process(&deactivate), Ok(()));
+        assert_eq!(accounts.table().meta.deactivation_slot, 10);
+        assert_eq!(
+            accounts.process(&deactivate),
+            Err(InstructionError::InvalidArgument)
+        );
+        assert_eq!(
+            accounts.process(&extend_lookup_table(
+                &accounts.table_key,
+                &accounts.authority_key,
+                vec![Pubkey::new_unique()],
+            )),
+            Err(InstructionError::InvalidArgument)
+        );
+
+        // Can't close while the deactivation slot is recent
+        assert_eq!(
+            accounts.process(&close),
+            Err(InstructionError::InvalidArgument)
+        );
+        accounts.set_slot(11, &[10]);
+        assert_eq!(
+            accounts.process(&close),
+            Err(InstructionError::InvalidArgument)
+        );
+
+        accounts.set_slot(1_000, &[999]);
+        assert_eq!(accounts.process(&close), Ok(()));
+        assert_eq!(accounts.table.borrow().lamports, 0);
+        assert!(accounts.table.borrow().data().iter().all(|byte| *byte == 0));
+        assert_eq!(accounts.recipient.borrow().lamports, 42);
+    }
+}
@@ -0,0 +1,271 @@
+use crate::error::AddressLookupError;
+use serde_derive::{Deserialize, Serialize};
+use solana_sdk::{
+    clock::Slot, instruction::InstructionError, pubkey::Pubkey, slot_hashes::SlotHashes,
+};
+use std::{convert::TryFrom, mem::size_of};
+
+/// Maximum number of addresses a lookup table can hold, since lookups refer to them by a u8
+/// index
+pub const LOOKUP_TABLE_MAX_ADDRESSES: usize = 256;
+
+/// Space reserved for the serialized `ProgramState` at the start of a lookup table account,
+/// the addresses are stored right after it
+pub const LOOKUP_TABLE_META_SIZE: usize = 56;
+
+/// Size of a lookup table account.  Accounts can't be resized, so tables are created with room
+/// for the maximum number of addresses.
+pub const LOOKUP_TABLE_SIZE: usize =
+    LOOKUP_TABLE_META_SIZE + LOOKUP_TABLE_MAX_ADDRESSES * size_of::<Pubkey>();
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub enum ProgramState {
+    /// Account created by the system program but not initialized as a table yet
+    Uninitialized,
+    LookupTable(LookupTableMeta),
+}
+
+impl ProgramState {
+    pub fn deserialize(data: &[u8]) -> Result<Self, InstructionError> {
+        if data.len() != LOOKUP_TABLE_SIZE {
+            return Err(InstructionError::InvalidAccountData);
+        }
+        bincode::deserialize(&data[..LOOKUP_TABLE_META_SIZE])
+            .map_err(|_| InstructionError::InvalidAccountData)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct LookupTableMeta {
+    /// Slot the table was deactivated in, `Slot::MAX` while the table is active
+    pub deactivation_slot: Slot,
+    /// Slot the table was last extended in.  Addresses added in that slot can only be looked
+    /// up from the next slot on.
+    pub last_extended_slot: Slot,
+    /// Number of addresses the table held before it was extended in `last_extended_slot`
+    pub last_extended_slot_start_index: u8,
+    /// Authority allowed to extend, freeze, deactivate and close the table, `None` once the
+    /// table is frozen
+    pub authority: Option<Pubkey>,
+    /// Number of addresses stored in the table
+    pub num_addresses: u16,
+}
+
+impl LookupTableMeta {
+    pub fn new(authority: Pubkey) -> Self {
+        Self {
+            deactivation_slot: Slot::MAX,
+            last_extended_slot: 0,
+            last_extended_slot_start_index: 0,
+            authority: Some(authority),
+            num_addresses: 0,
+        }
+    }
+
+    pub fn is_deactivated(&self) -> bool {
+        self.deactivation_slot != Slot::MAX
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AddressLookupTable {
+    pub meta: LookupTableMeta,
+    pub addresses: Vec<Pubkey>,
+}
+
+impl AddressLookupTable {
+    pub fn new(authority: Pubkey) -> Self {
+        Self {
+            meta: LookupTableMeta::new(authority),
+            addresses: vec![],
+        }
+    }
+
+    /// Deserialize an initialized lookup table from account data
+    pub fn deserialize(data: &[u8]) -> Result<Self, InstructionError> {
+        let meta = match ProgramState::deserialize(data)? {
+            ProgramState::LookupTable(meta) => meta,
+            ProgramState::Uninitialized => return Err(InstructionError::UninitializedAccount),
+        };
+        let num_addresses = usize::from(meta.num_addresses);
+        if num_addresses > LOOKUP_TABLE_MAX_ADDRESSES {
+            return Err(InstructionError::InvalidAccountData);
+        }
+        let addresses = data[LOOKUP_TABLE_META_SIZE..]
+            .chunks(size_of::<Pubkey>())
+            .take(num_addresses)
+            .map(Pubkey::new)
+            .collect();
+        Ok(Self { meta, addresses })
+    }
+
+    /// Serialize the lookup table into account data, clearing any unused space
+    pub fn serialize_into(&self, data: &mut [u8]) -> Result<(), InstructionError> {
+        if data.len() != LOOKUP_TABLE_SIZE {
+            return Err(InstructionError::InvalidAccountData);
+        }
+        let num_addresses = u16::try_from(self.addresses.len())
+            .ok()
+            .filter(|num_addresses| usize::from(*num_addresses) <= LOOKUP_TABLE_MAX_ADDRESSES)
+            .ok_or(InstructionError::InvalidArgument)?;
+        let state = ProgramState::LookupTable(LookupTableMeta {
+            num_addresses,
+            ..self.meta.clone()
+        });
+
+        let (meta_data, addresses_data) = data.split_at_mut(LOOKUP_TABLE_META_SIZE);
+        meta_data.iter_mut().for_each(|byte| *byte = 0);
+        bincode::serialize_into(meta_data, &state)
+            .map_err(|_| InstructionError::InvalidAccountData)?;
+        addresses_data.iter_mut().for_each(|byte| *byte = 0);
+        for (address, address_data) in self
+            .addresses
+            .iter()
+            .zip(addresses_data.chunks_mut(size_of::<Pubkey>()))
+        {
+            address_data.copy_from_slice(address.as_ref());
+        }
+        Ok(())
+    }
+
+    /// A table stays usable for lookups until its deactivation slot is no longer in the
+    /// slot hashes history, since it can only be closed after that
+    pub fn is_active(&self, current_slot: Slot, slot_hashes: &SlotHashes) -> bool {
+        match self.meta.deactivation_slot {
+            Slot::MAX => true,
+            deactivation_slot if deactivation_slot == current_slot => true,
+            deactivation_slot => slot_hashes.get(&deactivation_slot).is_some(),
+        }
+    }
+
+    /// Number of addresses that can be looked up in `current_slot`.  Addresses appended in the
+    /// current slot are not usable until the next one.
+    pub fn get_active_addresses_len(&self, current_slot: Slot) -> usize {
+        if current_slot > self.meta.last_extended_slot {
+            self.addresses.len()
+        } else {
+            usize::from(self.meta.last_extended_slot_start_index).min(self.addresses.len())
+        }
+    }
+
+    /// Look up the addresses at the given indexes
+    pub fn lookup(
+        &self,
+        current_slot: Slot,
+        indexes: &[u8],
+        slot_hashes: &SlotHashes,
+    ) -> Result<Vec<Pubkey>, AddressLookupError> {
+        if !self.is_active(current_slot, slot_hashes) {
+            // Deactivated tables may be closed and recreated with different addresses
+            return Err(AddressLookupError::LookupTableAccountNotFound);
+        }
+        let active_addresses = &self.addresses[..self.get_active_addresses_len(current_slot)];
+        indexes
+            .iter()
+            .map(|index| {
+                active_addresses
+                    .get(usize::from(*index))
+                    .copied()
+                    .ok_or(AddressLookupError::InvalidLookupIndex)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::hash::Hash;
+
+    #[test]
+    fn test_meta_size() {
+        let meta = LookupTableMeta {
+            deactivation_slot: Slot::MAX,
+            last_extended_slot: Slot::MAX,
+            last_extended_slot_start_index: u8::MAX,
+            authority: Some(Pubkey::new_unique()),
+            num_addresses: u16::MAX,
+        };
+        let size = bincode::serialized_size(&ProgramState::LookupTable(meta)).unwrap();
+        assert_eq!(size as usize, LOOKUP_TABLE_META_SIZE);
+    }
+
+    #[test]
+    fn test_serialize_roundtrip() {
+        let mut data = vec![0; LOOKUP_TABLE_SIZE];
+        assert_eq!(
+            ProgramState::deserialize(&data),
+            Ok(ProgramState::Uninitialized)
+        );
+        assert_eq!(
+            AddressLookupTable::deserialize(&data),
+            Err(InstructionError::UninitializedAccount)
+        );
+
+        let mut table = AddressLookupTable::new(Pubkey::new_unique());
+        table.addresses = (0..10).map(|_| Pubkey::new_unique()).collect();
+        table.serialize_into(&mut data).unwrap();
+        let deserialized = AddressLookupTable::deserialize(&data).unwrap();
+        assert_eq!(deserialized.meta.num_addresses, 10);
+        assert_eq!(deserialized.addresses, table.addresses);
+
+        // Frozen, shrunk table
+        table.meta.authority = None;
+        table.addresses.truncate(2);
+        table.serialize_into(&mut data).unwrap();
+        let deserialized = AddressLookupTable::deserialize(&data).unwrap();
+        assert_eq!(deserialized.meta.authority, None);
+        assert_eq!(deserialized.addresses, table.addresses);
+        assert!(data[LOOKUP_TABLE_META_SIZE + 2 * size_of::<Pubkey>()..]
+            .iter()
+            .all(|byte| *byte == 0));
+
+        table.addresses = vec![Pubkey::new_unique(); LOOKUP_TABLE_MAX_ADDRESSES + 1];
+        assert_eq!(
+            table.serialize_into(&mut data),
+            Err(InstructionError::InvalidArgument)
+        );
+        assert_eq!(
+            AddressLookupTable::deserialize(&data[1..]),
+            Err(InstructionError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_lookup() {
+        let mut table = AddressLookupTable::new(Pubkey::new_unique());
+        table.addresses = (0..4).map(|_| Pubkey::new_unique()).collect();
+        table.meta.last_extended_slot = 10;
+        table.meta.last_extended_slot_start_index = 2;
+        let slot_hashes = SlotHashes::new(&[(9, Hash::new_unique())]);
+
+        // Addresses added in the current slot can't be used yet
+        assert_eq!(
+            table.lookup(10, &[1, 0], &slot_hashes),
+            Ok(vec![table.addresses[1], table.addresses[0]])
+        );
+        assert_eq!(
+            table.lookup(10, &[2], &slot_hashes),
+            Err(AddressLookupError::InvalidLookupIndex)
+        );
+        assert_eq!(
+            table.lookup(11, &[3, 2], &slot_hashes),
+            Ok(vec![table.addresses[3], table.addresses[2]])
+        );
+        assert_eq!(
+            table.lookup(11, &[4], &slot_hashes),
+            Err(AddressLookupError::InvalidLookupIndex)
+        );
+
+        // Deactivated tables can be used while the deactivation slot is recent
+        table.meta.deactivation_slot = 9;
+        assert!(table.lookup(11, &[0], &slot_hashes).is_ok());
+        table.meta.deactivation_slot = 8;
+        assert_eq!(
+            table.lookup(11, &[0], &slot_hashes),
+            Err(AddressLookupError::LookupTableAccountNotFound)
+        );
+        table.meta.deactivation_slot = 11;
+        assert!(table.lookup(11, &[0], &slot_hashes).is_ok());
+    }
+}
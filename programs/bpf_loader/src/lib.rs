@@ -844,7 +844,7 @@ mod tests {
     use solana_runtime::{
         bank::Bank,
         bank_client::BankClient,
-        message_processor::{Executors, ThisInvokeContext},
+        message_processor::{Executors, ThisComputeMeter, ThisInvokeContext},
     };
     use solana_sdk::{
         account::{create_account_shared_data as create_account, AccountSharedData},
@@ -1111,6 +1111,7 @@ mod tests {
                 log_pubkey_units: 100,
                 max_cpi_instruction_size: usize::MAX,
             },
+            ThisComputeMeter::new_ref(1),
             Rc::new(RefCell::new(Executors::default())),
             None,
             Arc::new(FeatureSet::default()),
@@ -8,6 +8,7 @@ use rand::Rng;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use solana_runtime::{
     accounts::{create_test_accounts, AccountAddressFilter, Accounts},
+    accounts_index::AccountSecondaryIndexes,
     bank::*,
 };
 use solana_sdk::{
@@ -53,8 +54,9 @@ fn test_accounts_create(bencher: &mut Bencher) {
         &[],
         None,
         None,
-        HashSet::new(),
+        AccountSecondaryIndexes::default(),
         false,
+        None,
     );
     bencher.iter(|| {
         let mut pubkeys: Vec<Pubkey> = vec![];
@@ -72,8 +74,9 @@ fn test_accounts_squash(bencher: &mut Bencher) {
         &[],
         None,
         None,
-        HashSet::new(),
+        AccountSecondaryIndexes::default(),
         false,
+        None,
     ));
     let mut pubkeys: Vec<Pubkey> = vec![];
     deposit_many(&prev_bank, &mut pubkeys, 250_000);
@@ -97,8 +100,9 @@ fn test_accounts_hash_bank_hash(bencher: &mut Bencher) {
     let accounts = Accounts::new_with_config(
         vec![PathBuf::from("bench_accounts_hash_internal")],
         &ClusterType::Development,
-        HashSet::new(),
+        AccountSecondaryIndexes::default(),
         false,
+        None,
     );
     let mut pubkeys: Vec<Pubkey> = vec![];
     let num_accounts = 60_000;
@@ -115,8 +119,9 @@ fn test_update_accounts_hash(bencher: &mut Bencher) {
     let accounts = Accounts::new_with_config(
         vec![PathBuf::from("update_accounts_hash")],
         &ClusterType::Development,
-        HashSet::new(),
+        AccountSecondaryIndexes::default(),
         false,
+        None,
     );
     let mut pubkeys: Vec<Pubkey> = vec![];
     create_test_accounts(&accounts, &mut pubkeys, 50_000, 0);
@@ -132,8 +137,9 @@ fn test_accounts_delta_hash(bencher: &mut Bencher) {
     let accounts = Accounts::new_with_config(
         vec![PathBuf::from("accounts_delta_hash")],
         &ClusterType::Development,
-        HashSet::new(),
+        AccountSecondaryIndexes::default(),
         false,
+        None,
     );
     let mut pubkeys: Vec<Pubkey> = vec![];
     create_test_accounts(&accounts, &mut pubkeys, 100_000, 0);
@@ -148,8 +154,9 @@ fn bench_delete_dependencies(bencher: &mut Bencher) {
     let accounts = Accounts::new_with_config(
         vec![PathBuf::from("accounts_delete_deps")],
         &ClusterType::Development,
-        HashSet::new(),
+        AccountSecondaryIndexes::default(),
         false,
+        None,
     );
     let mut old_pubkey = Pubkey::default();
     let zero_account = AccountSharedData::new(0, 0, &AccountSharedData::default().owner);
@@ -181,8 +188,9 @@ fn store_accounts_with_possible_contention<F: 'static>(
                 .join(bench_name),
         ],
         &ClusterType::Development,
-        HashSet::new(),
+        AccountSecondaryIndexes::default(),
         false,
+        None,
     ));
     let num_keys = 1000;
     let slot = 0;
@@ -306,8 +314,9 @@ fn setup_bench_dashmap_iter() -> (Arc<Accounts>, DashMap<Pubkey, (AccountSharedD
                 .join("bench_dashmap_par_iter"),
         ],
         &ClusterType::Development,
-        HashSet::new(),
+        AccountSecondaryIndexes::default(),
         false,
+        None,
     ));
 
     let dashmap = DashMap::new();
@@ -357,8 +366,13 @@ fn bench_dashmap_iter(bencher: &mut Bencher) {
 
 #[bench]
 fn bench_load_largest_accounts(b: &mut Bencher) {
-    let accounts =
-        Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+    let accounts = Accounts::new_with_config(
+        Vec::new(),
+        &ClusterType::Development,
+        AccountSecondaryIndexes::default(),
+        false,
+        None,
+    );
     let mut rng = rand::thread_rng();
     for _ in 0..10_000 {
         let lamports = rng.gen();
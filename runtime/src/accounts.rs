@@ -1,6 +1,6 @@
 use crate::{
     accounts_db::{AccountsDb, BankHashInfo, ErrorCounters, LoadedAccount, ScanStorageResult},
-    accounts_index::{AccountIndex, Ancestors, IndexKey},
+    accounts_index::{AccountSecondaryIndexes, AccountsIndexConfig, Ancestors, IndexKey},
     bank::{
         NonceRollbackFull, NonceRollbackInfo, TransactionCheckResult, TransactionExecutionResult,
     },
@@ -15,6 +15,7 @@ use dashmap::{
 };
 use log::*;
 use rand::{thread_rng, Rng};
+use solana_address_lookup_table_program::{error::AddressLookupError, state::AddressLookupTable};
 use solana_sdk::{
     account::{Account, AccountSharedData},
     account_utils::StateMut,
@@ -24,9 +25,13 @@ use solana_sdk::{
     fee_calculator::{FeeCalculator, FeeConfig},
     genesis_config::ClusterType,
     hash::Hash,
-    message::Message,
+    message::{
+        v0::{LoadedAddresses, MessageAddressTableLookup},
+        Message,
+    },
     native_loader, nonce,
     pubkey::Pubkey,
+    slot_hashes::SlotHashes,
     transaction::Result,
     transaction::{Transaction, TransactionError},
 };
@@ -114,14 +119,21 @@ pub enum AccountAddressFilter {
 
 impl Accounts {
     pub fn new(paths: Vec<PathBuf>, cluster_type: &ClusterType) -> Self {
-        Self::new_with_config(paths, cluster_type, HashSet::new(), false)
+        Self::new_with_config(
+            paths,
+            cluster_type,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+        )
     }
 
     pub fn new_with_config(
         paths: Vec<PathBuf>,
         cluster_type: &ClusterType,
-        account_indexes: HashSet<AccountIndex>,
+        account_indexes: AccountSecondaryIndexes,
         caching_enabled: bool,
+        accounts_index_config: Option<AccountsIndexConfig>,
     ) -> Self {
         Self {
             accounts_db: Arc::new(AccountsDb::new_with_config(
@@ -129,6 +141,7 @@ impl Accounts {
                 cluster_type,
                 account_indexes,
                 caching_enabled,
+                accounts_index_config,
             )),
             account_locks: Mutex::new(AccountLocks::default()),
         }
@@ -396,6 +409,8 @@ impl Accounts {
         let fee_config = FeeConfig {
             secp256k1_program_enabled: feature_set
                 .is_active(&feature_set::secp256k1_program_enabled::id()),
+            compute_budget_program_enabled: feature_set
+                .is_active(&feature_set::compute_budget_program_enabled::id()),
         };
         OrderedIterator::new(txs, txs_iteration_order)
             .zip(lock_results.into_iter())
@@ -448,6 +463,37 @@ impl Accounts {
             .collect()
     }
 
+    /// Load the addresses an address table lookup of a versioned message refers to
+    pub fn load_lookup_table_addresses(
+        &self,
+        ancestors: &Ancestors,
+        address_table_lookup: &MessageAddressTableLookup,
+        current_slot: Slot,
+        slot_hashes: &SlotHashes,
+    ) -> std::result::Result<LoadedAddresses, AddressLookupError> {
+        let (table_account, _slot) = self
+            .load_slow(ancestors, &address_table_lookup.account_key)
+            .ok_or(AddressLookupError::LookupTableAccountNotFound)?;
+        if table_account.owner != solana_address_lookup_table_program::id() {
+            return Err(AddressLookupError::InvalidAccountOwner);
+        }
+        let table = AddressLookupTable::deserialize(&table_account.data)
+            .map_err(|_| AddressLookupError::InvalidAccountData)?;
+
+        Ok(LoadedAddresses {
+            writable: table.lookup(
+                current_slot,
+                &address_table_lookup.writable_indexes,
+                slot_hashes,
+            )?,
+            readonly: table.lookup(
+                current_slot,
+                &address_table_lookup.readonly_indexes,
+                slot_hashes,
+            )?,
+        })
+    }
+
     /// Slow because lock is held for 1 operation instead of many
     pub fn load_slow(
         &self,
@@ -1054,8 +1100,13 @@ mod tests {
     ) -> Vec<TransactionLoadResult> {
         let mut hash_queue = BlockhashQueue::new(100);
         hash_queue.register_hash(&tx.message().recent_blockhash, &fee_calculator);
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+        );
         for ka in ka.iter() {
             accounts.store_slow_uncached(0, &ka.0, &ka.1);
         }
@@ -1588,8 +1639,13 @@ mod tests {
 
     #[test]
     fn test_load_by_program_slot() {
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+        );
 
         // Load accounts owned by various programs into AccountsDb
         let pubkey0 = solana_sdk::pubkey::new_rand();
@@ -1610,10 +1666,89 @@ mod tests {
         assert_eq!(loaded, vec![]);
     }
 
+    #[test]
+    fn test_load_lookup_table_addresses() {
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+        );
+        let ancestors = vec![(0, 0)].into_iter().collect();
+        let slot_hashes = SlotHashes::default();
+
+        let table_key = Pubkey::new_unique();
+        let mut table = AddressLookupTable::new(Pubkey::new_unique());
+        table.addresses = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut table_account = AccountSharedData::new(
+            1,
+            solana_address_lookup_table_program::state::LOOKUP_TABLE_SIZE,
+            &solana_address_lookup_table_program::id(),
+        );
+        table
+            .serialize_into(table_account.data_as_mut_slice())
+            .unwrap();
+        accounts.store_slow_uncached(0, &table_key, &table_account);
+
+        let lookup = MessageAddressTableLookup {
+            account_key: table_key,
+            writable_indexes: vec![1],
+            readonly_indexes: vec![0],
+        };
+        assert_eq!(
+            accounts.load_lookup_table_addresses(&ancestors, &lookup, 1, &slot_hashes),
+            Ok(LoadedAddresses {
+                writable: vec![table.addresses[1]],
+                readonly: vec![table.addresses[0]],
+            })
+        );
+
+        let invalid_index_lookup = MessageAddressTableLookup {
+            writable_indexes: vec![2],
+            ..lookup.clone()
+        };
+        assert_eq!(
+            accounts.load_lookup_table_addresses(
+                &ancestors,
+                &invalid_index_lookup,
+                1,
+                &slot_hashes
+            ),
+            Err(AddressLookupError::InvalidLookupIndex)
+        );
+
+        let missing_table_lookup = MessageAddressTableLookup {
+            account_key: Pubkey::new_unique(),
+            ..lookup.clone()
+        };
+        assert_eq!(
+            accounts.load_lookup_table_addresses(
+                &ancestors,
+                &missing_table_lookup,
+                1,
+                &slot_hashes
+            ),
+            Err(AddressLookupError::LookupTableAccountNotFound)
+        );
+
+        table_account.set_owner(Pubkey::new_unique());
+        accounts.store_slow_uncached(0, &table_key, &table_account);
+        assert_eq!(
+            accounts.load_lookup_table_addresses(&ancestors, &lookup, 1, &slot_hashes),
+            Err(AddressLookupError::InvalidAccountOwner)
+        );
+    }
+
     #[test]
     fn test_accounts_account_not_found() {
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+        );
         let mut error_counters = ErrorCounters::default();
         let ancestors = vec![(0, 0)].into_iter().collect();
 
@@ -1631,8 +1766,13 @@ mod tests {
     #[test]
     #[should_panic]
     fn test_accounts_empty_bank_hash() {
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+        );
         accounts.bank_hash_at(1);
     }
 
@@ -1648,8 +1788,13 @@ mod tests {
         let account2 = AccountSharedData::new(3, 0, &Pubkey::default());
         let account3 = AccountSharedData::new(4, 0, &Pubkey::default());
 
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+        );
         accounts.store_slow_uncached(0, &keypair0.pubkey(), &account0);
         accounts.store_slow_uncached(0, &keypair1.pubkey(), &account1);
         accounts.store_slow_uncached(0, &keypair2.pubkey(), &account2);
@@ -1755,8 +1900,13 @@ mod tests {
         let account1 = AccountSharedData::new(2, 0, &Pubkey::default());
         let account2 = AccountSharedData::new(3, 0, &Pubkey::default());
 
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+        );
         accounts.store_slow_uncached(0, &keypair0.pubkey(), &account0);
         accounts.store_slow_uncached(0, &keypair1.pubkey(), &account1);
         accounts.store_slow_uncached(0, &keypair2.pubkey(), &account2);
@@ -1885,8 +2035,13 @@ mod tests {
 
         let mut loaded = vec![loaded0, loaded1];
 
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+        );
         {
             accounts
                 .account_locks
@@ -1933,8 +2088,13 @@ mod tests {
     #[test]
     fn huge_clean() {
         solana_logger::setup();
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+        );
         let mut old_pubkey = Pubkey::default();
         let zero_account = AccountSharedData::new(0, 0, &AccountSharedData::default().owner);
         info!("storing..");
@@ -1977,8 +2137,13 @@ mod tests {
     #[test]
     fn test_instructions() {
         solana_logger::setup();
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+        );
 
         let instructions_key = solana_sdk::sysvar::instructions::id();
         let keypair = Keypair::new();
@@ -2257,8 +2422,13 @@ mod tests {
         let mut loaded = vec![loaded];
 
         let next_blockhash = Hash::new_unique();
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+        );
         let collected_accounts = accounts.collect_accounts_to_store(
             &txs,
             None,
@@ -2368,8 +2538,13 @@ mod tests {
         let mut loaded = vec![loaded];
 
         let next_blockhash = Hash::new_unique();
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+        );
         let collected_accounts = accounts.collect_accounts_to_store(
             &txs,
             None,
@@ -2395,8 +2570,13 @@ mod tests {
 
     #[test]
     fn test_load_largest_accounts() {
-        let accounts =
-            Accounts::new_with_config(Vec::new(), &ClusterType::Development, HashSet::new(), false);
+        let accounts = Accounts::new_with_config(
+            Vec::new(),
+            &ClusterType::Development,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+        );
 
         let pubkey0 = Pubkey::new_unique();
         let account0 = AccountSharedData::new(42, 0, &Pubkey::default());
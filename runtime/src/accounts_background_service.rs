@@ -5,7 +5,7 @@
 use crate::{
     bank::{Bank, BankSlotDelta, DropCallback},
     bank_forks::{BankForks, SnapshotConfig},
-    snapshot_package::AccountsPackageSender,
+    snapshot_package::{AccountsPackageSender, SnapshotType},
     snapshot_utils,
 };
 use crossbeam_channel::{Receiver, SendError, Sender};
@@ -149,6 +149,8 @@ impl SnapshotRequestHandler {
                 };
                 hash_time.stop();
 
+                let snapshot_type = self.snapshot_type_for_bank(&snapshot_root_bank);
+
                 let mut clean_time = Measure::start("clean_time");
                 // Don't clean the slot we're snapshotting because it may have zero-lamport
                 // accounts that were included in the bank delta hash when the bank was frozen,
@@ -174,6 +176,7 @@ impl SnapshotRequestHandler {
                     self.snapshot_config.snapshot_version,
                     &self.snapshot_config.archive_format,
                     hash_for_testing,
+                    snapshot_type,
                 );
                 if r.is_err() {
                     warn!(
@@ -209,6 +212,26 @@ impl SnapshotRequestHandler {
                 snapshot_root_bank.block_height()
             })
     }
+
+    /// Decide what kind of snapshot archive, if any, should be made of `snapshot_root_bank`.
+    /// Full snapshots take precedence; incremental snapshots are built on top of the last full
+    /// snapshot, which the snapshot packager records in the bank's AccountsDb once its archive
+    /// is written.
+    fn snapshot_type_for_bank(&self, snapshot_root_bank: &Bank) -> Option<SnapshotType> {
+        let block_height = snapshot_root_bank.block_height();
+        let accounts_db = &snapshot_root_bank.rc.accounts.accounts_db;
+        if block_height % self.snapshot_config.snapshot_interval_slots == 0 {
+            Some(SnapshotType::FullSnapshot)
+        } else if self.snapshot_config.incremental_snapshots_enabled()
+            && block_height % self.snapshot_config.incremental_snapshot_interval_slots == 0
+        {
+            accounts_db
+                .last_full_snapshot_slot()
+                .map(SnapshotType::IncrementalSnapshot)
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Default)]
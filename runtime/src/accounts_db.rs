@@ -20,12 +20,15 @@
 
 use crate::{
     accounts_cache::{AccountsCache, CachedAccount, SlotCache},
+    accounts_file::AccountsFile,
     accounts_hash::{AccountsHash, CalculateHashIntermediate, HashStats},
     accounts_index::{
-        AccountIndex, AccountsIndex, AccountsIndexRootsStats, Ancestors, IndexKey, IsCached,
-        SlotList, SlotSlice, ZeroLamport,
+        AccountSecondaryIndexes, AccountsIndex, AccountsIndexConfig, AccountsIndexRootsStats,
+        Ancestors, IndexKey, IsCached, SlotList, SlotSlice, ZeroLamport,
     },
+    accounts_update_notifier_interface::AccountsUpdateNotifier,
     append_vec::{AppendVec, StoredAccountMeta, StoredMeta},
+    cold_storage::{ColdStorage, ColdStorageCache},
     contains::Contains,
 };
 use blake3::traits::digest::Digest;
@@ -51,10 +54,10 @@ use solana_vote_program::vote_state::MAX_LOCKOUT_HISTORY;
 use std::{
     borrow::Cow,
     boxed::Box,
-    collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet},
+    collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     convert::TryFrom,
     io::{Error as IoError, Result as IoResult},
-    ops::RangeBounds,
+    ops::{Range, RangeBounds},
     path::{Path, PathBuf},
     sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
     sync::{Arc, Mutex, MutexGuard, RwLock},
@@ -68,6 +71,7 @@ const STORE_META_OVERHEAD: usize = 256;
 const MAX_CACHE_SLOTS: usize = 200;
 const FLUSH_CACHE_RANDOM_THRESHOLD: usize = MAX_LOCKOUT_HISTORY;
 const SCAN_SLOT_PAR_ITER_THRESHOLD: usize = 4000;
+const MAX_COLD_STORAGE_SLOTS_PER_PASS: usize = 8;
 
 pub const DEFAULT_FILE_SIZE: u64 = PAGE_SIZE * 1024;
 pub const DEFAULT_NUM_THREADS: u32 = 8;
@@ -124,7 +128,7 @@ pub struct ErrorCounters {
     pub not_allowed_during_cluster_maintenance: usize,
 }
 
-#[derive(Default, Debug, PartialEq, Clone)]
+#[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct AccountInfo {
     /// index identifying the append storage
     store_id: AppendVecId,
@@ -358,7 +362,7 @@ pub struct AccountStorageEntry {
     pub(crate) slot: AtomicU64,
 
     /// storage holding the accounts
-    pub(crate) accounts: AppendVec,
+    pub(crate) accounts: AccountsFile,
 
     /// Keeps track of the number of accounts stored in a specific AppendVec.
     ///  This is periodically checked to reuse the stores that do not have
@@ -382,7 +386,7 @@ impl AccountStorageEntry {
     pub fn new(path: &Path, slot: Slot, id: usize, file_size: u64) -> Self {
         let tail = AppendVec::file_name(slot, id);
         let path = Path::new(path).join(tail);
-        let accounts = AppendVec::new(&path, true, file_size as usize);
+        let accounts = AccountsFile::AppendVec(AppendVec::new(&path, true, file_size as usize));
 
         Self {
             id: AtomicUsize::new(id),
@@ -397,7 +401,7 @@ impl AccountStorageEntry {
     pub(crate) fn new_existing(
         slot: Slot,
         id: AppendVecId,
-        accounts: AppendVec,
+        accounts: AccountsFile,
         num_accounts: usize,
     ) -> Self {
         Self {
@@ -493,6 +497,9 @@ impl AccountStorageEntry {
     }
 
     fn try_available(&self) -> bool {
+        if !self.accounts.is_appendable() {
+            return false;
+        }
         let mut count_and_status = self.count_and_status.write().unwrap();
         let (count, status) = *count_and_status;
 
@@ -624,8 +631,11 @@ pub const EXPIRATION_TTL_SECONDS: u64 = 1800;
 
 impl RecycleStores {
     fn add_entry(&mut self, new_entry: Arc<AccountStorageEntry>) {
-        self.total_bytes += new_entry.total_bytes();
-        self.entries.push((Instant::now(), new_entry))
+        // Cold storages can't be written to, so there is no use recycling them
+        if new_entry.accounts.is_appendable() {
+            self.total_bytes += new_entry.total_bytes();
+            self.entries.push((Instant::now(), new_entry))
+        }
     }
 
     fn iter(&self) -> std::slice::Iter<(Instant, Arc<AccountStorageEntry>)> {
@@ -633,10 +643,12 @@ impl RecycleStores {
     }
 
     fn add_entries(&mut self, new_entries: Vec<Arc<AccountStorageEntry>>) {
-        self.total_bytes += new_entries.iter().map(|e| e.total_bytes()).sum::<u64>();
         let now = Instant::now();
         for new_entry in new_entries {
-            self.entries.push((now, new_entry));
+            if new_entry.accounts.is_appendable() {
+                self.total_bytes += new_entry.total_bytes();
+                self.entries.push((now, new_entry));
+            }
         }
     }
 
@@ -740,7 +752,7 @@ pub struct AccountsDb {
 
     pub cluster_type: Option<ClusterType>,
 
-    pub account_indexes: HashSet<AccountIndex>,
+    pub account_indexes: AccountSecondaryIndexes,
 
     pub caching_enabled: bool,
 
@@ -748,6 +760,25 @@ pub struct AccountsDb {
     /// to drive clean_accounts
     /// Generated by get_accounts_delta_hash
     uncleaned_pubkeys: DashMap<Slot, Vec<Pubkey>>,
+
+    /// Notified of every account update committed through `store`
+    accounts_update_notifier: RwLock<Option<AccountsUpdateNotifier>>,
+
+    /// Slot of the last full snapshot taken or loaded, if any.  Zero-lamport accounts updated
+    /// after this slot must not be purged by clean, otherwise an incremental snapshot on top of
+    /// that full snapshot would resurrect them.
+    last_full_snapshot_slot: RwLock<Option<Slot>>,
+
+    /// Rooted slots at least this many slots older than the max root are rewritten to
+    /// `ColdStorage` when shrunk. Disabled if None.
+    cold_storage_slot_age: RwLock<Option<Slot>>,
+
+    /// Rooted slots, in increasing order, which are not yet old enough to be moved to
+    /// `ColdStorage`
+    cold_storage_candidate_slots: Mutex<VecDeque<Slot>>,
+
+    /// Decompressed blocks of all the `ColdStorage`s
+    pub(crate) cold_storage_cache: Arc<ColdStorageCache>,
 }
 
 #[derive(Debug, Default)]
@@ -1092,25 +1123,39 @@ impl Default for AccountsDb {
             shrink_stats: ShrinkStats::default(),
             stats: AccountsStats::default(),
             cluster_type: None,
-            account_indexes: HashSet::new(),
+            account_indexes: AccountSecondaryIndexes::default(),
             caching_enabled: false,
+            accounts_update_notifier: RwLock::new(None),
+            last_full_snapshot_slot: RwLock::new(None),
+            cold_storage_slot_age: RwLock::new(None),
+            cold_storage_candidate_slots: Mutex::new(VecDeque::new()),
+            cold_storage_cache: Arc::default(),
         }
     }
 }
 
 impl AccountsDb {
     pub fn new(paths: Vec<PathBuf>, cluster_type: &ClusterType) -> Self {
-        AccountsDb::new_with_config(paths, cluster_type, HashSet::new(), false)
+        AccountsDb::new_with_config(
+            paths,
+            cluster_type,
+            AccountSecondaryIndexes::default(),
+            false,
+            None,
+        )
     }
 
     pub fn new_with_config(
         paths: Vec<PathBuf>,
         cluster_type: &ClusterType,
-        account_indexes: HashSet<AccountIndex>,
+        account_indexes: AccountSecondaryIndexes,
         caching_enabled: bool,
+        accounts_index_config: Option<AccountsIndexConfig>,
     ) -> Self {
+        let accounts_index = AccountsIndex::new(accounts_index_config);
         let new = if !paths.is_empty() {
             Self {
+                accounts_index,
                 paths,
                 temp_paths: None,
                 cluster_type: Some(*cluster_type),
@@ -1123,6 +1168,7 @@ impl AccountsDb {
             // for testing
             let (temp_dirs, paths) = get_temp_accounts_paths(DEFAULT_NUM_DIRS).unwrap();
             Self {
+                accounts_index,
                 paths,
                 temp_paths: Some(temp_dirs),
                 cluster_type: Some(*cluster_type),
@@ -1148,6 +1194,48 @@ impl AccountsDb {
         *shrink_paths = Some(paths);
     }
 
+    pub fn set_accounts_update_notifier(&self, notifier: AccountsUpdateNotifier) {
+        *self.accounts_update_notifier.write().unwrap() = Some(notifier);
+    }
+
+    /// Moves the accounts of rooted slots at least `slot_age` slots older than the max root
+    /// to `ColdStorage` as they are shrunk
+    pub fn set_cold_storage_slot_age(&self, slot_age: Slot) {
+        let mut rooted_slots: Vec<_> = self
+            .storage
+            .all_slots()
+            .into_iter()
+            .filter(|slot| self.accounts_index.is_root(*slot))
+            .collect();
+        rooted_slots.sort_unstable();
+        *self.cold_storage_candidate_slots.lock().unwrap() = rooted_slots.into_iter().collect();
+        *self.cold_storage_slot_age.write().unwrap() = Some(slot_age);
+    }
+
+    /// Sets the number of decompressed bytes of `ColdStorage` blocks kept in memory
+    pub fn set_cold_storage_cache_size(&self, size: usize) {
+        self.cold_storage_cache.set_max_size(size);
+    }
+
+    /// Whether the accounts of `slot` belong in `ColdStorage`
+    fn is_cold_slot(&self, slot: Slot) -> bool {
+        match *self.cold_storage_slot_age.read().unwrap() {
+            Some(slot_age) => {
+                slot.saturating_add(slot_age) <= self.accounts_index.max_root()
+                    && self.accounts_index.is_root(slot)
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_last_full_snapshot_slot(&self, slot: Slot) {
+        *self.last_full_snapshot_slot.write().unwrap() = Some(slot);
+    }
+
+    pub fn last_full_snapshot_slot(&self) -> Option<Slot> {
+        *self.last_full_snapshot_slot.read().unwrap()
+    }
+
     pub fn file_size(&self) -> u64 {
         self.file_size
     }
@@ -1430,6 +1518,7 @@ impl AccountsDb {
 
         let mut key_timings = CleanKeyTimings::default();
         let pubkeys = self.construct_candidate_clean_keys(max_clean_root, &mut key_timings);
+        let last_full_snapshot_slot = self.last_full_snapshot_slot();
 
         let total_keys_count = pubkeys.len();
         let mut accounts_scan = Measure::start("accounts_scan");
@@ -1447,7 +1536,13 @@ impl AccountsDb {
                             {
                                 let slot_list = locked_entry.slot_list();
                                 let (slot, account_info) = &slot_list[index];
-                                if account_info.lamports == 0 {
+                                // A zero-lamport update newer than the last full snapshot is
+                                // what tells an incremental snapshot the account is gone
+                                let is_needed_by_incremental_snapshot = last_full_snapshot_slot
+                                    .map(|last_full_snapshot_slot| *slot > last_full_snapshot_slot)
+                                    .unwrap_or(false);
+                                if account_info.lamports == 0 && !is_needed_by_incremental_snapshot
+                                {
                                     purges.insert(
                                         *pubkey,
                                         self.accounts_index
@@ -1478,11 +1573,8 @@ impl AccountsDb {
                                     purges_in_root.push(*pubkey);
                                 }
                             } else {
-                                let r_accounts_index =
-                                    self.accounts_index.account_maps.read().unwrap();
-                                if !r_accounts_index.contains_key(pubkey) {
-                                    self.accounts_index.remove_zero_lamport_key(pubkey);
-                                }
+                                self.accounts_index
+                                    .remove_zero_lamport_key_if_missing(pubkey);
                             }
                         }
                         (purges, purges_in_root)
@@ -1833,37 +1925,44 @@ impl AccountsDb {
             start.stop();
             find_alive_elapsed = start.as_us();
 
-            let mut start = Measure::start("create_and_insert_store_elapsed");
-            let shrunken_store = if let Some(new_store) =
-                self.try_recycle_and_insert_store(slot, aligned_total, aligned_total + 1024)
-            {
-                new_store
+            if self.is_cold_slot(slot) {
+                // Old rooted slots are rewritten into a compressed, read-only `ColdStorage`
+                // instead, which is created along with its store
+                store_accounts_timing =
+                    self.store_accounts_cold(slot, &accounts, &hashes, &write_versions);
             } else {
-                let maybe_shrink_paths = self.shrink_paths.read().unwrap();
-                if let Some(ref shrink_paths) = *maybe_shrink_paths {
-                    self.create_and_insert_store_with_paths(
-                        slot,
-                        aligned_total,
-                        "shrink-w-path",
-                        shrink_paths,
-                    )
+                let mut start = Measure::start("create_and_insert_store_elapsed");
+                let shrunken_store = if let Some(new_store) =
+                    self.try_recycle_and_insert_store(slot, aligned_total, aligned_total + 1024)
+                {
+                    new_store
                 } else {
-                    self.create_and_insert_store(slot, aligned_total, "shrink")
-                }
-            };
-            start.stop();
-            create_and_insert_store_elapsed = start.as_us();
+                    let maybe_shrink_paths = self.shrink_paths.read().unwrap();
+                    if let Some(ref shrink_paths) = *maybe_shrink_paths {
+                        self.create_and_insert_store_with_paths(
+                            slot,
+                            aligned_total,
+                            "shrink-w-path",
+                            shrink_paths,
+                        )
+                    } else {
+                        self.create_and_insert_store(slot, aligned_total, "shrink")
+                    }
+                };
+                start.stop();
+                create_and_insert_store_elapsed = start.as_us();
 
-            // here, we're writing back alive_accounts. That should be an atomic operation
-            // without use of rather wide locks in this whole function, because we're
-            // mutating rooted slots; There should be no writers to them.
-            store_accounts_timing = self.store_accounts_frozen(
-                slot,
-                &accounts,
-                &hashes,
-                Some(Box::new(move |_, _| shrunken_store.clone())),
-                Some(Box::new(write_versions.into_iter())),
-            );
+                // here, we're writing back alive_accounts. That should be an atomic operation
+                // without use of rather wide locks in this whole function, because we're
+                // mutating rooted slots; There should be no writers to them.
+                store_accounts_timing = self.store_accounts_frozen(
+                    slot,
+                    &accounts,
+                    &hashes,
+                    Some(Box::new(move |_, _| shrunken_store.clone())),
+                    Some(Box::new(write_versions.into_iter())),
+                );
+            }
 
             // `store_accounts_frozen()` above may have purged accounts from some
             // other storage entries (the ones that were just overwritten by this
@@ -2001,7 +2100,49 @@ impl AccountsDb {
             measure.stop();
             inc_new_counter_info!("shrink_candidate_slots-ms", measure.as_ms() as usize);
         }
-        num_candidates
+        num_candidates + self.convert_cold_slots()
+    }
+
+    /// Rewrites the stores of up to `MAX_COLD_STORAGE_SLOTS_PER_PASS` rooted slots which have
+    /// become old enough into `ColdStorage`. Returns the number of slots converted.
+    fn convert_cold_slots(&self) -> usize {
+        let slot_age = match *self.cold_storage_slot_age.read().unwrap() {
+            Some(slot_age) => slot_age,
+            None => return 0,
+        };
+        let max_root = self.accounts_index.max_root();
+        let mut num_converted = 0;
+        while num_converted < MAX_COLD_STORAGE_SLOTS_PER_PASS {
+            let slot = {
+                let mut candidate_slots = self.cold_storage_candidate_slots.lock().unwrap();
+                match candidate_slots.front() {
+                    // Slots still in the accounts cache are converted once they are flushed
+                    Some(slot)
+                        if slot.saturating_add(slot_age) <= max_root
+                            && self.accounts_cache.slot_cache(*slot).is_none() =>
+                    {
+                        candidate_slots.pop_front().unwrap()
+                    }
+                    _ => break,
+                }
+            };
+            if !self.is_cold_slot(slot) {
+                continue;
+            }
+            let stores: Vec<Arc<AccountStorageEntry>> = match self.storage.get_slot_stores(slot) {
+                Some(stores_lock) => stores_lock.read().unwrap().values().cloned().collect(),
+                None => continue,
+            };
+            if stores.iter().all(|store| !store.accounts.is_appendable()) {
+                continue;
+            }
+            let mut measure = Measure::start("convert_cold_slots-ms");
+            self.do_shrink_slot_stores(slot, stores.iter());
+            measure.stop();
+            inc_new_counter_info!("convert_cold_slots-ms", measure.as_ms() as usize);
+            num_converted += 1;
+        }
+        num_converted
     }
 
     pub fn shrink_all_slots(&self) {
@@ -2805,7 +2946,7 @@ impl AccountsDb {
                 &account.account_meta.owner,
                 account.account_meta.executable,
                 account.account_meta.rent_epoch,
-                account.data,
+                &account.data,
                 &account.meta.pubkey,
                 include_owner,
             )
@@ -2816,7 +2957,7 @@ impl AccountsDb {
                 &account.account_meta.owner,
                 account.account_meta.executable,
                 account.account_meta.rent_epoch,
-                account.data,
+                &account.data,
                 &account.meta.pubkey,
                 include_owner,
             )
@@ -3504,14 +3645,7 @@ impl AccountsDb {
     ) -> Result<(Hash, u64), BankHashVerificationError> {
         use BankHashVerificationError::*;
         let mut scan = Measure::start("scan");
-        let keys: Vec<_> = self
-            .accounts_index
-            .account_maps
-            .read()
-            .unwrap()
-            .keys()
-            .cloned()
-            .collect();
+        let keys = self.accounts_index.keys();
         let mismatch_found = AtomicU64::new(0);
         // Pick a chunk size big enough to allow us to produce output vectors that are smaller than the overall size.
         // We'll also accumulate the lamports within each chunk and fewer chunks results in less contention to accumulate the sum.
@@ -4162,9 +4296,19 @@ impl AccountsDb {
             .store_hash_accounts
             .fetch_add(hash_time.as_us(), Ordering::Relaxed);
         self.store_accounts_unfrozen(slot, accounts, &hashes, is_cached_store);
+        self.notify_account_updates(slot, accounts);
         self.report_store_timings();
     }
 
+    fn notify_account_updates(&self, slot: Slot, accounts: &[(&Pubkey, &AccountSharedData)]) {
+        if let Some(accounts_update_notifier) = &*self.accounts_update_notifier.read().unwrap() {
+            let notifier = accounts_update_notifier.read().unwrap();
+            for (pubkey, account) in accounts {
+                notifier.notify_account_update(slot, pubkey, account);
+            }
+        }
+    }
+
     fn report_store_timings(&self) {
         let last = self.stats.last_store_report.load(Ordering::Relaxed);
         let now = solana_sdk::timing::timestamp();
@@ -4352,6 +4496,32 @@ impl AccountsDb {
         self.stats
             .store_accounts
             .fetch_add(store_accounts_time.as_us(), Ordering::Relaxed);
+        let (update_index_elapsed, handle_reclaims_elapsed) = self
+            .update_index_and_handle_reclaims(
+                slot,
+                infos,
+                accounts,
+                is_cached_store,
+                reset_accounts,
+            );
+
+        StoreAccountsTiming {
+            store_accounts_elapsed: store_accounts_time.as_us(),
+            update_index_elapsed,
+            handle_reclaims_elapsed,
+        }
+    }
+
+    /// Points the index at the newly stored `accounts`, and drops the versions they replace in
+    /// `slot` from their storages. Returns the time spent on each, in microseconds.
+    fn update_index_and_handle_reclaims(
+        &self,
+        slot: Slot,
+        infos: Vec<AccountInfo>,
+        accounts: &[(&Pubkey, &AccountSharedData)],
+        is_cached_store: bool,
+        reset_accounts: bool,
+    ) -> (u64, u64) {
         let mut update_index_time = Measure::start("update_index");
 
         // If the cache was flushed, then because `update_index` occurs
@@ -4393,10 +4563,103 @@ impl AccountsDb {
             .store_handle_reclaims
             .fetch_add(handle_reclaims_time.as_us(), Ordering::Relaxed);
 
+        (update_index_time.as_us(), handle_reclaims_time.as_us())
+    }
+
+    /// Writes the accounts of rooted `slot` to a new `ColdStorage`, and points the index at it
+    fn store_accounts_cold(
+        &self,
+        slot: Slot,
+        accounts: &[(&Pubkey, &AccountSharedData)],
+        hashes: &[Hash],
+        write_versions: &[u64],
+    ) -> StoreAccountsTiming {
+        let mut store_accounts_time = Measure::start("store_accounts");
+        let mut sorted: Vec<_> = accounts
+            .iter()
+            .zip(hashes)
+            .zip(write_versions)
+            .map(|((account, hash), write_version)| (*account, *hash, *write_version))
+            .collect();
+        sorted.sort_by(|a, b| a.0 .0.cmp(b.0 .0));
+        let accounts: Vec<_> = sorted.iter().map(|(account, _, _)| *account).collect();
+        let hashes: Vec<_> = sorted.iter().map(|(_, hash, _)| *hash).collect();
+        let default_account = AccountSharedData::default();
+        let accounts_and_meta_to_store: Vec<_> = sorted
+            .iter()
+            .map(|((pubkey, account), _, write_version)| {
+                let account = if account.lamports == 0 {
+                    &default_account
+                } else {
+                    *account
+                };
+                let meta = StoredMeta {
+                    write_version: *write_version,
+                    pubkey: **pubkey,
+                    data_len: account.data().len() as u64,
+                };
+                (meta, account)
+            })
+            .collect();
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        if id == CACHE_VIRTUAL_STORAGE_ID {
+            panic!("We've run out of storage ids!");
+        }
+        let path = {
+            let maybe_shrink_paths = self.shrink_paths.read().unwrap();
+            let paths = maybe_shrink_paths.as_ref().unwrap_or(&self.paths);
+            paths[thread_rng().gen_range(0, paths.len())].join(AppendVec::file_name(slot, id))
+        };
+        let (cold_storage, offsets) = ColdStorage::new(
+            &path,
+            &accounts_and_meta_to_store,
+            &hashes,
+            self.cold_storage_cache.clone(),
+        )
+        .unwrap_or_else(|err| panic!("Unable to create cold storage {}: {}", path.display(), err));
+        let store = Arc::new(AccountStorageEntry::new_existing(
+            slot,
+            id,
+            AccountsFile::Cold(cold_storage),
+            0,
+        ));
+        let infos: Vec<_> = offsets
+            .windows(2)
+            .zip(&accounts)
+            .map(|(offsets, (_, account))| {
+                let stored_size = offsets[1] - offsets[0];
+                store.add_account(stored_size);
+                AccountInfo {
+                    store_id: id,
+                    offset: offsets[0],
+                    stored_size,
+                    lamports: account.lamports,
+                }
+            })
+            .collect();
+        debug!(
+            "creating cold store: {} slot: {} len: {} file size: {} path: {:?}",
+            id,
+            slot,
+            store.accounts.len(),
+            std::fs::metadata(&path)
+                .map(|metadata| metadata.len())
+                .unwrap_or_default(),
+            path
+        );
+        self.insert_store(slot, store);
+        store_accounts_time.stop();
+        self.stats
+            .store_accounts
+            .fetch_add(store_accounts_time.as_us(), Ordering::Relaxed);
+
+        let (update_index_elapsed, handle_reclaims_elapsed) =
+            self.update_index_and_handle_reclaims(slot, infos, &accounts, false, false);
         StoreAccountsTiming {
             store_accounts_elapsed: store_accounts_time.as_us(),
-            update_index_elapsed: update_index_time.as_us(),
-            handle_reclaims_elapsed: handle_reclaims_time.as_us(),
+            update_index_elapsed,
+            handle_reclaims_elapsed,
         }
     }
 
@@ -4405,6 +4668,12 @@ impl AccountsDb {
         if self.caching_enabled {
             self.accounts_cache.add_root(slot);
         }
+        if self.cold_storage_slot_age.read().unwrap().is_some() {
+            self.cold_storage_candidate_slots
+                .lock()
+                .unwrap()
+                .push_back(slot);
+        }
     }
 
     pub fn get_snapshot_storages(&self, snapshot_slot: Slot) -> SnapshotStorages {
@@ -4505,7 +4774,7 @@ impl AccountsDb {
         }
 
         let mut stored_sizes_and_counts = HashMap::new();
-        for account_entry in self.accounts_index.account_maps.read().unwrap().values() {
+        for (_pubkey, account_entry) in self.accounts_index.iter(None::<Range<Pubkey>>).flatten() {
             for (_slot, account_entry) in account_entry.slot_list.read().unwrap().iter() {
                 let storage_entry_meta = stored_sizes_and_counts
                     .entry(account_entry.store_id)
@@ -4554,7 +4823,7 @@ impl AccountsDb {
         #[allow(clippy::stable_sort_primitive)]
         roots.sort();
         info!("{}: accounts_index roots: {:?}", label, roots,);
-        for (pubkey, account_entry) in self.accounts_index.account_maps.read().unwrap().iter() {
+        for (pubkey, account_entry) in self.accounts_index.iter(None::<Range<Pubkey>>).flatten() {
             info!("  key: {} ref_count: {}", pubkey, account_entry.ref_count(),);
             info!(
                 "      slots: {:?}",
@@ -4987,6 +5256,7 @@ pub mod tests {
     use super::*;
     use crate::{
         accounts_hash::MERKLE_FANOUT, accounts_index::tests::*, accounts_index::RefCount,
+        accounts_update_notifier_interface::AccountsUpdateNotifierInterface,
         append_vec::AccountMeta, inline_spl_token_v2_0,
     };
     use assert_matches::assert_matches;
@@ -5207,7 +5477,7 @@ pub mod tests {
         let size: usize = 123;
         let mut data = AccountStorageEntry::new(&paths[0], slot_expected, 0, size as u64);
         let av = AppendVec::new(&tf.path, true, 1024 * 1024);
-        data.accounts = av;
+        data.accounts = AccountsFile::AppendVec(av);
 
         let arc = Arc::new(data);
         let storages = vec![vec![arc]];
@@ -5945,6 +6215,35 @@ pub mod tests {
         assert!(accounts.accounts_index.get(&pubkey, None, None).is_none());
     }
 
+    #[test]
+    fn test_clean_zero_lamport_after_last_full_snapshot() {
+        solana_logger::setup();
+
+        let accounts = AccountsDb::new(Vec::new(), &ClusterType::Development);
+        let pubkey = solana_sdk::pubkey::new_rand();
+        let account = AccountSharedData::new(1, 0, &AccountSharedData::default().owner);
+        let zero_lamport_account =
+            AccountSharedData::new(0, 0, &AccountSharedData::default().owner);
+
+        accounts.store_uncached(0, &[(&pubkey, &account)]);
+        accounts.store_uncached(1, &[(&pubkey, &zero_lamport_account)]);
+        accounts.add_root(0);
+        accounts.add_root(1);
+        accounts.set_last_full_snapshot_slot(0);
+
+        // The zero-lamport update in slot 1 is newer than the last full snapshot, so it must
+        // survive for an incremental snapshot to record the removal
+        accounts.clean_accounts(None);
+        assert_eq!(accounts.alive_account_count_in_slot(1), 1);
+        assert!(accounts.accounts_index.get(&pubkey, None, None).is_some());
+
+        // Once a full snapshot covers slot 1 the account can be purged
+        accounts.set_last_full_snapshot_slot(1);
+        accounts.clean_accounts(None);
+        assert_eq!(accounts.alive_account_count_in_slot(1), 0);
+        assert!(accounts.accounts_index.get(&pubkey, None, None).is_none());
+    }
+
     #[test]
     fn test_clean_old_with_normal_account() {
         solana_logger::setup();
@@ -6016,6 +6315,7 @@ pub mod tests {
             &ClusterType::Development,
             spl_token_mint_index_enabled(),
             false,
+            None,
         );
         let pubkey1 = solana_sdk::pubkey::new_rand();
         let pubkey2 = solana_sdk::pubkey::new_rand();
@@ -6862,12 +7162,12 @@ pub mod tests {
             unsafe { std::mem::transmute::<InputBlob, InputTuple>(blob) };
 
         let stored_account = StoredAccountMeta {
-            meta: &meta,
-            account_meta: &account_meta,
-            data: &data,
+            meta: Cow::Borrowed(&meta),
+            account_meta: Cow::Borrowed(&account_meta),
+            data: Cow::Borrowed(&data),
             offset,
             stored_size: CACHE_VIRTUAL_STORED_SIZE,
-            hash: &hash,
+            hash: Cow::Borrowed(&hash),
         };
         let account = stored_account.clone_account();
         let expected_account_hash =
@@ -7680,6 +7980,88 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_convert_cold_slots() {
+        solana_logger::setup();
+
+        let accounts = AccountsDb::new_single();
+        accounts.set_cold_storage_slot_age(2);
+
+        let owner = Pubkey::new_unique();
+        let pubkeys: Vec<_> = (0..1000).map(|_| Pubkey::new_unique()).collect();
+        let mut current_slot = 1;
+        accounts.set_hash(current_slot, current_slot - 1);
+        for (i, pubkey) in pubkeys.iter().enumerate() {
+            let account = AccountSharedData::new(i as u64 + 1, i % 200, &owner);
+            accounts.store_uncached(current_slot, &[(pubkey, &account)]);
+        }
+        accounts.get_accounts_delta_hash(current_slot);
+        accounts.add_root(current_slot);
+        let cold_slot = current_slot;
+
+        // Update some of the accounts, so that the rewritten slot has dead accounts too
+        current_slot += 1;
+        accounts.set_hash(current_slot, current_slot - 1);
+        let updated_account = AccountSharedData::new(1_000_000, 10, &owner);
+        for pubkey in &pubkeys[..100] {
+            accounts.store_uncached(current_slot, &[(pubkey, &updated_account)]);
+        }
+        accounts.get_accounts_delta_hash(current_slot);
+        accounts.add_root(current_slot);
+        accounts.clean_accounts(None);
+
+        let ancestors = linear_ancestors(current_slot);
+        let expected_hash = accounts.update_accounts_hash_test(current_slot, &ancestors);
+        let expected_accounts: Vec<_> = pubkeys
+            .iter()
+            .map(|pubkey| accounts.load_slow(&ancestors, pubkey).unwrap())
+            .collect();
+
+        // Neither slot is old enough yet
+        assert_eq!(accounts.convert_cold_slots(), 0);
+
+        current_slot += 1;
+        accounts.set_hash(current_slot, current_slot - 1);
+        accounts.add_root(current_slot);
+        assert_eq!(accounts.convert_cold_slots(), 1);
+
+        let slot_stores = accounts.storage.get_slot_stores(cold_slot).unwrap();
+        let slot_stores = slot_stores.read().unwrap();
+        assert_eq!(slot_stores.len(), 1);
+        let store = slot_stores.values().next().unwrap();
+        assert!(!store.accounts.is_appendable());
+        assert_eq!(store.count(), pubkeys.len() - 100);
+        assert_eq!(
+            accounts.all_account_count_in_append_vec(cold_slot),
+            pubkeys.len() - 100
+        );
+        assert!(accounts
+            .storage
+            .get_slot_stores(cold_slot + 1)
+            .unwrap()
+            .read()
+            .unwrap()
+            .values()
+            .all(|store| store.accounts.is_appendable()));
+
+        let ancestors = linear_ancestors(current_slot);
+        for (pubkey, expected) in pubkeys.iter().zip(expected_accounts) {
+            assert_eq!(accounts.load_slow(&ancestors, pubkey).unwrap(), expected);
+        }
+        assert_eq!(
+            accounts.update_accounts_hash_test(current_slot, &ancestors),
+            expected_hash
+        );
+
+        // Cold slots are never picked up twice
+        assert_eq!(accounts.convert_cold_slots(), 0);
+        accounts.shrink_all_slots();
+        assert_eq!(
+            accounts.all_account_count_in_append_vec(cold_slot),
+            pubkeys.len() - 100
+        );
+    }
+
     #[test]
     fn test_shrink_stale_slots_skipped() {
         solana_logger::setup();
@@ -7777,7 +8159,7 @@ pub mod tests {
             &key0,
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             info0,
             &mut reclaims,
         );
@@ -7786,7 +8168,7 @@ pub mod tests {
             &key0,
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             info1.clone(),
             &mut reclaims,
         );
@@ -7795,7 +8177,7 @@ pub mod tests {
             &key1,
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             info1,
             &mut reclaims,
         );
@@ -7804,7 +8186,7 @@ pub mod tests {
             &key1,
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             info2.clone(),
             &mut reclaims,
         );
@@ -7813,7 +8195,7 @@ pub mod tests {
             &key2,
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             info2,
             &mut reclaims,
         );
@@ -7822,7 +8204,7 @@ pub mod tests {
             &key2,
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             info3,
             &mut reclaims,
         );
@@ -8211,8 +8593,9 @@ pub mod tests {
         let db = Arc::new(AccountsDb::new_with_config(
             Vec::new(),
             &ClusterType::Development,
-            HashSet::new(),
+            AccountSecondaryIndexes::default(),
             caching_enabled,
+            None,
         ));
 
         let account_key = Pubkey::new_unique();
@@ -8247,8 +8630,9 @@ pub mod tests {
         let db = Arc::new(AccountsDb::new_with_config(
             Vec::new(),
             &ClusterType::Development,
-            HashSet::new(),
+            AccountSecondaryIndexes::default(),
             caching_enabled,
+            None,
         ));
 
         let zero_lamport_account_key = Pubkey::new_unique();
@@ -8370,8 +8754,9 @@ pub mod tests {
         let db = Arc::new(AccountsDb::new_with_config(
             Vec::new(),
             &ClusterType::Development,
-            HashSet::new(),
+            AccountSecondaryIndexes::default(),
             caching_enabled,
+            None,
         ));
         let account_key = Pubkey::new_unique();
         let account_key2 = Pubkey::new_unique();
@@ -8454,8 +8839,9 @@ pub mod tests {
         let accounts_db = AccountsDb::new_with_config(
             Vec::new(),
             &ClusterType::Development,
-            HashSet::new(),
+            AccountSecondaryIndexes::default(),
             caching_enabled,
+            None,
         );
         let slot: Slot = 0;
         let num_keys = 10;
@@ -8508,8 +8894,9 @@ pub mod tests {
         let accounts_db = Arc::new(AccountsDb::new_with_config(
             Vec::new(),
             &ClusterType::Development,
-            HashSet::new(),
+            AccountSecondaryIndexes::default(),
             caching_enabled,
+            None,
         ));
         let slots: Vec<_> = (0..num_slots as Slot).into_iter().collect();
         let stall_slot = num_slots as Slot;
@@ -8896,8 +9283,9 @@ pub mod tests {
         let db = AccountsDb::new_with_config(
             Vec::new(),
             &ClusterType::Development,
-            HashSet::default(),
+            AccountSecondaryIndexes::default(),
             caching_enabled,
+            None,
         );
         let account_key1 = Pubkey::new_unique();
         let account_key2 = Pubkey::new_unique();
@@ -9106,4 +9494,39 @@ pub mod tests {
         assert_eq!(recycle_stores.entry_count(), 1);
         assert_eq!(recycle_stores.total_bytes(), dummy_size);
     }
+
+    #[derive(Debug, Default)]
+    struct AccountsUpdateRecorder {
+        updates: Mutex<Vec<(Slot, Pubkey, u64)>>,
+    }
+
+    impl AccountsUpdateNotifierInterface for AccountsUpdateRecorder {
+        fn notify_account_update(&self, slot: Slot, pubkey: &Pubkey, account: &AccountSharedData) {
+            self.updates
+                .lock()
+                .unwrap()
+                .push((slot, *pubkey, account.lamports()));
+        }
+    }
+
+    #[test]
+    fn test_accounts_update_notifier() {
+        let db = AccountsDb::new(Vec::new(), &ClusterType::Development);
+        let recorder = Arc::new(RwLock::new(AccountsUpdateRecorder::default()));
+        db.set_accounts_update_notifier(recorder.clone());
+
+        let key0 = solana_sdk::pubkey::new_rand();
+        let key1 = solana_sdk::pubkey::new_rand();
+        let account0 = AccountSharedData::new(1, 0, &Pubkey::default());
+        let account1 = AccountSharedData::new(2, 0, &Pubkey::default());
+        db.store_uncached(0, &[(&key0, &account0)]);
+        db.store_cached(1, &[(&key0, &account1), (&key1, &account0)]);
+        // An empty store is not a committed write
+        db.store_uncached(2, &[]);
+
+        assert_eq!(
+            *recorder.read().unwrap().updates.lock().unwrap(),
+            vec![(0, key0, 1), (1, key0, 2), (1, key1, 1)]
+        );
+    }
 }
@@ -0,0 +1,163 @@
+//! The file formats an `AccountStorageEntry` can keep its accounts in: an `AppendVec`, which
+//! new accounts are written to, or a read-only `ColdStorage` for old rooted slots.
+
+use crate::{
+    append_vec::{AppendVec, StoredAccountMeta, StoredMeta},
+    cold_storage::{ColdStorage, ColdStorageCache},
+};
+use solana_sdk::{account::AccountSharedData, hash::Hash};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+#[derive(Debug)]
+pub enum AccountsFile {
+    AppendVec(AppendVec),
+    Cold(ColdStorage),
+}
+
+impl AccountsFile {
+    /// Opens an existing file of either format. Returns the file and its number of accounts.
+    /// A cold storage keeps its decompressed blocks in `cold_storage_cache`.
+    pub fn new_from_file<P: AsRef<Path>>(
+        path: P,
+        current_len: usize,
+        cold_storage_cache: &Arc<ColdStorageCache>,
+    ) -> io::Result<(Self, usize)> {
+        if ColdStorage::is_cold_storage(&path)? {
+            let (storage, num_accounts) =
+                ColdStorage::new_from_file(path, current_len, cold_storage_cache.clone())?;
+            Ok((AccountsFile::Cold(storage), num_accounts))
+        } else {
+            let (append_vec, num_accounts) = AppendVec::new_from_file(path, current_len)?;
+            Ok((AccountsFile::AppendVec(append_vec), num_accounts))
+        }
+    }
+
+    /// Whether accounts can be appended to the file
+    pub fn is_appendable(&self) -> bool {
+        matches!(self, AccountsFile::AppendVec(_))
+    }
+
+    pub fn flush(&self) -> io::Result<()> {
+        match self {
+            AccountsFile::AppendVec(append_vec) => append_vec.flush(),
+            AccountsFile::Cold(_) => Ok(()),
+        }
+    }
+
+    /// Drops all accounts of an `AppendVec`. Cold storages are never reused, so this is a
+    /// no-op for them.
+    pub fn reset(&self) {
+        if let AccountsFile::AppendVec(append_vec) = self {
+            append_vec.reset();
+        }
+    }
+
+    /// The number of bytes used to store the accounts, uncompressed
+    pub fn len(&self) -> usize {
+        match self {
+            AccountsFile::AppendVec(append_vec) => append_vec.len(),
+            AccountsFile::Cold(storage) => storage.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of bytes available for storing accounts. A cold storage is always full.
+    pub fn capacity(&self) -> u64 {
+        match self {
+            AccountsFile::AppendVec(append_vec) => append_vec.capacity(),
+            AccountsFile::Cold(storage) => storage.len() as u64,
+        }
+    }
+
+    pub fn get_account(&self, offset: usize) -> Option<(StoredAccountMeta, usize)> {
+        match self {
+            AccountsFile::AppendVec(append_vec) => append_vec.get_account(offset),
+            AccountsFile::Cold(storage) => storage.get_account(offset),
+        }
+    }
+
+    pub fn accounts(&self, offset: usize) -> Vec<StoredAccountMeta> {
+        match self {
+            AccountsFile::AppendVec(append_vec) => append_vec.accounts(offset),
+            AccountsFile::Cold(storage) => storage.accounts(offset),
+        }
+    }
+
+    /// See `AppendVec::append_accounts()`. Nothing can be appended to a cold storage, so for
+    /// one this only returns its end offset, as for a full `AppendVec`.
+    pub fn append_accounts(
+        &self,
+        accounts: &[(StoredMeta, &AccountSharedData)],
+        hashes: &[Hash],
+    ) -> Vec<usize> {
+        match self {
+            AccountsFile::AppendVec(append_vec) => append_vec.append_accounts(accounts, hashes),
+            AccountsFile::Cold(storage) => vec![storage.len()],
+        }
+    }
+
+    pub fn get_path(&self) -> PathBuf {
+        match self {
+            AccountsFile::AppendVec(append_vec) => append_vec.get_path(),
+            AccountsFile::Cold(storage) => storage.get_path(),
+        }
+    }
+
+    pub fn set_no_remove_on_drop(&mut self) {
+        match self {
+            AccountsFile::AppendVec(append_vec) => append_vec.set_no_remove_on_drop(),
+            AccountsFile::Cold(storage) => storage.set_no_remove_on_drop(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::append_vec::test_utils::*;
+
+    #[test]
+    fn test_accounts_file_new_from_file() {
+        let (meta, account) = create_test_account(10);
+        let hash = Hash::new_unique();
+
+        let path = get_append_vec_path("test_accounts_file_new_from_file");
+        let mut append_vec = AppendVec::new(&path.path, true, 1024);
+        append_vec.append_account(meta.clone(), &account, hash);
+        append_vec.set_no_remove_on_drop();
+        let len = append_vec.len();
+        drop(append_vec);
+        let cold_storage_cache = Arc::new(ColdStorageCache::default());
+        let (accounts_file, num_accounts) =
+            AccountsFile::new_from_file(&path.path, len, &cold_storage_cache).unwrap();
+        assert!(accounts_file.is_appendable());
+        assert_eq!(num_accounts, 1);
+        assert_eq!(accounts_file.accounts(0)[0].clone_account(), account);
+
+        let cold_path = get_append_vec_path("test_accounts_file_new_from_file_cold");
+        let (mut storage, _) = ColdStorage::new(
+            &cold_path.path,
+            &[(meta, &account)],
+            &[hash],
+            cold_storage_cache.clone(),
+        )
+        .unwrap();
+        storage.set_no_remove_on_drop();
+        drop(storage);
+        let (accounts_file, num_accounts) =
+            AccountsFile::new_from_file(&cold_path.path, len, &cold_storage_cache).unwrap();
+        assert!(!accounts_file.is_appendable());
+        assert_eq!(num_accounts, 1);
+        assert_eq!(accounts_file.len(), len);
+        assert_eq!(accounts_file.capacity(), len as u64);
+        assert_eq!(accounts_file.accounts(0)[0].clone_account(), account);
+        assert_eq!(accounts_file.append_accounts(&[], &[]), vec![len]);
+    }
+}
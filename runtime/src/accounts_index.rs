@@ -1,14 +1,18 @@
 use crate::{
+    bucket_map::BucketMap,
     contains::Contains,
     inline_spl_token_v2_0::{self, SPL_TOKEN_ACCOUNT_MINT_OFFSET, SPL_TOKEN_ACCOUNT_OWNER_OFFSET},
     secondary_index::*,
 };
-use dashmap::DashSet;
+use dashmap::{DashMap, DashSet};
+use log::*;
 use ouroboros::self_referencing;
+use serde::{de::DeserializeOwned, Serialize};
 use solana_measure::measure::Measure;
 use solana_sdk::{
     clock::Slot,
     pubkey::{Pubkey, PUBKEY_BYTES},
+    system_instruction::MAX_PERMITTED_DATA_LENGTH,
 };
 use std::{
     collections::{
@@ -20,13 +24,17 @@ use std::{
         Bound::{Excluded, Included, Unbounded},
         Range, RangeBounds,
     },
+    path::PathBuf,
+    str::FromStr,
     sync::{
-        atomic::{AtomicU64, Ordering},
-        Arc, RwLock, RwLockReadGuard, RwLockWriteGuard,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard,
     },
 };
 
 pub const ITER_BATCH_SIZE: usize = 1000;
+pub const DEFAULT_ACCOUNTS_INDEX_MAX_IN_MEMORY_ENTRIES: usize = 1_000_000;
+pub const DEFAULT_ACCOUNTS_INDEX_NUM_BUCKETS_POW2: u8 = 12;
 
 pub type SlotList<T> = Vec<(Slot, T)>;
 pub type SlotSlice<'s, T> = &'s [(Slot, T)];
@@ -37,6 +45,39 @@ pub type AccountMap<K, V> = BTreeMap<K, V>;
 
 type AccountMapEntry<T> = Arc<AccountMapEntryInner<T>>;
 
+/// Values which can be kept in the accounts index, including in its files on disk
+pub trait IndexValue:
+    'static + Clone + IsCached + ZeroLamport + Send + Sync + Serialize + DeserializeOwned
+{
+}
+
+impl<T: 'static + Clone + IsCached + ZeroLamport + Send + Sync + Serialize + DeserializeOwned>
+    IndexValue for T
+{
+}
+
+/// Configures the accounts index to keep only its recently used entries in memory, and the
+/// rest in files on disk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountsIndexConfig {
+    /// Directories the index files are spread over
+    pub drives: Vec<PathBuf>,
+    /// Number of entries kept in memory before the least recently used ones are moved to disk
+    pub max_in_memory_entries: usize,
+    /// Log2 of the number of buckets the entries on disk are split into
+    pub num_buckets_pow2: u8,
+}
+
+impl AccountsIndexConfig {
+    pub fn new(drives: Vec<PathBuf>) -> Self {
+        Self {
+            drives,
+            max_in_memory_entries: DEFAULT_ACCOUNTS_INDEX_MAX_IN_MEMORY_ENTRIES,
+            num_buckets_pow2: DEFAULT_ACCOUNTS_INDEX_NUM_BUCKETS_POW2,
+        }
+    }
+}
+
 pub trait IsCached {
     fn is_cached(&self) -> bool;
 }
@@ -63,6 +104,7 @@ pub enum IndexKey {
     ProgramId(Pubkey),
     SplTokenMint(Pubkey),
     SplTokenOwner(Pubkey),
+    Custom(CustomIndex, Pubkey),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -70,15 +112,127 @@ pub enum AccountIndex {
     ProgramId,
     SplTokenMint,
     SplTokenOwner,
+    Custom(CustomIndex),
+}
+
+/// A secondary index of the accounts owned by `program_id`, keyed by the `length` bytes of
+/// their data at `offset`. Keys shorter than a pubkey are padded with zeros.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CustomIndex {
+    pub program_id: Pubkey,
+    pub offset: usize,
+    pub length: usize,
+}
+
+impl CustomIndex {
+    pub fn new(program_id: Pubkey, offset: usize, length: usize) -> Self {
+        assert!(
+            length > 0 && length <= PUBKEY_BYTES,
+            "custom index length must be between 1 and {}",
+            PUBKEY_BYTES
+        );
+        Self {
+            program_id,
+            offset,
+            length,
+        }
+    }
+
+    /// Returns the key of an account with `data` in this index, if its data is long enough.
+    pub fn key_of(&self, data: &[u8]) -> Option<Pubkey> {
+        let end = self.offset.checked_add(self.length)?;
+        self.key(data.get(self.offset..end)?)
+    }
+
+    /// Returns the key of `bytes` in this index, if they are the length of the indexed field.
+    pub fn key(&self, bytes: &[u8]) -> Option<Pubkey> {
+        if bytes.len() != self.length {
+            return None;
+        }
+        let mut key = [0u8; PUBKEY_BYTES];
+        key[..bytes.len()].copy_from_slice(bytes);
+        Some(Pubkey::new(&key))
+    }
+}
+
+impl FromStr for CustomIndex {
+    type Err = String;
+
+    /// Parses `PROGRAM_ID:OFFSET:LENGTH`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = s.split(':').collect();
+        if parts.len() != 3 {
+            return Err(format!(
+                "invalid custom index {}, expected PROGRAM_ID:OFFSET:LENGTH",
+                s
+            ));
+        }
+        let program_id = Pubkey::from_str(parts[0]).map_err(|err| err.to_string())?;
+        let offset: usize = parts[1].parse().map_err(|_| "invalid offset".to_string())?;
+        let length: usize = parts[2].parse().map_err(|_| "invalid length".to_string())?;
+        if length == 0 || length > PUBKEY_BYTES {
+            return Err(format!("length must be between 1 and {}", PUBKEY_BYTES));
+        }
+        if offset.saturating_add(length) as u64 > MAX_PERMITTED_DATA_LENGTH {
+            return Err(format!(
+                "the indexed field must end within {} bytes",
+                MAX_PERMITTED_DATA_LENGTH
+            ));
+        }
+        Ok(Self::new(program_id, offset, length))
+    }
+}
+
+/// Restricts which keys are added to the secondary indexes
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AccountSecondaryIndexesIncludeExclude {
+    /// If true, `keys` are left out of the indexes, otherwise only `keys` are indexed
+    pub exclude: bool,
+    pub keys: HashSet<Pubkey>,
+}
+
+/// The secondary indexes to maintain, and optionally which of their keys to index
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AccountSecondaryIndexes {
+    pub keys: Option<AccountSecondaryIndexesIncludeExclude>,
+    pub indexes: HashSet<AccountIndex>,
+}
+
+impl AccountSecondaryIndexes {
+    pub fn is_empty(&self) -> bool {
+        self.indexes.is_empty()
+    }
+
+    pub fn contains(&self, account_index: &AccountIndex) -> bool {
+        self.indexes.contains(account_index)
+    }
+
+    /// Whether `key` should be added to the enabled secondary indexes
+    pub fn include_key(&self, key: &Pubkey) -> bool {
+        match &self.keys {
+            Some(options) => options.exclude ^ options.keys.contains(key),
+            None => true,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct AccountMapEntryInner<T> {
     ref_count: AtomicU64,
     pub slot_list: RwLock<SlotList<T>>,
+    // Set on every access, and cleared when the entry is passed over for eviction to disk
+    accessed: AtomicBool,
 }
 
 impl<T> AccountMapEntryInner<T> {
+    pub fn new(slot_list: SlotList<T>, ref_count: RefCount) -> Self {
+        Self {
+            ref_count: AtomicU64::new(ref_count),
+            slot_list: RwLock::new(slot_list),
+            accessed: AtomicBool::new(true),
+        }
+    }
+
     pub fn ref_count(&self) -> u64 {
         self.ref_count.load(Ordering::Relaxed)
     }
@@ -190,6 +344,7 @@ pub struct AccountsIndexRootsStats {
 
 pub struct AccountsIndexIterator<'a, T> {
     account_maps: &'a RwLock<AccountMap<Pubkey, AccountMapEntry<T>>>,
+    disk: Option<&'a BucketMap<T>>,
     start_bound: Bound<Pubkey>,
     end_bound: Bound<Pubkey>,
     is_finished: bool,
@@ -206,6 +361,7 @@ impl<'a, T> AccountsIndexIterator<'a, T> {
 
     pub fn new<R>(
         account_maps: &'a RwLock<AccountMap<Pubkey, AccountMapEntry<T>>>,
+        disk: Option<&'a BucketMap<T>>,
         range: Option<R>,
     ) -> Self
     where
@@ -221,17 +377,64 @@ impl<'a, T> AccountsIndexIterator<'a, T> {
                 .map(|r| Self::clone_bound(r.end_bound()))
                 .unwrap_or(Unbounded),
             account_maps,
+            disk,
             is_finished: false,
         }
     }
 }
 
-impl<'a, T: 'static + Clone> Iterator for AccountsIndexIterator<'a, T> {
+impl<'a, T: IndexValue> AccountsIndexIterator<'a, T> {
+    // Returns the entries of the next bucket on disk which has any keys in range, along with
+    // the entries in memory which belong to the same bucket, sorted by key.
+    fn next_bucket(&mut self, disk: &BucketMap<T>) -> Option<Vec<(Pubkey, AccountMapEntry<T>)>> {
+        let mut ix = match self.start_bound {
+            Included(key) | Excluded(key) => disk.bucket_ix(&key),
+            Unbounded => 0,
+        };
+        loop {
+            let range = (self.start_bound, self.end_bound);
+            let mut chunk: Vec<_> = {
+                // Entries only move between memory and disk under the write lock.
+                let account_maps = self.account_maps.read().unwrap();
+                account_maps
+                    .range(range)
+                    .take_while(|(pubkey, _)| disk.bucket_ix(pubkey) == ix)
+                    .map(|(pubkey, account_map_entry)| (*pubkey, account_map_entry.clone()))
+                    .chain(disk.bucket_range(ix, &range).into_iter().map(
+                        |(pubkey, slot_list, ref_count)| {
+                            let entry = AccountMapEntryInner::new(slot_list, ref_count);
+                            (pubkey, Arc::new(entry))
+                        },
+                    ))
+                    .collect()
+            };
+            chunk.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+            ix += 1;
+            match disk.bucket_start(ix) {
+                Some(next_start) if RangeBounds::contains(&range, &next_start) => {
+                    self.start_bound = Included(next_start);
+                }
+                _ => self.is_finished = true,
+            }
+            if !chunk.is_empty() {
+                return Some(chunk);
+            }
+            if self.is_finished {
+                return None;
+            }
+        }
+    }
+}
+
+impl<'a, T: IndexValue> Iterator for AccountsIndexIterator<'a, T> {
     type Item = Vec<(Pubkey, AccountMapEntry<T>)>;
     fn next(&mut self) -> Option<Self::Item> {
         if self.is_finished {
             return None;
         }
+        if let Some(disk) = self.disk {
+            return self.next_bucket(disk);
+        }
 
         let chunk: Vec<(Pubkey, AccountMapEntry<T>)> = self
             .account_maps
@@ -256,23 +459,195 @@ pub trait ZeroLamport {
     fn is_zero_lamport(&self) -> bool;
 }
 
+#[derive(Debug, Default)]
+struct EvictionState {
+    // Key the next search for entries to evict starts at
+    cursor: Pubkey,
+    // Number of entries in memory above which entries are evicted
+    threshold: usize,
+}
+
 #[derive(Debug, Default)]
 pub struct AccountsIndex<T> {
-    pub account_maps: RwLock<AccountMap<Pubkey, AccountMapEntry<T>>>,
+    account_maps: RwLock<AccountMap<Pubkey, AccountMapEntry<T>>>,
+    // Entries which are not in `account_maps`, if the index is backed by disk. Each key is
+    // either in memory or on disk, never both.
+    disk: Option<BucketMap<T>>,
+    max_in_memory_entries: usize,
+    eviction: Mutex<EvictionState>,
     program_id_index: SecondaryIndex<DashMapSecondaryIndexEntry>,
     spl_token_mint_index: SecondaryIndex<DashMapSecondaryIndexEntry>,
     spl_token_owner_index: SecondaryIndex<RwLockSecondaryIndexEntry>,
+    custom_indexes: DashMap<CustomIndex, Arc<SecondaryIndex<DashMapSecondaryIndexEntry>>>,
     roots_tracker: RwLock<RootsTracker>,
     ongoing_scan_roots: RwLock<BTreeMap<Slot, u64>>,
     zero_lamport_pubkeys: DashSet<Pubkey>,
 }
 
-impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
-    fn iter<R>(&self, range: Option<R>) -> AccountsIndexIterator<T>
+impl<T: IndexValue> AccountsIndex<T> {
+    pub fn new(config: Option<AccountsIndexConfig>) -> Self {
+        let (disk, max_in_memory_entries) = match config {
+            Some(config) => (
+                Some(BucketMap::new(config.drives, config.num_buckets_pow2)),
+                config.max_in_memory_entries,
+            ),
+            None => (None, 0),
+        };
+        Self {
+            account_maps: RwLock::default(),
+            disk,
+            max_in_memory_entries,
+            eviction: Mutex::default(),
+            program_id_index: SecondaryIndex::default(),
+            spl_token_mint_index: SecondaryIndex::default(),
+            spl_token_owner_index: SecondaryIndex::default(),
+            custom_indexes: DashMap::default(),
+            roots_tracker: RwLock::default(),
+            ongoing_scan_roots: RwLock::default(),
+            zero_lamport_pubkeys: DashSet::default(),
+        }
+    }
+
+    pub(crate) fn iter<R>(&self, range: Option<R>) -> AccountsIndexIterator<T>
     where
         R: RangeBounds<Pubkey>,
     {
-        AccountsIndexIterator::new(&self.account_maps, range)
+        AccountsIndexIterator::new(&self.account_maps, self.disk.as_ref(), range)
+    }
+
+    pub fn contains_key(&self, pubkey: &Pubkey) -> bool {
+        let account_maps = self.account_maps.read().unwrap();
+        account_maps.contains_key(pubkey)
+            || self
+                .disk
+                .as_ref()
+                .map_or(false, |disk| disk.contains_key(pubkey))
+    }
+
+    /// Returns all keys in the index, sorted.
+    pub fn keys(&self) -> Vec<Pubkey> {
+        let account_maps = self.account_maps.read().unwrap();
+        let mut keys: Vec<_> = account_maps.keys().cloned().collect();
+        if let Some(disk) = &self.disk {
+            keys.extend(disk.keys());
+            keys.sort_unstable();
+        }
+        keys
+    }
+
+    /// Returns the number of entries in memory and on disk.
+    pub fn num_entries(&self) -> (usize, usize) {
+        let account_maps = self.account_maps.read().unwrap();
+        let on_disk = self.disk.as_ref().map_or(0, BucketMap::len);
+        (account_maps.len(), on_disk)
+    }
+
+    // Returns the entry of `pubkey`, first moving it into memory if it is on disk.
+    fn get_account_map_entry(&self, pubkey: &Pubkey) -> Option<AccountMapEntry<T>> {
+        {
+            let account_maps = self.account_maps.read().unwrap();
+            if let Some(entry) = account_maps.get(pubkey) {
+                if !entry.accessed.load(Ordering::Relaxed) {
+                    entry.accessed.store(true, Ordering::Relaxed);
+                }
+                return Some(entry.clone());
+            }
+            if !self
+                .disk
+                .as_ref()
+                .map_or(false, |disk| disk.contains_key(pubkey))
+            {
+                return None;
+            }
+        }
+        self.load_from_disk(&mut self.account_maps.write().unwrap(), pubkey)
+    }
+
+    // Moves the entry of `pubkey` from disk into memory, if it is not already there.
+    fn load_from_disk(
+        &self,
+        account_maps: &mut AccountMap<Pubkey, AccountMapEntry<T>>,
+        pubkey: &Pubkey,
+    ) -> Option<AccountMapEntry<T>> {
+        if let Some(entry) = account_maps.get(pubkey) {
+            return Some(entry.clone());
+        }
+        let disk = self.disk.as_ref()?;
+        let (slot_list, ref_count) = disk.read_value(pubkey)?;
+        disk.delete_key(pubkey);
+        let entry = Arc::new(AccountMapEntryInner::new(slot_list, ref_count));
+        account_maps.insert(*pubkey, entry.clone());
+        self.maybe_evict(account_maps);
+        Some(entry)
+    }
+
+    // Moves entries which are not in use and were not accessed recently from memory to disk,
+    // once there are too many entries in memory.
+    fn maybe_evict(&self, account_maps: &mut AccountMap<Pubkey, AccountMapEntry<T>>) {
+        let disk = match &self.disk {
+            Some(disk) => disk,
+            None => return,
+        };
+        let mut eviction = self.eviction.lock().unwrap();
+        if account_maps.len() <= eviction.threshold.max(self.max_in_memory_entries) {
+            return;
+        }
+        let slack = (self.max_in_memory_entries / 10).max(1);
+        let target = self.max_in_memory_entries.saturating_sub(slack);
+        let mut num_evicted = 0;
+        let mut evict_time = Measure::start("evict");
+        // Entries accessed since they were last looked at get a second chance, so this takes
+        // up to two passes over the entries.
+        for _ in 0..2 {
+            let num_to_evict = account_maps.len().saturating_sub(target);
+            if num_to_evict == 0 {
+                break;
+            }
+            let cursor = eviction.cursor;
+            let keys: Vec<Pubkey> = account_maps
+                .range(cursor..)
+                .chain(account_maps.range(..cursor))
+                .filter(|(_, entry)| {
+                    // Entries in use are referenced outside of the index.
+                    Arc::strong_count(entry) == 1
+                        && !entry.accessed.swap(false, Ordering::Relaxed)
+                        && !entry
+                            .slot_list
+                            .read()
+                            .unwrap()
+                            .iter()
+                            .any(|(_, info)| info.is_cached())
+                })
+                .map(|(pubkey, _)| *pubkey)
+                .take(num_to_evict)
+                .collect();
+            if let Some(pubkey) = keys.last() {
+                eviction.cursor = *pubkey;
+            }
+            for pubkey in keys {
+                let entry = &account_maps[&pubkey];
+                let slot_list = entry.slot_list.read().unwrap();
+                if let Err(err) = disk.insert(&pubkey, &slot_list, entry.ref_count()) {
+                    // The entry stays in memory
+                    warn!(
+                        "Unable to evict {} from the accounts index: {}",
+                        pubkey, err
+                    );
+                    continue;
+                }
+                drop(slot_list);
+                account_maps.remove(&pubkey);
+                num_evicted += 1;
+            }
+        }
+        eviction.threshold = account_maps.len() + slack;
+        evict_time.stop();
+        datapoint_info!(
+            "accounts_index-evict",
+            ("num_evicted", num_evicted, i64),
+            ("num_in_memory", account_maps.len(), i64),
+            ("evict_us", evict_time.as_us(), i64),
+        );
     }
 
     fn do_checked_scan_accounts<F, R>(
@@ -450,6 +825,12 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
                     Some(max_root),
                 );
             }
+            ScanTypes::Indexed(IndexKey::Custom(custom_index, key)) => {
+                // Nothing was indexed yet if the index doesn't exist.
+                if let Some(index) = self.custom_index(&custom_index) {
+                    self.do_scan_secondary_index(ancestors, func, &*index, &key, Some(max_root));
+                }
+            }
         }
 
         {
@@ -560,35 +941,28 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
     }
 
     pub fn get_account_read_entry(&self, pubkey: &Pubkey) -> Option<ReadAccountMapEntry<T>> {
-        self.account_maps
-            .read()
-            .unwrap()
-            .get(pubkey)
-            .cloned()
+        self.get_account_map_entry(pubkey)
             .map(ReadAccountMapEntry::from_account_map_entry)
     }
 
     fn get_account_write_entry(&self, pubkey: &Pubkey) -> Option<WriteAccountMapEntry<T>> {
-        self.account_maps
-            .read()
-            .unwrap()
-            .get(pubkey)
-            .cloned()
+        self.get_account_map_entry(pubkey)
             .map(WriteAccountMapEntry::from_account_map_entry)
     }
 
     fn insert_new_entry_if_missing(&self, pubkey: &Pubkey) -> (WriteAccountMapEntry<T>, bool) {
-        let new_entry = Arc::new(AccountMapEntryInner {
-            ref_count: AtomicU64::new(0),
-            slot_list: RwLock::new(SlotList::with_capacity(1)),
-        });
+        let new_entry = Arc::new(AccountMapEntryInner::new(SlotList::with_capacity(1), 0));
         let mut w_account_maps = self.account_maps.write().unwrap();
-        let mut is_newly_inserted = false;
-        let account_entry = w_account_maps.entry(*pubkey).or_insert_with(|| {
-            is_newly_inserted = true;
-            new_entry
-        });
-        let w_account_entry = WriteAccountMapEntry::from_account_map_entry(account_entry.clone());
+        let (account_entry, is_newly_inserted) =
+            match self.load_from_disk(&mut w_account_maps, pubkey) {
+                Some(account_entry) => (account_entry, false),
+                None => {
+                    w_account_maps.insert(*pubkey, new_entry.clone());
+                    self.maybe_evict(&mut w_account_maps);
+                    (new_entry, true)
+                }
+            };
+        let w_account_entry = WriteAccountMapEntry::from_account_map_entry(account_entry);
         (w_account_entry, is_newly_inserted)
     }
 
@@ -607,24 +981,34 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
         (w_account_entry.unwrap(), is_newly_inserted)
     }
 
-    pub fn handle_dead_keys(&self, dead_keys: &[&Pubkey], account_indexes: &HashSet<AccountIndex>) {
+    pub fn handle_dead_keys(
+        &self,
+        dead_keys: &[&Pubkey],
+        account_indexes: &AccountSecondaryIndexes,
+    ) {
         if !dead_keys.is_empty() {
             for key in dead_keys.iter() {
                 let mut w_index = self.account_maps.write().unwrap();
-                if let btree_map::Entry::Occupied(index_entry) = w_index.entry(**key) {
-                    if index_entry.get().slot_list.read().unwrap().is_empty() {
-                        index_entry.remove();
-
-                        // Note passing `None` to remove all the entries for this key
-                        // is only safe because we have the lock for this key's entry
-                        // in the AccountsIndex, so no other thread is also updating
-                        // the index
-                        self.purge_secondary_indexes_by_inner_key(
-                            key,
-                            None::<&Slot>,
-                            account_indexes,
-                        );
+                let is_dead = match w_index.entry(**key) {
+                    btree_map::Entry::Occupied(index_entry) => {
+                        let is_dead = index_entry.get().slot_list.read().unwrap().is_empty();
+                        if is_dead {
+                            index_entry.remove();
+                        }
+                        is_dead
                     }
+                    // The entry may have been moved to disk since its slot list was emptied.
+                    btree_map::Entry::Vacant(_) => self.disk.as_ref().map_or(false, |disk| {
+                        matches!(disk.read_value(key), Some((slot_list, _)) if slot_list.is_empty())
+                            && disk.delete_key(key)
+                    }),
+                };
+                if is_dead {
+                    // Note passing `None` to remove all the entries for this key
+                    // is only safe because we have the lock for this key's entry
+                    // in the AccountsIndex, so no other thread is also updating
+                    // the index
+                    self.purge_secondary_indexes_by_inner_key(key, None::<&Slot>, account_indexes);
                 }
             }
         }
@@ -709,7 +1093,7 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
         pubkey: &Pubkey,
         slots_to_purge: &'a C,
         reclaims: &mut SlotList<T>,
-        account_indexes: &HashSet<AccountIndex>,
+        account_indexes: &AccountSecondaryIndexes,
     ) -> bool
     where
         C: Contains<'a, Slot>,
@@ -812,21 +1196,51 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
         max_root
     }
 
+    fn custom_index(
+        &self,
+        custom_index: &CustomIndex,
+    ) -> Option<Arc<SecondaryIndex<DashMapSecondaryIndexEntry>>> {
+        self.custom_indexes
+            .get(custom_index)
+            .map(|index| index.value().clone())
+    }
+
     fn update_secondary_indexes(
         &self,
         pubkey: &Pubkey,
         slot: Slot,
         account_owner: &Pubkey,
         account_data: &[u8],
-        account_indexes: &HashSet<AccountIndex>,
+        account_indexes: &AccountSecondaryIndexes,
     ) {
         if account_indexes.is_empty() {
             return;
         }
 
-        if account_indexes.contains(&AccountIndex::ProgramId) {
+        if account_indexes.contains(&AccountIndex::ProgramId)
+            && account_indexes.include_key(account_owner)
+        {
             self.program_id_index.insert(account_owner, pubkey, slot);
         }
+        for account_index in &account_indexes.indexes {
+            if let AccountIndex::Custom(custom_index) = account_index {
+                if custom_index.program_id != *account_owner {
+                    continue;
+                }
+                if let Some(key) = custom_index
+                    .key_of(account_data)
+                    .filter(|key| account_indexes.include_key(key))
+                {
+                    let index = self.custom_index(custom_index).unwrap_or_else(|| {
+                        self.custom_indexes
+                            .entry(*custom_index)
+                            .or_default()
+                            .clone()
+                    });
+                    index.insert(&key, pubkey, slot);
+                }
+            }
+        }
         // Note because of the below check below on the account data length, when an
         // account hits zero lamports and is reset to AccountSharedData::Default, then we skip
         // the below updates to the secondary indexes.
@@ -848,7 +1262,9 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
                     &account_data[SPL_TOKEN_ACCOUNT_OWNER_OFFSET
                         ..SPL_TOKEN_ACCOUNT_OWNER_OFFSET + PUBKEY_BYTES],
                 );
-                self.spl_token_owner_index.insert(&owner_key, pubkey, slot);
+                if account_indexes.include_key(&owner_key) {
+                    self.spl_token_owner_index.insert(&owner_key, pubkey, slot);
+                }
             }
 
             if account_indexes.contains(&AccountIndex::SplTokenMint) {
@@ -856,7 +1272,9 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
                     &account_data[SPL_TOKEN_ACCOUNT_MINT_OFFSET
                         ..SPL_TOKEN_ACCOUNT_MINT_OFFSET + PUBKEY_BYTES],
                 );
-                self.spl_token_mint_index.insert(&mint_key, pubkey, slot);
+                if account_indexes.include_key(&mint_key) {
+                    self.spl_token_mint_index.insert(&mint_key, pubkey, slot);
+                }
             }
         }
     }
@@ -870,7 +1288,7 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
         pubkey: &Pubkey,
         account_owner: &Pubkey,
         account_data: &[u8],
-        account_indexes: &HashSet<AccountIndex>,
+        account_indexes: &AccountSecondaryIndexes,
         account_info: T,
         reclaims: &mut SlotList<T>,
     ) {
@@ -893,7 +1311,7 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
         pubkey: &Pubkey,
         account_owner: &Pubkey,
         account_data: &[u8],
-        account_indexes: &HashSet<AccountIndex>,
+        account_indexes: &AccountSecondaryIndexes,
         account_info: T,
         reclaims: &mut SlotList<T>,
     ) -> bool {
@@ -925,6 +1343,20 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
         self.zero_lamport_pubkeys.remove(pubkey);
     }
 
+    // Removes `pubkey` from the zero lamport keys if it is no longer in the index. The
+    // lock is held so the key can't be inserted again in the meantime.
+    pub fn remove_zero_lamport_key_if_missing(&self, pubkey: &Pubkey) {
+        let account_maps = self.account_maps.read().unwrap();
+        if !account_maps.contains_key(pubkey)
+            && !self
+                .disk
+                .as_ref()
+                .map_or(false, |disk| disk.contains_key(pubkey))
+        {
+            self.zero_lamport_pubkeys.remove(pubkey);
+        }
+    }
+
     pub fn zero_lamport_pubkeys(&self) -> &DashSet<Pubkey> {
         &self.zero_lamport_pubkeys
     }
@@ -947,7 +1379,7 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
         &'a self,
         inner_key: &Pubkey,
         slots_to_remove: Option<&'a C>,
-        account_indexes: &HashSet<AccountIndex>,
+        account_indexes: &AccountSecondaryIndexes,
     ) where
         C: Contains<'a, Slot>,
     {
@@ -965,6 +1397,14 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
             self.spl_token_mint_index
                 .remove_by_inner_key(inner_key, slots_to_remove);
         }
+
+        for account_index in &account_indexes.indexes {
+            if let AccountIndex::Custom(custom_index) = account_index {
+                if let Some(index) = self.custom_index(custom_index) {
+                    index.remove_by_inner_key(inner_key, slots_to_remove);
+                }
+            }
+        }
     }
 
     fn purge_older_root_entries(
@@ -973,7 +1413,7 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
         list: &mut SlotList<T>,
         reclaims: &mut SlotList<T>,
         max_clean_root: Option<Slot>,
-        account_indexes: &HashSet<AccountIndex>,
+        account_indexes: &AccountSecondaryIndexes,
     ) {
         let roots_tracker = &self.roots_tracker.read().unwrap();
         let max_root = Self::get_max_root(&roots_tracker.roots, &list, max_clean_root);
@@ -997,7 +1437,7 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
         pubkey: &Pubkey,
         reclaims: &mut SlotList<T>,
         max_clean_root: Option<Slot>,
-        account_indexes: &HashSet<AccountIndex>,
+        account_indexes: &AccountSecondaryIndexes,
     ) {
         if let Some(mut locked_entry) = self.get_account_write_entry(pubkey) {
             locked_entry.slot_list_mut(|slot_list| {
@@ -1151,6 +1591,7 @@ impl<T: 'static + Clone + IsCached + ZeroLamport> AccountsIndex<T> {
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use serde::Deserialize;
     use solana_sdk::signature::{Keypair, Signer};
 
     pub enum SecondaryIndexTypes<'a> {
@@ -1158,19 +1599,19 @@ pub mod tests {
         DashMap(&'a SecondaryIndex<DashMapSecondaryIndexEntry>),
     }
 
-    pub fn spl_token_mint_index_enabled() -> HashSet<AccountIndex> {
-        let mut account_indexes = HashSet::new();
-        account_indexes.insert(AccountIndex::SplTokenMint);
+    pub fn spl_token_mint_index_enabled() -> AccountSecondaryIndexes {
+        let mut account_indexes = AccountSecondaryIndexes::default();
+        account_indexes.indexes.insert(AccountIndex::SplTokenMint);
         account_indexes
     }
 
-    pub fn spl_token_owner_index_enabled() -> HashSet<AccountIndex> {
-        let mut account_indexes = HashSet::new();
-        account_indexes.insert(AccountIndex::SplTokenOwner);
+    pub fn spl_token_owner_index_enabled() -> AccountSecondaryIndexes {
+        let mut account_indexes = AccountSecondaryIndexes::default();
+        account_indexes.indexes.insert(AccountIndex::SplTokenOwner);
         account_indexes
     }
 
-    fn create_dashmap_secondary_index_state() -> (usize, usize, HashSet<AccountIndex>) {
+    fn create_dashmap_secondary_index_state() -> (usize, usize, AccountSecondaryIndexes) {
         {
             // Check that we're actually testing the correct variant
             let index = AccountsIndex::<bool>::default();
@@ -1180,7 +1621,7 @@ pub mod tests {
         (0, PUBKEY_BYTES, spl_token_mint_index_enabled())
     }
 
-    fn create_rwlock_secondary_index_state() -> (usize, usize, HashSet<AccountIndex>) {
+    fn create_rwlock_secondary_index_state() -> (usize, usize, AccountSecondaryIndexes) {
         {
             // Check that we're actually testing the correct variant
             let index = AccountsIndex::<bool>::default();
@@ -1217,7 +1658,7 @@ pub mod tests {
             &key.pubkey(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             true,
             &mut gc,
         );
@@ -1242,7 +1683,7 @@ pub mod tests {
             &key.pubkey(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             true,
             &mut gc,
         );
@@ -1266,7 +1707,7 @@ pub mod tests {
             &key.pubkey(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             true,
             &mut gc,
         );
@@ -1299,7 +1740,7 @@ pub mod tests {
                 &new_pubkey,
                 &Pubkey::default(),
                 &[],
-                &HashSet::new(),
+                &AccountSecondaryIndexes::default(),
                 true,
                 &mut vec![],
             );
@@ -1315,7 +1756,7 @@ pub mod tests {
                 &Pubkey::default(),
                 &Pubkey::default(),
                 &[],
-                &HashSet::new(),
+                &AccountSecondaryIndexes::default(),
                 true,
                 &mut vec![],
             );
@@ -1446,7 +1887,7 @@ pub mod tests {
             &solana_sdk::pubkey::new_rand(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             true,
             &mut gc,
         );
@@ -1471,7 +1912,7 @@ pub mod tests {
             &key.pubkey(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             true,
             &mut gc,
         );
@@ -1585,7 +2026,7 @@ pub mod tests {
             &key.pubkey(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             true,
             &mut gc,
         );
@@ -1600,7 +2041,7 @@ pub mod tests {
             &key.pubkey(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             false,
             &mut gc,
         );
@@ -1621,7 +2062,7 @@ pub mod tests {
             &key.pubkey(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             true,
             &mut gc,
         );
@@ -1631,7 +2072,7 @@ pub mod tests {
             &key.pubkey(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             false,
             &mut gc,
         );
@@ -1653,7 +2094,7 @@ pub mod tests {
             &key.pubkey(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             true,
             &mut gc,
         );
@@ -1663,7 +2104,7 @@ pub mod tests {
             &key.pubkey(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             false,
             &mut gc,
         );
@@ -1672,7 +2113,7 @@ pub mod tests {
             &key.pubkey(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             true,
             &mut gc,
         );
@@ -1681,7 +2122,7 @@ pub mod tests {
             &key.pubkey(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             true,
             &mut gc,
         );
@@ -1693,7 +2134,7 @@ pub mod tests {
             &key.pubkey(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             true,
             &mut gc,
         );
@@ -1727,7 +2168,7 @@ pub mod tests {
             &key.pubkey(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             12,
             &mut gc
         ));
@@ -1737,7 +2178,7 @@ pub mod tests {
             &key.pubkey(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             10,
             &mut gc
         ));
@@ -1754,7 +2195,7 @@ pub mod tests {
             &key.pubkey(),
             &Pubkey::default(),
             &[],
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
             9,
             &mut gc
         ));
@@ -1810,7 +2251,7 @@ pub mod tests {
         secondary_index: &SecondaryIndex<SecondaryIndexEntryType>,
         key_start: usize,
         key_end: usize,
-        account_index: &HashSet<AccountIndex>,
+        account_index: &AccountSecondaryIndexes,
     ) {
         // No roots, should be no reclaims
         let slots = vec![1, 2, 5, 9];
@@ -1899,7 +2340,7 @@ pub mod tests {
             &mut slot_list,
             &mut reclaims,
             None,
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
         );
         assert!(reclaims.is_empty());
         assert_eq!(slot_list, vec![(1, true), (2, true), (5, true), (9, true)]);
@@ -1915,7 +2356,7 @@ pub mod tests {
             &mut slot_list,
             &mut reclaims,
             None,
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
         );
         assert_eq!(reclaims, vec![(1, true), (2, true)]);
         assert_eq!(slot_list, vec![(5, true), (9, true)]);
@@ -1929,7 +2370,7 @@ pub mod tests {
             &mut slot_list,
             &mut reclaims,
             None,
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
         );
         assert_eq!(reclaims, vec![(1, true), (2, true)]);
         assert_eq!(slot_list, vec![(5, true), (9, true)]);
@@ -1943,7 +2384,7 @@ pub mod tests {
             &mut slot_list,
             &mut reclaims,
             Some(6),
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
         );
         assert_eq!(reclaims, vec![(1, true), (2, true)]);
         assert_eq!(slot_list, vec![(5, true), (9, true)]);
@@ -1956,7 +2397,7 @@ pub mod tests {
             &mut slot_list,
             &mut reclaims,
             Some(5),
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
         );
         assert_eq!(reclaims, vec![(1, true), (2, true)]);
         assert_eq!(slot_list, vec![(5, true), (9, true)]);
@@ -1970,7 +2411,7 @@ pub mod tests {
             &mut slot_list,
             &mut reclaims,
             Some(2),
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
         );
         assert!(reclaims.is_empty());
         assert_eq!(slot_list, vec![(1, true), (2, true), (5, true), (9, true)]);
@@ -1984,7 +2425,7 @@ pub mod tests {
             &mut slot_list,
             &mut reclaims,
             Some(1),
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
         );
         assert!(reclaims.is_empty());
         assert_eq!(slot_list, vec![(1, true), (2, true), (5, true), (9, true)]);
@@ -1998,7 +2439,7 @@ pub mod tests {
             &mut slot_list,
             &mut reclaims,
             Some(7),
-            &HashSet::new(),
+            &AccountSecondaryIndexes::default(),
         );
         assert_eq!(reclaims, vec![(1, true), (2, true)]);
         assert_eq!(slot_list, vec![(5, true), (9, true)]);
@@ -2037,7 +2478,7 @@ pub mod tests {
         secondary_index: &SecondaryIndex<SecondaryIndexEntryType>,
         key_start: usize,
         key_end: usize,
-        account_index: &HashSet<AccountIndex>,
+        account_index: &AccountSecondaryIndexes,
     ) {
         let account_key = Pubkey::new_unique();
         let index_key = Pubkey::new_unique();
@@ -2127,7 +2568,7 @@ pub mod tests {
         secondary_index: &SecondaryIndex<SecondaryIndexEntryType>,
         index_key_start: usize,
         index_key_end: usize,
-        account_index: &HashSet<AccountIndex>,
+        account_index: &AccountSecondaryIndexes,
     ) {
         let account_key = Pubkey::new_unique();
         let secondary_key1 = Pubkey::new_unique();
@@ -2229,6 +2670,341 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_custom_secondary_index() {
+        let program_id = Pubkey::new_unique();
+        let custom_index = CustomIndex::new(program_id, 8, 16);
+        let account_indexes = AccountSecondaryIndexes {
+            keys: None,
+            indexes: vec![AccountIndex::Custom(custom_index)]
+                .into_iter()
+                .collect(),
+        };
+        let index = AccountsIndex::<bool>::default();
+        let account_key = Pubkey::new_unique();
+        let mut account_data = vec![0; 40];
+        account_data[8..24].copy_from_slice(&[7; 16]);
+        let index_key = custom_index.key(&[7; 16]).unwrap();
+
+        // Wrong program id
+        index.upsert(
+            0,
+            &account_key,
+            &Pubkey::default(),
+            &account_data,
+            &account_indexes,
+            true,
+            &mut vec![],
+        );
+        assert!(index.custom_index(&custom_index).is_none());
+
+        // Account data too short
+        index.upsert(
+            0,
+            &account_key,
+            &program_id,
+            &account_data[..20],
+            &account_indexes,
+            true,
+            &mut vec![],
+        );
+        assert!(index.custom_index(&custom_index).is_none());
+
+        index.upsert(
+            1,
+            &account_key,
+            &program_id,
+            &account_data,
+            &account_indexes,
+            true,
+            &mut vec![],
+        );
+        let secondary_index = index.custom_index(&custom_index).unwrap();
+        check_secondary_index_unique(&*secondary_index, 1, &index_key, &account_key);
+
+        // Scans only see the given ancestors if they include the max root
+        let ancestors: Ancestors = vec![(0, 0), (1, 0)].into_iter().collect();
+        let mut found = vec![];
+        index.index_scan_accounts(
+            &ancestors,
+            IndexKey::Custom(custom_index, index_key),
+            |pubkey, _| found.push(*pubkey),
+        );
+        assert_eq!(found, vec![account_key]);
+        let other_key = custom_index.key(&[8; 16]).unwrap();
+        index.index_scan_accounts(
+            &ancestors,
+            IndexKey::Custom(custom_index, other_key),
+            |_, _| panic!("unexpected account"),
+        );
+
+        index
+            .get_account_write_entry(&account_key)
+            .unwrap()
+            .slot_list_mut(|slot_list| slot_list.clear());
+        index.handle_dead_keys(&[&account_key], &account_indexes);
+        assert!(secondary_index.index.is_empty());
+        assert!(secondary_index.reverse_index.is_empty());
+    }
+
+    #[test]
+    fn test_purge_exact_custom_secondary_index() {
+        let program_id = Pubkey::new_unique();
+        let custom_index = CustomIndex::new(program_id, 0, 32);
+        let account_indexes = AccountSecondaryIndexes {
+            keys: None,
+            indexes: vec![AccountIndex::Custom(custom_index)]
+                .into_iter()
+                .collect(),
+        };
+        let index = AccountsIndex::<bool>::default();
+        let account_key = Pubkey::new_unique();
+        let account_data = Pubkey::new_unique().to_bytes();
+        let index_key = custom_index.key(&account_data).unwrap();
+
+        let slots = vec![1, 2];
+        for slot in &slots {
+            index.upsert(
+                *slot,
+                &account_key,
+                &program_id,
+                &account_data,
+                &account_indexes,
+                true,
+                &mut vec![],
+            );
+        }
+        let secondary_index = index.custom_index(&custom_index).unwrap();
+        assert_eq!(secondary_index.index.get(&index_key).unwrap().len(), 1);
+
+        index.purge_exact(
+            &account_key,
+            &slots.into_iter().collect::<HashSet<Slot>>(),
+            &mut vec![],
+            &account_indexes,
+        );
+        assert!(secondary_index.index.is_empty());
+        assert!(secondary_index.reverse_index.is_empty());
+    }
+
+    #[test]
+    fn test_secondary_index_include_exclude_keys() {
+        let included_owner = Pubkey::new_unique();
+        let other_owner = Pubkey::new_unique();
+        let ancestors: Ancestors = vec![(0, 0)].into_iter().collect();
+        let scan = |index: &AccountsIndex<bool>, owner: &Pubkey| {
+            let mut found = vec![];
+            index.index_scan_accounts(&ancestors, IndexKey::ProgramId(*owner), |pubkey, _| {
+                found.push(*pubkey)
+            });
+            found
+        };
+
+        for exclude in [false, true].iter() {
+            let account_indexes = AccountSecondaryIndexes {
+                keys: Some(AccountSecondaryIndexesIncludeExclude {
+                    exclude: *exclude,
+                    keys: vec![included_owner].into_iter().collect(),
+                }),
+                indexes: vec![AccountIndex::ProgramId].into_iter().collect(),
+            };
+            assert_eq!(account_indexes.include_key(&included_owner), !exclude);
+            assert_eq!(account_indexes.include_key(&other_owner), *exclude);
+
+            let index = AccountsIndex::<bool>::default();
+            let included_account = Pubkey::new_unique();
+            let other_account = Pubkey::new_unique();
+            for (account, owner) in [
+                (included_account, included_owner),
+                (other_account, other_owner),
+            ]
+            .iter()
+            {
+                index.upsert(0, account, owner, &[], &account_indexes, true, &mut vec![]);
+            }
+
+            if *exclude {
+                assert!(scan(&index, &included_owner).is_empty());
+                assert_eq!(scan(&index, &other_owner), vec![other_account]);
+            } else {
+                assert_eq!(scan(&index, &included_owner), vec![included_account]);
+                assert!(scan(&index, &other_owner).is_empty());
+            }
+        }
+        assert!(AccountSecondaryIndexes::default().include_key(&other_owner));
+    }
+
+    #[test]
+    fn test_custom_index_from_str() {
+        let program_id = Pubkey::new_unique();
+        assert_eq!(
+            format!("{}:8:32", program_id).parse::<CustomIndex>(),
+            Ok(CustomIndex::new(program_id, 8, 32))
+        );
+        assert!(format!("{}:8", program_id).parse::<CustomIndex>().is_err());
+        assert!(format!("{}:8:0", program_id)
+            .parse::<CustomIndex>()
+            .is_err());
+        assert!(format!("{}:8:33", program_id)
+            .parse::<CustomIndex>()
+            .is_err());
+        assert!(format!("{}:-1:32", program_id)
+            .parse::<CustomIndex>()
+            .is_err());
+        assert!(format!("{}:{}:32", program_id, usize::MAX)
+            .parse::<CustomIndex>()
+            .is_err());
+        assert!(format!("{}:{}:1", program_id, MAX_PERMITTED_DATA_LENGTH)
+            .parse::<CustomIndex>()
+            .is_err());
+        assert!("foo:8:32".parse::<CustomIndex>().is_err());
+    }
+
+    #[test]
+    fn test_custom_index_key_of() {
+        let program_id = Pubkey::new_unique();
+        let index = CustomIndex::new(program_id, 2, 3);
+        let data = [1u8, 2, 3, 4, 5, 6];
+        let mut key = [0u8; PUBKEY_BYTES];
+        key[..3].copy_from_slice(&[3, 4, 5]);
+        assert_eq!(index.key_of(&data), Some(Pubkey::new(&key)));
+        assert_eq!(index.key_of(&data[..4]), None);
+        // A field ending past the address space is never found, rather than overflowing
+        let index = CustomIndex::new(program_id, usize::MAX, 1);
+        assert_eq!(index.key_of(&data), None);
+    }
+
+    fn new_disk_index(max_in_memory_entries: usize) -> (AccountsIndex<u64>, tempfile::TempDir) {
+        let drive = tempfile::TempDir::new().unwrap();
+        let config = AccountsIndexConfig {
+            max_in_memory_entries,
+            num_buckets_pow2: 4,
+            ..AccountsIndexConfig::new(vec![drive.path().to_path_buf()])
+        };
+        (AccountsIndex::new(Some(config)), drive)
+    }
+
+    #[test]
+    fn test_disk_index() {
+        let (index, _drive) = new_disk_index(10);
+        let mut pubkeys: Vec<_> = (0..200).map(|_| solana_sdk::pubkey::new_rand()).collect();
+        let mut gc = Vec::new();
+        for (i, pubkey) in pubkeys.iter().enumerate() {
+            index.upsert(
+                0,
+                pubkey,
+                &Pubkey::default(),
+                &[],
+                &AccountSecondaryIndexes::default(),
+                i as u64,
+                &mut gc,
+            );
+            index.upsert(
+                1,
+                pubkey,
+                &Pubkey::default(),
+                &[],
+                &AccountSecondaryIndexes::default(),
+                i as u64 + 1,
+                &mut gc,
+            );
+        }
+        assert!(gc.is_empty());
+        let (in_memory, on_disk) = index.num_entries();
+        assert!(in_memory <= 11);
+        assert_eq!(in_memory + on_disk, pubkeys.len());
+
+        // Entries are loaded back from disk.
+        let ancestors: Ancestors = vec![(1, 0)].into_iter().collect();
+        for (i, pubkey) in pubkeys.iter().enumerate() {
+            assert!(index.contains_key(pubkey));
+            let (list, ix) = index.get(pubkey, Some(&ancestors), None).unwrap();
+            assert_eq!(list.slot_list()[ix], (1, i as u64 + 1));
+            assert_eq!(list.ref_count().load(Ordering::Relaxed), 2);
+        }
+        assert!(!index.contains_key(&solana_sdk::pubkey::new_rand()));
+        assert!(index.num_entries().0 <= 11);
+
+        // Scans see the entries both in memory and on disk, in order.
+        pubkeys.sort();
+        assert_eq!(index.keys(), pubkeys);
+        let scanned: Vec<_> = index
+            .iter(None::<Range<Pubkey>>)
+            .flatten()
+            .map(|(pubkey, _)| pubkey)
+            .collect();
+        assert_eq!(scanned, pubkeys);
+        let range = pubkeys[50]..pubkeys[150];
+        let scanned: Vec<_> = index
+            .iter(Some(range))
+            .flatten()
+            .map(|(pubkey, _)| pubkey)
+            .collect();
+        assert_eq!(scanned, pubkeys[50..150]);
+        let mut num = 0;
+        index.unchecked_scan_accounts("", &ancestors, |_pubkey, (value, slot)| {
+            assert_eq!(slot, 1);
+            assert!(*value > 0);
+            num += 1;
+        });
+        assert_eq!(num, pubkeys.len());
+
+        // Dead keys are removed wherever their entries are.
+        for pubkey in &pubkeys {
+            let slots: HashSet<Slot> = vec![0, 1].into_iter().collect();
+            assert!(index.purge_exact(
+                pubkey,
+                &slots,
+                &mut gc,
+                &AccountSecondaryIndexes::default()
+            ));
+        }
+        assert_eq!(gc.len(), 2 * pubkeys.len());
+        let dead_keys: Vec<_> = pubkeys.iter().collect();
+        index.handle_dead_keys(&dead_keys, &AccountSecondaryIndexes::default());
+        assert!(pubkeys.iter().all(|pubkey| !index.contains_key(pubkey)));
+        assert_eq!(index.num_entries(), (0, 0));
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct CachedValue;
+
+    impl IsCached for CachedValue {
+        fn is_cached(&self) -> bool {
+            true
+        }
+    }
+
+    impl ZeroLamport for CachedValue {
+        fn is_zero_lamport(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_disk_index_keeps_cached_entries_in_memory() {
+        let drive = tempfile::TempDir::new().unwrap();
+        let config = AccountsIndexConfig {
+            max_in_memory_entries: 10,
+            ..AccountsIndexConfig::new(vec![drive.path().to_path_buf()])
+        };
+        let index = AccountsIndex::<CachedValue>::new(Some(config));
+        let mut gc = Vec::new();
+        let pubkeys: Vec<_> = (0..100).map(|_| solana_sdk::pubkey::new_rand()).collect();
+        for pubkey in &pubkeys {
+            index.upsert(
+                0,
+                pubkey,
+                &Pubkey::default(),
+                &[],
+                &AccountSecondaryIndexes::default(),
+                CachedValue,
+                &mut gc,
+            );
+        }
+        assert_eq!(index.num_entries(), (pubkeys.len(), 0));
+    }
+
     impl ZeroLamport for bool {
         fn is_zero_lamport(&self) -> bool {
             false
@@ -0,0 +1,11 @@
+use solana_sdk::{account::AccountSharedData, clock::Slot, pubkey::Pubkey};
+use std::sync::{Arc, RwLock};
+
+/// Receives a callback for every account write committed to `AccountsDb` through `store`
+/// so that account state can be streamed to an external consumer
+pub trait AccountsUpdateNotifierInterface: std::fmt::Debug {
+    /// Notified when an account is updated at runtime, due to transaction activities
+    fn notify_account_update(&self, slot: Slot, pubkey: &Pubkey, account: &AccountSharedData);
+}
+
+pub type AccountsUpdateNotifier = Arc<RwLock<dyn AccountsUpdateNotifierInterface + Sync + Send>>;
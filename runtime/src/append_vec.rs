@@ -11,6 +11,7 @@ use solana_sdk::{
     pubkey::Pubkey,
 };
 use std::{
+    borrow::Cow,
     fs::{remove_file, OpenOptions},
     io,
     io::{Seek, SeekFrom, Write},
@@ -69,16 +70,17 @@ impl<'a> From<&'a AccountSharedData> for AccountMeta {
 }
 
 /// References to account data stored elsewhere. Getting an `Account` requires cloning
-/// (see `StoredAccountMeta::clone_account()`).
+/// (see `StoredAccountMeta::clone_account()`). Accounts read from a `ColdStorage` own a copy
+/// of their data instead, since the decompressed block they are in may be evicted.
 #[derive(PartialEq, Debug)]
 pub struct StoredAccountMeta<'a> {
-    pub meta: &'a StoredMeta,
+    pub meta: Cow<'a, StoredMeta>,
     /// account data
-    pub account_meta: &'a AccountMeta,
-    pub data: &'a [u8],
+    pub account_meta: Cow<'a, AccountMeta>,
+    pub data: Cow<'a, [u8]>,
     pub offset: usize,
     pub stored_size: usize,
-    pub hash: &'a Hash,
+    pub hash: Cow<'a, Hash>,
 }
 
 impl<'a> StoredAccountMeta<'a> {
@@ -93,7 +95,19 @@ impl<'a> StoredAccountMeta<'a> {
         })
     }
 
-    fn sanitize(&self) -> bool {
+    /// Return a copy of the account which doesn't borrow from its storage
+    pub(crate) fn into_owned(self) -> StoredAccountMeta<'static> {
+        StoredAccountMeta {
+            meta: Cow::Owned(self.meta.into_owned()),
+            account_meta: Cow::Owned(self.account_meta.into_owned()),
+            data: Cow::Owned(self.data.into_owned()),
+            offset: self.offset,
+            stored_size: self.stored_size,
+            hash: Cow::Owned(self.hash.into_owned()),
+        }
+    }
+
+    pub(crate) fn sanitize(&self) -> bool {
         self.sanitize_executable() && self.sanitize_lamports()
     }
 
@@ -416,19 +430,19 @@ impl AppendVec {
         let stored_size = next - offset;
         Some((
             StoredAccountMeta {
-                meta,
-                account_meta,
-                data,
+                meta: Cow::Borrowed(meta),
+                account_meta: Cow::Borrowed(account_meta),
+                data: Cow::Borrowed(data),
                 offset,
                 stored_size,
-                hash,
+                hash: Cow::Borrowed(hash),
             },
             next,
         ))
     }
     pub fn get_account_test(&self, offset: usize) -> Option<(StoredMeta, AccountSharedData)> {
         let (stored_account, _) = self.get_account(offset)?;
-        let meta = stored_account.meta.clone();
+        let meta = stored_account.meta.clone().into_owned();
         Some((meta, stored_account.clone_account()))
     }
 
@@ -8,7 +8,8 @@ use crate::{
         TransactionLoadResult, TransactionLoaders,
     },
     accounts_db::{ErrorCounters, SnapshotStorages},
-    accounts_index::{AccountIndex, Ancestors, IndexKey},
+    accounts_index::{AccountSecondaryIndexes, AccountsIndexConfig, Ancestors, IndexKey},
+    accounts_update_notifier_interface::AccountsUpdateNotifier,
     blockhash_queue::BlockhashQueue,
     builtins::{self, ActivationType},
     epoch_stakes::{EpochStakes, NodeVoteAccounts},
@@ -21,13 +22,14 @@ use crate::{
     status_cache::{SlotDelta, StatusCache},
     system_instruction_processor::{get_system_account_kind, SystemAccountKind},
     transaction_batch::TransactionBatch,
+    transaction_scheduler,
     transaction_utils::OrderedIterator,
     vote_account::ArcVoteAccount,
 };
 use byteorder::{ByteOrder, LittleEndian};
 use itertools::Itertools;
 use log::*;
-use rayon::ThreadPool;
+use rayon::{prelude::*, ThreadPool};
 use solana_measure::measure::Measure;
 use solana_metrics::{datapoint_debug, inc_new_counter_debug, inc_new_counter_info};
 use solana_sdk::{
@@ -51,7 +53,7 @@ use solana_sdk::{
     incinerator,
     inflation::Inflation,
     instruction::CompiledInstruction,
-    message::Message,
+    message::{v0::LoadedAddresses, Message, VersionedMessage},
     native_loader,
     native_token::sol_to_lamports,
     nonce, nonce_account,
@@ -70,7 +72,7 @@ use solana_sdk::{
     system_transaction,
     sysvar::{self},
     timing::years_as_slots,
-    transaction::{self, Result, Transaction, TransactionError},
+    transaction::{self, Result, Transaction, TransactionError, VersionedTransaction},
 };
 use solana_stake_program::stake_state::{
     self, Delegation, InflationPointCalculationEvent, PointValue,
@@ -896,15 +898,16 @@ impl Bank {
             &[],
             None,
             None,
-            HashSet::new(),
+            AccountSecondaryIndexes::default(),
             false,
+            None,
         )
     }
 
     #[cfg(test)]
     pub(crate) fn new_with_config(
         genesis_config: &GenesisConfig,
-        account_indexes: HashSet<AccountIndex>,
+        account_indexes: AccountSecondaryIndexes,
         accounts_db_caching_enabled: bool,
     ) -> Self {
         Self::new_with_paths(
@@ -915,6 +918,7 @@ impl Bank {
             None,
             account_indexes,
             accounts_db_caching_enabled,
+            None,
         )
     }
 
@@ -924,8 +928,9 @@ impl Bank {
         frozen_account_pubkeys: &[Pubkey],
         debug_keys: Option<Arc<HashSet<Pubkey>>>,
         additional_builtins: Option<&Builtins>,
-        account_indexes: HashSet<AccountIndex>,
+        account_indexes: AccountSecondaryIndexes,
         accounts_db_caching_enabled: bool,
+        accounts_index_config: Option<AccountsIndexConfig>,
     ) -> Self {
         let mut bank = Self::default();
         bank.ancestors.insert(bank.slot(), 0);
@@ -937,6 +942,7 @@ impl Bank {
             &genesis_config.cluster_type,
             account_indexes,
             accounts_db_caching_enabled,
+            accounts_index_config,
         ));
         bank.process_genesis_config(genesis_config);
         bank.finish_init(genesis_config, additional_builtins);
@@ -2392,6 +2398,20 @@ impl Bank {
             .map_or(Ok(()), |sig| self.get_signature_status(sig).unwrap())
     }
 
+    /// Process the transactions of an entry in a single batch, resolving their address table
+    /// lookups first.  This is used for benches and unit tests.
+    pub fn process_entry_transactions(&self, txs: Vec<VersionedTransaction>) -> Vec<Result<()>> {
+        let num_txs = txs.len();
+        let txs: Result<Vec<_>> = txs
+            .into_iter()
+            .map(|tx| self.resolve_versioned_transaction(tx))
+            .collect();
+        match txs {
+            Ok(txs) => self.process_transactions(&txs),
+            Err(err) => vec![Err(err); num_txs],
+        }
+    }
+
     pub fn prepare_batch<'a, 'b>(
         &'a self,
         txs: &'b [Transaction],
@@ -2458,6 +2478,52 @@ impl Bank {
         (transaction_result, log_messages)
     }
 
+    /// Resolve the address table lookups of a versioned transaction into the transaction the
+    /// bank executes, whose message lists every account the versioned message references.
+    ///
+    /// The resolved transaction keeps the signatures of the versioned transaction to identify
+    /// it and charge its fees, but for versioned messages they sign the versioned message and
+    /// not the resolved one.  Signatures must be verified, and the transaction recorded into
+    /// entries, in its versioned form; the resolved form is only meant to be executed.
+    pub fn resolve_versioned_transaction(
+        &self,
+        transaction: VersionedTransaction,
+    ) -> Result<Transaction> {
+        transaction.sanitize()?;
+        let VersionedTransaction {
+            signatures,
+            message,
+        } = transaction;
+        let message = match message {
+            VersionedMessage::Legacy(message) => message,
+            VersionedMessage::V0(message) => {
+                if !self.versioned_tx_message_enabled() {
+                    return Err(TransactionError::SanitizeFailure);
+                }
+                let slot_hashes: SlotHashes = self
+                    .get_account(&sysvar::slot_hashes::id())
+                    .and_then(|account| from_account(&account))
+                    .unwrap_or_default();
+                let mut loaded_addresses = LoadedAddresses::default();
+                for address_table_lookup in &message.address_table_lookups {
+                    let addresses = self.rc.accounts.load_lookup_table_addresses(
+                        &self.ancestors,
+                        address_table_lookup,
+                        self.slot(),
+                        &slot_hashes,
+                    )?;
+                    loaded_addresses.writable.extend(addresses.writable);
+                    loaded_addresses.readonly.extend(addresses.readonly);
+                }
+                message.into_legacy_message(loaded_addresses)
+            }
+        };
+        Ok(Transaction {
+            signatures,
+            message,
+        })
+    }
+
     pub fn unlock_accounts(&self, batch: &mut TransactionBatch) {
         if batch.needs_unlock {
             batch.needs_unlock = false;
@@ -2477,6 +2543,27 @@ impl Bank {
         self.rc.accounts.accounts_db.set_shrink_paths(paths);
     }
 
+    pub fn set_cold_storage_slot_age(&self, slot_age: Slot) {
+        self.rc
+            .accounts
+            .accounts_db
+            .set_cold_storage_slot_age(slot_age);
+    }
+
+    pub fn set_cold_storage_cache_size(&self, size: usize) {
+        self.rc
+            .accounts
+            .accounts_db
+            .set_cold_storage_cache_size(size);
+    }
+
+    pub fn set_accounts_update_notifier(&self, notifier: AccountsUpdateNotifier) {
+        self.rc
+            .accounts
+            .accounts_db
+            .set_accounts_update_notifier(notifier);
+    }
+
     fn check_age(
         &self,
         txs: &[Transaction],
@@ -3094,6 +3181,7 @@ impl Bank {
 
         let fee_config = FeeConfig {
             secp256k1_program_enabled: self.secp256k1_program_enabled(),
+            compute_budget_program_enabled: self.compute_budget_program_enabled(),
         };
 
         let results = OrderedIterator::new(txs, iteration_order)
@@ -3764,6 +3852,62 @@ impl Bank {
         TransactionBalancesSet,
         Vec<Option<InnerInstructionsList>>,
         Vec<TransactionLogMessages>,
+    ) {
+        self.do_load_execute_and_commit_transactions(
+            batch,
+            None,
+            max_age,
+            collect_balances,
+            enable_cpi_recording,
+            enable_log_recording,
+            timings,
+        )
+    }
+
+    /// Like `load_execute_and_commit_transactions()`, but the transactions are loaded and
+    /// executed in parallel on `thread_pool` with `par_load_and_execute_transactions()`
+    #[allow(clippy::type_complexity)]
+    pub fn par_load_execute_and_commit_transactions(
+        &self,
+        batch: &TransactionBatch,
+        thread_pool: &ThreadPool,
+        max_age: usize,
+        collect_balances: bool,
+        enable_cpi_recording: bool,
+        enable_log_recording: bool,
+        timings: &mut ExecuteTimings,
+    ) -> (
+        TransactionResults,
+        TransactionBalancesSet,
+        Vec<Option<InnerInstructionsList>>,
+        Vec<TransactionLogMessages>,
+    ) {
+        self.do_load_execute_and_commit_transactions(
+            batch,
+            Some(thread_pool),
+            max_age,
+            collect_balances,
+            enable_cpi_recording,
+            enable_log_recording,
+            timings,
+        )
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn do_load_execute_and_commit_transactions(
+        &self,
+        batch: &TransactionBatch,
+        thread_pool: Option<&ThreadPool>,
+        max_age: usize,
+        collect_balances: bool,
+        enable_cpi_recording: bool,
+        enable_log_recording: bool,
+        timings: &mut ExecuteTimings,
+    ) -> (
+        TransactionResults,
+        TransactionBalancesSet,
+        Vec<Option<InnerInstructionsList>>,
+        Vec<TransactionLogMessages>,
     ) {
         let pre_balances = if collect_balances {
             self.collect_balances(batch)
@@ -3779,13 +3923,24 @@ impl Bank {
             _,
             tx_count,
             signature_count,
-        ) = self.load_and_execute_transactions(
-            batch,
-            max_age,
-            enable_cpi_recording,
-            enable_log_recording,
-            timings,
-        );
+        ) = if let Some(thread_pool) = thread_pool {
+            self.par_load_and_execute_transactions(
+                batch,
+                thread_pool,
+                max_age,
+                enable_cpi_recording,
+                enable_log_recording,
+                timings,
+            )
+        } else {
+            self.load_and_execute_transactions(
+                batch,
+                max_age,
+                enable_cpi_recording,
+                enable_log_recording,
+                timings,
+            )
+        };
 
         let results = self.commit_transactions(
             batch.transactions(),
@@ -3809,6 +3964,102 @@ impl Bank {
         )
     }
 
+    /// Loads and executes the transactions of `batch` like `load_and_execute_transactions()`,
+    /// but in chunks which are executed in parallel on `thread_pool`. The transactions of a batch
+    /// hold all of their account locks, so none of them conflict and the results are the same.
+    #[allow(clippy::type_complexity)]
+    pub fn par_load_and_execute_transactions(
+        &self,
+        batch: &TransactionBatch,
+        thread_pool: &ThreadPool,
+        max_age: usize,
+        enable_cpi_recording: bool,
+        enable_log_recording: bool,
+        timings: &mut ExecuteTimings,
+    ) -> (
+        Vec<TransactionLoadResult>,
+        Vec<TransactionExecutionResult>,
+        Vec<Option<InnerInstructionsList>>,
+        Vec<TransactionLogMessages>,
+        Vec<usize>,
+        u64,
+        u64,
+    ) {
+        let txs = batch.transactions();
+        let chunk_size =
+            transaction_scheduler::chunk_size(txs.len(), thread_pool.current_num_threads());
+        // The results are in iteration order, which chunks of `txs` don't follow
+        if batch.iteration_order().is_some() || chunk_size >= txs.len() {
+            return self.load_and_execute_transactions(
+                batch,
+                max_age,
+                enable_cpi_recording,
+                enable_log_recording,
+                timings,
+            );
+        }
+
+        let chunk_results: Vec<_> = thread_pool.install(|| {
+            txs.par_chunks(chunk_size)
+                .zip(batch.lock_results().par_chunks(chunk_size))
+                .map(|(txs, lock_results)| {
+                    let mut chunk = TransactionBatch::new(lock_results.to_vec(), self, txs, None);
+                    // The account locks are held, and released, by `batch`
+                    chunk.needs_unlock = false;
+                    let mut timings = ExecuteTimings::default();
+                    let results = self.load_and_execute_transactions(
+                        &chunk,
+                        max_age,
+                        enable_cpi_recording,
+                        enable_log_recording,
+                        &mut timings,
+                    );
+                    (results, timings)
+                })
+                .collect()
+        });
+
+        let mut loaded_accounts = Vec::with_capacity(txs.len());
+        let mut executed = Vec::with_capacity(txs.len());
+        let mut inner_instructions = Vec::with_capacity(txs.len());
+        let mut transaction_logs = Vec::with_capacity(txs.len());
+        let mut retryable_txs = vec![];
+        let mut tx_count = 0;
+        let mut signature_count = 0;
+        for (chunk_index, (results, chunk_timings)) in chunk_results.into_iter().enumerate() {
+            let (
+                chunk_loaded_accounts,
+                chunk_executed,
+                chunk_inner_instructions,
+                chunk_transaction_logs,
+                chunk_retryable_txs,
+                chunk_tx_count,
+                chunk_signature_count,
+            ) = results;
+            loaded_accounts.extend(chunk_loaded_accounts);
+            executed.extend(chunk_executed);
+            inner_instructions.extend(chunk_inner_instructions);
+            transaction_logs.extend(chunk_transaction_logs);
+            retryable_txs.extend(
+                chunk_retryable_txs
+                    .into_iter()
+                    .map(|index| chunk_index * chunk_size + index),
+            );
+            tx_count += chunk_tx_count;
+            signature_count += chunk_signature_count;
+            timings.accumulate(&chunk_timings);
+        }
+        (
+            loaded_accounts,
+            executed,
+            inner_instructions,
+            transaction_logs,
+            retryable_txs,
+            tx_count,
+            signature_count,
+        )
+    }
+
     #[must_use]
     pub fn process_transactions(&self, txs: &[Transaction]) -> Vec<Result<()>> {
         let batch = self.prepare_batch(txs, None);
@@ -4234,6 +4485,20 @@ impl Bank {
             .collect()
     }
 
+    /// Returns the storages of the rooted slots newer than `full_snapshot_slot`, which is what an
+    /// incremental snapshot on top of that full snapshot needs to hold
+    pub fn get_incremental_snapshot_storages(&self, full_snapshot_slot: Slot) -> SnapshotStorages {
+        self.get_snapshot_storages()
+            .into_iter()
+            .filter(|storage| {
+                storage
+                    .first()
+                    .map(|storage_entry| storage_entry.slot() > full_snapshot_slot)
+                    .unwrap_or(false)
+            })
+            .collect()
+    }
+
     #[must_use]
     fn verify_hash(&self) -> bool {
         assert!(self.is_frozen());
@@ -4623,6 +4888,16 @@ impl Bank {
             .is_active(&feature_set::secp256k1_program_enabled::id())
     }
 
+    pub fn compute_budget_program_enabled(&self) -> bool {
+        self.feature_set
+            .is_active(&feature_set::compute_budget_program_enabled::id())
+    }
+
+    pub fn versioned_tx_message_enabled(&self) -> bool {
+        self.feature_set
+            .is_active(&feature_set::versioned_tx_message_enabled::id())
+    }
+
     pub fn no_overflow_rent_distribution_enabled(&self) -> bool {
         self.feature_set
             .is_active(&feature_set::no_overflow_rent_distribution::id())
@@ -4897,7 +5172,7 @@ pub fn goto_end_of_slot(bank: &mut Bank) {
     }
 }
 
-fn is_simple_vote_transaction(transaction: &Transaction) -> bool {
+pub fn is_simple_vote_transaction(transaction: &Transaction) -> bool {
     if transaction.message.instructions.len() == 1 {
         let instruction = &transaction.message.instructions[0];
         let program_pubkey =
@@ -4920,7 +5195,7 @@ pub(crate) mod tests {
     use super::*;
     use crate::{
         accounts_db::SHRINK_RATIO,
-        accounts_index::{AccountMap, Ancestors, ITER_BATCH_SIZE},
+        accounts_index::{AccountIndex, AccountMap, Ancestors, ITER_BATCH_SIZE},
         genesis_utils::{
             activate_all_features, bootstrap_validator_stake_lamports,
             create_genesis_config_with_leader, create_genesis_config_with_vote_accounts,
@@ -4928,18 +5203,22 @@ pub(crate) mod tests {
         },
         native_loader::NativeLoaderError,
         status_cache::MAX_CACHE_ENTRIES,
+        transaction_scheduler::TransactionSchedule,
     };
     use crossbeam_channel::bounded;
+    use solana_address_lookup_table_program::state::{AddressLookupTable, LOOKUP_TABLE_SIZE};
     use solana_sdk::{
         account::Account,
         account_utils::StateMut,
+        address_lookup_table_account::AddressLookupTableAccount,
         clock::{DEFAULT_SLOTS_PER_EPOCH, DEFAULT_TICKS_PER_SLOT},
+        compute_budget,
         epoch_schedule::MINIMUM_SLOTS_PER_EPOCH,
         feature::Feature,
         genesis_config::create_genesis_config,
         instruction::{AccountMeta, CompiledInstruction, Instruction, InstructionError},
         keyed_account::KeyedAccount,
-        message::{Message, MessageHeader},
+        message::{v0, Message, MessageHeader},
         nonce,
         poh_config::PohConfig,
         process_instruction::InvokeContext,
@@ -7464,6 +7743,190 @@ pub(crate) mod tests {
         );
     }
 
+    #[test]
+    fn test_bank_tx_prioritization_fee() {
+        solana_logger::setup();
+
+        let mint = 1_000_000;
+        let leader = solana_sdk::pubkey::new_rand();
+        let GenesisConfigInfo {
+            mut genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config_with_leader(mint, &leader, 3);
+        genesis_config.fee_rate_governor = FeeRateGovernor::new(4, 0);
+        let bank = Bank::new(&genesis_config);
+        assert!(bank.compute_budget_program_enabled());
+
+        let key = solana_sdk::pubkey::new_rand();
+        let transfer = system_instruction::transfer(&mint_keypair.pubkey(), &key, 1);
+
+        // 10_000 units at one lamport each on top of the signature fee
+        let tx = Transaction::new_signed_with_payer(
+            &[
+                compute_budget::set_compute_unit_limit(10_000),
+                compute_budget::set_compute_unit_price(compute_budget::MICRO_LAMPORTS_PER_LAMPORT),
+                transfer.clone(),
+            ],
+            Some(&mint_keypair.pubkey()),
+            &[&mint_keypair],
+            bank.last_blockhash(),
+        );
+        assert_eq!(bank.process_transaction(&tx), Ok(()));
+        assert_eq!(
+            bank.get_balance(&mint_keypair.pubkey()),
+            mint - 1 - 4 - 10_000
+        );
+
+        // An invalid request fails the transaction, and only the signature fee is charged
+        let tx = Transaction::new_signed_with_payer(
+            &[
+                transfer,
+                compute_budget::set_compute_unit_limit(10_000),
+                compute_budget::set_compute_unit_limit(10_000),
+            ],
+            Some(&mint_keypair.pubkey()),
+            &[&mint_keypair],
+            bank.last_blockhash(),
+        );
+        assert_eq!(
+            bank.process_transaction(&tx),
+            Err(TransactionError::InstructionError(
+                2,
+                InstructionError::InvalidInstructionData
+            ))
+        );
+        assert_eq!(bank.get_balance(&key), 1);
+        assert_eq!(
+            bank.get_balance(&mint_keypair.pubkey()),
+            mint - 1 - 4 - 10_000 - 4
+        );
+    }
+
+    #[test]
+    fn test_bank_versioned_transaction() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config_with_leader(1_000_000, &solana_sdk::pubkey::new_rand(), 3);
+        let bank0 = Arc::new(Bank::new(&genesis_config));
+        assert!(bank0.versioned_tx_message_enabled());
+
+        let key = solana_sdk::pubkey::new_rand();
+        let table_key = solana_sdk::pubkey::new_rand();
+        let mut table = AddressLookupTable::new(solana_sdk::pubkey::new_rand());
+        table.addresses = vec![solana_sdk::pubkey::new_rand(), key];
+        // Rent is free in this genesis, so fund the table explicitly for it to be loadable
+        let mut table_account = AccountSharedData::new(
+            1_000,
+            LOOKUP_TABLE_SIZE,
+            &solana_address_lookup_table_program::id(),
+        );
+        table.serialize_into(&mut table_account.data).unwrap();
+        bank0.store_account(&table_key, &table_account);
+
+        let bank = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        let message = v0::Message::try_compile(
+            &mint_keypair.pubkey(),
+            &[system_instruction::transfer(
+                &mint_keypair.pubkey(),
+                &key,
+                1,
+            )],
+            &[AddressLookupTableAccount {
+                key: table_key,
+                addresses: table.addresses.clone(),
+            }],
+            bank.last_blockhash(),
+        )
+        .unwrap();
+        assert_eq!(message.num_lookup_addresses(), 1);
+        let versioned_tx =
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &[&mint_keypair]).unwrap();
+
+        let tx = bank
+            .resolve_versioned_transaction(versioned_tx.clone())
+            .unwrap();
+        assert!(tx.message.account_keys.contains(&key));
+        assert!(tx.message.is_writable(
+            tx.message
+                .account_keys
+                .iter()
+                .position(|account_key| *account_key == key)
+                .unwrap()
+        ));
+        assert_eq!(
+            bank.process_entry_transactions(vec![versioned_tx.clone()]),
+            vec![Ok(())]
+        );
+        assert_eq!(bank.get_balance(&key), 1);
+        assert_eq!(
+            bank.get_signature_status(&versioned_tx.signatures[0]),
+            Some(Ok(()))
+        );
+
+        // Addresses loaded from a table are locked like the ones the message lists
+        let payer = Keypair::new();
+        bank.transfer(10, &mint_keypair, &payer.pubkey()).unwrap();
+        let payer_message = v0::Message::try_compile(
+            &payer.pubkey(),
+            &[system_instruction::transfer(&payer.pubkey(), &key, 1)],
+            &[AddressLookupTableAccount {
+                key: table_key,
+                addresses: table.addresses.clone(),
+            }],
+            bank.last_blockhash(),
+        )
+        .unwrap();
+        let payer_tx =
+            VersionedTransaction::try_new(VersionedMessage::V0(payer_message), &[&payer]).unwrap();
+        let mint_tx = VersionedTransaction::try_new(
+            VersionedMessage::V0(
+                v0::Message::try_compile(
+                    &mint_keypair.pubkey(),
+                    &[system_instruction::transfer(
+                        &mint_keypair.pubkey(),
+                        &key,
+                        2,
+                    )],
+                    &[AddressLookupTableAccount {
+                        key: table_key,
+                        addresses: table.addresses.clone(),
+                    }],
+                    bank.last_blockhash(),
+                )
+                .unwrap(),
+            ),
+            &[&mint_keypair],
+        )
+        .unwrap();
+        let txs: Vec<_> = vec![payer_tx, mint_tx]
+            .into_iter()
+            .map(|tx| bank.resolve_versioned_transaction(tx).unwrap())
+            .collect();
+        let batch = bank.prepare_batch(&txs, None);
+        assert_eq!(
+            batch.lock_results(),
+            &vec![Ok(()), Err(TransactionError::AccountInUse)]
+        );
+        drop(batch);
+        for tx in &txs {
+            assert_eq!(bank.process_transaction(tx), Ok(()));
+        }
+        assert_eq!(bank.get_balance(&key), 4);
+
+        // Lookups that don't resolve fail the transaction
+        let mut invalid_tx = versioned_tx;
+        if let VersionedMessage::V0(message) = &mut invalid_tx.message {
+            message.address_table_lookups[0].account_key = solana_sdk::pubkey::new_rand();
+        }
+        assert_eq!(
+            bank.resolve_versioned_transaction(invalid_tx),
+            Err(TransactionError::AccountNotFound)
+        );
+    }
+
     #[test]
     fn test_bank_blockhash_fee_schedule() {
         //solana_logger::setup();
@@ -8759,8 +9222,8 @@ pub(crate) mod tests {
     #[test]
     fn test_get_filtered_indexed_accounts() {
         let (genesis_config, _mint_keypair) = create_genesis_config(500);
-        let mut account_indexes = HashSet::new();
-        account_indexes.insert(AccountIndex::ProgramId);
+        let mut account_indexes = AccountSecondaryIndexes::default();
+        account_indexes.indexes.insert(AccountIndex::ProgramId);
         let bank = Arc::new(Bank::new_with_config(
             &genesis_config,
             account_indexes,
@@ -10214,7 +10677,7 @@ pub(crate) mod tests {
         // of the storage for this slot
         let mut bank0 = Arc::new(Bank::new_with_config(
             &genesis_config,
-            HashSet::new(),
+            AccountSecondaryIndexes::default(),
             false,
         ));
         bank0.restore_old_behavior_for_fragile_tests();
@@ -10245,7 +10708,11 @@ pub(crate) mod tests {
         let pubkey2 = solana_sdk::pubkey::new_rand();
 
         // Set root for bank 0, with caching enabled
-        let mut bank0 = Arc::new(Bank::new_with_config(&genesis_config, HashSet::new(), true));
+        let mut bank0 = Arc::new(Bank::new_with_config(
+            &genesis_config,
+            AccountSecondaryIndexes::default(),
+            true,
+        ));
         bank0.restore_old_behavior_for_fragile_tests();
 
         let pubkey0_size = get_shrink_account_size();
@@ -11425,7 +11892,7 @@ pub(crate) mod tests {
         genesis_config.rent = Rent::free();
         let bank0 = Arc::new(Bank::new_with_config(
             &genesis_config,
-            HashSet::new(),
+            AccountSecondaryIndexes::default(),
             accounts_db_caching_enabled,
         ));
 
@@ -12217,4 +12684,201 @@ pub(crate) mod tests {
             vec![pubkeys_balances[3], pubkeys_balances[1]]
         );
     }
+
+    #[test]
+    fn test_par_load_execute_and_commit_transactions_scheduled() {
+        solana_logger::setup();
+        let (mut genesis_config, mint_keypair) = create_genesis_config(sol_to_lamports(1.));
+        genesis_config.fee_rate_governor = FeeRateGovernor::new(1, 0);
+        let keypairs: Vec<_> = (0..8).map(|_| Keypair::new()).collect();
+        let new_bank = || {
+            let bank = Bank::new(&genesis_config);
+            for keypair in &keypairs {
+                bank.transfer(1_000, &mint_keypair, &keypair.pubkey())
+                    .unwrap();
+            }
+            bank
+        };
+
+        // Transfers between a few accounts, so that many of them conflict, and some of them
+        // fail for lack of funds
+        let blockhash = genesis_config.hash();
+        let mut txs: Vec<_> = (0..48)
+            .map(|i| {
+                let to = keypairs[(i * 3 + 1) % keypairs.len()].pubkey();
+                let lamports = (i as u64 + 1) * 10;
+                system_transaction::transfer(
+                    &keypairs[i % keypairs.len()],
+                    &to,
+                    lamports,
+                    blockhash,
+                )
+            })
+            .collect();
+        // A transaction which fails to load
+        txs.insert(
+            20,
+            system_transaction::transfer(&Keypair::new(), &keypairs[0].pubkey(), 1, blockhash),
+        );
+
+        // Execute the transactions one at a time
+        let bank = new_bank();
+        let mut fee_collection_results = vec![];
+        let mut execution_results = vec![];
+        let mut pre_balances = vec![];
+        let mut post_balances = vec![];
+        // Only transactions which load have inner instructions and logs
+        let mut inner_instructions = vec![];
+        let mut transaction_logs = vec![];
+        for tx in &txs {
+            let batch = bank.prepare_batch(std::slice::from_ref(tx), None);
+            let (results, balances, tx_inner_instructions, tx_transaction_logs) = bank
+                .load_execute_and_commit_transactions(
+                    &batch,
+                    MAX_PROCESSING_AGE,
+                    true,
+                    true,
+                    true,
+                    &mut ExecuteTimings::default(),
+                );
+            fee_collection_results.extend(results.fee_collection_results);
+            execution_results.extend(results.execution_results);
+            pre_balances.extend(balances.pre_balances);
+            post_balances.extend(balances.post_balances);
+            inner_instructions.push(tx_inner_instructions.into_iter().next());
+            transaction_logs.push(tx_transaction_logs.into_iter().next());
+        }
+        assert!(execution_results.iter().any(|(result, _)| result.is_ok()));
+        assert!(execution_results.iter().any(|(result, _)| result.is_err()));
+
+        // Execute the waves of their schedule one after another
+        let schedule = TransactionSchedule::new(&txs);
+        assert!(schedule.num_waves() > 1);
+        let scheduled_txs = schedule.reorder(txs.clone());
+        let scheduled_bank = new_bank();
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        let mut scheduled_fee_collection_results = vec![];
+        let mut scheduled_execution_results = vec![];
+        let mut scheduled_pre_balances = vec![];
+        let mut scheduled_post_balances = vec![];
+        let mut scheduled_inner_instructions = vec![];
+        let mut scheduled_transaction_logs = vec![];
+        let mut wave_start = 0;
+        for wave_end in schedule.wave_ends() {
+            let batch = scheduled_bank.prepare_batch(&scheduled_txs[wave_start..wave_end], None);
+            wave_start = wave_end;
+            let (results, balances, wave_inner_instructions, wave_transaction_logs) =
+                scheduled_bank.par_load_execute_and_commit_transactions(
+                    &batch,
+                    &thread_pool,
+                    MAX_PROCESSING_AGE,
+                    true,
+                    true,
+                    true,
+                    &mut ExecuteTimings::default(),
+                );
+            assert!(batch.lock_results().iter().all(|result| result.is_ok()));
+            scheduled_fee_collection_results.extend(results.fee_collection_results);
+            scheduled_execution_results.extend(results.execution_results);
+            scheduled_pre_balances.extend(balances.pre_balances);
+            scheduled_post_balances.extend(balances.post_balances);
+            scheduled_inner_instructions.extend(wave_inner_instructions);
+            scheduled_transaction_logs.extend(wave_transaction_logs);
+        }
+        assert_eq!(
+            scheduled_fee_collection_results,
+            schedule.reorder(fee_collection_results)
+        );
+        assert_eq!(
+            scheduled_execution_results,
+            schedule.reorder(execution_results)
+        );
+        assert_eq!(scheduled_pre_balances, schedule.reorder(pre_balances));
+        assert_eq!(scheduled_post_balances, schedule.reorder(post_balances));
+        assert_eq!(
+            scheduled_inner_instructions,
+            schedule
+                .reorder(inner_instructions)
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            scheduled_transaction_logs,
+            schedule
+                .reorder(transaction_logs)
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+        );
+
+        for keypair in &keypairs {
+            assert_eq!(
+                scheduled_bank.get_balance(&keypair.pubkey()),
+                bank.get_balance(&keypair.pubkey())
+            );
+        }
+        bank.freeze();
+        scheduled_bank.freeze();
+        assert_eq!(scheduled_bank.hash(), bank.hash());
+    }
+
+    #[test]
+    fn test_par_load_and_execute_transactions() {
+        let (genesis_config, mint_keypair) = create_genesis_config(sol_to_lamports(1.));
+        let bank = Bank::new(&genesis_config);
+        let txs: Vec<_> = (0..32)
+            .map(|_| {
+                let keypair = Keypair::new();
+                bank.transfer(1_000, &mint_keypair, &keypair.pubkey())
+                    .unwrap();
+                system_transaction::transfer(
+                    &keypair,
+                    &solana_sdk::pubkey::new_rand(),
+                    100,
+                    genesis_config.hash(),
+                )
+            })
+            .collect();
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+
+        let batch = bank.prepare_batch(&txs, None);
+        let (loaded_accounts, executed, _, _, retryable_txs, tx_count, signature_count) = bank
+            .par_load_and_execute_transactions(
+                &batch,
+                &thread_pool,
+                MAX_PROCESSING_AGE,
+                false,
+                false,
+                &mut ExecuteTimings::default(),
+            );
+        assert_eq!(loaded_accounts.len(), txs.len());
+        assert!(executed.iter().all(|(result, _)| result.is_ok()));
+        assert!(retryable_txs.is_empty());
+        assert_eq!(tx_count, txs.len() as u64);
+        assert_eq!(signature_count, txs.len() as u64);
+        drop(batch);
+
+        // Transactions whose accounts are locked elsewhere are retryable, at their index in the
+        // whole batch
+        let locked_batch = bank.prepare_batch(&txs[25..26], None);
+        let batch = bank.prepare_batch(&txs[10..], None);
+        let (_, _, _, _, retryable_txs, tx_count, _) = bank.par_load_and_execute_transactions(
+            &batch,
+            &thread_pool,
+            MAX_PROCESSING_AGE,
+            false,
+            false,
+            &mut ExecuteTimings::default(),
+        );
+        assert_eq!(retryable_txs, vec![15]);
+        assert_eq!(tx_count, 21);
+        drop(locked_batch);
+    }
 }
@@ -30,6 +30,10 @@ pub struct SnapshotConfig {
     // Generate a new snapshot every this many slots
     pub snapshot_interval_slots: u64,
 
+    // Generate a new incremental snapshot, on top of the last full snapshot, every this many
+    // slots.  `Slot::MAX` disables incremental snapshots
+    pub incremental_snapshot_interval_slots: u64,
+
     // Where to store the latest packaged snapshot
     pub snapshot_package_output_path: PathBuf,
 
@@ -42,6 +46,12 @@ pub struct SnapshotConfig {
     pub snapshot_version: SnapshotVersion,
 }
 
+impl SnapshotConfig {
+    pub fn incremental_snapshots_enabled(&self) -> bool {
+        self.incremental_snapshot_interval_slots != Slot::MAX
+    }
+}
+
 pub struct BankForks {
     banks: HashMap<Slot, Arc<Bank>>,
     descendants: HashMap<Slot, HashSet<Slot>>,
@@ -0,0 +1,721 @@
+//! The `bucket_map` module holds the cold entries of the accounts index in memory-mapped
+//! bucket files, so that they don't take up RAM.
+//!
+//! Keys are spread over `2^num_buckets_pow2` buckets by their leading bits, so buckets are in
+//! key order and a range of keys only touches a range of buckets. Each bucket is an open
+//! addressing hash table of fixed size index cells in one file, probed by a randomly seeded
+//! hash of the whole key, pointing into data files which
+//! hold the serialized slot lists in cells of power of two sizes. Files double in size when they
+//! fill up. Each map keeps its files in a directory of its own on every drive, removed along
+//! with the map. The accounts index is rebuilt from storage on startup, so bucket files are
+//! scratch space and the directories left over by a previous run are removed with
+//! `remove_leftover_bucket_maps`.
+
+use crate::accounts_index::{RefCount, SlotList, SlotSlice};
+use log::*;
+use memmap2::MmapMut;
+use rand::{thread_rng, Rng};
+use serde::{de::DeserializeOwned, Serialize};
+use solana_sdk::pubkey::{Pubkey, PUBKEY_BYTES};
+use std::{
+    collections::hash_map::RandomState,
+    convert::TryInto,
+    fs::{self, OpenOptions},
+    hash::{BuildHasher, Hash, Hasher},
+    marker::PhantomData,
+    ops::{
+        Bound::{Excluded, Included, Unbounded},
+        RangeBounds,
+    },
+    path::PathBuf,
+    sync::{Arc, PoisonError, RwLock},
+};
+use thiserror::Error;
+
+const BUCKET_FILE_EXTENSION: &str = "bucket";
+const BUCKET_MAP_DIR_PREFIX: &str = "bucket_map-";
+// Every cell starts with a header which is zero if the cell is free.
+const HEADER_SIZE: usize = std::mem::size_of::<u64>();
+// key, ref count, data cell index and data length
+const INDEX_CELL_SIZE: usize = HEADER_SIZE + PUBKEY_BYTES + 3 * std::mem::size_of::<u64>();
+// Data cells of class `k` hold up to `DATA_CELL_UNIT << k` bytes.
+const DATA_CELL_UNIT: usize = 64;
+const INITIAL_NUM_CELLS: usize = 32;
+// Number of consecutive cells probed for a key before a file is grown.
+const MAX_SEARCH: usize = 32;
+// Number of times the index file of a bucket is doubled in one go, while looking for a size
+// which all keys fit in, before giving up.
+const MAX_INDEX_DOUBLINGS: usize = 8;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum BucketMapError {
+    #[error("accounts index bucket with {0} index cells can't fit its keys")]
+    IndexFull(usize),
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().unwrap())
+}
+
+fn write_u64(bytes: &mut [u8], value: u64) {
+    bytes[..8].copy_from_slice(&value.to_le_bytes());
+}
+
+// Returns the smallest class of data cells which fits `len` bytes.
+fn data_class(len: usize) -> usize {
+    let num_units = (len + DATA_CELL_UNIT - 1) / DATA_CELL_UNIT;
+    num_units.max(1).next_power_of_two().trailing_zeros() as usize
+}
+
+/// A memory-mapped file of fixed size cells
+#[derive(Debug)]
+struct BucketStorage {
+    path: PathBuf,
+    mmap: MmapMut,
+    cell_size: usize,
+    num_cells: usize,
+    num_occupied: usize,
+}
+
+impl BucketStorage {
+    fn new(drives: &[PathBuf], cell_size: usize, num_cells: usize) -> Self {
+        let mut rng = thread_rng();
+        let drive = &drives[rng.gen_range(0, drives.len())];
+        let path = drive.join(format!("{:x}.{}", rng.gen::<u128>(), BUCKET_FILE_EXTENSION));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .unwrap_or_else(|err| {
+                panic!(
+                    "Unable to create accounts index bucket file {}: {}",
+                    path.display(),
+                    err
+                )
+            });
+        // Extending the file zero fills it, so all cells start out free.
+        file.set_len((cell_size * num_cells) as u64)
+            .unwrap_or_else(|err| panic!("Unable to size {}: {}", path.display(), err));
+        let mmap = unsafe { MmapMut::map_mut(&file) }
+            .unwrap_or_else(|err| panic!("Unable to map {}: {}", path.display(), err));
+        Self {
+            path,
+            mmap,
+            cell_size,
+            num_cells,
+            num_occupied: 0,
+        }
+    }
+
+    // Returns a copy with twice as many cells, where the existing cells keep their index.
+    fn grow(&self, drives: &[PathBuf]) -> Self {
+        let mut storage = Self::new(drives, self.cell_size, self.num_cells * 2);
+        storage.mmap[..self.mmap.len()].copy_from_slice(&self.mmap);
+        storage.num_occupied = self.num_occupied;
+        storage
+    }
+
+    fn is_occupied(&self, ix: usize) -> bool {
+        read_u64(&self.mmap[ix * self.cell_size..]) != 0
+    }
+
+    fn occupy(&mut self, ix: usize) {
+        debug_assert!(!self.is_occupied(ix));
+        write_u64(&mut self.mmap[ix * self.cell_size..], 1);
+        self.num_occupied += 1;
+    }
+
+    fn free(&mut self, ix: usize) {
+        debug_assert!(self.is_occupied(ix));
+        write_u64(&mut self.mmap[ix * self.cell_size..], 0);
+        self.num_occupied -= 1;
+    }
+
+    fn cell(&self, ix: usize) -> &[u8] {
+        &self.mmap[ix * self.cell_size + HEADER_SIZE..(ix + 1) * self.cell_size]
+    }
+
+    fn cell_mut(&mut self, ix: usize) -> &mut [u8] {
+        &mut self.mmap[ix * self.cell_size + HEADER_SIZE..(ix + 1) * self.cell_size]
+    }
+
+    // Returns the indexes of the cells to probe for a key with the given hash, in order.
+    fn probe(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let num_cells = self.num_cells;
+        let start = (hash % num_cells as u64) as usize;
+        (0..MAX_SEARCH.min(num_cells)).map(move |k| (start + k) % num_cells)
+    }
+}
+
+impl Drop for BucketStorage {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            warn!("Unable to remove {}: {}", self.path.display(), err);
+        }
+    }
+}
+
+struct IndexEntry {
+    key: Pubkey,
+    ref_count: RefCount,
+    data_ix: u64,
+    data_len: u64,
+}
+
+impl IndexEntry {
+    fn read(cell: &[u8]) -> Self {
+        let (key, cell) = cell.split_at(PUBKEY_BYTES);
+        Self {
+            key: Pubkey::new(key),
+            ref_count: read_u64(cell),
+            data_ix: read_u64(&cell[8..]),
+            data_len: read_u64(&cell[16..]),
+        }
+    }
+
+    fn write(&self, cell: &mut [u8]) {
+        let (key, cell) = cell.split_at_mut(PUBKEY_BYTES);
+        key.copy_from_slice(self.key.as_ref());
+        write_u64(cell, self.ref_count);
+        write_u64(&mut cell[8..], self.data_ix);
+        write_u64(&mut cell[16..], self.data_len);
+    }
+}
+
+#[derive(Debug)]
+struct Bucket<T> {
+    drives: Arc<Vec<PathBuf>>,
+    // Keys are spread over buckets by their leading bytes, so cells are found by a hash of the
+    // whole key. It is seeded per bucket, so keys can't be picked to collide.
+    hash_state: RandomState,
+    index: BucketStorage,
+    // Data cells of each class, created on first use.
+    data: Vec<Option<BucketStorage>>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Clone + Serialize + DeserializeOwned> Bucket<T> {
+    fn new(drives: Arc<Vec<PathBuf>>) -> Self {
+        let index = BucketStorage::new(&drives, INDEX_CELL_SIZE, INITIAL_NUM_CELLS);
+        Self {
+            drives,
+            hash_state: RandomState::new(),
+            index,
+            data: Vec::default(),
+            _phantom: PhantomData::default(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.index.num_occupied
+    }
+
+    fn cell_hash(&self, key: &Pubkey) -> u64 {
+        let mut hasher = self.hash_state.build_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn find_index(&self, key: &Pubkey) -> Option<usize> {
+        self.index.probe(self.cell_hash(key)).find(|ix| {
+            self.index.is_occupied(*ix) && &self.index.cell(*ix)[..PUBKEY_BYTES] == key.as_ref()
+        })
+    }
+
+    fn read_value(&self, key: &Pubkey) -> Option<(SlotList<T>, RefCount)> {
+        let ix = self.find_index(key)?;
+        Some(self.read_entry(&IndexEntry::read(self.index.cell(ix))))
+    }
+
+    fn read_entry(&self, entry: &IndexEntry) -> (SlotList<T>, RefCount) {
+        let data_len = entry.data_len as usize;
+        let data = self.data[data_class(data_len)].as_ref().unwrap();
+        let bytes = &data.cell(entry.data_ix as usize)[..data_len];
+        let slot_list = bincode::deserialize(bytes).expect("deserialize accounts index entry");
+        (slot_list, entry.ref_count)
+    }
+
+    fn insert(
+        &mut self,
+        key: &Pubkey,
+        slot_list: SlotSlice<T>,
+        ref_count: RefCount,
+    ) -> Result<(), BucketMapError> {
+        let bytes = bincode::serialize(slot_list).unwrap();
+        let class = data_class(bytes.len());
+        let ix = match self.find_index(key) {
+            Some(ix) => ix,
+            None => self.allocate_index(key)?,
+        };
+        let mut entry = IndexEntry::read(self.index.cell(ix));
+        let old_class = (entry.data_len != 0).then(|| data_class(entry.data_len as usize));
+        if old_class != Some(class) {
+            if let Some(old_class) = old_class {
+                self.data[old_class]
+                    .as_mut()
+                    .unwrap()
+                    .free(entry.data_ix as usize);
+            }
+            entry.data_ix = self.allocate_data(class, key) as u64;
+        }
+        let data = self.data[class].as_mut().unwrap();
+        data.cell_mut(entry.data_ix as usize)[..bytes.len()].copy_from_slice(&bytes);
+        entry.key = *key;
+        entry.ref_count = ref_count;
+        entry.data_len = bytes.len() as u64;
+        entry.write(self.index.cell_mut(ix));
+        Ok(())
+    }
+
+    fn delete_key(&mut self, key: &Pubkey) -> bool {
+        let ix = match self.find_index(key) {
+            Some(ix) => ix,
+            None => return false,
+        };
+        let entry = IndexEntry::read(self.index.cell(ix));
+        if entry.data_len != 0 {
+            self.data[data_class(entry.data_len as usize)]
+                .as_mut()
+                .unwrap()
+                .free(entry.data_ix as usize);
+        }
+        self.index
+            .cell_mut(ix)
+            .iter_mut()
+            .for_each(|byte| *byte = 0);
+        self.index.free(ix);
+        true
+    }
+
+    // Returns the entries with keys in `range`, sorted by key.
+    fn range<R: RangeBounds<Pubkey>>(&self, range: &R) -> Vec<(Pubkey, SlotList<T>, RefCount)> {
+        let mut entries: Vec<_> = (0..self.index.num_cells)
+            .filter(|ix| self.index.is_occupied(*ix))
+            .map(|ix| IndexEntry::read(self.index.cell(ix)))
+            .filter(|entry| range.contains(&entry.key))
+            .collect();
+        entries.sort_unstable_by_key(|entry| entry.key);
+        entries
+            .into_iter()
+            .map(|entry| {
+                let (slot_list, ref_count) = self.read_entry(&entry);
+                (entry.key, slot_list, ref_count)
+            })
+            .collect()
+    }
+
+    fn keys(&self) -> impl Iterator<Item = Pubkey> + '_ {
+        (0..self.index.num_cells)
+            .filter(move |ix| self.index.is_occupied(*ix))
+            .map(move |ix| Pubkey::new(&self.index.cell(ix)[..PUBKEY_BYTES]))
+    }
+
+    fn allocate_index(&mut self, key: &Pubkey) -> Result<usize, BucketMapError> {
+        let hash = self.cell_hash(key);
+        loop {
+            if let Some(ix) = self
+                .index
+                .probe(hash)
+                .find(|ix| !self.index.is_occupied(*ix))
+            {
+                self.index.occupy(ix);
+                return Ok(ix);
+            }
+            self.grow_index()?;
+        }
+    }
+
+    // Index cells are found by probing from the hash of their key, so unlike data cells they
+    // have to be rehashed into the larger file.
+    fn grow_index(&mut self) -> Result<(), BucketMapError> {
+        let mut num_cells = self.index.num_cells;
+        'grow: for _ in 0..MAX_INDEX_DOUBLINGS {
+            num_cells *= 2;
+            let mut index = BucketStorage::new(&self.drives, INDEX_CELL_SIZE, num_cells);
+            for ix in (0..self.index.num_cells).filter(|ix| self.index.is_occupied(*ix)) {
+                let cell = self.index.cell(ix);
+                let key = Pubkey::new(&cell[..PUBKEY_BYTES]);
+                match index
+                    .probe(self.cell_hash(&key))
+                    .find(|ix| !index.is_occupied(*ix))
+                {
+                    None => continue 'grow,
+                    Some(new_ix) => {
+                        index.occupy(new_ix);
+                        index.cell_mut(new_ix).copy_from_slice(cell);
+                    }
+                }
+            }
+            self.index = index;
+            return Ok(());
+        }
+        Err(BucketMapError::IndexFull(num_cells))
+    }
+
+    fn allocate_data(&mut self, class: usize, key: &Pubkey) -> usize {
+        let hash = self.cell_hash(key);
+        if self.data.len() <= class {
+            self.data.resize_with(class + 1, || None);
+        }
+        let drives = &self.drives;
+        let data = self.data[class].get_or_insert_with(|| {
+            let cell_size = HEADER_SIZE + (DATA_CELL_UNIT << class);
+            BucketStorage::new(drives, cell_size, INITIAL_NUM_CELLS)
+        });
+        loop {
+            if let Some(ix) = data.probe(hash).find(|ix| !data.is_occupied(*ix)) {
+                data.occupy(ix);
+                return ix;
+            }
+            *data = data.grow(drives);
+        }
+    }
+}
+
+/// Removes the directories of the maps left over in `drives` by a previous run. Must not be
+/// called while a map is using any of the drives.
+pub fn remove_leftover_bucket_maps(drives: &[PathBuf]) {
+    for drive in drives {
+        let dirs = fs::read_dir(drive)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .map_or(false, |name| name.starts_with(BUCKET_MAP_DIR_PREFIX))
+            });
+        for dir in dirs {
+            if let Err(err) = fs::remove_dir_all(dir.path()) {
+                warn!("Unable to remove {}: {}", dir.path().display(), err);
+            }
+        }
+    }
+}
+
+/// A map from pubkeys to their slot lists and ref counts, kept in memory-mapped files
+#[derive(Debug)]
+pub struct BucketMap<T> {
+    buckets: Vec<RwLock<Option<Bucket<T>>>>,
+    drives: Arc<Vec<PathBuf>>,
+    num_buckets_pow2: u8,
+}
+
+impl<T: Clone + Serialize + DeserializeOwned> BucketMap<T> {
+    /// Creates an empty map with its files spread over a new directory in each of `drives`
+    pub fn new(drives: Vec<PathBuf>, num_buckets_pow2: u8) -> Self {
+        assert!(!drives.is_empty(), "accounts index requires a drive");
+        assert!(num_buckets_pow2 <= 24, "too many accounts index buckets");
+        let dir_name = format!("{}{:x}", BUCKET_MAP_DIR_PREFIX, thread_rng().gen::<u128>());
+        let drives = drives
+            .into_iter()
+            .map(|drive| {
+                let dir = drive.join(&dir_name);
+                fs::create_dir_all(&drive)
+                    .and_then(|_| fs::create_dir(&dir))
+                    .unwrap_or_else(|err| {
+                        panic!("Unable to create directory {}: {}", dir.display(), err)
+                    });
+                dir
+            })
+            .collect();
+        Self {
+            buckets: (0..1usize << num_buckets_pow2)
+                .map(|_| RwLock::default())
+                .collect(),
+            drives: Arc::new(drives),
+            num_buckets_pow2,
+        }
+    }
+
+    pub fn num_buckets(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Returns the bucket `key` belongs to.
+    pub fn bucket_ix(&self, key: &Pubkey) -> usize {
+        if self.num_buckets_pow2 == 0 {
+            0
+        } else {
+            let prefix = u64::from_be_bytes(key.as_ref()[..8].try_into().unwrap());
+            (prefix >> (64 - u32::from(self.num_buckets_pow2))) as usize
+        }
+    }
+
+    /// Returns the smallest key which belongs to bucket `ix`, if there is such a bucket.
+    pub fn bucket_start(&self, ix: usize) -> Option<Pubkey> {
+        if ix >= self.num_buckets() {
+            return None;
+        }
+        let mut key = [0u8; PUBKEY_BYTES];
+        if self.num_buckets_pow2 != 0 {
+            let prefix = (ix as u64) << (64 - u32::from(self.num_buckets_pow2));
+            key[..8].copy_from_slice(&prefix.to_be_bytes());
+        }
+        Some(Pubkey::new(&key))
+    }
+
+    pub fn len(&self) -> usize {
+        self.buckets
+            .iter()
+            .map(|bucket| bucket.read().unwrap().as_ref().map_or(0, Bucket::len))
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains_key(&self, key: &Pubkey) -> bool {
+        let bucket = self.buckets[self.bucket_ix(key)].read().unwrap();
+        bucket
+            .as_ref()
+            .map_or(false, |bucket| bucket.find_index(key).is_some())
+    }
+
+    pub fn read_value(&self, key: &Pubkey) -> Option<(SlotList<T>, RefCount)> {
+        let bucket = self.buckets[self.bucket_ix(key)].read().unwrap();
+        bucket.as_ref()?.read_value(key)
+    }
+
+    /// Inserts or replaces the entry of `key`. Fails if the bucket of `key` can't grow to fit
+    /// it, in which case the map is left unchanged.
+    pub fn insert(
+        &self,
+        key: &Pubkey,
+        slot_list: SlotSlice<T>,
+        ref_count: RefCount,
+    ) -> Result<(), BucketMapError> {
+        let mut bucket = self.buckets[self.bucket_ix(key)].write().unwrap();
+        bucket
+            .get_or_insert_with(|| Bucket::new(self.drives.clone()))
+            .insert(key, slot_list, ref_count)
+    }
+
+    /// Removes `key`, returning true if it was present.
+    pub fn delete_key(&self, key: &Pubkey) -> bool {
+        let mut bucket = self.buckets[self.bucket_ix(key)].write().unwrap();
+        bucket
+            .as_mut()
+            .map_or(false, |bucket| bucket.delete_key(key))
+    }
+
+    /// Returns the entries of bucket `ix` with keys in `range`, sorted by key.
+    pub fn bucket_range<R: RangeBounds<Pubkey>>(
+        &self,
+        ix: usize,
+        range: &R,
+    ) -> Vec<(Pubkey, SlotList<T>, RefCount)> {
+        let bucket = self.buckets[ix].read().unwrap();
+        bucket
+            .as_ref()
+            .map(|bucket| bucket.range(range))
+            .unwrap_or_default()
+    }
+
+    /// Returns the entries with keys in `range`, sorted by key.
+    pub fn range<R: RangeBounds<Pubkey>>(&self, range: &R) -> Vec<(Pubkey, SlotList<T>, RefCount)> {
+        let first = match range.start_bound() {
+            Included(key) | Excluded(key) => self.bucket_ix(key),
+            Unbounded => 0,
+        };
+        let last = match range.end_bound() {
+            Included(key) | Excluded(key) => self.bucket_ix(key),
+            Unbounded => self.num_buckets() - 1,
+        };
+        (first..=last)
+            .flat_map(|ix| self.bucket_range(ix, range))
+            .collect()
+    }
+
+    /// Returns all keys in the map, in no particular order.
+    pub fn keys(&self) -> Vec<Pubkey> {
+        self.buckets
+            .iter()
+            .flat_map(|bucket| {
+                let bucket = bucket.read().unwrap();
+                bucket
+                    .as_ref()
+                    .map(|bucket| bucket.keys().collect())
+                    .unwrap_or_else(Vec::default)
+            })
+            .collect()
+    }
+}
+
+impl<T> Drop for BucketMap<T> {
+    fn drop(&mut self) {
+        // Remove the bucket files before their directories
+        for bucket in &mut self.buckets {
+            *bucket.get_mut().unwrap_or_else(PoisonError::into_inner) = None;
+        }
+        for dir in self.drives.iter() {
+            if let Err(err) = fs::remove_dir(dir) {
+                warn!("Unable to remove {}: {}", dir.display(), err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::clock::Slot;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    fn new_rand_slot_list<R: Rng>(rng: &mut R) -> SlotList<u64> {
+        let len = rng.gen_range(0, 40);
+        std::iter::repeat_with(|| (rng.gen_range(0, 1000), rng.gen()))
+            .take(len)
+            .collect()
+    }
+
+    #[test]
+    fn test_data_class() {
+        assert_eq!(data_class(1), 0);
+        assert_eq!(data_class(DATA_CELL_UNIT), 0);
+        assert_eq!(data_class(DATA_CELL_UNIT + 1), 1);
+        assert_eq!(data_class(2 * DATA_CELL_UNIT), 1);
+        assert_eq!(data_class(2 * DATA_CELL_UNIT + 1), 2);
+        assert_eq!(data_class(100 * DATA_CELL_UNIT), 7);
+    }
+
+    #[test]
+    fn test_bucket_ix() {
+        let drive = TempDir::new().unwrap();
+        let map = BucketMap::<u64>::new(vec![drive.path().to_path_buf()], 4);
+        assert_eq!(map.num_buckets(), 16);
+        let mut keys: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+            .take(1000)
+            .chain(std::iter::repeat_with(solana_sdk::pubkey::new_rand).take(1000))
+            .chain((0..map.num_buckets()).filter_map(|ix| map.bucket_start(ix)))
+            .collect();
+        keys.sort_unstable();
+        // Buckets are in key order.
+        assert!(keys
+            .windows(2)
+            .all(|w| map.bucket_ix(&w[0]) <= map.bucket_ix(&w[1])));
+        for ix in 0..map.num_buckets() {
+            let start = map.bucket_start(ix).unwrap();
+            assert_eq!(map.bucket_ix(&start), ix);
+            assert!(keys
+                .iter()
+                .filter(|key| map.bucket_ix(key) == ix)
+                .all(|key| *key >= start));
+        }
+        assert_eq!(map.bucket_start(map.num_buckets()), None);
+        let map = BucketMap::<u64>::new(vec![drive.path().to_path_buf()], 0);
+        assert_eq!(map.bucket_ix(&solana_sdk::pubkey::new_rand()), 0);
+        assert_eq!(map.bucket_start(0), Some(Pubkey::default()));
+    }
+
+    #[test]
+    fn test_bucket_map() {
+        let mut rng = rand::thread_rng();
+        let drives: Vec<_> = (0..2).map(|_| TempDir::new().unwrap()).collect();
+        let map = BucketMap::<u64>::new(
+            drives
+                .iter()
+                .map(|drive| drive.path().to_path_buf())
+                .collect(),
+            2,
+        );
+        assert!(map.is_empty());
+        let mut entries = BTreeMap::<Pubkey, (SlotList<u64>, RefCount)>::new();
+        for _ in 0..5000 {
+            let key = if entries.is_empty() || rng.gen_ratio(2, 3) {
+                solana_sdk::pubkey::new_rand()
+            } else {
+                *entries.keys().nth(rng.gen_range(0, entries.len())).unwrap()
+            };
+            if rng.gen_ratio(1, 5) {
+                assert_eq!(map.delete_key(&key), entries.remove(&key).is_some());
+            } else {
+                let value = (new_rand_slot_list(&mut rng), rng.gen());
+                map.insert(&key, &value.0, value.1).unwrap();
+                entries.insert(key, value);
+            }
+        }
+        assert_eq!(map.len(), entries.len());
+        for (key, value) in &entries {
+            assert!(map.contains_key(key));
+            assert_eq!(map.read_value(key).as_ref(), Some(value));
+        }
+        let missing = solana_sdk::pubkey::new_rand();
+        assert!(!map.contains_key(&missing));
+        assert_eq!(map.read_value(&missing), None);
+        assert!(!map.delete_key(&missing));
+
+        let mut keys = map.keys();
+        keys.sort_unstable();
+        assert!(keys.iter().eq(entries.keys()));
+        let expected: Vec<_> = entries
+            .iter()
+            .map(|(key, (slot_list, ref_count))| (*key, slot_list.clone(), *ref_count))
+            .collect();
+        assert_eq!(map.range(&..), expected);
+        let (start, end) = (keys[keys.len() / 4], keys[keys.len() * 3 / 4]);
+        let expected: Vec<_> = expected
+            .into_iter()
+            .filter(|(key, _, _)| start <= *key && *key < end)
+            .collect();
+        assert_eq!(map.range(&(start..end)), expected);
+
+        // Bucket files are removed along with the map.
+        drop(map);
+        for drive in &drives {
+            assert_eq!(fs::read_dir(drive.path()).unwrap().count(), 0);
+        }
+    }
+
+    #[test]
+    fn test_bucket_map_dirs() {
+        let drive = TempDir::new().unwrap();
+        let drives = vec![drive.path().to_path_buf()];
+        let leftover = drive
+            .path()
+            .join(format!("{}leftover", BUCKET_MAP_DIR_PREFIX));
+        fs::create_dir(&leftover).unwrap();
+        fs::write(
+            leftover.join(format!("0.{}", BUCKET_FILE_EXTENSION)),
+            [0u8; 8],
+        )
+        .unwrap();
+        let other = drive.path().join("other");
+        fs::write(&other, [0u8; 8]).unwrap();
+        remove_leftover_bucket_maps(&drives);
+        assert!(!leftover.exists());
+        assert!(other.exists());
+
+        // Maps sharing a drive don't see each other's files
+        let key = solana_sdk::pubkey::new_rand();
+        let map = BucketMap::<Slot>::new(drives.clone(), 0);
+        map.insert(&key, &[(0, 0)], 1).unwrap();
+        let other_map = BucketMap::<Slot>::new(drives, 0);
+        other_map.insert(&key, &[(1, 0)], 1).unwrap();
+        drop(other_map);
+        assert_eq!(map.read_value(&key), Some((vec![(0, 0)], 1)));
+        drop(map);
+        assert_eq!(fs::read_dir(drive.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_bucket_map_similar_keys() {
+        // Unique pubkeys only differ in their leading bytes, so they all land in the same bucket
+        let drive = TempDir::new().unwrap();
+        let map = BucketMap::<Slot>::new(vec![drive.path().to_path_buf()], 2);
+        let keys: Vec<_> = std::iter::repeat_with(Pubkey::new_unique)
+            .take(10 * MAX_SEARCH)
+            .collect();
+        for (slot, key) in keys.iter().enumerate() {
+            map.insert(key, &[(slot as Slot, 0)], 1).unwrap();
+        }
+        assert_eq!(map.len(), keys.len());
+        for (slot, key) in keys.iter().enumerate() {
+            assert_eq!(map.read_value(key), Some((vec![(slot as Slot, 0)], 1)));
+        }
+    }
+}
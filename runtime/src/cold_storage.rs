@@ -0,0 +1,847 @@
+//! Read-only, compressed storage for the accounts of old rooted slots.
+//!
+//! An `AppendVec` keeps every account uncompressed in a mmapped file, which is wasteful for
+//! slots that are rarely read again. A `ColdStorage` file holds the same records, but sorted by
+//! pubkey, split into blocks which are compressed independently, and followed by an index of
+//! the pubkeys it contains:
+//!
+//! ```text
+//! | magic | index offset | index len | block 0 | block 1 | ... | index |
+//! ```
+//!
+//! Within a block the records have exactly the `AppendVec` layout, so the offsets of the
+//! accounts are the offsets they would have in an `AppendVec` holding the same records, and
+//! the accounts index refers to them the same way.
+//!
+//! Decompressed blocks are kept in a `ColdStorageCache` shared by all the storages of an
+//! `AccountsDb`, which evicts the least recently used blocks once it holds more than its size.
+//! The cache is split into shards so that reads of different blocks rarely wait on each other.
+
+use crate::append_vec::{AccountMeta, StoredAccountMeta, StoredMeta};
+use bincode::Options;
+use log::*;
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    account::{AccountSharedData, ReadableAccount},
+    hash::Hash,
+    pubkey::Pubkey,
+};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fs::{remove_file, File, OpenOptions},
+    io::{self, Seek, SeekFrom, Write},
+    mem,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+const COLD_STORAGE_MAGIC: [u8; 8] = *b"SOLCOLD1";
+const COLD_STORAGE_HEADER_SIZE: usize = COLD_STORAGE_MAGIC.len() + 2 * mem::size_of::<u64>();
+
+/// Number of uncompressed bytes after which a block is closed. Accounts are never split
+/// between blocks, so a block holding a single large account may be bigger.
+const COLD_STORAGE_BLOCK_SIZE: usize = 64 * 1024;
+const COLD_STORAGE_COMPRESSION_LEVEL: i32 = 3;
+
+/// Default number of decompressed bytes a `ColdStorageCache` holds
+pub const DEFAULT_COLD_STORAGE_CACHE_SIZE: usize = 256 * 1024 * 1024;
+const COLD_STORAGE_CACHE_NUM_SHARDS: usize = 16;
+
+const ALIGN_BOUNDARY_OFFSET: usize = mem::size_of::<u64>();
+
+fn u64_align(offset: usize) -> usize {
+    (offset + (ALIGN_BOUNDARY_OFFSET - 1)) & !(ALIGN_BOUNDARY_OFFSET - 1)
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, message)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ColdStorageBlock {
+    /// Position of the compressed block in the file
+    file_offset: u64,
+    compressed_len: u64,
+    /// Offset of the first account of the block
+    start: u64,
+    /// Uncompressed length of the block, a multiple of 8
+    len: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ColdStorageIndex {
+    /// End offset of the last account, as in an `AppendVec`, which isn't padded after it
+    len: u64,
+    blocks: Vec<ColdStorageBlock>,
+    /// The offset of every account, sorted by pubkey
+    accounts: Vec<(Pubkey, u64)>,
+}
+
+/// A decompressed block, kept in `u64`s so the records within it are aligned
+type BlockData = Arc<[u64]>;
+
+#[derive(Debug, Default)]
+struct ColdStorageCacheInner {
+    /// The cached blocks by (storage, block index), with the tick they were last used at
+    blocks: HashMap<(u64, usize), (u64, BlockData)>,
+    /// The cached blocks by the tick they were last used at, oldest first
+    lru: BTreeMap<u64, (u64, usize)>,
+    tick: u64,
+    /// Total decompressed size of the cached blocks
+    size: usize,
+}
+
+/// A least recently used cache of decompressed `ColdStorage` blocks, bounded by their total
+/// size in bytes. Blocks are spread over shards which each hold an equal part of the size and
+/// evict their own least recently used blocks.
+#[derive(Debug)]
+pub struct ColdStorageCache {
+    max_size: AtomicUsize,
+    next_storage_id: AtomicU64,
+    shards: Vec<Mutex<ColdStorageCacheInner>>,
+}
+
+impl Default for ColdStorageCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_COLD_STORAGE_CACHE_SIZE)
+    }
+}
+
+impl ColdStorageCache {
+    pub fn new(max_size: usize) -> Self {
+        Self::new_with_num_shards(max_size, COLD_STORAGE_CACHE_NUM_SHARDS)
+    }
+
+    fn new_with_num_shards(max_size: usize, num_shards: usize) -> Self {
+        assert!(num_shards > 0, "cold storage cache requires a shard");
+        Self {
+            max_size: AtomicUsize::new(max_size),
+            next_storage_id: AtomicU64::new(0),
+            shards: (0..num_shards).map(|_| Mutex::default()).collect(),
+        }
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size.load(Ordering::Relaxed)
+    }
+
+    /// Number of bytes each shard holds at most
+    fn shard_max_size(&self) -> usize {
+        self.max_size() / self.shards.len()
+    }
+
+    /// Total decompressed size of the cached blocks
+    pub fn size(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().size)
+            .sum()
+    }
+
+    pub fn set_max_size(&self, max_size: usize) {
+        self.max_size.store(max_size, Ordering::Relaxed);
+        let shard_max_size = self.shard_max_size();
+        for shard in &self.shards {
+            Self::evict(&mut shard.lock().unwrap(), shard_max_size);
+        }
+    }
+
+    fn new_storage_id(&self) -> u64 {
+        self.next_storage_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// The consecutive blocks of a storage, and the same block of consecutive storages, are in
+    /// different shards
+    fn shard(&self, (storage_id, ix): (u64, usize)) -> &Mutex<ColdStorageCacheInner> {
+        let shard = (storage_id as usize).wrapping_add(ix) % self.shards.len();
+        &self.shards[shard]
+    }
+
+    fn get(&self, key: (u64, usize)) -> Option<BlockData> {
+        let mut inner = self.shard(key).lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let (last_used, data) = inner.blocks.get_mut(&key)?;
+        let last_used = mem::replace(last_used, tick);
+        let data = data.clone();
+        inner.lru.remove(&last_used);
+        inner.lru.insert(tick, key);
+        Some(data)
+    }
+
+    fn insert(&self, key: (u64, usize), data: BlockData) {
+        let max_size = self.shard_max_size();
+        let mut inner = self.shard(key).lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let size = data.len() * ALIGN_BOUNDARY_OFFSET;
+        if let Some((last_used, old)) = inner.blocks.insert(key, (tick, data)) {
+            inner.lru.remove(&last_used);
+            inner.size -= old.len() * ALIGN_BOUNDARY_OFFSET;
+        }
+        inner.lru.insert(tick, key);
+        inner.size += size;
+        Self::evict(&mut inner, max_size);
+    }
+
+    /// Drops the least recently used blocks until the shard holds at most `max_size` bytes
+    fn evict(inner: &mut ColdStorageCacheInner, max_size: usize) {
+        while inner.size > max_size {
+            let (last_used, key) = match inner.lru.iter().next() {
+                Some((last_used, key)) => (*last_used, *key),
+                None => break,
+            };
+            inner.lru.remove(&last_used);
+            if let Some((_, data)) = inner.blocks.remove(&key) {
+                inner.size -= data.len() * ALIGN_BOUNDARY_OFFSET;
+            }
+        }
+    }
+
+    /// Drops the cached blocks of a storage which is going away
+    fn remove_storage(&self, storage_id: u64) {
+        for shard in &self.shards {
+            let mut inner = shard.lock().unwrap();
+            let keys: Vec<_> = inner
+                .blocks
+                .keys()
+                .filter(|(id, _)| *id == storage_id)
+                .copied()
+                .collect();
+            for key in keys {
+                if let Some((last_used, data)) = inner.blocks.remove(&key) {
+                    inner.lru.remove(&last_used);
+                    inner.size -= data.len() * ALIGN_BOUNDARY_OFFSET;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ColdStorage {
+    path: PathBuf,
+    map: Mmap,
+    index: ColdStorageIndex,
+    /// Blocks are decompressed on access and kept in this cache, under `cache_id`
+    cache: Arc<ColdStorageCache>,
+    cache_id: u64,
+    remove_on_drop: bool,
+}
+
+impl Drop for ColdStorage {
+    fn drop(&mut self) {
+        self.cache.remove_storage(self.cache_id);
+        if self.remove_on_drop {
+            if let Err(_e) = remove_file(&self.path) {
+                // Same as AppendVec, tests drop storages whose files were already removed
+            }
+        }
+    }
+}
+
+impl ColdStorage {
+    /// Writes `accounts`, which must be sorted by pubkey, to a new file at `path`.
+    /// Returns the storage and the offset of each account, followed by the end offset, the same
+    /// as `AppendVec::append_accounts()`.
+    pub fn new(
+        path: &Path,
+        accounts: &[(StoredMeta, &AccountSharedData)],
+        hashes: &[Hash],
+        cache: Arc<ColdStorageCache>,
+    ) -> io::Result<(Self, Vec<usize>)> {
+        assert_eq!(accounts.len(), hashes.len());
+        assert!(accounts
+            .windows(2)
+            .all(|pair| pair[0].0.pubkey <= pair[1].0.pubkey));
+
+        let _ignored = remove_file(path);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(path)?;
+        file.write_all(&[0; COLD_STORAGE_HEADER_SIZE])?;
+
+        let mut index = ColdStorageIndex::default();
+        let mut offsets = Vec::with_capacity(accounts.len() + 1);
+        let mut block = vec![];
+        // Uncompressed length of the blocks written so far
+        let mut block_start = 0;
+        let mut file_offset = COLD_STORAGE_HEADER_SIZE as u64;
+        for ((stored_meta, account), hash) in accounts.iter().zip(hashes) {
+            let record_size = stored_size(stored_meta.data_len as usize);
+            if !block.is_empty() && block.len() + record_size > COLD_STORAGE_BLOCK_SIZE {
+                file_offset +=
+                    Self::write_block(&mut file, file_offset, &mut index, block_start, &block)?;
+                block_start += block.len();
+                block.clear();
+            }
+            let offset = block_start + block.len();
+            offsets.push(offset);
+            index.accounts.push((stored_meta.pubkey, offset as u64));
+
+            let account_meta = AccountMeta::from(*account);
+            append_bytes(
+                &mut block,
+                stored_meta as *const StoredMeta as *const u8,
+                mem::size_of::<StoredMeta>(),
+            );
+            append_bytes(
+                &mut block,
+                &account_meta as *const AccountMeta as *const u8,
+                mem::size_of::<AccountMeta>(),
+            );
+            append_bytes(&mut block, hash.as_ref().as_ptr(), mem::size_of::<Hash>());
+            append_bytes(&mut block, account.data().as_ptr(), account.data().len());
+            index.len = (block_start + block.len()) as u64;
+            block.resize(u64_align(block.len()), 0);
+        }
+        if !block.is_empty() {
+            file_offset +=
+                Self::write_block(&mut file, file_offset, &mut index, block_start, &block)?;
+        }
+        offsets.push(index.len as usize);
+
+        let index_bytes =
+            bincode::serialize(&index).map_err(|err| invalid_data(err.to_string()))?;
+        file.write_all(&index_bytes)?;
+        file.seek(SeekFrom::Start(COLD_STORAGE_MAGIC.len() as u64))?;
+        file.write_all(&file_offset.to_le_bytes())?;
+        file.write_all(&(index_bytes.len() as u64).to_le_bytes())?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&COLD_STORAGE_MAGIC)?;
+        file.sync_all()?;
+
+        //UNSAFE: Required to create a Mmap
+        let map = unsafe { Mmap::map(&file)? };
+        let cache_id = cache.new_storage_id();
+        Ok((
+            Self {
+                path: path.to_path_buf(),
+                map,
+                index,
+                cache,
+                cache_id,
+                remove_on_drop: true,
+            },
+            offsets,
+        ))
+    }
+
+    /// Compresses `block` and writes it at `file_offset`, returning its compressed length
+    fn write_block(
+        file: &mut File,
+        file_offset: u64,
+        index: &mut ColdStorageIndex,
+        start: usize,
+        block: &[u8],
+    ) -> io::Result<u64> {
+        let compressed = zstd::block::compress(block, COLD_STORAGE_COMPRESSION_LEVEL)?;
+        file.write_all(&compressed)?;
+        index.blocks.push(ColdStorageBlock {
+            file_offset,
+            compressed_len: compressed.len() as u64,
+            start: start as u64,
+            len: block.len() as u64,
+        });
+        Ok(compressed.len() as u64)
+    }
+
+    /// Returns whether the file at `path` is a `ColdStorage`
+    pub fn is_cold_storage<P: AsRef<Path>>(path: P) -> io::Result<bool> {
+        let mut magic = [0; COLD_STORAGE_MAGIC.len()];
+        let mut file = File::open(path)?;
+        match io::Read::read_exact(&mut file, &mut magic) {
+            Ok(()) => Ok(magic == COLD_STORAGE_MAGIC),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Opens an existing file, checking that it holds `current_len` bytes of well formed
+    /// accounts. Returns the storage and its number of accounts.
+    pub fn new_from_file<P: AsRef<Path>>(
+        path: P,
+        current_len: usize,
+        cache: Arc<ColdStorageCache>,
+    ) -> io::Result<(Self, usize)> {
+        let file = File::open(&path)?;
+        //UNSAFE: Required to create a Mmap
+        let map = unsafe { Mmap::map(&file)? };
+        if map.len() < COLD_STORAGE_HEADER_SIZE
+            || map[..COLD_STORAGE_MAGIC.len()] != COLD_STORAGE_MAGIC
+        {
+            return Err(invalid_data("not a cold storage file".to_string()));
+        }
+        let read_u64 = |offset: usize| {
+            let mut bytes = [0; mem::size_of::<u64>()];
+            bytes.copy_from_slice(&map[offset..offset + mem::size_of::<u64>()]);
+            u64::from_le_bytes(bytes) as usize
+        };
+        let index_offset = read_u64(COLD_STORAGE_MAGIC.len());
+        let index_len = read_u64(COLD_STORAGE_MAGIC.len() + mem::size_of::<u64>());
+        if index_offset < COLD_STORAGE_HEADER_SIZE
+            || index_offset.checked_add(index_len) != Some(map.len())
+        {
+            return Err(invalid_data(
+                "invalid cold storage index position".to_string(),
+            ));
+        }
+        let index: ColdStorageIndex = bincode::options()
+            .with_limit(index_len as u64)
+            .with_fixint_encoding()
+            .deserialize(&map[index_offset..])
+            .map_err(|err| invalid_data(format!("invalid cold storage index: {}", err)))?;
+        if index.len as usize != current_len {
+            return Err(invalid_data(format!(
+                "cold storage length {} does not match expected length {}",
+                index.len, current_len
+            )));
+        }
+
+        let cache_id = cache.new_storage_id();
+        let new = Self {
+            path: path.as_ref().to_path_buf(),
+            map,
+            index,
+            cache,
+            cache_id,
+            remove_on_drop: true,
+        };
+        let num_accounts = new.sanitize(index_offset)?;
+        Ok((new, num_accounts))
+    }
+
+    /// Checks the layout of the blocks, every account in them, and that the pubkey index
+    /// matches the accounts. Blocks are decompressed one at a time and not cached, so that
+    /// opening a file does not pull all of it into memory.
+    fn sanitize(&self, index_offset: usize) -> io::Result<usize> {
+        let mut start = 0;
+        let mut file_end = COLD_STORAGE_HEADER_SIZE;
+        let mut accounts = Vec::with_capacity(self.index.accounts.len());
+        for (ix, block) in self.index.blocks.iter().enumerate() {
+            if block.start as usize != start
+                || block.file_offset as usize != file_end
+                || block.len as usize % ALIGN_BOUNDARY_OFFSET != 0
+            {
+                return Err(invalid_data(format!("invalid cold storage block {}", ix)));
+            }
+            start += block.len as usize;
+            file_end += block.compressed_len as usize;
+            if file_end > index_offset {
+                return Err(invalid_data(format!("cold storage block {} overruns", ix)));
+            }
+
+            let data = self.decompress_block(ix)?;
+            let data = block_bytes(&data);
+            let mut offset = 0;
+            while offset < data.len() {
+                let (account, next) = get_account_from_block(data, block.start as usize, offset)
+                    .ok_or_else(|| invalid_data(format!("truncated cold storage block {}", ix)))?;
+                if !account.sanitize() {
+                    return Err(invalid_data("incorrect layout/length/data".to_string()));
+                }
+                accounts.push((account.meta.pubkey, account.offset as u64));
+                offset = next - block.start as usize;
+            }
+        }
+        if start != u64_align(self.index.len as usize) || file_end != index_offset {
+            return Err(invalid_data("invalid cold storage length".to_string()));
+        }
+        accounts.sort_unstable();
+        let mut index_accounts = self.index.accounts.clone();
+        index_accounts.sort_unstable();
+        if accounts != index_accounts {
+            return Err(invalid_data(
+                "cold storage index does not match its accounts".to_string(),
+            ));
+        }
+        Ok(accounts.len())
+    }
+
+    fn decompress_block(&self, ix: usize) -> io::Result<BlockData> {
+        let block = &self.index.blocks[ix];
+        let start = block.file_offset as usize;
+        let compressed = self
+            .map
+            .get(start..start.saturating_add(block.compressed_len as usize))
+            .ok_or_else(|| invalid_data(format!("cold storage block {} overruns", ix)))?;
+        let bytes = zstd::block::decompress(compressed, block.len as usize)?;
+        if bytes.len() != block.len as usize {
+            return Err(invalid_data(format!(
+                "cold storage block {} has the wrong length",
+                ix
+            )));
+        }
+        let mut data = vec![0u64; bytes.len() / ALIGN_BOUNDARY_OFFSET];
+        //UNSAFE: `data` holds exactly `bytes.len()` bytes since the block length is aligned
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), data.as_mut_ptr() as *mut u8, bytes.len())
+        };
+        Ok(data.into())
+    }
+
+    /// Returns the decompressed contents of block `ix`, from the cache if it is there
+    fn block(&self, ix: usize) -> Option<BlockData> {
+        let key = (self.cache_id, ix);
+        if let Some(data) = self.cache.get(key) {
+            return Some(data);
+        }
+        match self.decompress_block(ix) {
+            Ok(data) => {
+                self.cache.insert(key, data.clone());
+                Some(data)
+            }
+            Err(err) => {
+                error!("{:?}: {}", self.path, err);
+                None
+            }
+        }
+    }
+
+    /// Returns the index of the block holding `offset`
+    fn block_ix(&self, offset: usize) -> Option<usize> {
+        let offset = offset as u64;
+        let ix = match self
+            .index
+            .blocks
+            .binary_search_by(|block| block.start.cmp(&offset))
+        {
+            Ok(ix) => ix,
+            Err(0) => return None,
+            Err(ix) => ix - 1,
+        };
+        let block = &self.index.blocks[ix];
+        (offset < block.start + block.len).then(|| ix)
+    }
+
+    /// Return account metadata for the account at `offset`, and the offset of the next account.
+    /// The account is copied out of its block, which may be evicted from the cache while the
+    /// account is in use.
+    pub fn get_account(&self, offset: usize) -> Option<(StoredAccountMeta, usize)> {
+        let ix = self.block_ix(offset)?;
+        let start = self.index.blocks[ix].start as usize;
+        let data = self.block(ix)?;
+        let (account, next) = get_account_from_block(block_bytes(&data), start, offset - start)?;
+        Some((account.into_owned(), next))
+    }
+
+    /// Look up an account by pubkey in the embedded index
+    pub fn get_account_by_pubkey(&self, pubkey: &Pubkey) -> Option<StoredAccountMeta> {
+        let ix = self
+            .index
+            .accounts
+            .binary_search_by(|(key, _)| key.cmp(pubkey))
+            .ok()?;
+        Some(self.get_account(self.index.accounts[ix].1 as usize)?.0)
+    }
+
+    /// Return account metadata for each account, starting from `offset`.
+    pub fn accounts(&self, mut offset: usize) -> Vec<StoredAccountMeta> {
+        let mut accounts = vec![];
+        while let Some((account, next)) = self.get_account(offset) {
+            accounts.push(account);
+            offset = next;
+        }
+        accounts
+    }
+
+    /// Uncompressed length of the accounts, which is the end offset of the last account
+    pub fn len(&self) -> usize {
+        self.index.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Size of the file on disk
+    pub fn file_size(&self) -> u64 {
+        self.map.len() as u64
+    }
+
+    pub fn num_accounts(&self) -> usize {
+        self.index.accounts.len()
+    }
+
+    pub fn get_path(&self) -> PathBuf {
+        self.path.clone()
+    }
+
+    pub fn set_no_remove_on_drop(&mut self) {
+        self.remove_on_drop = false;
+    }
+}
+
+/// Size of the record holding an account with `data_len` bytes of data, as in an `AppendVec`
+fn stored_size(data_len: usize) -> usize {
+    u64_align(mem::size_of::<StoredMeta>())
+        + u64_align(mem::size_of::<AccountMeta>())
+        + u64_align(mem::size_of::<Hash>())
+        + u64_align(data_len)
+}
+
+/// Copy `len` bytes from `src` to the next aligned position of `block`
+fn append_bytes(block: &mut Vec<u8>, src: *const u8, len: usize) {
+    block.resize(u64_align(block.len()), 0);
+    //UNSAFE: `src` points to a value, or the data of an account, of `len` bytes
+    block.extend_from_slice(unsafe { std::slice::from_raw_parts(src, len) });
+}
+
+fn block_bytes(data: &[u64]) -> &[u8] {
+    //UNSAFE: Any u64 slice can be read as bytes
+    unsafe {
+        std::slice::from_raw_parts(
+            data.as_ptr() as *const u8,
+            data.len() * ALIGN_BOUNDARY_OFFSET,
+        )
+    }
+}
+
+/// Return a reference to the type at `offset` of `block`, and the aligned offset after it
+fn get_type<T>(block: &[u8], offset: usize) -> Option<(&T, usize)> {
+    let end = offset.checked_add(mem::size_of::<T>())?;
+    if end > block.len() {
+        return None;
+    }
+    let ptr = block[offset..].as_ptr() as *const T;
+    //UNSAFE: The cast is safe because blocks are u64 aligned, every field starts at an aligned
+    //offset, and the value fits in the block
+    Some((unsafe { &*ptr }, u64_align(end)))
+}
+
+/// Parse the account at `offset` of a block starting at `block_start`. Returns the account,
+/// and the offset of the next account, both relative to the start of the storage.
+fn get_account_from_block(
+    block: &[u8],
+    block_start: usize,
+    offset: usize,
+) -> Option<(StoredAccountMeta, usize)> {
+    let (meta, next): (&StoredMeta, _) = get_type(block, offset)?;
+    let (account_meta, next): (&AccountMeta, _) = get_type(block, next)?;
+    let (hash, next): (&Hash, _) = get_type(block, next)?;
+    let data_end = next.checked_add(meta.data_len as usize)?;
+    let data = block.get(next..data_end)?;
+    let next = u64_align(data_end);
+    Some((
+        StoredAccountMeta {
+            meta: Cow::Borrowed(meta),
+            account_meta: Cow::Borrowed(account_meta),
+            data: Cow::Borrowed(data),
+            offset: block_start + offset,
+            stored_size: next - offset,
+            hash: Cow::Borrowed(hash),
+        },
+        block_start + next,
+    ))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::append_vec::{test_utils::*, AppendVec};
+    use solana_sdk::account::Account;
+
+    fn create_test_accounts(count: usize) -> Vec<(StoredMeta, AccountSharedData)> {
+        let mut accounts: Vec<_> = (0..count).map(create_test_account).collect();
+        for (meta, _) in accounts.iter_mut() {
+            meta.pubkey = solana_sdk::pubkey::new_rand();
+        }
+        accounts.sort_by(|a, b| a.0.pubkey.cmp(&b.0.pubkey));
+        accounts
+    }
+
+    #[test]
+    fn test_cold_storage_matches_append_vec() {
+        let accounts = create_test_accounts(1000);
+        let accounts: Vec<_> = accounts
+            .iter()
+            .map(|(meta, account)| (meta.clone(), account))
+            .collect();
+        let hashes: Vec<_> = (0..accounts.len()).map(|_| Hash::new_unique()).collect();
+
+        let path = get_append_vec_path("test_cold_storage_matches_append_vec");
+        let (cold, offsets) =
+            ColdStorage::new(&path.path, &accounts, &hashes, Arc::default()).unwrap();
+        let append_vec_path = get_append_vec_path("test_cold_storage_matches_append_vec_av");
+        let append_vec = AppendVec::new(&append_vec_path.path, true, 4 * 1024 * 1024);
+        assert_eq!(append_vec.append_accounts(&accounts, &hashes), offsets);
+
+        assert!(cold.index.blocks.len() > 1);
+        assert!(cold.file_size() < append_vec.len() as u64);
+        assert_eq!(cold.len(), append_vec.len());
+        assert_eq!(cold.accounts(0), append_vec.accounts(0));
+        for (i, offset) in offsets[..accounts.len()].iter().enumerate() {
+            let (account, next) = cold.get_account(*offset).unwrap();
+            assert_eq!(next, offsets[i + 1]);
+            assert_eq!(*account.meta, accounts[i].0);
+            assert_eq!(account.clone_account(), *accounts[i].1);
+            assert_eq!(*account.hash, hashes[i]);
+            assert_eq!(
+                cold.get_account_by_pubkey(&accounts[i].0.pubkey),
+                Some(account)
+            );
+        }
+        assert!(cold.get_account(cold.len()).is_none());
+        assert!(cold
+            .get_account_by_pubkey(&solana_sdk::pubkey::new_rand())
+            .is_none());
+    }
+
+    #[test]
+    fn test_cold_storage_cache_eviction() {
+        let accounts = create_test_accounts(1000);
+        let accounts: Vec<_> = accounts
+            .iter()
+            .map(|(meta, account)| (meta.clone(), account))
+            .collect();
+        let hashes: Vec<_> = (0..accounts.len()).map(|_| Hash::new_unique()).collect();
+        let cache = Arc::new(ColdStorageCache::new_with_num_shards(
+            COLD_STORAGE_BLOCK_SIZE,
+            1,
+        ));
+        let path = get_append_vec_path("test_cold_storage_cache_eviction");
+        let (cold, offsets) =
+            ColdStorage::new(&path.path, &accounts, &hashes, cache.clone()).unwrap();
+        assert!(cold.index.blocks.len() > 2);
+        assert_eq!(cache.size(), 0);
+
+        // Reading every account only keeps the blocks that fit in the cache
+        let first = cold.get_account(offsets[0]).unwrap().0;
+        for (i, account) in cold.accounts(0).iter().enumerate() {
+            assert_eq!(account.clone_account(), *accounts[i].1);
+            assert!(cache.size() <= COLD_STORAGE_BLOCK_SIZE);
+        }
+        // Accounts read from an evicted block own their data
+        assert!(cache.get((cold.cache_id, 0)).is_none());
+        assert_eq!(first.clone_account(), *accounts[0].1);
+        assert_eq!(*first.hash, hashes[0]);
+
+        // The least recently used block is evicted first
+        let block_size = |ix: usize| cold.index.blocks[ix].len as usize;
+        cache.set_max_size(0);
+        cache.set_max_size(block_size(0) + block_size(1).max(block_size(2)));
+        cold.block(0).unwrap();
+        cold.block(1).unwrap();
+        cold.block(0).unwrap();
+        cold.block(2).unwrap();
+        assert!(cache.get((cold.cache_id, 0)).is_some());
+        assert!(cache.get((cold.cache_id, 1)).is_none());
+        assert!(cache.get((cold.cache_id, 2)).is_some());
+
+        cache.set_max_size(0);
+        assert_eq!(cache.size(), 0);
+        cache.set_max_size(DEFAULT_COLD_STORAGE_CACHE_SIZE);
+        cold.block(0).unwrap();
+        assert!(cache.size() > 0);
+        drop(cold);
+        assert_eq!(cache.size(), 0);
+    }
+
+    #[test]
+    fn test_cold_storage_cache_shards() {
+        let accounts = create_test_accounts(1000);
+        let accounts: Vec<_> = accounts
+            .iter()
+            .map(|(meta, account)| (meta.clone(), account))
+            .collect();
+        let hashes: Vec<_> = (0..accounts.len()).map(|_| Hash::new_unique()).collect();
+        let cache = Arc::new(ColdStorageCache::new(
+            COLD_STORAGE_CACHE_NUM_SHARDS * 2 * COLD_STORAGE_BLOCK_SIZE,
+        ));
+        let path = get_append_vec_path("test_cold_storage_cache_shards");
+        let (cold, _) = ColdStorage::new(&path.path, &accounts, &hashes, cache.clone()).unwrap();
+        let num_blocks = cold.index.blocks.len();
+        assert!(num_blocks > 2);
+
+        // Consecutive blocks are in different shards, so they don't evict each other
+        for ix in 0..num_blocks {
+            cold.block(ix).unwrap();
+        }
+        for ix in 0..num_blocks.min(COLD_STORAGE_CACHE_NUM_SHARDS) {
+            assert!(cache.get((cold.cache_id, ix)).is_some());
+        }
+        assert!(cache.size() <= cache.max_size());
+
+        drop(cold);
+        assert_eq!(cache.size(), 0);
+    }
+
+    #[test]
+    fn test_cold_storage_new_from_file() {
+        let accounts = create_test_accounts(10);
+        let accounts: Vec<_> = accounts
+            .iter()
+            .map(|(meta, account)| (meta.clone(), account))
+            .collect();
+        let hashes: Vec<_> = (0..accounts.len()).map(|_| Hash::new_unique()).collect();
+        let path = get_append_vec_path("test_cold_storage_new_from_file");
+        let (mut cold, _) =
+            ColdStorage::new(&path.path, &accounts, &hashes, Arc::default()).unwrap();
+        cold.set_no_remove_on_drop();
+        let len = cold.len();
+        drop(cold);
+
+        assert!(ColdStorage::is_cold_storage(&path.path).unwrap());
+        assert!(ColdStorage::new_from_file(&path.path, len + 8, Arc::default()).is_err());
+        let (cold, num_accounts) =
+            ColdStorage::new_from_file(&path.path, len, Arc::default()).unwrap();
+        assert_eq!(num_accounts, accounts.len());
+        let stored: Vec<_> = cold
+            .accounts(0)
+            .iter()
+            .map(|account| (account.meta.clone().into_owned(), account.clone_account()))
+            .collect();
+        let expected: Vec<_> = accounts
+            .iter()
+            .map(|(meta, account)| (meta.clone(), (*account).clone()))
+            .collect();
+        assert_eq!(stored, expected);
+
+        let append_vec_path = get_append_vec_path("test_cold_storage_new_from_file_av");
+        let _append_vec = AppendVec::new(&append_vec_path.path, true, 1024);
+        assert!(!ColdStorage::is_cold_storage(&append_vec_path.path).unwrap());
+    }
+
+    #[test]
+    fn test_cold_storage_new_from_file_corrupted() {
+        let account = AccountSharedData::from(Account {
+            lamports: 1,
+            data: vec![7; 100],
+            ..Account::default()
+        });
+        let meta = StoredMeta {
+            write_version: 0,
+            pubkey: solana_sdk::pubkey::new_rand(),
+            data_len: 100,
+        };
+        let path = get_append_vec_path("test_cold_storage_new_from_file_corrupted");
+        let (mut cold, _) = ColdStorage::new(
+            &path.path,
+            &[(meta, &account)],
+            &[Hash::default()],
+            Arc::default(),
+        )
+        .unwrap();
+        cold.set_no_remove_on_drop();
+        let len = cold.len();
+        drop(cold);
+
+        // Corrupt the compressed block
+        let mut bytes = std::fs::read(&path.path).unwrap();
+        bytes[COLD_STORAGE_HEADER_SIZE] ^= 0xff;
+        bytes[COLD_STORAGE_HEADER_SIZE + 1] ^= 0xff;
+        std::fs::write(&path.path, &bytes).unwrap();
+        assert!(ColdStorage::new_from_file(&path.path, len, Arc::default()).is_err());
+
+        // Truncated file
+        std::fs::write(&path.path, &bytes[..COLD_STORAGE_HEADER_SIZE + 4]).unwrap();
+        assert!(ColdStorage::new_from_file(&path.path, len, Arc::default()).is_err());
+    }
+}
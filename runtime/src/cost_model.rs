@@ -0,0 +1,235 @@
+//! Estimates the cost of executing a transaction, in compute units, before it is executed.
+//!
+//! The cost is the sum of the signature verification cost, a fixed cost per write lock, a cost
+//! proportional to the instruction data size, and the compute units the transaction may consume
+//! during execution.  Builtin programs are charged a fixed cost per instruction, while other
+//! programs are charged the transaction's compute unit limit for each instruction.
+use solana_sdk::{
+    compute_budget::{self, ComputeBudgetRequest},
+    message::Message,
+    pubkey::Pubkey,
+    secp256k1_program, system_program,
+    transaction::Transaction,
+};
+
+/// Cost of verifying one signature
+pub const SIGNATURE_COST: u64 = 720;
+/// Cost of taking a write lock on one account
+pub const WRITE_LOCK_COST: u64 = 300;
+/// Number of bytes of instruction data that cost one compute unit
+pub const INSTRUCTION_DATA_BYTES_PER_UNIT: u64 = 4;
+/// Maximum cost of all transactions in a block
+pub const MAX_BLOCK_UNITS: u64 = 48_000_000;
+/// Maximum cost of all transactions that write lock the same account in a block
+pub const MAX_WRITABLE_ACCOUNT_UNITS: u64 = 12_000_000;
+
+// Execution cost of one instruction of each builtin program
+const SYSTEM_PROGRAM_COST: u64 = 150;
+const VOTE_PROGRAM_COST: u64 = 2_100;
+const STAKE_PROGRAM_COST: u64 = 750;
+const CONFIG_PROGRAM_COST: u64 = 450;
+const SECP256K1_PROGRAM_COST: u64 = 720;
+const COMPUTE_BUDGET_PROGRAM_COST: u64 = 150;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TransactionCost {
+    pub writable_accounts: Vec<Pubkey>,
+    pub signature_cost: u64,
+    pub write_lock_cost: u64,
+    pub data_bytes_cost: u64,
+    pub execution_cost: u64,
+}
+
+impl TransactionCost {
+    pub fn sum(&self) -> u64 {
+        self.signature_cost
+            .saturating_add(self.write_lock_cost)
+            .saturating_add(self.data_bytes_cost)
+            .saturating_add(self.execution_cost)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostModel {
+    account_cost_limit: u64,
+    block_cost_limit: u64,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self::new(MAX_WRITABLE_ACCOUNT_UNITS, MAX_BLOCK_UNITS)
+    }
+}
+
+impl CostModel {
+    pub fn new(account_cost_limit: u64, block_cost_limit: u64) -> Self {
+        Self {
+            account_cost_limit,
+            block_cost_limit,
+        }
+    }
+
+    pub fn account_cost_limit(&self) -> u64 {
+        self.account_cost_limit
+    }
+
+    pub fn block_cost_limit(&self) -> u64 {
+        self.block_cost_limit
+    }
+
+    pub fn calculate_cost(&self, transaction: &Transaction) -> TransactionCost {
+        let message = transaction.message();
+        let writable_accounts: Vec<_> = message
+            .account_keys
+            .iter()
+            .enumerate()
+            .filter_map(|(i, key)| {
+                if message.is_writable(i) {
+                    Some(*key)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        let data_bytes: u64 = message
+            .instructions
+            .iter()
+            .map(|instruction| instruction.data.len() as u64)
+            .sum();
+
+        TransactionCost {
+            signature_cost: SIGNATURE_COST
+                .saturating_mul(u64::from(message.header.num_required_signatures)),
+            write_lock_cost: WRITE_LOCK_COST.saturating_mul(writable_accounts.len() as u64),
+            data_bytes_cost: data_bytes / INSTRUCTION_DATA_BYTES_PER_UNIT,
+            execution_cost: Self::execution_cost(message),
+            writable_accounts,
+        }
+    }
+
+    fn execution_cost(message: &Message) -> u64 {
+        // An invalid request fails the transaction before any instruction is executed, but the
+        // cost is still estimated from the default limit to stay conservative
+        let compute_unit_limit = ComputeBudgetRequest::from_message(message)
+            .unwrap_or_default()
+            .compute_unit_limit_or(u64::from(compute_budget::DEFAULT_COMPUTE_UNIT_LIMIT));
+        message
+            .instructions
+            .iter()
+            .map(|instruction| {
+                message
+                    .account_keys
+                    .get(instruction.program_id_index as usize)
+                    .and_then(Self::builtin_instruction_cost)
+                    .unwrap_or(compute_unit_limit)
+            })
+            .fold(0u64, |cost, instruction_cost| {
+                cost.saturating_add(instruction_cost)
+            })
+    }
+
+    fn builtin_instruction_cost(program_id: &Pubkey) -> Option<u64> {
+        if system_program::check_id(program_id) {
+            Some(SYSTEM_PROGRAM_COST)
+        } else if solana_vote_program::check_id(program_id) {
+            Some(VOTE_PROGRAM_COST)
+        } else if solana_stake_program::check_id(program_id) {
+            Some(STAKE_PROGRAM_COST)
+        } else if solana_config_program::check_id(program_id) {
+            Some(CONFIG_PROGRAM_COST)
+        } else if secp256k1_program::check_id(program_id) {
+            Some(SECP256K1_PROGRAM_COST)
+        } else if compute_budget::check_id(program_id) {
+            Some(COMPUTE_BUDGET_PROGRAM_COST)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        hash::Hash,
+        instruction::Instruction,
+        signature::{Keypair, Signer},
+        system_instruction, system_transaction,
+    };
+
+    #[test]
+    fn test_cost_model_system_transfer() {
+        let from = Keypair::new();
+        let to = solana_sdk::pubkey::new_rand();
+        let tx = system_transaction::transfer(&from, &to, 1, Hash::default());
+
+        let cost = CostModel::default().calculate_cost(&tx);
+        assert_eq!(cost.writable_accounts, vec![from.pubkey(), to]);
+        assert_eq!(cost.signature_cost, SIGNATURE_COST);
+        assert_eq!(cost.write_lock_cost, 2 * WRITE_LOCK_COST);
+        assert_eq!(
+            cost.data_bytes_cost,
+            tx.message.instructions[0].data.len() as u64 / INSTRUCTION_DATA_BYTES_PER_UNIT
+        );
+        assert_eq!(cost.execution_cost, SYSTEM_PROGRAM_COST);
+        assert_eq!(
+            cost.sum(),
+            SIGNATURE_COST + 2 * WRITE_LOCK_COST + cost.data_bytes_cost + SYSTEM_PROGRAM_COST
+        );
+    }
+
+    #[test]
+    fn test_cost_model_compute_budget_request() {
+        let payer = Keypair::new();
+        let program_id = solana_sdk::pubkey::new_rand();
+        let program_ix = Instruction::new_with_bytes(program_id, &[0; 16], vec![]);
+
+        // Non-builtin instructions are charged the default limit
+        let tx = Transaction::new_signed_with_payer(
+            &[program_ix.clone(), program_ix.clone()],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        );
+        assert_eq!(
+            CostModel::default().calculate_cost(&tx).execution_cost,
+            2 * u64::from(compute_budget::DEFAULT_COMPUTE_UNIT_LIMIT)
+        );
+
+        // ...or the requested limit, if any
+        let tx = Transaction::new_signed_with_payer(
+            &[
+                compute_budget::set_compute_unit_limit(1_000),
+                program_ix.clone(),
+                system_instruction::transfer(&payer.pubkey(), &program_id, 1),
+                program_ix,
+            ],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        );
+        assert_eq!(
+            CostModel::default().calculate_cost(&tx).execution_cost,
+            COMPUTE_BUDGET_PROGRAM_COST + 1_000 + SYSTEM_PROGRAM_COST + 1_000
+        );
+    }
+
+    #[test]
+    fn test_cost_model_vote_transaction() {
+        let node_keypair = Keypair::new();
+        let vote_keypair = Keypair::new();
+        let tx = Transaction::new_signed_with_payer(
+            &[solana_vote_program::vote_instruction::vote(
+                &vote_keypair.pubkey(),
+                &vote_keypair.pubkey(),
+                solana_vote_program::vote_state::Vote::default(),
+            )],
+            Some(&node_keypair.pubkey()),
+            &[&node_keypair, &vote_keypair],
+            Hash::default(),
+        );
+        let cost = CostModel::default().calculate_cost(&tx);
+        assert_eq!(cost.signature_cost, 2 * SIGNATURE_COST);
+        assert_eq!(cost.execution_cost, VOTE_PROGRAM_COST);
+    }
+}
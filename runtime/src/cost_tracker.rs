@@ -0,0 +1,179 @@
+//! Tracks the accumulated cost of the transactions admitted into a block, so that a block, and
+//! each account written by the transactions in it, stays within the limits of a `CostModel`.
+use crate::cost_model::{CostModel, TransactionCost};
+use solana_sdk::{clock::Slot, pubkey::Pubkey};
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CostTrackerError {
+    #[error("would exceed block max limit")]
+    WouldExceedBlockMaxLimit,
+
+    #[error("would exceed account max limit")]
+    WouldExceedAccountMaxLimit,
+}
+
+#[derive(Debug, Default)]
+pub struct CostTracker {
+    cost_model: CostModel,
+    current_bank_slot: Slot,
+    cost_by_writable_accounts: HashMap<Pubkey, u64>,
+    block_cost: u64,
+}
+
+impl CostTracker {
+    pub fn new(cost_model: CostModel) -> Self {
+        Self {
+            cost_model,
+            ..Self::default()
+        }
+    }
+
+    pub fn cost_model(&self) -> &CostModel {
+        &self.cost_model
+    }
+
+    pub fn current_bank_slot(&self) -> Slot {
+        self.current_bank_slot
+    }
+
+    pub fn block_cost(&self) -> u64 {
+        self.block_cost
+    }
+
+    /// Forget the costs tracked for the previous bank once a new bank starts
+    pub fn reset_if_new_bank(&mut self, slot: Slot) {
+        if slot != self.current_bank_slot {
+            self.current_bank_slot = slot;
+            self.cost_by_writable_accounts.clear();
+            self.block_cost = 0;
+        }
+    }
+
+    pub fn would_fit(&self, cost: &TransactionCost) -> Result<(), CostTrackerError> {
+        let total_cost = cost.sum();
+        if self.block_cost.saturating_add(total_cost) > self.cost_model.block_cost_limit() {
+            return Err(CostTrackerError::WouldExceedBlockMaxLimit);
+        }
+        // A single transaction above the account limit can never fit
+        if total_cost > self.cost_model.account_cost_limit() {
+            return Err(CostTrackerError::WouldExceedAccountMaxLimit);
+        }
+        for account_key in &cost.writable_accounts {
+            if let Some(account_cost) = self.cost_by_writable_accounts.get(account_key) {
+                if account_cost.saturating_add(total_cost) > self.cost_model.account_cost_limit() {
+                    return Err(CostTrackerError::WouldExceedAccountMaxLimit);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn add_transaction_cost(&mut self, cost: &TransactionCost) {
+        let total_cost = cost.sum();
+        for account_key in &cost.writable_accounts {
+            let account_cost = self
+                .cost_by_writable_accounts
+                .entry(*account_key)
+                .or_insert(0);
+            *account_cost = account_cost.saturating_add(total_cost);
+        }
+        self.block_cost = self.block_cost.saturating_add(total_cost);
+    }
+
+    /// Add the cost of a transaction if it fits, returning the new block cost
+    pub fn try_add(&mut self, cost: &TransactionCost) -> Result<u64, CostTrackerError> {
+        self.would_fit(cost)?;
+        self.add_transaction_cost(cost);
+        Ok(self.block_cost)
+    }
+
+    /// Give back the cost of a transaction that was added but ended up not being committed
+    pub fn remove_transaction_cost(&mut self, cost: &TransactionCost) {
+        let total_cost = cost.sum();
+        for account_key in &cost.writable_accounts {
+            if let Some(account_cost) = self.cost_by_writable_accounts.get_mut(account_key) {
+                *account_cost = account_cost.saturating_sub(total_cost);
+                if *account_cost == 0 {
+                    self.cost_by_writable_accounts.remove(account_key);
+                }
+            }
+        }
+        self.block_cost = self.block_cost.saturating_sub(total_cost);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cost(writable_accounts: Vec<Pubkey>, execution_cost: u64) -> TransactionCost {
+        TransactionCost {
+            writable_accounts,
+            execution_cost,
+            ..TransactionCost::default()
+        }
+    }
+
+    #[test]
+    fn test_cost_tracker_account_limit() {
+        let hot_account = solana_sdk::pubkey::new_rand();
+        let mut tracker = CostTracker::new(CostModel::new(10, 100));
+
+        assert_eq!(tracker.try_add(&test_cost(vec![hot_account], 6)), Ok(6));
+        assert_eq!(
+            tracker.try_add(&test_cost(vec![hot_account], 5)),
+            Err(CostTrackerError::WouldExceedAccountMaxLimit)
+        );
+        // Other accounts are not affected by the hot one
+        let other_account = solana_sdk::pubkey::new_rand();
+        assert_eq!(tracker.try_add(&test_cost(vec![other_account], 5)), Ok(11));
+        assert_eq!(
+            tracker.try_add(&test_cost(vec![other_account, hot_account], 4)),
+            Ok(15)
+        );
+        // A transaction can never exceed the account limit by itself
+        assert_eq!(
+            tracker.try_add(&test_cost(vec![], 11)),
+            Err(CostTrackerError::WouldExceedAccountMaxLimit)
+        );
+    }
+
+    #[test]
+    fn test_cost_tracker_block_limit() {
+        let mut tracker = CostTracker::new(CostModel::new(10, 25));
+        for expected_block_cost in &[10, 20] {
+            let cost = test_cost(vec![solana_sdk::pubkey::new_rand()], 10);
+            assert_eq!(tracker.try_add(&cost), Ok(*expected_block_cost));
+        }
+        let cost = test_cost(vec![solana_sdk::pubkey::new_rand()], 10);
+        assert_eq!(
+            tracker.try_add(&cost),
+            Err(CostTrackerError::WouldExceedBlockMaxLimit)
+        );
+        assert_eq!(tracker.block_cost(), 20);
+    }
+
+    #[test]
+    fn test_cost_tracker_remove_and_reset() {
+        let account = solana_sdk::pubkey::new_rand();
+        let mut tracker = CostTracker::new(CostModel::new(10, 100));
+        tracker.reset_if_new_bank(1);
+
+        let cost = test_cost(vec![account], 10);
+        assert_eq!(tracker.try_add(&cost), Ok(10));
+        assert!(tracker.would_fit(&cost).is_err());
+        tracker.remove_transaction_cost(&cost);
+        assert_eq!(tracker.block_cost(), 0);
+        assert_eq!(tracker.try_add(&cost), Ok(10));
+
+        // Same bank, nothing changes
+        tracker.reset_if_new_bank(1);
+        assert_eq!(tracker.block_cost(), 10);
+        tracker.reset_if_new_bank(2);
+        assert_eq!(tracker.current_bank_slot(), 2);
+        assert_eq!(tracker.block_cost(), 0);
+        assert_eq!(tracker.try_add(&cost), Ok(10));
+    }
+}
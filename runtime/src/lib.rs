@@ -4,8 +4,10 @@ pub mod accounts;
 pub mod accounts_background_service;
 pub mod accounts_cache;
 pub mod accounts_db;
+pub mod accounts_file;
 pub mod accounts_hash;
 pub mod accounts_index;
+pub mod accounts_update_notifier_interface;
 pub mod append_vec;
 pub mod bank;
 pub mod bank_client;
@@ -13,9 +15,13 @@ pub mod bank_forks;
 pub mod bank_utils;
 mod blockhash_queue;
 pub mod bloom;
+pub mod bucket_map;
 pub mod builtins;
+pub mod cold_storage;
 pub mod commitment;
 pub mod contains;
+pub mod cost_model;
+pub mod cost_tracker;
 pub mod epoch_stakes;
 pub mod genesis_utils;
 pub mod hardened_unpack;
@@ -34,6 +40,7 @@ pub mod stakes;
 pub mod status_cache;
 mod system_instruction_processor;
 pub mod transaction_batch;
+pub mod transaction_scheduler;
 pub mod transaction_utils;
 pub mod vote_account;
 pub mod vote_sender_types;
@@ -8,7 +8,11 @@ use solana_sdk::{
     account::{AccountSharedData, ReadableAccount, WritableAccount},
     account_utils::StateMut,
     bpf_loader_upgradeable::{self, UpgradeableLoaderState},
-    feature_set::{cpi_share_ro_and_exec_accounts, instructions_sysvar_enabled, FeatureSet},
+    compute_budget::ComputeBudgetRequest,
+    feature_set::{
+        compute_budget_program_enabled, cpi_share_ro_and_exec_accounts,
+        instructions_sysvar_enabled, FeatureSet,
+    },
     ic_msg,
     instruction::{CompiledInstruction, Instruction, InstructionError},
     keyed_account::{create_keyed_readonly_accounts, KeyedAccount},
@@ -237,6 +241,11 @@ impl PreAccount {
 pub struct ThisComputeMeter {
     remaining: u64,
 }
+impl ThisComputeMeter {
+    pub fn new_ref(remaining: u64) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self { remaining }))
+    }
+}
 impl ComputeMeter for ThisComputeMeter {
     fn consume(&mut self, amount: u64) -> Result<(), InstructionError> {
         let exceeded = self.remaining < amount;
@@ -276,6 +285,7 @@ impl<'a> ThisInvokeContext<'a> {
         programs: &'a [(Pubkey, ProcessInstructionWithContext)],
         log_collector: Option<Rc<LogCollector>>,
         bpf_compute_budget: BpfComputeBudget,
+        compute_meter: Rc<RefCell<dyn ComputeMeter>>,
         executors: Rc<RefCell<Executors>>,
         instruction_recorder: Option<InstructionRecorder>,
         feature_set: Arc<FeatureSet>,
@@ -291,9 +301,7 @@ impl<'a> ThisInvokeContext<'a> {
             programs,
             logger: Rc::new(RefCell::new(ThisLogger { log_collector })),
             bpf_compute_budget,
-            compute_meter: Rc::new(RefCell::new(ThisComputeMeter {
-                remaining: bpf_compute_budget.max_units,
-            })),
+            compute_meter,
             executors,
             instruction_recorder,
             feature_set,
@@ -1018,6 +1026,7 @@ impl MessageProcessor {
         instruction_index: usize,
         feature_set: Arc<FeatureSet>,
         bpf_compute_budget: BpfComputeBudget,
+        compute_meter: Rc<RefCell<dyn ComputeMeter>>,
         timings: &mut ExecuteDetailsTimings,
     ) -> Result<(), InstructionError> {
         // Fixup the special instructions key if present
@@ -1046,6 +1055,7 @@ impl MessageProcessor {
             &self.programs,
             log_collector,
             bpf_compute_budget,
+            compute_meter,
             executors,
             instruction_recorder,
             feature_set,
@@ -1089,13 +1099,30 @@ impl MessageProcessor {
         executors: Rc<RefCell<Executors>>,
         instruction_recorders: Option<&[InstructionRecorder]>,
         feature_set: Arc<FeatureSet>,
-        bpf_compute_budget: BpfComputeBudget,
+        mut bpf_compute_budget: BpfComputeBudget,
         timings: &mut ExecuteDetailsTimings,
     ) -> Result<(), TransactionError> {
+        // Once compute budget requests are enabled, the compute unit limit applies to the
+        // message as a whole and all of its instructions draw from the same meter
+        let message_compute_meter = if feature_set.is_active(&compute_budget_program_enabled::id())
+        {
+            let request = ComputeBudgetRequest::from_message(message).map_err(|err| {
+                TransactionError::InstructionError(err.instruction_index(), err.into())
+            })?;
+            bpf_compute_budget.max_units =
+                request.compute_unit_limit_or(bpf_compute_budget.max_units);
+            Some(ThisComputeMeter::new_ref(bpf_compute_budget.max_units))
+        } else {
+            None
+        };
         for (instruction_index, instruction) in message.instructions.iter().enumerate() {
             let instruction_recorder = instruction_recorders
                 .as_ref()
                 .map(|recorders| recorders[instruction_index].clone());
+            let compute_meter: Rc<RefCell<dyn ComputeMeter>> = match &message_compute_meter {
+                Some(compute_meter) => compute_meter.clone(),
+                None => ThisComputeMeter::new_ref(bpf_compute_budget.max_units),
+            };
             self.execute_instruction(
                 message,
                 instruction,
@@ -1109,6 +1136,7 @@ impl MessageProcessor {
                 instruction_index,
                 feature_set.clone(),
                 bpf_compute_budget,
+                compute_meter,
                 timings,
             )
             .map_err(|err| TransactionError::InstructionError(instruction_index as u8, err))?;
@@ -1122,6 +1150,7 @@ mod tests {
     use super::*;
     use solana_sdk::{
         account::Account,
+        compute_budget,
         instruction::{AccountMeta, Instruction, InstructionError},
         message::Message,
         native_loader::create_loadable_account,
@@ -1158,6 +1187,7 @@ mod tests {
             &[],
             None,
             BpfComputeBudget::default(),
+            ThisComputeMeter::new_ref(BpfComputeBudget::default().max_units),
             Rc::new(RefCell::new(Executors::default())),
             None,
             Arc::new(FeatureSet::all_enabled()),
@@ -1978,6 +2008,240 @@ mod tests {
         assert_eq!(accounts[0].borrow().data(), &vec![42]);
     }
 
+    #[test]
+    fn test_process_message_compute_budget_request() {
+        fn mock_process_instruction(
+            _program_id: &Pubkey,
+            _keyed_accounts: &[KeyedAccount],
+            data: &[u8],
+            invoke_context: &mut dyn InvokeContext,
+        ) -> Result<(), InstructionError> {
+            let expected_units: u64 = bincode::deserialize(data).unwrap();
+            if invoke_context.get_compute_meter().borrow().get_remaining() == expected_units {
+                Ok(())
+            } else {
+                Err(InstructionError::InvalidArgument)
+            }
+        }
+        fn noop_process_instruction(
+            _program_id: &Pubkey,
+            _keyed_accounts: &[KeyedAccount],
+            _data: &[u8],
+            _invoke_context: &mut dyn InvokeContext,
+        ) -> Result<(), InstructionError> {
+            Ok(())
+        }
+
+        let mock_program_id = Pubkey::new(&[2u8; 32]);
+        let rent_collector = RentCollector::default();
+        let mut message_processor = MessageProcessor::default();
+        message_processor.add_program(mock_program_id, mock_process_instruction);
+        message_processor.add_program(compute_budget::id(), noop_process_instruction);
+
+        let mock_loader = vec![(
+            mock_program_id,
+            Rc::new(RefCell::new(create_loadable_account("mock_program", 1))),
+        )];
+        let compute_budget_loader = vec![(
+            compute_budget::id(),
+            Rc::new(RefCell::new(create_loadable_account(
+                "compute_budget_program",
+                1,
+            ))),
+        )];
+        let accounts = vec![AccountSharedData::new_ref(100, 0, &mock_program_id)];
+        let payer = solana_sdk::pubkey::new_rand();
+        let bpf_compute_budget = BpfComputeBudget::new();
+
+        let process_message = |instructions: &[Instruction],
+                               loaders: &[Vec<(Pubkey, Rc<RefCell<AccountSharedData>>)>],
+                               feature_set: FeatureSet| {
+            message_processor.process_message(
+                &Message::new(instructions, Some(&payer)),
+                loaders,
+                &accounts,
+                &[],
+                &rent_collector,
+                None,
+                Rc::new(RefCell::new(Executors::default())),
+                None,
+                Arc::new(feature_set),
+                bpf_compute_budget,
+                &mut ExecuteDetailsTimings::default(),
+            )
+        };
+        let expect_units =
+            |units: u64| Instruction::new_with_bincode(mock_program_id, &units, vec![]);
+
+        // No request, the cluster budget applies
+        assert_eq!(
+            process_message(
+                &[expect_units(bpf_compute_budget.max_units)],
+                &[mock_loader.clone()],
+                FeatureSet::all_enabled(),
+            ),
+            Ok(())
+        );
+
+        // The requested limit replaces the cluster budget
+        let loaders = vec![compute_budget_loader.clone(), mock_loader.clone()];
+        let instructions = [
+            compute_budget::set_compute_unit_limit(1_000_000),
+            expect_units(1_000_000),
+        ];
+        assert_eq!(
+            process_message(&instructions, &loaders, FeatureSet::all_enabled()),
+            Ok(())
+        );
+
+        // ...but only once the feature is active
+        let mut feature_set = FeatureSet::all_enabled();
+        feature_set
+            .active
+            .remove(&compute_budget_program_enabled::id());
+        assert_eq!(
+            process_message(&instructions, &loaders, feature_set),
+            Err(TransactionError::InstructionError(
+                1,
+                InstructionError::InvalidArgument
+            ))
+        );
+
+        // Invalid requests fail the transaction at the offending instruction
+        let loaders = vec![
+            mock_loader.clone(),
+            compute_budget_loader.clone(),
+            compute_budget_loader,
+        ];
+        assert_eq!(
+            process_message(
+                &[
+                    expect_units(1),
+                    compute_budget::set_compute_unit_limit(1),
+                    compute_budget::set_compute_unit_limit(1),
+                ],
+                &loaders,
+                FeatureSet::all_enabled(),
+            ),
+            Err(TransactionError::InstructionError(
+                2,
+                InstructionError::InvalidInstructionData
+            ))
+        );
+    }
+
+    #[test]
+    fn test_process_message_shared_compute_meter() {
+        fn mock_process_instruction(
+            _program_id: &Pubkey,
+            _keyed_accounts: &[KeyedAccount],
+            data: &[u8],
+            invoke_context: &mut dyn InvokeContext,
+        ) -> Result<(), InstructionError> {
+            let (expected_remaining, units): (u64, u64) = bincode::deserialize(data).unwrap();
+            let compute_meter = invoke_context.get_compute_meter();
+            if compute_meter.borrow().get_remaining() != expected_remaining {
+                return Err(InstructionError::InvalidArgument);
+            }
+            let result = compute_meter.borrow_mut().consume(units);
+            result
+        }
+        fn noop_process_instruction(
+            _program_id: &Pubkey,
+            _keyed_accounts: &[KeyedAccount],
+            _data: &[u8],
+            _invoke_context: &mut dyn InvokeContext,
+        ) -> Result<(), InstructionError> {
+            Ok(())
+        }
+
+        let mock_program_id = Pubkey::new(&[2u8; 32]);
+        let rent_collector = RentCollector::default();
+        let mut message_processor = MessageProcessor::default();
+        message_processor.add_program(mock_program_id, mock_process_instruction);
+        message_processor.add_program(compute_budget::id(), noop_process_instruction);
+
+        let mock_loader = vec![(
+            mock_program_id,
+            Rc::new(RefCell::new(create_loadable_account("mock_program", 1))),
+        )];
+        let compute_budget_loader = vec![(
+            compute_budget::id(),
+            Rc::new(RefCell::new(create_loadable_account(
+                "compute_budget_program",
+                1,
+            ))),
+        )];
+        let loaders = vec![compute_budget_loader, mock_loader.clone(), mock_loader];
+        let accounts = vec![AccountSharedData::new_ref(100, 0, &mock_program_id)];
+        let payer = solana_sdk::pubkey::new_rand();
+        let bpf_compute_budget = BpfComputeBudget::new();
+
+        let process_message = |instructions: &[Instruction], feature_set: FeatureSet| {
+            message_processor.process_message(
+                &Message::new(instructions, Some(&payer)),
+                &loaders,
+                &accounts,
+                &[],
+                &rent_collector,
+                None,
+                Rc::new(RefCell::new(Executors::default())),
+                None,
+                Arc::new(feature_set),
+                bpf_compute_budget,
+                &mut ExecuteDetailsTimings::default(),
+            )
+        };
+        let consume_units = |expected_remaining: u64, units: u64| {
+            Instruction::new_with_bincode(mock_program_id, &(expected_remaining, units), vec![])
+        };
+
+        // Instructions draw from the limit of the whole message
+        assert_eq!(
+            process_message(
+                &[
+                    compute_budget::set_compute_unit_limit(1_000),
+                    consume_units(1_000, 600),
+                    consume_units(400, 400),
+                ],
+                FeatureSet::all_enabled(),
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            process_message(
+                &[
+                    compute_budget::set_compute_unit_limit(1_000),
+                    consume_units(1_000, 600),
+                    consume_units(400, 600),
+                ],
+                FeatureSet::all_enabled(),
+            ),
+            Err(TransactionError::InstructionError(
+                2,
+                InstructionError::ComputationalBudgetExceeded
+            ))
+        );
+
+        // Before the feature is active every instruction gets the full cluster budget
+        let mut feature_set = FeatureSet::all_enabled();
+        feature_set
+            .active
+            .remove(&compute_budget_program_enabled::id());
+        let max_units = bpf_compute_budget.max_units;
+        assert_eq!(
+            process_message(
+                &[
+                    compute_budget::set_compute_unit_limit(1_000),
+                    consume_units(max_units, 600),
+                    consume_units(max_units, 600),
+                ],
+                feature_set,
+            ),
+            Ok(())
+        );
+    }
+
     #[test]
     fn test_process_cross_program() {
         #[derive(Debug, Serialize, Deserialize)]
@@ -2057,6 +2321,7 @@ mod tests {
             programs.as_slice(),
             None,
             BpfComputeBudget::default(),
+            ThisComputeMeter::new_ref(BpfComputeBudget::default().max_units),
             Rc::new(RefCell::new(Executors::default())),
             None,
             Arc::new(FeatureSet::all_enabled()),
@@ -2,7 +2,8 @@ use {
     crate::{
         accounts::Accounts,
         accounts_db::{AccountStorageEntry, AccountsDb, AppendVecId, BankHashInfo},
-        accounts_index::{AccountIndex, Ancestors},
+        accounts_file::AccountsFile,
+        accounts_index::{AccountSecondaryIndexes, AccountsIndexConfig, Ancestors},
         append_vec::AppendVec,
         bank::{Bank, BankFieldsToDeserialize, BankRc, Builtins},
         blockhash_queue::BlockhashQueue,
@@ -117,6 +118,13 @@ where
         .deserialize_from::<R, T>(reader)
 }
 
+/// The bank file of a full snapshot and, optionally, the bank file of an incremental snapshot
+/// taken on top of it
+pub(crate) struct SnapshotStreams<'a, R> {
+    pub full_snapshot_stream: &'a mut BufReader<R>,
+    pub incremental_snapshot_stream: Option<&'a mut BufReader<R>>,
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn bank_from_stream<R>(
     serde_style: SerdeStyle,
@@ -127,15 +135,67 @@ pub(crate) fn bank_from_stream<R>(
     frozen_account_pubkeys: &[Pubkey],
     debug_keys: Option<Arc<HashSet<Pubkey>>>,
     additional_builtins: Option<&Builtins>,
-    account_indexes: HashSet<AccountIndex>,
+    account_indexes: AccountSecondaryIndexes,
     caching_enabled: bool,
+    accounts_index_config: Option<AccountsIndexConfig>,
+) -> std::result::Result<Bank, Error>
+where
+    R: Read,
+{
+    bank_from_streams(
+        serde_style,
+        SnapshotStreams {
+            full_snapshot_stream: stream,
+            incremental_snapshot_stream: None,
+        },
+        account_paths,
+        unpacked_append_vec_map,
+        genesis_config,
+        frozen_account_pubkeys,
+        debug_keys,
+        additional_builtins,
+        account_indexes,
+        caching_enabled,
+        accounts_index_config,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn bank_from_streams<R>(
+    serde_style: SerdeStyle,
+    snapshot_streams: SnapshotStreams<R>,
+    account_paths: &[PathBuf],
+    unpacked_append_vec_map: UnpackedAppendVecMap,
+    genesis_config: &GenesisConfig,
+    frozen_account_pubkeys: &[Pubkey],
+    debug_keys: Option<Arc<HashSet<Pubkey>>>,
+    additional_builtins: Option<&Builtins>,
+    account_indexes: AccountSecondaryIndexes,
+    caching_enabled: bool,
+    accounts_index_config: Option<AccountsIndexConfig>,
 ) -> std::result::Result<Bank, Error>
 where
     R: Read,
 {
     macro_rules! INTO {
         ($x:ident) => {{
-            let (bank_fields, accounts_db_fields) = $x::deserialize_bank_fields(stream)?;
+            let (full_snapshot_bank_fields, full_snapshot_accounts_db_fields) =
+                $x::deserialize_bank_fields(snapshot_streams.full_snapshot_stream)?;
+            let (bank_fields, accounts_db_fields) =
+                match snapshot_streams.incremental_snapshot_stream {
+                    None => (full_snapshot_bank_fields, full_snapshot_accounts_db_fields),
+                    Some(incremental_snapshot_stream) => {
+                        let (incremental_snapshot_bank_fields, incremental_snapshot_accounts_db_fields) =
+                            $x::deserialize_bank_fields(incremental_snapshot_stream)?;
+                        (
+                            incremental_snapshot_bank_fields,
+                            merge_accounts_db_fields(
+                                full_snapshot_accounts_db_fields,
+                                incremental_snapshot_accounts_db_fields,
+                            )?,
+                        )
+                    }
+                };
 
             let bank = reconstruct_bank_from_fields(
                 bank_fields,
@@ -148,6 +208,7 @@ where
                 additional_builtins,
                 account_indexes,
                 caching_enabled,
+                accounts_index_config,
             )?;
             Ok(bank)
         }};
@@ -161,6 +222,36 @@ where
     })
 }
 
+/// Combine the storages of a full snapshot with those of an incremental snapshot taken on top of
+/// it.  The incremental snapshot only holds storages for slots newer than the full snapshot, and
+/// its bank hash info, write version and slot describe the resulting bank.
+fn merge_accounts_db_fields<E>(
+    full_snapshot_accounts_db_fields: AccountsDbFields<E>,
+    incremental_snapshot_accounts_db_fields: AccountsDbFields<E>,
+) -> Result<AccountsDbFields<E>, Error> {
+    let AccountsDbFields(mut storage, _, full_snapshot_slot, _) = full_snapshot_accounts_db_fields;
+    let AccountsDbFields(incremental_storage, version, slot, bank_hash_info) =
+        incremental_snapshot_accounts_db_fields;
+
+    if slot <= full_snapshot_slot {
+        return Err(Box::new(bincode::ErrorKind::Custom(format!(
+            "incremental snapshot slot {} is not newer than full snapshot slot {}",
+            slot, full_snapshot_slot
+        ))));
+    }
+    for (storage_slot, storage_entries) in incremental_storage {
+        if storage_slot <= full_snapshot_slot {
+            return Err(Box::new(bincode::ErrorKind::Custom(format!(
+                "incremental snapshot contains storage for slot {}, which is covered by full snapshot slot {}",
+                storage_slot, full_snapshot_slot
+            ))));
+        }
+        storage.insert(storage_slot, storage_entries);
+    }
+
+    Ok(AccountsDbFields(storage, version, slot, bank_hash_info))
+}
+
 pub(crate) fn bank_to_stream<W>(
     serde_style: SerdeStyle,
     stream: &mut BufWriter<W>,
@@ -235,8 +326,9 @@ fn reconstruct_bank_from_fields<E>(
     unpacked_append_vec_map: UnpackedAppendVecMap,
     debug_keys: Option<Arc<HashSet<Pubkey>>>,
     additional_builtins: Option<&Builtins>,
-    account_indexes: HashSet<AccountIndex>,
+    account_indexes: AccountSecondaryIndexes,
     caching_enabled: bool,
+    accounts_index_config: Option<AccountsIndexConfig>,
 ) -> Result<Bank, Error>
 where
     E: SerializableStorage,
@@ -248,6 +340,7 @@ where
         &genesis_config.cluster_type,
         account_indexes,
         caching_enabled,
+        accounts_index_config,
     )?;
     accounts_db.freeze_accounts(&bank_fields.ancestors, frozen_account_pubkeys);
 
@@ -268,8 +361,9 @@ fn reconstruct_accountsdb_from_fields<E>(
     account_paths: &[PathBuf],
     unpacked_append_vec_map: UnpackedAppendVecMap,
     cluster_type: &ClusterType,
-    account_indexes: HashSet<AccountIndex>,
+    account_indexes: AccountSecondaryIndexes,
     caching_enabled: bool,
+    accounts_index_config: Option<AccountsIndexConfig>,
 ) -> Result<AccountsDb, Error>
 where
     E: SerializableStorage,
@@ -279,6 +373,7 @@ where
         cluster_type,
         account_indexes,
         caching_enabled,
+        accounts_index_config,
     );
     let AccountsDbFields(storage, version, slot, bank_hash_info) = accounts_db_fields;
 
@@ -313,8 +408,11 @@ where
                     )
                 })?;
 
-                let (accounts, num_accounts) =
-                    AppendVec::new_from_file(append_vec_path, storage_entry.current_len())?;
+                let (accounts, num_accounts) = AccountsFile::new_from_file(
+                    append_vec_path,
+                    storage_entry.current_len(),
+                    &accounts_db.cold_storage_cache,
+                )?;
                 let u_storage_entry = AccountStorageEntry::new_existing(
                     slot,
                     storage_entry.id(),
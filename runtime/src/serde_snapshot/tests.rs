@@ -71,8 +71,9 @@ where
         account_paths,
         unpacked_append_vec_map,
         &ClusterType::Development,
-        HashSet::new(),
+        AccountSecondaryIndexes::default(),
         false,
+        None,
     )
 }
 
@@ -123,8 +124,13 @@ where
 fn test_accounts_serialize_style(serde_style: SerdeStyle) {
     solana_logger::setup();
     let (_accounts_dir, paths) = get_temp_accounts_paths(4).unwrap();
-    let accounts =
-        Accounts::new_with_config(paths, &ClusterType::Development, HashSet::new(), false);
+    let accounts = Accounts::new_with_config(
+        paths,
+        &ClusterType::Development,
+        AccountSecondaryIndexes::default(),
+        false,
+        None,
+    );
 
     let mut pubkeys: Vec<Pubkey> = vec![];
     create_test_accounts(&accounts, &mut pubkeys, 100, 0);
@@ -220,8 +226,9 @@ fn test_bank_serialize_style(serde_style: SerdeStyle) {
         &[],
         None,
         None,
-        HashSet::new(),
+        AccountSecondaryIndexes::default(),
         false,
+        None,
     )
     .unwrap();
     dbank.src = ref_sc;
@@ -273,6 +280,69 @@ fn test_accounts_serialize_newer() {
     test_accounts_serialize_style(SerdeStyle::Newer)
 }
 
+#[test]
+fn test_accounts_serialize_cold_storage() {
+    solana_logger::setup();
+    let (_accounts_dir, paths) = get_temp_accounts_paths(4).unwrap();
+    let accounts = Accounts::new_with_config(
+        paths,
+        &ClusterType::Development,
+        AccountSecondaryIndexes::default(),
+        false,
+        None,
+    );
+
+    let mut pubkeys: Vec<Pubkey> = vec![];
+    create_test_accounts(&accounts, &mut pubkeys, 100, 0);
+    accounts.add_root(0);
+    accounts.accounts_db.set_cold_storage_slot_age(0);
+    accounts.accounts_db.shrink_candidate_slots();
+    let is_cold = |accounts: &Accounts| {
+        accounts
+            .accounts_db
+            .storage
+            .0
+            .get(&0)
+            .unwrap()
+            .read()
+            .unwrap()
+            .values()
+            .all(|store| !store.accounts.is_appendable())
+    };
+    assert!(is_cold(&accounts));
+    check_accounts(&accounts, &pubkeys, 100);
+
+    let mut writer = Cursor::new(vec![]);
+    accountsdb_to_stream(
+        SerdeStyle::Newer,
+        &mut writer,
+        &*accounts.accounts_db,
+        0,
+        &accounts.accounts_db.get_snapshot_storages(0),
+    )
+    .unwrap();
+
+    let copied_accounts = TempDir::new().unwrap();
+    let unpacked_append_vec_map =
+        copy_append_vecs(&accounts.accounts_db, copied_accounts.path()).unwrap();
+
+    let buf = writer.into_inner();
+    let mut reader = BufReader::new(&buf[..]);
+    let (_accounts_dir, daccounts_paths) = get_temp_accounts_paths(2).unwrap();
+    let daccounts = Accounts::new_empty(
+        accountsdb_from_stream(
+            SerdeStyle::Newer,
+            &mut reader,
+            &daccounts_paths,
+            unpacked_append_vec_map,
+        )
+        .unwrap(),
+    );
+    assert!(is_cold(&daccounts));
+    check_accounts(&daccounts, &pubkeys, 100);
+    assert_eq!(accounts.bank_hash_at(0), daccounts.bank_hash_at(0));
+}
+
 #[test]
 fn test_bank_serialize_newer() {
     test_bank_serialize_style(SerdeStyle::Newer)
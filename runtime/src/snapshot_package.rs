@@ -13,6 +13,25 @@ pub type AccountsPackageSender = Sender<AccountsPackagePre>;
 pub type AccountsPackageReceiver = Receiver<AccountsPackagePre>;
 pub type AccountsPackageSendError = SendError<AccountsPackagePre>;
 
+/// The kind of snapshot archive an accounts package should be turned into
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SnapshotType {
+    /// All the accounts as of the package's slot
+    FullSnapshot,
+    /// Only the accounts that changed since the full snapshot at the given base slot
+    IncrementalSnapshot(Slot),
+}
+
+impl SnapshotType {
+    pub fn is_full_snapshot(&self) -> bool {
+        matches!(self, SnapshotType::FullSnapshot)
+    }
+
+    pub fn is_incremental_snapshot(&self) -> bool {
+        matches!(self, SnapshotType::IncrementalSnapshot(_))
+    }
+}
+
 #[derive(Debug)]
 pub struct AccountsPackagePre {
     pub slot: Slot,
@@ -26,6 +45,8 @@ pub struct AccountsPackagePre {
     pub snapshot_output_dir: PathBuf,
     pub expected_capitalization: u64,
     pub hash_for_testing: Option<Hash>,
+    // `None` if this package is only used to verify the accounts hash
+    pub snapshot_type: Option<SnapshotType>,
 }
 
 impl AccountsPackagePre {
@@ -42,6 +63,7 @@ impl AccountsPackagePre {
         snapshot_output_dir: PathBuf,
         expected_capitalization: u64,
         hash_for_testing: Option<Hash>,
+        snapshot_type: Option<SnapshotType>,
     ) -> Self {
         Self {
             slot,
@@ -55,6 +77,7 @@ impl AccountsPackagePre {
             snapshot_output_dir,
             expected_capitalization,
             hash_for_testing,
+            snapshot_type,
         }
     }
 }
@@ -69,9 +92,11 @@ pub struct AccountsPackage {
     pub hash: Hash,
     pub archive_format: ArchiveFormat,
     pub snapshot_version: SnapshotVersion,
+    pub snapshot_type: Option<SnapshotType>,
 }
 
 impl AccountsPackage {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         slot: Slot,
         block_height: u64,
@@ -82,6 +107,7 @@ impl AccountsPackage {
         hash: Hash,
         archive_format: ArchiveFormat,
         snapshot_version: SnapshotVersion,
+        snapshot_type: Option<SnapshotType>,
     ) -> Self {
         Self {
             slot,
@@ -93,6 +119,7 @@ impl AccountsPackage {
             hash,
             archive_format,
             snapshot_version,
+            snapshot_type,
         }
     }
 }
@@ -1,15 +1,17 @@
 use {
     crate::{
         accounts_db::AccountsDb,
-        accounts_index::AccountIndex,
+        accounts_index::{AccountSecondaryIndexes, AccountsIndexConfig},
         bank::{Bank, BankSlotDelta, Builtins},
         bank_forks::ArchiveFormat,
         hardened_unpack::{unpack_snapshot, UnpackError, UnpackedAppendVecMap},
         serde_snapshot::{
-            bank_from_stream, bank_to_stream, SerdeStyle, SnapshotStorage, SnapshotStorages,
+            bank_from_streams, bank_to_stream, SerdeStyle, SnapshotStorage, SnapshotStorages,
+            SnapshotStreams,
         },
         snapshot_package::{
             AccountsPackage, AccountsPackagePre, AccountsPackageSendError, AccountsPackageSender,
+            SnapshotType,
         },
     },
     bincode::{config::Options, serialize_into},
@@ -40,6 +42,7 @@ use {
 pub const SNAPSHOT_STATUS_CACHE_FILE_NAME: &str = "status_cache";
 
 pub const MAX_SNAPSHOTS: usize = 8; // Save some snapshots but not too many
+pub const MAX_INCREMENTAL_SNAPSHOT_ARCHIVES_TO_RETAIN: usize = 4;
 const MAX_SNAPSHOT_DATA_FILE_SIZE: u64 = 32 * 1024 * 1024 * 1024; // 32 GiB
 const VERSION_STRING_V1_2_0: &str = "1.2.0";
 const DEFAULT_SNAPSHOT_VERSION: SnapshotVersion = SnapshotVersion::V1_2_0;
@@ -140,6 +143,7 @@ impl Ord for SlotSnapshotPaths {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn package_snapshot<P: AsRef<Path>, Q: AsRef<Path>>(
     bank: &Bank,
     snapshot_files: &SlotSnapshotPaths,
@@ -150,6 +154,7 @@ pub fn package_snapshot<P: AsRef<Path>, Q: AsRef<Path>>(
     archive_format: ArchiveFormat,
     snapshot_version: SnapshotVersion,
     hash_for_testing: Option<Hash>,
+    snapshot_type: Option<SnapshotType>,
 ) -> Result<AccountsPackagePre> {
     // Hard link all the snapshots we need for this package
     let snapshot_tmpdir = tempfile::Builder::new()
@@ -187,6 +192,7 @@ pub fn package_snapshot<P: AsRef<Path>, Q: AsRef<Path>>(
         snapshot_package_output_path.as_ref().to_path_buf(),
         bank.capitalization(),
         hash_for_testing,
+        snapshot_type,
     );
 
     Ok(package)
@@ -592,31 +598,88 @@ pub fn bank_from_archive<P: AsRef<Path>>(
     genesis_config: &GenesisConfig,
     debug_keys: Option<Arc<HashSet<Pubkey>>>,
     additional_builtins: Option<&Builtins>,
-    account_indexes: HashSet<AccountIndex>,
+    account_indexes: AccountSecondaryIndexes,
+    accounts_db_caching_enabled: bool,
+    accounts_index_config: Option<AccountsIndexConfig>,
+) -> Result<Bank> {
+    bank_from_snapshot_archives(
+        account_paths,
+        frozen_account_pubkeys,
+        snapshot_path,
+        (snapshot_tar.as_ref(), archive_format),
+        None,
+        genesis_config,
+        debug_keys,
+        additional_builtins,
+        account_indexes,
+        accounts_db_caching_enabled,
+        accounts_index_config,
+    )
+}
+
+/// Rebuild a bank from a full snapshot archive and, optionally, an incremental snapshot archive
+/// that was taken on top of it
+#[allow(clippy::too_many_arguments)]
+pub fn bank_from_snapshot_archives(
+    account_paths: &[PathBuf],
+    frozen_account_pubkeys: &[Pubkey],
+    snapshot_path: &Path,
+    full_snapshot_archive: (&Path, ArchiveFormat),
+    incremental_snapshot_archive: Option<(&Path, ArchiveFormat)>,
+    genesis_config: &GenesisConfig,
+    debug_keys: Option<Arc<HashSet<Pubkey>>>,
+    additional_builtins: Option<&Builtins>,
+    account_indexes: AccountSecondaryIndexes,
     accounts_db_caching_enabled: bool,
+    accounts_index_config: Option<AccountsIndexConfig>,
 ) -> Result<Bank> {
-    let unpack_dir = tempfile::Builder::new()
+    let full_snapshot_unpack_dir = tempfile::Builder::new()
         .prefix(TMP_SNAPSHOT_PREFIX)
         .tempdir_in(snapshot_path)?;
-
-    let unpacked_append_vec_map = untar_snapshot_in(
-        &snapshot_tar,
-        &unpack_dir.as_ref(),
+    let mut unpacked_append_vec_map = untar_snapshot_in(
+        full_snapshot_archive.0,
+        &full_snapshot_unpack_dir.as_ref(),
         account_paths,
-        archive_format,
+        full_snapshot_archive.1,
     )?;
 
-    let mut measure = Measure::start("bank rebuild from snapshot");
-    let unpacked_snapshots_dir = unpack_dir.as_ref().join("snapshots");
-    let unpacked_version_file = unpack_dir.as_ref().join("version");
+    let incremental_snapshot_unpack_dir = match incremental_snapshot_archive {
+        None => None,
+        Some((incremental_snapshot_tar, incremental_archive_format)) => {
+            let unpack_dir = tempfile::Builder::new()
+                .prefix(TMP_SNAPSHOT_PREFIX)
+                .tempdir_in(snapshot_path)?;
+            unpacked_append_vec_map.extend(untar_snapshot_in(
+                incremental_snapshot_tar,
+                &unpack_dir.as_ref(),
+                account_paths,
+                incremental_archive_format,
+            )?);
+            Some(unpack_dir)
+        }
+    };
 
-    let mut snapshot_version = String::new();
-    File::open(unpacked_version_file).and_then(|mut f| f.read_to_string(&mut snapshot_version))?;
+    let mut measure = Measure::start("bank rebuild from snapshot");
+    let full_snapshot_version = read_unpacked_snapshot_version(&full_snapshot_unpack_dir)?;
+    let incremental_snapshot = incremental_snapshot_unpack_dir
+        .as_ref()
+        .map(|unpack_dir| -> Result<_> {
+            Ok((
+                read_unpacked_snapshot_version(unpack_dir)?,
+                unpack_dir.as_ref().join("snapshots"),
+            ))
+        })
+        .transpose()?;
 
     let bank = rebuild_bank_from_snapshots(
-        snapshot_version.trim(),
+        (
+            full_snapshot_version.trim(),
+            &full_snapshot_unpack_dir.as_ref().join("snapshots"),
+        ),
+        incremental_snapshot
+            .as_ref()
+            .map(|(version, snapshots_dir)| (version.trim(), snapshots_dir.as_path())),
         frozen_account_pubkeys,
-        &unpacked_snapshots_dir,
         account_paths,
         unpacked_append_vec_map,
         genesis_config,
@@ -624,6 +687,7 @@ pub fn bank_from_archive<P: AsRef<Path>>(
         additional_builtins,
         account_indexes,
         accounts_db_caching_enabled,
+        accounts_index_config,
     )?;
 
     if !bank.verify_snapshot_bank() {
@@ -635,6 +699,13 @@ pub fn bank_from_archive<P: AsRef<Path>>(
     Ok(bank)
 }
 
+fn read_unpacked_snapshot_version<P: AsRef<Path>>(unpack_dir: P) -> Result<String> {
+    let mut snapshot_version = String::new();
+    File::open(unpack_dir.as_ref().join("version"))
+        .and_then(|mut f| f.read_to_string(&mut snapshot_version))?;
+    Ok(snapshot_version)
+}
+
 pub fn get_snapshot_archive_path(
     snapshot_output_dir: PathBuf,
     snapshot_hash: &(Slot, Hash),
@@ -658,9 +729,24 @@ fn archive_format_from_str(archive_format: &str) -> Option<ArchiveFormat> {
     }
 }
 
+pub fn get_incremental_snapshot_archive_path(
+    snapshot_output_dir: PathBuf,
+    full_snapshot_slot: Slot,
+    snapshot_hash: &(Slot, Hash),
+    archive_format: ArchiveFormat,
+) -> PathBuf {
+    snapshot_output_dir.join(format!(
+        "incremental-snapshot-{}-{}-{}{}",
+        full_snapshot_slot,
+        snapshot_hash.0,
+        snapshot_hash.1,
+        get_archive_ext(archive_format),
+    ))
+}
+
 fn snapshot_hash_of(archive_filename: &str) -> Option<(Slot, Hash, ArchiveFormat)> {
     let snapshot_filename_regex =
-        Regex::new(r"snapshot-(\d+)-([[:alnum:]]+)\.(tar|tar\.bz2|tar\.zst|tar\.gz)$").unwrap();
+        Regex::new(r"^snapshot-(\d+)-([[:alnum:]]+)\.(tar|tar\.bz2|tar\.zst|tar\.gz)$").unwrap();
 
     if let Some(captures) = snapshot_filename_regex.captures(archive_filename) {
         let slot_str = captures.get(1).unwrap().as_str();
@@ -678,37 +764,82 @@ fn snapshot_hash_of(archive_filename: &str) -> Option<(Slot, Hash, ArchiveFormat
     None
 }
 
-pub fn get_snapshot_archives<P: AsRef<Path>>(
-    snapshot_output_dir: P,
-) -> Vec<(PathBuf, (Slot, Hash, ArchiveFormat))> {
+/// Parse an incremental snapshot archive file name into its full snapshot (base) slot, slot,
+/// accounts hash and archive format
+pub fn incremental_snapshot_hash_of(
+    archive_filename: &str,
+) -> Option<(Slot, Slot, Hash, ArchiveFormat)> {
+    let incremental_snapshot_filename_regex = Regex::new(
+        r"^incremental-snapshot-(\d+)-(\d+)-([[:alnum:]]+)\.(tar|tar\.bz2|tar\.zst|tar\.gz)$",
+    )
+    .unwrap();
+
+    if let Some(captures) = incremental_snapshot_filename_regex.captures(archive_filename) {
+        let full_snapshot_slot_str = captures.get(1).unwrap().as_str();
+        let slot_str = captures.get(2).unwrap().as_str();
+        let hash_str = captures.get(3).unwrap().as_str();
+        let ext = captures.get(4).unwrap().as_str();
+
+        if let (Ok(full_snapshot_slot), Ok(slot), Ok(hash), Some(archive_format)) = (
+            full_snapshot_slot_str.parse::<Slot>(),
+            slot_str.parse::<Slot>(),
+            hash_str.parse::<Hash>(),
+            archive_format_from_str(ext),
+        ) {
+            if full_snapshot_slot < slot {
+                return Some((full_snapshot_slot, slot, hash, archive_format));
+            }
+        }
+    }
+    None
+}
+
+fn get_archives<P, T, F>(snapshot_output_dir: P, parse: F) -> Vec<(PathBuf, T)>
+where
+    P: AsRef<Path>,
+    F: Fn(&str) -> Option<T>,
+{
     match fs::read_dir(&snapshot_output_dir) {
         Err(err) => {
             info!("Unable to read snapshot directory: {}", err);
             vec![]
         }
-        Ok(files) => {
-            let mut archives: Vec<_> = files
-                .filter_map(|entry| {
-                    if let Ok(entry) = entry {
-                        let path = entry.path();
-                        if path.is_file() {
-                            if let Some(snapshot_hash) =
-                                snapshot_hash_of(path.file_name().unwrap().to_str().unwrap())
-                            {
-                                return Some((path, snapshot_hash));
-                            }
+        Ok(files) => files
+            .filter_map(|entry| {
+                if let Ok(entry) = entry {
+                    let path = entry.path();
+                    if path.is_file() {
+                        if let Some(snapshot_hash) =
+                            parse(path.file_name().unwrap().to_str().unwrap())
+                        {
+                            return Some((path, snapshot_hash));
                         }
                     }
-                    None
-                })
-                .collect();
-
-            archives.sort_by(|a, b| (b.1).0.cmp(&(a.1).0)); // reverse sort by slot
-            archives
-        }
+                }
+                None
+            })
+            .collect(),
     }
 }
 
+pub fn get_snapshot_archives<P: AsRef<Path>>(
+    snapshot_output_dir: P,
+) -> Vec<(PathBuf, (Slot, Hash, ArchiveFormat))> {
+    let mut archives = get_archives(snapshot_output_dir, snapshot_hash_of);
+    archives.sort_by(|a, b| (b.1).0.cmp(&(a.1).0)); // reverse sort by slot
+    archives
+}
+
+/// Returns the incremental snapshot archives in `snapshot_output_dir` as
+/// `(path, (full_snapshot_slot, slot, hash, archive_format))`, reverse sorted by slot
+pub fn get_incremental_snapshot_archives<P: AsRef<Path>>(
+    snapshot_output_dir: P,
+) -> Vec<(PathBuf, (Slot, Slot, Hash, ArchiveFormat))> {
+    let mut archives = get_archives(snapshot_output_dir, incremental_snapshot_hash_of);
+    archives.sort_by(|a, b| (b.1).1.cmp(&(a.1).1)); // reverse sort by slot
+    archives
+}
+
 pub fn get_highest_snapshot_archive_path<P: AsRef<Path>>(
     snapshot_output_dir: P,
 ) -> Option<(PathBuf, (Slot, Hash, ArchiveFormat))> {
@@ -716,14 +847,44 @@ pub fn get_highest_snapshot_archive_path<P: AsRef<Path>>(
     archives.into_iter().next()
 }
 
+/// Returns the highest incremental snapshot archive that was taken on top of the full snapshot
+/// at `full_snapshot_slot`
+pub fn get_highest_incremental_snapshot_archive_path<P: AsRef<Path>>(
+    snapshot_output_dir: P,
+    full_snapshot_slot: Slot,
+) -> Option<(PathBuf, (Slot, Slot, Hash, ArchiveFormat))> {
+    get_incremental_snapshot_archives(snapshot_output_dir)
+        .into_iter()
+        .find(|(_path, (base_slot, _slot, _hash, _archive_format))| {
+            *base_slot == full_snapshot_slot
+        })
+}
+
 pub fn purge_old_snapshot_archives<P: AsRef<Path>>(snapshot_output_dir: P) {
-    let mut archives = get_snapshot_archives(snapshot_output_dir);
+    let mut archives = get_snapshot_archives(&snapshot_output_dir);
+    let highest_full_snapshot_slot = archives.first().map(|(_path, (slot, _, _))| *slot);
     // Keep the oldest snapshot so we can always play the ledger from it.
     archives.pop();
     for old_archive in archives.into_iter().skip(2) {
         fs::remove_file(old_archive.0)
             .unwrap_or_else(|err| info!("Failed to remove old snapshot: {:}", err));
     }
+
+    // Incremental snapshots are only useful on top of the highest full snapshot
+    let mut num_retained_incremental_snapshot_archives = 0;
+    for (path, (full_snapshot_slot, _slot, _hash, _archive_format)) in
+        get_incremental_snapshot_archives(&snapshot_output_dir)
+    {
+        if Some(full_snapshot_slot) == highest_full_snapshot_slot
+            && num_retained_incremental_snapshot_archives
+                < MAX_INCREMENTAL_SNAPSHOT_ARCHIVES_TO_RETAIN
+        {
+            num_retained_incremental_snapshot_archives += 1;
+            continue;
+        }
+        fs::remove_file(path)
+            .unwrap_or_else(|err| info!("Failed to remove old incremental snapshot: {:}", err));
+    }
 }
 
 fn untar_snapshot_in<P: AsRef<Path>>(
@@ -761,19 +922,10 @@ fn untar_snapshot_in<P: AsRef<Path>>(
     Ok(account_paths_map)
 }
 
-#[allow(clippy::too_many_arguments)]
-fn rebuild_bank_from_snapshots(
+fn get_unpacked_snapshot_paths(
     snapshot_version: &str,
-    frozen_account_pubkeys: &[Pubkey],
     unpacked_snapshots_dir: &Path,
-    account_paths: &[PathBuf],
-    unpacked_append_vec_map: UnpackedAppendVecMap,
-    genesis_config: &GenesisConfig,
-    debug_keys: Option<Arc<HashSet<Pubkey>>>,
-    additional_builtins: Option<&Builtins>,
-    account_indexes: HashSet<AccountIndex>,
-    accounts_db_caching_enabled: bool,
-) -> Result<Bank> {
+) -> Result<(SnapshotVersion, SlotSnapshotPaths)> {
     info!("snapshot version: {}", snapshot_version);
 
     let snapshot_version_enum =
@@ -790,29 +942,93 @@ fn rebuild_bank_from_snapshots(
     let root_paths = snapshot_paths
         .pop()
         .ok_or_else(|| get_io_error("No snapshots found in snapshots directory"))?;
+    Ok((snapshot_version_enum, root_paths))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn rebuild_bank_from_snapshots(
+    full_snapshot: (&str, &Path),
+    incremental_snapshot: Option<(&str, &Path)>,
+    frozen_account_pubkeys: &[Pubkey],
+    account_paths: &[PathBuf],
+    unpacked_append_vec_map: UnpackedAppendVecMap,
+    genesis_config: &GenesisConfig,
+    debug_keys: Option<Arc<HashSet<Pubkey>>>,
+    additional_builtins: Option<&Builtins>,
+    account_indexes: AccountSecondaryIndexes,
+    accounts_db_caching_enabled: bool,
+    accounts_index_config: Option<AccountsIndexConfig>,
+) -> Result<Bank> {
+    let (full_snapshot_version, full_snapshot_root_paths) =
+        get_unpacked_snapshot_paths(full_snapshot.0, full_snapshot.1)?;
+    let incremental_snapshot_root_paths = incremental_snapshot
+        .map(|(snapshot_version, unpacked_snapshots_dir)| {
+            let (incremental_snapshot_version, root_paths) =
+                get_unpacked_snapshot_paths(snapshot_version, unpacked_snapshots_dir)?;
+            if incremental_snapshot_version != full_snapshot_version {
+                return Err(get_io_error(&format!(
+                    "incremental snapshot version {} does not match full snapshot version {}",
+                    incremental_snapshot_version, full_snapshot_version
+                )));
+            }
+            Ok(root_paths)
+        })
+        .transpose()?;
 
     info!(
-        "Loading bank from {}",
-        &root_paths.snapshot_file_path.display()
+        "Loading bank from full snapshot {} and incremental snapshot {:?}",
+        full_snapshot_root_paths.snapshot_file_path.display(),
+        incremental_snapshot_root_paths
+            .as_ref()
+            .map(|root_paths| root_paths.snapshot_file_path.display()),
     );
-    let bank = deserialize_snapshot_data_file(&root_paths.snapshot_file_path, |mut stream| {
-        Ok(match snapshot_version_enum {
-            SnapshotVersion::V1_2_0 => bank_from_stream(
-                SerdeStyle::Newer,
-                &mut stream,
-                account_paths,
-                unpacked_append_vec_map,
-                genesis_config,
-                frozen_account_pubkeys,
-                debug_keys,
-                additional_builtins,
-                account_indexes,
-                accounts_db_caching_enabled,
-            ),
-        }?)
-    })?;
+    let bank = deserialize_snapshot_data_file(
+        &full_snapshot_root_paths.snapshot_file_path,
+        |full_snapshot_stream| {
+            let deserialize =
+                |incremental_snapshot_stream: Option<&mut BufReader<File>>| -> Result<Bank> {
+                    let snapshot_streams = SnapshotStreams {
+                        full_snapshot_stream,
+                        incremental_snapshot_stream,
+                    };
+                    Ok(match full_snapshot_version {
+                        SnapshotVersion::V1_2_0 => bank_from_streams(
+                            SerdeStyle::Newer,
+                            snapshot_streams,
+                            account_paths,
+                            unpacked_append_vec_map,
+                            genesis_config,
+                            frozen_account_pubkeys,
+                            debug_keys,
+                            additional_builtins,
+                            account_indexes,
+                            accounts_db_caching_enabled,
+                            accounts_index_config,
+                        ),
+                    }?)
+                };
+            match &incremental_snapshot_root_paths {
+                None => deserialize(None),
+                Some(root_paths) => deserialize_snapshot_data_file(
+                    &root_paths.snapshot_file_path,
+                    |incremental_snapshot_stream| deserialize(Some(incremental_snapshot_stream)),
+                ),
+            }
+        },
+    )?;
 
-    let status_cache_path = unpacked_snapshots_dir.join(SNAPSHOT_STATUS_CACHE_FILE_NAME);
+    // Keep the zero-lamport accounts stored after the full snapshot when verifying the bank, so
+    // that later incremental snapshots on top of the same full snapshot still record them
+    bank.rc
+        .accounts
+        .accounts_db
+        .set_last_full_snapshot_slot(full_snapshot_root_paths.slot);
+
+    // The status cache of the incremental snapshot supersedes that of the full snapshot
+    let status_cache_path = incremental_snapshot
+        .map(|(_, unpacked_snapshots_dir)| unpacked_snapshots_dir)
+        .unwrap_or(full_snapshot.1)
+        .join(SNAPSHOT_STATUS_CACHE_FILE_NAME);
     let slot_deltas = deserialize_snapshot_data_file(&status_cache_path, |stream| {
         info!(
             "Rebuilding status cache from {}",
@@ -896,8 +1112,14 @@ pub fn snapshot_bank(
     snapshot_version: SnapshotVersion,
     archive_format: &ArchiveFormat,
     hash_for_testing: Option<Hash>,
+    snapshot_type: Option<SnapshotType>,
 ) -> Result<()> {
-    let storages: Vec<_> = root_bank.get_snapshot_storages();
+    let storages = match snapshot_type {
+        Some(SnapshotType::IncrementalSnapshot(full_snapshot_slot)) => {
+            root_bank.get_incremental_snapshot_storages(full_snapshot_slot)
+        }
+        _ => root_bank.get_snapshot_storages(),
+    };
     let mut add_snapshot_time = Measure::start("add-snapshot-ms");
     add_snapshot(snapshot_path, &root_bank, &storages, snapshot_version)?;
     add_snapshot_time.stop();
@@ -919,6 +1141,7 @@ pub fn snapshot_bank(
         *archive_format,
         snapshot_version,
         hash_for_testing,
+        snapshot_type,
     )?;
 
     accounts_package_sender.send(package)?;
@@ -935,6 +1158,62 @@ pub fn bank_to_snapshot_archive<P: AsRef<Path>, Q: AsRef<Path>>(
     snapshot_package_output_path: Q,
     archive_format: ArchiveFormat,
     thread_pool: Option<&ThreadPool>,
+) -> Result<PathBuf> {
+    bank_to_archive(
+        snapshot_path,
+        bank,
+        snapshot_version,
+        snapshot_package_output_path,
+        archive_format,
+        thread_pool,
+        SnapshotType::FullSnapshot,
+    )
+}
+
+/// Convenience function to create an incremental snapshot archive out of any Bank, regardless of
+/// state.  The archive only holds the accounts stored after `full_snapshot_slot`, so it can only
+/// be loaded on top of the full snapshot archive for that slot.  The Bank will be frozen during
+/// the process.
+pub fn bank_to_incremental_snapshot_archive<P: AsRef<Path>, Q: AsRef<Path>>(
+    snapshot_path: P,
+    bank: &Bank,
+    full_snapshot_slot: Slot,
+    snapshot_version: Option<SnapshotVersion>,
+    snapshot_package_output_path: Q,
+    archive_format: ArchiveFormat,
+    thread_pool: Option<&ThreadPool>,
+) -> Result<PathBuf> {
+    if bank.slot() <= full_snapshot_slot {
+        return Err(get_io_error(&format!(
+            "incremental snapshot slot {} must be newer than full snapshot slot {}",
+            bank.slot(),
+            full_snapshot_slot
+        )));
+    }
+    // Zero-lamport accounts stored after the full snapshot must survive the clean below
+    bank.rc
+        .accounts
+        .accounts_db
+        .set_last_full_snapshot_slot(full_snapshot_slot);
+    bank_to_archive(
+        snapshot_path,
+        bank,
+        snapshot_version,
+        snapshot_package_output_path,
+        archive_format,
+        thread_pool,
+        SnapshotType::IncrementalSnapshot(full_snapshot_slot),
+    )
+}
+
+fn bank_to_archive<P: AsRef<Path>, Q: AsRef<Path>>(
+    snapshot_path: P,
+    bank: &Bank,
+    snapshot_version: Option<SnapshotVersion>,
+    snapshot_package_output_path: Q,
+    archive_format: ArchiveFormat,
+    thread_pool: Option<&ThreadPool>,
+    snapshot_type: SnapshotType,
 ) -> Result<PathBuf> {
     let snapshot_version = snapshot_version.unwrap_or_default();
 
@@ -947,7 +1226,12 @@ pub fn bank_to_snapshot_archive<P: AsRef<Path>, Q: AsRef<Path>>(
 
     let temp_dir = tempfile::tempdir_in(snapshot_path)?;
 
-    let storages: Vec<_> = bank.get_snapshot_storages();
+    let storages = match snapshot_type {
+        SnapshotType::FullSnapshot => bank.get_snapshot_storages(),
+        SnapshotType::IncrementalSnapshot(full_snapshot_slot) => {
+            bank.get_incremental_snapshot_storages(full_snapshot_slot)
+        }
+    };
     let slot_snapshot_paths = add_snapshot(&temp_dir, &bank, &storages, snapshot_version)?;
     let package = package_snapshot(
         &bank,
@@ -959,6 +1243,7 @@ pub fn bank_to_snapshot_archive<P: AsRef<Path>, Q: AsRef<Path>>(
         archive_format,
         snapshot_version,
         None,
+        Some(snapshot_type),
     )?;
 
     let package = process_accounts_package_pre(package, thread_pool);
@@ -974,7 +1259,15 @@ pub fn process_accounts_package_pre(
     let mut time = Measure::start("hash");
 
     let hash = accounts_package.hash; // temporarily remaining here
-    if let Some(expected_hash) = accounts_package.hash_for_testing {
+    let is_incremental_snapshot = accounts_package
+        .snapshot_type
+        .map(|snapshot_type| snapshot_type.is_incremental_snapshot())
+        .unwrap_or(false);
+    // An incremental snapshot's storages only cover part of the accounts, so they can't be used
+    // to recalculate the full accounts hash
+    if let (Some(expected_hash), false) =
+        (accounts_package.hash_for_testing, is_incremental_snapshot)
+    {
         let (hash, lamports) = AccountsDb::calculate_accounts_hash_without_index(
             &accounts_package.storages,
             thread_pool,
@@ -991,11 +1284,21 @@ pub fn process_accounts_package_pre(
         ("calculate_hash", time.as_us(), i64),
     );
 
-    let tar_output_file = get_snapshot_archive_path(
-        accounts_package.snapshot_output_dir,
-        &(accounts_package.slot, hash),
-        accounts_package.archive_format,
-    );
+    let tar_output_file = match accounts_package.snapshot_type {
+        Some(SnapshotType::IncrementalSnapshot(full_snapshot_slot)) => {
+            get_incremental_snapshot_archive_path(
+                accounts_package.snapshot_output_dir,
+                full_snapshot_slot,
+                &(accounts_package.slot, hash),
+                accounts_package.archive_format,
+            )
+        }
+        _ => get_snapshot_archive_path(
+            accounts_package.snapshot_output_dir,
+            &(accounts_package.slot, hash),
+            accounts_package.archive_format,
+        ),
+    };
 
     AccountsPackage::new(
         accounts_package.slot,
@@ -1007,6 +1310,7 @@ pub fn process_accounts_package_pre(
         hash,
         accounts_package.archive_format,
         accounts_package.snapshot_version,
+        accounts_package.snapshot_type,
     )
 }
 
@@ -1138,5 +1442,39 @@ mod tests {
         );
 
         assert!(snapshot_hash_of("invalid").is_none());
+        assert!(snapshot_hash_of(&format!(
+            "incremental-snapshot-42-43-{}.tar.zst",
+            Hash::default()
+        ))
+        .is_none());
+    }
+
+    #[test]
+    fn test_incremental_snapshot_hash_of() {
+        assert_eq!(
+            incremental_snapshot_hash_of(&format!(
+                "incremental-snapshot-42-123-{}.tar.bz2",
+                Hash::default()
+            )),
+            Some((42, 123, Hash::default(), ArchiveFormat::TarBzip2))
+        );
+        assert_eq!(
+            incremental_snapshot_hash_of(&format!(
+                "incremental-snapshot-43-234-{}.tar.zst",
+                Hash::default()
+            )),
+            Some((43, 234, Hash::default(), ArchiveFormat::TarZstd))
+        );
+
+        // The incremental snapshot must be newer than its full snapshot
+        assert!(incremental_snapshot_hash_of(&format!(
+            "incremental-snapshot-42-42-{}.tar",
+            Hash::default()
+        ))
+        .is_none());
+        assert!(
+            incremental_snapshot_hash_of(&format!("snapshot-42-{}.tar", Hash::default())).is_none()
+        );
+        assert!(incremental_snapshot_hash_of("invalid").is_none());
     }
 }
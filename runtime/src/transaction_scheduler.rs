@@ -0,0 +1,200 @@
+//! Schedules transactions by the accounts they lock. Two transactions conflict when one of them
+//! write-locks an account the other one locks, and only transactions which conflict need to be
+//! executed in order. The rest can be executed in parallel without changing any results.
+use solana_sdk::{pubkey::Pubkey, sanitize::Sanitize, transaction::Transaction};
+use std::collections::HashMap;
+
+/// Transactions which are executed in parallel are split into chunks of at least this many
+/// transactions, as executing a chunk has a fixed cost
+pub const MIN_TRANSACTIONS_PER_CHUNK: usize = 4;
+
+/// The size of the chunks to split `num_transactions` transactions into, to execute them on
+/// `num_threads` threads
+pub fn chunk_size(num_transactions: usize, num_threads: usize) -> usize {
+    let num_threads = num_threads.max(1);
+    ((num_transactions + num_threads - 1) / num_threads).max(MIN_TRANSACTIONS_PER_CHUNK)
+}
+
+/// The waves a transaction locking an account last read or wrote it in
+#[derive(Default)]
+struct AccountWaves {
+    last_write: Option<usize>,
+    last_read: Option<usize>,
+}
+
+/// The levels of the conflict graph of a list of transactions, in which every transaction
+/// depends on the earlier transactions it conflicts with.
+///
+/// Each wave holds the transactions all of whose dependencies are in earlier waves, so the
+/// transactions of a wave don't conflict with each other. Executing the waves one after another
+/// gives the same results as executing the transactions in order.
+#[derive(Debug, Default, PartialEq)]
+pub struct TransactionSchedule {
+    waves: Vec<Vec<usize>>,
+}
+
+impl TransactionSchedule {
+    pub fn new<'a, I>(txs: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Transaction>,
+    {
+        let mut account_waves: HashMap<&Pubkey, AccountWaves> = HashMap::new();
+        let mut waves: Vec<Vec<usize>> = vec![];
+        for (index, tx) in txs.into_iter().enumerate() {
+            // Transactions which fail to sanitize can't lock any accounts, and won't be executed
+            let (writable_keys, readonly_keys) = if tx.sanitize().is_ok() {
+                tx.message.get_account_keys_by_lock_type()
+            } else {
+                (vec![], vec![])
+            };
+
+            // A write has to follow every earlier lock of the account, a read only the writes
+            let after = |wave: Option<usize>| wave.map_or(0, |wave| wave + 1);
+            let writes_wave = writable_keys.iter().map(|key| {
+                account_waves.get(key).map_or(0, |account| {
+                    after(account.last_write).max(after(account.last_read))
+                })
+            });
+            let reads_wave = readonly_keys.iter().map(|key| {
+                account_waves
+                    .get(key)
+                    .map_or(0, |account| after(account.last_write))
+            });
+            let wave = writes_wave.chain(reads_wave).max().unwrap_or(0);
+
+            for key in writable_keys {
+                account_waves.entry(key).or_default().last_write = Some(wave);
+            }
+            for key in readonly_keys {
+                let account = account_waves.entry(key).or_default();
+                account.last_read = account.last_read.max(Some(wave));
+            }
+            if wave == waves.len() {
+                waves.push(vec![]);
+            }
+            waves[wave].push(index);
+        }
+        Self { waves }
+    }
+
+    /// The indexes of the transactions in each wave, in increasing order
+    pub fn waves(&self) -> &[Vec<usize>] {
+        &self.waves
+    }
+
+    pub fn num_waves(&self) -> usize {
+        self.waves.len()
+    }
+
+    /// Moves `items`, one for each scheduled transaction, into the order of the waves. The
+    /// transactions of each wave are then next to each other, and the ones which conflict are
+    /// still in their original order.
+    pub fn reorder<T>(&self, items: Vec<T>) -> Vec<T> {
+        let mut items: Vec<_> = items.into_iter().map(Some).collect();
+        self.waves
+            .iter()
+            .flatten()
+            .map(|index| items[*index].take().unwrap())
+            .collect()
+    }
+
+    /// The index in the reordered transactions at which each wave ends
+    pub fn wave_ends(&self) -> Vec<usize> {
+        self.waves
+            .iter()
+            .scan(0, |end, wave| {
+                *end += wave.len();
+                Some(*end)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        message::Message,
+        signature::{Keypair, Signer},
+    };
+
+    fn create_transaction(
+        payer: &Keypair,
+        writable: &[Pubkey],
+        readonly: &[Pubkey],
+    ) -> Transaction {
+        let accounts = writable
+            .iter()
+            .map(|key| AccountMeta::new(*key, false))
+            .chain(
+                readonly
+                    .iter()
+                    .map(|key| AccountMeta::new_readonly(*key, false)),
+            )
+            .collect();
+        let instruction = Instruction::new_with_bincode(Pubkey::new_unique(), &0, accounts);
+        let message = Message::new(&[instruction], Some(&payer.pubkey()));
+        Transaction::new(&[payer], message, Hash::default())
+    }
+
+    #[test]
+    fn test_transaction_schedule() {
+        let payers: Vec<_> = (0..6).map(|_| Keypair::new()).collect();
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        let c = Pubkey::new_unique();
+
+        let txs = vec![
+            // Reads of an account don't conflict
+            create_transaction(&payers[0], &[], &[a]),
+            create_transaction(&payers[1], &[b], &[a]),
+            // A write waits for the reads before it
+            create_transaction(&payers[2], &[a], &[]),
+            // A read waits for the write before it
+            create_transaction(&payers[3], &[c], &[b]),
+            // Nothing in common with anything else
+            create_transaction(&payers[4], &[], &[]),
+            // The same fee payer is written by both
+            create_transaction(&payers[0], &[], &[c]),
+        ];
+        let schedule = TransactionSchedule::new(&txs);
+        assert_eq!(schedule.waves(), &[vec![0, 1, 4], vec![2, 3], vec![5]][..]);
+        assert_eq!(schedule.num_waves(), 3);
+        assert_eq!(schedule.wave_ends(), vec![3, 5, 6]);
+        assert_eq!(
+            schedule.reorder((0..txs.len()).collect()),
+            vec![0, 1, 4, 2, 3, 5]
+        );
+
+        assert_eq!(
+            TransactionSchedule::new(&[]),
+            TransactionSchedule::default()
+        );
+    }
+
+    #[test]
+    fn test_transaction_schedule_chain() {
+        let payer = Keypair::new();
+        let txs: Vec<_> = (0..4)
+            .map(|_| create_transaction(&payer, &[], &[]))
+            .collect();
+        let schedule = TransactionSchedule::new(&txs);
+        assert_eq!(schedule.num_waves(), txs.len());
+        assert!(schedule
+            .waves()
+            .iter()
+            .enumerate()
+            .all(|(wave, indexes)| indexes == &[wave]));
+    }
+
+    #[test]
+    fn test_chunk_size() {
+        assert_eq!(chunk_size(0, 4), MIN_TRANSACTIONS_PER_CHUNK);
+        assert_eq!(chunk_size(100, 4), 25);
+        assert_eq!(chunk_size(101, 4), 26);
+        assert_eq!(chunk_size(10, 4), MIN_TRANSACTIONS_PER_CHUNK);
+        assert_eq!(chunk_size(100, 0), 100);
+    }
+}
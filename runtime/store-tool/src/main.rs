@@ -1,6 +1,7 @@
 use clap::{crate_description, crate_name, value_t_or_exit, App, Arg};
 use log::*;
-use solana_runtime::append_vec::AppendVec;
+use solana_runtime::{accounts_file::AccountsFile, cold_storage::ColdStorageCache};
+use std::sync::Arc;
 
 fn main() {
     solana_logger::setup_with_default("solana=info");
@@ -25,10 +26,17 @@ fn main() {
 
     let file = value_t_or_exit!(matches, "file", String);
     let len = value_t_or_exit!(matches, "len", usize);
-    let (mut store, num_accounts) = AppendVec::new_from_file(file, len).expect("should succeed");
+    let cold_storage_cache = Arc::new(ColdStorageCache::default());
+    let (mut store, num_accounts) =
+        AccountsFile::new_from_file(file, len, &cold_storage_cache).expect("should succeed");
     store.set_no_remove_on_drop();
+    let format = match store {
+        AccountsFile::AppendVec(_) => "append vec",
+        AccountsFile::Cold(_) => "cold storage",
+    };
     info!(
-        "store: len: {} capacity: {} accounts: {}",
+        "store: format: {} len: {} capacity: {} accounts: {}",
+        format,
         store.len(),
         store.capacity(),
         num_accounts,
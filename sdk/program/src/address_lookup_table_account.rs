@@ -0,0 +1,12 @@
+//! The contents of an on-chain address lookup table, used to compile versioned messages
+
+use crate::pubkey::Pubkey;
+
+/// The addresses stored in an address lookup table account
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AddressLookupTableAccount {
+    /// Address of the lookup table account
+    pub key: Pubkey,
+    /// Addresses stored in the table, in order
+    pub addresses: Vec<Pubkey>,
+}
@@ -0,0 +1,221 @@
+//! Instructions for the native compute budget program
+//!
+//! A transaction may include these instructions to request a compute unit limit other than the
+//! cluster default, and to pay a per-unit price on top of the signature fee in order to be
+//! prioritized by the leader.
+
+use crate::{
+    instruction::{Instruction, InstructionError},
+    message::Message,
+};
+use thiserror::Error;
+
+crate::declare_id!("ComputeBudget111111111111111111111111111111");
+
+/// Maximum number of compute units a transaction may request
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Number of compute units a transaction is given when it does not request a limit
+pub const DEFAULT_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
+/// Compute unit prices are denominated in micro-lamports
+pub const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, AbiExample, AbiEnumVisitor)]
+pub enum ComputeBudgetInstruction {
+    /// Request a specific maximum number of compute units the transaction is allowed to consume
+    SetComputeUnitLimit(u32),
+    /// Set a compute unit price in micro-lamports to pay a higher transaction fee for higher
+    /// transaction prioritization
+    SetComputeUnitPrice(u64),
+}
+
+/// Create a `ComputeBudgetInstruction::SetComputeUnitLimit` instruction
+pub fn set_compute_unit_limit(units: u32) -> Instruction {
+    Instruction::new_with_bincode(
+        id(),
+        &ComputeBudgetInstruction::SetComputeUnitLimit(units),
+        vec![],
+    )
+}
+
+/// Create a `ComputeBudgetInstruction::SetComputeUnitPrice` instruction
+pub fn set_compute_unit_price(micro_lamports: u64) -> Instruction {
+    Instruction::new_with_bincode(
+        id(),
+        &ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports),
+        vec![],
+    )
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ComputeBudgetError {
+    #[error("instruction {0} contains invalid compute budget data")]
+    InvalidInstructionData(u8),
+    #[error("instruction {0} duplicates an earlier compute budget instruction")]
+    DuplicateInstruction(u8),
+    #[error("instruction {0} requests more than the maximum compute unit limit")]
+    ComputeUnitLimitTooHigh(u8),
+}
+
+impl ComputeBudgetError {
+    /// Index of the offending instruction within the message
+    pub fn instruction_index(&self) -> u8 {
+        match self {
+            Self::InvalidInstructionData(index)
+            | Self::DuplicateInstruction(index)
+            | Self::ComputeUnitLimitTooHigh(index) => *index,
+        }
+    }
+}
+
+impl From<ComputeBudgetError> for InstructionError {
+    fn from(_err: ComputeBudgetError) -> Self {
+        InstructionError::InvalidInstructionData
+    }
+}
+
+/// Compute budget parameters requested by a message
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBudgetRequest {
+    pub compute_unit_limit: Option<u32>,
+    pub compute_unit_price: Option<u64>,
+}
+
+impl ComputeBudgetRequest {
+    /// Collect the compute budget instructions of a message.  Each instruction may appear at
+    /// most once.
+    pub fn from_message(message: &Message) -> Result<Self, ComputeBudgetError> {
+        let mut request = Self::default();
+        for (index, instruction) in message.instructions.iter().enumerate() {
+            let index = index as u8;
+            // Transaction may not be sanitized here
+            match message
+                .account_keys
+                .get(instruction.program_id_index as usize)
+            {
+                Some(program_id) if check_id(program_id) => {}
+                _ => continue,
+            }
+            match bincode::deserialize(&instruction.data) {
+                Ok(ComputeBudgetInstruction::SetComputeUnitLimit(units)) => {
+                    if request.compute_unit_limit.is_some() {
+                        return Err(ComputeBudgetError::DuplicateInstruction(index));
+                    }
+                    if units > MAX_COMPUTE_UNIT_LIMIT {
+                        return Err(ComputeBudgetError::ComputeUnitLimitTooHigh(index));
+                    }
+                    request.compute_unit_limit = Some(units);
+                }
+                Ok(ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports)) => {
+                    if request.compute_unit_price.is_some() {
+                        return Err(ComputeBudgetError::DuplicateInstruction(index));
+                    }
+                    request.compute_unit_price = Some(micro_lamports);
+                }
+                Err(_) => return Err(ComputeBudgetError::InvalidInstructionData(index)),
+            }
+        }
+        Ok(request)
+    }
+
+    /// Number of compute units the message is allowed to consume
+    pub fn compute_unit_limit_or(&self, default_units: u64) -> u64 {
+        self.compute_unit_limit
+            .map(u64::from)
+            .unwrap_or(default_units)
+    }
+
+    /// Additional fee in lamports paid for prioritization, rounded up to the nearest lamport
+    pub fn prioritization_fee(&self, default_units: u64) -> u64 {
+        let micro_lamports = u128::from(self.compute_unit_price.unwrap_or(0))
+            .saturating_mul(u128::from(self.compute_unit_limit_or(default_units)));
+        let lamports = micro_lamports.saturating_add(u128::from(MICRO_LAMPORTS_PER_LAMPORT - 1))
+            / u128::from(MICRO_LAMPORTS_PER_LAMPORT);
+        lamports.min(u128::from(u64::MAX)) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pubkey::Pubkey, system_instruction};
+
+    fn request_for(
+        instructions: &[Instruction],
+    ) -> Result<ComputeBudgetRequest, ComputeBudgetError> {
+        let payer = Pubkey::new_unique();
+        ComputeBudgetRequest::from_message(&Message::new(instructions, Some(&payer)))
+    }
+
+    #[test]
+    fn test_compute_budget_request_from_message() {
+        let payer = Pubkey::new_unique();
+        let transfer = system_instruction::transfer(&payer, &Pubkey::new_unique(), 1);
+
+        assert_eq!(
+            request_for(&[transfer.clone()]),
+            Ok(ComputeBudgetRequest::default())
+        );
+        assert_eq!(
+            request_for(&[
+                set_compute_unit_limit(400_000),
+                transfer.clone(),
+                set_compute_unit_price(5),
+            ]),
+            Ok(ComputeBudgetRequest {
+                compute_unit_limit: Some(400_000),
+                compute_unit_price: Some(5),
+            })
+        );
+        assert_eq!(
+            request_for(&[
+                set_compute_unit_limit(1),
+                transfer.clone(),
+                set_compute_unit_limit(2),
+            ]),
+            Err(ComputeBudgetError::DuplicateInstruction(2))
+        );
+        assert_eq!(
+            request_for(&[
+                transfer.clone(),
+                set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT + 1)
+            ]),
+            Err(ComputeBudgetError::ComputeUnitLimitTooHigh(1))
+        );
+
+        let mut garbage = set_compute_unit_price(1);
+        garbage.data = vec![42];
+        let err = request_for(&[transfer, garbage]).unwrap_err();
+        assert_eq!(err, ComputeBudgetError::InvalidInstructionData(1));
+        assert_eq!(err.instruction_index(), 1);
+    }
+
+    #[test]
+    fn test_prioritization_fee() {
+        let default_units = u64::from(DEFAULT_COMPUTE_UNIT_LIMIT);
+        assert_eq!(
+            ComputeBudgetRequest::default().prioritization_fee(default_units),
+            0
+        );
+
+        let request = ComputeBudgetRequest {
+            compute_unit_limit: None,
+            compute_unit_price: Some(MICRO_LAMPORTS_PER_LAMPORT),
+        };
+        assert_eq!(request.prioritization_fee(default_units), default_units);
+
+        // Fractional lamports round up
+        let request = ComputeBudgetRequest {
+            compute_unit_limit: Some(3),
+            compute_unit_price: Some(1),
+        };
+        assert_eq!(request.prioritization_fee(default_units), 1);
+
+        let request = ComputeBudgetRequest {
+            compute_unit_limit: Some(MAX_COMPUTE_UNIT_LIMIT),
+            compute_unit_price: Some(u64::MAX),
+        };
+        assert_eq!(request.prioritization_fee(default_units), u64::MAX);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::clock::DEFAULT_MS_PER_SLOT;
+use crate::compute_budget::{self, ComputeBudgetRequest};
 use crate::message::Message;
 use crate::secp256k1_program;
 use log::*;
@@ -21,12 +22,14 @@ impl Default for FeeCalculator {
 
 pub struct FeeConfig {
     pub secp256k1_program_enabled: bool,
+    pub compute_budget_program_enabled: bool,
 }
 
 impl Default for FeeConfig {
     fn default() -> Self {
         Self {
             secp256k1_program_enabled: true,
+            compute_budget_program_enabled: true,
         }
     }
 }
@@ -57,8 +60,22 @@ impl FeeCalculator {
             }
         }
 
-        self.lamports_per_signature
-            * (u64::from(message.header.num_required_signatures) + num_secp256k1_signatures)
+        // An invalid compute budget request fails the transaction without charging a
+        // prioritization fee
+        let prioritization_fee = if fee_config.compute_budget_program_enabled {
+            ComputeBudgetRequest::from_message(message)
+                .map(|request| {
+                    request
+                        .prioritization_fee(u64::from(compute_budget::DEFAULT_COMPUTE_UNIT_LIMIT))
+                })
+                .unwrap_or(0)
+        } else {
+            0
+        };
+
+        (self.lamports_per_signature
+            * (u64::from(message.header.num_required_signatures) + num_secp256k1_signatures))
+            .saturating_add(prioritization_fee)
     }
 }
 
@@ -262,7 +279,8 @@ mod tests {
             FeeCalculator::new(1).calculate_fee_with_config(
                 &message,
                 &FeeConfig {
-                    secp256k1_program_enabled: false
+                    secp256k1_program_enabled: false,
+                    compute_budget_program_enabled: true,
                 }
             ),
             1
@@ -274,6 +292,57 @@ mod tests {
         assert_eq!(FeeCalculator::new(1).calculate_fee(&message), 11);
     }
 
+    #[test]
+    fn test_fee_calculator_calculate_fee_compute_budget() {
+        let pubkey0 = Pubkey::new(&[0; 32]);
+        let pubkey1 = Pubkey::new(&[1; 32]);
+        let ix0 = system_instruction::transfer(&pubkey0, &pubkey1, 1);
+
+        let message = Message::new(
+            &[
+                compute_budget::set_compute_unit_limit(1_000_000),
+                compute_budget::set_compute_unit_price(3),
+                ix0.clone(),
+            ],
+            Some(&pubkey0),
+        );
+        assert_eq!(FeeCalculator::new(5).calculate_fee(&message), 5 + 3);
+        assert_eq!(
+            FeeCalculator::new(5).calculate_fee_with_config(
+                &message,
+                &FeeConfig {
+                    secp256k1_program_enabled: true,
+                    compute_budget_program_enabled: false,
+                }
+            ),
+            5
+        );
+
+        // Without a limit the price applies to the default compute unit limit
+        let message = Message::new(
+            &[
+                compute_budget::set_compute_unit_price(compute_budget::MICRO_LAMPORTS_PER_LAMPORT),
+                ix0.clone(),
+            ],
+            Some(&pubkey0),
+        );
+        assert_eq!(
+            FeeCalculator::new(5).calculate_fee(&message),
+            5 + u64::from(compute_budget::DEFAULT_COMPUTE_UNIT_LIMIT)
+        );
+
+        // Invalid requests don't pay a prioritization fee
+        let message = Message::new(
+            &[
+                compute_budget::set_compute_unit_price(compute_budget::MICRO_LAMPORTS_PER_LAMPORT),
+                compute_budget::set_compute_unit_price(compute_budget::MICRO_LAMPORTS_PER_LAMPORT),
+                ix0,
+            ],
+            Some(&pubkey0),
+        );
+        assert_eq!(FeeCalculator::new(5).calculate_fee(&message), 5);
+    }
+
     #[test]
     fn test_fee_rate_governor_derived_default() {
         solana_logger::setup();
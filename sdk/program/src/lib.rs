@@ -6,11 +6,13 @@
 extern crate self as solana_program;
 
 pub mod account_info;
+pub mod address_lookup_table_account;
 pub mod borsh;
 pub mod bpf_loader;
 pub mod bpf_loader_deprecated;
 pub mod bpf_loader_upgradeable;
 pub mod clock;
+pub mod compute_budget;
 pub mod decode_error;
 pub mod entrypoint;
 pub mod entrypoint_deprecated;
@@ -1,4 +1,4 @@
-//! A library for generating a message from a sequence of instructions
+//! The original message format, which lists every account it references
 
 use crate::sanitize::{Sanitize, SanitizeError};
 use crate::serialize_utils::{
@@ -31,13 +31,16 @@ fn compile_instruction(ix: &Instruction, keys: &[Pubkey]) -> CompiledInstruction
     }
 }
 
-fn compile_instructions(ixs: &[Instruction], keys: &[Pubkey]) -> Vec<CompiledInstruction> {
+pub(super) fn compile_instructions(
+    ixs: &[Instruction],
+    keys: &[Pubkey],
+) -> Vec<CompiledInstruction> {
     ixs.iter().map(|ix| compile_instruction(ix, keys)).collect()
 }
 
 /// A helper struct to collect pubkeys referenced by a set of instructions and read-only counts
 #[derive(Debug, PartialEq, Eq)]
-struct InstructionKeys {
+pub(super) struct InstructionKeys {
     pub signed_keys: Vec<Pubkey>,
     pub unsigned_keys: Vec<Pubkey>,
     pub num_readonly_signed_accounts: u8,
@@ -64,7 +67,7 @@ impl InstructionKeys {
 /// payer key is provided, it is always placed first in the list of signed keys. Read-only signed
 /// accounts are placed last in the set of signed accounts. Read-only unsigned accounts,
 /// including program ids, are placed last in the set. No duplicates and order is preserved.
-fn get_keys(instructions: &[Instruction], payer: Option<&Pubkey>) -> InstructionKeys {
+pub(super) fn get_keys(instructions: &[Instruction], payer: Option<&Pubkey>) -> InstructionKeys {
     let programs: Vec<_> = get_program_ids(instructions)
         .iter()
         .map(|program_id| AccountMeta {
@@ -160,7 +163,7 @@ pub struct MessageHeader {
     pub num_readonly_unsigned_accounts: u8,
 }
 
-#[frozen_abi(digest = "BPBJZxpRQ4JS7LGJtsgoyctg4BXyBbbY4uc7FjowtxLV")]
+#[frozen_abi(digest = "76hbeH6XBeumKyjqrZRmu2U8QZnWmded3NRd6L2fkn3k")]
 #[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone, AbiExample)]
 #[serde(rename_all = "camelCase")]
 pub struct Message {
@@ -0,0 +1,16 @@
+//! A library for generating a message from a sequence of instructions
+//!
+//! Legacy messages list every account they reference.  Versioned messages are prefixed with a
+//! byte that has its high bit set, a value legacy messages never start with since they can't
+//! require that many signatures, and may load accounts from on-chain address lookup tables.
+
+mod legacy;
+pub mod v0;
+mod versions;
+
+pub use legacy::*;
+pub use versions::*;
+
+/// Bit set in the first byte of a serialized message to indicate that it is versioned.  The
+/// remaining bits hold the version number.
+pub const MESSAGE_VERSION_PREFIX: u8 = 0x80;
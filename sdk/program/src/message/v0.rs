@@ -0,0 +1,497 @@
+//! Version 0 messages, which may load accounts from on-chain address lookup tables in addition
+//! to the accounts they list themselves
+
+use crate::{
+    address_lookup_table_account::AddressLookupTableAccount,
+    hash::Hash,
+    instruction::{CompiledInstruction, Instruction},
+    message::{legacy, MessageHeader},
+    pubkey::Pubkey,
+    sanitize::{Sanitize, SanitizeError},
+    short_vec,
+};
+use thiserror::Error;
+
+/// Maximum number of accounts a message may reference, since instructions refer to accounts by
+/// a u8 index
+pub const MAX_ACCOUNT_KEYS: usize = u8::MAX as usize + 1;
+
+/// Addresses loaded from a single address lookup table
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone, AbiExample)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageAddressTableLookup {
+    /// Address of the lookup table account
+    pub account_key: Pubkey,
+    /// Indexes of the table addresses loaded as writable accounts
+    #[serde(with = "short_vec")]
+    pub writable_indexes: Vec<u8>,
+    /// Indexes of the table addresses loaded as read-only accounts
+    #[serde(with = "short_vec")]
+    pub readonly_indexes: Vec<u8>,
+}
+
+impl Sanitize for MessageAddressTableLookup {}
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone, AbiExample)]
+#[serde(rename_all = "camelCase")]
+pub struct Message {
+    /// The message header, identifying signed and read-only `account_keys`.  The read-only
+    /// counts only apply to `account_keys`, addresses loaded from lookup tables are never signers
+    pub header: MessageHeader,
+
+    /// The account keys listed in the message itself
+    #[serde(with = "short_vec")]
+    pub account_keys: Vec<Pubkey>,
+
+    /// The id of a recent ledger entry.
+    pub recent_blockhash: Hash,
+
+    /// Programs that will be executed in sequence and committed in one atomic transaction if all
+    /// succeed.  Account indexes first refer to `account_keys`, then to the writable addresses
+    /// of every lookup, then to the read-only addresses of every lookup.
+    #[serde(with = "short_vec")]
+    pub instructions: Vec<CompiledInstruction>,
+
+    /// Lookups of additional accounts stored in address lookup tables
+    #[serde(with = "short_vec")]
+    pub address_table_lookups: Vec<MessageAddressTableLookup>,
+}
+
+/// Addresses resolved from the lookups of a message
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct LoadedAddresses {
+    /// Addresses loaded as writable accounts, in lookup order
+    pub writable: Vec<Pubkey>,
+    /// Addresses loaded as read-only accounts, in lookup order
+    pub readonly: Vec<Pubkey>,
+}
+
+impl LoadedAddresses {
+    pub fn len(&self) -> usize {
+        self.writable.len().saturating_add(self.readonly.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writable.is_empty() && self.readonly.is_empty()
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CompileError {
+    #[error("the instructions reference more than {} accounts", MAX_ACCOUNT_KEYS)]
+    AccountIndexOverflow,
+    #[error("the instructions require more than {} signers", u8::MAX)]
+    SignerOverflow,
+}
+
+impl Sanitize for Message {
+    fn sanitize(&self) -> Result<(), SanitizeError> {
+        let num_static_account_keys = self.account_keys.len();
+        // signing area and read-only non-signing area should not overlap
+        if usize::from(self.header.num_required_signatures)
+            .saturating_add(usize::from(self.header.num_readonly_unsigned_accounts))
+            > num_static_account_keys
+        {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+
+        // there should be at least 1 RW fee-payer account.
+        if self.header.num_readonly_signed_accounts >= self.header.num_required_signatures {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+
+        let mut num_loaded_account_keys = 0usize;
+        for lookup in &self.address_table_lookups {
+            // each lookup should load at least one address
+            if lookup.writable_indexes.is_empty() && lookup.readonly_indexes.is_empty() {
+                return Err(SanitizeError::InvalidValue);
+            }
+            num_loaded_account_keys = num_loaded_account_keys
+                .saturating_add(lookup.writable_indexes.len())
+                .saturating_add(lookup.readonly_indexes.len());
+        }
+
+        let num_account_keys = num_static_account_keys.saturating_add(num_loaded_account_keys);
+        if num_account_keys > MAX_ACCOUNT_KEYS {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+
+        for ci in &self.instructions {
+            // Programs must be listed in the message, they can't be loaded from a table
+            if usize::from(ci.program_id_index) >= num_static_account_keys {
+                return Err(SanitizeError::IndexOutOfBounds);
+            }
+            // A program cannot be a payer.
+            if ci.program_id_index == 0 {
+                return Err(SanitizeError::IndexOutOfBounds);
+            }
+            for ai in &ci.accounts {
+                if usize::from(*ai) >= num_account_keys {
+                    return Err(SanitizeError::IndexOutOfBounds);
+                }
+            }
+        }
+        self.account_keys.sanitize()?;
+        self.recent_blockhash.sanitize()?;
+        self.instructions.sanitize()?;
+        self.address_table_lookups.sanitize()?;
+        Ok(())
+    }
+}
+
+impl Message {
+    /// Compile a message that loads every non-signer, non-program account it can from the
+    /// given lookup tables.  Accounts found in several tables are loaded from the first one.
+    pub fn try_compile(
+        payer: &Pubkey,
+        instructions: &[Instruction],
+        address_lookup_table_accounts: &[AddressLookupTableAccount],
+        recent_blockhash: Hash,
+    ) -> Result<Self, CompileError> {
+        let legacy::InstructionKeys {
+            signed_keys,
+            unsigned_keys,
+            num_readonly_signed_accounts,
+            num_readonly_unsigned_accounts,
+        } = legacy::get_keys(instructions, Some(payer));
+        if signed_keys.len() > usize::from(u8::MAX) {
+            return Err(CompileError::SignerOverflow);
+        }
+
+        let num_writable_unsigned_keys =
+            unsigned_keys.len() - usize::from(num_readonly_unsigned_accounts);
+        let mut unsigned_keys: Vec<(Pubkey, bool)> = unsigned_keys
+            .into_iter()
+            .enumerate()
+            .map(|(i, key)| (key, i < num_writable_unsigned_keys))
+            .collect();
+
+        let mut address_table_lookups = vec![];
+        let mut loaded_addresses = LoadedAddresses::default();
+        for table in address_lookup_table_accounts {
+            let mut lookup = MessageAddressTableLookup {
+                account_key: table.key,
+                ..MessageAddressTableLookup::default()
+            };
+            unsigned_keys.retain(|(key, is_writable)| {
+                if instructions.iter().any(|ix| ix.program_id == *key) {
+                    return true;
+                }
+                // Only the first addresses of a table can be referenced by a u8 index
+                let index = table
+                    .addresses
+                    .iter()
+                    .take(MAX_ACCOUNT_KEYS)
+                    .position(|address| address == key);
+                match index {
+                    Some(index) if *is_writable => {
+                        lookup.writable_indexes.push(index as u8);
+                        loaded_addresses.writable.push(*key);
+                        false
+                    }
+                    Some(index) => {
+                        lookup.readonly_indexes.push(index as u8);
+                        loaded_addresses.readonly.push(*key);
+                        false
+                    }
+                    None => true,
+                }
+            });
+            if !lookup.writable_indexes.is_empty() || !lookup.readonly_indexes.is_empty() {
+                address_table_lookups.push(lookup);
+            }
+        }
+
+        let num_readonly_unsigned_accounts = unsigned_keys
+            .iter()
+            .filter(|(_, is_writable)| !is_writable)
+            .count();
+        let num_required_signatures = signed_keys.len();
+        let mut account_keys = signed_keys;
+        account_keys.extend(unsigned_keys.into_iter().map(|(key, _)| key));
+
+        let mut all_keys = account_keys.clone();
+        all_keys.extend(&loaded_addresses.writable);
+        all_keys.extend(&loaded_addresses.readonly);
+        if all_keys.len() > MAX_ACCOUNT_KEYS {
+            return Err(CompileError::AccountIndexOverflow);
+        }
+
+        Ok(Self {
+            header: MessageHeader {
+                num_required_signatures: num_required_signatures as u8,
+                num_readonly_signed_accounts,
+                num_readonly_unsigned_accounts: num_readonly_unsigned_accounts as u8,
+            },
+            account_keys,
+            recent_blockhash,
+            instructions: legacy::compile_instructions(instructions, &all_keys),
+            address_table_lookups,
+        })
+    }
+
+    /// Number of addresses loaded by the lookups of this message
+    pub fn num_lookup_addresses(&self) -> usize {
+        self.address_table_lookups
+            .iter()
+            .map(|lookup| {
+                lookup
+                    .writable_indexes
+                    .len()
+                    .saturating_add(lookup.readonly_indexes.len())
+            })
+            .sum()
+    }
+
+    /// Convert a sanitized message into the equivalent legacy message, given the addresses
+    /// loaded by its lookups.  Loaded writable addresses are placed right after the static
+    /// writable non-signers and loaded read-only addresses at the end, so that the legacy
+    /// header describes the same signer and writable accounts.
+    ///
+    /// The resulting message is only meant to be executed; its serialization differs from the
+    /// one that was signed.
+    pub fn into_legacy_message(self, loaded_addresses: LoadedAddresses) -> legacy::Message {
+        let num_static_account_keys = self.account_keys.len();
+        let num_static_writable_account_keys =
+            num_static_account_keys - usize::from(self.header.num_readonly_unsigned_accounts);
+        let num_loaded_writable = loaded_addresses.writable.len();
+        let remap_index = |index: u8| -> u8 {
+            let index = usize::from(index);
+            let index = if index < num_static_writable_account_keys {
+                index
+            } else if index < num_static_account_keys {
+                index + num_loaded_writable
+            } else if index < num_static_account_keys + num_loaded_writable {
+                index - num_static_account_keys + num_static_writable_account_keys
+            } else {
+                index
+            };
+            index as u8
+        };
+
+        let instructions = self
+            .instructions
+            .into_iter()
+            .map(|instruction| CompiledInstruction {
+                program_id_index: remap_index(instruction.program_id_index),
+                accounts: instruction.accounts.into_iter().map(remap_index).collect(),
+                data: instruction.data,
+            })
+            .collect();
+
+        let num_readonly_unsigned_accounts =
+            usize::from(self.header.num_readonly_unsigned_accounts)
+                + loaded_addresses.readonly.len();
+        let mut static_account_keys = self.account_keys;
+        let static_readonly_account_keys =
+            static_account_keys.split_off(num_static_writable_account_keys);
+        let mut account_keys = static_account_keys;
+        account_keys.extend(loaded_addresses.writable);
+        account_keys.extend(static_readonly_account_keys);
+        account_keys.extend(loaded_addresses.readonly);
+
+        legacy::Message {
+            header: MessageHeader {
+                num_readonly_unsigned_accounts: num_readonly_unsigned_accounts as u8,
+                ..self.header
+            },
+            account_keys,
+            recent_blockhash: self.recent_blockhash,
+            instructions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::AccountMeta;
+
+    fn compiled_instruction(
+        program_id_index: u8,
+        data: Vec<u8>,
+        accounts: Vec<u8>,
+    ) -> CompiledInstruction {
+        CompiledInstruction {
+            program_id_index,
+            accounts,
+            data,
+        }
+    }
+
+    fn simple_message() -> Message {
+        Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![1],
+            }],
+            instructions: vec![compiled_instruction(1, vec![], vec![0, 2, 3])],
+            ..Message::default()
+        }
+    }
+
+    #[test]
+    fn test_sanitize() {
+        assert!(simple_message().sanitize().is_ok());
+
+        // no signers
+        let mut message = simple_message();
+        message.header.num_required_signatures = 0;
+        assert!(message.sanitize().is_err());
+
+        // lookup without any index
+        let mut message = simple_message();
+        message.address_table_lookups[0].writable_indexes.clear();
+        message.address_table_lookups[0].readonly_indexes.clear();
+        assert_eq!(message.sanitize(), Err(SanitizeError::InvalidValue));
+
+        // program loaded from a lookup table
+        let mut message = simple_message();
+        message.instructions[0].program_id_index = 2;
+        assert_eq!(message.sanitize(), Err(SanitizeError::IndexOutOfBounds));
+
+        // account index past the loaded addresses
+        let mut message = simple_message();
+        message.instructions[0].accounts.push(4);
+        assert_eq!(message.sanitize(), Err(SanitizeError::IndexOutOfBounds));
+    }
+
+    #[test]
+    fn test_sanitize_max_account_keys() {
+        let mut message = simple_message();
+        message.address_table_lookups[0].writable_indexes = (0..=u8::MAX).collect();
+        message.address_table_lookups[0].readonly_indexes.clear();
+        // 2 static keys + 256 loaded ones
+        assert_eq!(message.sanitize(), Err(SanitizeError::IndexOutOfBounds));
+
+        message.address_table_lookups[0].writable_indexes = (0..=u8::MAX - 2).collect();
+        assert!(message.sanitize().is_ok());
+    }
+
+    #[test]
+    fn test_try_compile() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let writable_key = Pubkey::new_unique();
+        let readonly_key = Pubkey::new_unique();
+        let static_key = Pubkey::new_unique();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            // The program id can't be loaded, even if the table contains it
+            addresses: vec![readonly_key, program_id, writable_key],
+        };
+        let unused_table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![Pubkey::new_unique()],
+        };
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &[1, 2, 3],
+            vec![
+                AccountMeta::new(writable_key, false),
+                AccountMeta::new_readonly(readonly_key, false),
+                AccountMeta::new_readonly(static_key, false),
+            ],
+        );
+
+        let message = Message::try_compile(
+            &payer,
+            &[instruction],
+            &[unused_table, table.clone()],
+            Hash::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            message,
+            Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 2,
+                },
+                account_keys: vec![payer, static_key, program_id],
+                recent_blockhash: Hash::default(),
+                instructions: vec![compiled_instruction(2, vec![1, 2, 3], vec![3, 4, 1],)],
+                address_table_lookups: vec![MessageAddressTableLookup {
+                    account_key: table.key,
+                    writable_indexes: vec![2],
+                    readonly_indexes: vec![0],
+                }],
+            }
+        );
+        assert!(message.sanitize().is_ok());
+        assert_eq!(message.num_lookup_addresses(), 2);
+    }
+
+    #[test]
+    fn test_try_compile_account_index_overflow() {
+        let payer = Pubkey::new_unique();
+        let accounts: Vec<_> = (0..MAX_ACCOUNT_KEYS)
+            .map(|_| AccountMeta::new(Pubkey::new_unique(), false))
+            .collect();
+        let instruction = Instruction::new_with_bytes(Pubkey::new_unique(), &[], accounts);
+        assert_eq!(
+            Message::try_compile(&payer, &[instruction], &[], Hash::default()),
+            Err(CompileError::AccountIndexOverflow)
+        );
+    }
+
+    #[test]
+    fn test_into_legacy_message() {
+        let keys: Vec<_> = (0..8).map(|_| Pubkey::new_unique()).collect();
+        let message = Message {
+            header: MessageHeader {
+                num_required_signatures: 2,
+                num_readonly_signed_accounts: 1,
+                num_readonly_unsigned_accounts: 1,
+            },
+            // writable signer, read-only signer, writable, read-only
+            account_keys: keys[0..4].to_vec(),
+            recent_blockhash: Hash::new_unique(),
+            instructions: vec![compiled_instruction(3, vec![42], (0..7).collect())],
+            address_table_lookups: vec![
+                MessageAddressTableLookup {
+                    account_key: Pubkey::new_unique(),
+                    writable_indexes: vec![0],
+                    readonly_indexes: vec![1],
+                },
+                MessageAddressTableLookup {
+                    account_key: Pubkey::new_unique(),
+                    writable_indexes: vec![],
+                    readonly_indexes: vec![0],
+                },
+            ],
+        };
+        assert!(message.sanitize().is_ok());
+        let recent_blockhash = message.recent_blockhash;
+
+        let loaded_addresses = LoadedAddresses {
+            writable: vec![keys[4]],
+            readonly: vec![keys[5], keys[6]],
+        };
+        let legacy_message = message.into_legacy_message(loaded_addresses);
+        assert_eq!(
+            legacy_message,
+            legacy::Message {
+                header: MessageHeader {
+                    num_required_signatures: 2,
+                    num_readonly_signed_accounts: 1,
+                    num_readonly_unsigned_accounts: 3,
+                },
+                account_keys: vec![keys[0], keys[1], keys[2], keys[4], keys[3], keys[5], keys[6]],
+                recent_blockhash,
+                instructions: vec![compiled_instruction(4, vec![42], vec![0, 1, 2, 4, 3, 5, 6],)],
+            }
+        );
+        assert!(legacy_message.sanitize().is_ok());
+        assert!(legacy_message.is_writable(3));
+        assert!(!legacy_message.is_writable(4));
+    }
+}
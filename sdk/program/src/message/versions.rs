@@ -0,0 +1,271 @@
+use crate::{
+    hash::Hash,
+    instruction::CompiledInstruction,
+    message::{legacy::Message as LegacyMessage, v0, MessageHeader, MESSAGE_VERSION_PREFIX},
+    pubkey::Pubkey,
+    sanitize::{Sanitize, SanitizeError},
+    short_vec,
+};
+use serde::{
+    de::{self, Deserializer, SeqAccess, Visitor},
+    ser::{SerializeTuple, Serializer},
+    {Deserialize, Serialize},
+};
+use std::fmt;
+
+/// Either a legacy message or a message of a known version.
+///
+/// Legacy messages are serialized as is.  Versioned messages are serialized with a leading
+/// `MESSAGE_VERSION_PREFIX | version` byte, which can't be mistaken for the
+/// `num_required_signatures` field legacy messages start with.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum VersionedMessage {
+    Legacy(LegacyMessage),
+    V0(v0::Message),
+}
+
+impl Default for VersionedMessage {
+    fn default() -> Self {
+        Self::Legacy(LegacyMessage::default())
+    }
+}
+
+impl From<LegacyMessage> for VersionedMessage {
+    fn from(message: LegacyMessage) -> Self {
+        Self::Legacy(message)
+    }
+}
+
+impl From<v0::Message> for VersionedMessage {
+    fn from(message: v0::Message) -> Self {
+        Self::V0(message)
+    }
+}
+
+impl Sanitize for VersionedMessage {
+    fn sanitize(&self) -> Result<(), SanitizeError> {
+        match self {
+            Self::Legacy(message) => message.sanitize(),
+            Self::V0(message) => message.sanitize(),
+        }
+    }
+}
+
+impl VersionedMessage {
+    /// Version number of the message, or `None` for legacy messages
+    pub fn version(&self) -> Option<u8> {
+        match self {
+            Self::Legacy(_) => None,
+            Self::V0(_) => Some(0),
+        }
+    }
+
+    pub fn header(&self) -> &MessageHeader {
+        match self {
+            Self::Legacy(message) => &message.header,
+            Self::V0(message) => &message.header,
+        }
+    }
+
+    /// The account keys listed in the message itself, not including the ones loaded from
+    /// address lookup tables
+    pub fn static_account_keys(&self) -> &[Pubkey] {
+        match self {
+            Self::Legacy(message) => &message.account_keys,
+            Self::V0(message) => &message.account_keys,
+        }
+    }
+
+    pub fn address_table_lookups(&self) -> Option<&[v0::MessageAddressTableLookup]> {
+        match self {
+            Self::Legacy(_) => None,
+            Self::V0(message) => Some(&message.address_table_lookups),
+        }
+    }
+
+    pub fn recent_blockhash(&self) -> &Hash {
+        match self {
+            Self::Legacy(message) => &message.recent_blockhash,
+            Self::V0(message) => &message.recent_blockhash,
+        }
+    }
+
+    pub fn set_recent_blockhash(&mut self, recent_blockhash: Hash) {
+        match self {
+            Self::Legacy(message) => message.recent_blockhash = recent_blockhash,
+            Self::V0(message) => message.recent_blockhash = recent_blockhash,
+        }
+    }
+
+    pub fn instructions(&self) -> &[CompiledInstruction] {
+        match self {
+            Self::Legacy(message) => &message.instructions,
+            Self::V0(message) => &message.instructions,
+        }
+    }
+
+    /// The bytes that are signed by the transaction signers
+    pub fn serialize(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+}
+
+impl Serialize for VersionedMessage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Legacy(message) => Serialize::serialize(message, serializer),
+            Self::V0(message) => {
+                let mut seq = serializer.serialize_tuple(2)?;
+                seq.serialize_element(&MESSAGE_VERSION_PREFIX)?;
+                seq.serialize_element(message)?;
+                seq.end()
+            }
+        }
+    }
+}
+
+/// The fields of a legacy message that follow `num_required_signatures`
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RemainingLegacyMessage {
+    pub num_readonly_signed_accounts: u8,
+    pub num_readonly_unsigned_accounts: u8,
+    #[serde(with = "short_vec")]
+    pub account_keys: Vec<Pubkey>,
+    pub recent_blockhash: Hash,
+    #[serde(with = "short_vec")]
+    pub instructions: Vec<CompiledInstruction>,
+}
+
+struct MessageVisitor;
+
+impl<'de> Visitor<'de> for MessageVisitor {
+    type Value = VersionedMessage;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a legacy or versioned message")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<VersionedMessage, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let prefix: u8 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+
+        if prefix & MESSAGE_VERSION_PREFIX != 0 {
+            let version = prefix & !MESSAGE_VERSION_PREFIX;
+            match version {
+                0 => {
+                    let message: v0::Message = seq
+                        .next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                    Ok(VersionedMessage::V0(message))
+                }
+                _ => Err(de::Error::invalid_value(
+                    de::Unexpected::Unsigned(u64::from(version)),
+                    &"a supported message version",
+                )),
+            }
+        } else {
+            // The prefix is the `num_required_signatures` field of a legacy message
+            let message: RemainingLegacyMessage = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+            Ok(VersionedMessage::Legacy(LegacyMessage {
+                header: MessageHeader {
+                    num_required_signatures: prefix,
+                    num_readonly_signed_accounts: message.num_readonly_signed_accounts,
+                    num_readonly_unsigned_accounts: message.num_readonly_unsigned_accounts,
+                },
+                account_keys: message.account_keys,
+                recent_blockhash: message.recent_blockhash,
+                instructions: message.instructions,
+            }))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for VersionedMessage {
+    fn deserialize<D>(deserializer: D) -> Result<VersionedMessage, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple(2, MessageVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        instruction::{AccountMeta, Instruction},
+        message::v0::MessageAddressTableLookup,
+    };
+
+    #[test]
+    fn test_legacy_message_serialization() {
+        let program_id = Pubkey::new_unique();
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &[1, 2, 3],
+            vec![
+                AccountMeta::new(Pubkey::new_unique(), true),
+                AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            ],
+        );
+        let mut message = LegacyMessage::new(&[instruction], None);
+        message.recent_blockhash = Hash::new_unique();
+
+        let bytes = bincode::serialize(&message).unwrap();
+        let versioned_message = VersionedMessage::from(message.clone());
+        assert_eq!(versioned_message.serialize(), bytes);
+        assert_eq!(versioned_message.version(), None);
+        assert_eq!(
+            bincode::deserialize::<VersionedMessage>(&bytes).unwrap(),
+            VersionedMessage::Legacy(message)
+        );
+    }
+
+    #[test]
+    fn test_v0_message_serialization() {
+        let message = v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            recent_blockhash: Hash::new_unique(),
+            instructions: vec![CompiledInstruction {
+                program_id_index: 1,
+                accounts: vec![0, 2],
+                data: vec![1, 2, 3],
+            }],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![1],
+                readonly_indexes: vec![],
+            }],
+        };
+
+        let versioned_message = VersionedMessage::V0(message.clone());
+        let bytes = versioned_message.serialize();
+        assert_eq!(bytes[0], MESSAGE_VERSION_PREFIX);
+        assert_eq!(&bytes[1..], &bincode::serialize(&message).unwrap()[..]);
+        assert_eq!(versioned_message.version(), Some(0));
+        assert_eq!(
+            bincode::deserialize::<VersionedMessage>(&bytes).unwrap(),
+            versioned_message
+        );
+
+        // Unknown versions are rejected
+        let mut bytes = bytes;
+        bytes[0] = MESSAGE_VERSION_PREFIX | 1;
+        assert!(bincode::deserialize::<VersionedMessage>(&bytes).is_err());
+    }
+}
@@ -115,6 +115,26 @@ pub mod require_stake_for_gossip {
     solana_sdk::declare_id!("6oNzd5Z3M2L1xo4Q5hoox7CR2DuW7m1ETLWH5jHJthwa");
 }
 
+pub mod compute_budget_program_enabled {
+    solana_sdk::declare_id!("3NGCz2Bem9NaC7Gxb9DRpPDbpGCpKhTGkDcXmkRbLSRU");
+}
+
+pub mod versioned_tx_message_enabled {
+    solana_sdk::declare_id!("6ASU4DydiPNpmVjDZMJVtUHqvnsrVZ8fQFCWmsXkmYLG");
+}
+
+pub mod sign_repair_requests {
+    solana_sdk::declare_id!("JAsH2uWjuvs1BEzUSLZsb8X9mUs8XMD1GzjuxBcAeMoA");
+}
+
+pub mod merkle_shreds {
+    solana_sdk::declare_id!("3ZefB8zzcN5RnZvoxs6nRbnGfiQZZzjtApJVtjASFV2A");
+}
+
+pub mod gossip_pull_request_with_kinds {
+    solana_sdk::declare_id!("63LQnCRoLHSNk8sMDFChmQcuXciRwafLKtPFmz9mFYzF");
+}
+
 lazy_static! {
     /// Map of feature identifiers to user-visible description
     pub static ref FEATURE_NAMES: HashMap<Pubkey, &'static str> = [
@@ -144,6 +164,11 @@ lazy_static! {
         (cpi_share_ro_and_exec_accounts::id(), "Share RO and Executable accounts during cross-program invocations"),
         (skip_ro_deserialization::id(), "Skip deserialization of read-only accounts"),
         (require_stake_for_gossip::id(), "require stakes for propagating crds values through gossip #15561"),
+        (compute_budget_program_enabled::id(), "compute budget program with prioritization fees"),
+        (versioned_tx_message_enabled::id(), "versioned transaction messages and address lookup table program"),
+        (sign_repair_requests::id(), "sign and verify repair requests"),
+        (merkle_shreds::id(), "broadcast Merkle shreds signed once per FEC set"),
+        (gossip_pull_request_with_kinds::id(), "gossip pull requests for a subset of crds value kinds"),
         /*************** ADD NEW FEATURES HERE ***************/
     ]
     .iter()
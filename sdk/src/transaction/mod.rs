@@ -19,6 +19,10 @@ use crate::{
 use std::result;
 use thiserror::Error;
 
+mod versioned;
+
+pub use versioned::*;
+
 /// Reasons a transaction might be rejected.
 #[derive(
     Error, Serialize, Deserialize, Debug, PartialEq, Eq, Clone, AbiExample, AbiEnumVisitor,
@@ -105,7 +109,7 @@ impl From<SanitizeError> for TransactionError {
 }
 
 /// An atomic transaction
-#[frozen_abi(digest = "2Kr1C1pRytLsmUbg8p2nLoZyrjrEQCriAYLTCYvwj1Fo")]
+#[frozen_abi(digest = "7vu8t4rzm6bdX8EeL4KRZf5otoUYwk6tttdJ7Vir1upT")]
 #[derive(Debug, PartialEq, Default, Eq, Clone, Serialize, Deserialize, AbiExample)]
 pub struct Transaction {
     /// A set of digital signatures of `account_keys`, `program_ids`, `recent_blockhash`, and `instructions`, signed by the first
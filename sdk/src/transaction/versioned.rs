@@ -0,0 +1,252 @@
+//! Defines a transaction which supports multiple versions of messages
+
+use crate::{
+    message::VersionedMessage,
+    sanitize::{Sanitize, SanitizeError},
+    secp256k1_instruction::verify_eth_addresses,
+    short_vec,
+    signature::{Signature, SignerError},
+    signers::Signers,
+    transaction::{Result, Transaction, TransactionError},
+};
+use std::cmp::Ordering;
+
+/// An atomic transaction whose message may be a legacy or a versioned message
+#[derive(Debug, PartialEq, Default, Eq, Clone, Serialize, Deserialize)]
+pub struct VersionedTransaction {
+    /// Signatures of the serialized message, one for each of the first
+    /// `num_required_signatures` static account keys of the message
+    #[serde(with = "short_vec")]
+    pub signatures: Vec<Signature>,
+
+    /// The message to sign.
+    pub message: VersionedMessage,
+}
+
+impl Sanitize for VersionedTransaction {
+    fn sanitize(&self) -> std::result::Result<(), SanitizeError> {
+        if self.message.header().num_required_signatures as usize > self.signatures.len() {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+        // Signers can't be loaded from lookup tables
+        if self.signatures.len() > self.message.static_account_keys().len() {
+            return Err(SanitizeError::IndexOutOfBounds);
+        }
+        self.message.sanitize()
+    }
+}
+
+impl From<Transaction> for VersionedTransaction {
+    fn from(transaction: Transaction) -> Self {
+        Self {
+            signatures: transaction.signatures,
+            message: VersionedMessage::Legacy(transaction.message),
+        }
+    }
+}
+
+impl VersionedTransaction {
+    /// Sign a message with every one of its required signers
+    pub fn try_new<T: Signers>(
+        message: VersionedMessage,
+        keypairs: &T,
+    ) -> std::result::Result<Self, SignerError> {
+        let num_required_signatures = message.header().num_required_signatures as usize;
+        let static_account_keys = message.static_account_keys();
+        if static_account_keys.len() < num_required_signatures {
+            return Err(SignerError::InvalidInput("invalid message".to_string()));
+        }
+        let signer_keys = keypairs.try_pubkeys()?;
+        let expected_signer_keys = &static_account_keys[0..num_required_signatures];
+        match signer_keys.len().cmp(&expected_signer_keys.len()) {
+            Ordering::Greater => Err(SignerError::KeypairPubkeyMismatch),
+            Ordering::Less => Err(SignerError::NotEnoughSigners),
+            Ordering::Equal => Ok(()),
+        }?;
+        let positions = signer_keys
+            .iter()
+            .map(|signer_key| {
+                expected_signer_keys
+                    .iter()
+                    .position(|key| key == signer_key)
+                    .ok_or(SignerError::KeypairPubkeyMismatch)
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let message_data = message.serialize();
+        let mut signatures = vec![Signature::default(); num_required_signatures];
+        for (position, signature) in positions
+            .into_iter()
+            .zip(keypairs.try_sign_message(&message_data)?)
+        {
+            signatures[position] = signature;
+        }
+        // A signer was given twice
+        if signatures.contains(&Signature::default()) {
+            return Err(SignerError::NotEnoughSigners);
+        }
+        Ok(Self {
+            signatures,
+            message,
+        })
+    }
+
+    /// Return the legacy transaction if the message is not versioned
+    pub fn into_legacy_transaction(self) -> Option<Transaction> {
+        match self.message {
+            VersionedMessage::Legacy(message) => Some(Transaction {
+                signatures: self.signatures,
+                message,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Return the serialized message data to sign.
+    pub fn message_data(&self) -> Vec<u8> {
+        self.message.serialize()
+    }
+
+    pub fn verify_with_results(&self) -> Vec<bool> {
+        let message_data = self.message_data();
+        self.signatures
+            .iter()
+            .zip(self.message.static_account_keys())
+            .map(|(signature, pubkey)| signature.verify(pubkey.as_ref(), &message_data))
+            .collect()
+    }
+
+    /// Verify the transaction
+    pub fn verify(&self) -> Result<()> {
+        if self
+            .verify_with_results()
+            .iter()
+            .all(|verify_result| *verify_result)
+        {
+            Ok(())
+        } else {
+            Err(TransactionError::SignatureFailure)
+        }
+    }
+
+    pub fn verify_precompiles(&self) -> Result<()> {
+        let instructions = self.message.instructions();
+        for instruction in instructions {
+            // The transaction may not be sanitized at this point, and programs are always
+            // static account keys
+            let program_id = self
+                .message
+                .static_account_keys()
+                .get(instruction.program_id_index as usize)
+                .ok_or(TransactionError::AccountNotFound)?;
+            if crate::secp256k1_program::check_id(program_id) {
+                let instruction_datas: Vec<_> = instructions
+                    .iter()
+                    .map(|instruction| instruction.data.as_ref())
+                    .collect();
+                verify_eth_addresses(&instruction.data, &instruction_datas)
+                    .map_err(|_| TransactionError::InvalidAccountIndex)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        message::{v0, Message},
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        system_instruction,
+    };
+
+    #[test]
+    fn test_legacy_transaction_serialization() {
+        let from = Keypair::new();
+        let to = Pubkey::new_unique();
+        let transaction = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(&from.pubkey(), &to, 1)],
+            Some(&from.pubkey()),
+            &[&from],
+            Hash::new_unique(),
+        );
+        let bytes = bincode::serialize(&transaction).unwrap();
+
+        let versioned_transaction: VersionedTransaction = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(versioned_transaction.message.version(), None);
+        assert!(versioned_transaction.sanitize().is_ok());
+        assert!(versioned_transaction.verify().is_ok());
+        assert_eq!(bincode::serialize(&versioned_transaction).unwrap(), bytes);
+        assert_eq!(
+            versioned_transaction.into_legacy_transaction(),
+            Some(transaction)
+        );
+    }
+
+    #[test]
+    fn test_v0_transaction() {
+        let payer = Keypair::new();
+        let signer = Keypair::new();
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![
+                AccountMeta::new_readonly(signer.pubkey(), true),
+                AccountMeta::new(Pubkey::new_unique(), false),
+            ],
+        );
+        let message =
+            v0::Message::try_compile(&payer.pubkey(), &[instruction], &[], Hash::new_unique())
+                .unwrap();
+
+        // Signers may be given in any order
+        let transaction = VersionedTransaction::try_new(
+            VersionedMessage::V0(message.clone()),
+            &[&signer, &payer],
+        )
+        .unwrap();
+        assert!(transaction.sanitize().is_ok());
+        assert!(transaction.verify().is_ok());
+        assert_eq!(transaction.verify_with_results(), vec![true, true]);
+
+        let bytes = bincode::serialize(&transaction).unwrap();
+        let deserialized: VersionedTransaction = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(deserialized, transaction);
+        assert!(deserialized.into_legacy_transaction().is_none());
+
+        assert_eq!(
+            VersionedTransaction::try_new(VersionedMessage::V0(message.clone()), &[&payer]),
+            Err(SignerError::NotEnoughSigners)
+        );
+        assert_eq!(
+            VersionedTransaction::try_new(
+                VersionedMessage::V0(message),
+                &[&payer, &Keypair::new()]
+            ),
+            Err(SignerError::KeypairPubkeyMismatch)
+        );
+    }
+
+    #[test]
+    fn test_sanitize_signatures() {
+        let payer = Keypair::new();
+        let message = Message::new(
+            &[system_instruction::transfer(
+                &payer.pubkey(),
+                &Pubkey::new_unique(),
+                1,
+            )],
+            Some(&payer.pubkey()),
+        );
+        let mut transaction =
+            VersionedTransaction::try_new(VersionedMessage::Legacy(message), &[&payer]).unwrap();
+        assert!(transaction.sanitize().is_ok());
+
+        transaction.signatures.clear();
+        assert_eq!(transaction.sanitize(), Err(SanitizeError::IndexOutOfBounds));
+    }
+}
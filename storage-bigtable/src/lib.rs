@@ -12,8 +12,8 @@ use solana_storage_proto::convert::generated;
 use solana_storage_proto::convert::tx_by_addr;
 use solana_transaction_status::{
     ConfirmedBlock, ConfirmedTransaction, ConfirmedTransactionStatusWithSignature, Reward,
-    TransactionByAddrInfo, TransactionConfirmationStatus, TransactionStatus, TransactionStatusMeta,
-    TransactionWithStatusMeta,
+    SignaturesForAddressFilter, TransactionByAddrInfo, TransactionConfirmationStatus,
+    TransactionStatus, TransactionStatusMeta, TransactionWithStatusMeta,
 };
 use std::{collections::HashMap, convert::TryInto};
 use thiserror::Error;
@@ -407,12 +407,14 @@ impl LedgerStorage {
     /// before_signature: start with the first signature older than this one
     /// until_signature: end with the last signature more recent than this one
     /// limit: stop after this many signatures; if limit==0, all records in the table will be read
+    /// filter: slot bounds and transaction status to restrict the listing to
     pub async fn get_confirmed_signatures_for_address(
         &self,
         address: &Pubkey,
         before_signature: Option<&Signature>,
         until_signature: Option<&Signature>,
         limit: usize,
+        filter: SignaturesForAddressFilter,
     ) -> Result<
         Vec<(
             ConfirmedTransactionStatusWithSignature,
@@ -422,31 +424,46 @@ impl LedgerStorage {
         let mut bigtable = self.connection.client();
         let address_prefix = format!("{}/", address);
 
-        // Figure out where to start listing from based on `before_signature`
+        // Figure out where to start listing from based on `before_signature`, or the `max_slot`
+        // bound if that is lower
+        let max_slot = filter.max_slot.unwrap_or(Slot::MAX);
         let (first_slot, before_transaction_index) = match before_signature {
-            None => (Slot::MAX, 0),
+            None => (max_slot, None),
             Some(before_signature) => {
                 let TransactionInfo { slot, index, .. } = bigtable
                     .get_bincode_cell("tx", before_signature.to_string())
                     .await?;
 
-                (slot, index)
+                if slot > max_slot {
+                    (max_slot, None)
+                } else {
+                    (slot, Some(index))
+                }
             }
         };
 
-        // Figure out where to end listing from based on `until_signature`
+        // Figure out where to end listing from based on `until_signature`, or the `min_slot`
+        // bound if that is higher
+        let min_slot = filter.min_slot.unwrap_or(0);
         let (last_slot, until_transaction_index) = match until_signature {
-            None => (0, u32::MAX),
+            None => (min_slot, None),
             Some(until_signature) => {
                 let TransactionInfo { slot, index, .. } = bigtable
                     .get_bincode_cell("tx", until_signature.to_string())
                     .await?;
 
-                (slot, index)
+                if slot < min_slot {
+                    (min_slot, None)
+                } else {
+                    (slot, Some(index))
+                }
             }
         };
 
         let mut infos = vec![];
+        if first_slot < last_slot {
+            return Ok(infos);
+        }
 
         let starting_slot_tx_len = bigtable
             .get_protobuf_or_bincode_cell::<Vec<LegacyTransactionByAddrInfo>, tx_by_addr::TransactionByAddr>(
@@ -462,70 +479,95 @@ impl LedgerStorage {
             })
             .unwrap_or(0);
 
-        // Return the next tx-by-addr data of amount `limit` plus extra to account for the largest
-        // number that might be flitered out
-        let tx_by_addr_data = bigtable
-            .get_row_data(
-                "tx-by-addr",
-                Some(format!("{}{}", address_prefix, slot_to_key(!first_slot))),
-                Some(format!("{}{}", address_prefix, slot_to_key(!last_slot))),
-                limit as i64 + starting_slot_tx_len as i64,
-            )
-            .await?;
-
-        'outer: for (row_key, data) in tx_by_addr_data {
-            let slot = !key_to_slot(&row_key[address_prefix.len()..]).ok_or_else(|| {
-                bigtable::Error::ObjectCorrupt(format!(
-                    "Failed to convert key to slot: tx-by-addr/{}",
-                    row_key
-                ))
-            })?;
-
-            let deserialized_cell_data = bigtable::deserialize_protobuf_or_bincode_cell_data::<
-                Vec<LegacyTransactionByAddrInfo>,
-                tx_by_addr::TransactionByAddr,
-            >(&data, "tx-by-addr", row_key.clone())?;
-
-            let mut cell_data: Vec<TransactionByAddrInfo> = match deserialized_cell_data {
-                bigtable::CellData::Bincode(tx_by_addr) => {
-                    tx_by_addr.into_iter().map(|legacy| legacy.into()).collect()
-                }
-                bigtable::CellData::Protobuf(tx_by_addr) => {
-                    tx_by_addr.try_into().map_err(|error| {
-                        bigtable::Error::ObjectCorrupt(format!(
-                            "Failed to deserialize: {}: tx-by-addr/{}",
-                            error,
-                            row_key.clone()
-                        ))
-                    })?
+        // Rows are listed in batches.  Without a status filter the first batch always has enough
+        // records, otherwise keep listing until `limit` records passed the filter.
+        let rows_limit = limit as i64 + starting_slot_tx_len as i64;
+        let mut start_slot = first_slot;
+        'outer: loop {
+            // Return the next tx-by-addr data of amount `limit` plus extra to account for the
+            // largest number that might be flitered out
+            let tx_by_addr_data = bigtable
+                .get_row_data(
+                    "tx-by-addr",
+                    Some(format!("{}{}", address_prefix, slot_to_key(!start_slot))),
+                    Some(format!("{}{}", address_prefix, slot_to_key(!last_slot))),
+                    rows_limit,
+                )
+                .await?;
+            let num_rows = tx_by_addr_data.len();
+            let mut slot = start_slot;
+
+            for (row_key, data) in tx_by_addr_data {
+                slot = !key_to_slot(&row_key[address_prefix.len()..]).ok_or_else(|| {
+                    bigtable::Error::ObjectCorrupt(format!(
+                        "Failed to convert key to slot: tx-by-addr/{}",
+                        row_key
+                    ))
+                })?;
+
+                let deserialized_cell_data =
+                    bigtable::deserialize_protobuf_or_bincode_cell_data::<
+                        Vec<LegacyTransactionByAddrInfo>,
+                        tx_by_addr::TransactionByAddr,
+                    >(&data, "tx-by-addr", row_key.clone())?;
+
+                let mut cell_data: Vec<TransactionByAddrInfo> = match deserialized_cell_data {
+                    bigtable::CellData::Bincode(tx_by_addr) => {
+                        tx_by_addr.into_iter().map(|legacy| legacy.into()).collect()
+                    }
+                    bigtable::CellData::Protobuf(tx_by_addr) => {
+                        tx_by_addr.try_into().map_err(|error| {
+                            bigtable::Error::ObjectCorrupt(format!(
+                                "Failed to deserialize: {}: tx-by-addr/{}",
+                                error,
+                                row_key.clone()
+                            ))
+                        })?
+                    }
+                };
+
+                cell_data.reverse();
+                for tx_by_addr_info in cell_data.into_iter() {
+                    // Filter out records before `before_transaction_index`
+                    if let Some(before_transaction_index) = before_transaction_index {
+                        if slot == first_slot && tx_by_addr_info.index >= before_transaction_index {
+                            continue;
+                        }
+                    }
+                    // Filter out records after `until_transaction_index`
+                    if let Some(until_transaction_index) = until_transaction_index {
+                        if slot == last_slot && tx_by_addr_info.index <= until_transaction_index {
+                            continue;
+                        }
+                    }
+                    if !filter.matches_status(&tx_by_addr_info.err) {
+                        continue;
+                    }
+                    infos.push((
+                        ConfirmedTransactionStatusWithSignature {
+                            signature: tx_by_addr_info.signature,
+                            slot,
+                            err: tx_by_addr_info.err,
+                            memo: tx_by_addr_info.memo,
+                            block_time: tx_by_addr_info.block_time,
+                        },
+                        tx_by_addr_info.index,
+                    ));
+                    // Respect limit
+                    if infos.len() >= limit {
+                        break 'outer;
+                    }
                 }
-            };
+            }
 
-            cell_data.reverse();
-            for tx_by_addr_info in cell_data.into_iter() {
-                // Filter out records before `before_transaction_index`
-                if slot == first_slot && tx_by_addr_info.index >= before_transaction_index {
-                    continue;
-                }
-                // Filter out records after `until_transaction_index`
-                if slot == last_slot && tx_by_addr_info.index <= until_transaction_index {
-                    continue;
-                }
-                infos.push((
-                    ConfirmedTransactionStatusWithSignature {
-                        signature: tx_by_addr_info.signature,
-                        slot,
-                        err: tx_by_addr_info.err,
-                        memo: tx_by_addr_info.memo,
-                        block_time: tx_by_addr_info.block_time,
-                    },
-                    tx_by_addr_info.index,
-                ));
-                // Respect limit
-                if infos.len() >= limit {
-                    break 'outer;
-                }
+            if filter.status.is_none()
+                || num_rows == 0
+                || (num_rows as i64) < rows_limit
+                || slot <= last_slot
+            {
+                break;
             }
+            start_slot = slot - 1;
         }
         Ok(infos)
     }
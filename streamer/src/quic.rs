@@ -0,0 +1,558 @@
+//! The `quic` module implements a QUIC server for the TPU.  Clients authenticate with a
+//! self-signed certificate of their identity keypair and send one transaction per
+//! unidirectional stream.  The number of streams a connection may open every second is
+//! weighted by the stake of the client, unstaked clients share a cap on open connections, and
+//! the received transactions are forwarded in packet batches, as the UDP receivers do.
+
+use crate::{
+    packet::{Packet, Packets, PacketsRecycler, PACKETS_PER_BATCH, PACKET_DATA_SIZE},
+    streamer::PacketSender,
+    tls_certificates::{get_pubkey_from_tls_certificate, new_self_signed_tls_certificate},
+};
+use futures_util::stream::StreamExt;
+use quinn::{
+    Connecting, Endpoint, EndpointError, Incoming, NewConnection, RecvStream, ServerConfig,
+    TransportConfig, VarInt,
+};
+use rcgen::RcgenError;
+use rustls::{
+    Certificate, ClientCertVerified, ClientCertVerifier, DistinguishedNames, ProtocolVersion,
+    TLSError,
+};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair};
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex, RwLock,
+    },
+    thread::{Builder, JoinHandle},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tokio::{runtime::Runtime, time::timeout};
+
+/// ALPN protocol id negotiated by TPU clients and servers
+pub const ALPN_TPU_PROTOCOL_ID: &[u8] = b"solana-tpu";
+
+/// Offset of the QUIC port from the UDP TPU port.  Nodes only accept transactions over QUIC on
+/// the port at this offset, so that the address of a node which does not serve QUIC is never
+/// taken for a QUIC address.
+pub const QUIC_PORT_OFFSET: u16 = 6;
+
+/// Maximum number of streams a client may have open at once on a connection
+pub const QUIC_MAX_CONCURRENT_STREAMS: u64 = 128;
+
+/// Maximum number of connections a client may have open at once
+pub const QUIC_MAX_CONNECTIONS_PER_PEER: usize = 8;
+
+/// Maximum number of connections open at once across all unstaked clients.  Unstaked clients
+/// can mint new identities at will, so the per peer limit alone does not bound them.
+pub const QUIC_MAX_UNSTAKED_CONNECTIONS: usize = 500;
+
+/// Number of streams per second accepted on a connection of an unstaked client
+pub const QUIC_MIN_STAKED_STREAMS_PER_SECOND: u64 = 128;
+
+/// Number of streams per second accepted on a connection of a client holding all of the stake
+pub const QUIC_MAX_STAKED_STREAMS_PER_SECOND: u64 = 128 * 1024;
+
+const STREAM_QUOTA_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long the server waits for a connection or stream before checking the exit flag
+const WAIT_FOR_EXIT_CHECK: Duration = Duration::from_secs(1);
+
+#[derive(Error, Debug)]
+pub enum QuicServerError {
+    #[error("certificate error: {0}")]
+    Certificate(#[from] RcgenError),
+
+    #[error("TLS error: {0}")]
+    Tls(#[from] TLSError),
+
+    #[error("endpoint error: {0}")]
+    Endpoint(#[from] EndpointError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Stakes of the nodes, which set the stream quotas of their connections
+#[derive(Debug, Default)]
+pub struct StakedNodes {
+    stakes: HashMap<Pubkey, u64>,
+    total_stake: u64,
+}
+
+impl StakedNodes {
+    pub fn new(stakes: HashMap<Pubkey, u64>) -> Self {
+        let total_stake = stakes.values().sum();
+        Self {
+            stakes,
+            total_stake,
+        }
+    }
+
+    pub fn is_staked(&self, pubkey: &Pubkey) -> bool {
+        self.stakes.get(pubkey).copied().unwrap_or_default() > 0
+    }
+
+    /// Number of streams per second a connection of `pubkey` may open
+    pub fn max_streams_per_second(&self, pubkey: &Pubkey) -> u64 {
+        let stake = self.stakes.get(pubkey).copied().unwrap_or_default();
+        if stake == 0 || self.total_stake == 0 {
+            return QUIC_MIN_STAKED_STREAMS_PER_SECOND;
+        }
+        let staked_streams =
+            u128::from(QUIC_MAX_STAKED_STREAMS_PER_SECOND - QUIC_MIN_STAKED_STREAMS_PER_SECOND)
+                * u128::from(stake)
+                / u128::from(self.total_stake);
+        QUIC_MIN_STAKED_STREAMS_PER_SECOND + staked_streams as u64
+    }
+}
+
+/// Numbers of open connections per client, and of open unstaked connections in total
+#[derive(Debug, Default)]
+struct ConnectionTable {
+    connections_per_peer: HashMap<Pubkey, usize>,
+    unstaked_connections: usize,
+}
+
+impl ConnectionTable {
+    /// Count a new connection of `pubkey`, unless the client or, for an unstaked client, all
+    /// unstaked clients together are at their limit
+    fn try_add(&mut self, pubkey: Pubkey, staked: bool) -> bool {
+        if !staked && self.unstaked_connections >= QUIC_MAX_UNSTAKED_CONNECTIONS {
+            return false;
+        }
+        let num_connections = self.connections_per_peer.entry(pubkey).or_default();
+        if *num_connections >= QUIC_MAX_CONNECTIONS_PER_PEER {
+            return false;
+        }
+        *num_connections += 1;
+        if !staked {
+            self.unstaked_connections += 1;
+        }
+        true
+    }
+
+    /// Release a connection counted by `try_add`
+    fn remove(&mut self, pubkey: &Pubkey, staked: bool) {
+        if let Some(num_connections) = self.connections_per_peer.get_mut(pubkey) {
+            *num_connections -= 1;
+            if *num_connections == 0 {
+                self.connections_per_peer.remove(pubkey);
+            }
+        }
+        if !staked {
+            self.unstaked_connections -= 1;
+        }
+    }
+}
+
+/// Accepts any certificate of an ed25519 key.  Clients are identified by the certificate key
+/// rather than by a certificate chain.
+struct ClientCertificateVerifier;
+
+impl ClientCertVerifier for ClientCertificateVerifier {
+    fn client_auth_root_subjects(
+        &self,
+        _sni: Option<&webpki::DNSName>,
+    ) -> Option<DistinguishedNames> {
+        Some(DistinguishedNames::new())
+    }
+
+    fn verify_client_cert(
+        &self,
+        presented_certs: &[Certificate],
+        _sni: Option<&webpki::DNSName>,
+    ) -> Result<ClientCertVerified, TLSError> {
+        presented_certs
+            .first()
+            .and_then(get_pubkey_from_tls_certificate)
+            .map(|_| ClientCertVerified::assertion())
+            .ok_or_else(|| TLSError::General("invalid client certificate".to_string()))
+    }
+}
+
+fn configure_server(keypair: &Keypair) -> Result<ServerConfig, QuicServerError> {
+    let (certificate, private_key) = new_self_signed_tls_certificate(keypair)?;
+    let mut crypto = rustls::ServerConfig::new(Arc::new(ClientCertificateVerifier));
+    crypto.versions = vec![ProtocolVersion::TLSv1_3];
+    crypto.set_single_cert(vec![certificate], private_key)?;
+    crypto.set_protocols(&[ALPN_TPU_PROTOCOL_ID.to_vec()]);
+
+    let mut transport = TransportConfig::default();
+    transport
+        .max_concurrent_uni_streams(QUIC_MAX_CONCURRENT_STREAMS)
+        .unwrap();
+    transport.max_concurrent_bidi_streams(0).unwrap();
+
+    let mut server_config = ServerConfig::default();
+    server_config.crypto = Arc::new(crypto);
+    server_config.transport = Arc::new(transport);
+    Ok(server_config)
+}
+
+/// Spawn a thread serving QUIC connections on `sock`, which forwards the received transactions
+/// to `packet_sender` in batches allocated from `recycler`
+pub fn spawn_server(
+    sock: UdpSocket,
+    keypair: &Keypair,
+    packet_sender: PacketSender,
+    recycler: PacketsRecycler,
+    exit: Arc<AtomicBool>,
+    staked_nodes: Arc<RwLock<StakedNodes>>,
+    coalesce_ms: u64,
+) -> Result<JoinHandle<()>, QuicServerError> {
+    let server_config = configure_server(keypair)?;
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .thread_name("sol-quic-server")
+        .enable_all()
+        .build()?;
+    let (endpoint, incoming) = {
+        let _guard = runtime.enter();
+        let mut builder = Endpoint::builder();
+        builder.listen(server_config);
+        builder.with_socket(sock)?
+    };
+
+    let (sender, receiver) = channel();
+    let batch_thread = Builder::new()
+        .name("solana-quic-batch".to_string())
+        .spawn(move || {
+            send_packet_batches(
+                receiver,
+                packet_sender,
+                recycler,
+                Duration::from_millis(coalesce_ms),
+            )
+        })
+        .unwrap();
+    Ok(Builder::new()
+        .name("solana-quic-server".to_string())
+        .spawn(move || {
+            run_server(&runtime, incoming, sender, exit, staked_nodes);
+            drop(endpoint);
+            // Dropping the runtime drops the connection tasks, and their packet senders along
+            // with them, so that the batch thread exits
+            drop(runtime);
+            batch_thread.join().unwrap();
+        })
+        .unwrap())
+}
+
+fn run_server(
+    runtime: &Runtime,
+    mut incoming: Incoming,
+    packet_sender: Sender<Packet>,
+    exit: Arc<AtomicBool>,
+    staked_nodes: Arc<RwLock<StakedNodes>>,
+) {
+    let connection_table = Arc::new(Mutex::new(ConnectionTable::default()));
+    runtime.block_on(async {
+        while !exit.load(Ordering::Relaxed) {
+            let connecting = match timeout(WAIT_FOR_EXIT_CHECK, incoming.next()).await {
+                Ok(Some(connecting)) => connecting,
+                Ok(None) => break,
+                Err(_) => continue,
+            };
+            tokio::spawn(handle_connection(
+                connecting,
+                packet_sender.clone(),
+                exit.clone(),
+                staked_nodes.clone(),
+                connection_table.clone(),
+            ));
+        }
+    });
+}
+
+async fn handle_connection(
+    connecting: Connecting,
+    packet_sender: Sender<Packet>,
+    exit: Arc<AtomicBool>,
+    staked_nodes: Arc<RwLock<StakedNodes>>,
+    connection_table: Arc<Mutex<ConnectionTable>>,
+) {
+    let NewConnection {
+        connection,
+        mut uni_streams,
+        ..
+    } = match connecting.await {
+        Ok(new_connection) => new_connection,
+        Err(err) => {
+            debug!("QUIC connection failed: {:?}", err);
+            return;
+        }
+    };
+    let pubkey = match connection.peer_identity().and_then(|certificates| {
+        certificates
+            .iter()
+            .next()
+            .and_then(get_pubkey_from_tls_certificate)
+    }) {
+        Some(pubkey) => pubkey,
+        None => {
+            connection.close(VarInt::from_u32(0), b"missing client certificate");
+            return;
+        }
+    };
+    // Keep the stake status the connection was counted with, as the stakes may change before
+    // it closes
+    let staked = staked_nodes.read().unwrap().is_staked(&pubkey);
+    if !connection_table.lock().unwrap().try_add(pubkey, staked) {
+        inc_new_counter_debug!("quic-server-connections-dropped", 1);
+        connection.close(VarInt::from_u32(0), b"too many connections");
+        return;
+    }
+
+    let remote_addr = connection.remote_address();
+    let mut quota_start = Instant::now();
+    let mut num_streams = 0;
+    while !exit.load(Ordering::Relaxed) {
+        let mut stream = match timeout(WAIT_FOR_EXIT_CHECK, uni_streams.next()).await {
+            Ok(Some(Ok(stream))) => stream,
+            Ok(Some(Err(_))) | Ok(None) => break,
+            Err(_) => continue,
+        };
+        if quota_start.elapsed() >= STREAM_QUOTA_INTERVAL {
+            quota_start = Instant::now();
+            num_streams = 0;
+        }
+        // Read the stakes on every stream, so that quotas follow the epoch stakes
+        let max_streams = staked_nodes.read().unwrap().max_streams_per_second(&pubkey);
+        if num_streams >= max_streams {
+            inc_new_counter_debug!("quic-server-streams-dropped", 1);
+            let _ = stream.stop(VarInt::from_u32(0));
+            continue;
+        }
+        num_streams += 1;
+        tokio::spawn(handle_stream(stream, remote_addr, packet_sender.clone()));
+    }
+
+    connection_table.lock().unwrap().remove(&pubkey, staked);
+}
+
+async fn handle_stream(stream: RecvStream, remote_addr: SocketAddr, packet_sender: Sender<Packet>) {
+    match stream.read_to_end(PACKET_DATA_SIZE).await {
+        Ok(data) => {
+            if let Some(packet) = new_packet(&data, &remote_addr) {
+                let _ = packet_sender.send(packet);
+            }
+        }
+        Err(err) => debug!("QUIC stream from {} failed: {:?}", remote_addr, err),
+    }
+}
+
+fn new_packet(data: &[u8], remote_addr: &SocketAddr) -> Option<Packet> {
+    if data.is_empty() || data.len() > PACKET_DATA_SIZE {
+        return None;
+    }
+    let mut packet = Packet::default();
+    packet.data[..data.len()].copy_from_slice(data);
+    packet.meta.size = data.len();
+    packet.meta.set_addr(remote_addr);
+    Some(packet)
+}
+
+/// Collect the received packets in batches of up to `PACKETS_PER_BATCH` packets, waiting at
+/// most `coalesce` for a batch to fill up
+fn send_packet_batches(
+    receiver: Receiver<Packet>,
+    packet_sender: PacketSender,
+    recycler: PacketsRecycler,
+    coalesce: Duration,
+) {
+    while let Ok(packet) = receiver.recv() {
+        let (mut packets, should_send) =
+            Packets::new_with_recycler(recycler.clone(), PACKETS_PER_BATCH)
+                .map(|allocated| (allocated, true))
+                .unwrap_or((Packets::with_capacity(PACKETS_PER_BATCH), false));
+        packets.packets.push(packet);
+        let start = Instant::now();
+        let mut disconnected = false;
+        while packets.packets.len() < PACKETS_PER_BATCH {
+            let remaining = match coalesce.checked_sub(start.elapsed()) {
+                Some(remaining) => remaining,
+                None => break,
+            };
+            match receiver.recv_timeout(remaining) {
+                Ok(packet) => packets.packets.push(packet),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    disconnected = true;
+                    break;
+                }
+            }
+        }
+        // As the UDP receivers do, drop the batch if the recycler is out of packets
+        if should_send {
+            inc_new_counter_debug!("quic-server-packets", packets.packets.len());
+            if packet_sender.send(packets).is_err() || disconnected {
+                return;
+            }
+        } else {
+            inc_new_counter_debug!("quic-server-packets-dropped", packets.packets.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls_certificates::QUIC_SERVER_NAME;
+    use rustls::{RootCertStore, ServerCertVerified, ServerCertVerifier};
+    use std::net::{IpAddr, Ipv4Addr};
+
+    struct SkipServerVerification;
+
+    impl ServerCertVerifier for SkipServerVerification {
+        fn verify_server_cert(
+            &self,
+            _roots: &RootCertStore,
+            _presented_certs: &[Certificate],
+            _dns_name: webpki::DNSNameRef,
+            _ocsp_response: &[u8],
+        ) -> Result<ServerCertVerified, TLSError> {
+            Ok(ServerCertVerified::assertion())
+        }
+    }
+
+    fn new_client_config(keypair: &Keypair) -> quinn::ClientConfig {
+        let (certificate, private_key) = new_self_signed_tls_certificate(keypair).unwrap();
+        let mut crypto = rustls::ClientConfig::new();
+        crypto.versions = vec![ProtocolVersion::TLSv1_3];
+        crypto
+            .dangerous()
+            .set_certificate_verifier(Arc::new(SkipServerVerification));
+        crypto
+            .set_single_client_cert(vec![certificate], private_key)
+            .unwrap();
+        crypto.set_protocols(&[ALPN_TPU_PROTOCOL_ID.to_vec()]);
+        let mut client_config = quinn::ClientConfig::default();
+        client_config.crypto = Arc::new(crypto);
+        client_config
+    }
+
+    #[test]
+    fn test_max_streams_per_second() {
+        let staked = Pubkey::new_unique();
+        let whale = Pubkey::new_unique();
+        let staked_nodes = StakedNodes::new(
+            vec![(staked, 1), (whale, 3), (Pubkey::new_unique(), 0)]
+                .into_iter()
+                .collect(),
+        );
+        assert_eq!(
+            staked_nodes.max_streams_per_second(&Pubkey::new_unique()),
+            QUIC_MIN_STAKED_STREAMS_PER_SECOND
+        );
+        let staked_streams =
+            QUIC_MAX_STAKED_STREAMS_PER_SECOND - QUIC_MIN_STAKED_STREAMS_PER_SECOND;
+        assert_eq!(
+            staked_nodes.max_streams_per_second(&staked),
+            QUIC_MIN_STAKED_STREAMS_PER_SECOND + staked_streams / 4
+        );
+        assert_eq!(
+            staked_nodes.max_streams_per_second(&whale),
+            QUIC_MIN_STAKED_STREAMS_PER_SECOND + staked_streams * 3 / 4
+        );
+        assert_eq!(
+            StakedNodes::default().max_streams_per_second(&staked),
+            QUIC_MIN_STAKED_STREAMS_PER_SECOND
+        );
+    }
+
+    #[test]
+    fn test_connection_table() {
+        let mut table = ConnectionTable::default();
+        let peer = Pubkey::new_unique();
+        for _ in 0..QUIC_MAX_CONNECTIONS_PER_PEER {
+            assert!(table.try_add(peer, false));
+        }
+        assert!(!table.try_add(peer, false));
+        assert_eq!(table.unstaked_connections, QUIC_MAX_CONNECTIONS_PER_PEER);
+
+        // Unstaked clients are capped in total, however many identities they use
+        while table.unstaked_connections < QUIC_MAX_UNSTAKED_CONNECTIONS {
+            assert!(table.try_add(Pubkey::new_unique(), false));
+        }
+        let unstaked = Pubkey::new_unique();
+        assert!(!table.try_add(unstaked, false));
+        assert!(!table.connections_per_peer.contains_key(&unstaked));
+
+        // Staked clients are not counted against the unstaked cap
+        let staked = Pubkey::new_unique();
+        assert!(table.try_add(staked, true));
+        assert_eq!(table.unstaked_connections, QUIC_MAX_UNSTAKED_CONNECTIONS);
+
+        table.remove(&peer, false);
+        assert_eq!(
+            table.connections_per_peer[&peer],
+            QUIC_MAX_CONNECTIONS_PER_PEER - 1
+        );
+        assert!(table.try_add(unstaked, false));
+        assert!(!table.try_add(Pubkey::new_unique(), false));
+
+        table.remove(&staked, true);
+        assert!(!table.connections_per_peer.contains_key(&staked));
+        assert_eq!(table.unstaked_connections, QUIC_MAX_UNSTAKED_CONNECTIONS);
+    }
+
+    #[test]
+    fn test_quic_server() {
+        solana_logger::setup();
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server_addr = sock.local_addr().unwrap();
+        let exit = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = channel();
+        let server = spawn_server(
+            sock,
+            &Keypair::new(),
+            sender,
+            PacketsRecycler::new_without_limit(""),
+            exit.clone(),
+            Arc::new(RwLock::new(StakedNodes::default())),
+            1,
+        )
+        .unwrap();
+
+        let client_keypair = Keypair::new();
+        let runtime = Runtime::new().unwrap();
+        let client_addr = runtime.block_on(async {
+            let mut builder = Endpoint::builder();
+            builder.default_client_config(new_client_config(&client_keypair));
+            let (endpoint, _) = builder
+                .bind(&SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0))
+                .unwrap();
+            let NewConnection { connection, .. } = endpoint
+                .connect(&server_addr, QUIC_SERVER_NAME)
+                .unwrap()
+                .await
+                .unwrap();
+            for i in 0..10u8 {
+                let mut stream = connection.open_uni().await.unwrap();
+                stream.write_all(&[i; 100]).await.unwrap();
+                stream.finish().await.unwrap();
+            }
+            endpoint.local_addr().unwrap()
+        });
+
+        let mut packets = vec![];
+        while packets.len() < 10 {
+            let batch = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+            packets.extend(batch.packets.iter().cloned());
+        }
+        packets.sort_by_key(|packet| packet.data[0]);
+        for (i, packet) in packets.iter().enumerate() {
+            assert_eq!(packet.meta.size, 100);
+            assert_eq!(&packet.data[..100], &[i as u8; 100][..]);
+            assert_eq!(packet.meta.addr().port(), client_addr.port());
+        }
+
+        exit.store(true, Ordering::Relaxed);
+        server.join().unwrap();
+    }
+}
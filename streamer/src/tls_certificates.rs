@@ -0,0 +1,66 @@
+//! The `tls_certificates` module creates self-signed TLS certificates of a node's identity
+//! keypair, so that QUIC peers are identified by the pubkey of their certificate.
+
+use rcgen::{Certificate, CertificateParams, RcgenError, PKCS_ED25519};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair};
+use x509_parser::parse_x509_certificate;
+
+/// Name the certificates are issued for.  Peers are identified by the certificate key, so the
+/// name is the same for every node.
+pub const QUIC_SERVER_NAME: &str = "solana-tpu";
+
+/// PKCS#8 v1 header of an ed25519 private key, followed by the 32 bytes of the secret key
+const ED25519_PKCS8_PREFIX: [u8; 16] = [
+    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+];
+
+/// Create a certificate of the keypair's public key, signed by the keypair
+pub fn new_self_signed_tls_certificate(
+    keypair: &Keypair,
+) -> Result<(rustls::Certificate, rustls::PrivateKey), RcgenError> {
+    let mut key_der = ED25519_PKCS8_PREFIX.to_vec();
+    key_der.extend_from_slice(keypair.secret().as_bytes());
+    let key_pair = rcgen::KeyPair::from_der(&key_der)?;
+
+    let mut params = CertificateParams::new(vec![QUIC_SERVER_NAME.to_string()]);
+    params.alg = &PKCS_ED25519;
+    params.key_pair = Some(key_pair);
+    let certificate = Certificate::from_params(params)?;
+    let certificate_der = certificate.serialize_der()?;
+    Ok((
+        rustls::Certificate(certificate_der),
+        rustls::PrivateKey(key_der),
+    ))
+}
+
+/// Return the pubkey of an ed25519 certificate, or `None` if the certificate can't be parsed
+/// or is not of an ed25519 key
+pub fn get_pubkey_from_tls_certificate(certificate: &rustls::Certificate) -> Option<Pubkey> {
+    let (_, certificate) = parse_x509_certificate(&certificate.0).ok()?;
+    let subject_public_key = &certificate.tbs_certificate.subject_pki.subject_public_key;
+    if subject_public_key.data.len() == std::mem::size_of::<Pubkey>() {
+        Some(Pubkey::new(subject_public_key.data))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signer;
+
+    #[test]
+    fn test_tls_certificate_pubkey() {
+        let keypair = Keypair::new();
+        let (certificate, _) = new_self_signed_tls_certificate(&keypair).unwrap();
+        assert_eq!(
+            get_pubkey_from_tls_certificate(&certificate),
+            Some(keypair.pubkey())
+        );
+        assert_eq!(
+            get_pubkey_from_tls_certificate(&rustls::Certificate(vec![0; 64])),
+            None
+        );
+    }
+}
@@ -24,11 +24,11 @@ use solana_sdk::{
     commitment_config::CommitmentConfig,
     deserialize_utils::default_on_eof,
     instruction::CompiledInstruction,
-    message::{Message, MessageHeader},
+    message::{v0::MessageAddressTableLookup, Message, MessageHeader, VersionedMessage},
     pubkey::Pubkey,
     sanitize::Sanitize,
     signature::Signature,
-    transaction::{Result, Transaction, TransactionError},
+    transaction::{Result, Transaction, TransactionError, VersionedTransaction},
 };
 use std::fmt;
 /// A duplicate representation of an Instruction for pretty JSON serialization
@@ -325,6 +325,38 @@ pub struct ConfirmedTransactionStatusWithSignature {
     pub block_time: Option<UnixTimestamp>,
 }
 
+/// Restricts an address signatures query to transactions that succeeded or failed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransactionStatusFilter {
+    Succeeded,
+    Failed,
+}
+
+impl TransactionStatusFilter {
+    pub fn matches(&self, err: &Option<TransactionError>) -> bool {
+        match self {
+            Self::Succeeded => err.is_none(),
+            Self::Failed => err.is_some(),
+        }
+    }
+}
+
+/// Slot bounds and a status filter for an address signatures query, applied on top of its
+/// `before` and `until` signatures.  Both slot bounds are inclusive.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SignaturesForAddressFilter {
+    pub min_slot: Option<Slot>,
+    pub max_slot: Option<Slot>,
+    pub status: Option<TransactionStatusFilter>,
+}
+
+impl SignaturesForAddressFilter {
+    pub fn matches_status(&self, err: &Option<TransactionError>) -> bool {
+        self.status.map_or(true, |status| status.matches(err))
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Reward {
@@ -362,6 +394,48 @@ impl ConfirmedBlock {
             block_time: self.block_time,
         }
     }
+
+    pub fn configure(
+        self,
+        encoding: UiTransactionEncoding,
+        transaction_details: TransactionDetails,
+        show_rewards: bool,
+    ) -> UiConfirmedBlock {
+        let (transactions, signatures) = match transaction_details {
+            TransactionDetails::Full => (
+                Some(
+                    self.transactions
+                        .into_iter()
+                        .map(|tx| tx.encode(encoding))
+                        .collect(),
+                ),
+                None,
+            ),
+            TransactionDetails::Signatures => (
+                None,
+                Some(
+                    self.transactions
+                        .into_iter()
+                        .map(|tx| tx.transaction.signatures[0].to_string())
+                        .collect(),
+                ),
+            ),
+            TransactionDetails::None => (None, None),
+        };
+        UiConfirmedBlock {
+            previous_blockhash: self.previous_blockhash,
+            blockhash: self.blockhash,
+            parent_slot: self.parent_slot,
+            transactions,
+            signatures,
+            rewards: if show_rewards {
+                Some(self.rewards)
+            } else {
+                None
+            },
+            block_time: self.block_time,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -375,6 +449,52 @@ pub struct EncodedConfirmedBlock {
     pub block_time: Option<UnixTimestamp>,
 }
 
+/// An `EncodedConfirmedBlock` with the transaction details and rewards made optional. With
+/// `TransactionDetails::Full` and rewards shown, it serializes identically to
+/// `EncodedConfirmedBlock`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiConfirmedBlock {
+    pub previous_blockhash: String,
+    pub blockhash: String,
+    pub parent_slot: Slot,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transactions: Option<Vec<EncodedTransactionWithStatusMeta>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signatures: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rewards: Option<Rewards>,
+    pub block_time: Option<UnixTimestamp>,
+}
+
+impl From<EncodedConfirmedBlock> for UiConfirmedBlock {
+    fn from(block: EncodedConfirmedBlock) -> Self {
+        Self {
+            previous_blockhash: block.previous_blockhash,
+            blockhash: block.blockhash,
+            parent_slot: block.parent_slot,
+            transactions: Some(block.transactions),
+            signatures: None,
+            rewards: Some(block.rewards),
+            block_time: block.block_time,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransactionDetails {
+    Full,
+    Signatures,
+    None,
+}
+
+impl Default for TransactionDetails {
+    fn default() -> Self {
+        Self::Full
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ConfirmedTransaction {
@@ -426,6 +546,28 @@ pub struct UiRawMessage {
     pub account_keys: Vec<String>,
     pub recent_blockhash: String,
     pub instructions: Vec<UiCompiledInstruction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_table_lookups: Option<Vec<UiAddressTableLookup>>,
+}
+
+/// A duplicate representation of a MessageAddressTableLookup, in raw format, for pretty JSON
+/// serialization
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UiAddressTableLookup {
+    pub account_key: String,
+    pub writable_indexes: Vec<u8>,
+    pub readonly_indexes: Vec<u8>,
+}
+
+impl From<&MessageAddressTableLookup> for UiAddressTableLookup {
+    fn from(lookup: &MessageAddressTableLookup) -> Self {
+        Self {
+            account_key: lookup.account_key.to_string(),
+            writable_indexes: lookup.writable_indexes.clone(),
+            readonly_indexes: lookup.readonly_indexes.clone(),
+        }
+    }
 }
 
 /// A duplicate representation of a Message, in parsed format, for pretty JSON serialization
@@ -455,7 +597,7 @@ impl TransactionWithStatusMeta {
     }
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EncodedTransactionWithStatusMeta {
     pub transaction: EncodedTransaction,
@@ -528,6 +670,7 @@ impl EncodedTransaction {
                             .iter()
                             .map(|instruction| instruction.into())
                             .collect(),
+                        address_table_lookups: None,
                     })
                 } else {
                     UiMessage::Parsed(UiParsedMessage {
@@ -554,8 +697,73 @@ impl EncodedTransaction {
             }
         }
     }
+    /// Encode a transaction which may have a versioned message.  Versioned messages can't be
+    /// parsed yet, so they are always encoded in raw format for `JsonParsed`.
+    pub fn encode_versioned(
+        transaction: VersionedTransaction,
+        encoding: UiTransactionEncoding,
+    ) -> Self {
+        let transaction = match transaction.message {
+            VersionedMessage::Legacy(message) => {
+                return Self::encode(
+                    Transaction {
+                        signatures: transaction.signatures,
+                        message,
+                    },
+                    encoding,
+                )
+            }
+            VersionedMessage::V0(_) => transaction,
+        };
+        match encoding {
+            UiTransactionEncoding::Binary => EncodedTransaction::LegacyBinary(
+                bs58::encode(bincode::serialize(&transaction).unwrap()).into_string(),
+            ),
+            UiTransactionEncoding::Base58 => EncodedTransaction::Binary(
+                bs58::encode(bincode::serialize(&transaction).unwrap()).into_string(),
+                encoding,
+            ),
+            UiTransactionEncoding::Base64 => EncodedTransaction::Binary(
+                base64::encode(bincode::serialize(&transaction).unwrap()),
+                encoding,
+            ),
+            UiTransactionEncoding::Json | UiTransactionEncoding::JsonParsed => {
+                let message = &transaction.message;
+                EncodedTransaction::Json(UiTransaction {
+                    signatures: transaction
+                        .signatures
+                        .iter()
+                        .map(|sig| sig.to_string())
+                        .collect(),
+                    message: UiMessage::Raw(UiRawMessage {
+                        header: message.header().clone(),
+                        account_keys: message
+                            .static_account_keys()
+                            .iter()
+                            .map(|pubkey| pubkey.to_string())
+                            .collect(),
+                        recent_blockhash: message.recent_blockhash().to_string(),
+                        instructions: message
+                            .instructions()
+                            .iter()
+                            .map(|instruction| instruction.into())
+                            .collect(),
+                        address_table_lookups: message.address_table_lookups().map(|lookups| {
+                            lookups.iter().map(UiAddressTableLookup::from).collect()
+                        }),
+                    }),
+                })
+            }
+        }
+    }
+
     pub fn decode(&self) -> Option<Transaction> {
-        let transaction: Option<Transaction> = match self {
+        self.decode_versioned()
+            .and_then(|transaction| transaction.into_legacy_transaction())
+    }
+
+    pub fn decode_versioned(&self) -> Option<VersionedTransaction> {
+        let transaction: Option<VersionedTransaction> = match self {
             EncodedTransaction::Json(_) => None,
             EncodedTransaction::LegacyBinary(blob) => bs58::decode(blob)
                 .into_vec()
@@ -592,6 +800,7 @@ pub struct TransactionByAddrInfo {
 #[cfg(test)]
 mod test {
     use super::*;
+    use solana_sdk::message::v0;
 
     #[test]
     fn test_decode_invalid_transaction() {
@@ -607,6 +816,48 @@ mod test {
         assert!(unsanitary_transaction.decode().is_none());
     }
 
+    #[test]
+    fn test_encode_versioned_transaction() {
+        let lookup = MessageAddressTableLookup {
+            account_key: Pubkey::new_unique(),
+            writable_indexes: vec![0],
+            readonly_indexes: vec![1],
+        };
+        let transaction = VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::V0(v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+                instructions: vec![CompiledInstruction::new(1, &(), vec![0, 2, 3])],
+                address_table_lookups: vec![lookup.clone()],
+                ..v0::Message::default()
+            }),
+        };
+
+        let encoded = EncodedTransaction::encode_versioned(
+            transaction.clone(),
+            UiTransactionEncoding::Base64,
+        );
+        assert_eq!(encoded.decode_versioned(), Some(transaction.clone()));
+        // Versioned transactions can't be decoded as legacy transactions
+        assert_eq!(encoded.decode(), None);
+
+        match EncodedTransaction::encode_versioned(transaction, UiTransactionEncoding::JsonParsed) {
+            EncodedTransaction::Json(UiTransaction {
+                message: UiMessage::Raw(message),
+                ..
+            }) => assert_eq!(
+                message.address_table_lookups,
+                Some(vec![UiAddressTableLookup::from(&lookup)])
+            ),
+            encoded => panic!("unexpected encoding: {:?}", encoded),
+        }
+    }
+
     #[test]
     fn test_satisfies_commitment() {
         let status = TransactionStatus {
@@ -685,4 +936,50 @@ mod test {
         };
         assert!(status.satisfies_commitment(CommitmentConfig::confirmed()));
     }
+
+    #[test]
+    fn test_configure_confirmed_block() {
+        let transaction = Transaction {
+            signatures: vec![Signature::new(&[1u8; 64])],
+            message: Message::default(),
+        };
+        let block = || ConfirmedBlock {
+            previous_blockhash: "previous".to_string(),
+            blockhash: "blockhash".to_string(),
+            parent_slot: 1,
+            transactions: vec![TransactionWithStatusMeta {
+                transaction: transaction.clone(),
+                meta: None,
+            }],
+            rewards: vec![],
+            block_time: Some(42),
+        };
+
+        let full = block().configure(UiTransactionEncoding::Json, TransactionDetails::Full, true);
+        assert_eq!(
+            full,
+            UiConfirmedBlock::from(block().encode(UiTransactionEncoding::Json))
+        );
+        assert_eq!(
+            serde_json::to_value(&full).unwrap(),
+            serde_json::to_value(&block().encode(UiTransactionEncoding::Json)).unwrap()
+        );
+
+        let signatures = block().configure(
+            UiTransactionEncoding::Json,
+            TransactionDetails::Signatures,
+            false,
+        );
+        assert_eq!(signatures.transactions, None);
+        assert_eq!(
+            signatures.signatures,
+            Some(vec![transaction.signatures[0].to_string()])
+        );
+        assert_eq!(signatures.rewards, None);
+
+        let none = block().configure(UiTransactionEncoding::Json, TransactionDetails::None, true);
+        assert_eq!(none.transactions, None);
+        assert_eq!(none.signatures, None);
+        assert_eq!(none.rewards, Some(vec![]));
+    }
 }
@@ -0,0 +1,375 @@
+//! Simulates the propagation of shreds through turbine trees over synthetic stake
+//! distributions, and reports how deep in the tree nodes receive the shreds and how many
+//! packets each node sends.
+#![allow(clippy::integer_arithmetic)]
+use clap::{
+    crate_description, crate_name, value_t_or_exit, values_t_or_exit, App, Arg, ArgMatches,
+};
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    Rng,
+};
+use solana_core::{
+    cluster_info::{compute_retransmit_peers, DATA_PLANE_FANOUT},
+    turbine_tree::{
+        fanout_for_num_layers, num_layers, sort_nodes, LegacyTurbineTree,
+        StakeWeightedSlotTurbineTree, TurbineNode, TurbineTree,
+    },
+};
+use solana_sdk::{clock::Slot, pubkey};
+use std::{collections::VecDeque, iter::repeat_with};
+
+const TREES: &[&str] = &["legacy", "stake-weighted-slot"];
+const STAKE_DISTRIBUTIONS: &[&str] = &["equal", "uniform", "power-law"];
+
+#[derive(Clone, Copy, Debug)]
+enum StakeDistribution {
+    Equal,
+    Uniform,
+    PowerLaw(f64),
+}
+
+fn new_nodes<R: Rng>(
+    rng: &mut R,
+    num_staked_nodes: usize,
+    num_unstaked_nodes: usize,
+    distribution: StakeDistribution,
+) -> Vec<TurbineNode> {
+    let mut nodes: Vec<_> = (0..num_staked_nodes)
+        .map(|k| {
+            let stake = match distribution {
+                StakeDistribution::Equal => 1_000_000,
+                StakeDistribution::Uniform => rng.gen_range(1, 1_000_000),
+                StakeDistribution::PowerLaw(exponent) => {
+                    (1_000_000_000.0 / ((k + 1) as f64).powf(exponent)) as u64
+                }
+            };
+            TurbineNode {
+                id: pubkey::new_rand(),
+                stake,
+            }
+        })
+        .collect();
+    nodes.extend(
+        repeat_with(|| TurbineNode {
+            id: pubkey::new_rand(),
+            stake: 0,
+        })
+        .take(num_unstaked_nodes),
+    );
+    sort_nodes(&mut nodes);
+    nodes
+}
+
+struct PropagationStats {
+    num_shreds: usize,
+    // Number of times a node received a shred at each depth, where the root is at depth 1.
+    depths: Vec<usize>,
+    stake_weighted_depth: f64,
+    num_missed: usize,
+    // Packets sent and received by each node, indexed the same as the nodes.
+    sent: Vec<u64>,
+    received: Vec<u64>,
+}
+
+impl PropagationStats {
+    fn new(num_nodes: usize) -> Self {
+        Self {
+            num_shreds: 0,
+            depths: Vec::default(),
+            stake_weighted_depth: 0.0,
+            num_missed: 0,
+            sent: vec![0; num_nodes],
+            received: vec![0; num_nodes],
+        }
+    }
+
+    fn depth_percentile(&self, percentile: usize) -> usize {
+        let total: usize = self.depths.iter().sum();
+        let mut count = 0;
+        for (depth, num) in self.depths.iter().enumerate() {
+            count += num;
+            if count * 100 >= total * percentile {
+                return depth;
+            }
+        }
+        0
+    }
+}
+
+// Propagates a shred from the leader through the tree, the same way retransmit_stage does:
+// a node which receives a shred directly from its parent retransmits it to its neighbors and
+// children, whereas a node which received a forwarded shred retransmits it only to its
+// children, marked as forwarded. Nodes retransmit a shred only the first time they receive
+// it, and never to the slot leader.
+fn propagate(
+    tree: &dyn TurbineTree,
+    leader: usize,
+    slot: Slot,
+    shred_seed: [u8; 32],
+    nodes: &[TurbineNode],
+    stats: &mut PropagationStats,
+) {
+    let leader_id = nodes[leader].id;
+    let shuffle = tree.shuffle(Some(&leader_id), slot, shred_seed, nodes);
+    let mut positions = vec![None; nodes.len()];
+    for (position, index) in shuffle.iter().enumerate() {
+        positions[*index] = Some(position);
+    }
+    let mut depths = vec![None; nodes.len()];
+    let mut queue = VecDeque::new();
+    if let Some(root) = tree.root(Some(&leader_id), slot, shred_seed, nodes) {
+        stats.sent[leader] += 1;
+        queue.push_back((root, /*forwarded:*/ false, /*depth:*/ 1));
+    }
+    while let Some((node, forwarded, depth)) = queue.pop_front() {
+        stats.received[node] += 1;
+        if depths[node].is_some() {
+            continue;
+        }
+        depths[node] = Some(depth);
+        let (neighbors, children) =
+            compute_retransmit_peers(tree.fanout(), positions[node].unwrap(), &shuffle);
+        let neighbors = if forwarded { Vec::default() } else { neighbors };
+        let peers: Vec<_> = neighbors
+            .into_iter()
+            .filter(|index| *index != node)
+            .map(|index| (index, true))
+            .chain(children.into_iter().map(|index| (index, forwarded)))
+            .filter(|(index, _)| *index != leader)
+            .collect();
+        stats.sent[node] += peers.len() as u64;
+        queue.extend(
+            peers
+                .into_iter()
+                .map(|(index, forwarded)| (index, forwarded, depth + 1)),
+        );
+    }
+    let total_stake: u64 = nodes.iter().map(|node| node.stake).sum();
+    stats.num_shreds += 1;
+    for (index, depth) in depths.into_iter().enumerate() {
+        if index == leader {
+            continue;
+        }
+        match depth {
+            None => stats.num_missed += 1,
+            Some(depth) => {
+                if stats.depths.len() <= depth {
+                    stats.depths.resize(depth + 1, 0);
+                }
+                stats.depths[depth] += 1;
+                stats.stake_weighted_depth +=
+                    depth as f64 * nodes[index].stake as f64 / total_stake as f64;
+            }
+        }
+    }
+}
+
+fn print_stats(
+    name: &str,
+    tree: &dyn TurbineTree,
+    nodes: &[TurbineNode],
+    stats: &PropagationStats,
+) {
+    let num_shreds = stats.num_shreds as f64;
+    let num_receptions: usize = stats.depths.iter().sum();
+    let mean_depth = stats
+        .depths
+        .iter()
+        .enumerate()
+        .map(|(depth, num)| depth * num)
+        .sum::<usize>() as f64
+        / num_receptions.max(1) as f64;
+    println!(
+        "{}: fanout: {}, layers: {}",
+        name,
+        tree.fanout(),
+        num_layers(tree.fanout(), nodes.len())
+    );
+    println!(
+        "  coverage: {:.4}%, missed: {}",
+        100.0 * num_receptions as f64 / (num_receptions + stats.num_missed).max(1) as f64,
+        stats.num_missed
+    );
+    println!(
+        "  depth: mean: {:.3}, stake weighted mean: {:.3}, p50: {}, p90: {}, p99: {}, max: {}",
+        mean_depth,
+        stats.stake_weighted_depth / num_shreds,
+        stats.depth_percentile(50),
+        stats.depth_percentile(90),
+        stats.depth_percentile(99),
+        stats.depths.len().saturating_sub(1),
+    );
+    // Nodes are sorted by stake, so the first decile is the highest staked nodes.
+    let decile = (nodes.len() / 10).max(1);
+    let mean = |load: &[u64]| load.iter().sum::<u64>() as f64 / num_shreds / load.len() as f64;
+    let max = |load: &[u64]| *load.iter().max().unwrap_or(&0) as f64 / num_shreds;
+    for (what, load) in [("sent", &stats.sent), ("received", &stats.received)].iter() {
+        let load = &load[..];
+        println!(
+            "  packets {} per shred: mean: {:.3}, max: {:.3}, top stake decile mean: {:.3}, bottom stake decile mean: {:.3}",
+            what,
+            mean(load),
+            max(load),
+            mean(&load[..decile]),
+            mean(&load[load.len() - decile..]),
+        );
+    }
+}
+
+fn parse_stake_distribution(matches: &ArgMatches) -> StakeDistribution {
+    match matches.value_of("stake_distribution").unwrap() {
+        "equal" => StakeDistribution::Equal,
+        "uniform" => StakeDistribution::Uniform,
+        "power-law" => {
+            StakeDistribution::PowerLaw(value_t_or_exit!(matches, "power_law_exponent", f64))
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn new_tree(name: &str, fanout: usize) -> Box<dyn TurbineTree> {
+    match name {
+        "legacy" => Box::new(LegacyTurbineTree::new(fanout)),
+        "stake-weighted-slot" => Box::new(StakeWeightedSlotTurbineTree::new(fanout)),
+        _ => unreachable!(),
+    }
+}
+
+fn main() {
+    solana_logger::setup();
+
+    let fanout = DATA_PLANE_FANOUT.to_string();
+    let matches = App::new(crate_name!())
+        .about(crate_description!())
+        .version(solana_version::version!())
+        .arg(
+            Arg::with_name("num_nodes")
+                .long("num-nodes")
+                .value_name("NUM")
+                .takes_value(true)
+                .default_value("2000")
+                .help("Number of staked nodes"),
+        )
+        .arg(
+            Arg::with_name("num_unstaked_nodes")
+                .long("num-unstaked-nodes")
+                .value_name("NUM")
+                .takes_value(true)
+                .default_value("0")
+                .help("Number of additional nodes without stake"),
+        )
+        .arg(
+            Arg::with_name("stake_distribution")
+                .long("stake-distribution")
+                .value_name("DISTRIBUTION")
+                .takes_value(true)
+                .possible_values(STAKE_DISTRIBUTIONS)
+                .default_value("power-law")
+                .help("Distribution of the stakes of the staked nodes"),
+        )
+        .arg(
+            Arg::with_name("power_law_exponent")
+                .long("power-law-exponent")
+                .value_name("EXPONENT")
+                .takes_value(true)
+                .default_value("1.0")
+                .help("Stake of the k-th largest node is proportional to 1/k^EXPONENT"),
+        )
+        .arg(
+            Arg::with_name("tree")
+                .long("tree")
+                .value_name("TREE")
+                .takes_value(true)
+                .multiple(true)
+                .possible_values(TREES)
+                .help("Turbine trees to simulate [default: all]"),
+        )
+        .arg(
+            Arg::with_name("fanout")
+                .long("fanout")
+                .value_name("NUM")
+                .takes_value(true)
+                .default_value(&fanout)
+                .help("Neighborhood size of the trees"),
+        )
+        .arg(
+            Arg::with_name("num_layers")
+                .long("num-layers")
+                .value_name("NUM")
+                .takes_value(true)
+                .conflicts_with("fanout")
+                .help("Use the smallest fanout which fits all nodes in this many layers"),
+        )
+        .arg(
+            Arg::with_name("num_slots")
+                .long("num-slots")
+                .value_name("NUM")
+                .takes_value(true)
+                .default_value("8")
+                .help("Number of slots to simulate, each with a stake weighted random leader"),
+        )
+        .arg(
+            Arg::with_name("num_shreds_per_slot")
+                .long("num-shreds-per-slot")
+                .value_name("NUM")
+                .takes_value(true)
+                .default_value("64")
+                .help("Number of shreds to propagate in each slot"),
+        )
+        .get_matches();
+
+    let num_staked_nodes = value_t_or_exit!(matches, "num_nodes", usize);
+    let num_unstaked_nodes = value_t_or_exit!(matches, "num_unstaked_nodes", usize);
+    let distribution = parse_stake_distribution(&matches);
+    let num_slots = value_t_or_exit!(matches, "num_slots", Slot);
+    let num_shreds_per_slot = value_t_or_exit!(matches, "num_shreds_per_slot", usize);
+    let num_nodes = num_staked_nodes + num_unstaked_nodes;
+    if num_nodes < 2 {
+        eprintln!("error: simulating a cluster requires at least 2 nodes");
+        std::process::exit(1);
+    }
+    let fanout = if matches.is_present("num_layers") {
+        fanout_for_num_layers(value_t_or_exit!(matches, "num_layers", usize), num_nodes)
+    } else {
+        value_t_or_exit!(matches, "fanout", usize)
+    };
+    let trees = if matches.is_present("tree") {
+        values_t_or_exit!(matches, "tree", String)
+    } else {
+        TREES.iter().map(|tree| tree.to_string()).collect()
+    };
+
+    let mut rng = rand::thread_rng();
+    let nodes = new_nodes(&mut rng, num_staked_nodes, num_unstaked_nodes, distribution);
+    let leaders = WeightedIndex::new(nodes.iter().map(|node| node.stake)).unwrap();
+    let leaders: Vec<usize> = (0..num_slots).map(|_| leaders.sample(&mut rng)).collect();
+    let shred_seeds: Vec<Vec<[u8; 32]>> = (0..num_slots)
+        .map(|_| {
+            repeat_with(|| rng.gen())
+                .take(num_shreds_per_slot)
+                .collect()
+        })
+        .collect();
+    println!(
+        "nodes: {} ({} staked, {:?} stakes), slots: {}, shreds per slot: {}",
+        num_nodes, num_staked_nodes, distribution, num_slots, num_shreds_per_slot
+    );
+    for name in trees {
+        let tree = new_tree(&name, fanout);
+        let mut stats = PropagationStats::new(num_nodes);
+        for (slot, (leader, shred_seeds)) in leaders.iter().zip(&shred_seeds).enumerate() {
+            for shred_seed in shred_seeds {
+                propagate(
+                    tree.as_ref(),
+                    *leader,
+                    slot as Slot,
+                    *shred_seed,
+                    &nodes,
+                    &mut stats,
+                );
+            }
+        }
+        print_stats(&name, tree.as_ref(), &nodes, &stats);
+    }
+}
@@ -33,12 +33,20 @@ use {
             is_snapshot_config_invalid, Validator, ValidatorConfig, ValidatorStartProgress,
         },
     },
-    solana_download_utils::{download_genesis_if_missing, download_snapshot},
-    solana_ledger::blockstore_db::BlockstoreRecoveryMode,
+    solana_download_utils::{
+        download_genesis_if_missing, download_incremental_snapshot, download_snapshot,
+    },
+    solana_ledger::blockstore_db::{
+        column_names, BlockstoreCompressionType, BlockstoreRecoveryMode, ColumnOptions,
+    },
     solana_perf::recycler::enable_recycler_warming,
     solana_runtime::{
-        accounts_index::AccountIndex,
+        accounts_index::{
+            AccountIndex, AccountSecondaryIndexes, AccountSecondaryIndexesIncludeExclude,
+            AccountsIndexConfig, CustomIndex, DEFAULT_ACCOUNTS_INDEX_MAX_IN_MEMORY_ENTRIES,
+        },
         bank_forks::{ArchiveFormat, SnapshotConfig, SnapshotVersion},
+        cold_storage::DEFAULT_COLD_STORAGE_CACHE_SIZE,
         hardened_unpack::{unpack_genesis_archive, MAX_GENESIS_ARCHIVE_UNPACKED_SIZE},
         snapshot_utils::get_highest_snapshot_archive_path,
     },
@@ -55,7 +63,7 @@ use {
         redirect_stderr_to_file,
     },
     std::{
-        collections::{HashSet, VecDeque},
+        collections::{HashMap, HashSet, VecDeque},
         env,
         fs::{self, File},
         net::{IpAddr, SocketAddr, TcpListener, UdpSocket},
@@ -273,6 +281,34 @@ fn hash_validator(hash: String) -> Result<(), String> {
         .map_err(|e| format!("{:?}", e))
 }
 
+/// Split a `COLUMN:VALUE` argument, checking that COLUMN names a ledger column
+fn parse_ledger_column_arg(arg: &str) -> Result<(&str, &str), String> {
+    let mut parts = arg.splitn(2, ':');
+    match (parts.next(), parts.next()) {
+        (Some(column), Some(value)) if column_names().contains(&column) => Ok((column, value)),
+        (Some(column), Some(_)) => Err(format!(
+            "Unknown ledger column {}, expected one of: {}",
+            column,
+            column_names().join(", ")
+        )),
+        _ => Err(format!("Expected COLUMN:VALUE, got {}", arg)),
+    }
+}
+
+fn ledger_column_compression_validator(arg: String) -> Result<(), String> {
+    let (_, compression_type) = parse_ledger_column_arg(&arg)?;
+    BlockstoreCompressionType::from_str(compression_type).map(|_| ())
+}
+
+fn ledger_column_block_size_validator(arg: String) -> Result<(), String> {
+    let (_, block_size) = parse_ledger_column_arg(&arg)?;
+    match usize::from_str(block_size) {
+        Ok(0) => Err("Block size must be greater than zero".to_string()),
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("Invalid block size {}: {}", block_size, err)),
+    }
+}
+
 fn is_trusted_validator(id: &Pubkey, trusted_validators: &Option<HashSet<Pubkey>>) -> bool {
     if let Some(trusted_validators) = trusted_validators {
         trusted_validators.contains(id)
@@ -607,6 +643,26 @@ fn validators_set(
     }
 }
 
+/// Reads the keys of an `--account-index-include-keys` or `--account-index-exclude-keys`
+/// file, one pubkey per line. Blank lines and lines starting with `#` are ignored.
+fn account_index_keys_from_file(path: &str) -> HashSet<Pubkey> {
+    let contents = fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("Unable to read account index keys from {}: {}", path, err);
+        exit(1);
+    });
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            Pubkey::from_str(line).unwrap_or_else(|err| {
+                eprintln!("Invalid account index key {} in {}: {}", line, path, err);
+                exit(1);
+            })
+        })
+        .collect()
+}
+
 fn check_genesis_hash(
     genesis_config: &GenesisConfig,
     expected_genesis_hash: Option<Hash>,
@@ -691,6 +747,9 @@ fn verify_reachable_ports(
     if ContactInfo::is_valid_address(&node.info.tpu_forwards) {
         udp_sockets.extend(node.sockets.tpu_forwards.iter());
     }
+    if validator_config.tpu_use_quic && ContactInfo::is_valid_address(&node.info.tpu_quic) {
+        udp_sockets.push(&node.sockets.tpu_quic);
+    }
     if ContactInfo::is_valid_address(&node.info.tvu) {
         udp_sockets.extend(node.sockets.tvu.iter());
         udp_sockets.extend(node.sockets.broadcast.iter());
@@ -736,6 +795,7 @@ fn verify_reachable_ports(
 struct RpcBootstrapConfig {
     no_genesis_fetch: bool,
     no_snapshot_fetch: bool,
+    incremental_snapshot_fetch: bool,
     no_untrusted_rpc: bool,
     max_genesis_archive_unpacked_size: u64,
     no_check_vote_account: bool,
@@ -746,6 +806,7 @@ impl Default for RpcBootstrapConfig {
         Self {
             no_genesis_fetch: true,
             no_snapshot_fetch: true,
+            incremental_snapshot_fetch: false,
             no_untrusted_rpc: true,
             max_genesis_archive_unpacked_size: MAX_GENESIS_ARCHIVE_UNPACKED_SIZE,
             no_check_vote_account: true,
@@ -862,7 +923,7 @@ fn rpc_bootstrap(
             }
 
             if let Some(snapshot_hash) = snapshot_hash {
-                let mut use_local_snapshot = false;
+                let mut local_snapshot_slot = None;
 
                 if let Some(highest_local_snapshot_slot) =
                     get_highest_snapshot_archive_path(ledger_path)
@@ -876,7 +937,7 @@ fn rpc_bootstrap(
                                of downloading a snapshot for slot {}",
                             highest_local_snapshot_slot, snapshot_hash.0
                         );
-                        use_local_snapshot = true;
+                        local_snapshot_slot = Some(highest_local_snapshot_slot);
                     } else {
                         info!(
                             "Local snapshot from slot {} is too old. \
@@ -886,8 +947,8 @@ fn rpc_bootstrap(
                     }
                 }
 
-                if use_local_snapshot {
-                    Ok(())
+                let full_snapshot_slot = if let Some(local_snapshot_slot) = local_snapshot_slot {
+                    Ok(local_snapshot_slot)
                 } else {
                     rpc_client
                         .get_slot_with_commitment(CommitmentConfig::finalized())
@@ -910,9 +971,26 @@ fn rpc_bootstrap(
                                 use_progress_bar,
                             );
                             gossip_service.join().unwrap();
-                            ret
+                            ret.map(|_| snapshot_hash.0)
                         })
-                }
+                };
+
+                full_snapshot_slot.map(|full_snapshot_slot| {
+                    if bootstrap_config.incremental_snapshot_fetch {
+                        // Incremental snapshot hashes aren't advertised in gossip, so unlike
+                        // the full snapshot nothing vouches for this one; it is only fetched
+                        // when asked for.  It is an optimization only, so fall back to
+                        // replaying from the full snapshot if one isn't available
+                        if let Err(err) = download_incremental_snapshot(
+                            &rpc_contact_info.rpc,
+                            &ledger_path,
+                            full_snapshot_slot,
+                            use_progress_bar,
+                        ) {
+                            warn!("Unable to download incremental snapshot: {}", err);
+                        }
+                    }
+                })
             } else {
                 Ok(())
             }
@@ -984,6 +1062,9 @@ pub fn main() {
         .send_transaction_leader_forward_count
         .to_string();
     let default_rpc_threads = num_cpus::get().to_string();
+    let default_accounts_index_max_in_memory_entries =
+        &DEFAULT_ACCOUNTS_INDEX_MAX_IN_MEMORY_ENTRIES.to_string();
+    let default_accounts_cold_storage_cache_size = &DEFAULT_COLD_STORAGE_CACHE_SIZE.to_string();
 
     let matches = App::new(crate_name!()).about(crate_description!())
         .version(solana_version::version!())
@@ -1062,6 +1143,15 @@ pub fn main() {
                 .help("Do not attempt to fetch a snapshot from the cluster, \
                       start from a local snapshot if present"),
         )
+        .arg(
+            Arg::with_name("incremental_snapshot_fetch")
+                .long("incremental-snapshot-fetch")
+                .takes_value(false)
+                .help("Also fetch an incremental snapshot on top of the full snapshot from \
+                      the RPC node. Unlike full snapshots, its hash is not checked against \
+                      the trusted validators, so the RPC node is trusted with the account \
+                      state it contains"),
+        )
         .arg(
             Arg::with_name("no_genesis_fetch")
                 .long("no-genesis-fetch")
@@ -1259,6 +1349,15 @@ pub fn main() {
                 .help("Number of slots between generating snapshots, \
                       0 to disable snapshots"),
         )
+        .arg(
+            Arg::with_name("incremental_snapshot_interval_slots")
+                .long("incremental-snapshot-interval-slots")
+                .value_name("NUMBER_OF_SLOTS")
+                .takes_value(true)
+                .default_value("0")
+                .help("Number of slots between generating incremental snapshots \
+                      on top of the last full snapshot, 0 to disable incremental snapshots"),
+        )
         .arg(
             Arg::with_name("contact_debug_interval")
                 .long("contact-debug-interval")
@@ -1436,6 +1535,12 @@ pub fn main() {
                 .validator(is_parsable::<u64>)
                 .help("Milliseconds to wait in the TPU receiver for packet coalescing."),
         )
+        .arg(
+            Arg::with_name("tpu_use_quic")
+                .long("tpu-use-quic")
+                .takes_value(false)
+                .help("Also receive transactions over QUIC, on a TPU port advertised in gossip"),
+        )
         .arg(
             Arg::with_name("rocksdb_max_compaction_jitter")
                 .long("rocksdb-max-compaction-jitter-slots")
@@ -1484,6 +1589,12 @@ pub fn main() {
                 .takes_value(false)
                 .help("Enable the unstable RPC PubSub `voteSubscribe` subscription"),
         )
+        .arg(
+            Arg::with_name("rpc_pubsub_enable_block_subscription")
+                .long("rpc-pubsub-enable-block-subscription")
+                .takes_value(false)
+                .help("Enable the unstable RPC PubSub `blockSubscribe` subscription"),
+        )
         .arg(
             Arg::with_name("rpc_pubsub_max_connections")
                 .long("rpc-pubsub-max-connections")
@@ -1541,6 +1652,12 @@ pub fn main() {
                 .default_value(&default_rpc_send_transaction_leader_forward_count)
                 .help("The number of upcoming leaders to which to forward transactions sent via rpc service."),
         )
+        .arg(
+            Arg::with_name("rpc_send_transaction_use_quic")
+                .long("rpc-send-transaction-use-quic")
+                .takes_value(false)
+                .help("Send transactions sent via rpc service over QUIC to the leaders that accept it."),
+        )
         .arg(
             Arg::with_name("halt_on_trusted_validators_accounts_hash_mismatch")
                 .long("halt-on-trusted-validators-accounts-hash-mismatch")
@@ -1593,6 +1710,30 @@ pub fn main() {
                     "Mode to recovery the ledger db write ahead log."
                 ),
         )
+        .arg(
+            Arg::with_name("ledger_column_compression")
+                .long("ledger-column-compression")
+                .value_name("COLUMN:TYPE")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .validator(ledger_column_compression_validator)
+                .help("Compress the ledger column COLUMN with TYPE, one of none, lz4 or zstd, \
+                       for example data_shred:lz4.  Only newly written data is affected. \
+                       May be specified multiple times. [default: none]"),
+        )
+        .arg(
+            Arg::with_name("ledger_column_block_size")
+                .long("ledger-column-block-size")
+                .value_name("COLUMN:BYTES")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .validator(ledger_column_block_size_validator)
+                .help("Size of the uncompressed data blocks of the ledger column COLUMN. \
+                       Larger blocks compress better but are slower to read at random. \
+                       May be specified multiple times. [default: rocksdb default]"),
+        )
         .arg(
             Arg::with_name("no_bpf_jit")
                 .long("no-bpf-jit")
@@ -1640,11 +1781,105 @@ pub fn main() {
                 .value_name("INDEX")
                 .help("Enable an accounts index, indexed by the selected account field"),
         )
+        .arg(
+            Arg::with_name("custom_account_indexes")
+                .long("custom-account-index")
+                .takes_value(true)
+                .multiple(true)
+                .value_name("PROGRAM_ID:OFFSET:LENGTH")
+                .validator(is_parsable::<CustomIndex>)
+                .help("Enable an accounts index of the accounts owned by PROGRAM_ID, \
+                       indexed by the LENGTH bytes of their data at OFFSET. \
+                       LENGTH is at most 32. getProgramAccounts requests with a matching \
+                       memcmp filter use the index"),
+        )
+        .arg(
+            Arg::with_name("account_index_include_keys")
+                .long("account-index-include-keys")
+                .takes_value(true)
+                .value_name("FILE")
+                .conflicts_with("account_index_exclude_keys")
+                .help("Only add the keys listed in FILE, one pubkey per line, to the enabled \
+                       accounts indexes. RPC requests for other keys fail instead of \
+                       returning an empty result"),
+        )
+        .arg(
+            Arg::with_name("account_index_exclude_keys")
+                .long("account-index-exclude-keys")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("Leave the keys listed in FILE, one pubkey per line, out of the enabled \
+                       accounts indexes. RPC requests for these keys fail instead of \
+                       returning an empty result"),
+        )
         .arg(
             Arg::with_name("no_accounts_db_caching")
                 .long("no-accounts-db-caching")
                 .help("Disables accounts caching"),
         )
+        .arg(
+            Arg::with_name("enable_accounts_disk_index")
+                .long("enable-accounts-disk-index")
+                .help("Keep only recently used entries of the accounts index in memory, \
+                       and the rest in files on disk"),
+        )
+        .arg(
+            Arg::with_name("accounts_index_path")
+                .long("accounts-index-path")
+                .value_name("PATH")
+                .takes_value(true)
+                .multiple(true)
+                .requires("enable_accounts_disk_index")
+                .help("Scratch space for the accounts index files, which are wiped on startup \
+                       as the index is rebuilt from the accounts. \
+                       May be specified multiple times. \
+                       [default: [ledger]/accounts_index]"),
+        )
+        .arg(
+            Arg::with_name("accounts_index_max_in_memory_entries")
+                .long("accounts-index-max-in-memory-entries")
+                .value_name("COUNT")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .default_value(default_accounts_index_max_in_memory_entries)
+                .help("Number of accounts index entries kept in memory \
+                       when the accounts index is on disk"),
+        )
+        .arg(
+            Arg::with_name("accounts_cold_storage_slot_age")
+                .long("accounts-cold-storage-slot-age")
+                .value_name("SLOTS")
+                .takes_value(true)
+                .validator(is_slot)
+                .help("Move the accounts of rooted slots this many slots older than the \
+                       latest root to compressed, read-only cold storage files"),
+        )
+        .arg(
+            Arg::with_name("accounts_cold_storage_cache_size")
+                .long("accounts-cold-storage-cache-size")
+                .value_name("BYTES")
+                .takes_value(true)
+                .validator(is_parsable::<usize>)
+                .default_value(default_accounts_cold_storage_cache_size)
+                .help("Number of decompressed bytes of cold storage files kept in memory"),
+        )
+        .arg(
+            Arg::with_name("enable_transaction_scheduler")
+                .long("enable-transaction-scheduler")
+                .takes_value(false)
+                .help("Execute transactions which don't lock the same accounts in parallel, \
+                       when replaying blocks and in the banking stage"),
+        )
+        .arg(
+            Arg::with_name("accountsdb_plugin_config")
+                .long("accountsdb-plugin-config")
+                .value_name("FILE")
+                .takes_value(true)
+                .multiple(true)
+                .help("Specify the configuration file for an AccountsDb plugin. \
+                       The plugin is notified of account updates, slot status changes and \
+                       committed transactions"),
+        )
         .arg(
             Arg::with_name("accounts_db_test_hash_calculation")
                 .long("accounts-db-test-hash-calculation")
@@ -1816,6 +2051,7 @@ pub fn main() {
     let rpc_bootstrap_config = RpcBootstrapConfig {
         no_genesis_fetch: matches.is_present("no_genesis_fetch"),
         no_snapshot_fetch: matches.is_present("no_snapshot_fetch"),
+        incremental_snapshot_fetch: matches.is_present("incremental_snapshot_fetch"),
         no_check_vote_account: matches.is_present("no_check_vote_account"),
         no_untrusted_rpc: matches.is_present("no_untrusted_rpc"),
         max_genesis_archive_unpacked_size: value_t_or_exit!(
@@ -1836,6 +2072,29 @@ pub fn main() {
     let wal_recovery_mode = matches
         .value_of("wal_recovery_mode")
         .map(BlockstoreRecoveryMode::from);
+    let mut ledger_column_options: HashMap<String, ColumnOptions> = HashMap::new();
+    for arg in matches
+        .values_of("ledger_column_compression")
+        .into_iter()
+        .flatten()
+    {
+        let (column, compression_type) = parse_ledger_column_arg(arg).unwrap();
+        ledger_column_options
+            .entry(column.to_string())
+            .or_default()
+            .compression_type = compression_type.parse().unwrap();
+    }
+    for arg in matches
+        .values_of("ledger_column_block_size")
+        .into_iter()
+        .flatten()
+    {
+        let (column, block_size) = parse_ledger_column_arg(arg).unwrap();
+        ledger_column_options
+            .entry(column.to_string())
+            .or_default()
+            .block_size = Some(block_size.parse().unwrap());
+    }
 
     // Canonicalize ledger path to avoid issues with symlink creation
     let _ = fs::create_dir_all(&ledger_path);
@@ -1884,7 +2143,20 @@ pub fn main() {
 
     let contact_debug_interval = value_t_or_exit!(matches, "contact_debug_interval", u64);
 
-    let account_indexes: HashSet<AccountIndex> = matches
+    let account_indexes_keys = if let Some(path) = matches.value_of("account_index_include_keys") {
+        Some(AccountSecondaryIndexesIncludeExclude {
+            exclude: false,
+            keys: account_index_keys_from_file(path),
+        })
+    } else {
+        matches.value_of("account_index_exclude_keys").map(|path| {
+            AccountSecondaryIndexesIncludeExclude {
+                exclude: true,
+                keys: account_index_keys_from_file(path),
+            }
+        })
+    };
+    let indexes: HashSet<AccountIndex> = matches
         .values_of("account_indexes")
         .unwrap_or_default()
         .map(|value| match value {
@@ -1893,7 +2165,31 @@ pub fn main() {
             "spl-token-owner" => AccountIndex::SplTokenOwner,
             _ => unreachable!(),
         })
+        .chain(
+            values_t!(matches, "custom_account_indexes", CustomIndex)
+                .unwrap_or_default()
+                .into_iter()
+                .map(AccountIndex::Custom),
+        )
         .collect();
+    let account_indexes = AccountSecondaryIndexes {
+        keys: account_indexes_keys,
+        indexes,
+    };
+
+    let accounts_index_config = matches.is_present("enable_accounts_disk_index").then(|| {
+        let drives = values_t!(matches, "accounts_index_path", String)
+            .map(|paths| paths.into_iter().map(PathBuf::from).collect())
+            .unwrap_or_else(|_| vec![ledger_path.join("accounts_index")]);
+        AccountsIndexConfig {
+            max_in_memory_entries: value_t_or_exit!(
+                matches,
+                "accounts_index_max_in_memory_entries",
+                usize
+            ),
+            ..AccountsIndexConfig::new(drives)
+        }
+    });
 
     let restricted_repair_only_mode = matches.is_present("restricted_repair_only_mode");
     let mut validator_config = ValidatorConfig {
@@ -1946,6 +2242,7 @@ pub fn main() {
         }),
         pubsub_config: PubSubConfig {
             enable_vote_subscription: matches.is_present("rpc_pubsub_enable_vote_subscription"),
+            enable_block_subscription: matches.is_present("rpc_pubsub_enable_block_subscription"),
             max_connections: value_t_or_exit!(matches, "rpc_pubsub_max_connections", usize),
             max_fragment_size: value_t_or_exit!(matches, "rpc_pubsub_max_fragment_size", usize),
             max_in_buffer_capacity: value_t_or_exit!(
@@ -1969,6 +2266,7 @@ pub fn main() {
         rocksdb_compaction_interval,
         rocksdb_max_compaction_jitter,
         wal_recovery_mode,
+        ledger_column_options,
         poh_verify: !matches.is_present("skip_poh_verify"),
         debug_keys,
         contact_debug_interval,
@@ -1979,6 +2277,7 @@ pub fn main() {
             "rpc_send_transaction_leader_forward_count",
             u64
         ),
+        send_transaction_use_quic: matches.is_present("rpc_send_transaction_use_quic"),
         no_poh_speed_test: matches.is_present("no_poh_speed_test"),
         poh_pinned_cpu_core: value_of(&matches, "poh_pinned_cpu_core")
             .unwrap_or(poh_service::DEFAULT_PINNED_CPU_CORE),
@@ -1986,9 +2285,22 @@ pub fn main() {
             .unwrap_or(poh_service::DEFAULT_HASHES_PER_BATCH),
         account_indexes,
         accounts_db_caching_enabled: !matches.is_present("no_accounts_db_caching"),
+        accounts_index_config,
+        accounts_cold_storage_slot_age: value_t!(matches, "accounts_cold_storage_slot_age", Slot)
+            .ok(),
+        accounts_cold_storage_cache_size: value_t_or_exit!(
+            matches,
+            "accounts_cold_storage_cache_size",
+            usize
+        ),
+        enable_transaction_scheduler: matches.is_present("enable_transaction_scheduler"),
         accounts_db_test_hash_calculation: matches.is_present("accounts_db_test_hash_calculation"),
         accounts_db_use_index_hash_calculation: matches.is_present("accounts_db_index_hashing"),
         tpu_coalesce_ms,
+        tpu_use_quic: matches.is_present("tpu_use_quic"),
+        accountsdb_plugin_config_files: values_t!(matches, "accountsdb_plugin_config", String)
+            .ok()
+            .map(|config_files| config_files.into_iter().map(PathBuf::from).collect()),
         ..ValidatorConfig::default()
     };
 
@@ -2057,6 +2369,8 @@ pub fn main() {
     });
 
     let snapshot_interval_slots = value_t_or_exit!(matches, "snapshot_interval_slots", u64);
+    let incremental_snapshot_interval_slots =
+        value_t_or_exit!(matches, "incremental_snapshot_interval_slots", u64);
     let maximum_local_snapshot_age = value_t_or_exit!(matches, "maximum_local_snapshot_age", u64);
     let snapshot_path = ledger_path.join("snapshot");
     fs::create_dir_all(&snapshot_path).unwrap_or_else(|err| {
@@ -2093,6 +2407,11 @@ pub fn main() {
         } else {
             std::u64::MAX
         },
+        incremental_snapshot_interval_slots: if incremental_snapshot_interval_slots > 0 {
+            incremental_snapshot_interval_slots
+        } else {
+            std::u64::MAX
+        },
         snapshot_path,
         snapshot_package_output_path: ledger_path.clone(),
         archive_format,
@@ -2115,6 +2434,25 @@ pub fn main() {
         );
         exit(1);
     }
+    if incremental_snapshot_interval_slots > 0 {
+        if is_snapshot_config_invalid(
+            incremental_snapshot_interval_slots,
+            validator_config.accounts_hash_interval_slots,
+        ) {
+            eprintln!("Invalid incremental snapshot interval provided ({}), must be a multiple of accounts_hash_interval_slots ({})",
+                incremental_snapshot_interval_slots,
+                validator_config.accounts_hash_interval_slots,
+            );
+            exit(1);
+        }
+        if incremental_snapshot_interval_slots >= snapshot_interval_slots {
+            eprintln!("Invalid incremental snapshot interval provided ({}), must be less than the snapshot interval ({})",
+                incremental_snapshot_interval_slots,
+                snapshot_interval_slots,
+            );
+            exit(1);
+        }
+    }
 
     if matches.is_present("limit_ledger_size") {
         let limit_ledger_size = match matches.value_of("limit_ledger_size") {
@@ -2292,6 +2630,9 @@ pub fn main() {
     }
     solana_ledger::entry::init_poh();
     solana_runtime::snapshot_utils::remove_tmp_snapshot_archives(&ledger_path);
+    if let Some(accounts_index_config) = &validator_config.accounts_index_config {
+        solana_runtime::bucket_map::remove_leftover_bucket_maps(&accounts_index_config.drives);
+    }
 
     let should_check_duplicate_instance = !matches.is_present("no_duplicate_instance_check");
     if !cluster_entrypoints.is_empty() {